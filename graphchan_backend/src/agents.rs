@@ -0,0 +1,467 @@
+use crate::config::GraphchanPaths;
+use crate::database::models::AgentKeyRecord;
+use crate::database::repositories::AgentKeyRepository;
+use crate::database::Database;
+use crate::errors::invalid;
+use crate::threading::{AgentInfo, PostView};
+use anyhow::{bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Longest agent name we accept.
+pub const MAX_AGENT_NAME_LEN: usize = 64;
+/// How long after its claimed `created_at` a post by a since-revoked agent may
+/// first reach us.
+pub const REVOCATION_GRACE_SECS: i64 = 10 * 60;
+
+const AGENT_POST_DOMAIN: &[u8] = b"orbweaver-agent-post-v1";
+
+/// A node's grant of an Ed25519 posting key to one of its agents.
+///
+/// Signed with the node's OpenPGP key, so anyone who can verify the node's
+/// posts can tell the agent really speaks for it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AgentDelegation {
+    pub agent_id: String,
+    pub owner_peer_id: String,
+    pub name: String,
+    /// Ed25519 public key (base64)
+    pub public_key: String,
+    /// Unix seconds
+    pub issued_at: i64,
+    /// Armored detached OpenPGP signature over `signing_payload()`
+    pub signature: String,
+}
+
+impl AgentDelegation {
+    /// Canonical bytes covered by the owner's signature.
+    pub fn signing_payload(&self) -> Vec<u8> {
+        #[derive(Serialize)]
+        struct SigningPayload<'a> {
+            domain: &'static str,
+            agent_id: &'a str,
+            owner_peer_id: &'a str,
+            name: &'a str,
+            public_key: &'a str,
+            issued_at: i64,
+        }
+
+        serde_json::to_vec(&SigningPayload {
+            domain: "orbweaver-agent-delegation-v1",
+            agent_id: &self.agent_id,
+            owner_peer_id: &self.owner_peer_id,
+            name: &self.name,
+            public_key: &self.public_key,
+            issued_at: self.issued_at,
+        })
+        .expect("agent delegation payload serializes")
+    }
+
+    fn verifying_key(&self) -> Result<VerifyingKey> {
+        let bytes: [u8; 32] = BASE64
+            .decode(&self.public_key)
+            .context("agent key is not base64")?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("agent key has wrong length"))?;
+        VerifyingKey::from_bytes(&bytes).context("agent key is not a valid ed25519 key")
+    }
+}
+
+/// The owner withdrawing an agent's key; posts made from `revoked_at` on are rejected.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AgentRevocation {
+    pub agent_id: String,
+    pub owner_peer_id: String,
+    /// Unix seconds
+    pub revoked_at: i64,
+    /// Armored detached OpenPGP signature over `signing_payload()`
+    pub signature: String,
+}
+
+impl AgentRevocation {
+    /// Canonical bytes covered by the owner's signature.
+    pub fn signing_payload(&self) -> Vec<u8> {
+        #[derive(Serialize)]
+        struct SigningPayload<'a> {
+            domain: &'static str,
+            agent_id: &'a str,
+            owner_peer_id: &'a str,
+            revoked_at: i64,
+        }
+
+        serde_json::to_vec(&SigningPayload {
+            domain: "orbweaver-agent-revocation-v1",
+            agent_id: &self.agent_id,
+            owner_peer_id: &self.owner_peer_id,
+            revoked_at: self.revoked_at,
+        })
+        .expect("agent revocation payload serializes")
+    }
+}
+
+/// One entry of the agent roster published in `ProfileUpdate::agent_keys`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DelegatedAgent {
+    pub delegation: AgentDelegation,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revocation: Option<AgentRevocation>,
+}
+
+impl DelegatedAgent {
+    /// Row for storing a peer's agent; signatures must already be checked.
+    pub fn to_record(&self) -> Result<AgentKeyRecord> {
+        Ok(AgentKeyRecord {
+            owner_peer_id: self.delegation.owner_peer_id.clone(),
+            agent_id: self.delegation.agent_id.clone(),
+            name: self.delegation.name.clone(),
+            public_key: self.delegation.public_key.clone(),
+            delegation: serde_json::to_string(&self.delegation)?,
+            secret_key: None,
+            created_at: self.delegation.issued_at,
+            revoked_at: self.revocation.as_ref().map(|revocation| revocation.revoked_at),
+            revocation: self.revocation.as_ref().map(serde_json::to_string).transpose()?,
+        })
+    }
+}
+
+/// An agent key as the API shows it (never the secret).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentKeyView {
+    pub agent_id: String,
+    pub name: String,
+    pub public_key: String,
+    pub created_at: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revoked_at: Option<i64>,
+}
+
+impl AgentKeyView {
+    fn from_record(record: &AgentKeyRecord) -> Self {
+        Self {
+            agent_id: record.agent_id.clone(),
+            name: record.name.clone(),
+            public_key: record.public_key.clone(),
+            created_at: record.created_at,
+            revoked_at: record.revoked_at,
+        }
+    }
+}
+
+/// What an agent's post signature covers.
+///
+/// Like proof-of-work stamps, it is bound to the post's identity rather than
+/// its body, so it survives edits; the body stays covered by the node's own
+/// signature, which in turn covers the agent proof inside the metadata.
+pub fn post_resource(post_id: &str, thread_id: &str, author_peer_id: &str, created_at: &str, agent_id: &str) -> Vec<u8> {
+    let mut resource = AGENT_POST_DOMAIN.to_vec();
+    resource.extend_from_slice(format!("\n{post_id}\n{thread_id}\n{author_peer_id}\n{created_at}\n{agent_id}").as_bytes());
+    resource
+}
+
+/// Whether a post by an agent revoked at `revoked_at`, first seen at
+/// `received_at`, was made while the agent was still valid.
+///
+/// `created_at` is picked by whoever holds the agent key, so a leaked key could
+/// backdate posts past the revocation. Once the revocation has passed, a claimed
+/// time only counts for posts that arrive within `REVOCATION_GRACE_SECS` of it.
+pub fn made_before_revocation(created_at: &str, revoked_at: i64, received_at: i64) -> bool {
+    let Ok(created_at) = chrono::DateTime::parse_from_rfc3339(created_at).map(|created_at| created_at.timestamp()) else {
+        return false;
+    };
+    created_at < revoked_at && (received_at < revoked_at || received_at - created_at <= REVOCATION_GRACE_SECS)
+}
+
+/// Checks the agent's own signature on `post` and that its delegation names
+/// the same agent and the post's author.
+///
+/// The delegation's OpenPGP signature and revocation state are left to the
+/// caller, which knows the author's key. Returns the delegation to verify.
+pub fn check_post_agent<'a>(post: &PostView, agent: &'a AgentInfo) -> Result<&'a AgentDelegation> {
+    let (Some(delegation), Some(signature)) = (&agent.delegation, &agent.signature) else {
+        bail!("agent '{}' on post {} carries no delegated signature", agent.name, post.id);
+    };
+    let author = post.author_peer_id.as_deref().unwrap_or_default();
+    if delegation.owner_peer_id != author {
+        bail!("agent '{}' on post {} is delegated by another node", agent.name, post.id);
+    }
+    if delegation.name != agent.name {
+        bail!("agent '{}' on post {} presents the key of '{}'", agent.name, post.id, delegation.name);
+    }
+    let signature: [u8; 64] = BASE64
+        .decode(signature)
+        .context("agent signature is not base64")?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("agent signature has wrong length"))?;
+    let resource = post_resource(&post.id, &post.thread_id, author, &post.created_at, &delegation.agent_id);
+    delegation
+        .verifying_key()?
+        .verify_strict(&resource, &Signature::from_bytes(&signature))
+        .with_context(|| format!("agent signature on post {} does not verify", post.id))?;
+    Ok(delegation)
+}
+
+/// The local node's agents and their delegated posting keys.
+///
+/// Each agent gets its own Ed25519 key, delegated by the node's OpenPGP key.
+/// Posts naming an agent carry the delegation and the agent's signature, and
+/// the roster (with revocations) rides along in our `ProfileUpdate`s.
+#[derive(Clone)]
+pub struct AgentService {
+    database: Database,
+    paths: GraphchanPaths,
+}
+
+impl AgentService {
+    pub fn new(database: Database, paths: GraphchanPaths) -> Self {
+        Self { database, paths }
+    }
+
+    /// Creates an agent and delegates a fresh posting key to it.
+    pub fn create(&self, name: &str) -> Result<AgentKeyView> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(invalid("agent name is required"));
+        }
+        if name.chars().count() > MAX_AGENT_NAME_LEN {
+            return Err(invalid(format!("agent name is longer than {MAX_AGENT_NAME_LEN} characters")));
+        }
+        let owner = self.local_peer_id()?;
+        if self.active_by_name(&owner, name)?.is_some() {
+            return Err(invalid(format!("agent already exists: {name}")));
+        }
+
+        let mut seed = [0u8; 32];
+        rand::rng().fill_bytes(&mut seed);
+        let signing_key = SigningKey::from_bytes(&seed);
+        let mut delegation = AgentDelegation {
+            agent_id: Uuid::new_v4().to_string(),
+            owner_peer_id: owner,
+            name: name.to_string(),
+            public_key: BASE64.encode(signing_key.verifying_key().as_bytes()),
+            issued_at: chrono::Utc::now().timestamp(),
+            signature: String::new(),
+        };
+        delegation.signature = crate::crypto::sign_detached(&self.paths, &delegation.signing_payload())
+            .context("failed to sign agent delegation")?;
+
+        let mut record = DelegatedAgent { delegation, revocation: None }.to_record()?;
+        record.secret_key = Some(BASE64.encode(seed));
+        self.database.with_repositories(|repos| repos.agent_keys().upsert(&record))?;
+        Ok(AgentKeyView::from_record(&record))
+    }
+
+    /// Revokes the active agent with this name or ID; `None` if there is none.
+    pub fn revoke(&self, name_or_id: &str) -> Result<Option<AgentKeyView>> {
+        let owner = self.local_peer_id()?;
+        let Some(record) = self
+            .list_records(&owner)?
+            .into_iter()
+            .find(|record| record.revoked_at.is_none() && (record.agent_id == name_or_id || record.name == name_or_id))
+        else {
+            return Ok(None);
+        };
+
+        let mut revocation = AgentRevocation {
+            agent_id: record.agent_id.clone(),
+            owner_peer_id: owner.clone(),
+            revoked_at: chrono::Utc::now().timestamp(),
+            signature: String::new(),
+        };
+        revocation.signature = crate::crypto::sign_detached(&self.paths, &revocation.signing_payload())
+            .context("failed to sign agent revocation")?;
+        let revocation_json = serde_json::to_string(&revocation)?;
+        self.database.with_repositories(|repos| {
+            repos
+                .agent_keys()
+                .revoke(&owner, &record.agent_id, revocation.revoked_at, &revocation_json)
+        })?;
+        Ok(Some(AgentKeyView {
+            revoked_at: Some(revocation.revoked_at),
+            ..AgentKeyView::from_record(&record)
+        }))
+    }
+
+    /// All our agents, revoked ones included.
+    pub fn list(&self) -> Result<Vec<AgentKeyView>> {
+        let owner = self.local_peer_id()?;
+        Ok(self.list_records(&owner)?.iter().map(AgentKeyView::from_record).collect())
+    }
+
+    /// Names of our active agents, for the legacy `ProfileUpdate::agents` list.
+    pub fn active_names(&self) -> Result<Vec<String>> {
+        Ok(self
+            .list()?
+            .into_iter()
+            .filter(|agent| agent.revoked_at.is_none())
+            .map(|agent| agent.name)
+            .collect())
+    }
+
+    /// Our delegations and revocations, as published to peers.
+    pub fn roster(&self) -> Result<Vec<DelegatedAgent>> {
+        let owner = self.local_peer_id()?;
+        self.list_records(&owner)?
+            .iter()
+            .map(|record| {
+                Ok(DelegatedAgent {
+                    delegation: serde_json::from_str(&record.delegation)?,
+                    revocation: record.revocation.as_deref().map(serde_json::from_str).transpose()?,
+                })
+            })
+            .collect()
+    }
+
+    /// Fills in the delegation and signature for a post our node is about to make as `agent`.
+    pub fn attest(&self, agent: &mut AgentInfo, post_id: &str, thread_id: &str, created_at: &str) -> Result<()> {
+        let owner = self.local_peer_id()?;
+        let Some(record) = self.active_by_name(&owner, &agent.name)? else {
+            return Err(invalid(format!("unknown agent: {}", agent.name)));
+        };
+        let seed: [u8; 32] = record
+            .secret_key
+            .as_deref()
+            .map(|secret| BASE64.decode(secret))
+            .transpose()
+            .context("stored agent key is not base64")?
+            .and_then(|bytes| bytes.try_into().ok())
+            .with_context(|| format!("no posting key stored for agent {}", agent.name))?;
+        let signing_key = SigningKey::from_bytes(&seed);
+        let resource = post_resource(post_id, thread_id, &owner, created_at, &record.agent_id);
+        agent.delegation = Some(serde_json::from_str(&record.delegation)?);
+        agent.signature = Some(BASE64.encode(signing_key.sign(&resource).to_bytes()));
+        Ok(())
+    }
+
+    fn active_by_name(&self, owner: &str, name: &str) -> Result<Option<AgentKeyRecord>> {
+        Ok(self
+            .list_records(owner)?
+            .into_iter()
+            .find(|record| record.revoked_at.is_none() && record.name == name))
+    }
+
+    fn list_records(&self, owner: &str) -> Result<Vec<AgentKeyRecord>> {
+        self.database.with_repositories(|repos| repos.agent_keys().list_for_owner(owner))
+    }
+
+    fn local_peer_id(&self) -> Result<String> {
+        self.database
+            .get_identity()?
+            .map(|(fingerprint, _, _)| fingerprint)
+            .context("local identity not initialised")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::threading::{CreatePostInput, PostMetadata, ThreadService};
+    use rusqlite::Connection;
+
+    fn setup(dir: &std::path::Path) -> (AgentService, ThreadService, String) {
+        let paths = GraphchanPaths::from_base_dir(dir).expect("paths");
+        std::fs::create_dir_all(&paths.gpg_dir).expect("gpg dir");
+        let identity = crate::identity::ensure_local_identity(&paths).expect("identity");
+        let database = Database::from_connection(Connection::open_in_memory().expect("db"), true);
+        database.ensure_migrations().expect("migrations");
+        database
+            .save_identity(&identity.gpg_fingerprint, &identity.iroh_peer_id, &identity.friendcode)
+            .expect("save identity");
+        (
+            AgentService::new(database.clone(), paths.clone()),
+            ThreadService::with_file_paths(database, paths),
+            identity.gpg_fingerprint,
+        )
+    }
+
+    fn agent_post(threads: &ThreadService, author: &str, agent: &str) -> Result<PostView> {
+        let thread = threads.create_thread(crate::threading::CreateThreadInput {
+            title: "Agents".into(),
+            creator_peer_id: Some(author.into()),
+            ..Default::default()
+        })?;
+        threads.create_post(CreatePostInput {
+            thread_id: thread.thread.id,
+            author_peer_id: Some(author.into()),
+            body: "beep".into(),
+            metadata: Some(PostMetadata {
+                agent: Some(AgentInfo { name: agent.into(), version: None, delegation: None, signature: None }),
+                client: None,
+            }),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn agent_posts_carry_a_verifiable_delegation() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let (agents, threads, fingerprint) = setup(dir.path());
+        let created = agents.create("scout").expect("create agent");
+        // Bad names are typed so the API answers 400
+        let is_invalid = |err: anyhow::Error| {
+            matches!(err.downcast_ref::<crate::errors::RequestError>(), Some(crate::errors::RequestError::Invalid(_)))
+        };
+        assert!(is_invalid(agents.create(" scout ").unwrap_err()));
+        assert!(is_invalid(agents.create("  ").unwrap_err()));
+        assert!(is_invalid(agents.create(&"x".repeat(MAX_AGENT_NAME_LEN + 1)).unwrap_err()));
+
+        let post = agent_post(&threads, &fingerprint, "scout").expect("agent post");
+        let agent = post.metadata.as_ref().and_then(|meta| meta.agent.as_ref()).expect("agent info");
+        let delegation = check_post_agent(&post, agent).expect("agent signature verifies");
+        assert_eq!(delegation.agent_id, created.agent_id);
+        let public_key = crate::crypto::load_public_key_armored(&agents.paths).expect("public key");
+        crate::crypto::verify_detached(&public_key, &fingerprint, &delegation.signing_payload(), &delegation.signature)
+            .expect("delegation signed by the node");
+
+        // The agent signature doesn't transfer to another post or agent name
+        let mut moved = post.clone();
+        moved.id = "other".into();
+        assert!(check_post_agent(&moved, agent).is_err());
+        let mut renamed = agent.clone();
+        renamed.name = "impostor".into();
+        assert!(check_post_agent(&post, &renamed).is_err());
+
+        // Unregistered names can't be claimed
+        assert!(agent_post(&threads, &fingerprint, "ghost").is_err());
+    }
+
+    #[test]
+    fn revoked_agents_stop_posting_and_stay_in_the_roster() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let (agents, threads, fingerprint) = setup(dir.path());
+        let created = agents.create("scout").expect("create agent");
+        let revoked = agents.revoke("scout").expect("revoke").expect("agent exists");
+        assert_eq!(revoked.agent_id, created.agent_id);
+        assert!(agents.revoke(&created.agent_id).expect("revoke again").is_none());
+
+        assert!(agent_post(&threads, &fingerprint, "scout").is_err());
+        assert!(agents.active_names().expect("names").is_empty());
+        let roster = agents.roster().expect("roster");
+        assert_eq!(roster.len(), 1);
+        let revocation = roster[0].revocation.as_ref().expect("revocation published");
+        let public_key = crate::crypto::load_public_key_armored(&agents.paths).expect("public key");
+        crate::crypto::verify_detached(&public_key, &fingerprint, &revocation.signing_payload(), &revocation.signature)
+            .expect("revocation signed by the node");
+
+        // The name can be delegated again with a new key
+        let replacement = agents.create("scout").expect("recreate agent");
+        assert_ne!(replacement.public_key, created.public_key);
+        assert!(agent_post(&threads, &fingerprint, "scout").is_ok());
+    }
+
+    #[test]
+    fn backdated_posts_of_revoked_agents_are_refused() {
+        let revoked_at = chrono::DateTime::parse_from_rfc3339("2026-01-01T12:00:00Z").unwrap().timestamp();
+        let before = "2026-01-01T11:59:00Z";
+        // Arriving before the revocation, or soon after the claimed time
+        assert!(made_before_revocation(before, revoked_at, revoked_at - 30));
+        assert!(made_before_revocation(before, revoked_at, revoked_at + 60));
+        // A claimed time at or after the revocation never counts
+        assert!(!made_before_revocation("2026-01-01T12:00:00Z", revoked_at, revoked_at - 30));
+        // First seen long after the revocation: the claimed time can't be trusted
+        assert!(!made_before_revocation(before, revoked_at, revoked_at + REVOCATION_GRACE_SECS));
+        assert!(!made_before_revocation("not a time", revoked_at, revoked_at - 30));
+    }
+}
@@ -0,0 +1,147 @@
+use super::{ApiError, ApiResult, AppState};
+use crate::api_tokens::{scope_for_route, ApiScope, ApiTokenView};
+use crate::config::ApiConfig;
+use axum::extract::{MatchedPath, Path, Request, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub(crate) struct CreateTokenRequest {
+    name: String,
+    scopes: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct CreatedTokenResponse {
+    #[serde(flatten)]
+    info: ApiTokenView,
+    /// Shown only once; the node keeps just its hash
+    token: String,
+}
+
+/// Rejects requests without a bearer token whose scopes cover the route.
+///
+/// The accepted token is added to the request's extensions for handlers that
+/// narrow what they return by scope.
+pub(crate) async fn require_token(State(state): State<AppState>, mut request: Request, next: Next) -> Response {
+    if !state.config.api.require_auth {
+        return next.run(request).await;
+    }
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+    let required = scope_for_route(request.method().as_str(), &route);
+
+    let Some(secret) = bearer_token(request.headers()) else {
+        return ApiError::Unauthorized("missing bearer token".into()).into_response();
+    };
+    let Some(token) = state.tokens.authenticate(secret) else {
+        return ApiError::Unauthorized("invalid or revoked token".into()).into_response();
+    };
+    if !token.grants(required) {
+        return ApiError::Forbidden(format!(
+            "token '{}' lacks the {} scope",
+            token.name,
+            required.as_str()
+        ))
+        .into_response();
+    }
+    request.extensions_mut().insert(token);
+    next.run(request).await
+}
+
+/// Checks that the calling token may post as `agent`, or edit and delete its posts.
+///
+/// Agents are tied to the token of the same name, so a bot holding a post-scoped
+/// token can't sign as another agent of this node; admin tokens may use any
+/// agent. Anything goes when auth is off.
+pub(crate) fn authorize_agent(config: &ApiConfig, token: Option<&ApiTokenView>, agent: Option<&str>) -> Result<(), ApiError> {
+    let Some(agent) = agent else {
+        return Ok(());
+    };
+    if !config.require_auth {
+        return Ok(());
+    }
+    match token {
+        Some(token) if token.name == agent || token.grants(ApiScope::Admin) => Ok(()),
+        Some(token) => Err(ApiError::Forbidden(format!(
+            "token '{}' may not post as agent '{agent}'",
+            token.name
+        ))),
+        None => Err(ApiError::Unauthorized("missing bearer token".into())),
+    }
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = value.split_once(' ')?;
+    scheme.eq_ignore_ascii_case("bearer").then_some(token.trim())
+}
+
+pub(crate) async fn list_tokens_handler(State(state): State<AppState>) -> ApiResult<Vec<ApiTokenView>> {
+    Ok(Json(state.tokens.list()))
+}
+
+pub(crate) async fn create_token_handler(
+    State(state): State<AppState>,
+    Json(req): Json<CreateTokenRequest>,
+) -> Result<(StatusCode, Json<CreatedTokenResponse>), ApiError> {
+    let scopes = req
+        .scopes
+        .iter()
+        .map(|scope| ApiScope::parse(scope.trim()))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let (info, token) = state.tokens.create(&req.name, &scopes)?;
+    Ok((StatusCode::CREATED, Json(CreatedTokenResponse { info, token })))
+}
+
+pub(crate) async fn revoke_token_handler(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    if state.tokens.revoke(&id)? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ApiError::NotFound(format!("token not found: {id}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(name: &str, scope: ApiScope) -> ApiTokenView {
+        ApiTokenView {
+            id: format!("id-{name}"),
+            name: name.into(),
+            scopes: vec![scope],
+            created_at: "2026-01-01T00:00:00Z".into(),
+        }
+    }
+
+    #[test]
+    fn only_an_agents_own_token_acts_for_it() {
+        let config = ApiConfig::default();
+        let scout = token("scout", ApiScope::Post);
+        let other = token("other-bot", ApiScope::Post);
+
+        // Another agent's token may not post as, edit or delete the posts of `scout`
+        assert!(matches!(
+            authorize_agent(&config, Some(&other), Some("scout")),
+            Err(ApiError::Forbidden(_))
+        ));
+        assert!(matches!(authorize_agent(&config, None, Some("scout")), Err(ApiError::Unauthorized(_))));
+        assert!(authorize_agent(&config, Some(&scout), Some("scout")).is_ok());
+        assert!(authorize_agent(&config, Some(&token("owner", ApiScope::Admin)), Some("scout")).is_ok());
+
+        // Posts without an agent, and nodes without auth, aren't restricted
+        assert!(authorize_agent(&config, Some(&other), None).is_ok());
+        let open = ApiConfig { require_auth: false, ..ApiConfig::default() };
+        assert!(authorize_agent(&open, None, Some("scout")).is_ok());
+    }
+}
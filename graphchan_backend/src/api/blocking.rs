@@ -1,6 +1,8 @@
 use super::{AppState, ApiError};
 use crate::blocking::{BlockChecker, BlockedPeerView, BlocklistEntryView, BlocklistSubscriptionView};
-use crate::database::repositories::PeerIpRepository;
+use crate::database::models::{EndpointPathRecord, PeerIpRecord};
+use crate::database::repositories::{EndpointPathRepository, PeerIpRepository, PeerRepository};
+use crate::trust::{QuarantinedPostView, TrustService};
 use axum::extract::{Path, State};
 use axum::http::StatusCode;
 use axum::Json;
@@ -62,6 +64,10 @@ pub(crate) struct IpBlockStatsResponse {
 pub(crate) struct PeerIpResponse {
     peer_id: String,
     ips: Vec<String>,
+    /// Each IP with its source (friendcode or direct) and first/last seen
+    addresses: Vec<PeerIpRecord>,
+    /// Direct and relay paths observed for the peer's iroh endpoint
+    paths: Vec<EndpointPathRecord>,
 }
 
 pub(crate) async fn list_blocked_peers_handler(
@@ -79,12 +85,9 @@ pub(crate) async fn block_peer_handler(
 ) -> Result<StatusCode, ApiError> {
     let checker = BlockChecker::new(state.database.clone());
     checker
-        .block_peer(&peer_id, payload.reason)
+        .block_peer(&peer_id, payload.reason.clone())
         .map_err(ApiError::Internal)?;
-
-    // NOTE: Block action broadcasting disabled pending privacy redesign (OrbWeaver-9rc).
-    // Broadcasting who you block to your peer topic is a privacy/safety concern.
-
+    publish_block_action(&state, &checker, &peer_id, payload.reason, false).await?;
     Ok(StatusCode::OK)
 }
 
@@ -94,9 +97,67 @@ pub(crate) async fn unblock_peer_handler(
 ) -> Result<StatusCode, ApiError> {
     let checker = BlockChecker::new(state.database.clone());
     checker.unblock_peer(&peer_id).map_err(ApiError::Internal)?;
+    publish_block_action(&state, &checker, &peer_id, None, true).await?;
     Ok(StatusCode::OK)
 }
 
+/// Broadcasts a signed block action to our blocklist's subscribers if the user
+/// maintains a public blocklist; who you block stays private otherwise.
+async fn publish_block_action(
+    state: &AppState,
+    checker: &BlockChecker,
+    peer_id: &str,
+    reason: Option<String>,
+    is_unblock: bool,
+) -> Result<(), ApiError> {
+    if !checker.publishes_block_actions().map_err(ApiError::Internal)? {
+        return Ok(());
+    }
+    let action = checker
+        .signed_block_action(
+            &state.config.paths,
+            &state.identity.gpg_fingerprint,
+            peer_id,
+            reason,
+            is_unblock,
+        )
+        .map_err(ApiError::Internal)?;
+    state.network.publish_block_action(action).await.map_err(ApiError::Internal)
+}
+
+pub(crate) async fn list_quarantine_handler(
+    State(state): State<AppState>,
+) -> ApiResult<Vec<QuarantinedPostView>> {
+    let trust = TrustService::new(state.database.clone());
+    Ok(Json(trust.list_quarantined().map_err(ApiError::Internal)?))
+}
+
+pub(crate) async fn approve_quarantined_post_handler(
+    State(state): State<AppState>,
+    Path(post_id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let trust = TrustService::new(state.database.clone());
+    trust.approve(&post_id).map_err(quarantine_error)?;
+    Ok(StatusCode::OK)
+}
+
+pub(crate) async fn reject_quarantined_post_handler(
+    State(state): State<AppState>,
+    Path(post_id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let trust = TrustService::new(state.database.clone());
+    trust.reject(&post_id).map_err(quarantine_error)?;
+    Ok(StatusCode::OK)
+}
+
+fn quarantine_error(err: anyhow::Error) -> ApiError {
+    if err.to_string().contains("not in quarantine") {
+        ApiError::NotFound(err.to_string())
+    } else {
+        ApiError::Internal(err)
+    }
+}
+
 pub(crate) async fn list_blocklists_handler(
     State(state): State<AppState>,
 ) -> ApiResult<Vec<BlocklistSubscriptionView>> {
@@ -377,13 +438,20 @@ pub(crate) async fn get_peer_ip_handler(
     State(state): State<AppState>,
     Path(peer_id): Path<String>,
 ) -> ApiResult<PeerIpResponse> {
-    let ips = state.database.with_repositories(|repos| {
-        repos.peer_ips().get_ips(&peer_id)
+    let (addresses, paths) = state.database.with_repositories(|repos| {
+        let addresses = repos.peer_ips().list_for_peer(&peer_id)?;
+        let paths = match repos.peers().get(&peer_id)?.and_then(|peer| peer.iroh_peer_id) {
+            Some(endpoint_id) => repos.endpoint_paths().list_for_endpoint(&endpoint_id)?,
+            None => Vec::new(),
+        };
+        Ok((addresses, paths))
     }).map_err(ApiError::Internal)?;
 
     Ok(Json(PeerIpResponse {
         peer_id,
-        ips,
+        ips: addresses.iter().map(|record| record.ip_address.clone()).collect(),
+        addresses,
+        paths,
     }))
 }
 
@@ -436,8 +504,11 @@ pub(crate) async fn import_peer_blocks_handler(
             .filter(|r| !r.is_empty())
             .map(|r| r.to_string());
 
-        match checker.block_peer(peer_id, reason) {
-            Ok(_) => added_count += 1,
+        match checker.block_peer(peer_id, reason.clone()) {
+            Ok(_) => {
+                publish_block_action(&state, &checker, peer_id, reason, false).await?;
+                added_count += 1;
+            }
             Err(err) => {
                 tracing::warn!(error = ?err, peer_id = %peer_id, "failed to block peer during import");
                 error_count += 1;
@@ -1,14 +1,64 @@
 use super::{AppState, ApiError, ApiResult};
-use crate::dms::{ConversationView, DirectMessageView, DmService};
-use axum::extract::{Path, Query, State};
-use axum::http::StatusCode;
+use crate::database::repositories::DmAttachmentRepository;
+use crate::dms::{ConversationView, DirectMessageView, DmAttachmentView, DmGroupView, DmService};
+use crate::files::{FileService, SaveDmAttachmentInput};
+use crate::network::DirectMessageEvent;
+use anyhow::Context;
+use axum::body::Body;
+use axum::extract::{Multipart, Path, Query, State};
+use axum::http::header::{CONTENT_DISPOSITION, CONTENT_LENGTH, CONTENT_TYPE, X_CONTENT_TYPE_OPTIONS};
+use axum::http::{HeaderValue, StatusCode};
+use axum::response::Response;
 use axum::Json;
 use serde::{Deserialize, Serialize};
+use tokio::fs::File as TokioFile;
+use tokio_util::io::ReaderStream;
+
+/// Attachment types served under their own mime type; anything else is a download.
+const INLINE_MIME_TYPES: &[&str] = &[
+    "image/jpeg",
+    "image/png",
+    "image/gif",
+    "image/webp",
+    "video/webm",
+    "video/mp4",
+    "audio/mpeg",
+    "audio/ogg",
+];
 
 #[derive(Debug, Deserialize)]
 pub(crate) struct SendDmRequest {
     to_peer_id: String,
     body: String,
+    /// ID of a message in this conversation
+    #[serde(default)]
+    reply_to: Option<String>,
+    /// IDs returned by `POST /dms/attachments`
+    #[serde(default)]
+    attachments: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct CreateGroupRequest {
+    name: String,
+    members: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct SendGroupDmRequest {
+    body: String,
+    #[serde(default)]
+    reply_to: Option<String>,
+    #[serde(default)]
+    attachments: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct UpdateGroupMembersRequest {
+    #[serde(default)]
+    add: Vec<String>,
+    #[serde(default)]
+    remove: Vec<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -26,6 +76,11 @@ pub(crate) struct UnreadCountResponse {
     count: usize,
 }
 
+#[derive(Debug, Serialize)]
+pub(crate) struct AttachmentDownloadResponse {
+    status: String,
+}
+
 pub(crate) async fn list_conversations_handler(
     State(state): State<AppState>,
 ) -> ApiResult<Vec<ConversationView>> {
@@ -39,20 +94,10 @@ pub(crate) async fn send_dm_handler(
     Json(payload): Json<SendDmRequest>,
 ) -> Result<(StatusCode, Json<DirectMessageView>), ApiError> {
     let service = DmService::new(state.database.clone(), state.config.paths.clone());
-    let (message, ciphertext, nonce) = service
-        .send_dm(&payload.to_peer_id, &payload.body)
-        .map_err(ApiError::Internal)?;
+    let (message, dm_event) =
+        service.send_dm(&payload.to_peer_id, &payload.body, payload.reply_to.as_deref(), &payload.attachments)?;
 
     // Broadcast encrypted DM over gossip to recipient
-    let dm_event = crate::network::DirectMessageEvent {
-        from_peer_id: message.from_peer_id.clone(),
-        to_peer_id: message.to_peer_id.clone(),
-        encrypted_body: ciphertext,
-        nonce,
-        message_id: message.id.clone(),
-        conversation_id: message.conversation_id.clone(),
-        created_at: message.created_at.clone(),
-    };
     if let Err(err) = state.network.publish_direct_message(dm_event).await {
         tracing::warn!(error = ?err, "failed to broadcast DM over gossip");
     }
@@ -91,3 +136,212 @@ pub(crate) async fn count_unread_handler(
     let count = service.count_unread().map_err(ApiError::Internal)?;
     Ok(Json(UnreadCountResponse { count }))
 }
+
+pub(crate) async fn list_groups_handler(State(state): State<AppState>) -> ApiResult<Vec<DmGroupView>> {
+    let service = DmService::new(state.database.clone(), state.config.paths.clone());
+    let groups = service.list_groups().map_err(ApiError::Internal)?;
+    Ok(Json(groups))
+}
+
+pub(crate) async fn create_group_handler(
+    State(state): State<AppState>,
+    Json(payload): Json<CreateGroupRequest>,
+) -> Result<(StatusCode, Json<DmGroupView>), ApiError> {
+    let service = DmService::new(state.database.clone(), state.config.paths.clone());
+    let (group, events) = service.create_group(&payload.name, &payload.members)?;
+    publish_group_copies(&state, events).await;
+    Ok((StatusCode::CREATED, Json(group)))
+}
+
+pub(crate) async fn get_group_handler(
+    State(state): State<AppState>,
+    Path(group_id): Path<String>,
+) -> ApiResult<DmGroupView> {
+    let service = DmService::new(state.database.clone(), state.config.paths.clone());
+    let group = service.get_group(&group_id).map_err(ApiError::Internal)?;
+    group
+        .map(Json)
+        .ok_or_else(|| ApiError::NotFound(format!("group {group_id} not found")))
+}
+
+pub(crate) async fn get_group_messages_handler(
+    State(state): State<AppState>,
+    Path(group_id): Path<String>,
+    Query(params): Query<GetMessagesParams>,
+) -> ApiResult<Vec<DirectMessageView>> {
+    let service = DmService::new(state.database.clone(), state.config.paths.clone());
+    let limit = params.limit.min(200);
+    let messages = service.get_group_messages(&group_id, limit)?;
+    Ok(Json(messages))
+}
+
+pub(crate) async fn send_group_dm_handler(
+    State(state): State<AppState>,
+    Path(group_id): Path<String>,
+    Json(payload): Json<SendGroupDmRequest>,
+) -> Result<(StatusCode, Json<DirectMessageView>), ApiError> {
+    let service = DmService::new(state.database.clone(), state.config.paths.clone());
+    let (message, events) =
+        service.send_group_dm(&group_id, &payload.body, payload.reply_to.as_deref(), &payload.attachments)?;
+    publish_group_copies(&state, events).await;
+    Ok((StatusCode::CREATED, Json(message)))
+}
+
+pub(crate) async fn update_group_members_handler(
+    State(state): State<AppState>,
+    Path(group_id): Path<String>,
+    Json(payload): Json<UpdateGroupMembersRequest>,
+) -> ApiResult<DmGroupView> {
+    let service = DmService::new(state.database.clone(), state.config.paths.clone());
+    let (group, events) = service.update_group_members(&group_id, &payload.add, &payload.remove)?;
+    publish_group_copies(&state, events).await;
+    Ok(Json(group))
+}
+
+pub(crate) async fn leave_group_handler(
+    State(state): State<AppState>,
+    Path(group_id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let service = DmService::new(state.database.clone(), state.config.paths.clone());
+    let events = service.leave_group(&group_id)?;
+    publish_group_copies(&state, events).await;
+    Ok(StatusCode::OK)
+}
+
+/// Stores an encrypted file to be sent with a DM; its ID goes into `attachments`.
+pub(crate) async fn upload_dm_attachment_handler(
+    State(state): State<AppState>,
+    mut multipart: Multipart,
+) -> Result<(StatusCode, Json<DmAttachmentView>), ApiError> {
+    let service = file_service(&state);
+    let mut input = None;
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|err| ApiError::Internal(anyhow::Error::new(err)))?
+    {
+        if field.name() == Some("file") {
+            let original_name = field.file_name().map(|s| s.to_string());
+            let mime = field.content_type().map(|s| s.to_string());
+            let data = field
+                .bytes()
+                .await
+                .map_err(|err| ApiError::Internal(anyhow::Error::new(err)))?;
+            input = Some(SaveDmAttachmentInput {
+                original_name,
+                mime,
+                data: data.to_vec(),
+            });
+            break;
+        }
+    }
+    let input = input.ok_or_else(|| ApiError::BadRequest("missing file field".into()))?;
+
+    let view = service.save_dm_attachment(input).await?;
+    let blob_id = state
+        .database
+        .with_repositories(|repos| Ok(repos.dm_attachments().get(&view.id)?.map(|record| record.blob_id)))
+        .map_err(ApiError::Internal)?;
+    match blob_id.as_deref().and_then(|blob| state.network.make_blob_ticket(blob)) {
+        Some(ticket) => service
+            .persist_dm_attachment_ticket(&view.id, &ticket)
+            .map_err(ApiError::Internal)?,
+        None => tracing::warn!(attachment_id = %view.id, "no blob ticket for DM attachment"),
+    }
+    Ok((StatusCode::CREATED, Json(view)))
+}
+
+/// Serves the decrypted attachment once uploaded or downloaded.
+pub(crate) async fn get_dm_attachment_handler(
+    State(state): State<AppState>,
+    Path(attachment_id): Path<String>,
+) -> Result<Response, ApiError> {
+    let Some((view, absolute_path)) = file_service(&state)
+        .prepare_dm_attachment_download(&attachment_id)
+        .map_err(ApiError::Internal)?
+    else {
+        return Err(ApiError::NotFound(format!("attachment {attachment_id} not available")));
+    };
+
+    let file = TokioFile::open(&absolute_path)
+        .await
+        .with_context(|| format!("unable to open {}", absolute_path.display()))
+        .map_err(ApiError::Internal)?;
+    // The sender's size claim isn't trusted for the header
+    let size = file
+        .metadata()
+        .await
+        .context("unable to stat DM attachment")
+        .map_err(ApiError::Internal)?
+        .len();
+    let mut response = Response::new(Body::from_stream(ReaderStream::new(file)));
+    let headers = response.headers_mut();
+    // The mime type is the sender's claim; only types safe to render inline keep it
+    let content_type = INLINE_MIME_TYPES
+        .iter()
+        .find(|allowed| view.mime.as_deref() == Some(**allowed))
+        .copied()
+        .unwrap_or("application/octet-stream");
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+    headers.insert(X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+    headers.insert(CONTENT_LENGTH, HeaderValue::from(size));
+    let name = view
+        .original_name
+        .map(|name| name.chars().filter(|c| *c != '"' && *c != '\\').collect::<String>());
+    let disposition = match name {
+        Some(name) => format!("attachment; filename=\"{}\"", name),
+        None => "attachment".to_string(),
+    };
+    let disposition = HeaderValue::from_str(&disposition).unwrap_or(HeaderValue::from_static("attachment"));
+    headers.insert(CONTENT_DISPOSITION, disposition);
+    Ok(response)
+}
+
+/// Fetches a received attachment from its sender in the background.
+pub(crate) async fn download_dm_attachment_handler(
+    State(state): State<AppState>,
+    Path(attachment_id): Path<String>,
+) -> Result<Json<AttachmentDownloadResponse>, ApiError> {
+    let record = state
+        .database
+        .with_repositories(|repos| repos.dm_attachments().get(&attachment_id))
+        .map_err(ApiError::Internal)?
+        .ok_or_else(|| ApiError::NotFound(format!("attachment {attachment_id} not found")))?;
+    if record.download_status == "available" || record.download_status == "downloading" {
+        return Ok(Json(AttachmentDownloadResponse {
+            status: record.download_status,
+        }));
+    }
+    if record.ticket.is_none() {
+        return Err(ApiError::BadRequest("attachment has no download ticket".into()));
+    }
+
+    let service = file_service(&state);
+    let endpoint = state.network.endpoint();
+    tokio::spawn(async move {
+        if let Err(err) = service.fetch_dm_attachment(&attachment_id, &endpoint).await {
+            tracing::warn!(attachment_id = %attachment_id, error = ?err, "failed to fetch DM attachment");
+        }
+    });
+    Ok(Json(AttachmentDownloadResponse {
+        status: "downloading".to_string(),
+    }))
+}
+
+fn file_service(state: &AppState) -> FileService {
+    FileService::new(
+        state.database.clone(),
+        state.config.paths.clone(),
+        state.config.file.clone(),
+        state.blobs.clone(),
+    )
+}
+
+/// Queues one member's copy each; they are retried like any other DM.
+async fn publish_group_copies(state: &AppState, events: Vec<DirectMessageEvent>) {
+    for event in events {
+        if let Err(err) = state.network.publish_direct_message(event).await {
+            tracing::warn!(error = ?err, "failed to broadcast group DM over gossip");
+        }
+    }
+}
@@ -261,6 +261,7 @@ pub(crate) async fn trigger_file_download(
     let blobs = state.blobs.clone();
     let endpoint = state.network.endpoint();
     let file_id = id.clone();
+    let encryption_key = record.encryption_key.clone();
 
     tokio::spawn(async move {
         let hash = ticket.hash();
@@ -287,7 +288,7 @@ pub(crate) async fn trigger_file_download(
             }
 
             // Export to file
-            let relative_path = format!("files/downloads/{}", file_id);
+            let relative_path = crate::files::download_path(&file_id)?;
             let absolute_path = paths.base.join(&relative_path);
 
             // Ensure directory exists
@@ -300,9 +301,17 @@ pub(crate) async fn trigger_file_download(
                 .context("failed to export blob")?;
 
             // Read and verify
-            let data = tokio::fs::read(&absolute_path).await
+            let mut data = tokio::fs::read(&absolute_path).await
                 .context("failed to read exported file")?;
 
+            // Attachments of private threads are stored as ciphertext in the blob
+            if let Some(key) = encryption_key.as_deref() {
+                data = crate::private_threads::open_attachment(key, &data)
+                    .context("failed to decrypt private attachment")?;
+                tokio::fs::write(&absolute_path, &data).await
+                    .context("failed to write decrypted file")?;
+            }
+
             let size = data.len() as i64;
             let mut hasher = blake3::Hasher::new();
             hasher.update(&data);
@@ -1,3 +1,4 @@
+mod auth;
 mod blocking;
 mod dms;
 mod files;
@@ -5,24 +6,28 @@ mod peers;
 mod reactions;
 mod search;
 mod settings;
+mod stream;
 mod threads;
 
-use crate::config::GraphchanConfig;
+use crate::api_tokens::ApiTokenStore;
+use crate::config::{ApiConfig, GraphchanConfig};
 use crate::database::Database;
+use crate::errors::RequestError;
 use crate::files::FileView;
 use crate::identity::IdentitySummary;
 use crate::network::NetworkHandle;
 use anyhow::{Context, Result};
 use axum::extract::DefaultBodyLimit;
-use axum::http::StatusCode;
+use axum::http::{header, StatusCode};
+use axum::middleware;
 use axum::response::{IntoResponse, Response};
-use axum::routing::{delete, get, post};
+use axum::routing::{delete, get, patch, post, put};
 use axum::{Json, Router};
 use iroh_blobs::store::fs::FsStore;
 use serde::Serialize;
-use std::net::SocketAddr;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use tokio::net::TcpListener;
-use tower_http::cors::{Any, CorsLayer};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
 
 #[derive(Clone)]
 pub struct AppState {
@@ -32,6 +37,7 @@ pub struct AppState {
     pub network: NetworkHandle,
     pub blobs: FsStore,
     pub http_client: reqwest::Client,
+    pub tokens: ApiTokenStore,
 }
 
 pub(crate) type ApiResult<T> = Result<Json<T>, ApiError>;
@@ -39,6 +45,8 @@ pub(crate) type ApiResult<T> = Result<Json<T>, ApiError>;
 #[derive(Debug)]
 pub enum ApiError {
     BadRequest(String),
+    Unauthorized(String),
+    Forbidden(String),
     NotFound(String),
     Internal(anyhow::Error),
 }
@@ -47,6 +55,8 @@ impl ApiError {
     fn into_response_parts(self) -> (StatusCode, ErrorResponse) {
         match self {
             ApiError::BadRequest(msg) => (StatusCode::BAD_REQUEST, ErrorResponse { message: msg }),
+            ApiError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, ErrorResponse { message: msg }),
+            ApiError::Forbidden(msg) => (StatusCode::FORBIDDEN, ErrorResponse { message: msg }),
             ApiError::NotFound(msg) => (StatusCode::NOT_FOUND, ErrorResponse { message: msg }),
             ApiError::Internal(err) => {
                 tracing::error!(error = ?err, "internal server error");
@@ -64,13 +74,21 @@ impl ApiError {
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
         let (status, body) = self.into_response_parts();
+        if status == StatusCode::UNAUTHORIZED {
+            return (status, [(header::WWW_AUTHENTICATE, "Bearer")], Json(body)).into_response();
+        }
         (status, Json(body)).into_response()
     }
 }
 
 impl From<anyhow::Error> for ApiError {
     fn from(err: anyhow::Error) -> Self {
-        ApiError::Internal(err)
+        match err.downcast_ref::<RequestError>() {
+            Some(RequestError::NotFound(message)) => ApiError::NotFound(message.clone()),
+            Some(RequestError::Invalid(message)) => ApiError::BadRequest(message.clone()),
+            Some(RequestError::Forbidden(message)) => ApiError::Forbidden(message.clone()),
+            None => ApiError::Internal(err),
+        }
     }
 }
 
@@ -113,13 +131,24 @@ pub(crate) fn map_file_view(file: FileView) -> FileResponse {
     }
 }
 
+/// CORS for browser clients: loopback pages and `GRAPHCHAN_CORS_ORIGINS` only.
+fn cors_layer(api: &ApiConfig) -> CorsLayer {
+    let api = api.clone();
+    CorsLayer::new()
+        .allow_origin(AllowOrigin::predicate(move |origin, _| {
+            origin.to_str().is_ok_and(|origin| api.allows_origin(origin))
+        }))
+        .allow_methods(Any)
+        .allow_headers(Any)
+}
+
 /// Tries to bind to the given port, or finds the next available port
-async fn find_available_port(start_port: u16) -> Result<(TcpListener, u16)> {
+async fn find_available_port(ip: IpAddr, start_port: u16) -> Result<(TcpListener, u16)> {
     const MAX_PORT_ATTEMPTS: u16 = 100;
 
     for offset in 0..MAX_PORT_ATTEMPTS {
         let port = start_port + offset;
-        let addr = SocketAddr::from(([0, 0, 0, 0], port));
+        let addr = SocketAddr::new(ip, port);
 
         match TcpListener::bind(addr).await {
             Ok(listener) => return Ok((listener, port)),
@@ -152,6 +181,13 @@ pub async fn serve_http(
         .build()
         .context("failed to build shared HTTP client")?;
 
+    let tokens = ApiTokenStore::open(&config.paths)?;
+    if config.api.require_auth {
+        tokens.ensure_owner_token()?;
+    } else {
+        tracing::warn!("HTTP API authentication is disabled (GRAPHCHAN_DISABLE_API_AUTH)");
+    }
+
     let state = AppState {
         config: config.clone(),
         identity,
@@ -159,6 +195,7 @@ pub async fn serve_http(
         network,
         blobs,
         http_client,
+        tokens,
     };
 
     // Configure body limit for file uploads (default 10GB if not specified)
@@ -166,6 +203,7 @@ pub async fn serve_http(
     let max_upload_bytes = config.file.max_upload_bytes.unwrap_or(10 * 1024 * 1024 * 1024);
     let router = Router::new()
         .route("/health", get(threads::health_handler))
+        .route("/network/rate-limits", get(threads::rate_limit_stats_handler))
         .route("/threads", get(threads::list_threads).post(threads::create_thread))
         .route("/threads/:id", get(threads::get_thread))
         .route("/threads/:id/download", post(threads::download_thread))
@@ -173,7 +211,14 @@ pub async fn serve_http(
         .route("/threads/:id/delete", post(threads::delete_thread))
         .route("/threads/:id/ignore", post(threads::set_thread_ignored))
         .route("/threads/:id/posts", post(threads::create_post))
+        .route("/threads/:id/members", get(threads::list_thread_members).post(threads::invite_thread_member))
+        .route("/threads/:id/members/:peer_id", delete(threads::remove_thread_member))
         .route("/posts/recent", get(threads::list_recent_posts))
+        .route("/posts/:id", get(threads::get_post).patch(threads::edit_post).delete(threads::delete_post))
+        .route("/posts/:id/ancestors", get(threads::get_post_ancestors))
+        .route("/posts/:id/descendants", get(threads::get_post_descendants))
+        .route("/posts/:id/path/:other_id", get(threads::get_post_path))
+        .route("/posts/:id/history", get(threads::get_post_history))
         .route("/posts/:id/files", get(files::list_post_files))
         .route("/posts/:id/files", post(files::upload_post_file))
         .route("/posts/:id/reactions", get(reactions::get_post_reactions))
@@ -184,6 +229,7 @@ pub async fn serve_http(
         .route("/peers", get(peers::list_peers))
         .route("/peers", post(peers::add_peer))
         .route("/peers/:id/unfollow", post(peers::unfollow_peer))
+        .route("/peers/:id/trust", put(peers::set_peer_trust_handler))
         .route("/peers/self", get(peers::get_self_peer))
         .route("/identity/avatar", post(peers::upload_avatar))
         .route("/identity/profile", post(peers::update_profile_handler))
@@ -199,11 +245,25 @@ pub async fn serve_http(
         .route("/dms/:peer_id/messages", get(dms::get_messages_handler))
         .route("/dms/messages/:message_id/read", post(dms::mark_message_read_handler))
         .route("/dms/unread/count", get(dms::count_unread_handler))
+        .route("/dms/attachments", post(dms::upload_dm_attachment_handler))
+        .route("/dms/attachments/:attachment_id", get(dms::get_dm_attachment_handler))
+        .route("/dms/attachments/:attachment_id/download", post(dms::download_dm_attachment_handler))
+        .route("/dms/groups", get(dms::list_groups_handler).post(dms::create_group_handler))
+        .route("/dms/groups/:group_id", get(dms::get_group_handler))
+        .route(
+            "/dms/groups/:group_id/messages",
+            get(dms::get_group_messages_handler).post(dms::send_group_dm_handler),
+        )
+        .route("/dms/groups/:group_id/members", post(dms::update_group_members_handler))
+        .route("/dms/groups/:group_id/leave", post(dms::leave_group_handler))
         .route("/blocking/peers", get(blocking::list_blocked_peers_handler))
         .route("/blocking/peers/:peer_id", post(blocking::block_peer_handler))
         .route("/blocking/peers/:peer_id", delete(blocking::unblock_peer_handler))
         .route("/blocking/peers/export", get(blocking::export_peer_blocks_handler))
         .route("/blocking/peers/import", post(blocking::import_peer_blocks_handler))
+        .route("/blocking/quarantine", get(blocking::list_quarantine_handler))
+        .route("/blocking/quarantine/:post_id/approve", post(blocking::approve_quarantined_post_handler))
+        .route("/blocking/quarantine/:post_id/reject", post(blocking::reject_quarantined_post_handler))
         .route("/blocking/blocklists", get(blocking::list_blocklists_handler))
         .route("/blocking/blocklists", post(blocking::subscribe_blocklist_handler))
         .route("/blocking/blocklists/:id", delete(blocking::unsubscribe_blocklist_handler))
@@ -217,16 +277,22 @@ pub async fn serve_http(
         .route("/blocking/ips/stats", get(blocking::ip_block_stats_handler))
         .route("/peers/:peer_id/ip", get(blocking::get_peer_ip_handler))
         .route("/search", get(search::search_handler))
+        .route("/events", get(stream::event_stream_handler))
         .route("/settings/:key", get(settings::get_setting_handler).put(settings::set_setting_handler))
         .route("/topics", get(settings::list_topics_handler).post(settings::subscribe_topic_handler))
-        .route("/topics/:topic_id", delete(settings::unsubscribe_topic_handler))
-        .layer(DefaultBodyLimit::max(max_upload_bytes as usize))
-        .layer(
-            CorsLayer::new()
-                .allow_origin(Any)
-                .allow_methods(Any)
-                .allow_headers(Any),
+        .route(
+            "/topics/:topic_id",
+            get(settings::get_topic_handler)
+                .patch(settings::update_topic_settings_handler)
+                .delete(settings::unsubscribe_topic_handler),
         )
+        .route("/topics/:topic_id/metadata", put(settings::publish_topic_metadata_handler))
+        .route("/topics/:topic_id/pow", put(settings::set_topic_pow_handler))
+        .route("/auth/tokens", get(auth::list_tokens_handler).post(auth::create_token_handler))
+        .route("/auth/tokens/:id", delete(auth::revoke_token_handler))
+        .route_layer(middleware::from_fn_with_state(state.clone(), auth::require_token))
+        .layer(DefaultBodyLimit::max(max_upload_bytes as usize))
+        .layer(cors_layer(&config.api))
         .with_state(state.clone());
 
     tracing::info!(
@@ -235,8 +301,13 @@ pub async fn serve_http(
     );
 
     // Try to bind to the configured port, or find the next available port
-    let (listener, actual_port) = find_available_port(config.api_port).await?;
-    let addr = SocketAddr::from(([0, 0, 0, 0], actual_port));
+    let bind_ip = if config.api.loopback_only {
+        IpAddr::V4(Ipv4Addr::LOCALHOST)
+    } else {
+        IpAddr::V4(Ipv4Addr::UNSPECIFIED)
+    };
+    let (listener, actual_port) = find_available_port(bind_ip, config.api_port).await?;
+    let addr = SocketAddr::new(bind_ip, actual_port);
 
     if actual_port != config.api_port {
         tracing::warn!(
@@ -1,9 +1,12 @@
 use super::{AppState, ApiError, ApiResult};
+use crate::agents::{AgentKeyView, AgentService};
 use crate::database::repositories::PeerRepository;
+use crate::dms::DmService;
 use crate::files::FileService;
 use crate::identity::decode_friendcode_auto;
-use crate::network::ProfileUpdate;
+use crate::network::{DmPrekeyBundle, ProfileUpdate};
 use crate::peers::{PeerService, PeerView};
+use crate::trust::{TrustLevel, TrustService};
 use axum::extract::{Multipart, Path, State};
 use axum::http::StatusCode;
 use axum::Json;
@@ -17,6 +20,19 @@ pub(crate) struct AddPeerRequest {
     friendcode: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub(crate) struct SetTrustRequest {
+    level: String,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct SetTrustResponse {
+    peer_id: String,
+    trust_state: String,
+    /// Quarantined posts shown again because the peer is now a friend or known
+    released_posts: usize,
+}
+
 #[derive(Debug, Deserialize)]
 pub(crate) struct UpdateProfileRequest {
     pub username: Option<String>,
@@ -30,7 +46,10 @@ pub(crate) struct AddAgentRequest {
 
 #[derive(Debug, Serialize)]
 pub(crate) struct AgentsResponse {
+    /// Active agent names
     pub agents: Vec<String>,
+    /// Delegated posting keys, revoked ones included
+    pub keys: Vec<AgentKeyView>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -129,6 +148,26 @@ pub(crate) async fn unfollow_peer(
     Ok(StatusCode::OK)
 }
 
+pub(crate) async fn set_peer_trust_handler(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(request): Json<SetTrustRequest>,
+) -> ApiResult<SetTrustResponse> {
+    let level = TrustLevel::parse(request.level.trim()).map_err(|err| ApiError::BadRequest(err.to_string()))?;
+    if id == state.identity.gpg_fingerprint {
+        return Err(ApiError::BadRequest("cannot change trust in ourselves".into()));
+    }
+    let released_posts = TrustService::new(state.database.clone())
+        .set_level(&id, level)
+        .map_err(ApiError::Internal)?
+        .ok_or_else(|| ApiError::NotFound(format!("peer not found: {id}")))?;
+    Ok(Json(SetTrustResponse {
+        peer_id: id,
+        trust_state: level.as_str().to_string(),
+        released_posts,
+    }))
+}
+
 pub(crate) async fn upload_avatar(
     State(state): State<AppState>,
     mut multipart: Multipart,
@@ -187,6 +226,8 @@ pub(crate) async fn upload_avatar(
         bio: None,
         agents: None,
         x25519_pubkey: Some(state.identity.x25519_pubkey.clone()),
+        dm_prekey: local_dm_prekey(&state),
+        agent_keys: None,
     };
     state.network.publish_profile_update(update).await.map_err(ApiError::Internal)?;
 
@@ -221,6 +262,8 @@ pub(crate) async fn update_profile_handler(
         bio: payload.bio,
         agents: None,
         x25519_pubkey: Some(state.identity.x25519_pubkey.clone()),
+        dm_prekey: local_dm_prekey(&state),
+        agent_keys: None,
     };
     state.network.publish_profile_update(update).await.map_err(ApiError::Internal)?;
 
@@ -230,84 +273,81 @@ pub(crate) async fn update_profile_handler(
 pub(crate) async fn get_agents_handler(
     State(state): State<AppState>,
 ) -> Result<Json<AgentsResponse>, ApiError> {
-    let peer_service = PeerService::new(state.database.clone());
-
-    let peer = peer_service.get_local_peer().map_err(ApiError::Internal)?;
-    let agents = peer.and_then(|p| p.agents).unwrap_or_default();
-
-    Ok(Json(AgentsResponse { agents }))
+    let service = AgentService::new(state.database.clone(), state.config.paths.clone());
+    let keys = service.list()?;
+    let agents = keys
+        .iter()
+        .filter(|key| key.revoked_at.is_none())
+        .map(|key| key.name.clone())
+        .collect();
+
+    Ok(Json(AgentsResponse { agents, keys }))
 }
 
+/// Delegates a posting key to a new agent; an existing active agent is returned as is.
 pub(crate) async fn add_agent_handler(
     State(state): State<AppState>,
     Json(payload): Json<AddAgentRequest>,
-) -> Result<StatusCode, ApiError> {
-    let peer_id = state.identity.gpg_fingerprint.clone();
-    let peer_service = PeerService::new(state.database.clone());
-
-    // Get current agents list
-    let peer = peer_service.get_local_peer().map_err(ApiError::Internal)?;
-    let mut agents = peer.and_then(|p| p.agents).unwrap_or_default();
-
-    // Add new agent if not already present
-    if !agents.contains(&payload.name) {
-        agents.push(payload.name.clone());
-
-        // Update profile with new agents list
-        peer_service.update_profile(&peer_id, None, None, None, Some(agents.clone()), None)
-            .map_err(ApiError::Internal)?;
-
-        // Broadcast ProfileUpdate
-        let update = ProfileUpdate {
-            peer_id: peer_id.clone(),
-            avatar_file_id: None,
-            ticket: None,
-            username: None,
-            bio: None,
-            agents: Some(agents),
-            x25519_pubkey: Some(state.identity.x25519_pubkey.clone()),
-        };
-        state.network.publish_profile_update(update).await.map_err(ApiError::Internal)?;
+) -> Result<(StatusCode, Json<AgentKeyView>), ApiError> {
+    let service = AgentService::new(state.database.clone(), state.config.paths.clone());
+    let name = payload.name.trim();
+    if let Some(existing) = service
+        .list()?
+        .into_iter()
+        .find(|key| key.revoked_at.is_none() && key.name == name)
+    {
+        return Ok((StatusCode::OK, Json(existing)));
     }
 
-    Ok(StatusCode::OK)
+    let created = service.create(name)?;
+    publish_agent_roster(&state, &service).await?;
+
+    Ok((StatusCode::CREATED, Json(created)))
 }
 
+/// Revokes the agent's posting key; peers reject its posts from then on.
 pub(crate) async fn remove_agent_handler(
     State(state): State<AppState>,
     Path(name): Path<String>,
 ) -> Result<StatusCode, ApiError> {
-    let peer_id = state.identity.gpg_fingerprint.clone();
-    let peer_service = PeerService::new(state.database.clone());
-
-    // Get current agents list
-    let peer = peer_service.get_local_peer().map_err(ApiError::Internal)?;
-    let mut agents = peer.and_then(|p| p.agents).unwrap_or_default();
-
-    // Remove agent if present
-    if agents.iter().position(|a| a == &name).is_some() {
-        agents.retain(|a| a != &name);
-
-        // Update profile with new agents list
-        peer_service.update_profile(&peer_id, None, None, None, Some(agents.clone()), None)
-            .map_err(ApiError::Internal)?;
-
-        // Broadcast ProfileUpdate
-        let update = ProfileUpdate {
-            peer_id: peer_id.clone(),
-            avatar_file_id: None,
-            ticket: None,
-            username: None,
-            bio: None,
-            agents: Some(agents),
-            x25519_pubkey: Some(state.identity.x25519_pubkey.clone()),
-        };
-        state.network.publish_profile_update(update).await.map_err(ApiError::Internal)?;
+    let service = AgentService::new(state.database.clone(), state.config.paths.clone());
+    if service.revoke(&name)?.is_some() {
+        publish_agent_roster(&state, &service).await?;
     }
 
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// Stores our active agent names and broadcasts them with the signed roster.
+async fn publish_agent_roster(state: &AppState, service: &AgentService) -> Result<(), ApiError> {
+    let peer_id = state.identity.gpg_fingerprint.clone();
+    let agents = service.active_names()?;
+    PeerService::new(state.database.clone())
+        .update_profile(&peer_id, None, None, None, Some(agents.clone()), None)
+        .map_err(ApiError::Internal)?;
+
+    let update = ProfileUpdate {
+        peer_id,
+        avatar_file_id: None,
+        ticket: None,
+        username: None,
+        bio: None,
+        agents: Some(agents),
+        x25519_pubkey: Some(state.identity.x25519_pubkey.clone()),
+        dm_prekey: local_dm_prekey(state),
+        agent_keys: Some(service.roster()?),
+    };
+    state.network.publish_profile_update(update).await.map_err(ApiError::Internal)
+}
+
+/// Our signed DM prekey to ride along in a `ProfileUpdate`; `None` if it can't be prepared.
+fn local_dm_prekey(state: &AppState) -> Option<DmPrekeyBundle> {
+    DmService::new(state.database.clone(), state.config.paths.clone())
+        .prekey_bundle()
+        .map_err(|err| tracing::warn!(error = ?err, "failed to prepare DM prekey bundle"))
+        .ok()
+}
+
 pub(crate) async fn get_theme_color_handler(
     State(state): State<AppState>,
 ) -> Result<Json<ThemeColorResponse>, ApiError> {
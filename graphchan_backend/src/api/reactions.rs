@@ -72,40 +72,35 @@ pub(crate) async fn add_reaction(
         result.map_err(|e| anyhow::Error::from(e))
     })?;
 
-    // Create signature (simplified - in production would use GPG)
-    let signature = format!("sig:{}:{}:{}", post_id, identity, payload.emoji);
-    let created_at = chrono::Utc::now().to_rfc3339();
-
-    let reaction = ReactionRecord {
-        post_id: post_id.clone(),
-        reactor_peer_id: identity,
-        emoji: payload.emoji,
-        signature,
-        created_at,
-    };
-
-    state.database.with_repositories(|repos| {
-        repos.reactions().add(&reaction)
-    })?;
-
-    // Get thread_id for the post
+    // Get thread_id for the post (it is part of the signed payload)
     let thread_id: String = state.database.with_repositories(|repos| {
         use crate::database::repositories::PostRepository;
         let post = repos.posts().get(&post_id)?;
         Ok(post.ok_or_else(|| anyhow::anyhow!("Post not found"))?.thread_id)
     })?;
 
-    // Broadcast via gossip
-    let reaction_update = crate::network::ReactionUpdate {
+    let reaction_update = signed_reaction_update(
+        &state,
         post_id,
         thread_id,
-        reactor_peer_id: reaction.reactor_peer_id.clone(),
-        emoji: reaction.emoji.clone(),
-        signature: reaction.signature.clone(),
-        created_at: reaction.created_at.clone(),
-        is_removal: false,
+        identity,
+        payload.emoji,
+        false,
+    )?;
+
+    let reaction = ReactionRecord {
+        post_id: reaction_update.post_id.clone(),
+        reactor_peer_id: reaction_update.reactor_peer_id.clone(),
+        emoji: reaction_update.emoji.clone(),
+        signature: reaction_update.signature.clone(),
+        created_at: reaction_update.created_at.clone(),
     };
 
+    state.database.with_repositories(|repos| {
+        repos.reactions().add(&reaction)
+    })?;
+
+    // Broadcast via gossip
     state.network.publish_reaction_update(reaction_update).await?;
 
     Ok(StatusCode::OK)
@@ -117,6 +112,7 @@ pub(crate) async fn remove_reaction(
     Json(payload): Json<RemoveReactionRequest>,
 ) -> Result<StatusCode, ApiError> {
     use crate::database::repositories::ReactionRepository;
+    use crate::database::models::ReactionRecord;
 
     let identity: String = state.database.with_repositories(|repos| {
         let result: Result<String, rusqlite::Error> = repos.conn().query_row(
@@ -127,10 +123,6 @@ pub(crate) async fn remove_reaction(
         result.map_err(|e| anyhow::Error::from(e))
     })?;
 
-    state.database.with_repositories(|repos| {
-        repos.reactions().remove(&post_id, &identity, &payload.emoji)
-    })?;
-
     // Get thread_id for the post
     let thread_id: String = state.database.with_repositories(|repos| {
         use crate::database::repositories::PostRepository;
@@ -138,18 +130,57 @@ pub(crate) async fn remove_reaction(
         Ok(post.ok_or_else(|| anyhow::anyhow!("Post not found"))?.thread_id)
     })?;
 
-    // Broadcast unreact via gossip
-    let reaction_update = crate::network::ReactionUpdate {
+    // Broadcast unreact via gossip (signed so peers can't strip others' reactions)
+    let reaction_update = signed_reaction_update(
+        &state,
         post_id,
         thread_id,
-        reactor_peer_id: identity,
-        emoji: payload.emoji,
-        signature: "".to_string(), // Not needed for removal
-        created_at: chrono::Utc::now().to_rfc3339(),
-        is_removal: true,
+        identity,
+        payload.emoji,
+        true,
+    )?;
+
+    // The signed removal is kept so a replayed add can't bring the reaction back
+    let removal = ReactionRecord {
+        post_id: reaction_update.post_id.clone(),
+        reactor_peer_id: reaction_update.reactor_peer_id.clone(),
+        emoji: reaction_update.emoji.clone(),
+        signature: reaction_update.signature.clone(),
+        created_at: reaction_update.created_at.clone(),
     };
+    state.database.with_repositories(|repos| {
+        repos.reactions().remove(&removal.post_id, &removal.reactor_peer_id, &removal.emoji)?;
+        repos.reactions().record_removal(&removal)
+    })?;
 
     state.network.publish_reaction_update(reaction_update).await?;
 
     Ok(StatusCode::OK)
 }
+
+/// Builds a reaction add/removal signed with the local node key.
+fn signed_reaction_update(
+    state: &AppState,
+    post_id: String,
+    thread_id: String,
+    reactor_peer_id: String,
+    emoji: String,
+    is_removal: bool,
+) -> Result<crate::network::ReactionUpdate, ApiError> {
+    let mut update = crate::network::ReactionUpdate {
+        post_id,
+        thread_id,
+        reactor_peer_id,
+        emoji,
+        signature: String::new(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+        is_removal,
+        reactor_public_key: None,
+    };
+    let paths = &state.config.paths;
+    update.signature = crate::crypto::sign_detached(paths, &update.signing_payload())
+        .map_err(ApiError::Internal)?;
+    update.reactor_public_key =
+        Some(crate::crypto::load_public_key_armored(paths).map_err(ApiError::Internal)?);
+    Ok(update)
+}
@@ -70,10 +70,17 @@ pub(crate) async fn search_handler(
                 body: r.post.body,
                 created_at: r.post.created_at,
                 updated_at: r.post.updated_at,
+                deleted: false,
+                quarantined: false,
+                pow: None,
                 parent_post_ids: Vec::new(),
                 files: Vec::new(),
                 thread_hash: None,
+                canonical_thread_hash: None,
                 metadata,
+                signature: r.post.signature,
+                signature_status: r.post.signature_status,
+                author_public_key: None,
             },
             file: r.file.map(|f| {
                 let download_url = format!("/files/{}", f.id);
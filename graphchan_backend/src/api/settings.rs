@@ -1,8 +1,9 @@
 use super::{AppState, ApiError, ApiResult};
+use crate::topic_config::{TopicConfigService, TopicMetadataDraft, TopicSettingsUpdate, TopicView};
 use axum::extract::{Path, State};
 use axum::http::StatusCode;
 use axum::Json;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize)]
 pub(crate) struct SetSettingRequest {
@@ -14,6 +15,17 @@ pub(crate) struct SubscribeTopicRequest {
     topic_id: String,
 }
 
+#[derive(Deserialize)]
+pub(crate) struct SetTopicPowRequest {
+    difficulty: u32,
+}
+
+#[derive(Serialize)]
+pub(crate) struct TopicPowResponse {
+    topic_id: String,
+    difficulty: u32,
+}
+
 pub(crate) async fn get_setting_handler(
     State(state): State<AppState>,
     Path(key): Path<String>,
@@ -69,10 +81,11 @@ pub(crate) async fn unsubscribe_topic_handler(
     State(state): State<AppState>,
     Path(topic_id): Path<String>,
 ) -> Result<StatusCode, ApiError> {
-    use crate::database::repositories::TopicRepository;
+    use crate::database::repositories::{TopicAddressRepository, TopicRepository};
 
     state.database.with_repositories(|repos| {
-        repos.topics().unsubscribe(&topic_id)
+        repos.topics().unsubscribe(&topic_id)?;
+        repos.topic_addresses().clear_topic(&topic_id)
     }).map_err(ApiError::Internal)?;
 
     // Note: We don't unsubscribe from the gossip topic because it's harmless to stay subscribed
@@ -80,3 +93,74 @@ pub(crate) async fn unsubscribe_topic_handler(
 
     Ok(StatusCode::NO_CONTENT)
 }
+
+/// Sets the proof-of-work difficulty demanded of non-friends on a subscribed topic.
+pub(crate) async fn set_topic_pow_handler(
+    State(state): State<AppState>,
+    Path(topic_id): Path<String>,
+    Json(req): Json<SetTopicPowRequest>,
+) -> ApiResult<TopicPowResponse> {
+    let pow = crate::pow::PowService::new(state.database.clone());
+    pow.set_topic_difficulty(&topic_id, req.difficulty).map_err(topic_error)?;
+    Ok(Json(TopicPowResponse {
+        topic_id,
+        difficulty: req.difficulty,
+    }))
+}
+
+pub(crate) async fn get_topic_handler(
+    State(state): State<AppState>,
+    Path(topic_id): Path<String>,
+) -> ApiResult<TopicView> {
+    let service = TopicConfigService::new(state.database.clone());
+    let view = service
+        .view(&topic_id, &state.identity.gpg_fingerprint)
+        .map_err(ApiError::Internal)?;
+    Ok(Json(view))
+}
+
+/// Changes our local settings for a subscribed topic.
+pub(crate) async fn update_topic_settings_handler(
+    State(state): State<AppState>,
+    Path(topic_id): Path<String>,
+    Json(update): Json<TopicSettingsUpdate>,
+) -> ApiResult<TopicView> {
+    let service = TopicConfigService::new(state.database.clone());
+    service.update_settings(&topic_id, update).map_err(topic_error)?;
+    let view = service
+        .view(&topic_id, &state.identity.gpg_fingerprint)
+        .map_err(ApiError::Internal)?;
+    Ok(Json(view))
+}
+
+/// Signs a new version of a topic's metadata as its founder and broadcasts it.
+pub(crate) async fn publish_topic_metadata_handler(
+    State(state): State<AppState>,
+    Path(topic_id): Path<String>,
+    Json(draft): Json<TopicMetadataDraft>,
+) -> ApiResult<TopicView> {
+    let service = TopicConfigService::new(state.database.clone());
+    let metadata = service
+        .publish_metadata(&state.config.paths, &state.identity.gpg_fingerprint, &topic_id, draft)
+        .map_err(topic_error)?;
+    state
+        .network
+        .publish_topic_metadata(metadata)
+        .await
+        .map_err(ApiError::Internal)?;
+    let view = service
+        .view(&topic_id, &state.identity.gpg_fingerprint)
+        .map_err(ApiError::Internal)?;
+    Ok(Json(view))
+}
+
+fn topic_error(err: anyhow::Error) -> ApiError {
+    let message = err.to_string();
+    if message.contains("not subscribed") {
+        ApiError::NotFound(message)
+    } else if message.contains("maintained by") || message.contains("at most") {
+        ApiError::BadRequest(message)
+    } else {
+        ApiError::Internal(err)
+    }
+}
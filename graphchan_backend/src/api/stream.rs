@@ -0,0 +1,144 @@
+use super::{ApiError, AppState};
+use crate::api_tokens::{ApiScope, ApiTokenView};
+use crate::live::{LiveEvent, LiveEventService, LiveFilter};
+use axum::extract::{Extension, Query, State};
+use axum::http::HeaderMap;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use futures_util::Stream;
+use serde::Deserialize;
+use std::convert::Infallible;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// How often an idle stream looks for new events in the log.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Events read from the log per query while catching up.
+const REPLAY_PAGE: usize = 256;
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct EventStreamQuery {
+    /// Resume after this `seq`; the `Last-Event-ID` header takes precedence
+    pub since: Option<i64>,
+    pub thread: Option<String>,
+    pub topic: Option<String>,
+    pub peer: Option<String>,
+    /// Comma-separated event types
+    pub types: Option<String>,
+}
+
+/// `GET /events`: server-sent events for everything ingest applies.
+///
+/// Each SSE message has the event type as `event`, its `seq` as `id` and the
+/// `LiveEvent` as JSON `data`. Without a cursor the stream starts at "now".
+/// If the cursor is older than the retained log, a `gap` message comes first
+/// so the client knows to refetch state. `direct_message` events are only
+/// sent to tokens with the DM scope.
+pub(crate) async fn event_stream_handler(
+    State(state): State<AppState>,
+    token: Option<Extension<ApiTokenView>>,
+    headers: HeaderMap,
+    Query(query): Query<EventStreamQuery>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    let can_read_dms = !state.config.api.require_auth || token.is_some_and(|Extension(token)| token.grants(ApiScope::Dm));
+    let filter = LiveFilter {
+        thread_id: query.thread,
+        topic: query.topic,
+        peer_id: query.peer,
+        kinds: query.types.map(|types| {
+            types
+                .split(',')
+                .map(str::trim)
+                .filter(|kind| !kind.is_empty())
+                .map(str::to_string)
+                .collect()
+        }),
+        hide_direct_messages: !can_read_dms,
+    };
+    filter
+        .validate()
+        .map_err(|err| ApiError::BadRequest(err.to_string()))?;
+
+    let last_event_id = headers
+        .get("last-event-id")
+        .and_then(|value| value.to_str().ok())
+        .map(|value| {
+            value
+                .trim()
+                .parse::<i64>()
+                .map_err(|_| ApiError::BadRequest(format!("invalid Last-Event-ID: {value}")))
+        })
+        .transpose()?;
+
+    let service = LiveEventService::new(state.database.clone());
+    let cursor = match last_event_id.or(query.since) {
+        Some(cursor) => cursor.max(0),
+        None => service.latest_seq()?,
+    };
+
+    let (tx, rx) = mpsc::channel(64);
+    tokio::spawn(forward_events(service, filter, cursor, tx));
+    let stream = futures_util::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|event| (Ok(event), rx))
+    });
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+/// Tails the event log from `cursor` into `tx` until the client goes away.
+async fn forward_events(service: LiveEventService, filter: LiveFilter, mut cursor: i64, tx: mpsc::Sender<Event>) {
+    loop {
+        let batch = match service.after(cursor, REPLAY_PAGE) {
+            Ok(batch) => batch,
+            Err(err) => {
+                tracing::warn!(error = ?err, "failed to read live events");
+                return;
+            }
+        };
+
+        // `seq`s are contiguous except where old events were pruned
+        if let Some(first) = batch.first() {
+            if first.seq > cursor + 1 {
+                let gap = Event::default()
+                    .event("gap")
+                    .data(format!(r#"{{"missed_after":{},"resumed_at":{}}}"#, cursor, first.seq));
+                if tx.send(gap).await.is_err() {
+                    return;
+                }
+            }
+        }
+
+        let caught_up = batch.len() < REPLAY_PAGE;
+        for event in batch {
+            cursor = event.seq;
+            if !filter.matches(&event) {
+                continue;
+            }
+            let Some(message) = sse_message(&event) else {
+                continue;
+            };
+            if tx.send(message).await.is_err() {
+                return;
+            }
+        }
+
+        if caught_up {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            if tx.is_closed() {
+                return;
+            }
+        }
+    }
+}
+
+fn sse_message(event: &LiveEvent) -> Option<Event> {
+    match Event::default()
+        .id(event.seq.to_string())
+        .event(event.data.kind())
+        .json_data(event)
+    {
+        Ok(message) => Some(message),
+        Err(err) => {
+            tracing::warn!(error = ?err, seq = event.seq, "failed to encode live event");
+            None
+        }
+    }
+}
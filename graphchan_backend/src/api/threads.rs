@@ -1,12 +1,17 @@
-use super::{AppState, ApiError, FileResponse, map_file_view};
+use super::{auth, AppState, ApiError, FileResponse, map_file_view};
+use crate::api_tokens::ApiTokenView;
 use crate::database::Database;
 use crate::database::repositories::{ThreadRepository, PostRepository, FileRepository};
 use crate::files::{FileService, FileView};
 use crate::identity::IdentitySummary;
-use crate::network::{FileAnnouncement, NetworkHandle, DhtStatus};
-use crate::threading::{CreatePostInput, CreateThreadInput, ThreadDetails, ThreadService, ThreadSummary};
+use crate::network::dedup::DedupStats;
+use crate::network::ratelimit::RateLimitStats;
+use crate::network::{FileAnnouncement, NetworkHandle, DhtStatus, PostEditEvent};
+use crate::private_threads::{PrivateThreadService, ThreadMemberView};
+use crate::threading::{CreatePostInput, CreateThreadInput, PostGraph, PostHistory, PostPath, PostView, ThreadDetails, ThreadService, ThreadSummary};
+use crate::trust::TrustService;
 use anyhow::{Context, Result};
-use axum::extract::{Multipart, Path, Query, State};
+use axum::extract::{Extension, Multipart, Path, Query, State};
 use axum::http::StatusCode;
 use axum::Json;
 use iroh_blobs::store::fs::FsStore;
@@ -43,6 +48,13 @@ pub(crate) struct RecentPostsResponse {
     pub posts: Vec<RecentPostView>,
 }
 
+#[derive(Debug, Deserialize)]
+pub(crate) struct TraversalParams {
+    /// Generations to follow; all of them when omitted
+    #[serde(default)]
+    depth: Option<usize>,
+}
+
 #[derive(Debug, Deserialize)]
 pub(crate) struct RecentPostsParams {
     #[serde(default = "default_recent_limit")]
@@ -75,6 +87,7 @@ pub(crate) struct NetworkInfo {
     peer_id: String,
     addresses: Vec<String>,
     dht_status: String,  // "checking", "connected", or "unreachable"
+    dedup: DedupStats,
 }
 
 impl NetworkInfo {
@@ -98,6 +111,7 @@ impl NetworkInfo {
             peer_id: handle.peer_id(),
             addresses,
             dht_status,
+            dedup: handle.dedup_stats(),
         }
     }
 }
@@ -120,6 +134,16 @@ pub(crate) struct SetIgnoredRequest {
     ignored: bool,
 }
 
+#[derive(Debug, Deserialize)]
+pub(crate) struct EditPostRequest {
+    body: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct InviteMemberRequest {
+    peer_id: String,
+}
+
 pub(crate) async fn health_handler(State(state): State<AppState>) -> Json<HealthResponse> {
     Json(HealthResponse {
         status: "ok",
@@ -135,6 +159,10 @@ pub(crate) async fn health_handler(State(state): State<AppState>) -> Json<Health
     })
 }
 
+pub(crate) async fn rate_limit_stats_handler(State(state): State<AppState>) -> Json<RateLimitStats> {
+    Json(state.network.rate_limit_stats())
+}
+
 pub(crate) async fn list_threads(
     State(state): State<AppState>,
     Query(params): Query<ListThreadsParams>,
@@ -151,7 +179,10 @@ pub(crate) async fn get_thread(
 ) -> ApiResult<ThreadDetails> {
     let service = ThreadService::with_file_paths(state.database.clone(), state.config.paths.clone());
     match service.get_thread(&id)? {
-        Some(thread) => Ok(Json(thread)),
+        Some(mut thread) => {
+            TrustService::new(state.database.clone()).apply_to_thread(&mut thread)?;
+            Ok(Json(thread))
+        }
         None => Err(ApiError::NotFound(format!("thread {id} not found"))),
     }
 }
@@ -206,6 +237,11 @@ pub(crate) async fn download_thread(
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!("failed to read blob: {}", e)))?;
 
+    // Private thread snapshots are sealed with the thread key we were given
+    let blob_data = PrivateThreadService::new(state.database.clone(), state.config.paths.clone())
+        .open_snapshot(&thread_id, blob_data)
+        .map_err(ApiError::Internal)?;
+
     // Deserialize as ThreadDetails
     let thread_details: ThreadDetails = serde_json::from_slice(&blob_data)
         .map_err(|e| ApiError::Internal(anyhow::anyhow!("invalid thread data: {}", e)))?;
@@ -249,6 +285,7 @@ pub(crate) async fn download_thread(
 
 pub(crate) async fn create_thread(
     State(state): State<AppState>,
+    token: Option<Extension<ApiTokenView>>,
     mut multipart: Multipart,
 ) -> Result<Json<ThreadDetails>, ApiError> {
     let mut input: Option<CreateThreadInput> = None;
@@ -283,16 +320,17 @@ pub(crate) async fn create_thread(
     }
 
     let mut input = input.ok_or(ApiError::BadRequest("missing json field".into()))?;
+    let agent = input.metadata.as_ref().and_then(|meta| meta.agent.as_ref());
+    auth::authorize_agent(&state.config.api, token.as_deref(), agent.map(|agent| agent.name.as_str()))?;
 
     // If no creator specified, use the local peer (GPG fingerprint is the peer ID)
     if input.creator_peer_id.is_none() {
         input.creator_peer_id = Some(state.identity.gpg_fingerprint.clone());
     }
 
-    let thread_service = ThreadService::new(state.database.clone());
-    let details = thread_service
-        .create_thread(input)
-        .map_err(ApiError::Internal)?;
+    let thread_service =
+        ThreadService::with_file_paths(state.database.clone(), state.config.paths.clone());
+    let details = thread_service.create_thread(input)?;
 
     // Attach files to the first post
     if !files.is_empty() {
@@ -356,18 +394,30 @@ pub(crate) async fn create_thread(
         }
     }
 
-    // Broadcast thread announcement
-    state.network.publish_thread_announcement(details.clone(), &state.identity.gpg_fingerprint).await.ok();
+    // Broadcast thread announcement; minting a proof-of-work stamp can take a while,
+    // so it happens after we've answered
+    let network = state.network.clone();
+    let announced = details.clone();
+    let local_peer_id = state.identity.gpg_fingerprint.clone();
+    tokio::spawn(async move {
+        if let Err(err) = network.publish_thread_announcement(announced, &local_peer_id).await {
+            tracing::warn!(error = ?err, "failed to announce new thread");
+        }
+    });
 
     Ok(Json(details))
 }
 
 pub(crate) async fn create_post(
     State(state): State<AppState>,
+    token: Option<Extension<ApiTokenView>>,
     Path(thread_id): Path<String>,
     Json(mut payload): Json<CreatePostInput>,
 ) -> Result<(StatusCode, Json<PostResponse>), ApiError> {
-    let service = ThreadService::new(state.database.clone());
+    let agent = payload.metadata.as_ref().and_then(|meta| meta.agent.as_ref());
+    auth::authorize_agent(&state.config.api, token.as_deref(), agent.map(|agent| agent.name.as_str()))?;
+    // File paths give the service access to the node key so the post gets signed
+    let service = ThreadService::with_file_paths(state.database.clone(), state.config.paths.clone());
     payload.thread_id = thread_id.clone();
 
     // If no author specified, use the local peer (GPG fingerprint is the peer ID)
@@ -375,62 +425,205 @@ pub(crate) async fn create_post(
         payload.author_peer_id = Some(state.identity.gpg_fingerprint.clone());
     }
 
-    match service.create_post(payload) {
-        Ok(mut post) => {
-            // Calculate thread hash for synchronization
-            // Get all posts in thread to calculate the hash
-            let service_with_paths = ThreadService::with_file_paths(
-                state.database.clone(),
-                state.config.paths.clone()
-            );
-            if let Ok(Some(thread_details)) = service_with_paths.get_thread(&thread_id) {
-                let thread_hash = crate::threading::calculate_thread_hash(&thread_details.posts);
-                post.thread_hash = Some(thread_hash);
-            }
+    let mut post = service.create_post(payload)?;
 
-            // Broadcast the post update with thread hash for synchronization
-            if let Err(err) = state.network.publish_post_update(post.clone()).await {
-                tracing::warn!(
-                    error = ?err,
-                    thread_id = %post.thread_id,
-                    post_id = %post.id,
-                    "failed to publish post update over network"
-                );
-            }
+    // Calculate thread hash for synchronization
+    // Get all posts in thread to calculate the hash
+    let service_with_paths = ThreadService::with_file_paths(
+        state.database.clone(),
+        state.config.paths.clone()
+    );
+    if let Ok(Some(thread_details)) = service_with_paths.get_thread(&thread_id) {
+        post.thread_hash = Some(crate::threading::calculate_legacy_thread_hash(&thread_details.posts));
+        post.canonical_thread_hash = Some(crate::threading::calculate_thread_hash(&thread_details.posts));
+    }
 
-            // Re-announce the thread so peers can discover it (with updated post_count and hash)
-            // This allows transitive discovery: if peer B adds to a thread, peer C who isn't subscribed
-            // yet can discover the thread exists
-            let service_with_paths = ThreadService::with_file_paths(
-                state.database.clone(),
-                state.config.paths.clone()
+    // Publishing mints a proof-of-work stamp on topics that demand one,
+    // so it runs in the background instead of holding up the response
+    let task_state = state.clone();
+    let published = post.clone();
+    let task_thread_id = thread_id.clone();
+    tokio::spawn(async move {
+        publish_new_post(task_state, task_thread_id, published).await;
+    });
+
+    // Clear thread_hash before returning to client (they don't need it)
+    post.thread_hash = None;
+
+    Ok((StatusCode::CREATED, Json(PostResponse { post })))
+}
+
+/// Gossips a freshly created post, then re-announces its thread.
+async fn publish_new_post(state: AppState, thread_id: String, post: PostView) {
+    // Broadcast the post update with thread hash for synchronization
+    if let Err(err) = state.network.publish_post_update(post.clone()).await {
+        tracing::warn!(
+            error = ?err,
+            thread_id = %post.thread_id,
+            post_id = %post.id,
+            "failed to publish post update over network"
+        );
+    }
+
+    // Re-announce the thread so peers can discover it (with updated post_count and hash)
+    // This allows transitive discovery: if peer B adds to a thread, peer C who isn't subscribed
+    // yet can discover the thread exists
+    let service_with_paths = ThreadService::with_file_paths(
+        state.database.clone(),
+        state.config.paths.clone()
+    );
+    if let Ok(Some(thread_details)) = service_with_paths.get_thread(&thread_id) {
+        // Re-announce thread with updated metadata
+        if let Err(err) = state.network.publish_thread_announcement(
+            thread_details,
+            &state.identity.gpg_fingerprint
+        ).await {
+            tracing::warn!(
+                error = ?err,
+                thread_id = %thread_id,
+                "failed to re-announce thread after new post"
             );
-            if let Ok(Some(thread_details)) = service_with_paths.get_thread(&thread_id) {
-                // Re-announce thread with updated metadata
-                if let Err(err) = state.network.publish_thread_announcement(
-                    thread_details,
-                    &state.identity.gpg_fingerprint
-                ).await {
-                    tracing::warn!(
-                        error = ?err,
-                        thread_id = %thread_id,
-                        "failed to re-announce thread after new post"
-                    );
-                }
-            }
+        }
+    }
+}
+
+pub(crate) async fn edit_post(
+    State(state): State<AppState>,
+    token: Option<Extension<ApiTokenView>>,
+    Path(post_id): Path<String>,
+    Json(payload): Json<EditPostRequest>,
+) -> ApiResult<PostResponse> {
+    let service = ThreadService::with_file_paths(state.database.clone(), state.config.paths.clone());
+    let agent = post_agent(&service, &post_id)?;
+    auth::authorize_agent(&state.config.api, token.as_deref(), agent.as_deref())?;
+    let post = service.edit_post(&post_id, payload.body)?;
+    publish_post_edit(&state, &post).await;
+    Ok(Json(PostResponse { post }))
+}
 
-            // Clear thread_hash before returning to client (they don't need it)
-            post.thread_hash = None;
+pub(crate) async fn delete_post(
+    State(state): State<AppState>,
+    token: Option<Extension<ApiTokenView>>,
+    Path(post_id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let service = ThreadService::with_file_paths(state.database.clone(), state.config.paths.clone());
+    let agent = post_agent(&service, &post_id)?;
+    auth::authorize_agent(&state.config.api, token.as_deref(), agent.as_deref())?;
+    let (post, file_paths) = service.delete_post(&post_id)?;
 
-            Ok((StatusCode::CREATED, Json(PostResponse { post })))
+    for path in file_paths {
+        let absolute = state.config.paths.base.join(&path);
+        if absolute.exists() {
+            if let Err(err) = std::fs::remove_file(&absolute) {
+                tracing::warn!(error = ?err, path = %absolute.display(), "failed to delete attachment of deleted post");
+            }
         }
-        Err(err) if err.to_string().contains("thread not found") => {
-            Err(ApiError::NotFound(format!("thread {thread_id} not found")))
+    }
+
+    publish_post_edit(&state, &post).await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub(crate) async fn get_post_history(
+    State(state): State<AppState>,
+    Path(post_id): Path<String>,
+) -> ApiResult<PostHistory> {
+    match visible_history(&state.database, &post_id)? {
+        Some(history) => Ok(Json(history)),
+        None => Err(ApiError::NotFound(format!("post {post_id} not found"))),
+    }
+}
+
+/// A post's history as `get_post` would show it: none for a rejected post.
+fn visible_history(database: &Database, post_id: &str) -> Result<Option<PostHistory>> {
+    let Some(mut history) = ThreadService::new(database.clone()).post_history(post_id)? else {
+        return Ok(None);
+    };
+    let mut posts = vec![history.post];
+    TrustService::new(database.clone()).apply_to_posts(&mut posts)?;
+    Ok(posts.pop().map(|post| {
+        history.post = post;
+        history
+    }))
+}
+
+pub(crate) async fn get_post(
+    State(state): State<AppState>,
+    Path(post_id): Path<String>,
+) -> ApiResult<PostResponse> {
+    let service = ThreadService::with_file_paths(state.database.clone(), state.config.paths.clone());
+    let mut posts: Vec<PostView> = service.get_post(&post_id)?.into_iter().collect();
+    TrustService::new(state.database.clone()).apply_to_posts(&mut posts)?;
+    match posts.pop() {
+        Some(post) => Ok(Json(PostResponse { post })),
+        None => Err(ApiError::NotFound(format!("post {post_id} not found"))),
+    }
+}
+
+pub(crate) async fn get_post_ancestors(
+    State(state): State<AppState>,
+    Path(post_id): Path<String>,
+    Query(params): Query<TraversalParams>,
+) -> ApiResult<PostGraph> {
+    let service = ThreadService::with_file_paths(state.database.clone(), state.config.paths.clone());
+    match service.post_ancestors(&post_id, params.depth)? {
+        Some(mut graph) => {
+            TrustService::new(state.database.clone()).apply_to_posts(&mut graph.posts)?;
+            Ok(Json(graph))
         }
-        Err(err) if err.to_string().contains("may not be empty") => {
-            Err(ApiError::BadRequest(err.to_string()))
+        None => Err(ApiError::NotFound(format!("post {post_id} not found"))),
+    }
+}
+
+pub(crate) async fn get_post_descendants(
+    State(state): State<AppState>,
+    Path(post_id): Path<String>,
+    Query(params): Query<TraversalParams>,
+) -> ApiResult<PostGraph> {
+    let service = ThreadService::with_file_paths(state.database.clone(), state.config.paths.clone());
+    match service.post_descendants(&post_id, params.depth)? {
+        Some(mut graph) => {
+            TrustService::new(state.database.clone()).apply_to_posts(&mut graph.posts)?;
+            Ok(Json(graph))
         }
-        Err(err) => Err(ApiError::Internal(err)),
+        None => Err(ApiError::NotFound(format!("post {post_id} not found"))),
+    }
+}
+
+pub(crate) async fn get_post_path(
+    State(state): State<AppState>,
+    Path((from, to)): Path<(String, String)>,
+) -> ApiResult<PostPath> {
+    let service = ThreadService::with_file_paths(state.database.clone(), state.config.paths.clone());
+    let Some(mut path) = service.post_path(&from, &to)? else {
+        return Err(ApiError::NotFound(format!("posts {from} and {to} share no ancestor")));
+    };
+    let length = path.posts.len();
+    TrustService::new(state.database.clone()).apply_to_posts(&mut path.posts)?;
+    if path.posts.len() < length {
+        // With a rejected post cut out the chain no longer connects the two
+        return Err(ApiError::NotFound(format!(
+            "the path from {from} to {to} runs through a rejected post"
+        )));
+    }
+    Ok(Json(path))
+}
+
+/// The agent a stored post was made as; editing or deleting it needs that agent's token.
+fn post_agent(service: &ThreadService, post_id: &str) -> Result<Option<String>> {
+    Ok(service
+        .get_post(post_id)?
+        .and_then(|post| post.metadata?.agent)
+        .map(|agent| agent.name))
+}
+
+async fn publish_post_edit(state: &AppState, post: &PostView) {
+    let result = match PostEditEvent::from_post(post) {
+        Ok(edit) => state.network.publish_post_edit(edit).await,
+        Err(err) => Err(err),
+    };
+    if let Err(err) = result {
+        tracing::warn!(error = ?err, post_id = %post.id, "failed to publish post edit over network");
     }
 }
 
@@ -456,9 +649,14 @@ pub(crate) async fn list_recent_posts(
         repos.posts().list_recent(limit)
     }).map_err(ApiError::Internal)?;
 
+    let trust = TrustService::new(state.database.clone());
     let mut recent_posts = Vec::new();
 
     for post_record in post_records {
+        // Posts waiting for review stay out of the feed
+        if trust.is_quarantined(&post_record.id).map_err(ApiError::Internal)? {
+            continue;
+        }
         // Get thread title
         let thread_title = state.database.with_repositories(|repos| {
             repos.threads().get(&post_record.thread_id)
@@ -490,10 +688,17 @@ pub(crate) async fn list_recent_posts(
             body: post_record.body.clone(),
             created_at: post_record.created_at.clone(),
             updated_at: post_record.updated_at.clone(),
+            deleted: false,
+            quarantined: false,
+            pow: None,
             parent_post_ids,
             files: file_views,
             thread_hash: None,
+            canonical_thread_hash: None,
             metadata,
+            signature: post_record.signature.clone(),
+            signature_status: post_record.signature_status.clone(),
+            author_public_key: None,
         };
 
         recent_posts.push(RecentPostView {
@@ -556,6 +761,55 @@ pub(crate) async fn set_thread_ignored(
     Ok(StatusCode::OK)
 }
 
+pub(crate) async fn list_thread_members(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> ApiResult<Vec<ThreadMemberView>> {
+    let service = PrivateThreadService::new(state.database.clone(), state.config.paths.clone());
+    Ok(Json(service.list_members(&id)?))
+}
+
+pub(crate) async fn invite_thread_member(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(payload): Json<InviteMemberRequest>,
+) -> Result<(StatusCode, Json<ThreadMemberView>), ApiError> {
+    let service = PrivateThreadService::new(state.database.clone(), state.config.paths.clone());
+    let member = service.invite_member(&id, &payload.peer_id)?;
+
+    // Re-announce so the new member receives their wrapped key
+    reannounce_private_thread(&state, &id).await;
+
+    Ok((StatusCode::CREATED, Json(member)))
+}
+
+pub(crate) async fn remove_thread_member(
+    State(state): State<AppState>,
+    Path((id, peer_id)): Path<(String, String)>,
+) -> Result<StatusCode, ApiError> {
+    let service = PrivateThreadService::new(state.database.clone(), state.config.paths.clone());
+    let key_epoch = service.remove_member(&id, &peer_id)?;
+    tracing::info!(thread_id = %id, peer_id = %peer_id, key_epoch, "removed member and rotated private thread key");
+
+    // Re-announce sealed with the rotated key
+    reannounce_private_thread(&state, &id).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn reannounce_private_thread(state: &AppState, thread_id: &str) {
+    let service = ThreadService::with_file_paths(state.database.clone(), state.config.paths.clone());
+    if let Ok(Some(details)) = service.get_thread(thread_id) {
+        if let Err(err) = state
+            .network
+            .publish_thread_announcement(details, &state.identity.gpg_fingerprint)
+            .await
+        {
+            tracing::warn!(error = ?err, thread_id = %thread_id, "failed to re-announce private thread");
+        }
+    }
+}
+
 pub(crate) async fn import_thread_handler(
     State(state): State<AppState>,
     Json(request): Json<ImportRequest>,
@@ -589,3 +843,82 @@ pub(crate) async fn refresh_thread_handler(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::AgentService;
+    use crate::api_tokens::ApiScope;
+    use crate::config::{ApiConfig, GraphchanPaths};
+    use crate::threading::{AgentInfo, PostMetadata};
+    use rusqlite::Connection;
+
+    #[test]
+    fn rejected_posts_have_no_history() {
+        let database = Database::from_connection(Connection::open_in_memory().expect("db"), true);
+        database.ensure_migrations().expect("migrations");
+        let service = ThreadService::new(database.clone());
+        let thread = service
+            .create_thread(CreateThreadInput { title: "History".into(), ..Default::default() })
+            .expect("thread");
+        let post = service
+            .create_post(CreatePostInput { thread_id: thread.thread.id.clone(), body: "v1".into(), ..Default::default() })
+            .expect("post");
+        let trust = TrustService::new(database.clone());
+        trust.quarantine_post(&post.id, &thread.thread.id, None).expect("quarantine");
+
+        let pending = visible_history(&database, &post.id).expect("history").expect("pending is shown");
+        assert!(pending.post.quarantined);
+        trust.reject(&post.id).expect("reject");
+        assert!(visible_history(&database, &post.id).expect("history").is_none());
+    }
+
+    #[test]
+    fn another_agents_token_may_not_edit_its_posts() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let paths = GraphchanPaths::from_base_dir(dir.path()).expect("paths");
+        std::fs::create_dir_all(&paths.gpg_dir).expect("gpg dir");
+        std::fs::create_dir_all(&paths.keys_dir).expect("keys dir");
+        let identity = crate::identity::ensure_local_identity(&paths).expect("identity");
+        let database = Database::from_connection(Connection::open_in_memory().expect("db"), true);
+        database.ensure_migrations().expect("migrations");
+        database
+            .save_identity(&identity.gpg_fingerprint, &identity.iroh_peer_id, &identity.friendcode)
+            .expect("save identity");
+        AgentService::new(database.clone(), paths.clone()).create("scout").expect("create agent");
+        let service = ThreadService::with_file_paths(database, paths);
+        let thread = service
+            .create_thread(CreateThreadInput { title: "Agents".into(), ..Default::default() })
+            .expect("thread");
+        let post = service
+            .create_post(CreatePostInput {
+                thread_id: thread.thread.id,
+                author_peer_id: Some(identity.gpg_fingerprint),
+                body: "beep".into(),
+                metadata: Some(PostMetadata {
+                    agent: Some(AgentInfo { name: "scout".into(), version: None, delegation: None, signature: None }),
+                    client: None,
+                }),
+                ..Default::default()
+            })
+            .expect("agent post");
+        let token = |name: &str| ApiTokenView {
+            id: format!("id-{name}"),
+            name: name.into(),
+            scopes: vec![ApiScope::Post],
+            created_at: "2026-01-01T00:00:00Z".into(),
+        };
+
+        let agent = post_agent(&service, &post.id).expect("lookup");
+        assert_eq!(agent.as_deref(), Some("scout"));
+        let config = ApiConfig::default();
+        assert!(matches!(
+            auth::authorize_agent(&config, Some(&token("other-bot")), agent.as_deref()),
+            Err(ApiError::Forbidden(_))
+        ));
+        assert!(auth::authorize_agent(&config, Some(&token("scout")), agent.as_deref()).is_ok());
+        let op = &thread.posts[0].id;
+        assert_eq!(post_agent(&service, op).expect("lookup"), None);
+        assert_eq!(post_agent(&service, "missing").expect("lookup"), None);
+    }
+}
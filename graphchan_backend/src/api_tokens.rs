@@ -0,0 +1,379 @@
+use crate::config::GraphchanPaths;
+use crate::errors::invalid;
+use crate::utils::now_utc_iso;
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::fs;
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
+use uuid::Uuid;
+
+/// Name of the admin token provisioned for the local UI.
+pub const OWNER_TOKEN_NAME: &str = "owner";
+const TOKENS_FILE: &str = "api_tokens.json";
+/// Plaintext owner token, readable only by the user running the node.
+const OWNER_TOKEN_FILE: &str = "api_token";
+const TOKEN_PREFIX: &str = "gct_";
+
+/// What a token may do over the HTTP API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiScope {
+    /// Read threads, posts, files, peers, topics and events
+    Read,
+    /// Create threads and posts, edit, react, upload and fetch files
+    Post,
+    /// Read and send direct messages
+    Dm,
+    /// Everything, including blocking, trust, topics, settings and tokens
+    Admin,
+}
+
+impl ApiScope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ApiScope::Read => "read",
+            ApiScope::Post => "post",
+            ApiScope::Dm => "dm",
+            ApiScope::Admin => "admin",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "read" => Ok(ApiScope::Read),
+            "post" => Ok(ApiScope::Post),
+            "dm" => Ok(ApiScope::Dm),
+            "admin" => Ok(ApiScope::Admin),
+            other => Err(invalid(format!("unknown scope: {other}"))),
+        }
+    }
+
+    /// Whether holding `self` allows a request that needs `required`.
+    /// `post` and `dm` include `read`; `admin` includes everything.
+    pub fn grants(self, required: ApiScope) -> bool {
+        match self {
+            ApiScope::Admin => true,
+            ApiScope::Post | ApiScope::Dm => required == self || required == ApiScope::Read,
+            ApiScope::Read => required == ApiScope::Read,
+        }
+    }
+}
+
+/// Scope a request needs, from its method and matched route (`/posts/:id`, not `/posts/abc`).
+/// Unlisted writes need `admin`, so new routes fail closed.
+pub fn scope_for_route(method: &str, route: &str) -> ApiScope {
+    let under = |prefix: &str| route == prefix || route.starts_with(&format!("{prefix}/"));
+    if under("/auth") || under("/blocking") || under("/settings") || route == "/peers/:peer_id/ip" {
+        return ApiScope::Admin;
+    }
+    if under("/dms") {
+        return ApiScope::Dm;
+    }
+    if matches!(method, "GET" | "HEAD") {
+        return ApiScope::Read;
+    }
+    match route {
+        "/threads"
+        | "/threads/:id/posts"
+        | "/threads/:id/download"
+        | "/threads/:id/refresh"
+        | "/posts/:id"
+        | "/posts/:id/files"
+        | "/posts/:id/react"
+        | "/posts/:id/unreact"
+        | "/files/:id/download"
+        | "/import" => ApiScope::Post,
+        _ => ApiScope::Admin,
+    }
+}
+
+/// A stored token; only the hash of the secret is kept.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ApiTokenRecord {
+    id: String,
+    name: String,
+    scopes: Vec<ApiScope>,
+    token_hash: String,
+    created_at: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ApiTokenView {
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<ApiScope>,
+    pub created_at: String,
+}
+
+impl ApiTokenView {
+    pub fn grants(&self, required: ApiScope) -> bool {
+        self.scopes.iter().any(|scope| scope.grants(required))
+    }
+}
+
+impl From<&ApiTokenRecord> for ApiTokenView {
+    fn from(record: &ApiTokenRecord) -> Self {
+        Self {
+            id: record.id.clone(),
+            name: record.name.clone(),
+            scopes: record.scopes.clone(),
+            created_at: record.created_at.clone(),
+        }
+    }
+}
+
+struct LoadedTokens {
+    tokens: Vec<ApiTokenRecord>,
+    /// mtime and size of the file when read, to notice edits by `graphchan_backend token`
+    stamp: Option<(SystemTime, u64)>,
+}
+
+/// Bearer tokens for the HTTP API, kept in `data/api_tokens.json`.
+///
+/// Secrets are shown once when created and stored only as blake3 hashes.
+/// The owner token is the exception: its plaintext sits in `data/api_token`
+/// (mode 0600) so the bundled UI and local tools can pick it up.
+#[derive(Clone)]
+pub struct ApiTokenStore {
+    tokens_path: PathBuf,
+    owner_path: PathBuf,
+    loaded: Arc<RwLock<LoadedTokens>>,
+}
+
+impl ApiTokenStore {
+    pub fn open(paths: &GraphchanPaths) -> Result<Self> {
+        Self::open_in(&paths.data_dir)
+    }
+
+    fn open_in(data_dir: &Path) -> Result<Self> {
+        let tokens_path = data_dir.join(TOKENS_FILE);
+        let loaded = read_tokens(&tokens_path)?;
+        Ok(Self {
+            tokens_path,
+            owner_path: data_dir.join(OWNER_TOKEN_FILE),
+            loaded: Arc::new(RwLock::new(loaded)),
+        })
+    }
+
+    /// Rereads the file if another process (the `token` subcommand) changed it.
+    fn refresh(&self) {
+        let stamp = file_stamp(&self.tokens_path);
+        if self.loaded.read().expect("api token lock poisoned").stamp == stamp {
+            return;
+        }
+        match read_tokens(&self.tokens_path) {
+            Ok(fresh) => *self.loaded.write().expect("api token lock poisoned") = fresh,
+            Err(err) => tracing::warn!(error = ?err, "failed to reload API tokens"),
+        }
+    }
+
+    /// Creates a token and returns it with its secret, which is not stored.
+    pub fn create(&self, name: &str, scopes: &[ApiScope]) -> Result<(ApiTokenView, String)> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(invalid("token name is required"));
+        }
+        if scopes.is_empty() {
+            return Err(invalid("at least one scope is required"));
+        }
+        self.refresh();
+        let mut loaded = self.loaded.write().expect("api token lock poisoned");
+        if loaded.tokens.iter().any(|token| token.name == name) {
+            return Err(invalid(format!("token name already exists: {name}")));
+        }
+        let secret = generate_secret();
+        let mut unique = Vec::new();
+        for scope in scopes {
+            if !unique.contains(scope) {
+                unique.push(*scope);
+            }
+        }
+        let record = ApiTokenRecord {
+            id: Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            scopes: unique,
+            token_hash: hash_secret(&secret),
+            created_at: now_utc_iso(),
+        };
+        let view = ApiTokenView::from(&record);
+        loaded.tokens.push(record);
+        self.save(&mut loaded)?;
+        Ok((view, secret))
+    }
+
+    pub fn list(&self) -> Vec<ApiTokenView> {
+        self.refresh();
+        let loaded = self.loaded.read().expect("api token lock poisoned");
+        loaded.tokens.iter().map(ApiTokenView::from).collect()
+    }
+
+    /// Revokes by ID or name; returns whether a token was removed.
+    pub fn revoke(&self, id_or_name: &str) -> Result<bool> {
+        self.refresh();
+        let mut loaded = self.loaded.write().expect("api token lock poisoned");
+        let before = loaded.tokens.len();
+        loaded
+            .tokens
+            .retain(|token| token.id != id_or_name && token.name != id_or_name);
+        if loaded.tokens.len() == before {
+            return Ok(false);
+        }
+        self.save(&mut loaded)?;
+        Ok(true)
+    }
+
+    /// The token a bearer secret belongs to, if it is still valid.
+    pub fn authenticate(&self, secret: &str) -> Option<ApiTokenView> {
+        if !secret.starts_with(TOKEN_PREFIX) {
+            return None;
+        }
+        self.refresh();
+        let hash = hash_secret(secret);
+        let loaded = self.loaded.read().expect("api token lock poisoned");
+        loaded
+            .tokens
+            .iter()
+            .find(|token| token.token_hash == hash)
+            .map(ApiTokenView::from)
+    }
+
+    /// Returns the owner token from `data/api_token`, minting a new admin token
+    /// (and replacing any stale owner entry) if the file is missing or revoked.
+    pub fn ensure_owner_token(&self) -> Result<String> {
+        if let Ok(existing) = fs::read_to_string(&self.owner_path) {
+            let existing = existing.trim();
+            if self.authenticate(existing).is_some() {
+                return Ok(existing.to_string());
+            }
+        }
+        self.revoke(OWNER_TOKEN_NAME)?;
+        let (_, secret) = self.create(OWNER_TOKEN_NAME, &[ApiScope::Admin])?;
+        fs::write(&self.owner_path, &secret)
+            .with_context(|| format!("failed to write {}", self.owner_path.display()))?;
+        tighten_permissions(&self.owner_path);
+        tracing::info!(path = %self.owner_path.display(), "provisioned owner API token");
+        Ok(secret)
+    }
+
+    fn save(&self, loaded: &mut LoadedTokens) -> Result<()> {
+        let tmp = self.tokens_path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(&loaded.tokens)?)
+            .with_context(|| format!("failed to write {}", tmp.display()))?;
+        tighten_permissions(&tmp);
+        fs::rename(&tmp, &self.tokens_path)
+            .with_context(|| format!("failed to replace {}", self.tokens_path.display()))?;
+        loaded.stamp = file_stamp(&self.tokens_path);
+        Ok(())
+    }
+}
+
+fn read_tokens(path: &Path) -> Result<LoadedTokens> {
+    let stamp = file_stamp(path);
+    let tokens = if path.exists() {
+        let raw = fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+        serde_json::from_str(&raw).with_context(|| format!("failed to parse {}", path.display()))?
+    } else {
+        Vec::new()
+    };
+    Ok(LoadedTokens { tokens, stamp })
+}
+
+fn file_stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let meta = fs::metadata(path).ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+fn generate_secret() -> String {
+    let mut bytes = [0u8; 32];
+    rand::rng().fill_bytes(&mut bytes);
+    format!("{TOKEN_PREFIX}{}", URL_SAFE_NO_PAD.encode(bytes))
+}
+
+fn hash_secret(secret: &str) -> String {
+    blake3::hash(secret.as_bytes()).to_hex().to_string()
+}
+
+#[cfg(unix)]
+fn tighten_permissions(path: &Path) {
+    if let Err(err) = fs::set_permissions(path, fs::Permissions::from_mode(0o600)) {
+        tracing::warn!(path = %path.display(), error = ?err, "failed to tighten permissions");
+    }
+}
+
+#[cfg(not(unix))]
+fn tighten_permissions(_path: &Path) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::RequestError;
+    use tempfile::tempdir;
+
+    #[test]
+    fn tokens_authenticate_until_revoked_and_survive_reload() {
+        let dir = tempdir().unwrap();
+        let store = ApiTokenStore::open_in(dir.path()).unwrap();
+        let (bot, secret) = store.create("bot", &[ApiScope::Post]).unwrap();
+        // Bad requests are typed so the API answers 400
+        let is_invalid = |err: anyhow::Error| matches!(err.downcast_ref::<RequestError>(), Some(RequestError::Invalid(_)));
+        assert!(is_invalid(store.create("bot", &[ApiScope::Read]).unwrap_err()));
+        assert!(is_invalid(store.create("empty", &[]).unwrap_err()));
+        assert!(is_invalid(ApiScope::parse("root").unwrap_err()));
+
+        let authed = store.authenticate(&secret).expect("valid token");
+        assert_eq!(authed, bot);
+        assert!(authed.grants(ApiScope::Read));
+        assert!(authed.grants(ApiScope::Post));
+        assert!(!authed.grants(ApiScope::Dm));
+        assert!(store.authenticate("gct_nope").is_none());
+
+        // Only the hash is written to disk
+        let raw = fs::read_to_string(dir.path().join(TOKENS_FILE)).unwrap();
+        assert!(!raw.contains(&secret));
+        let reloaded = ApiTokenStore::open_in(dir.path()).unwrap();
+        assert_eq!(reloaded.authenticate(&secret), Some(bot.clone()));
+
+        assert!(reloaded.revoke(&bot.id).unwrap());
+        assert!(reloaded.authenticate(&secret).is_none());
+        assert!(!reloaded.revoke("bot").unwrap());
+        // The first store notices the revocation made through the second
+        assert!(store.authenticate(&secret).is_none());
+    }
+
+    #[test]
+    fn owner_token_is_reused_and_replaced_when_revoked() {
+        let dir = tempdir().unwrap();
+        let store = ApiTokenStore::open_in(dir.path()).unwrap();
+        let owner = store.ensure_owner_token().unwrap();
+        assert_eq!(store.ensure_owner_token().unwrap(), owner);
+        assert!(store.authenticate(&owner).unwrap().grants(ApiScope::Admin));
+
+        store.revoke(OWNER_TOKEN_NAME).unwrap();
+        let replaced = ApiTokenStore::open_in(dir.path()).unwrap().ensure_owner_token().unwrap();
+        assert_ne!(replaced, owner);
+        assert_eq!(fs::read_to_string(dir.path().join(OWNER_TOKEN_FILE)).unwrap(), replaced);
+    }
+
+    #[test]
+    fn routes_map_to_scopes() {
+        assert_eq!(scope_for_route("GET", "/threads/:id"), ApiScope::Read);
+        assert_eq!(scope_for_route("GET", "/events"), ApiScope::Read);
+        assert_eq!(scope_for_route("POST", "/threads/:id/posts"), ApiScope::Post);
+        assert_eq!(scope_for_route("DELETE", "/posts/:id"), ApiScope::Post);
+        assert_eq!(scope_for_route("GET", "/dms/conversations"), ApiScope::Dm);
+        assert_eq!(scope_for_route("POST", "/dms/send"), ApiScope::Dm);
+        assert_eq!(scope_for_route("GET", "/blocking/peers"), ApiScope::Admin);
+        assert_eq!(scope_for_route("GET", "/auth/tokens"), ApiScope::Admin);
+        assert_eq!(scope_for_route("PUT", "/peers/:id/trust"), ApiScope::Admin);
+        assert_eq!(scope_for_route("POST", "/threads/:id/delete"), ApiScope::Admin);
+        // Writes nobody classified need admin
+        assert_eq!(scope_for_route("POST", "/something/new"), ApiScope::Admin);
+        assert_eq!(scope_for_route("GET", "/dmsx"), ApiScope::Read);
+    }
+}
@@ -6,7 +6,9 @@ use crate::database::repositories::{
     BlockedPeerRepository, BlocklistRepository, PeerRepository, RedactedPostRepository,
     IpBlockRepository, PeerIpRepository,
 };
+use crate::config::GraphchanPaths;
 use crate::database::Database;
+use crate::network::BlockActionEvent;
 use crate::utils::now_utc_iso;
 use anyhow::{anyhow, Context, Result};
 use ipnetwork::IpNetwork;
@@ -16,6 +18,13 @@ use std::net::IpAddr;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+/// Settings key holding the last sequence number used for our own block actions.
+const BLOCK_ACTION_SEQUENCE_KEY: &str = "block_action_sequence";
+/// Settings key that, when "true", makes us a blocklist maintainer: our blocks and unblocks are broadcast.
+pub const PUBLISH_BLOCK_ACTIONS_KEY: &str = "publish_block_actions";
+/// How often batched IP block hit counts are written to the database.
+pub const HIT_FLUSH_INTERVAL_SECS: u64 = 30;
+
 #[derive(Clone)]
 pub struct BlockChecker {
     database: Database,
@@ -102,6 +111,15 @@ impl BlockChecker {
                 .get(maintainer_peer_id)?
                 .ok_or_else(|| anyhow!("maintainer peer not found: {}", maintainer_peer_id))?;
 
+            // Keep the replay watermark when re-subscribing to the same list
+            let last_sequence = repos
+                .blocklists()
+                .list_subscriptions()?
+                .into_iter()
+                .find(|existing| existing.id == blocklist_id)
+                .map(|existing| existing.last_sequence)
+                .unwrap_or(0);
+
             let record = BlocklistSubscriptionRecord {
                 id: blocklist_id.to_string(),
                 maintainer_peer_id: maintainer_peer_id.to_string(),
@@ -109,6 +127,7 @@ impl BlockChecker {
                 description,
                 auto_apply,
                 last_synced_at: None,
+                last_sequence,
             };
 
             repos.blocklists().subscribe(&record)
@@ -139,6 +158,7 @@ impl BlockChecker {
                     description: record.description,
                     auto_apply: record.auto_apply,
                     last_synced_at: record.last_synced_at,
+                    last_sequence: record.last_sequence,
                     entry_count,
                 });
             }
@@ -172,6 +192,42 @@ impl BlockChecker {
             .with_repositories(|repos| repos.blocklists().remove_entry(blocklist_id, peer_id))
     }
 
+    /// Whether the user publishes their blocks as a blocklist (off by default: who you block is private).
+    pub fn publishes_block_actions(&self) -> Result<bool> {
+        Ok(self
+            .database
+            .get_setting(PUBLISH_BLOCK_ACTIONS_KEY)?
+            .is_some_and(|value| value == "true"))
+    }
+
+    /// Build a signed, sequenced block action for broadcasting to blocklist subscribers.
+    ///
+    /// The sequence counter is persisted so it keeps increasing across restarts.
+    pub fn signed_block_action(
+        &self,
+        paths: &GraphchanPaths,
+        blocker_peer_id: &str,
+        blocked_peer_id: &str,
+        reason: Option<String>,
+        is_unblock: bool,
+    ) -> Result<BlockActionEvent> {
+        let sequence = self.database.increment_setting(BLOCK_ACTION_SEQUENCE_KEY)?;
+
+        let mut action = BlockActionEvent {
+            blocker_peer_id: blocker_peer_id.to_string(),
+            blocked_peer_id: blocked_peer_id.to_string(),
+            reason,
+            is_unblock,
+            sequence,
+            created_at: now_utc_iso(),
+            signature: None,
+            blocker_public_key: None,
+        };
+        action.signature = Some(crate::crypto::sign_detached(paths, &action.signing_payload())?);
+        action.blocker_public_key = Some(crate::crypto::load_public_key_armored(paths)?);
+        Ok(action)
+    }
+
     /// Get entries for a specific blocklist.
     pub fn list_blocklist_entries(&self, blocklist_id: &str) -> Result<Vec<BlocklistEntryView>> {
         self.database.with_repositories(|repos| {
@@ -308,6 +364,7 @@ pub struct BlocklistSubscriptionView {
     pub description: Option<String>,
     pub auto_apply: bool,
     pub last_synced_at: Option<String>,
+    pub last_sequence: i64,
     pub entry_count: usize,
 }
 
@@ -339,10 +396,13 @@ pub struct RedactedPostView {
 /// - Exact IP blocks: O(1) lookup via HashSet
 /// - CIDR range blocks: O(n) where n = number of CIDR rules (typically small)
 /// - Cache refresh: Only when blocks are added/removed
+/// - Hit counts: Aggregated in memory, written every `HIT_FLUSH_INTERVAL_SECS`
 #[derive(Clone)]
 pub struct IpBlockChecker {
     database: Database,
     cache: Arc<RwLock<IpBlockCache>>,
+    /// Hits per block ID not yet written to `ip_blocks.hit_count`
+    pending_hits: Arc<std::sync::Mutex<HashMap<i64, i64>>>,
 }
 
 struct IpBlockCache {
@@ -370,6 +430,7 @@ impl IpBlockChecker {
                 range_blocks: Vec::new(),
                 block_metadata: HashMap::new(),
             })),
+            pending_hits: Arc::new(std::sync::Mutex::new(HashMap::new())),
         }
     }
 
@@ -465,15 +526,17 @@ impl IpBlockChecker {
 
     /// Check if a peer is blocked based on their known IP addresses
     ///
+    /// Every known address counts, whether advertised in a friendcode or
+    /// observed on a connection (most recent first).
     /// Returns (is_blocked, block_id, ip) where ip is the blocked IP if any
     pub async fn is_peer_blocked(&self, peer_id: &str) -> Result<(bool, Option<i64>, Option<IpAddr>)> {
         // Look up peer's known IP addresses
-        let peer_ip_record = self.database.with_repositories(|repos| {
-            repos.peer_ips().get(peer_id)
+        let peer_ips = self.database.with_repositories(|repos| {
+            repos.peer_ips().get_ips(peer_id)
         })?;
 
-        if let Some(record) = peer_ip_record {
-            if let Ok(ip) = record.ip_address.parse::<IpAddr>() {
+        for address in peer_ips {
+            if let Ok(ip) = address.parse::<IpAddr>() {
                 let (is_blocked, block_id) = self.is_blocked(&ip).await?;
                 if is_blocked {
                     return Ok((true, block_id, Some(ip)));
@@ -540,13 +603,54 @@ impl IpBlockChecker {
         Ok(())
     }
 
-    /// Increment hit count for a block (called when a block triggers)
-    pub async fn record_hit(&self, block_id: i64) -> Result<()> {
-        self.database.with_repositories(|repos| {
-            repos.ip_blocks().increment_hit_count(block_id)
-        })?;
+    /// Counts a hit for a block (called when a block triggers).
+    ///
+    /// A blocked neighbor can trigger this for every message it sends, so the
+    /// count is only kept in memory; `flush_hits` writes it out.
+    pub fn record_hit(&self, block_id: i64) {
+        if let Ok(mut pending) = self.pending_hits.lock() {
+            *pending.entry(block_id).or_insert(0) += 1;
+        }
+    }
 
-        Ok(())
+    /// Writes the batched hit counts in one go; returns how many blocks were updated.
+    pub fn flush_hits(&self) -> Result<usize> {
+        let pending = match self.pending_hits.lock() {
+            Ok(mut pending) => std::mem::take(&mut *pending),
+            Err(_) => return Ok(0),
+        };
+        if pending.is_empty() {
+            return Ok(0);
+        }
+        let written = self.database.with_repositories(|repos| {
+            for (block_id, count) in &pending {
+                repos.ip_blocks().add_hit_count(*block_id, *count)?;
+            }
+            Ok(pending.len())
+        });
+        if written.is_err() {
+            // Keep the counts for the next attempt
+            if let Ok(mut current) = self.pending_hits.lock() {
+                for (block_id, count) in pending {
+                    *current.entry(block_id).or_insert(0) += count;
+                }
+            }
+        }
+        written
+    }
+
+    /// Flushes hit counts every `HIT_FLUSH_INTERVAL_SECS` until every other clone is dropped.
+    pub async fn run_hit_flush_loop(self) {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(HIT_FLUSH_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            if let Err(err) = self.flush_hits() {
+                tracing::warn!(error = ?err, "failed to flush IP block hit counts");
+            }
+            if Arc::strong_count(&self.pending_hits) == 1 {
+                break;
+            }
+        }
     }
 
     /// List all IP blocks (active and inactive)
@@ -563,3 +667,28 @@ impl IpBlockChecker {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    #[tokio::test]
+    async fn ip_block_hits_are_batched_until_flushed() {
+        let database = Database::from_connection(Connection::open_in_memory().unwrap(), true);
+        database.ensure_migrations().expect("migrations");
+        let checker = IpBlockChecker::new(database);
+        let block_id = checker.add_block("10.0.0.0/8", None).await.unwrap();
+        let hit_count = || checker.list_all().unwrap()[0].hit_count;
+
+        for _ in 0..5 {
+            checker.clone().record_hit(block_id);
+        }
+        assert_eq!(hit_count(), 0, "hits stay in memory");
+
+        assert_eq!(checker.flush_hits().unwrap(), 1);
+        assert_eq!(hit_count(), 5);
+        assert_eq!(checker.flush_hits().unwrap(), 0);
+        assert_eq!(hit_count(), 5);
+    }
+}
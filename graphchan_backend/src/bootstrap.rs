@@ -37,6 +37,13 @@ pub async fn initialize(config: &GraphchanConfig) -> Result<BootstrapResources>
         &identity.friendcode,
     )?;
 
+    // Store our own public key so locally signed posts verify like remote ones
+    let public_key = crate::crypto::load_public_key_armored(&config.paths)?;
+    database.with_repositories(|repos| {
+        use crate::database::repositories::PeerRepository;
+        repos.peers().set_gpg_public_key(&identity.gpg_fingerprint, &public_key)
+    })?;
+
     Ok(BootstrapResources {
         directories_created,
         database_initialized,
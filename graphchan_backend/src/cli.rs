@@ -407,6 +407,7 @@ impl CliSession {
             created_at: None, // Use current time for interactive posts
             visibility: Some("social".to_string()), // CLI defaults to social visibility
             topics: vec![], // CLI doesn't support topic selection yet
+            metadata: None,
         };
         let details = self.thread_service.create_thread(input)?;
         println!("Created thread {}", details.thread.id);
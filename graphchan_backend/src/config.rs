@@ -9,6 +9,7 @@ pub struct GraphchanConfig {
     pub paths: GraphchanPaths,
     pub network: NetworkConfig,
     pub file: FileConfig,
+    pub api: ApiConfig,
 }
 
 impl GraphchanConfig {
@@ -20,11 +21,13 @@ impl GraphchanConfig {
             .unwrap_or(8080);
         let network = NetworkConfig::from_env();
         let file = FileConfig::from_env();
+        let api = ApiConfig::from_env();
         Ok(Self {
             api_port,
             paths,
             network,
             file,
+            api,
         })
     }
 
@@ -34,6 +37,7 @@ impl GraphchanConfig {
             paths,
             network,
             file: FileConfig::from_env(),
+            api: ApiConfig::from_env(),
         }
     }
 
@@ -48,6 +52,7 @@ impl GraphchanConfig {
             paths,
             network,
             file,
+            api: ApiConfig::from_env(),
         }
     }
 }
@@ -85,6 +90,76 @@ impl FileConfig {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct ApiConfig {
+    /// Bind the HTTP API to 127.0.0.1 instead of every interface
+    pub loopback_only: bool,
+    /// Require a bearer token on every request (see `api_tokens.rs`)
+    pub require_auth: bool,
+    /// Browser origins allowed to call the API besides loopback pages
+    pub cors_origins: Vec<String>,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            loopback_only: false,
+            require_auth: true,
+            cors_origins: Vec::new(),
+        }
+    }
+}
+
+impl ApiConfig {
+    pub fn from_env() -> Self {
+        let loopback_only = env::var("GRAPHCHAN_API_LOOPBACK_ONLY")
+            .ok()
+            .map(|v| v == "1" || v.to_lowercase() == "true")
+            .unwrap_or(false);
+
+        // Auth is on unless explicitly disabled
+        let require_auth = env::var("GRAPHCHAN_DISABLE_API_AUTH")
+            .ok()
+            .map(|v| v != "1" && v.to_lowercase() != "true")
+            .unwrap_or(true);
+
+        let cors_origins = env::var("GRAPHCHAN_CORS_ORIGINS")
+            .ok()
+            .map(|raw| {
+                raw.split(',')
+                    .map(|part| part.trim().trim_end_matches('/').to_string())
+                    .filter(|part| !part.is_empty())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        Self {
+            loopback_only,
+            require_auth,
+            cors_origins,
+        }
+    }
+
+    /// Whether a browser page at `origin` may call the API: any port on
+    /// localhost, 127.0.0.1 or [::1], or an origin listed in `cors_origins`.
+    pub fn allows_origin(&self, origin: &str) -> bool {
+        let loopback = origin
+            .strip_prefix("http://")
+            .or_else(|| origin.strip_prefix("https://"))
+            .is_some_and(|authority| {
+                ["localhost", "127.0.0.1", "[::1]"].iter().any(|host| {
+                    authority.strip_prefix(host).is_some_and(|port| {
+                        port.is_empty()
+                            || port
+                                .strip_prefix(':')
+                                .is_some_and(|digits| !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()))
+                    })
+                })
+            });
+        loopback || self.cors_origins.iter().any(|allowed| allowed == origin)
+    }
+}
+
 impl NetworkConfig {
     pub fn from_env() -> Self {
         let relay_url = env::var("GRAPHCHAN_RELAY_URL").ok().and_then(|raw| {
@@ -9,6 +9,12 @@ use std::os::unix::fs::PermissionsExt;
 use x25519_dalek::{PublicKey, StaticSecret};
 
 const ENCRYPTION_KEY_FILE: &str = "encryption.key";
+const DM_PREKEYS_FILE: &str = "dm_prekeys.json";
+
+/// How long a signed DM prekey is offered before a new one replaces it.
+pub const DM_PREKEY_ROTATION_SECS: i64 = 7 * 24 * 60 * 60;
+/// Retired prekeys kept so first messages sent against them still decrypt.
+const DM_PREKEYS_KEPT: usize = 4;
 
 #[derive(Clone)]
 pub struct X25519Identity {
@@ -32,6 +38,41 @@ struct StoredX25519Identity {
     secret_key_b64: String,
 }
 
+/// A signed prekey for X3DH DM sessions (see `ratchet.rs`).
+#[derive(Clone)]
+pub struct DmPrekey {
+    pub id: u32,
+    pub secret: StaticSecret,
+    pub public: PublicKey,
+    pub created_at: i64,
+}
+
+impl std::fmt::Debug for DmPrekey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DmPrekey")
+            .field("id", &self.id)
+            .field("public", &BASE64.encode(self.public.as_bytes()))
+            .field("created_at", &self.created_at)
+            .field("secret", &"<redacted>")
+            .finish()
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct StoredDmPrekeys {
+    version: u8,
+    /// Oldest first; the last entry is the current prekey
+    prekeys: Vec<StoredDmPrekey>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredDmPrekey {
+    id: u32,
+    public_key_b64: String,
+    secret_key_b64: String,
+    created_at: i64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WrappedKey {
     pub ciphertext: Vec<u8>,
@@ -60,17 +101,7 @@ pub fn ensure_x25519_identity(paths: &GraphchanPaths) -> Result<(String, bool)>
         secret_key_b64: BASE64.encode(secret.to_bytes()),
     };
 
-    let json = serde_json::to_string_pretty(&stored)?;
-    fs::write(&key_path, json)?;
-
-    // Tighten permissions on Unix
-    #[cfg(unix)]
-    {
-        let metadata = fs::metadata(&key_path)?;
-        let mut permissions = metadata.permissions();
-        permissions.set_mode(0o600);
-        fs::set_permissions(&key_path, permissions)?;
-    }
+    write_private(&key_path, &serde_json::to_string_pretty(&stored)?)?;
 
     tracing::info!("generated new X25519 encryption keypair");
     Ok((stored.public_key_b64, true))
@@ -95,6 +126,94 @@ pub fn load_x25519_secret(paths: &GraphchanPaths) -> Result<X25519Identity> {
     Ok(X25519Identity { secret, public })
 }
 
+/// Returns the current signed DM prekey, generating or rotating it as needed.
+pub fn ensure_dm_prekey(paths: &GraphchanPaths) -> Result<DmPrekey> {
+    ensure_dm_prekey_at(paths, chrono::Utc::now().timestamp())
+}
+
+fn ensure_dm_prekey_at(paths: &GraphchanPaths, now: i64) -> Result<DmPrekey> {
+    let path = paths.keys_dir.join(DM_PREKEYS_FILE);
+    let mut stored = if path.exists() {
+        let json = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read DM prekeys from {}", path.display()))?;
+        serde_json::from_str(&json).context("failed to deserialize DM prekeys")?
+    } else {
+        StoredDmPrekeys {
+            version: 1,
+            prekeys: Vec::new(),
+        }
+    };
+
+    if let Some(current) = stored.prekeys.last() {
+        if now - current.created_at < DM_PREKEY_ROTATION_SECS {
+            return decode_prekey(current);
+        }
+    }
+
+    let mut secret_bytes = [0u8; 32];
+    rand::rng().fill_bytes(&mut secret_bytes);
+    let secret = StaticSecret::from(secret_bytes);
+    let id = stored.prekeys.last().map_or(1, |current| current.id.wrapping_add(1));
+    stored.prekeys.push(StoredDmPrekey {
+        id,
+        public_key_b64: BASE64.encode(PublicKey::from(&secret).as_bytes()),
+        secret_key_b64: BASE64.encode(secret.to_bytes()),
+        created_at: now,
+    });
+    let excess = stored.prekeys.len().saturating_sub(DM_PREKEYS_KEPT + 1);
+    stored.prekeys.drain(..excess);
+    write_private(&path, &serde_json::to_string_pretty(&stored)?)?;
+
+    tracing::info!(prekey_id = id, "generated new DM prekey");
+    decode_prekey(stored.prekeys.last().expect("prekey just added"))
+}
+
+/// Loads a current or retired DM prekey by ID; `None` once it was dropped.
+pub fn load_dm_prekey(paths: &GraphchanPaths, id: u32) -> Result<Option<DmPrekey>> {
+    let path = paths.keys_dir.join(DM_PREKEYS_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let json = fs::read_to_string(&path)
+        .with_context(|| format!("failed to read DM prekeys from {}", path.display()))?;
+    let stored: StoredDmPrekeys = serde_json::from_str(&json).context("failed to deserialize DM prekeys")?;
+    stored
+        .prekeys
+        .iter()
+        .find(|prekey| prekey.id == id)
+        .map(decode_prekey)
+        .transpose()
+}
+
+fn decode_prekey(stored: &StoredDmPrekey) -> Result<DmPrekey> {
+    let secret_array: [u8; 32] = BASE64
+        .decode(&stored.secret_key_b64)
+        .context("failed to decode DM prekey base64")?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("DM prekey must be 32 bytes"))?;
+    let secret = StaticSecret::from(secret_array);
+    Ok(DmPrekey {
+        id: stored.id,
+        public: PublicKey::from(&secret),
+        secret,
+        created_at: stored.created_at,
+    })
+}
+
+fn write_private(path: &std::path::Path, contents: &str) -> Result<()> {
+    fs::write(path, contents)?;
+
+    // Tighten permissions on Unix
+    #[cfg(unix)]
+    {
+        let metadata = fs::metadata(path)?;
+        let mut permissions = metadata.permissions();
+        permissions.set_mode(0o600);
+        fs::set_permissions(path, permissions)?;
+    }
+    Ok(())
+}
+
 fn load_stored_identity(path: &std::path::Path) -> Result<StoredX25519Identity> {
     let json = fs::read_to_string(path)
         .with_context(|| format!("failed to read X25519 key from {}", path.display()))?;
@@ -141,4 +260,28 @@ mod tests {
         let expected_public = PublicKey::from(&identity.secret);
         assert_eq!(identity.public.as_bytes(), expected_public.as_bytes());
     }
+
+    #[test]
+    fn test_dm_prekey_rotation_keeps_recent_prekeys() {
+        let temp = TempDir::new().unwrap();
+        let paths = GraphchanPaths {
+            keys_dir: temp.path().to_path_buf(),
+            ..Default::default()
+        };
+
+        let first = ensure_dm_prekey_at(&paths, 1_000).unwrap();
+        assert_eq!(first.id, 1);
+        assert_eq!(ensure_dm_prekey_at(&paths, 2_000).unwrap().id, 1);
+
+        let mut now = 1_000;
+        for expected_id in 2..=(DM_PREKEYS_KEPT as u32 + 2) {
+            now += DM_PREKEY_ROTATION_SECS;
+            assert_eq!(ensure_dm_prekey_at(&paths, now).unwrap().id, expected_id);
+        }
+
+        // The first prekey aged out; the one after it is still around
+        assert!(load_dm_prekey(&paths, 1).unwrap().is_none());
+        let kept = load_dm_prekey(&paths, 2).unwrap().expect("retired prekey kept");
+        assert_eq!(kept.public.as_bytes(), PublicKey::from(&kept.secret).as_bytes());
+    }
 }
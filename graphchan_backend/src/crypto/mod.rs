@@ -2,14 +2,19 @@ mod keys;
 mod utils;
 mod thread_crypto;
 mod dm_crypto;
+mod ratchet;
+mod signing;
 
 pub use keys::{
     ensure_x25519_identity,
     load_x25519_secret,
+    ensure_dm_prekey,
+    load_dm_prekey,
+    DmPrekey,
     X25519Identity,
     WrappedKey,
 };
-pub use utils::{derive_key, generate_nonce_12, generate_nonce_24};
+pub use utils::{derive_key, derive_key_salted, generate_nonce_12, generate_nonce_24};
 pub use thread_crypto::{
     encrypt_thread_blob,
     decrypt_thread_blob,
@@ -22,3 +27,21 @@ pub use dm_crypto::{
     decrypt_dm,
     derive_dm_shared_secret,
 };
+pub use ratchet::{
+    associated_data,
+    x3dh_initiator,
+    x3dh_responder,
+    RatchetHeader,
+    RatchetSession,
+};
+pub use signing::{
+    load_signing_cert,
+    load_public_key_armored,
+    armor_public_cert,
+    sign_detached,
+    sign_detached_with_cert,
+    verify_detached,
+    verify_detached_with_cert,
+    fingerprint_matches,
+    public_key_matches,
+};
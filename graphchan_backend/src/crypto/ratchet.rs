@@ -0,0 +1,515 @@
+use super::utils::{derive_key, derive_key_salted};
+use anyhow::{bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    ChaCha20Poly1305, Nonce,
+};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use x25519_dalek::{PublicKey, StaticSecret};
+
+/// Most message keys derived ahead on one chain for out-of-order delivery.
+const MAX_SKIP: u32 = 1000;
+/// Most skipped message keys a session keeps; the oldest are dropped first.
+const MAX_STORED_SKIPPED: usize = 2000;
+
+const X3DH_INFO: &[u8] = b"orbweaver-x3dh-v1";
+const ROOT_INFO: &[u8] = b"orbweaver-ratchet-root-v1";
+const CHAIN_INFO: &[u8] = b"orbweaver-ratchet-chain-v1";
+const MESSAGE_INFO: &[u8] = b"orbweaver-ratchet-message-v1";
+const AEAD_INFO: &[u8] = b"orbweaver-ratchet-aead-v1";
+const AD_DOMAIN: &[u8] = b"orbweaver-dm-ratchet-v1";
+
+/// Sent in the clear next to every ratchet ciphertext (authenticated as AAD).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RatchetHeader {
+    /// The initiator's X3DH ephemeral key (base64); identifies the session
+    pub session_id: String,
+    /// The sender's current ratchet public key (base64)
+    pub ratchet_key: String,
+    /// Messages sent on the sender's previous chain
+    pub previous_chain_length: u32,
+    /// Index of this message on the current chain
+    pub message_number: u32,
+    /// Signed prekey the session was built on; set until the responder replies
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prekey_id: Option<u32>,
+}
+
+impl RatchetHeader {
+    fn encode(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(72);
+        bytes.extend_from_slice(&decode_key(&self.session_id)?);
+        bytes.extend_from_slice(&decode_key(&self.ratchet_key)?);
+        bytes.extend_from_slice(&self.previous_chain_length.to_be_bytes());
+        bytes.extend_from_slice(&self.message_number.to_be_bytes());
+        Ok(bytes)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct SkippedKey {
+    ratchet_key: [u8; 32],
+    message_number: u32,
+    message_key: [u8; 32],
+}
+
+/// Double-ratchet state for one DM session, persisted as JSON between messages.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RatchetSession {
+    session_id: [u8; 32],
+    dh_self: [u8; 32],
+    dh_remote: Option<[u8; 32]>,
+    root_key: [u8; 32],
+    send_chain: Option<[u8; 32]>,
+    recv_chain: Option<[u8; 32]>,
+    send_count: u32,
+    recv_count: u32,
+    previous_send_count: u32,
+    pending_prekey_id: Option<u32>,
+    skipped: Vec<SkippedKey>,
+}
+
+impl std::fmt::Debug for RatchetSession {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RatchetSession")
+            .field("session_id", &BASE64.encode(self.session_id))
+            .field("send_count", &self.send_count)
+            .field("recv_count", &self.recv_count)
+            .field("keys", &"<redacted>")
+            .finish()
+    }
+}
+
+impl RatchetSession {
+    /// Starts a session towards a peer's signed prekey (X3DH initiator).
+    pub fn initiate(
+        identity: &StaticSecret,
+        their_identity: &PublicKey,
+        their_prekey: &PublicKey,
+        prekey_id: u32,
+    ) -> Self {
+        Self::initiate_with(
+            identity,
+            &random_secret(),
+            random_secret(),
+            their_identity,
+            their_prekey,
+            prekey_id,
+        )
+    }
+
+    fn initiate_with(
+        identity: &StaticSecret,
+        ephemeral: &StaticSecret,
+        ratchet_secret: StaticSecret,
+        their_identity: &PublicKey,
+        their_prekey: &PublicKey,
+        prekey_id: u32,
+    ) -> Self {
+        let shared = x3dh_initiator(identity, ephemeral, their_identity, their_prekey);
+        let (root_key, send_chain) = kdf_root(&shared, ratchet_secret.diffie_hellman(their_prekey).as_bytes());
+        Self {
+            session_id: *PublicKey::from(ephemeral).as_bytes(),
+            dh_self: ratchet_secret.to_bytes(),
+            dh_remote: Some(*their_prekey.as_bytes()),
+            root_key,
+            send_chain: Some(send_chain),
+            recv_chain: None,
+            send_count: 0,
+            recv_count: 0,
+            previous_send_count: 0,
+            pending_prekey_id: Some(prekey_id),
+            skipped: Vec::new(),
+        }
+    }
+
+    /// Accepts a session from the first message's header (X3DH responder).
+    ///
+    /// `prekey` must be the signed prekey named by `header.prekey_id`. The
+    /// session can send once it decrypted that first message.
+    pub fn respond(
+        identity: &StaticSecret,
+        prekey: &StaticSecret,
+        their_identity: &PublicKey,
+        header: &RatchetHeader,
+    ) -> Result<Self> {
+        let session_id = decode_key(&header.session_id)?;
+        let ephemeral = PublicKey::from(session_id);
+        Ok(Self {
+            session_id,
+            dh_self: prekey.to_bytes(),
+            dh_remote: None,
+            root_key: x3dh_responder(identity, prekey, their_identity, &ephemeral),
+            send_chain: None,
+            recv_chain: None,
+            send_count: 0,
+            recv_count: 0,
+            previous_send_count: 0,
+            pending_prekey_id: None,
+            skipped: Vec::new(),
+        })
+    }
+
+    /// The session ID as carried in `RatchetHeader::session_id`.
+    pub fn session_id(&self) -> String {
+        BASE64.encode(self.session_id)
+    }
+
+    /// Whether `encrypt` works yet; a responder first needs the opening message.
+    pub fn can_send(&self) -> bool {
+        self.send_chain.is_some()
+    }
+
+    /// Encrypts the next message; `ad` comes from `associated_data`.
+    pub fn encrypt(&mut self, plaintext: &[u8], ad: &[u8]) -> Result<(RatchetHeader, Vec<u8>)> {
+        let chain = self
+            .send_chain
+            .context("DM session can't send before it received a message")?;
+        let (next_chain, message_key) = kdf_chain(&chain);
+        let header = RatchetHeader {
+            session_id: self.session_id(),
+            ratchet_key: BASE64.encode(PublicKey::from(&StaticSecret::from(self.dh_self)).as_bytes()),
+            previous_chain_length: self.previous_send_count,
+            message_number: self.send_count,
+            prekey_id: self.pending_prekey_id,
+        };
+        let ciphertext = seal(&message_key, plaintext, &aad(ad, &header)?)?;
+        self.send_chain = Some(next_chain);
+        self.send_count += 1;
+        Ok((header, ciphertext))
+    }
+
+    /// Decrypts a message; the session only advances when decryption succeeds.
+    pub fn decrypt(&mut self, header: &RatchetHeader, ciphertext: &[u8], ad: &[u8]) -> Result<Vec<u8>> {
+        let mut next = self.clone();
+        let plaintext = next.decrypt_in_place(header, ciphertext, ad)?;
+        *self = next;
+        Ok(plaintext)
+    }
+
+    fn decrypt_in_place(&mut self, header: &RatchetHeader, ciphertext: &[u8], ad: &[u8]) -> Result<Vec<u8>> {
+        if decode_key(&header.session_id)? != self.session_id {
+            bail!("message belongs to another DM session");
+        }
+        let aad = aad(ad, header)?;
+        let ratchet_key = decode_key(&header.ratchet_key)?;
+
+        if let Some(index) = self
+            .skipped
+            .iter()
+            .position(|key| key.ratchet_key == ratchet_key && key.message_number == header.message_number)
+        {
+            let skipped = self.skipped.remove(index);
+            return open(&skipped.message_key, ciphertext, &aad);
+        }
+
+        if self.dh_remote != Some(ratchet_key) {
+            self.skip_until(header.previous_chain_length)?;
+            self.dh_ratchet(ratchet_key);
+        }
+        self.skip_until(header.message_number)?;
+
+        let chain = self.recv_chain.context("DM session has no receiving chain")?;
+        let (next_chain, message_key) = kdf_chain(&chain);
+        let plaintext = open(&message_key, ciphertext, &aad)?;
+        self.recv_chain = Some(next_chain);
+        self.recv_count += 1;
+        // The peer answered on this session, so it no longer needs our prekey
+        self.pending_prekey_id = None;
+        Ok(plaintext)
+    }
+
+    fn skip_until(&mut self, until: u32) -> Result<()> {
+        let (Some(mut chain), Some(remote)) = (self.recv_chain, self.dh_remote) else {
+            return Ok(());
+        };
+        if until > self.recv_count.saturating_add(MAX_SKIP) {
+            bail!("too many skipped DM messages");
+        }
+        while self.recv_count < until {
+            let (next_chain, message_key) = kdf_chain(&chain);
+            self.skipped.push(SkippedKey {
+                ratchet_key: remote,
+                message_number: self.recv_count,
+                message_key,
+            });
+            chain = next_chain;
+            self.recv_count += 1;
+        }
+        self.recv_chain = Some(chain);
+        if self.skipped.len() > MAX_STORED_SKIPPED {
+            let excess = self.skipped.len() - MAX_STORED_SKIPPED;
+            self.skipped.drain(..excess);
+        }
+        Ok(())
+    }
+
+    fn dh_ratchet(&mut self, their_ratchet_key: [u8; 32]) {
+        let their_key = PublicKey::from(their_ratchet_key);
+        let (root_key, recv_chain) = kdf_root(
+            &self.root_key,
+            StaticSecret::from(self.dh_self).diffie_hellman(&their_key).as_bytes(),
+        );
+        let next_self = random_secret();
+        let (root_key, send_chain) = kdf_root(&root_key, next_self.diffie_hellman(&their_key).as_bytes());
+
+        self.previous_send_count = self.send_count;
+        self.send_count = 0;
+        self.recv_count = 0;
+        self.dh_remote = Some(their_ratchet_key);
+        self.dh_self = next_self.to_bytes();
+        self.root_key = root_key;
+        self.recv_chain = Some(recv_chain);
+        self.send_chain = Some(send_chain);
+    }
+}
+
+/// X3DH shared secret for the initiator: DH(IK_A, SPK_B) ‖ DH(EK_A, IK_B) ‖ DH(EK_A, SPK_B).
+pub fn x3dh_initiator(
+    identity: &StaticSecret,
+    ephemeral: &StaticSecret,
+    their_identity: &PublicKey,
+    their_prekey: &PublicKey,
+) -> [u8; 32] {
+    x3dh_secret(
+        identity.diffie_hellman(their_prekey).as_bytes(),
+        ephemeral.diffie_hellman(their_identity).as_bytes(),
+        ephemeral.diffie_hellman(their_prekey).as_bytes(),
+    )
+}
+
+/// X3DH shared secret for the responder; equals the initiator's.
+pub fn x3dh_responder(
+    identity: &StaticSecret,
+    prekey: &StaticSecret,
+    their_identity: &PublicKey,
+    their_ephemeral: &PublicKey,
+) -> [u8; 32] {
+    x3dh_secret(
+        prekey.diffie_hellman(their_identity).as_bytes(),
+        identity.diffie_hellman(their_ephemeral).as_bytes(),
+        prekey.diffie_hellman(their_ephemeral).as_bytes(),
+    )
+}
+
+/// Binds ciphertexts to both identity keys, sender first.
+pub fn associated_data(sender_identity: &PublicKey, recipient_identity: &PublicKey) -> Vec<u8> {
+    let mut ad = AD_DOMAIN.to_vec();
+    ad.extend_from_slice(sender_identity.as_bytes());
+    ad.extend_from_slice(recipient_identity.as_bytes());
+    ad
+}
+
+fn x3dh_secret(dh1: &[u8; 32], dh2: &[u8; 32], dh3: &[u8; 32]) -> [u8; 32] {
+    // 32 0xFF bytes first, as in X3DH, so the input never equals a lone DH output
+    let mut ikm = vec![0xFF; 32];
+    ikm.extend_from_slice(dh1);
+    ikm.extend_from_slice(dh2);
+    ikm.extend_from_slice(dh3);
+    to_array(&derive_key(&ikm, X3DH_INFO, 32))
+}
+
+fn kdf_root(root_key: &[u8; 32], dh_output: &[u8; 32]) -> ([u8; 32], [u8; 32]) {
+    let output = derive_key_salted(root_key, dh_output, ROOT_INFO, 64);
+    (to_array(&output[..32]), to_array(&output[32..]))
+}
+
+fn kdf_chain(chain_key: &[u8; 32]) -> ([u8; 32], [u8; 32]) {
+    (
+        to_array(&derive_key(chain_key, CHAIN_INFO, 32)),
+        to_array(&derive_key(chain_key, MESSAGE_INFO, 32)),
+    )
+}
+
+fn aad(ad: &[u8], header: &RatchetHeader) -> Result<Vec<u8>> {
+    let mut aad = ad.to_vec();
+    aad.extend(header.encode()?);
+    Ok(aad)
+}
+
+fn seal(message_key: &[u8; 32], plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+    // Each message key is used once, so the nonce can be derived with the key
+    let material = derive_key(message_key, AEAD_INFO, 44);
+    ChaCha20Poly1305::new(material[..32].into())
+        .encrypt(Nonce::from_slice(&material[32..]), Payload { msg: plaintext, aad })
+        .map_err(|e| anyhow::anyhow!("DM encryption failed: {}", e))
+}
+
+fn open(message_key: &[u8; 32], ciphertext: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+    let material = derive_key(message_key, AEAD_INFO, 44);
+    ChaCha20Poly1305::new(material[..32].into())
+        .decrypt(Nonce::from_slice(&material[32..]), Payload { msg: ciphertext, aad })
+        .map_err(|e| anyhow::anyhow!("DM decryption failed: {}", e))
+}
+
+fn decode_key(value: &str) -> Result<[u8; 32]> {
+    BASE64
+        .decode(value)
+        .context("invalid base64 in ratchet header")?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("ratchet header keys must be 32 bytes"))
+}
+
+fn to_array(bytes: &[u8]) -> [u8; 32] {
+    let mut array = [0u8; 32];
+    array.copy_from_slice(bytes);
+    array
+}
+
+fn random_secret() -> StaticSecret {
+    let mut bytes = [0u8; 32];
+    rand::rng().fill_bytes(&mut bytes);
+    StaticSecret::from(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secret(byte: u8) -> StaticSecret {
+        StaticSecret::from([byte; 32])
+    }
+
+    fn to_hex(bytes: impl AsRef<[u8]>) -> String {
+        bytes.as_ref().iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    struct Pair {
+        alice: RatchetSession,
+        bob: RatchetSession,
+        alice_ad: Vec<u8>,
+        bob_ad: Vec<u8>,
+    }
+
+    /// Alice (IK [1;32]) opens a session to Bob (IK [3;32], prekey [4;32]) and
+    /// Bob accepts it from her first message.
+    fn pair() -> Pair {
+        let (alice_ik, bob_ik, bob_spk) = (secret(1), secret(3), secret(4));
+        let (alice_pub, bob_pub) = (PublicKey::from(&alice_ik), PublicKey::from(&bob_ik));
+        let mut alice = RatchetSession::initiate(&alice_ik, &bob_pub, &PublicKey::from(&bob_spk), 7);
+        let alice_ad = associated_data(&alice_pub, &bob_pub);
+        let bob_ad = associated_data(&bob_pub, &alice_pub);
+
+        let (header, ciphertext) = alice.encrypt(b"hi", &alice_ad).unwrap();
+        assert_eq!(header.prekey_id, Some(7));
+        let mut bob = RatchetSession::respond(&bob_ik, &bob_spk, &alice_pub, &header).unwrap();
+        assert_eq!(bob.decrypt(&header, &ciphertext, &alice_ad).unwrap(), b"hi");
+        Pair {
+            alice,
+            bob,
+            alice_ad,
+            bob_ad,
+        }
+    }
+
+    #[test]
+    fn test_vector_first_message() {
+        let alice_ik = secret(1);
+        let bob_ik = secret(3);
+        let bob_spk = secret(4);
+        let alice_pub = PublicKey::from(&alice_ik);
+        let bob_pub = PublicKey::from(&bob_ik);
+
+        assert_eq!(
+            to_hex(x3dh_initiator(&alice_ik, &secret(2), &bob_pub, &PublicKey::from(&bob_spk))),
+            "719d4f5cde189580add52efd545496ba1277dcb5c55f7f991f044662ed7c45a6"
+        );
+
+        let mut alice =
+            RatchetSession::initiate_with(&alice_ik, &secret(2), secret(5), &bob_pub, &PublicKey::from(&bob_spk), 1);
+        let ad = associated_data(&alice_pub, &bob_pub);
+        let (header, ciphertext) = alice.encrypt(b"hello bob", &ad).unwrap();
+
+        assert_eq!(header.session_id, "zo060cy2M+x7cMF4FKXHbs0CloUFDTRHRboFhw5YfVk=");
+        assert_eq!(header.ratchet_key, "UKYUCbHd0DJemxa3AOcZ6XcsBwALG9d4bpB8ZT0gSV0=");
+        assert_eq!((header.previous_chain_length, header.message_number), (0, 0));
+        assert_eq!(to_hex(&ciphertext), "8490a733c6233e6519bcf5625c95ad2f828468f5ec7588bb50");
+
+        let mut bob = RatchetSession::respond(&bob_ik, &bob_spk, &alice_pub, &header).unwrap();
+        assert_eq!(bob.decrypt(&header, &ciphertext, &ad).unwrap(), b"hello bob");
+    }
+
+    #[test]
+    fn conversation_ratchets_in_both_directions() {
+        let Pair {
+            mut alice,
+            mut bob,
+            alice_ad,
+            bob_ad,
+        } = pair();
+
+        let (reply, ciphertext) = bob.encrypt(b"hey alice", &bob_ad).unwrap();
+        assert_eq!(reply.prekey_id, None);
+        assert_eq!(alice.decrypt(&reply, &ciphertext, &bob_ad).unwrap(), b"hey alice");
+
+        // Once Bob answered, Alice stops naming his prekey and uses a fresh ratchet key
+        let (next, ciphertext) = alice.encrypt(b"again", &alice_ad).unwrap();
+        assert_eq!(next.prekey_id, None);
+        assert_ne!(next.ratchet_key, reply.ratchet_key);
+        assert_eq!(bob.decrypt(&next, &ciphertext, &alice_ad).unwrap(), b"again");
+
+        // State survives a JSON round trip, the way it is persisted
+        let mut bob: RatchetSession = serde_json::from_str(&serde_json::to_string(&bob).unwrap()).unwrap();
+        let (header, ciphertext) = bob.encrypt(b"persisted", &bob_ad).unwrap();
+        assert_eq!(alice.decrypt(&header, &ciphertext, &bob_ad).unwrap(), b"persisted");
+    }
+
+    #[test]
+    fn out_of_order_messages_use_skipped_keys() {
+        let Pair {
+            mut alice,
+            mut bob,
+            alice_ad,
+            ..
+        } = pair();
+
+        let first = alice.encrypt(b"one", &alice_ad).unwrap();
+        let second = alice.encrypt(b"two", &alice_ad).unwrap();
+        assert_eq!(bob.decrypt(&second.0, &second.1, &alice_ad).unwrap(), b"two");
+        assert_eq!(bob.decrypt(&first.0, &first.1, &alice_ad).unwrap(), b"one");
+        // A replay finds no key anymore
+        assert!(bob.decrypt(&first.0, &first.1, &alice_ad).is_err());
+    }
+
+    #[test]
+    fn tampered_messages_leave_the_session_untouched() {
+        let Pair {
+            mut alice,
+            mut bob,
+            alice_ad,
+            ..
+        } = pair();
+
+        let (header, mut ciphertext) = alice.encrypt(b"secret", &alice_ad).unwrap();
+        ciphertext[0] ^= 1;
+        assert!(bob.decrypt(&header, &ciphertext, &alice_ad).is_err());
+        ciphertext[0] ^= 1;
+
+        let mut forged = header.clone();
+        forged.message_number += 1;
+        assert!(bob.decrypt(&forged, &ciphertext, &alice_ad).is_err());
+
+        let mut far_ahead = header.clone();
+        far_ahead.message_number = MAX_SKIP + 10;
+        assert!(bob.decrypt(&far_ahead, &ciphertext, &alice_ad).is_err());
+
+        assert_eq!(bob.decrypt(&header, &ciphertext, &alice_ad).unwrap(), b"secret");
+    }
+
+    #[test]
+    fn responder_cannot_send_first() {
+        let bob_ik = secret(3);
+        let header = RatchetHeader {
+            session_id: BASE64.encode(PublicKey::from(&secret(2)).as_bytes()),
+            ratchet_key: BASE64.encode(PublicKey::from(&secret(5)).as_bytes()),
+            previous_chain_length: 0,
+            message_number: 0,
+            prekey_id: Some(1),
+        };
+        let mut bob = RatchetSession::respond(&bob_ik, &secret(4), &PublicKey::from(&secret(1)), &header).unwrap();
+        assert!(!bob.can_send());
+        assert!(bob.encrypt(b"too early", b"ad").is_err());
+    }
+}
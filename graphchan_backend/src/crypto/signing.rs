@@ -0,0 +1,206 @@
+use crate::config::GraphchanPaths;
+use anyhow::{anyhow, Context, Result};
+use sequoia_openpgp as openpgp;
+use openpgp::parse::stream::{
+    DetachedVerifierBuilder, MessageLayer, MessageStructure, VerificationHelper,
+};
+use openpgp::parse::Parse;
+use openpgp::policy::StandardPolicy;
+use openpgp::serialize::stream::{Armorer, Message, Signer};
+use openpgp::serialize::SerializeInto;
+use openpgp::Cert;
+use std::io::Write;
+
+/// Loads the node's full certificate (including secret keys) from disk.
+pub fn load_signing_cert(paths: &GraphchanPaths) -> Result<Cert> {
+    Cert::from_file(&paths.gpg_private_key).with_context(|| {
+        format!(
+            "failed to load OpenPGP key from {}",
+            paths.gpg_private_key.display()
+        )
+    })
+}
+
+/// Returns the node's armored public certificate, suitable for sharing with peers.
+pub fn load_public_key_armored(paths: &GraphchanPaths) -> Result<String> {
+    let cert = load_signing_cert(paths)?;
+    armor_public_cert(&cert)
+}
+
+/// Serializes only the public half of a certificate as ASCII armor.
+pub fn armor_public_cert(cert: &Cert) -> Result<String> {
+    let bytes = cert.armored().to_vec()?;
+    String::from_utf8(bytes).context("armored certificate is not valid UTF-8")
+}
+
+/// Produces an armored detached signature over `data` using the node's key on disk.
+pub fn sign_detached(paths: &GraphchanPaths, data: &[u8]) -> Result<String> {
+    let cert = load_signing_cert(paths)?;
+    sign_detached_with_cert(&cert, data)
+}
+
+/// Produces an armored detached signature over `data` using the first valid signing key in `cert`.
+pub fn sign_detached_with_cert(cert: &Cert, data: &[u8]) -> Result<String> {
+    let policy = StandardPolicy::new();
+    let keypair = cert
+        .keys()
+        .unencrypted_secret()
+        .with_policy(&policy, None)
+        .supported()
+        .alive()
+        .revoked(false)
+        .for_signing()
+        .next()
+        .ok_or_else(|| anyhow!("certificate has no usable signing key"))?
+        .key()
+        .clone()
+        .into_keypair()?;
+
+    let mut sink = Vec::new();
+    {
+        let message = Message::new(&mut sink);
+        let message = Armorer::new(message)
+            .kind(openpgp::armor::Kind::Signature)
+            .build()?;
+        let mut signer = Signer::new(message, keypair)?.detached().build()?;
+        signer.write_all(data)?;
+        signer.finalize()?;
+    }
+
+    String::from_utf8(sink).context("armored signature is not valid UTF-8")
+}
+
+/// Verifies an armored detached signature against an armored public certificate.
+///
+/// The certificate's fingerprint must match `expected_fingerprint` (the peer ID),
+/// which binds the key to the identity the sender claims.
+pub fn verify_detached(
+    public_key_armored: &str,
+    expected_fingerprint: &str,
+    data: &[u8],
+    signature_armored: &str,
+) -> Result<()> {
+    let cert = Cert::from_bytes(public_key_armored.as_bytes())
+        .context("failed to parse signer certificate")?;
+    if !fingerprint_matches(&cert, expected_fingerprint) {
+        anyhow::bail!(
+            "signer certificate {} does not match claimed fingerprint {}",
+            cert.fingerprint(),
+            expected_fingerprint
+        );
+    }
+    verify_detached_with_cert(&cert, data, signature_armored)
+}
+
+/// Verifies an armored detached signature against an already parsed certificate.
+pub fn verify_detached_with_cert(cert: &Cert, data: &[u8], signature_armored: &str) -> Result<()> {
+    let policy = StandardPolicy::new();
+    let helper = SingleCertHelper { cert };
+    let mut verifier = DetachedVerifierBuilder::from_bytes(signature_armored.as_bytes())
+        .context("failed to parse detached signature")?
+        .with_policy(&policy, None, helper)?;
+    verifier.verify_bytes(data)?;
+    Ok(())
+}
+
+/// Returns true if `public_key_armored` parses and belongs to `expected_fingerprint`.
+///
+/// Used before persisting keys learned from gossip so a peer record can never be
+/// bound to a key other than its own.
+pub fn public_key_matches(public_key_armored: &str, expected_fingerprint: &str) -> bool {
+    Cert::from_bytes(public_key_armored.as_bytes())
+        .map(|cert| fingerprint_matches(&cert, expected_fingerprint))
+        .unwrap_or(false)
+}
+
+/// Compares a certificate fingerprint with a peer ID, ignoring case and whitespace.
+pub fn fingerprint_matches(cert: &Cert, expected_fingerprint: &str) -> bool {
+    let normalize = |s: &str| -> String {
+        s.chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| c.to_ascii_uppercase())
+            .collect()
+    };
+    normalize(&cert.fingerprint().to_hex()) == normalize(expected_fingerprint)
+}
+
+struct SingleCertHelper<'a> {
+    cert: &'a Cert,
+}
+
+impl VerificationHelper for SingleCertHelper<'_> {
+    fn get_certs(&mut self, _ids: &[openpgp::KeyHandle]) -> openpgp::Result<Vec<Cert>> {
+        Ok(vec![self.cert.clone()])
+    }
+
+    fn check(&mut self, structure: MessageStructure) -> openpgp::Result<()> {
+        for layer in structure.into_iter() {
+            if let MessageLayer::SignatureGroup { results } = layer {
+                if results.iter().any(|result| result.is_ok()) {
+                    return Ok(());
+                }
+            }
+        }
+        Err(anyhow!("no valid signature from the expected key"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openpgp::cert::{CertBuilder, CipherSuite};
+
+    fn generate_cert() -> Cert {
+        let (cert, _revocation) = CertBuilder::new()
+            .add_userid("Test Node <test@graphchan.local>")
+            .set_cipher_suite(CipherSuite::Cv25519)
+            .add_signing_subkey()
+            .generate()
+            .expect("generate cert");
+        cert
+    }
+
+    #[test]
+    fn test_sign_and_verify_roundtrip() {
+        let cert = generate_cert();
+        let public = armor_public_cert(&cert).unwrap();
+        let fingerprint = cert.fingerprint().to_hex();
+
+        let signature = sign_detached_with_cert(&cert, b"hello graphchan").unwrap();
+        verify_detached(&public, &fingerprint, b"hello graphchan", &signature).unwrap();
+    }
+
+    #[test]
+    fn test_tampered_data_fails() {
+        let cert = generate_cert();
+        let public = armor_public_cert(&cert).unwrap();
+        let fingerprint = cert.fingerprint().to_hex();
+
+        let signature = sign_detached_with_cert(&cert, b"original").unwrap();
+        assert!(verify_detached(&public, &fingerprint, b"tampered", &signature).is_err());
+    }
+
+    #[test]
+    fn test_wrong_fingerprint_fails() {
+        let cert = generate_cert();
+        let other = generate_cert();
+        let public = armor_public_cert(&cert).unwrap();
+        let other_fingerprint = other.fingerprint().to_hex();
+
+        let signature = sign_detached_with_cert(&cert, b"data").unwrap();
+        assert!(verify_detached(&public, &other_fingerprint, b"data", &signature).is_err());
+        assert!(!public_key_matches(&public, &other_fingerprint));
+        assert!(public_key_matches(&public, &cert.fingerprint().to_hex().to_lowercase()));
+    }
+
+    #[test]
+    fn test_signature_from_other_key_fails() {
+        let cert = generate_cert();
+        let attacker = generate_cert();
+        let public = armor_public_cert(&cert).unwrap();
+        let fingerprint = cert.fingerprint().to_hex();
+
+        let forged = sign_detached_with_cert(&attacker, b"data").unwrap();
+        assert!(verify_detached(&public, &fingerprint, b"data", &forged).is_err());
+    }
+}
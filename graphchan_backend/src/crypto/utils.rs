@@ -9,6 +9,14 @@ pub fn derive_key(input_key: &[u8], info: &[u8], output_len: usize) -> Vec<u8> {
     output
 }
 
+/// Derives a key using HKDF-SHA256 with an explicit salt.
+pub fn derive_key_salted(salt: &[u8], input_key: &[u8], info: &[u8], output_len: usize) -> Vec<u8> {
+    let hk = Hkdf::<Sha256>::new(Some(salt), input_key);
+    let mut output = vec![0u8; output_len];
+    hk.expand(info, &mut output).expect("invalid HKDF length");
+    output
+}
+
 /// Generates a random 12-byte nonce for ChaCha20Poly1305.
 pub fn generate_nonce_12() -> [u8; 12] {
     use rand::RngCore;
@@ -51,6 +59,17 @@ mod tests {
         assert_ne!(key1, key2);
     }
 
+    #[test]
+    fn test_derive_key_salted_depends_on_salt() {
+        let input = b"test input key material";
+
+        let key1 = derive_key_salted(b"salt1", input, b"context", 32);
+        let key2 = derive_key_salted(b"salt2", input, b"context", 32);
+
+        assert_ne!(key1, key2);
+        assert_ne!(key1, derive_key(input, b"context", 32));
+    }
+
     #[test]
     fn test_nonce_generation() {
         let nonce1 = generate_nonce_12();
@@ -29,7 +29,7 @@ pub(crate) const MIGRATIONS: &str = r#"
         iroh_peer_id TEXT UNIQUE,
         gpg_fingerprint TEXT,
         last_seen TEXT,
-        trust_state TEXT DEFAULT 'unknown',
+        trust_state TEXT DEFAULT 'stranger',
         avatar_file_id TEXT,
         username TEXT,
         bio TEXT
@@ -155,6 +155,26 @@ impl Database {
             self.ensure_peers_agents_column(conn)?;
             self.ensure_topic_tables(conn)?;
             self.ensure_import_tracking(conn)?;
+            self.ensure_post_signature_columns(conn)?;
+            self.ensure_peer_public_key_column(conn)?;
+            self.ensure_blocklist_sequence_column(conn)?;
+            self.ensure_file_encryption_key_column(conn)?;
+            self.ensure_thread_member_key_epoch_columns(conn)?;
+            self.ensure_seen_messages_table(conn)?;
+            self.ensure_post_edit_tables(conn)?;
+            self.ensure_peer_address_tracking(conn)?;
+            self.ensure_dm_outbox_table(conn)?;
+            self.ensure_dm_session_tables(conn)?;
+            self.ensure_dm_group_tables(conn)?;
+            self.ensure_dm_attachment_tables(conn)?;
+            self.ensure_trust_tables(conn)?;
+            self.ensure_pow_columns(conn)?;
+            self.ensure_topic_config_tables(conn)?;
+            self.ensure_topic_address_table(conn)?;
+            self.ensure_live_event_table(conn)?;
+            self.ensure_agent_key_table(conn)?;
+            self.ensure_reaction_removal_table(conn)?;
+            self.ensure_thread_key_grant_table(conn)?;
             Ok(())
         })?;
         Ok(self.newly_created)
@@ -249,7 +269,7 @@ impl Database {
             conn.execute(
                 r#"
                 INSERT INTO peers (id, alias, friendcode, iroh_peer_id, gpg_fingerprint, last_seen, trust_state)
-                VALUES (?1, 'local', ?2, ?3, ?1, datetime('now'), 'trusted')
+                VALUES (?1, 'local', ?2, ?3, ?1, datetime('now'), 'friend')
                 ON CONFLICT(id) DO UPDATE SET
                     friendcode = excluded.friendcode,
                     iroh_peer_id = excluded.iroh_peer_id,
@@ -313,6 +333,22 @@ impl Database {
         })
     }
 
+    /// Adds one to an integer setting (missing counts as 0) in a single statement and returns the new value.
+    pub fn increment_setting(&self, key: &str) -> Result<u64> {
+        self.with_conn(|conn| {
+            let value: i64 = conn
+                .query_row(
+                    "INSERT INTO settings (key, value) VALUES (?1, '1')
+                     ON CONFLICT(key) DO UPDATE SET value = CAST(value AS INTEGER) + 1
+                     RETURNING CAST(value AS INTEGER)",
+                    [key],
+                    |row| row.get(0),
+                )
+                .context("failed to increment setting")?;
+            Ok(value as u64)
+        })
+    }
+
     fn ensure_avatar_column(&self, conn: &Connection) -> Result<()> {
         let mut stmt = conn.prepare("PRAGMA table_info(peers)")?;
         let mut has_avatar = false;
@@ -530,6 +566,134 @@ impl Database {
         Ok(())
     }
 
+    fn ensure_post_signature_columns(&self, conn: &Connection) -> Result<()> {
+        let mut stmt = conn.prepare("PRAGMA table_info(posts)")?;
+        let mut has_signature = false;
+        let mut has_signature_status = false;
+        let rows = stmt.query_map([], |row| {
+            let name: String = row.get(1)?;
+            Ok(name)
+        })?;
+        for row in rows {
+            let name = row?;
+            if name.eq_ignore_ascii_case("signature") {
+                has_signature = true;
+            }
+            if name.eq_ignore_ascii_case("signature_status") {
+                has_signature_status = true;
+            }
+        }
+        if !has_signature {
+            // ASCII-armored detached OpenPGP signature by the post author
+            conn.execute("ALTER TABLE posts ADD COLUMN signature TEXT", [])?;
+        }
+        if !has_signature_status {
+            // Values: 'verified', 'unsigned', 'unverified' (NULL for posts stored before signing existed)
+            conn.execute("ALTER TABLE posts ADD COLUMN signature_status TEXT", [])?;
+        }
+        Ok(())
+    }
+
+    fn ensure_peer_public_key_column(&self, conn: &Connection) -> Result<()> {
+        let mut stmt = conn.prepare("PRAGMA table_info(peers)")?;
+        let mut has_public_key = false;
+        let rows = stmt.query_map([], |row| {
+            let name: String = row.get(1)?;
+            Ok(name)
+        })?;
+        for row in rows {
+            let name = row?;
+            if name.eq_ignore_ascii_case("gpg_public_key") {
+                has_public_key = true;
+                break;
+            }
+        }
+        if !has_public_key {
+            // ASCII-armored OpenPGP certificate, learned from signed content
+            conn.execute("ALTER TABLE peers ADD COLUMN gpg_public_key TEXT", [])?;
+        }
+        Ok(())
+    }
+
+    fn ensure_blocklist_sequence_column(&self, conn: &Connection) -> Result<()> {
+        let mut stmt = conn.prepare("PRAGMA table_info(blocklist_subscriptions)")?;
+        let mut has_sequence = false;
+        let rows = stmt.query_map([], |row| {
+            let name: String = row.get(1)?;
+            Ok(name)
+        })?;
+        for row in rows {
+            let name = row?;
+            if name.eq_ignore_ascii_case("last_sequence") {
+                has_sequence = true;
+                break;
+            }
+        }
+        if !has_sequence {
+            // Highest signed BlockAction sequence applied from the maintainer
+            conn.execute(
+                "ALTER TABLE blocklist_subscriptions ADD COLUMN last_sequence INTEGER NOT NULL DEFAULT 0",
+                [],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn ensure_file_encryption_key_column(&self, conn: &Connection) -> Result<()> {
+        let mut stmt = conn.prepare("PRAGMA table_info(files)")?;
+        let mut has_encryption_key = false;
+        let rows = stmt.query_map([], |row| {
+            let name: String = row.get(1)?;
+            Ok(name)
+        })?;
+        for row in rows {
+            let name = row?;
+            if name.eq_ignore_ascii_case("encryption_key") {
+                has_encryption_key = true;
+                break;
+            }
+        }
+        if !has_encryption_key {
+            // Base64 per-file key for private thread attachments (blob is ciphertext)
+            conn.execute("ALTER TABLE files ADD COLUMN encryption_key TEXT", [])?;
+        }
+        Ok(())
+    }
+
+    fn ensure_thread_member_key_epoch_columns(&self, conn: &Connection) -> Result<()> {
+        let mut stmt = conn.prepare("PRAGMA table_info(thread_member_keys)")?;
+        let mut has_key_epoch = false;
+        let mut has_wrapper_pubkey = false;
+        let rows = stmt.query_map([], |row| {
+            let name: String = row.get(1)?;
+            Ok(name)
+        })?;
+        for row in rows {
+            let name = row?;
+            if name.eq_ignore_ascii_case("key_epoch") {
+                has_key_epoch = true;
+            }
+            if name.eq_ignore_ascii_case("wrapper_x25519_pubkey") {
+                has_wrapper_pubkey = true;
+            }
+        }
+        if !has_key_epoch {
+            // Bumped each time a member is removed and the thread key is rotated
+            conn.execute(
+                "ALTER TABLE thread_member_keys ADD COLUMN key_epoch INTEGER NOT NULL DEFAULT 0",
+                [],
+            )?;
+        }
+        if !has_wrapper_pubkey {
+            // X25519 public key of the member who wrapped the key (usually the thread creator)
+            conn.execute(
+                "ALTER TABLE thread_member_keys ADD COLUMN wrapper_x25519_pubkey TEXT",
+                [],
+            )?;
+        }
+        Ok(())
+    }
+
     fn ensure_thread_member_keys_table(&self, conn: &Connection) -> Result<()> {
         conn.execute(
             r#"
@@ -875,6 +1039,414 @@ impl Database {
         Ok(())
     }
 
+    fn ensure_seen_messages_table(&self, conn: &Connection) -> Result<()> {
+        // Persisted gossip dedup window (see network/dedup.rs)
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS seen_messages (
+                msg_id TEXT PRIMARY KEY,
+                seen_at INTEGER NOT NULL
+            )
+            "#,
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_seen_messages_seen_at ON seen_messages(seen_at)",
+            [],
+        )?;
+        Ok(())
+    }
+
+    fn ensure_post_edit_tables(&self, conn: &Connection) -> Result<()> {
+        // Earlier bodies of edited posts, newest replaced last
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS post_revisions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                post_id TEXT NOT NULL,
+                body TEXT NOT NULL,
+                authored_at TEXT NOT NULL,
+                replaced_at TEXT NOT NULL,
+                FOREIGN KEY (post_id) REFERENCES posts(id) ON DELETE CASCADE
+            )
+            "#,
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_post_revisions_post ON post_revisions(post_id)",
+            [],
+        )?;
+        // Retracted posts keep their row (and DAG edges) with an empty body
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS post_tombstones (
+                post_id TEXT PRIMARY KEY,
+                deleted_at TEXT NOT NULL,
+                FOREIGN KEY (post_id) REFERENCES posts(id) ON DELETE CASCADE
+            )
+            "#,
+            [],
+        )?;
+        Ok(())
+    }
+
+    fn ensure_peer_address_tracking(&self, conn: &Connection) -> Result<()> {
+        let mut stmt = conn.prepare("PRAGMA table_info(peer_ips)")?;
+        let columns: Vec<String> = stmt
+            .query_map([], |row| row.get::<_, String>(1))?
+            .collect::<rusqlite::Result<_>>()?;
+        if !columns.iter().any(|name| name.eq_ignore_ascii_case("first_seen")) {
+            conn.execute("ALTER TABLE peer_ips ADD COLUMN first_seen INTEGER", [])?;
+        }
+        if !columns.iter().any(|name| name.eq_ignore_ascii_case("source")) {
+            // "friendcode" (advertised multiaddr) or "direct" (observed connection)
+            conn.execute(
+                "ALTER TABLE peer_ips ADD COLUMN source TEXT NOT NULL DEFAULT 'friendcode'",
+                [],
+            )?;
+        }
+
+        // Paths our gossip neighbors were reached over, keyed by iroh endpoint ID
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS endpoint_paths (
+                endpoint_id TEXT NOT NULL,
+                address TEXT NOT NULL,
+                path_kind TEXT NOT NULL,
+                first_seen INTEGER NOT NULL,
+                last_seen INTEGER NOT NULL,
+                deliveries INTEGER NOT NULL DEFAULT 0,
+                PRIMARY KEY (endpoint_id, address)
+            )
+            "#,
+            [],
+        )?;
+        Ok(())
+    }
+
+    fn ensure_dm_outbox_table(&self, conn: &Connection) -> Result<()> {
+        // Outgoing DMs until the recipient acknowledges them, plus DMs we hold
+        // for offline friends (see network/outbox.rs). Times are unix seconds.
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS dm_outbox (
+                message_id TEXT PRIMARY KEY,
+                from_peer_id TEXT NOT NULL,
+                to_peer_id TEXT NOT NULL,
+                event_json TEXT NOT NULL,
+                state TEXT NOT NULL,
+                attempts INTEGER NOT NULL DEFAULT 0,
+                created_at INTEGER NOT NULL,
+                last_attempt_at INTEGER,
+                acked_at INTEGER
+            )
+            "#,
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_dm_outbox_recipient ON dm_outbox(to_peer_id, state)",
+            [],
+        )?;
+        Ok(())
+    }
+
+    fn ensure_dm_session_tables(&self, conn: &Connection) -> Result<()> {
+        let mut stmt = conn.prepare("PRAGMA table_info(direct_messages)")?;
+        let columns: Vec<String> = stmt
+            .query_map([], |row| row.get::<_, String>(1))?
+            .collect::<rusqlite::Result<_>>()?;
+        if !columns.iter().any(|name| name.eq_ignore_ascii_case("sealed_to_self")) {
+            // Ratcheted DMs can't be re-decrypted, so our copy is boxed to our own key
+            conn.execute(
+                "ALTER TABLE direct_messages ADD COLUMN sealed_to_self INTEGER NOT NULL DEFAULT 0",
+                [],
+            )?;
+        }
+
+        // Double-ratchet state per DM session (see crypto/ratchet.rs)
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS dm_sessions (
+                session_id TEXT PRIMARY KEY,
+                conversation_id TEXT NOT NULL,
+                peer_id TEXT NOT NULL,
+                state_json TEXT NOT NULL,
+                updated_at INTEGER NOT NULL
+            )
+            "#,
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_dm_sessions_conversation ON dm_sessions(conversation_id, updated_at)",
+            [],
+        )?;
+
+        // Latest verified prekey bundle each peer published in a ProfileUpdate
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS dm_prekey_bundles (
+                peer_id TEXT PRIMARY KEY,
+                bundle_json TEXT NOT NULL,
+                created_at INTEGER NOT NULL
+            )
+            "#,
+            [],
+        )?;
+        Ok(())
+    }
+
+    fn ensure_dm_group_tables(&self, conn: &Connection) -> Result<()> {
+        // Group DM conversations; their messages live in direct_messages under the group ID
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS dm_groups (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                created_by TEXT NOT NULL,
+                membership_version INTEGER NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL,
+                last_message_at TEXT,
+                last_message_preview TEXT
+            )
+            "#,
+            [],
+        )?;
+
+        // Members may include peers we don't know, so no foreign key to peers
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS dm_group_members (
+                group_id TEXT NOT NULL,
+                peer_id TEXT NOT NULL,
+                PRIMARY KEY (group_id, peer_id),
+                FOREIGN KEY (group_id) REFERENCES dm_groups(id) ON DELETE CASCADE
+            )
+            "#,
+            [],
+        )?;
+        Ok(())
+    }
+
+    fn ensure_dm_attachment_tables(&self, conn: &Connection) -> Result<()> {
+        let mut stmt = conn.prepare("PRAGMA table_info(direct_messages)")?;
+        let columns: Vec<String> = stmt
+            .query_map([], |row| row.get::<_, String>(1))?
+            .collect::<rusqlite::Result<_>>()?;
+        if !columns.iter().any(|name| name.eq_ignore_ascii_case("reply_to")) {
+            conn.execute("ALTER TABLE direct_messages ADD COLUMN reply_to TEXT", [])?;
+        }
+
+        // Files sent with DMs; the blob is ciphertext and the key came inside the DM
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS dm_attachments (
+                id TEXT PRIMARY KEY,
+                message_id TEXT,
+                original_name TEXT,
+                mime TEXT,
+                size_bytes INTEGER NOT NULL,
+                blob_id TEXT NOT NULL,
+                ticket TEXT,
+                encryption_key TEXT NOT NULL,
+                path TEXT,
+                download_status TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                FOREIGN KEY (message_id) REFERENCES direct_messages(id) ON DELETE CASCADE
+            )
+            "#,
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_dm_attachments_message ON dm_attachments(message_id)",
+            [],
+        )?;
+        Ok(())
+    }
+
+    fn ensure_trust_tables(&self, conn: &Connection) -> Result<()> {
+        // Older rows used "trusted" / "unknown"; anything unrecognised is a stranger
+        conn.execute("UPDATE peers SET trust_state = 'friend' WHERE trust_state = 'trusted'", [])?;
+        conn.execute(
+            "UPDATE peers SET trust_state = 'stranger' WHERE trust_state IS NULL OR trust_state NOT IN ('friend', 'known', 'stranger', 'muted')",
+            [],
+        )?;
+
+        // Live posts from strangers wait here until reviewed
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS quarantined_posts (
+                post_id TEXT PRIMARY KEY,
+                thread_id TEXT NOT NULL,
+                author_peer_id TEXT,
+                status TEXT NOT NULL DEFAULT 'pending',
+                quarantined_at TEXT NOT NULL,
+                FOREIGN KEY (post_id) REFERENCES posts(id) ON DELETE CASCADE
+            )
+            "#,
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_quarantined_posts_thread ON quarantined_posts(thread_id)",
+            [],
+        )?;
+        Ok(())
+    }
+
+    fn ensure_pow_columns(&self, conn: &Connection) -> Result<()> {
+        let mut stmt = conn.prepare("PRAGMA table_info(threads)")?;
+        let thread_columns: Vec<String> = stmt
+            .query_map([], |row| row.get::<_, String>(1))?
+            .collect::<rusqlite::Result<_>>()?;
+        // The creator's proof-of-work stamp, re-sent with every announcement of the thread
+        if !thread_columns.iter().any(|name| name.eq_ignore_ascii_case("pow_difficulty")) {
+            conn.execute("ALTER TABLE threads ADD COLUMN pow_difficulty INTEGER", [])?;
+        }
+        if !thread_columns.iter().any(|name| name.eq_ignore_ascii_case("pow_nonce")) {
+            conn.execute("ALTER TABLE threads ADD COLUMN pow_nonce INTEGER", [])?;
+        }
+
+        let mut stmt = conn.prepare("PRAGMA table_info(user_topics)")?;
+        let topic_columns: Vec<String> = stmt
+            .query_map([], |row| row.get::<_, String>(1))?
+            .collect::<rusqlite::Result<_>>()?;
+        // NULL means `pow::DEFAULT_TOPIC_DIFFICULTY`
+        if !topic_columns.iter().any(|name| name.eq_ignore_ascii_case("pow_difficulty")) {
+            conn.execute("ALTER TABLE user_topics ADD COLUMN pow_difficulty INTEGER", [])?;
+        }
+        Ok(())
+    }
+
+    fn ensure_topic_config_tables(&self, conn: &Connection) -> Result<()> {
+        let mut stmt = conn.prepare("PRAGMA table_info(user_topics)")?;
+        let columns: Vec<String> = stmt
+            .query_map([], |row| row.get::<_, String>(1))?
+            .collect::<rusqlite::Result<_>>()?;
+        // Local per-topic settings; never published
+        if !columns.iter().any(|name| name.eq_ignore_ascii_case("auto_download")) {
+            conn.execute("ALTER TABLE user_topics ADD COLUMN auto_download INTEGER NOT NULL DEFAULT 1", [])?;
+        }
+        if !columns.iter().any(|name| name.eq_ignore_ascii_case("mute_strangers")) {
+            conn.execute("ALTER TABLE user_topics ADD COLUMN mute_strangers INTEGER NOT NULL DEFAULT 0", [])?;
+        }
+        if !columns.iter().any(|name| name.eq_ignore_ascii_case("notification_level")) {
+            conn.execute(
+                "ALTER TABLE user_topics ADD COLUMN notification_level TEXT NOT NULL DEFAULT 'all'",
+                [],
+            )?;
+        }
+
+        // Latest founder-signed metadata per topic, kept verbatim so it can be re-served
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS topic_metadata (
+                topic_id TEXT PRIMARY KEY,
+                founder_peer_id TEXT NOT NULL,
+                version INTEGER NOT NULL,
+                record_json TEXT NOT NULL,
+                received_at TEXT NOT NULL
+            )
+            "#,
+            [],
+        )?;
+        Ok(())
+    }
+
+    fn ensure_live_event_table(&self, conn: &Connection) -> Result<()> {
+        // Recent ingest events for `GET /events`; `seq` is the clients' resume cursor
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS live_events (
+                seq INTEGER PRIMARY KEY AUTOINCREMENT,
+                kind TEXT NOT NULL,
+                thread_id TEXT,
+                peer_id TEXT,
+                topics TEXT NOT NULL DEFAULT '[]',
+                data TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            )
+            "#,
+            [],
+        )?;
+        Ok(())
+    }
+
+    fn ensure_topic_address_table(&self, conn: &Connection) -> Result<()> {
+        // Endpoints met on each topic, so a restart can rejoin without waiting on the DHT
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS topic_peer_addresses (
+                topic_id TEXT NOT NULL,
+                endpoint_id TEXT NOT NULL,
+                relay_url TEXT,
+                direct_addrs TEXT NOT NULL DEFAULT '[]',
+                first_seen INTEGER NOT NULL,
+                last_seen INTEGER NOT NULL,
+                last_success INTEGER,
+                PRIMARY KEY (topic_id, endpoint_id)
+            )
+            "#,
+            [],
+        )?;
+        Ok(())
+    }
+
+    fn ensure_agent_key_table(&self, conn: &Connection) -> Result<()> {
+        // Agent posting keys delegated by a node; the secret is only kept for our own
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS agent_keys (
+                owner_peer_id TEXT NOT NULL,
+                agent_id TEXT NOT NULL,
+                name TEXT NOT NULL,
+                public_key TEXT NOT NULL,
+                delegation TEXT NOT NULL,
+                secret_key TEXT,
+                created_at INTEGER NOT NULL,
+                revoked_at INTEGER,
+                revocation TEXT,
+                PRIMARY KEY (owner_peer_id, agent_id)
+            )
+            "#,
+            [],
+        )?;
+        Ok(())
+    }
+
+    fn ensure_reaction_removal_table(&self, conn: &Connection) -> Result<()> {
+        // Newest signed removal per reaction, so a replayed older add can't bring it back
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS reaction_removals (
+                post_id TEXT NOT NULL,
+                reactor_peer_id TEXT NOT NULL,
+                emoji TEXT NOT NULL,
+                signature TEXT NOT NULL,
+                removed_at TEXT NOT NULL,
+                PRIMARY KEY (post_id, reactor_peer_id, emoji),
+                FOREIGN KEY (post_id) REFERENCES posts(id) ON DELETE CASCADE
+            )
+            "#,
+            [],
+        )?;
+        Ok(())
+    }
+
+    fn ensure_thread_key_grant_table(&self, conn: &Connection) -> Result<()> {
+        // Latest creator-signed key grant a member accepted, relayed verbatim in its announcements
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS thread_key_grants (
+                thread_id TEXT PRIMARY KEY,
+                key_epoch INTEGER NOT NULL,
+                grant_json TEXT NOT NULL,
+                FOREIGN KEY (thread_id) REFERENCES threads(id) ON DELETE CASCADE
+            )
+            "#,
+            [],
+        )?;
+        Ok(())
+    }
+
     fn ensure_topic_tables(&self, conn: &Connection) -> Result<()> {
         // Create user_topics table - tracks which topics the user subscribes to
         conn.execute(
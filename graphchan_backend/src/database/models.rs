@@ -15,6 +15,8 @@ pub struct PeerRecord {
     pub trust_state: String,
     /// JSON-encoded Vec<String> of authorized agent names
     pub agents: Option<String>,
+    /// ASCII-armored OpenPGP public certificate (fingerprint must equal gpg_fingerprint)
+    pub gpg_public_key: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,6 +35,17 @@ pub struct ThreadRecord {
     pub last_refreshed_at: Option<String>, // ISO timestamp of last refresh
 }
 
+/// An earlier body of an edited post.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PostRevisionRecord {
+    pub post_id: String,
+    pub body: String,
+    /// When this version was written (the post's created_at or previous updated_at)
+    pub authored_at: String,
+    /// When the next version replaced it
+    pub replaced_at: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PostRecord {
     pub id: String,
@@ -45,6 +58,10 @@ pub struct PostRecord {
     pub updated_at: Option<String>,
     /// JSON-encoded PostMetadata
     pub metadata: Option<String>,
+    /// ASCII-armored detached OpenPGP signature by the author
+    pub signature: Option<String>,
+    /// 'verified', 'unsigned' or 'unverified' (invalid signatures are never stored)
+    pub signature_status: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -65,6 +82,8 @@ pub struct FileRecord {
     pub checksum: Option<String>,
     pub ticket: Option<String>,
     pub download_status: Option<String>,
+    /// Base64 per-file key for attachments of private threads; the blob holds ciphertext
+    pub encryption_key: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -82,6 +101,10 @@ pub struct ThreadMemberKey {
     pub member_peer_id: String,
     pub wrapped_key_ciphertext: Vec<u8>,
     pub wrapped_key_nonce: Vec<u8>,
+    /// Incremented every time the thread key is rotated
+    pub key_epoch: i64,
+    /// Base64 X25519 public key of whoever wrapped this key (needed to unwrap it)
+    pub wrapper_x25519_pubkey: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -94,6 +117,74 @@ pub struct DirectMessageRecord {
     pub nonce: Vec<u8>,
     pub created_at: String,
     pub read_at: Option<String>,
+    /// Boxed to our own X25519 key instead of the peer's (ratcheted DMs)
+    pub sealed_to_self: bool,
+    /// Local ID of the message this one replies to
+    pub reply_to: Option<String>,
+}
+
+/// An encrypted file sent with a DM. Ours sit unattached until sent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DmAttachmentRecord {
+    pub id: String,
+    /// Local ID of the carrying message; `None` for an upload not sent yet
+    pub message_id: Option<String>,
+    pub original_name: Option<String>,
+    pub mime: Option<String>,
+    pub size_bytes: i64,
+    /// Hash of the encrypted blob
+    pub blob_id: String,
+    pub ticket: Option<String>,
+    /// Base64 per-file key; only ever travels inside an encrypted DM
+    pub encryption_key: String,
+    /// Plaintext copy under the data directory, once uploaded or downloaded
+    pub path: Option<String>,
+    /// "pending", "downloading", "available" or "failed"
+    pub download_status: String,
+    pub created_at: String,
+}
+
+/// A DM waiting for (or holding) a delivery receipt.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DmOutboxRecord {
+    pub message_id: String,
+    pub from_peer_id: String,
+    pub to_peer_id: String,
+    /// Serialized `DirectMessageEvent`, resent as-is on every attempt
+    pub event_json: String,
+    /// "queued", "sent", "acked", or "held" (forwarding for a friend)
+    pub state: String,
+    pub attempts: i64,
+    pub created_at: i64,
+    pub last_attempt_at: Option<i64>,
+    pub acked_at: Option<i64>,
+}
+
+/// Persisted double-ratchet state of one DM session.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DmSessionRecord {
+    /// Base64 X3DH ephemeral key of whoever opened the session
+    pub session_id: String,
+    pub conversation_id: String,
+    pub peer_id: String,
+    /// Serialized `crypto::RatchetSession`
+    pub state_json: String,
+    /// Unix seconds of the last message sent or received on the session
+    pub updated_at: i64,
+}
+
+/// A group DM conversation; members live in `dm_group_members`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DmGroupRecord {
+    pub id: String,
+    pub name: String,
+    /// The only member allowed to change the membership
+    pub created_by: String,
+    /// Bumped by the creator on every membership change; 0 until the first one arrived
+    pub membership_version: i64,
+    pub created_at: String,
+    pub last_message_at: Option<String>,
+    pub last_message_preview: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -120,6 +211,8 @@ pub struct BlocklistSubscriptionRecord {
     pub description: Option<String>,
     pub auto_apply: bool,
     pub last_synced_at: Option<String>,
+    /// Highest maintainer BlockAction sequence applied; older actions are replays
+    pub last_sequence: i64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -141,6 +234,84 @@ pub struct RedactedPostRecord {
     pub discovered_at: String,
 }
 
+/// Local settings for a subscribed topic (`user_topics` columns).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TopicSettingsRecord {
+    pub topic_id: String,
+    pub auto_download: bool,
+    pub mute_strangers: bool,
+    /// "all", "replies" or "off"
+    pub notification_level: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TopicMetadataRecord {
+    pub topic_id: String,
+    pub founder_peer_id: String,
+    pub version: i64,
+    /// Serialized `TopicMetadata`, signature included
+    pub record_json: String,
+    pub received_at: String,
+}
+
+/// A cached address for an endpoint met on a topic.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TopicPeerAddressRecord {
+    pub topic_id: String,
+    /// iroh endpoint ID
+    pub endpoint_id: String,
+    pub relay_url: Option<String>,
+    /// JSON array of socket addresses
+    pub direct_addrs: String,
+    pub first_seen: i64,
+    pub last_seen: i64,
+    /// Last time the endpoint became our gossip neighbor on the topic
+    pub last_success: Option<i64>,
+}
+
+/// One entry of the live event log behind `GET /events`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LiveEventRecord {
+    /// Assigned on append; ignored when inserting
+    pub seq: i64,
+    pub kind: String,
+    pub thread_id: Option<String>,
+    pub peer_id: Option<String>,
+    /// JSON array of the thread's topics
+    pub topics: String,
+    /// The serialized event body
+    pub data: String,
+    pub created_at: String,
+}
+
+/// A posting key a node delegated to one of its agents (ours or a peer's).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AgentKeyRecord {
+    pub owner_peer_id: String,
+    pub agent_id: String,
+    pub name: String,
+    /// Ed25519 public key (base64)
+    pub public_key: String,
+    /// The owner-signed `AgentDelegation` as JSON
+    pub delegation: String,
+    /// Ed25519 seed (base64); only set for our own agents
+    pub secret_key: Option<String>,
+    pub created_at: i64,
+    pub revoked_at: Option<i64>,
+    /// The owner-signed `AgentRevocation` as JSON
+    pub revocation: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuarantinedPostRecord {
+    pub post_id: String,
+    pub thread_id: String,
+    pub author_peer_id: Option<String>,
+    /// "pending" until reviewed, then "rejected"; approved posts lose their row
+    pub status: String,
+    pub quarantined_at: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum SearchResultType {
     Post,
@@ -161,7 +332,24 @@ pub struct SearchResultRecord {
 pub struct PeerIpRecord {
     pub peer_id: String,
     pub ip_address: String,
+    pub first_seen: i64, // Unix timestamp
     pub last_seen: i64,  // Unix timestamp
+    /// "friendcode" (advertised) or "direct" (observed on a connection)
+    pub source: String,
+}
+
+/// One network path a gossip neighbor was observed on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EndpointPathRecord {
+    pub endpoint_id: String,
+    /// IP address for direct paths, relay URL for relayed ones
+    pub address: String,
+    /// "direct" or "relay"
+    pub path_kind: String,
+    pub first_seen: i64,
+    pub last_seen: i64,
+    /// Gossip messages this neighbor delivered while on the path
+    pub deliveries: i64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
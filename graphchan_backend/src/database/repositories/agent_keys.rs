@@ -0,0 +1,86 @@
+use crate::database::models::AgentKeyRecord;
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+pub(super) struct SqliteAgentKeyRepository<'conn> {
+    pub(super) conn: &'conn Connection,
+}
+
+const COLUMNS: &str = "owner_peer_id, agent_id, name, public_key, delegation, secret_key, created_at, revoked_at, revocation";
+
+fn map_row(row: &Row<'_>) -> rusqlite::Result<AgentKeyRecord> {
+    Ok(AgentKeyRecord {
+        owner_peer_id: row.get(0)?,
+        agent_id: row.get(1)?,
+        name: row.get(2)?,
+        public_key: row.get(3)?,
+        delegation: row.get(4)?,
+        secret_key: row.get(5)?,
+        created_at: row.get(6)?,
+        revoked_at: row.get(7)?,
+        revocation: row.get(8)?,
+    })
+}
+
+impl<'conn> super::AgentKeyRepository for SqliteAgentKeyRepository<'conn> {
+    fn upsert(&self, record: &AgentKeyRecord) -> Result<()> {
+        // The delegation itself never changes; a revocation is never undone
+        self.conn.execute(
+            r#"
+            INSERT INTO agent_keys (owner_peer_id, agent_id, name, public_key, delegation, secret_key, created_at, revoked_at, revocation)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            ON CONFLICT(owner_peer_id, agent_id) DO UPDATE SET
+                secret_key = COALESCE(agent_keys.secret_key, excluded.secret_key),
+                revoked_at = COALESCE(agent_keys.revoked_at, excluded.revoked_at),
+                revocation = COALESCE(agent_keys.revocation, excluded.revocation)
+            "#,
+            params![
+                record.owner_peer_id,
+                record.agent_id,
+                record.name,
+                record.public_key,
+                record.delegation,
+                record.secret_key,
+                record.created_at,
+                record.revoked_at,
+                record.revocation
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn get(&self, owner_peer_id: &str, agent_id: &str) -> Result<Option<AgentKeyRecord>> {
+        let record = self
+            .conn
+            .query_row(
+                &format!("SELECT {COLUMNS} FROM agent_keys WHERE owner_peer_id = ?1 AND agent_id = ?2"),
+                params![owner_peer_id, agent_id],
+                map_row,
+            )
+            .optional()?;
+        Ok(record)
+    }
+
+    fn list_for_owner(&self, owner_peer_id: &str) -> Result<Vec<AgentKeyRecord>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {COLUMNS} FROM agent_keys WHERE owner_peer_id = ?1 ORDER BY created_at ASC, agent_id ASC"
+        ))?;
+        let rows = stmt.query_map(params![owner_peer_id], map_row)?;
+        let mut records = Vec::new();
+        for row in rows {
+            records.push(row?);
+        }
+        Ok(records)
+    }
+
+    fn revoke(&self, owner_peer_id: &str, agent_id: &str, revoked_at: i64, revocation: &str) -> Result<bool> {
+        let updated = self.conn.execute(
+            r#"
+            UPDATE agent_keys SET revoked_at = ?3, revocation = ?4
+            WHERE owner_peer_id = ?1 AND agent_id = ?2 AND revoked_at IS NULL
+            "#,
+            params![owner_peer_id, agent_id, revoked_at, revocation],
+        )?;
+        Ok(updated > 0)
+    }
+}
@@ -11,8 +11,8 @@ impl<'conn> super::BlocklistRepository for SqliteBlocklistRepository<'conn> {
         self.conn.execute(
             r#"
             INSERT OR REPLACE INTO blocklist_subscriptions
-            (id, maintainer_peer_id, name, description, auto_apply, last_synced_at)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            (id, maintainer_peer_id, name, description, auto_apply, last_synced_at, last_sequence)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
             "#,
             params![
                 record.id,
@@ -20,7 +20,8 @@ impl<'conn> super::BlocklistRepository for SqliteBlocklistRepository<'conn> {
                 record.name,
                 record.description,
                 if record.auto_apply { 1 } else { 0 },
-                record.last_synced_at
+                record.last_synced_at,
+                record.last_sequence
             ],
         )?;
         Ok(())
@@ -40,7 +41,7 @@ impl<'conn> super::BlocklistRepository for SqliteBlocklistRepository<'conn> {
     fn list_subscriptions(&self) -> Result<Vec<BlocklistSubscriptionRecord>> {
         let mut stmt = self.conn.prepare(
             r#"
-            SELECT id, maintainer_peer_id, name, description, auto_apply, last_synced_at
+            SELECT id, maintainer_peer_id, name, description, auto_apply, last_synced_at, last_sequence
             FROM blocklist_subscriptions
             ORDER BY name
             "#,
@@ -54,6 +55,7 @@ impl<'conn> super::BlocklistRepository for SqliteBlocklistRepository<'conn> {
                 description: row.get(3)?,
                 auto_apply: row.get::<_, i64>(4)? != 0,
                 last_synced_at: row.get(5)?,
+                last_sequence: row.get(6)?,
             })
         })?;
 
@@ -125,4 +127,16 @@ impl<'conn> super::BlocklistRepository for SqliteBlocklistRepository<'conn> {
         )?;
         Ok(count > 0)
     }
+
+    fn advance_sequence(&self, blocklist_id: &str, sequence: i64) -> Result<bool> {
+        let updated = self.conn.execute(
+            r#"
+            UPDATE blocklist_subscriptions
+            SET last_sequence = ?2, last_synced_at = ?3
+            WHERE id = ?1 AND last_sequence < ?2
+            "#,
+            params![blocklist_id, sequence, crate::utils::now_utc_iso()],
+        )?;
+        Ok(updated > 0)
+    }
 }
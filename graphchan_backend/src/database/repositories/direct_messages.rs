@@ -10,8 +10,8 @@ impl<'conn> super::DirectMessageRepository for SqliteDirectMessageRepository<'co
     fn create(&self, record: &DirectMessageRecord) -> Result<()> {
         self.conn.execute(
             r#"
-            INSERT INTO direct_messages (id, conversation_id, from_peer_id, to_peer_id, encrypted_body, nonce, created_at, read_at)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            INSERT INTO direct_messages (id, conversation_id, from_peer_id, to_peer_id, encrypted_body, nonce, created_at, read_at, sealed_to_self, reply_to)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
             "#,
             params![
                 record.id,
@@ -21,7 +21,9 @@ impl<'conn> super::DirectMessageRepository for SqliteDirectMessageRepository<'co
                 record.encrypted_body,
                 record.nonce,
                 record.created_at,
-                record.read_at
+                record.read_at,
+                record.sealed_to_self,
+                record.reply_to
             ],
         )?;
         Ok(())
@@ -30,7 +32,7 @@ impl<'conn> super::DirectMessageRepository for SqliteDirectMessageRepository<'co
     fn get(&self, id: &str) -> Result<Option<DirectMessageRecord>> {
         let result = self.conn.query_row(
             r#"
-            SELECT id, conversation_id, from_peer_id, to_peer_id, encrypted_body, nonce, created_at, read_at
+            SELECT id, conversation_id, from_peer_id, to_peer_id, encrypted_body, nonce, created_at, read_at, sealed_to_self, reply_to
             FROM direct_messages
             WHERE id = ?1
             "#,
@@ -45,6 +47,8 @@ impl<'conn> super::DirectMessageRepository for SqliteDirectMessageRepository<'co
                     nonce: row.get(5)?,
                     created_at: row.get(6)?,
                     read_at: row.get(7)?,
+                    sealed_to_self: row.get(8)?,
+                    reply_to: row.get(9)?,
                 })
             },
         ).optional()?;
@@ -54,7 +58,7 @@ impl<'conn> super::DirectMessageRepository for SqliteDirectMessageRepository<'co
     fn list_for_conversation(&self, conversation_id: &str, limit: usize) -> Result<Vec<DirectMessageRecord>> {
         let mut stmt = self.conn.prepare(
             r#"
-            SELECT id, conversation_id, from_peer_id, to_peer_id, encrypted_body, nonce, created_at, read_at
+            SELECT id, conversation_id, from_peer_id, to_peer_id, encrypted_body, nonce, created_at, read_at, sealed_to_self, reply_to
             FROM direct_messages
             WHERE conversation_id = ?1
             ORDER BY created_at DESC
@@ -72,6 +76,8 @@ impl<'conn> super::DirectMessageRepository for SqliteDirectMessageRepository<'co
                 nonce: row.get(5)?,
                 created_at: row.get(6)?,
                 read_at: row.get(7)?,
+                sealed_to_self: row.get(8)?,
+                reply_to: row.get(9)?,
             })
         })?;
 
@@ -106,4 +112,29 @@ impl<'conn> super::DirectMessageRepository for SqliteDirectMessageRepository<'co
         )?;
         Ok(count as usize)
     }
+
+    fn count_unread_in_conversation(&self, conversation_id: &str, to_peer_id: &str) -> Result<usize> {
+        let count: i64 = self.conn.query_row(
+            r#"
+            SELECT COUNT(*)
+            FROM direct_messages
+            WHERE conversation_id = ?1 AND to_peer_id = ?2 AND read_at IS NULL
+            "#,
+            params![conversation_id, to_peer_id],
+            |row| row.get(0),
+        )?;
+        Ok(count as usize)
+    }
+
+    fn delete_from_non_members(&self, group_id: &str) -> Result<usize> {
+        let deleted = self.conn.execute(
+            r#"
+            DELETE FROM direct_messages
+            WHERE conversation_id = ?1
+              AND from_peer_id NOT IN (SELECT peer_id FROM dm_group_members WHERE group_id = ?1)
+            "#,
+            params![group_id],
+        )?;
+        Ok(deleted)
+    }
 }
@@ -0,0 +1,96 @@
+use crate::database::models::DmAttachmentRecord;
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+pub(super) struct SqliteDmAttachmentRepository<'conn> {
+    pub(super) conn: &'conn Connection,
+}
+
+const SELECT_COLUMNS: &str = "id, message_id, original_name, mime, size_bytes, blob_id, ticket, encryption_key, path, download_status, created_at";
+
+fn map_row(row: &Row<'_>) -> rusqlite::Result<DmAttachmentRecord> {
+    Ok(DmAttachmentRecord {
+        id: row.get(0)?,
+        message_id: row.get(1)?,
+        original_name: row.get(2)?,
+        mime: row.get(3)?,
+        size_bytes: row.get(4)?,
+        blob_id: row.get(5)?,
+        ticket: row.get(6)?,
+        encryption_key: row.get(7)?,
+        path: row.get(8)?,
+        download_status: row.get(9)?,
+        created_at: row.get(10)?,
+    })
+}
+
+impl<'conn> super::DmAttachmentRepository for SqliteDmAttachmentRepository<'conn> {
+    fn create(&self, record: &DmAttachmentRecord) -> Result<bool> {
+        let inserted = self.conn.execute(
+            r#"
+            INSERT OR IGNORE INTO dm_attachments (id, message_id, original_name, mime, size_bytes, blob_id, ticket, encryption_key, path, download_status, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+            "#,
+            params![
+                record.id,
+                record.message_id,
+                record.original_name,
+                record.mime,
+                record.size_bytes,
+                record.blob_id,
+                record.ticket,
+                record.encryption_key,
+                record.path,
+                record.download_status,
+                record.created_at,
+            ],
+        )?;
+        Ok(inserted > 0)
+    }
+
+    fn get(&self, id: &str) -> Result<Option<DmAttachmentRecord>> {
+        let record = self
+            .conn
+            .query_row(
+                &format!("SELECT {SELECT_COLUMNS} FROM dm_attachments WHERE id = ?1"),
+                params![id],
+                map_row,
+            )
+            .optional()?;
+        Ok(record)
+    }
+
+    fn list_for_message(&self, message_id: &str) -> Result<Vec<DmAttachmentRecord>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {SELECT_COLUMNS} FROM dm_attachments WHERE message_id = ?1 ORDER BY rowid"
+        ))?;
+        let rows = stmt
+            .query_map(params![message_id], map_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    fn attach_to_message(&self, id: &str, message_id: &str) -> Result<bool> {
+        let updated = self.conn.execute(
+            "UPDATE dm_attachments SET message_id = ?2 WHERE id = ?1 AND message_id IS NULL",
+            params![id, message_id],
+        )?;
+        Ok(updated > 0)
+    }
+
+    fn set_ticket(&self, id: &str, ticket: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE dm_attachments SET ticket = ?2 WHERE id = ?1",
+            params![id, ticket],
+        )?;
+        Ok(())
+    }
+
+    fn set_download_state(&self, id: &str, path: Option<&str>, status: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE dm_attachments SET path = COALESCE(?2, path), download_status = ?3 WHERE id = ?1",
+            params![id, path, status],
+        )?;
+        Ok(())
+    }
+}
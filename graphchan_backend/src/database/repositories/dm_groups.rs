@@ -0,0 +1,103 @@
+use crate::database::models::DmGroupRecord;
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+pub(super) struct SqliteDmGroupRepository<'conn> {
+    pub(super) conn: &'conn Connection,
+}
+
+const SELECT_COLUMNS: &str =
+    "id, name, created_by, membership_version, created_at, last_message_at, last_message_preview";
+
+fn map_row(row: &Row<'_>) -> rusqlite::Result<DmGroupRecord> {
+    Ok(DmGroupRecord {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        created_by: row.get(2)?,
+        membership_version: row.get(3)?,
+        created_at: row.get(4)?,
+        last_message_at: row.get(5)?,
+        last_message_preview: row.get(6)?,
+    })
+}
+
+impl<'conn> super::DmGroupRepository for SqliteDmGroupRepository<'conn> {
+    fn upsert(&self, record: &DmGroupRecord) -> Result<()> {
+        self.conn.execute(
+            r#"
+            INSERT INTO dm_groups (id, name, created_by, membership_version, created_at, last_message_at, last_message_preview)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            ON CONFLICT(id) DO UPDATE SET
+                name = excluded.name,
+                created_by = excluded.created_by,
+                membership_version = excluded.membership_version
+            "#,
+            params![
+                record.id,
+                record.name,
+                record.created_by,
+                record.membership_version,
+                record.created_at,
+                record.last_message_at,
+                record.last_message_preview,
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn get(&self, id: &str) -> Result<Option<DmGroupRecord>> {
+        let record = self
+            .conn
+            .query_row(
+                &format!("SELECT {SELECT_COLUMNS} FROM dm_groups WHERE id = ?1"),
+                params![id],
+                map_row,
+            )
+            .optional()?;
+        Ok(record)
+    }
+
+    fn list(&self) -> Result<Vec<DmGroupRecord>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {SELECT_COLUMNS} FROM dm_groups ORDER BY COALESCE(last_message_at, created_at) DESC"
+        ))?;
+        let rows = stmt
+            .query_map([], map_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    fn set_members(&self, group_id: &str, members: &[String]) -> Result<()> {
+        self.conn
+            .execute("DELETE FROM dm_group_members WHERE group_id = ?1", params![group_id])?;
+        let mut stmt = self
+            .conn
+            .prepare("INSERT OR IGNORE INTO dm_group_members (group_id, peer_id) VALUES (?1, ?2)")?;
+        for peer_id in members {
+            stmt.execute(params![group_id, peer_id])?;
+        }
+        Ok(())
+    }
+
+    fn list_members(&self, group_id: &str) -> Result<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT peer_id FROM dm_group_members WHERE group_id = ?1 ORDER BY peer_id")?;
+        let rows = stmt
+            .query_map(params![group_id], |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    fn update_last_message(&self, group_id: &str, message_at: &str, preview: &str) -> Result<()> {
+        self.conn.execute(
+            r#"
+            UPDATE dm_groups
+            SET last_message_at = ?1, last_message_preview = ?2
+            WHERE id = ?3
+            "#,
+            params![message_at, preview, group_id],
+        )?;
+        Ok(())
+    }
+}
@@ -0,0 +1,128 @@
+use crate::database::models::DmOutboxRecord;
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+pub(super) struct SqliteDmOutboxRepository<'conn> {
+    pub(super) conn: &'conn Connection,
+}
+
+const SELECT_COLUMNS: &str =
+    "message_id, from_peer_id, to_peer_id, event_json, state, attempts, created_at, last_attempt_at, acked_at";
+
+fn map_row(row: &Row<'_>) -> rusqlite::Result<DmOutboxRecord> {
+    Ok(DmOutboxRecord {
+        message_id: row.get(0)?,
+        from_peer_id: row.get(1)?,
+        to_peer_id: row.get(2)?,
+        event_json: row.get(3)?,
+        state: row.get(4)?,
+        attempts: row.get(5)?,
+        created_at: row.get(6)?,
+        last_attempt_at: row.get(7)?,
+        acked_at: row.get(8)?,
+    })
+}
+
+impl<'conn> super::DmOutboxRepository for SqliteDmOutboxRepository<'conn> {
+    fn enqueue(&self, record: &DmOutboxRecord) -> Result<bool> {
+        let inserted = self.conn.execute(
+            r#"
+            INSERT OR IGNORE INTO dm_outbox
+                (message_id, from_peer_id, to_peer_id, event_json, state, attempts, created_at, last_attempt_at, acked_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            "#,
+            params![
+                record.message_id,
+                record.from_peer_id,
+                record.to_peer_id,
+                record.event_json,
+                record.state,
+                record.attempts,
+                record.created_at,
+                record.last_attempt_at,
+                record.acked_at,
+            ],
+        )?;
+        Ok(inserted > 0)
+    }
+
+    fn get(&self, message_id: &str) -> Result<Option<DmOutboxRecord>> {
+        let record = self
+            .conn
+            .query_row(
+                &format!("SELECT {SELECT_COLUMNS} FROM dm_outbox WHERE message_id = ?1"),
+                params![message_id],
+                map_row,
+            )
+            .optional()?;
+        Ok(record)
+    }
+
+    fn mark_attempt(&self, message_id: &str, at: i64) -> Result<()> {
+        self.conn.execute(
+            r#"
+            UPDATE dm_outbox
+            SET attempts = attempts + 1,
+                last_attempt_at = ?2,
+                state = CASE state WHEN 'queued' THEN 'sent' ELSE state END
+            WHERE message_id = ?1
+            "#,
+            params![message_id, at],
+        )?;
+        Ok(())
+    }
+
+    fn mark_acked(&self, message_id: &str, at: i64) -> Result<bool> {
+        let updated = self.conn.execute(
+            r#"
+            UPDATE dm_outbox
+            SET state = 'acked', acked_at = ?2
+            WHERE message_id = ?1 AND state IN ('queued', 'sent', 'failed')
+            "#,
+            params![message_id, at],
+        )?;
+        Ok(updated > 0)
+    }
+
+    fn remove(&self, message_id: &str) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM dm_outbox WHERE message_id = ?1",
+            params![message_id],
+        )?;
+        Ok(())
+    }
+
+    fn list_pending(&self, to_peer_id: Option<&str>) -> Result<Vec<DmOutboxRecord>> {
+        let mut stmt = self.conn.prepare(&format!(
+            r#"
+            SELECT {SELECT_COLUMNS}
+            FROM dm_outbox
+            WHERE state IN ('queued', 'sent', 'held')
+              AND (?1 IS NULL OR to_peer_id = ?1)
+            ORDER BY created_at ASC
+            "#
+        ))?;
+        let rows = stmt.query_map(params![to_peer_id], map_row)?;
+        let mut records = Vec::new();
+        for row in rows {
+            records.push(row?);
+        }
+        Ok(records)
+    }
+
+    fn prune_held(&self, before: i64) -> Result<usize> {
+        let removed = self.conn.execute(
+            "DELETE FROM dm_outbox WHERE state = 'held' AND created_at < ?1",
+            params![before],
+        )?;
+        Ok(removed)
+    }
+
+    fn fail_expired(&self, before: i64) -> Result<usize> {
+        let failed = self.conn.execute(
+            "UPDATE dm_outbox SET state = 'failed' WHERE state IN ('queued', 'sent') AND created_at < ?1",
+            params![before],
+        )?;
+        Ok(failed)
+    }
+}
@@ -0,0 +1,106 @@
+use crate::database::models::DmSessionRecord;
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+pub(super) struct SqliteDmSessionRepository<'conn> {
+    pub(super) conn: &'conn Connection,
+}
+
+const SELECT_COLUMNS: &str = "session_id, conversation_id, peer_id, state_json, updated_at";
+
+fn map_row(row: &Row<'_>) -> rusqlite::Result<DmSessionRecord> {
+    Ok(DmSessionRecord {
+        session_id: row.get(0)?,
+        conversation_id: row.get(1)?,
+        peer_id: row.get(2)?,
+        state_json: row.get(3)?,
+        updated_at: row.get(4)?,
+    })
+}
+
+impl<'conn> super::DmSessionRepository for SqliteDmSessionRepository<'conn> {
+    fn upsert(&self, record: &DmSessionRecord) -> Result<()> {
+        self.conn.execute(
+            r#"
+            INSERT INTO dm_sessions (session_id, conversation_id, peer_id, state_json, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            ON CONFLICT(session_id) DO UPDATE SET
+                state_json = excluded.state_json,
+                updated_at = excluded.updated_at
+            "#,
+            params![
+                record.session_id,
+                record.conversation_id,
+                record.peer_id,
+                record.state_json,
+                record.updated_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn get(&self, session_id: &str) -> Result<Option<DmSessionRecord>> {
+        let record = self
+            .conn
+            .query_row(
+                &format!("SELECT {SELECT_COLUMNS} FROM dm_sessions WHERE session_id = ?1"),
+                params![session_id],
+                map_row,
+            )
+            .optional()?;
+        Ok(record)
+    }
+
+    fn list_for_conversation(&self, conversation_id: &str) -> Result<Vec<DmSessionRecord>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {SELECT_COLUMNS} FROM dm_sessions WHERE conversation_id = ?1 ORDER BY updated_at DESC, session_id"
+        ))?;
+        let rows = stmt
+            .query_map(params![conversation_id], map_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    fn prune(&self, conversation_id: &str, keep: usize) -> Result<usize> {
+        let removed = self.conn.execute(
+            r#"
+            DELETE FROM dm_sessions
+            WHERE conversation_id = ?1 AND session_id NOT IN (
+                SELECT session_id FROM dm_sessions
+                WHERE conversation_id = ?1
+                ORDER BY updated_at DESC, session_id
+                LIMIT ?2
+            )
+            "#,
+            params![conversation_id, keep as i64],
+        )?;
+        Ok(removed)
+    }
+
+    fn set_prekey_bundle(&self, peer_id: &str, bundle_json: &str, created_at: i64) -> Result<bool> {
+        let stored = self.conn.execute(
+            r#"
+            INSERT INTO dm_prekey_bundles (peer_id, bundle_json, created_at)
+            VALUES (?1, ?2, ?3)
+            ON CONFLICT(peer_id) DO UPDATE SET
+                bundle_json = excluded.bundle_json,
+                created_at = excluded.created_at
+            WHERE excluded.created_at > dm_prekey_bundles.created_at
+            "#,
+            params![peer_id, bundle_json, created_at],
+        )?;
+        Ok(stored > 0)
+    }
+
+    fn get_prekey_bundle(&self, peer_id: &str) -> Result<Option<String>> {
+        let bundle = self
+            .conn
+            .query_row(
+                "SELECT bundle_json FROM dm_prekey_bundles WHERE peer_id = ?1",
+                params![peer_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(bundle)
+    }
+}
@@ -0,0 +1,50 @@
+use crate::database::models::EndpointPathRecord;
+use anyhow::Result;
+use rusqlite::{params, Connection};
+
+pub(super) struct SqliteEndpointPathRepository<'conn> {
+    pub(super) conn: &'conn Connection,
+}
+
+impl<'conn> super::EndpointPathRepository for SqliteEndpointPathRepository<'conn> {
+    fn record(&self, endpoint_id: &str, address: &str, path_kind: &str, seen_at: i64) -> Result<()> {
+        self.conn.execute(
+            r#"
+            INSERT INTO endpoint_paths (endpoint_id, address, path_kind, first_seen, last_seen, deliveries)
+            VALUES (?1, ?2, ?3, ?4, ?4, 1)
+            ON CONFLICT(endpoint_id, address) DO UPDATE SET
+                path_kind = excluded.path_kind,
+                last_seen = excluded.last_seen,
+                deliveries = deliveries + 1
+            "#,
+            params![endpoint_id, address, path_kind, seen_at],
+        )?;
+        Ok(())
+    }
+
+    fn list_for_endpoint(&self, endpoint_id: &str) -> Result<Vec<EndpointPathRecord>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT endpoint_id, address, path_kind, first_seen, last_seen, deliveries
+            FROM endpoint_paths
+            WHERE endpoint_id = ?1
+            ORDER BY last_seen DESC
+            "#,
+        )?;
+        let rows = stmt.query_map(params![endpoint_id], |row| {
+            Ok(EndpointPathRecord {
+                endpoint_id: row.get(0)?,
+                address: row.get(1)?,
+                path_kind: row.get(2)?,
+                first_seen: row.get(3)?,
+                last_seen: row.get(4)?,
+                deliveries: row.get(5)?,
+            })
+        })?;
+        let mut records = Vec::new();
+        for row in rows {
+            records.push(row?);
+        }
+        Ok(records)
+    }
+}
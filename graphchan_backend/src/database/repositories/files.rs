@@ -10,8 +10,8 @@ impl<'conn> super::FileRepository for SqliteFileRepository<'conn> {
     fn attach(&self, record: &FileRecord) -> Result<()> {
         self.conn.execute(
             r#"
-            INSERT INTO files (id, post_id, path, original_name, mime, blob_id, size_bytes, checksum, ticket, download_status)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+            INSERT INTO files (id, post_id, path, original_name, mime, blob_id, size_bytes, checksum, ticket, download_status, encryption_key)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
             "#,
             params![
                 record.id,
@@ -23,7 +23,8 @@ impl<'conn> super::FileRepository for SqliteFileRepository<'conn> {
                 record.size_bytes,
                 record.checksum,
                 record.ticket,
-                record.download_status
+                record.download_status,
+                record.encryption_key
             ],
         )?;
         Ok(())
@@ -32,8 +33,8 @@ impl<'conn> super::FileRepository for SqliteFileRepository<'conn> {
     fn upsert(&self, record: &FileRecord) -> Result<()> {
         self.conn.execute(
             r#"
-            INSERT INTO files (id, post_id, path, original_name, mime, blob_id, size_bytes, checksum, ticket, download_status)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+            INSERT INTO files (id, post_id, path, original_name, mime, blob_id, size_bytes, checksum, ticket, download_status, encryption_key)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
             ON CONFLICT(id) DO UPDATE SET
                 post_id = excluded.post_id,
                 path = excluded.path,
@@ -43,7 +44,8 @@ impl<'conn> super::FileRepository for SqliteFileRepository<'conn> {
                 size_bytes = excluded.size_bytes,
                 checksum = excluded.checksum,
                 ticket = excluded.ticket,
-                download_status = excluded.download_status
+                download_status = excluded.download_status,
+                encryption_key = COALESCE(excluded.encryption_key, files.encryption_key)
             "#,
             params![
                 record.id,
@@ -55,7 +57,8 @@ impl<'conn> super::FileRepository for SqliteFileRepository<'conn> {
                 record.size_bytes,
                 record.checksum,
                 record.ticket,
-                record.download_status
+                record.download_status,
+                record.encryption_key
             ],
         )?;
         Ok(())
@@ -64,7 +67,7 @@ impl<'conn> super::FileRepository for SqliteFileRepository<'conn> {
     fn list_for_post(&self, post_id: &str) -> Result<Vec<FileRecord>> {
         let mut stmt = self.conn.prepare(
             r#"
-            SELECT id, post_id, path, original_name, mime, blob_id, size_bytes, checksum, ticket, download_status
+            SELECT id, post_id, path, original_name, mime, blob_id, size_bytes, checksum, ticket, download_status, encryption_key
             FROM files
             WHERE post_id = ?1
             ORDER BY id ASC
@@ -82,6 +85,7 @@ impl<'conn> super::FileRepository for SqliteFileRepository<'conn> {
                 checksum: row.get(7)?,
                 ticket: row.get(8)?,
                 download_status: row.get(9)?,
+                encryption_key: row.get(10)?,
             })
         })?;
         let mut files = Vec::new();
@@ -94,7 +98,7 @@ impl<'conn> super::FileRepository for SqliteFileRepository<'conn> {
     fn list_for_thread(&self, thread_id: &str) -> Result<Vec<FileRecord>> {
         tracing::info!("FileRepository::list_for_thread called for thread_id: {}", thread_id);
         let query = r#"
-            SELECT f.id, f.post_id, f.path, f.original_name, f.mime, f.blob_id, f.size_bytes, f.checksum, f.ticket, f.download_status
+            SELECT f.id, f.post_id, f.path, f.original_name, f.mime, f.blob_id, f.size_bytes, f.checksum, f.ticket, f.download_status, f.encryption_key
             FROM files f
             INNER JOIN posts p ON f.post_id = p.id
             WHERE p.thread_id = ?1
@@ -119,6 +123,7 @@ impl<'conn> super::FileRepository for SqliteFileRepository<'conn> {
                 checksum: row.get(7)?,
                 ticket: row.get(8)?,
                 download_status: row.get(9)?,
+                encryption_key: row.get(10)?,
             })
         })?;
         let mut files = Vec::new();
@@ -133,7 +138,7 @@ impl<'conn> super::FileRepository for SqliteFileRepository<'conn> {
             .conn
             .query_row(
                 r#"
-                SELECT id, post_id, path, original_name, mime, blob_id, size_bytes, checksum, ticket, download_status
+                SELECT id, post_id, path, original_name, mime, blob_id, size_bytes, checksum, ticket, download_status, encryption_key
                 FROM files
                 WHERE id = ?1
                 "#,
@@ -150,6 +155,8 @@ impl<'conn> super::FileRepository for SqliteFileRepository<'conn> {
                         checksum: row.get(7)?,
                         ticket: row.get(8)?,
                         download_status: row.get(9)?,
+                        encryption_key: row.get(10)?,
+                encryption_key: row.get(10)?,
                     })
                 },
             )
@@ -48,14 +48,14 @@ impl<'conn> super::IpBlockRepository for SqliteIpBlockRepository<'conn> {
         Ok(())
     }
 
-    fn increment_hit_count(&self, id: i64) -> Result<()> {
+    fn add_hit_count(&self, id: i64, count: i64) -> Result<()> {
         self.conn.execute(
             r#"
             UPDATE ip_blocks
-            SET hit_count = hit_count + 1
+            SET hit_count = hit_count + ?2
             WHERE id = ?1
             "#,
-            params![id],
+            params![id, count],
         )?;
         Ok(())
     }
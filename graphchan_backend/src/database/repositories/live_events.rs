@@ -0,0 +1,75 @@
+use crate::database::models::LiveEventRecord;
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+
+pub(super) struct SqliteLiveEventRepository<'conn> {
+    pub(super) conn: &'conn Connection,
+}
+
+impl<'conn> super::LiveEventRepository for SqliteLiveEventRepository<'conn> {
+    fn append(&self, record: &LiveEventRecord) -> Result<i64> {
+        self.conn.execute(
+            r#"
+            INSERT INTO live_events (kind, thread_id, peer_id, topics, data, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            "#,
+            params![
+                record.kind,
+                record.thread_id,
+                record.peer_id,
+                record.topics,
+                record.data,
+                record.created_at
+            ],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    fn list_after(&self, after: i64, limit: usize) -> Result<Vec<LiveEventRecord>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT seq, kind, thread_id, peer_id, topics, data, created_at
+            FROM live_events
+            WHERE seq > ?1
+            ORDER BY seq ASC
+            LIMIT ?2
+            "#,
+        )?;
+        let rows = stmt.query_map(params![after, limit as i64], |row| {
+            Ok(LiveEventRecord {
+                seq: row.get(0)?,
+                kind: row.get(1)?,
+                thread_id: row.get(2)?,
+                peer_id: row.get(3)?,
+                topics: row.get(4)?,
+                data: row.get(5)?,
+                created_at: row.get(6)?,
+            })
+        })?;
+        let mut records = Vec::new();
+        for row in rows {
+            records.push(row?);
+        }
+        Ok(records)
+    }
+
+    fn latest_seq(&self) -> Result<i64> {
+        // AUTOINCREMENT keeps the high-water mark even after pruning empties the table
+        let seq = self
+            .conn
+            .query_row(
+                "SELECT seq FROM sqlite_sequence WHERE name = 'live_events'",
+                [],
+                |row| row.get::<_, i64>(0),
+            )
+            .optional()?;
+        Ok(seq.unwrap_or(0))
+    }
+
+    fn prune_through(&self, through: i64) -> Result<usize> {
+        let removed = self
+            .conn
+            .execute("DELETE FROM live_events WHERE seq <= ?1", params![through])?;
+        Ok(removed)
+    }
+}
@@ -5,22 +5,32 @@ mod peer_ips;
 mod peers;
 mod posts;
 mod reactions;
+mod seen_messages;
 mod thread_member_keys;
 mod threads;
 mod topics;
+mod topic_addresses;
+mod live_events;
+mod agent_keys;
 
 mod blocked_peers;
+mod endpoint_paths;
 mod blocklists;
 mod conversations;
 mod direct_messages;
+mod dm_attachments;
+mod dm_outbox;
+mod dm_groups;
+mod dm_sessions;
+mod quarantine;
 mod redacted_posts;
 mod search;
 
 use super::models::{
-    FileRecord, PeerRecord, PostRecord, ReactionRecord, ThreadRecord, ThreadMemberKey,
-    DirectMessageRecord, ConversationRecord, BlockedPeerRecord, BlocklistSubscriptionRecord,
-    BlocklistEntryRecord, RedactedPostRecord, SearchResultRecord,
-    PeerIpRecord, IpBlockRecord,
+    FileRecord, PeerRecord, PostRecord, PostRevisionRecord, ReactionRecord, ThreadRecord, ThreadMemberKey,
+    DirectMessageRecord, DmAttachmentRecord, DmGroupRecord, DmOutboxRecord, DmSessionRecord, ConversationRecord, BlockedPeerRecord, BlocklistSubscriptionRecord,
+    BlocklistEntryRecord, QuarantinedPostRecord, RedactedPostRecord, SearchResultRecord,
+    PeerIpRecord, IpBlockRecord, EndpointPathRecord, LiveEventRecord, AgentKeyRecord, TopicMetadataRecord, TopicPeerAddressRecord, TopicSettingsRecord,
 };
 use anyhow::Result;
 use rusqlite::Connection;
@@ -38,6 +48,10 @@ pub trait ThreadRepository {
     fn is_ignored(&self, thread_id: &str) -> Result<bool>;
     fn set_source_info(&self, thread_id: &str, source_url: &str, platform: &str) -> Result<()>;
     fn set_last_refreshed(&self, thread_id: &str) -> Result<()>;
+    /// Stores the creator's proof-of-work stamp for the thread.
+    fn set_pow_stamp(&self, thread_id: &str, difficulty: u32, nonce: u64) -> Result<()>;
+    /// `(difficulty, nonce)` of the thread's stamp, if it has one.
+    fn pow_stamp(&self, thread_id: &str) -> Result<Option<(u32, u64)>>;
 }
 
 pub trait ImportPostMapRepository {
@@ -53,14 +67,41 @@ pub trait PostRepository {
     fn list_recent(&self, limit: usize) -> Result<Vec<PostRecord>>;
     fn add_relationships(&self, child_id: &str, parent_ids: &[String]) -> Result<()>;
     fn parents_of(&self, child_id: &str) -> Result<Vec<String>>;
+    fn children_of(&self, parent_id: &str) -> Result<Vec<String>>;
     fn has_children(&self, post_id: &str) -> Result<bool>;
+    /// Replaces the body, keeping the previous one as a revision.
+    fn apply_edit(
+        &self,
+        post_id: &str,
+        body: &str,
+        updated_at: &str,
+        signature: Option<&str>,
+        signature_status: &str,
+    ) -> Result<()>;
+    /// Retracts a post: empties the body, drops its revisions and attachments,
+    /// and keeps the row so replies still point at it.
+    fn tombstone(
+        &self,
+        post_id: &str,
+        deleted_at: &str,
+        signature: Option<&str>,
+        signature_status: &str,
+    ) -> Result<()>;
+    fn is_tombstoned(&self, post_id: &str) -> Result<bool>;
+    /// Oldest first.
+    fn list_revisions(&self, post_id: &str) -> Result<Vec<PostRevisionRecord>>;
 }
 
 pub trait PeerRepository {
     fn upsert(&self, record: &PeerRecord) -> Result<()>;
     fn get(&self, id: &str) -> Result<Option<PeerRecord>>;
     fn list(&self) -> Result<Vec<PeerRecord>>;
+    fn set_gpg_public_key(&self, id: &str, public_key_armored: &str) -> Result<()>;
+    /// `upsert` never changes trust; this is the only way to. False if the peer is unknown.
+    fn set_trust_state(&self, id: &str, trust_state: &str) -> Result<bool>;
     fn delete(&self, id: &str) -> Result<()>;
+    /// ID of the peer whose friendcode carried this iroh endpoint ID.
+    fn id_for_iroh_peer(&self, iroh_peer_id: &str) -> Result<Option<String>>;
 }
 
 pub trait FileRepository {
@@ -74,6 +115,9 @@ pub trait FileRepository {
 pub trait ReactionRepository {
     fn add(&self, record: &ReactionRecord) -> Result<()>;
     fn remove(&self, post_id: &str, reactor_peer_id: &str, emoji: &str) -> Result<()>;
+    /// Keeps a signed removal (`created_at` is when it was removed); an older one never replaces a newer.
+    fn record_removal(&self, removal: &ReactionRecord) -> Result<()>;
+    fn removed_at(&self, post_id: &str, reactor_peer_id: &str, emoji: &str) -> Result<Option<String>>;
     fn list_for_post(&self, post_id: &str) -> Result<Vec<ReactionRecord>>;
     /// Returns HashMap<emoji, count>
     fn count_for_post(&self, post_id: &str) -> Result<HashMap<String, usize>>;
@@ -84,6 +128,11 @@ pub trait ThreadMemberKeyRepository {
     fn get(&self, thread_id: &str, member_peer_id: &str) -> Result<Option<ThreadMemberKey>>;
     fn list_for_thread(&self, thread_id: &str) -> Result<Vec<ThreadMemberKey>>;
     fn remove(&self, thread_id: &str, member_peer_id: &str) -> Result<()>;
+    /// Swaps the whole member set of a thread for `records` (used on key rotation).
+    fn replace_all(&self, thread_id: &str, records: &[ThreadMemberKey]) -> Result<()>;
+    /// Keeps the creator-signed grant (JSON) we accepted so we can relay it unchanged.
+    fn save_signed_grant(&self, thread_id: &str, key_epoch: i64, grant_json: &str) -> Result<()>;
+    fn signed_grant(&self, thread_id: &str) -> Result<Option<String>>;
 }
 
 pub trait DirectMessageRepository {
@@ -92,6 +141,61 @@ pub trait DirectMessageRepository {
     fn list_for_conversation(&self, conversation_id: &str, limit: usize) -> Result<Vec<DirectMessageRecord>>;
     fn mark_as_read(&self, id: &str, read_at: &str) -> Result<()>;
     fn count_unread(&self, to_peer_id: &str) -> Result<usize>;
+    fn count_unread_in_conversation(&self, conversation_id: &str, to_peer_id: &str) -> Result<usize>;
+    /// Deletes messages of a group whose sender is not in its member list; returns how many.
+    fn delete_from_non_members(&self, group_id: &str) -> Result<usize>;
+}
+
+pub trait DmOutboxRepository {
+    /// Inserts the entry unless the message is already tracked; returns whether it was new.
+    fn enqueue(&self, record: &DmOutboxRecord) -> Result<bool>;
+    fn get(&self, message_id: &str) -> Result<Option<DmOutboxRecord>>;
+    /// Counts a transmission; a queued entry becomes sent.
+    fn mark_attempt(&self, message_id: &str, at: i64) -> Result<()>;
+    /// Marks an own message acknowledged; returns false if it was not waiting for one.
+    fn mark_acked(&self, message_id: &str, at: i64) -> Result<bool>;
+    fn remove(&self, message_id: &str) -> Result<()>;
+    /// Entries still to deliver (queued, sent or held), oldest first.
+    fn list_pending(&self, to_peer_id: Option<&str>) -> Result<Vec<DmOutboxRecord>>;
+    /// Drops held entries created before `before`; returns how many.
+    fn prune_held(&self, before: i64) -> Result<usize>;
+    /// Marks own entries created before `before` that are still unacknowledged as failed; returns how many.
+    fn fail_expired(&self, before: i64) -> Result<usize>;
+}
+
+pub trait DmSessionRepository {
+    fn upsert(&self, record: &DmSessionRecord) -> Result<()>;
+    fn get(&self, session_id: &str) -> Result<Option<DmSessionRecord>>;
+    /// Most recently used first.
+    fn list_for_conversation(&self, conversation_id: &str) -> Result<Vec<DmSessionRecord>>;
+    /// Drops all but the `keep` most recently used sessions; returns how many went.
+    fn prune(&self, conversation_id: &str, keep: usize) -> Result<usize>;
+    /// Stores a peer's verified prekey bundle unless a newer one is already held.
+    fn set_prekey_bundle(&self, peer_id: &str, bundle_json: &str, created_at: i64) -> Result<bool>;
+    fn get_prekey_bundle(&self, peer_id: &str) -> Result<Option<String>>;
+}
+
+pub trait DmGroupRepository {
+    /// Inserts the group or updates its name, creator and membership version.
+    fn upsert(&self, record: &DmGroupRecord) -> Result<()>;
+    fn get(&self, id: &str) -> Result<Option<DmGroupRecord>>;
+    /// Most recent activity first.
+    fn list(&self) -> Result<Vec<DmGroupRecord>>;
+    /// Replaces the member list.
+    fn set_members(&self, group_id: &str, members: &[String]) -> Result<()>;
+    fn list_members(&self, group_id: &str) -> Result<Vec<String>>;
+    fn update_last_message(&self, group_id: &str, message_at: &str, preview: &str) -> Result<()>;
+}
+
+pub trait DmAttachmentRepository {
+    /// Inserts the attachment; returns false if its ID is already taken.
+    fn create(&self, record: &DmAttachmentRecord) -> Result<bool>;
+    fn get(&self, id: &str) -> Result<Option<DmAttachmentRecord>>;
+    fn list_for_message(&self, message_id: &str) -> Result<Vec<DmAttachmentRecord>>;
+    /// Ties an unsent upload to its message; false if it is missing or already sent.
+    fn attach_to_message(&self, id: &str, message_id: &str) -> Result<bool>;
+    fn set_ticket(&self, id: &str, ticket: &str) -> Result<()>;
+    fn set_download_state(&self, id: &str, path: Option<&str>, status: &str) -> Result<()>;
 }
 
 pub trait ConversationRepository {
@@ -117,6 +221,8 @@ pub trait BlocklistRepository {
     fn remove_entry(&self, blocklist_id: &str, peer_id: &str) -> Result<()>;
     fn list_entries(&self, blocklist_id: &str) -> Result<Vec<BlocklistEntryRecord>>;
     fn is_in_any_blocklist(&self, peer_id: &str) -> Result<bool>;
+    /// Records `sequence` as applied; returns false if it is not newer than the last one.
+    fn advance_sequence(&self, blocklist_id: &str, sequence: i64) -> Result<bool>;
 }
 
 pub trait RedactedPostRepository {
@@ -125,23 +231,92 @@ pub trait RedactedPostRepository {
     fn list_for_thread(&self, thread_id: &str) -> Result<Vec<RedactedPostRecord>>;
 }
 
+pub trait QuarantineRepository {
+    /// Quarantines a post; returns false if it already has a row.
+    fn add(&self, record: &QuarantinedPostRecord) -> Result<bool>;
+    fn get(&self, post_id: &str) -> Result<Option<QuarantinedPostRecord>>;
+    /// Oldest first; `None` lists every status.
+    fn list(&self, status: Option<&str>) -> Result<Vec<QuarantinedPostRecord>>;
+    fn list_for_thread(&self, thread_id: &str) -> Result<Vec<QuarantinedPostRecord>>;
+    fn set_status(&self, post_id: &str, status: &str) -> Result<bool>;
+    /// Drops the row so the post shows normally again.
+    fn release(&self, post_id: &str) -> Result<bool>;
+    /// Releases every pending post by `author_peer_id`; returns how many.
+    fn release_author(&self, author_peer_id: &str) -> Result<usize>;
+}
+
 pub trait SearchRepository {
     fn search(&self, query: &str, limit: usize) -> Result<Vec<SearchResultRecord>>;
 }
 
 pub trait PeerIpRepository {
+    /// Records an address advertised in the peer's friendcode.
     fn update(&self, peer_id: &str, ip_address: &str, last_seen: i64) -> Result<()>;
+    /// Records an address the peer was actually connected from.
+    fn record_direct(&self, peer_id: &str, ip_address: &str, seen_at: i64) -> Result<()>;
     fn get(&self, peer_id: &str) -> Result<Option<PeerIpRecord>>;
     fn get_by_ip(&self, ip_address: &str) -> Result<Vec<PeerIpRecord>>;
     fn get_ips(&self, peer_id: &str) -> Result<Vec<String>>;
+    fn list_for_peer(&self, peer_id: &str) -> Result<Vec<PeerIpRecord>>;
     fn list_all(&self) -> Result<Vec<PeerIpRecord>>;
 }
 
+pub trait EndpointPathRepository {
+    /// Upserts the path and counts one delivery over it.
+    fn record(&self, endpoint_id: &str, address: &str, path_kind: &str, seen_at: i64) -> Result<()>;
+    /// Most recently seen first.
+    fn list_for_endpoint(&self, endpoint_id: &str) -> Result<Vec<EndpointPathRecord>>;
+}
+
+pub trait TopicAddressRepository {
+    /// Upserts the endpoint's addresses; `None`/empty values keep what is stored.
+    /// `success` also stamps `last_success`.
+    fn record(
+        &self,
+        topic_id: &str,
+        endpoint_id: &str,
+        relay_url: Option<&str>,
+        direct_addrs: Option<&str>,
+        seen_at: i64,
+        success: bool,
+    ) -> Result<()>;
+    /// Most recently connected first, then most recently seen.
+    fn list_for_topic(&self, topic_id: &str, limit: usize) -> Result<Vec<TopicPeerAddressRecord>>;
+    /// Drops entries that never connected and were last seen before `unconfirmed_before`,
+    /// and entries whose last success is older than `confirmed_before`.
+    fn expire(&self, topic_id: &str, unconfirmed_before: i64, confirmed_before: i64) -> Result<usize>;
+    /// Keeps only the `keep` best entries (same order as `list_for_topic`).
+    fn evict_beyond(&self, topic_id: &str, keep: usize) -> Result<usize>;
+    fn clear_topic(&self, topic_id: &str) -> Result<()>;
+}
+
+pub trait LiveEventRepository {
+    /// Inserts the event and returns its sequence number.
+    fn append(&self, record: &LiveEventRecord) -> Result<i64>;
+    /// Events with `seq > after`, oldest first.
+    fn list_after(&self, after: i64, limit: usize) -> Result<Vec<LiveEventRecord>>;
+    /// Highest sequence number ever assigned (0 before the first event).
+    fn latest_seq(&self) -> Result<i64>;
+    /// Drops events with `seq <= through`.
+    fn prune_through(&self, through: i64) -> Result<usize>;
+}
+
+pub trait AgentKeyRepository {
+    /// Inserts the key, or fills in a secret or revocation the stored row lacks.
+    fn upsert(&self, record: &AgentKeyRecord) -> Result<()>;
+    fn get(&self, owner_peer_id: &str, agent_id: &str) -> Result<Option<AgentKeyRecord>>;
+    /// Oldest first, revoked keys included.
+    fn list_for_owner(&self, owner_peer_id: &str) -> Result<Vec<AgentKeyRecord>>;
+    /// Marks the key revoked; returns false if it is unknown or already revoked.
+    fn revoke(&self, owner_peer_id: &str, agent_id: &str, revoked_at: i64, revocation: &str) -> Result<bool>;
+}
+
 pub trait IpBlockRepository {
     fn add(&self, record: &IpBlockRecord) -> Result<i64>;
     fn remove(&self, id: i64) -> Result<()>;
     fn set_active(&self, id: i64, active: bool) -> Result<()>;
-    fn increment_hit_count(&self, id: i64) -> Result<()>;
+    /// Adds `count` hits at once; hits are batched in memory by `IpBlockChecker`.
+    fn add_hit_count(&self, id: i64, count: i64) -> Result<()>;
     fn list_active(&self) -> Result<Vec<IpBlockRecord>>;
     fn list_all(&self) -> Result<Vec<IpBlockRecord>>;
     fn get(&self, id: i64) -> Result<Option<IpBlockRecord>>;
@@ -156,6 +331,25 @@ pub trait TopicRepository {
     fn remove_thread_topic(&self, thread_id: &str, topic_id: &str) -> Result<()>;
     fn list_thread_topics(&self, thread_id: &str) -> Result<Vec<String>>;
     fn list_threads_for_topic(&self, topic_id: &str) -> Result<Vec<String>>;
+    /// Returns false if we aren't subscribed to the topic.
+    fn set_pow_difficulty(&self, topic_id: &str, difficulty: u32) -> Result<bool>;
+    /// `None` if unsubscribed or left at the default.
+    fn pow_difficulty(&self, topic_id: &str) -> Result<Option<u32>>;
+    /// `None` if we aren't subscribed to the topic.
+    fn settings(&self, topic_id: &str) -> Result<Option<TopicSettingsRecord>>;
+    /// Returns false if we aren't subscribed to the topic.
+    fn update_settings(&self, settings: &TopicSettingsRecord) -> Result<bool>;
+    fn metadata(&self, topic_id: &str) -> Result<Option<TopicMetadataRecord>>;
+    fn upsert_metadata(&self, record: &TopicMetadataRecord) -> Result<()>;
+}
+
+pub trait SeenMessageRepository {
+    /// Inserts or refreshes a gossip message ID (`seen_at` is unix seconds).
+    fn record(&self, msg_id: &str, seen_at: i64) -> Result<()>;
+    /// Newest first, at most `limit` rows seen at or after `since`.
+    fn list_since(&self, since: i64, limit: usize) -> Result<Vec<(String, i64)>>;
+    /// Deletes rows older than `before` and all but the newest `keep`; returns rows removed.
+    fn prune(&self, before: i64, keep: usize) -> Result<usize>;
 }
 
 /// Thin wrapper that will eventually host rusqlite-backed implementations.
@@ -212,6 +406,10 @@ impl<'conn> SqliteRepositories<'conn> {
         redacted_posts::SqliteRedactedPostRepository { conn: self.conn }
     }
 
+    pub fn quarantine(&self) -> impl QuarantineRepository + '_ {
+        quarantine::SqliteQuarantineRepository { conn: self.conn }
+    }
+
     pub fn search(&self) -> impl SearchRepository + '_ {
         search::SqliteSearchRepository { conn: self.conn }
     }
@@ -220,6 +418,26 @@ impl<'conn> SqliteRepositories<'conn> {
         peer_ips::SqlitePeerIpRepository { conn: self.conn }
     }
 
+    pub fn dm_outbox(&self) -> impl DmOutboxRepository + '_ {
+        dm_outbox::SqliteDmOutboxRepository { conn: self.conn }
+    }
+
+    pub fn dm_sessions(&self) -> impl DmSessionRepository + '_ {
+        dm_sessions::SqliteDmSessionRepository { conn: self.conn }
+    }
+
+    pub fn dm_groups(&self) -> impl DmGroupRepository + '_ {
+        dm_groups::SqliteDmGroupRepository { conn: self.conn }
+    }
+
+    pub fn dm_attachments(&self) -> impl DmAttachmentRepository + '_ {
+        dm_attachments::SqliteDmAttachmentRepository { conn: self.conn }
+    }
+
+    pub fn endpoint_paths(&self) -> impl EndpointPathRepository + '_ {
+        endpoint_paths::SqliteEndpointPathRepository { conn: self.conn }
+    }
+
     pub fn ip_blocks(&self) -> impl IpBlockRepository + '_ {
         ip_blocks::SqliteIpBlockRepository { conn: self.conn }
     }
@@ -228,6 +446,22 @@ impl<'conn> SqliteRepositories<'conn> {
         topics::SqliteTopicRepository { conn: self.conn }
     }
 
+    pub fn topic_addresses(&self) -> impl TopicAddressRepository + '_ {
+        topic_addresses::SqliteTopicAddressRepository { conn: self.conn }
+    }
+
+    pub fn live_events(&self) -> impl LiveEventRepository + '_ {
+        live_events::SqliteLiveEventRepository { conn: self.conn }
+    }
+
+    pub fn agent_keys(&self) -> impl AgentKeyRepository + '_ {
+        agent_keys::SqliteAgentKeyRepository { conn: self.conn }
+    }
+
+    pub fn seen_messages(&self) -> impl SeenMessageRepository + '_ {
+        seen_messages::SqliteSeenMessageRepository { conn: self.conn }
+    }
+
     pub fn import_post_map(&self) -> impl ImportPostMapRepository + '_ {
         import_post_map::SqliteImportPostMapRepository { conn: self.conn }
     }
@@ -270,7 +504,7 @@ mod tests {
             gpg_fingerprint: None,
             x25519_pubkey: None,
             last_seen: None,
-            trust_state: "unknown".into(),
+            trust_state: "stranger".into(),
             avatar_file_id: None,
         };
         repos.peers().upsert(&peer).unwrap();
@@ -324,7 +558,7 @@ mod tests {
             gpg_fingerprint: Some("fingerprint".into()),
             x25519_pubkey: None,
             last_seen: Some("2024-01-01T00:00:00Z".into()),
-            trust_state: "trusted".into(),
+            trust_state: "friend".into(),
             avatar_file_id: None,
         };
         repos.peers().upsert(&peer).unwrap();
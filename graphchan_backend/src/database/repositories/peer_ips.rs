@@ -1,17 +1,29 @@
 use crate::database::models::PeerIpRecord;
 use anyhow::Result;
-use rusqlite::{params, Connection, OptionalExtension};
+use rusqlite::{params, Connection, OptionalExtension, Row};
 
 pub(super) struct SqlitePeerIpRepository<'conn> {
     pub(super) conn: &'conn Connection,
 }
 
+const SELECT_COLUMNS: &str = "peer_id, ip_address, COALESCE(first_seen, last_seen), last_seen, source";
+
+fn map_row(row: &Row<'_>) -> rusqlite::Result<PeerIpRecord> {
+    Ok(PeerIpRecord {
+        peer_id: row.get(0)?,
+        ip_address: row.get(1)?,
+        first_seen: row.get(2)?,
+        last_seen: row.get(3)?,
+        source: row.get(4)?,
+    })
+}
+
 impl<'conn> super::PeerIpRepository for SqlitePeerIpRepository<'conn> {
     fn update(&self, peer_id: &str, ip_address: &str, last_seen: i64) -> Result<()> {
         self.conn.execute(
             r#"
-            INSERT INTO peer_ips (peer_id, ip_address, last_seen)
-            VALUES (?1, ?2, ?3)
+            INSERT INTO peer_ips (peer_id, ip_address, first_seen, last_seen, source)
+            VALUES (?1, ?2, ?3, ?3, 'friendcode')
             ON CONFLICT(peer_id, ip_address) DO UPDATE SET
                 last_seen = excluded.last_seen
             "#,
@@ -20,44 +32,38 @@ impl<'conn> super::PeerIpRepository for SqlitePeerIpRepository<'conn> {
         Ok(())
     }
 
-    fn get(&self, peer_id: &str) -> Result<Option<PeerIpRecord>> {
-        let result = self.conn.query_row(
+    fn record_direct(&self, peer_id: &str, ip_address: &str, seen_at: i64) -> Result<()> {
+        // An observed connection outranks an advertised address
+        self.conn.execute(
             r#"
-            SELECT peer_id, ip_address, last_seen
-            FROM peer_ips
-            WHERE peer_id = ?1
-            ORDER BY last_seen DESC
-            LIMIT 1
+            INSERT INTO peer_ips (peer_id, ip_address, first_seen, last_seen, source)
+            VALUES (?1, ?2, ?3, ?3, 'direct')
+            ON CONFLICT(peer_id, ip_address) DO UPDATE SET
+                last_seen = excluded.last_seen,
+                source = 'direct'
             "#,
+            params![peer_id, ip_address, seen_at],
+        )?;
+        Ok(())
+    }
+
+    fn get(&self, peer_id: &str) -> Result<Option<PeerIpRecord>> {
+        let result = self.conn.query_row(
+            &format!(
+                "SELECT {SELECT_COLUMNS} FROM peer_ips WHERE peer_id = ?1 ORDER BY last_seen DESC LIMIT 1"
+            ),
             params![peer_id],
-            |row| {
-                Ok(PeerIpRecord {
-                    peer_id: row.get(0)?,
-                    ip_address: row.get(1)?,
-                    last_seen: row.get(2)?,
-                })
-            },
+            map_row,
         ).optional()?;
         Ok(result)
     }
 
     fn get_by_ip(&self, ip_address: &str) -> Result<Vec<PeerIpRecord>> {
-        let mut stmt = self.conn.prepare(
-            r#"
-            SELECT peer_id, ip_address, last_seen
-            FROM peer_ips
-            WHERE ip_address = ?1
-            ORDER BY last_seen DESC
-            "#,
-        )?;
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {SELECT_COLUMNS} FROM peer_ips WHERE ip_address = ?1 ORDER BY last_seen DESC"
+        ))?;
 
-        let rows = stmt.query_map(params![ip_address], |row| {
-            Ok(PeerIpRecord {
-                peer_id: row.get(0)?,
-                ip_address: row.get(1)?,
-                last_seen: row.get(2)?,
-            })
-        })?;
+        let rows = stmt.query_map(params![ip_address], map_row)?;
 
         let mut records = Vec::new();
         for row in rows {
@@ -87,22 +93,26 @@ impl<'conn> super::PeerIpRepository for SqlitePeerIpRepository<'conn> {
         Ok(ips)
     }
 
+    fn list_for_peer(&self, peer_id: &str) -> Result<Vec<PeerIpRecord>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {SELECT_COLUMNS} FROM peer_ips WHERE peer_id = ?1 ORDER BY last_seen DESC"
+        ))?;
+
+        let rows = stmt.query_map(params![peer_id], map_row)?;
+
+        let mut records = Vec::new();
+        for row in rows {
+            records.push(row?);
+        }
+        Ok(records)
+    }
+
     fn list_all(&self) -> Result<Vec<PeerIpRecord>> {
-        let mut stmt = self.conn.prepare(
-            r#"
-            SELECT peer_id, ip_address, last_seen
-            FROM peer_ips
-            ORDER BY last_seen DESC
-            "#,
-        )?;
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {SELECT_COLUMNS} FROM peer_ips ORDER BY last_seen DESC"
+        ))?;
 
-        let rows = stmt.query_map([], |row| {
-            Ok(PeerIpRecord {
-                peer_id: row.get(0)?,
-                ip_address: row.get(1)?,
-                last_seen: row.get(2)?,
-            })
-        })?;
+        let rows = stmt.query_map([], map_row)?;
 
         let mut records = Vec::new();
         for row in rows {
@@ -10,8 +10,8 @@ impl<'conn> super::PeerRepository for SqlitePeerRepository<'conn> {
     fn upsert(&self, record: &PeerRecord) -> Result<()> {
         self.conn.execute(
             r#"
-            INSERT INTO peers (id, alias, friendcode, iroh_peer_id, gpg_fingerprint, x25519_pubkey, last_seen, trust_state, avatar_file_id, username, bio, agents)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+            INSERT INTO peers (id, alias, friendcode, iroh_peer_id, gpg_fingerprint, x25519_pubkey, last_seen, trust_state, avatar_file_id, username, bio, agents, gpg_public_key)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
             ON CONFLICT(id) DO UPDATE SET
                 alias = excluded.alias,
                 friendcode = excluded.friendcode,
@@ -19,7 +19,6 @@ impl<'conn> super::PeerRepository for SqlitePeerRepository<'conn> {
                 gpg_fingerprint = excluded.gpg_fingerprint,
                 x25519_pubkey = excluded.x25519_pubkey,
                 last_seen = excluded.last_seen,
-                trust_state = excluded.trust_state,
                 avatar_file_id = excluded.avatar_file_id,
                 username = excluded.username,
                 bio = excluded.bio,
@@ -37,7 +36,8 @@ impl<'conn> super::PeerRepository for SqlitePeerRepository<'conn> {
                 record.avatar_file_id,
                 record.username,
                 record.bio,
-                record.agents
+                record.agents,
+                record.gpg_public_key
             ],
         )?;
         Ok(())
@@ -48,7 +48,7 @@ impl<'conn> super::PeerRepository for SqlitePeerRepository<'conn> {
             .conn
             .query_row(
                 r#"
-                SELECT id, alias, friendcode, iroh_peer_id, gpg_fingerprint, x25519_pubkey, last_seen, trust_state, avatar_file_id, username, bio, agents
+                SELECT id, alias, friendcode, iroh_peer_id, gpg_fingerprint, x25519_pubkey, last_seen, trust_state, avatar_file_id, username, bio, agents, gpg_public_key
                 FROM peers
                 WHERE id = ?1
                 "#,
@@ -67,6 +67,7 @@ impl<'conn> super::PeerRepository for SqlitePeerRepository<'conn> {
                         username: row.get(9)?,
                         bio: row.get(10)?,
                         agents: row.get(11)?,
+                        gpg_public_key: row.get(12)?,
                     })
                 },
             )
@@ -77,7 +78,7 @@ impl<'conn> super::PeerRepository for SqlitePeerRepository<'conn> {
     fn list(&self) -> Result<Vec<PeerRecord>> {
         let mut stmt = self.conn.prepare(
             r#"
-            SELECT id, alias, friendcode, iroh_peer_id, gpg_fingerprint, x25519_pubkey, last_seen, trust_state, avatar_file_id, username, bio, agents
+            SELECT id, alias, friendcode, iroh_peer_id, gpg_fingerprint, x25519_pubkey, last_seen, trust_state, avatar_file_id, username, bio, agents, gpg_public_key
             FROM peers
             ORDER BY datetime(COALESCE(last_seen, '1970-01-01T00:00:00Z')) DESC
             "#,
@@ -96,6 +97,7 @@ impl<'conn> super::PeerRepository for SqlitePeerRepository<'conn> {
                 username: row.get(9)?,
                 bio: row.get(10)?,
                 agents: row.get(11)?,
+                gpg_public_key: row.get(12)?,
             })
         })?;
         let mut peers = Vec::new();
@@ -105,6 +107,22 @@ impl<'conn> super::PeerRepository for SqlitePeerRepository<'conn> {
         Ok(peers)
     }
 
+    fn set_gpg_public_key(&self, id: &str, public_key_armored: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE peers SET gpg_public_key = ?2 WHERE id = ?1",
+            params![id, public_key_armored],
+        )?;
+        Ok(())
+    }
+
+    fn set_trust_state(&self, id: &str, trust_state: &str) -> Result<bool> {
+        let updated = self.conn.execute(
+            "UPDATE peers SET trust_state = ?2 WHERE id = ?1",
+            params![id, trust_state],
+        )?;
+        Ok(updated > 0)
+    }
+
     fn delete(&self, id: &str) -> Result<()> {
         self.conn.execute(
             "DELETE FROM peers WHERE id = ?1",
@@ -112,4 +130,16 @@ impl<'conn> super::PeerRepository for SqlitePeerRepository<'conn> {
         )?;
         Ok(())
     }
+
+    fn id_for_iroh_peer(&self, iroh_peer_id: &str) -> Result<Option<String>> {
+        let id = self
+            .conn
+            .query_row(
+                "SELECT id FROM peers WHERE iroh_peer_id = ?1 LIMIT 1",
+                params![iroh_peer_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(id)
+    }
 }
@@ -1,4 +1,4 @@
-use crate::database::models::PostRecord;
+use crate::database::models::{PostRecord, PostRevisionRecord};
 use anyhow::Result;
 use rusqlite::{params, Connection, OptionalExtension};
 
@@ -10,8 +10,8 @@ impl<'conn> super::PostRepository for SqlitePostRepository<'conn> {
     fn create(&self, record: &PostRecord) -> Result<()> {
         self.conn.execute(
             r#"
-            INSERT INTO posts (id, thread_id, author_peer_id, author_friendcode, body, created_at, updated_at, metadata)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            INSERT INTO posts (id, thread_id, author_peer_id, author_friendcode, body, created_at, updated_at, metadata, signature, signature_status)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
             "#,
             params![
                 record.id,
@@ -21,7 +21,9 @@ impl<'conn> super::PostRepository for SqlitePostRepository<'conn> {
                 record.body,
                 record.created_at,
                 record.updated_at,
-                record.metadata
+                record.metadata,
+                record.signature,
+                record.signature_status
             ],
         )?;
         Ok(())
@@ -30,8 +32,8 @@ impl<'conn> super::PostRepository for SqlitePostRepository<'conn> {
     fn upsert(&self, record: &PostRecord) -> Result<()> {
         self.conn.execute(
             r#"
-            INSERT INTO posts (id, thread_id, author_peer_id, author_friendcode, body, created_at, updated_at, metadata)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            INSERT INTO posts (id, thread_id, author_peer_id, author_friendcode, body, created_at, updated_at, metadata, signature, signature_status)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
             ON CONFLICT(id) DO UPDATE SET
                 thread_id = excluded.thread_id,
                 author_peer_id = excluded.author_peer_id,
@@ -49,7 +51,9 @@ impl<'conn> super::PostRepository for SqlitePostRepository<'conn> {
                 record.body,
                 record.created_at,
                 record.updated_at,
-                record.metadata
+                record.metadata,
+                record.signature,
+                record.signature_status
             ],
         )?;
         Ok(())
@@ -60,7 +64,7 @@ impl<'conn> super::PostRepository for SqlitePostRepository<'conn> {
             .conn
             .query_row(
                 r#"
-                SELECT id, thread_id, author_peer_id, author_friendcode, body, created_at, updated_at, metadata
+                SELECT id, thread_id, author_peer_id, author_friendcode, body, created_at, updated_at, metadata, signature, signature_status
                 FROM posts
                 WHERE id = ?1
                 "#,
@@ -75,6 +79,8 @@ impl<'conn> super::PostRepository for SqlitePostRepository<'conn> {
                         created_at: row.get(5)?,
                         updated_at: row.get(6)?,
                         metadata: row.get(7)?,
+                        signature: row.get(8)?,
+                        signature_status: row.get(9)?,
                     })
                 },
             )
@@ -84,7 +90,7 @@ impl<'conn> super::PostRepository for SqlitePostRepository<'conn> {
     fn list_for_thread(&self, thread_id: &str) -> Result<Vec<PostRecord>> {
         let mut stmt = self.conn.prepare(
             r#"
-            SELECT id, thread_id, author_peer_id, author_friendcode, body, created_at, updated_at, metadata
+            SELECT id, thread_id, author_peer_id, author_friendcode, body, created_at, updated_at, metadata, signature, signature_status
             FROM posts
             WHERE thread_id = ?1
             ORDER BY datetime(created_at) ASC
@@ -100,6 +106,8 @@ impl<'conn> super::PostRepository for SqlitePostRepository<'conn> {
                 created_at: row.get(5)?,
                 updated_at: row.get(6)?,
                 metadata: row.get(7)?,
+                signature: row.get(8)?,
+                signature_status: row.get(9)?,
             })
         })?;
         let mut posts = Vec::new();
@@ -112,7 +120,7 @@ impl<'conn> super::PostRepository for SqlitePostRepository<'conn> {
     fn list_recent(&self, limit: usize) -> Result<Vec<PostRecord>> {
         let mut stmt = self.conn.prepare(
             r#"
-            SELECT p.id, p.thread_id, p.author_peer_id, p.author_friendcode, p.body, p.created_at, p.updated_at, p.metadata
+            SELECT p.id, p.thread_id, p.author_peer_id, p.author_friendcode, p.body, p.created_at, p.updated_at, p.metadata, p.signature, p.signature_status
             FROM posts p
             INNER JOIN threads t ON p.thread_id = t.id
             WHERE t.sync_status = 'downloaded'
@@ -130,6 +138,8 @@ impl<'conn> super::PostRepository for SqlitePostRepository<'conn> {
                 created_at: row.get(5)?,
                 updated_at: row.get(6)?,
                 metadata: row.get(7)?,
+                signature: row.get(8)?,
+                signature_status: row.get(9)?,
             })
         })?;
         let mut posts = Vec::new();
@@ -176,6 +186,23 @@ impl<'conn> super::PostRepository for SqlitePostRepository<'conn> {
         Ok(parents)
     }
 
+    fn children_of(&self, parent_id: &str) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT child_id
+            FROM post_relationships
+            WHERE parent_id = ?1
+            ORDER BY child_id ASC
+            "#,
+        )?;
+        let rows = stmt.query_map(params![parent_id], |row| row.get::<_, String>(0))?;
+        let mut children = Vec::new();
+        for row in rows {
+            children.push(row?);
+        }
+        Ok(children)
+    }
+
     fn has_children(&self, post_id: &str) -> Result<bool> {
         let count: i64 = self.conn.query_row(
             r#"
@@ -188,4 +215,102 @@ impl<'conn> super::PostRepository for SqlitePostRepository<'conn> {
         )?;
         Ok(count > 0)
     }
+
+    fn apply_edit(
+        &self,
+        post_id: &str,
+        body: &str,
+        updated_at: &str,
+        signature: Option<&str>,
+        signature_status: &str,
+    ) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        let inserted = tx.execute(
+            r#"
+            INSERT INTO post_revisions (post_id, body, authored_at, replaced_at)
+            SELECT id, body, COALESCE(updated_at, created_at), ?2
+            FROM posts
+            WHERE id = ?1
+            "#,
+            params![post_id, updated_at],
+        )?;
+        if inserted == 0 {
+            anyhow::bail!("post not found: {post_id}");
+        }
+        tx.execute(
+            r#"
+            UPDATE posts
+            SET body = ?2, updated_at = ?3, signature = ?4, signature_status = ?5
+            WHERE id = ?1
+            "#,
+            params![post_id, body, updated_at, signature, signature_status],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn tombstone(
+        &self,
+        post_id: &str,
+        deleted_at: &str,
+        signature: Option<&str>,
+        signature_status: &str,
+    ) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        let updated = tx.execute(
+            r#"
+            UPDATE posts
+            SET body = '', updated_at = ?2, signature = ?3, signature_status = ?4
+            WHERE id = ?1
+            "#,
+            params![post_id, deleted_at, signature, signature_status],
+        )?;
+        if updated == 0 {
+            anyhow::bail!("post not found: {post_id}");
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO post_tombstones (post_id, deleted_at) VALUES (?1, ?2)",
+            params![post_id, deleted_at],
+        )?;
+        tx.execute("DELETE FROM post_revisions WHERE post_id = ?1", params![post_id])?;
+        tx.execute("DELETE FROM files WHERE post_id = ?1", params![post_id])?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn is_tombstoned(&self, post_id: &str) -> Result<bool> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT 1 FROM post_tombstones WHERE post_id = ?1",
+                params![post_id],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some())
+    }
+
+    fn list_revisions(&self, post_id: &str) -> Result<Vec<PostRevisionRecord>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT post_id, body, authored_at, replaced_at
+            FROM post_revisions
+            WHERE post_id = ?1
+            ORDER BY id ASC
+            "#,
+        )?;
+        let rows = stmt.query_map(params![post_id], |row| {
+            Ok(PostRevisionRecord {
+                post_id: row.get(0)?,
+                body: row.get(1)?,
+                authored_at: row.get(2)?,
+                replaced_at: row.get(3)?,
+            })
+        })?;
+        let mut revisions = Vec::new();
+        for row in rows {
+            revisions.push(row?);
+        }
+        Ok(revisions)
+    }
 }
@@ -0,0 +1,94 @@
+use crate::database::models::QuarantinedPostRecord;
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+pub(super) struct SqliteQuarantineRepository<'conn> {
+    pub(super) conn: &'conn Connection,
+}
+
+const SELECT_COLUMNS: &str = "post_id, thread_id, author_peer_id, status, quarantined_at";
+
+fn map_row(row: &Row<'_>) -> rusqlite::Result<QuarantinedPostRecord> {
+    Ok(QuarantinedPostRecord {
+        post_id: row.get(0)?,
+        thread_id: row.get(1)?,
+        author_peer_id: row.get(2)?,
+        status: row.get(3)?,
+        quarantined_at: row.get(4)?,
+    })
+}
+
+impl<'conn> super::QuarantineRepository for SqliteQuarantineRepository<'conn> {
+    fn add(&self, record: &QuarantinedPostRecord) -> Result<bool> {
+        let inserted = self.conn.execute(
+            r#"
+            INSERT OR IGNORE INTO quarantined_posts (post_id, thread_id, author_peer_id, status, quarantined_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            "#,
+            params![
+                record.post_id,
+                record.thread_id,
+                record.author_peer_id,
+                record.status,
+                record.quarantined_at,
+            ],
+        )?;
+        Ok(inserted > 0)
+    }
+
+    fn get(&self, post_id: &str) -> Result<Option<QuarantinedPostRecord>> {
+        let record = self
+            .conn
+            .query_row(
+                &format!("SELECT {SELECT_COLUMNS} FROM quarantined_posts WHERE post_id = ?1"),
+                params![post_id],
+                map_row,
+            )
+            .optional()?;
+        Ok(record)
+    }
+
+    fn list(&self, status: Option<&str>) -> Result<Vec<QuarantinedPostRecord>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {SELECT_COLUMNS} FROM quarantined_posts WHERE ?1 IS NULL OR status = ?1 ORDER BY quarantined_at, post_id"
+        ))?;
+        let rows = stmt
+            .query_map(params![status], map_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    fn list_for_thread(&self, thread_id: &str) -> Result<Vec<QuarantinedPostRecord>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {SELECT_COLUMNS} FROM quarantined_posts WHERE thread_id = ?1 ORDER BY quarantined_at, post_id"
+        ))?;
+        let rows = stmt
+            .query_map(params![thread_id], map_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    fn set_status(&self, post_id: &str, status: &str) -> Result<bool> {
+        let updated = self.conn.execute(
+            "UPDATE quarantined_posts SET status = ?2 WHERE post_id = ?1",
+            params![post_id, status],
+        )?;
+        Ok(updated > 0)
+    }
+
+    fn release(&self, post_id: &str) -> Result<bool> {
+        let removed = self.conn.execute(
+            "DELETE FROM quarantined_posts WHERE post_id = ?1",
+            params![post_id],
+        )?;
+        Ok(removed > 0)
+    }
+
+    fn release_author(&self, author_peer_id: &str) -> Result<usize> {
+        let removed = self.conn.execute(
+            "DELETE FROM quarantined_posts WHERE author_peer_id = ?1 AND status = 'pending'",
+            params![author_peer_id],
+        )?;
+        Ok(removed)
+    }
+}
@@ -1,6 +1,6 @@
 use crate::database::models::ReactionRecord;
 use anyhow::Result;
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, OptionalExtension};
 use std::collections::HashMap;
 
 pub(super) struct SqliteReactionRepository<'conn> {
@@ -39,6 +39,43 @@ impl<'conn> super::ReactionRepository for SqliteReactionRepository<'conn> {
         Ok(())
     }
 
+    fn record_removal(&self, removal: &ReactionRecord) -> Result<()> {
+        self.conn.execute(
+            r#"
+            INSERT INTO reaction_removals (post_id, reactor_peer_id, emoji, signature, removed_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            ON CONFLICT(post_id, reactor_peer_id, emoji) DO UPDATE SET
+                signature = excluded.signature,
+                removed_at = excluded.removed_at
+            WHERE excluded.removed_at > reaction_removals.removed_at
+            "#,
+            params![
+                removal.post_id,
+                removal.reactor_peer_id,
+                removal.emoji,
+                removal.signature,
+                removal.created_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn removed_at(&self, post_id: &str, reactor_peer_id: &str, emoji: &str) -> Result<Option<String>> {
+        let removed_at = self
+            .conn
+            .query_row(
+                r#"
+                SELECT removed_at
+                FROM reaction_removals
+                WHERE post_id = ?1 AND reactor_peer_id = ?2 AND emoji = ?3
+                "#,
+                params![post_id, reactor_peer_id, emoji],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(removed_at)
+    }
+
     fn list_for_post(&self, post_id: &str) -> Result<Vec<ReactionRecord>> {
         let mut stmt = self.conn.prepare(
             r#"
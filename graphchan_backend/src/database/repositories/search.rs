@@ -20,7 +20,8 @@ impl<'conn> super::SearchRepository for SqliteSearchRepository<'conn> {
                 p.id, p.thread_id, p.author_peer_id, p.author_friendcode, p.body, p.created_at, p.updated_at, p.metadata,
                 bm25(posts_fts) as score,
                 t.title,
-                snippet(posts_fts, -1, '<mark>', '</mark>', '...', 30) as snippet
+                snippet(posts_fts, -1, '<mark>', '</mark>', '...', 30) as snippet,
+                p.signature, p.signature_status
             FROM posts_fts
             JOIN posts p ON posts_fts.id = p.id
             JOIN threads t ON p.thread_id = t.id
@@ -41,6 +42,8 @@ impl<'conn> super::SearchRepository for SqliteSearchRepository<'conn> {
                     created_at: row.get(5)?,
                     updated_at: row.get(6)?,
                     metadata: row.get(7)?,
+                    signature: row.get(11)?,
+                    signature_status: row.get(12)?,
                 },
                 file: None,
                 bm25_score: row.get(8)?,
@@ -60,7 +63,8 @@ impl<'conn> super::SearchRepository for SqliteSearchRepository<'conn> {
                 f.id, f.post_id, f.path, f.original_name, f.mime, f.size_bytes, f.blob_id, f.checksum, f.ticket, f.download_status,
                 bm25(files_fts) as score,
                 t.title,
-                snippet(files_fts, -1, '<mark>', '</mark>', '...', 30) as snippet
+                snippet(files_fts, -1, '<mark>', '</mark>', '...', 30) as snippet,
+                p.signature, p.signature_status
             FROM files_fts
             JOIN files f ON files_fts.id = f.id
             JOIN posts p ON f.post_id = p.id
@@ -82,6 +86,8 @@ impl<'conn> super::SearchRepository for SqliteSearchRepository<'conn> {
                     created_at: row.get(5)?,
                     updated_at: row.get(6)?,
                     metadata: row.get(7)?,
+                    signature: row.get(21)?,
+                    signature_status: row.get(22)?,
                 },
                 file: Some(FileRecord {
                     id: row.get(8)?,
@@ -94,6 +100,7 @@ impl<'conn> super::SearchRepository for SqliteSearchRepository<'conn> {
                     checksum: row.get(15)?,
                     ticket: row.get(16)?,
                     download_status: row.get(17)?,
+                    encryption_key: None,
                 }),
                 bm25_score: row.get(18)?,
                 thread_title: row.get(19)?,
@@ -0,0 +1,54 @@
+use anyhow::Result;
+use rusqlite::{params, Connection};
+
+pub(super) struct SqliteSeenMessageRepository<'conn> {
+    pub(super) conn: &'conn Connection,
+}
+
+impl<'conn> super::SeenMessageRepository for SqliteSeenMessageRepository<'conn> {
+    fn record(&self, msg_id: &str, seen_at: i64) -> Result<()> {
+        self.conn.execute(
+            r#"
+            INSERT INTO seen_messages (msg_id, seen_at)
+            VALUES (?1, ?2)
+            ON CONFLICT(msg_id) DO UPDATE SET
+                seen_at = excluded.seen_at
+            "#,
+            params![msg_id, seen_at],
+        )?;
+        Ok(())
+    }
+
+    fn list_since(&self, since: i64, limit: usize) -> Result<Vec<(String, i64)>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT msg_id, seen_at
+            FROM seen_messages
+            WHERE seen_at >= ?1
+            ORDER BY seen_at DESC
+            LIMIT ?2
+            "#,
+        )?;
+        let rows = stmt
+            .query_map(params![since, limit as i64], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<(String, i64)>, _>>()?;
+        Ok(rows)
+    }
+
+    fn prune(&self, before: i64, keep: usize) -> Result<usize> {
+        let expired = self.conn.execute(
+            "DELETE FROM seen_messages WHERE seen_at < ?1",
+            params![before],
+        )?;
+        let overflow = self.conn.execute(
+            r#"
+            DELETE FROM seen_messages
+            WHERE msg_id NOT IN (
+                SELECT msg_id FROM seen_messages ORDER BY seen_at DESC LIMIT ?1
+            )
+            "#,
+            params![keep as i64],
+        )?;
+        Ok(expired + overflow)
+    }
+}
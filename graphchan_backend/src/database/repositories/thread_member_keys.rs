@@ -1,6 +1,6 @@
 use crate::database::models::ThreadMemberKey;
 use anyhow::Result;
-use rusqlite::{params, Connection, OptionalExtension};
+use rusqlite::{params, Connection, OptionalExtension, Row};
 
 pub(super) struct SqliteThreadMemberKeyRepository<'conn> {
     pub(super) conn: &'conn Connection,
@@ -10,14 +10,16 @@ impl<'conn> super::ThreadMemberKeyRepository for SqliteThreadMemberKeyRepository
     fn add(&self, record: &ThreadMemberKey) -> Result<()> {
         self.conn.execute(
             r#"
-            INSERT OR REPLACE INTO thread_member_keys (thread_id, member_peer_id, wrapped_key_ciphertext, wrapped_key_nonce)
-            VALUES (?1, ?2, ?3, ?4)
+            INSERT OR REPLACE INTO thread_member_keys (thread_id, member_peer_id, wrapped_key_ciphertext, wrapped_key_nonce, key_epoch, wrapper_x25519_pubkey)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
             "#,
             params![
                 record.thread_id,
                 record.member_peer_id,
                 record.wrapped_key_ciphertext,
-                record.wrapped_key_nonce
+                record.wrapped_key_nonce,
+                record.key_epoch,
+                record.wrapper_x25519_pubkey
             ],
         )?;
         Ok(())
@@ -26,19 +28,12 @@ impl<'conn> super::ThreadMemberKeyRepository for SqliteThreadMemberKeyRepository
     fn get(&self, thread_id: &str, member_peer_id: &str) -> Result<Option<ThreadMemberKey>> {
         let result = self.conn.query_row(
             r#"
-            SELECT thread_id, member_peer_id, wrapped_key_ciphertext, wrapped_key_nonce
+            SELECT thread_id, member_peer_id, wrapped_key_ciphertext, wrapped_key_nonce, key_epoch, wrapper_x25519_pubkey
             FROM thread_member_keys
             WHERE thread_id = ?1 AND member_peer_id = ?2
             "#,
             params![thread_id, member_peer_id],
-            |row| {
-                Ok(ThreadMemberKey {
-                    thread_id: row.get(0)?,
-                    member_peer_id: row.get(1)?,
-                    wrapped_key_ciphertext: row.get(2)?,
-                    wrapped_key_nonce: row.get(3)?,
-                })
-            },
+            map_member_key,
         ).optional()?;
         Ok(result)
     }
@@ -46,20 +41,14 @@ impl<'conn> super::ThreadMemberKeyRepository for SqliteThreadMemberKeyRepository
     fn list_for_thread(&self, thread_id: &str) -> Result<Vec<ThreadMemberKey>> {
         let mut stmt = self.conn.prepare(
             r#"
-            SELECT thread_id, member_peer_id, wrapped_key_ciphertext, wrapped_key_nonce
+            SELECT thread_id, member_peer_id, wrapped_key_ciphertext, wrapped_key_nonce, key_epoch, wrapper_x25519_pubkey
             FROM thread_member_keys
             WHERE thread_id = ?1
+            ORDER BY member_peer_id ASC
             "#,
         )?;
 
-        let rows = stmt.query_map(params![thread_id], |row| {
-            Ok(ThreadMemberKey {
-                thread_id: row.get(0)?,
-                member_peer_id: row.get(1)?,
-                wrapped_key_ciphertext: row.get(2)?,
-                wrapped_key_nonce: row.get(3)?,
-            })
-        })?;
+        let rows = stmt.query_map(params![thread_id], map_member_key)?;
 
         let mut keys = Vec::new();
         for row in rows {
@@ -78,4 +67,65 @@ impl<'conn> super::ThreadMemberKeyRepository for SqliteThreadMemberKeyRepository
         )?;
         Ok(())
     }
+
+    fn replace_all(&self, thread_id: &str, records: &[ThreadMemberKey]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "DELETE FROM thread_member_keys WHERE thread_id = ?1",
+            params![thread_id],
+        )?;
+        for record in records {
+            tx.execute(
+                r#"
+                INSERT INTO thread_member_keys (thread_id, member_peer_id, wrapped_key_ciphertext, wrapped_key_nonce, key_epoch, wrapper_x25519_pubkey)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                "#,
+                params![
+                    record.thread_id,
+                    record.member_peer_id,
+                    record.wrapped_key_ciphertext,
+                    record.wrapped_key_nonce,
+                    record.key_epoch,
+                    record.wrapper_x25519_pubkey
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn save_signed_grant(&self, thread_id: &str, key_epoch: i64, grant_json: &str) -> Result<()> {
+        self.conn.execute(
+            r#"
+            INSERT INTO thread_key_grants (thread_id, key_epoch, grant_json)
+            VALUES (?1, ?2, ?3)
+            ON CONFLICT(thread_id) DO UPDATE SET
+                key_epoch = excluded.key_epoch,
+                grant_json = excluded.grant_json
+            WHERE excluded.key_epoch >= thread_key_grants.key_epoch
+            "#,
+            params![thread_id, key_epoch, grant_json],
+        )?;
+        Ok(())
+    }
+
+    fn signed_grant(&self, thread_id: &str) -> Result<Option<String>> {
+        let result = self.conn.query_row(
+            "SELECT grant_json FROM thread_key_grants WHERE thread_id = ?1",
+            params![thread_id],
+            |row| row.get(0),
+        ).optional()?;
+        Ok(result)
+    }
+}
+
+fn map_member_key(row: &Row<'_>) -> rusqlite::Result<ThreadMemberKey> {
+    Ok(ThreadMemberKey {
+        thread_id: row.get(0)?,
+        member_peer_id: row.get(1)?,
+        wrapped_key_ciphertext: row.get(2)?,
+        wrapped_key_nonce: row.get(3)?,
+        key_epoch: row.get(4)?,
+        wrapper_x25519_pubkey: row.get(5)?,
+    })
 }
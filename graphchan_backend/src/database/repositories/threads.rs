@@ -213,4 +213,28 @@ impl<'conn> super::ThreadRepository for SqliteThreadRepository<'conn> {
         )?;
         Ok(())
     }
+
+    fn set_pow_stamp(&self, thread_id: &str, difficulty: u32, nonce: u64) -> Result<()> {
+        // Nonces are searched upwards from zero, so they always fit in an i64
+        self.conn.execute(
+            "UPDATE threads SET pow_difficulty = ?2, pow_nonce = ?3 WHERE id = ?1",
+            params![thread_id, difficulty, nonce as i64],
+        )?;
+        Ok(())
+    }
+
+    fn pow_stamp(&self, thread_id: &str) -> Result<Option<(u32, u64)>> {
+        let stamp = self
+            .conn
+            .query_row(
+                "SELECT pow_difficulty, pow_nonce FROM threads WHERE id = ?1",
+                params![thread_id],
+                |row| Ok((row.get::<_, Option<u32>>(0)?, row.get::<_, Option<i64>>(1)?)),
+            )
+            .optional()?;
+        Ok(match stamp {
+            Some((Some(difficulty), Some(nonce))) => Some((difficulty, nonce as u64)),
+            _ => None,
+        })
+    }
 }
@@ -0,0 +1,104 @@
+use crate::database::models::TopicPeerAddressRecord;
+use anyhow::Result;
+use rusqlite::{params, Connection};
+
+pub(super) struct SqliteTopicAddressRepository<'conn> {
+    pub(super) conn: &'conn Connection,
+}
+
+/// Best entries first: connected most recently, then seen most recently.
+const BEST_FIRST: &str = "last_success IS NULL, last_success DESC, last_seen DESC";
+
+impl<'conn> super::TopicAddressRepository for SqliteTopicAddressRepository<'conn> {
+    fn record(
+        &self,
+        topic_id: &str,
+        endpoint_id: &str,
+        relay_url: Option<&str>,
+        direct_addrs: Option<&str>,
+        seen_at: i64,
+        success: bool,
+    ) -> Result<()> {
+        let last_success = success.then_some(seen_at);
+        self.conn.execute(
+            r#"
+            INSERT INTO topic_peer_addresses
+                (topic_id, endpoint_id, relay_url, direct_addrs, first_seen, last_seen, last_success)
+            VALUES (?1, ?2, ?3, COALESCE(?4, '[]'), ?5, ?5, ?6)
+            ON CONFLICT(topic_id, endpoint_id) DO UPDATE SET
+                relay_url = COALESCE(excluded.relay_url, relay_url),
+                direct_addrs = COALESCE(?4, direct_addrs),
+                last_seen = excluded.last_seen,
+                last_success = COALESCE(excluded.last_success, last_success)
+            "#,
+            params![topic_id, endpoint_id, relay_url, direct_addrs, seen_at, last_success],
+        )?;
+        Ok(())
+    }
+
+    fn list_for_topic(&self, topic_id: &str, limit: usize) -> Result<Vec<TopicPeerAddressRecord>> {
+        let mut stmt = self.conn.prepare(&format!(
+            r#"
+            SELECT topic_id, endpoint_id, relay_url, direct_addrs, first_seen, last_seen, last_success
+            FROM topic_peer_addresses
+            WHERE topic_id = ?1
+            ORDER BY {BEST_FIRST}
+            LIMIT ?2
+            "#
+        ))?;
+        let rows = stmt.query_map(params![topic_id, limit as i64], |row| {
+            Ok(TopicPeerAddressRecord {
+                topic_id: row.get(0)?,
+                endpoint_id: row.get(1)?,
+                relay_url: row.get(2)?,
+                direct_addrs: row.get(3)?,
+                first_seen: row.get(4)?,
+                last_seen: row.get(5)?,
+                last_success: row.get(6)?,
+            })
+        })?;
+        let mut records = Vec::new();
+        for row in rows {
+            records.push(row?);
+        }
+        Ok(records)
+    }
+
+    fn expire(&self, topic_id: &str, unconfirmed_before: i64, confirmed_before: i64) -> Result<usize> {
+        let removed = self.conn.execute(
+            r#"
+            DELETE FROM topic_peer_addresses
+            WHERE topic_id = ?1
+              AND ((last_success IS NULL AND last_seen < ?2) OR last_success < ?3)
+            "#,
+            params![topic_id, unconfirmed_before, confirmed_before],
+        )?;
+        Ok(removed)
+    }
+
+    fn evict_beyond(&self, topic_id: &str, keep: usize) -> Result<usize> {
+        let removed = self.conn.execute(
+            &format!(
+                r#"
+                DELETE FROM topic_peer_addresses
+                WHERE topic_id = ?1 AND endpoint_id NOT IN (
+                    SELECT endpoint_id FROM topic_peer_addresses
+                    WHERE topic_id = ?1
+                    ORDER BY {BEST_FIRST}
+                    LIMIT ?2
+                )
+                "#
+            ),
+            params![topic_id, keep as i64],
+        )?;
+        Ok(removed)
+    }
+
+    fn clear_topic(&self, topic_id: &str) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM topic_peer_addresses WHERE topic_id = ?1",
+            params![topic_id],
+        )?;
+        Ok(())
+    }
+}
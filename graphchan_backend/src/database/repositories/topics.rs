@@ -1,5 +1,6 @@
+use crate::database::models::{TopicMetadataRecord, TopicSettingsRecord};
 use anyhow::Result;
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, OptionalExtension};
 
 pub(super) struct SqliteTopicRepository<'conn> {
     pub(super) conn: &'conn Connection,
@@ -74,4 +75,98 @@ impl<'conn> super::TopicRepository for SqliteTopicRepository<'conn> {
             .collect::<Result<Vec<String>, _>>()?;
         Ok(threads)
     }
+
+    fn set_pow_difficulty(&self, topic_id: &str, difficulty: u32) -> Result<bool> {
+        let updated = self.conn.execute(
+            "UPDATE user_topics SET pow_difficulty = ?2 WHERE topic_id = ?1",
+            params![topic_id, difficulty],
+        )?;
+        Ok(updated > 0)
+    }
+
+    fn pow_difficulty(&self, topic_id: &str) -> Result<Option<u32>> {
+        let difficulty = self
+            .conn
+            .query_row(
+                "SELECT pow_difficulty FROM user_topics WHERE topic_id = ?1",
+                params![topic_id],
+                |row| row.get::<_, Option<u32>>(0),
+            )
+            .optional()?;
+        Ok(difficulty.flatten())
+    }
+
+    fn settings(&self, topic_id: &str) -> Result<Option<TopicSettingsRecord>> {
+        let settings = self
+            .conn
+            .query_row(
+                "SELECT topic_id, auto_download, mute_strangers, notification_level FROM user_topics WHERE topic_id = ?1",
+                params![topic_id],
+                |row| {
+                    Ok(TopicSettingsRecord {
+                        topic_id: row.get(0)?,
+                        auto_download: row.get::<_, i64>(1)? != 0,
+                        mute_strangers: row.get::<_, i64>(2)? != 0,
+                        notification_level: row.get(3)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(settings)
+    }
+
+    fn update_settings(&self, settings: &TopicSettingsRecord) -> Result<bool> {
+        let updated = self.conn.execute(
+            "UPDATE user_topics SET auto_download = ?2, mute_strangers = ?3, notification_level = ?4 WHERE topic_id = ?1",
+            params![
+                settings.topic_id,
+                settings.auto_download as i64,
+                settings.mute_strangers as i64,
+                settings.notification_level,
+            ],
+        )?;
+        Ok(updated > 0)
+    }
+
+    fn metadata(&self, topic_id: &str) -> Result<Option<TopicMetadataRecord>> {
+        let record = self
+            .conn
+            .query_row(
+                "SELECT topic_id, founder_peer_id, version, record_json, received_at FROM topic_metadata WHERE topic_id = ?1",
+                params![topic_id],
+                |row| {
+                    Ok(TopicMetadataRecord {
+                        topic_id: row.get(0)?,
+                        founder_peer_id: row.get(1)?,
+                        version: row.get(2)?,
+                        record_json: row.get(3)?,
+                        received_at: row.get(4)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(record)
+    }
+
+    fn upsert_metadata(&self, record: &TopicMetadataRecord) -> Result<()> {
+        self.conn.execute(
+            r#"
+            INSERT INTO topic_metadata (topic_id, founder_peer_id, version, record_json, received_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            ON CONFLICT(topic_id) DO UPDATE SET
+                founder_peer_id = excluded.founder_peer_id,
+                version = excluded.version,
+                record_json = excluded.record_json,
+                received_at = excluded.received_at
+            "#,
+            params![
+                record.topic_id,
+                record.founder_peer_id,
+                record.version,
+                record.record_json,
+                record.received_at,
+            ],
+        )?;
+        Ok(())
+    }
 }
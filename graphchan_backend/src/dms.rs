@@ -1,8 +1,19 @@
 use crate::config::GraphchanPaths;
-use crate::crypto::{decrypt_dm, encrypt_dm, load_x25519_secret};
-use crate::database::models::{ConversationRecord, DirectMessageRecord};
-use crate::database::repositories::{ConversationRepository, DirectMessageRepository, PeerRepository};
+use crate::crypto::{
+    associated_data, decrypt_dm, encrypt_dm, ensure_dm_prekey, load_dm_prekey, load_public_key_armored,
+    load_x25519_secret, sign_detached, RatchetHeader, RatchetSession, X25519Identity,
+};
+use crate::database::models::{
+    ConversationRecord, DirectMessageRecord, DmAttachmentRecord, DmGroupRecord, DmSessionRecord,
+};
+use crate::database::repositories::{
+    ConversationRepository, DirectMessageRepository, DmAttachmentRepository, DmGroupRepository, DmOutboxRepository,
+    DmSessionRepository, PeerRepository, SqliteRepositories,
+};
 use crate::database::Database;
+use crate::errors::{forbidden, invalid, not_found};
+use crate::network::{DirectMessageEvent, DmPrekeyBundle, DmReceiptEvent};
+use crate::network::outbox::{OUTBOX_FAILED, OUTBOX_QUEUED, OUTBOX_SENT};
 use crate::utils::now_utc_iso;
 use anyhow::{anyhow, Context, Result};
 use base64::prelude::*;
@@ -10,6 +21,64 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use x25519_dalek::PublicKey;
 
+/// Ratchet sessions kept per conversation; older ones only serve late messages.
+const DM_SESSIONS_KEPT: usize = 4;
+/// Largest group DM, ourselves included; every message is encrypted once per member.
+pub const MAX_GROUP_MEMBERS: usize = 32;
+
+/// Most files one message may carry.
+pub const MAX_DM_ATTACHMENTS: usize = 10;
+
+/// Plaintext of a one-to-one DM with `DirectMessageEvent::structured` set.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct DmContent {
+    body: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    reply_to: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    attachments: Vec<DmAttachment>,
+}
+
+/// What a recipient needs to fetch and decrypt one attachment.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct DmAttachment {
+    id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mime: Option<String>,
+    size_bytes: i64,
+    /// Hash of the encrypted blob
+    blob_id: String,
+    ticket: String,
+    /// Base64 key the blob is sealed with (see `crypto::encrypt_thread_blob`)
+    key: String,
+}
+
+/// Plaintext of one member's copy of a group DM (`DirectMessageEvent::group_id` set).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum GroupDmPayload {
+    Message {
+        body: String,
+        /// Message ID as sent, without a member's copy suffix
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        reply_to: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        attachments: Vec<DmAttachment>,
+    },
+    /// The full member list after the creator changed it
+    Membership {
+        name: String,
+        created_by: String,
+        members: Vec<String>,
+        version: i64,
+        created_at: String,
+    },
+    /// The sender left the group
+    Leave,
+}
+
 #[derive(Clone)]
 pub struct DmService {
     database: Database,
@@ -33,8 +102,19 @@ impl DmService {
             .collect()
     }
 
-    /// Send a direct message to a peer. Returns (view, ciphertext, nonce) for gossip broadcast.
-    pub fn send_dm(&self, to_peer_id: &str, body: &str) -> Result<(DirectMessageView, Vec<u8>, Vec<u8>)> {
+    /// Send a direct message to a peer. Returns the view and the event to deliver.
+    ///
+    /// Uses a double-ratchet session when we share one with the peer or they
+    /// published a prekey, and the static box for peers that haven't upgraded.
+    /// `reply_to` must name a message of this conversation and `attachment_ids`
+    /// uploads from `FileService::save_dm_attachment` not sent yet.
+    pub fn send_dm(
+        &self,
+        to_peer_id: &str,
+        body: &str,
+        reply_to: Option<&str>,
+        attachment_ids: &[String],
+    ) -> Result<(DirectMessageView, DirectMessageEvent)> {
         // Load our X25519 secret key
         let my_secret = load_x25519_secret(&self.paths)?;
 
@@ -49,11 +129,11 @@ impl DmService {
             let peer = repos
                 .peers()
                 .get(to_peer_id)?
-                .ok_or_else(|| anyhow!("peer not found: {}", to_peer_id))?;
+                .ok_or_else(|| invalid(format!("peer not found: {}", to_peer_id)))?;
 
             let pubkey_str = peer
                 .x25519_pubkey
-                .ok_or_else(|| anyhow!("Cannot send DM: peer {} has no X25519 public key. They may have been added via short friendcode. Ask them to share their full friendcode.", to_peer_id))?;
+                .ok_or_else(|| invalid(format!("Cannot send DM: peer {} has no X25519 public key. They may have been added via short friendcode. Ask them to share their full friendcode.", to_peer_id)))?;
 
             // Decode base64 public key
             let pubkey_bytes = BASE64_STANDARD.decode(&pubkey_str)
@@ -68,11 +148,41 @@ impl DmService {
             Ok::<PublicKey, anyhow::Error>(PublicKey::from(key_array))
         })?;
 
-        // Encrypt the message
-        let (ciphertext, nonce) = encrypt_dm(body, &my_secret.secret, &their_pubkey)?;
-
         // Derive conversation ID
         let conversation_id = Self::derive_conversation_id(&my_peer_id, to_peer_id);
+        let (reply_to, attachments) = self.prepare_extras(&conversation_id, reply_to, attachment_ids)?;
+
+        // Plain text goes out bare so older peers can still read it
+        let structured = reply_to.is_some() || !attachments.is_empty();
+        let plaintext = if structured {
+            serde_json::to_string(&DmContent {
+                body: body.to_string(),
+                reply_to: reply_to.clone(),
+                attachments: attachments.iter().map(wire_attachment).collect::<Result<_>>()?,
+            })?
+        } else {
+            body.to_string()
+        };
+
+        let (wire_body, wire_nonce, ratchet) =
+            match self.ratchet_encrypt(&my_secret, &their_pubkey, &conversation_id, to_peer_id, &plaintext)? {
+                Some((header, wire_body)) => (wire_body, Vec::new(), Some(header)),
+                None => {
+                    let (wire_body, nonce) = encrypt_dm(&plaintext, &my_secret.secret, &their_pubkey)?;
+                    (wire_body, nonce.to_vec(), None)
+                }
+            };
+
+        // Our copy holds the bare body. A ratchet message key is gone once
+        // used, so our own copy of a ratcheted message is boxed to ourselves
+        // instead, as is any copy whose wire plaintext isn't the bare body.
+        let sealed_to_self = ratchet.is_some() || structured;
+        let (ciphertext, nonce) = if sealed_to_self {
+            let (ciphertext, nonce) = encrypt_dm(body, &my_secret.secret, &my_secret.public)?;
+            (ciphertext, nonce.to_vec())
+        } else {
+            (wire_body.clone(), wire_nonce.clone())
+        };
 
         // Create message record
         let message_id = Uuid::new_v4().to_string();
@@ -83,22 +193,25 @@ impl DmService {
             conversation_id: conversation_id.clone(),
             from_peer_id: my_peer_id.clone(),
             to_peer_id: to_peer_id.to_string(),
-            encrypted_body: ciphertext.clone(),
-            nonce: nonce.to_vec(),
+            encrypted_body: ciphertext,
+            nonce,
             created_at: created_at.clone(),
             read_at: None,
+            sealed_to_self,
+            reply_to: reply_to.clone(),
         };
 
         self.database.with_repositories(|repos| {
             // Store the message
             repos.direct_messages().create(&record)?;
+            attach_uploads(&repos, &message_id, &attachments)?;
 
             // Update conversation metadata
             let conversation = ConversationRecord {
                 id: conversation_id.clone(),
                 peer_id: to_peer_id.to_string(),
                 last_message_at: Some(created_at.clone()),
-                last_message_preview: Some(body.chars().take(100).collect()),
+                last_message_preview: Some(message_preview(body, attachments.len())),
                 unread_count: 0, // We sent it, so it's not unread for us
             };
             repos.conversations().upsert(&conversation)?;
@@ -106,6 +219,19 @@ impl DmService {
             Ok(())
         })?;
 
+        let event = DirectMessageEvent {
+            from_peer_id: my_peer_id.clone(),
+            to_peer_id: to_peer_id.to_string(),
+            encrypted_body: wire_body,
+            nonce: wire_nonce,
+            message_id: message_id.clone(),
+            conversation_id: conversation_id.clone(),
+            created_at: created_at.clone(),
+            ratchet,
+            group_id: None,
+            structured,
+        };
+
         let view = DirectMessageView {
             id: message_id,
             conversation_id,
@@ -114,28 +240,102 @@ impl DmService {
             body: body.to_string(),
             created_at,
             read_at: None,
+            delivery_state: Some(OUTBOX_QUEUED.to_string()),
+            reply_to,
+            attachments: attachments.into_iter().map(DmAttachmentView::from_record).collect(),
         };
 
-        Ok((view, ciphertext, nonce.to_vec()))
+        Ok((view, event))
     }
 
-    /// Ingest a DM received via gossip. Stores the encrypted record and updates conversation.
-    pub fn ingest_dm(&self, from_peer_id: &str, to_peer_id: &str, encrypted_body: &[u8], nonce: &[u8], message_id: &str, conversation_id: &str, created_at: &str) -> Result<()> {
-        let record = DirectMessageRecord {
-            id: message_id.to_string(),
-            conversation_id: conversation_id.to_string(),
-            from_peer_id: from_peer_id.to_string(),
-            to_peer_id: to_peer_id.to_string(),
-            encrypted_body: encrypted_body.to_vec(),
-            nonce: nonce.to_vec(),
-            created_at: created_at.to_string(),
+    /// Encrypts `body` on the most recently used session that can send,
+    /// opening one from the peer's prekey bundle when there is none.
+    ///
+    /// `None` means we have neither, so the caller uses the static box.
+    fn ratchet_encrypt(
+        &self,
+        my_secret: &X25519Identity,
+        their_pubkey: &PublicKey,
+        conversation_id: &str,
+        peer_id: &str,
+        body: &str,
+    ) -> Result<Option<(RatchetHeader, Vec<u8>)>> {
+        self.database.with_repositories(|repos| {
+            let mut session = None;
+            for record in repos.dm_sessions().list_for_conversation(conversation_id)? {
+                let candidate: RatchetSession =
+                    serde_json::from_str(&record.state_json).context("corrupt DM session state")?;
+                if candidate.can_send() {
+                    session = Some(candidate);
+                    break;
+                }
+            }
+            let mut session = match session {
+                Some(session) => session,
+                None => match peer_prekey(&repos, peer_id, their_pubkey)? {
+                    Some((prekey_id, prekey)) => {
+                        RatchetSession::initiate(&my_secret.secret, their_pubkey, &prekey, prekey_id)
+                    }
+                    None => return Ok(None),
+                },
+            };
+
+            let ad = associated_data(&my_secret.public, their_pubkey);
+            let (header, ciphertext) = session.encrypt(body.as_bytes(), &ad)?;
+            store_session(&repos, &session, conversation_id, peer_id)?;
+            Ok(Some((header, ciphertext)))
+        })
+    }
+
+    /// Ingest a DM delivered over the network. Stores the encrypted record and updates conversation.
+    ///
+    /// Ratcheted DMs are decrypted right away, since their message key can only
+    /// be used once, and stored boxed to our own key. One that no session can
+    /// open is rejected without storing anything. Structured DMs are unpacked
+    /// the same way; their attachments are recorded but fetched on request.
+    pub fn ingest_dm(&self, dm: &DirectMessageEvent) -> Result<()> {
+        if let Some(group_id) = &dm.group_id {
+            return self.ingest_group_dm(dm, group_id);
+        }
+
+        let mut record = DirectMessageRecord {
+            id: dm.message_id.clone(),
+            conversation_id: dm.conversation_id.clone(),
+            from_peer_id: dm.from_peer_id.clone(),
+            to_peer_id: dm.to_peer_id.clone(),
+            encrypted_body: dm.encrypted_body.clone(),
+            nonce: dm.nonce.clone(),
+            created_at: dm.created_at.clone(),
             read_at: None,
+            sealed_to_self: false,
+            reply_to: None,
         };
+        let mut attachments = Vec::new();
 
-        // Store the raw encrypted record
+        if dm.ratchet.is_some() || dm.structured {
+            let my_secret = load_x25519_secret(&self.paths)?;
+            let plaintext = match &dm.ratchet {
+                Some(header) => self.ratchet_decrypt(&my_secret, dm, header)?,
+                None => self.static_decrypt(&my_secret, dm)?,
+            };
+            let body = if dm.structured {
+                let content: DmContent = serde_json::from_str(&plaintext).context("malformed DM content")?;
+                record.reply_to = content.reply_to;
+                attachments = content.attachments;
+                content.body
+            } else {
+                plaintext
+            };
+            let (ciphertext, nonce) = encrypt_dm(&body, &my_secret.secret, &my_secret.public)?;
+            record.encrypted_body = ciphertext;
+            record.nonce = nonce.to_vec();
+            record.sealed_to_self = true;
+        }
+
+        // Store the encrypted record
         self.database.with_repositories(|repos| {
             repos.direct_messages().create(&record)?;
-            Ok(())
+            store_received_attachments(&repos, &record.id, &attachments)
         })?;
 
         // Decrypt and update conversation metadata (receive_dm handles conversation upsert)
@@ -146,6 +346,56 @@ impl DmService {
         Ok(())
     }
 
+    /// Opens a ratcheted DM on its session, accepting a new session from the
+    /// sender when the header names one of our prekeys.
+    fn ratchet_decrypt(&self, my_secret: &X25519Identity, dm: &DirectMessageEvent, header: &RatchetHeader) -> Result<String> {
+        // Sessions belong to the pair, whatever conversation the sender names
+        let conversation_id = Self::derive_conversation_id(&dm.from_peer_id, &dm.to_peer_id);
+        self.database.with_repositories(|repos| {
+            let peer = repos
+                .peers()
+                .get(&dm.from_peer_id)?
+                .ok_or_else(|| anyhow!("sender peer not found: {}", dm.from_peer_id))?;
+            let their_pubkey = decode_x25519(
+                peer.x25519_pubkey
+                    .as_deref()
+                    .ok_or_else(|| anyhow!("sender {} has no X25519 public key", dm.from_peer_id))?,
+            )?;
+
+            let mut session = match repos.dm_sessions().get(&header.session_id)? {
+                Some(record) => {
+                    if record.peer_id != dm.from_peer_id {
+                        anyhow::bail!("DM session {} belongs to another peer", header.session_id);
+                    }
+                    serde_json::from_str(&record.state_json).context("corrupt DM session state")?
+                }
+                None => {
+                    let prekey_id = header
+                        .prekey_id
+                        .ok_or_else(|| anyhow!("DM {} names an unknown session", dm.message_id))?;
+                    let prekey = load_dm_prekey(&self.paths, prekey_id)?
+                        .ok_or_else(|| anyhow!("DM {} uses retired prekey {}", dm.message_id, prekey_id))?;
+                    RatchetSession::respond(&my_secret.secret, &prekey.secret, &their_pubkey, header)?
+                }
+            };
+
+            let ad = associated_data(&their_pubkey, &my_secret.public);
+            let plaintext = session.decrypt(header, &dm.encrypted_body, &ad)?;
+            store_session(&repos, &session, &conversation_id, &dm.from_peer_id)?;
+            String::from_utf8(plaintext).map_err(|e| anyhow!("invalid UTF-8 in decrypted DM: {}", e))
+        })
+    }
+
+    /// Opens a DM sealed with the static box between the sender's key and ours.
+    fn static_decrypt(&self, my_secret: &X25519Identity, dm: &DirectMessageEvent) -> Result<String> {
+        let their_pubkey = self
+            .database
+            .with_repositories(|repos| peer_x25519(&repos, &dm.from_peer_id))?;
+        let nonce = <[u8; 24]>::try_from(dm.nonce.as_slice())
+            .map_err(|_| anyhow!("invalid nonce length: {}", dm.nonce.len()))?;
+        decrypt_dm(&dm.encrypted_body, &nonce, &my_secret.secret, &their_pubkey)
+    }
+
     /// Receive and decrypt a direct message.
     pub fn receive_dm(&self, record: DirectMessageRecord) -> Result<DirectMessageView> {
         // Load our X25519 secret key
@@ -182,8 +432,9 @@ impl DmService {
         let mut nonce = [0u8; 24];
         nonce.copy_from_slice(&record.nonce);
 
-        // Decrypt the message
-        let body = decrypt_dm(&record.encrypted_body, &nonce, &my_secret.secret, &their_pubkey)?;
+        // Decrypt the message (ratcheted ones were re-boxed to our own key on ingest)
+        let box_key = if record.sealed_to_self { my_secret.public } else { their_pubkey };
+        let body = decrypt_dm(&record.encrypted_body, &nonce, &my_secret.secret, &box_key)?;
 
         // Update conversation metadata
         let (my_peer_id, _, _) = self
@@ -191,18 +442,20 @@ impl DmService {
             .get_identity()?
             .ok_or_else(|| anyhow!("no local identity found"))?;
 
-        self.database.with_repositories(|repos| {
+        let attachments = self.database.with_repositories(|repos| {
+            let attachments = attachment_views(&repos, &record.id)?;
+
             // Update conversation
             let conversation = ConversationRecord {
                 id: record.conversation_id.clone(),
                 peer_id: record.from_peer_id.clone(),
                 last_message_at: Some(record.created_at.clone()),
-                last_message_preview: Some(body.chars().take(100).collect()),
+                last_message_preview: Some(message_preview(&body, attachments.len())),
                 unread_count: 1, // New unread message
             };
             repos.conversations().upsert(&conversation)?;
 
-            Ok(())
+            Ok(attachments)
         })?;
 
         Ok(DirectMessageView {
@@ -213,9 +466,53 @@ impl DmService {
             body,
             created_at: record.created_at,
             read_at: record.read_at,
+            delivery_state: None,
+            reply_to: record.reply_to,
+            attachments,
         })
     }
 
+    /// Signs a receipt telling the sender of `message_id` that it reached us.
+    pub fn delivery_receipt(&self, message_id: &str, sender_peer_id: &str) -> Result<DmReceiptEvent> {
+        let (my_peer_id, _, _) = self
+            .database
+            .get_identity()?
+            .ok_or_else(|| anyhow!("no local identity found"))?;
+
+        let mut receipt = DmReceiptEvent {
+            message_id: message_id.to_string(),
+            from_peer_id: my_peer_id,
+            to_peer_id: sender_peer_id.to_string(),
+            received_at: now_utc_iso(),
+            signature: String::new(),
+            signer_public_key: Some(load_public_key_armored(&self.paths)?),
+        };
+        receipt.signature = sign_detached(&self.paths, &receipt.signing_payload())?;
+        Ok(receipt)
+    }
+
+    /// Our current DM prekey, signed for publication in a `ProfileUpdate`.
+    pub fn prekey_bundle(&self) -> Result<DmPrekeyBundle> {
+        let (my_peer_id, _, _) = self
+            .database
+            .get_identity()?
+            .ok_or_else(|| anyhow!("no local identity found"))?;
+        let identity = load_x25519_secret(&self.paths)?;
+        let prekey = ensure_dm_prekey(&self.paths)?;
+
+        let mut bundle = DmPrekeyBundle {
+            peer_id: my_peer_id,
+            identity_key: BASE64_STANDARD.encode(identity.public.as_bytes()),
+            prekey_id: prekey.id,
+            prekey: BASE64_STANDARD.encode(prekey.public.as_bytes()),
+            created_at: prekey.created_at,
+            signature: String::new(),
+            signer_public_key: Some(load_public_key_armored(&self.paths)?),
+        };
+        bundle.signature = sign_detached(&self.paths, &bundle.signing_payload())?;
+        Ok(bundle)
+    }
+
     /// List conversations, sorted by last message time.
     pub fn list_conversations(&self) -> Result<Vec<ConversationView>> {
         self.database.with_repositories(|repos| {
@@ -299,9 +596,17 @@ impl DmService {
                 nonce.copy_from_slice(&record.nonce);
 
                 // Decrypt
-                match decrypt_dm(&record.encrypted_body, &nonce, &my_secret.secret, &their_pubkey) {
+                let box_key = if record.sealed_to_self { my_secret.public } else { their_pubkey };
+                match decrypt_dm(&record.encrypted_body, &nonce, &my_secret.secret, &box_key) {
                     Ok(body) => {
+                        // Only our own messages have an outbox entry
+                        let delivery_state = if record.from_peer_id == my_peer_id {
+                            repos.dm_outbox().get(&record.id)?.map(|entry| entry.state)
+                        } else {
+                            None
+                        };
                         views.push(DirectMessageView {
+                            attachments: attachment_views(&repos, &record.id)?,
                             id: record.id,
                             conversation_id: record.conversation_id,
                             from_peer_id: record.from_peer_id,
@@ -309,6 +614,8 @@ impl DmService {
                             body,
                             created_at: record.created_at,
                             read_at: record.read_at,
+                            delivery_state,
+                            reply_to: record.reply_to,
                         });
                     }
                     Err(e) => {
@@ -341,6 +648,669 @@ impl DmService {
             repos.direct_messages().count_unread(&my_peer_id)
         })
     }
+
+    /// Creates a group DM with `members` and ourselves.
+    ///
+    /// Every other member must be a peer we hold an X25519 key for. Returns the
+    /// group and the membership copies to deliver.
+    pub fn create_group(&self, name: &str, members: &[String]) -> Result<(DmGroupView, Vec<DirectMessageEvent>)> {
+        let my_peer_id = self.local_peer_id()?;
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(invalid("group name may not be empty"));
+        }
+        let mut members = members.to_vec();
+        members.push(my_peer_id.clone());
+        members.sort();
+        members.dedup();
+        if members.len() < 2 {
+            return Err(invalid("a group needs at least one other member"));
+        }
+        self.check_group_members(&members, &my_peer_id)?;
+
+        let record = DmGroupRecord {
+            id: Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            created_by: my_peer_id.clone(),
+            membership_version: 1,
+            created_at: now_utc_iso(),
+            last_message_at: None,
+            last_message_preview: None,
+        };
+        let view = self.database.with_repositories(|repos| {
+            repos.dm_groups().upsert(&record)?;
+            repos.dm_groups().set_members(&record.id, &members)?;
+            group_view(&repos, record.clone(), &my_peer_id)
+        })?;
+
+        let payload = membership_payload(&record, &members);
+        let events = self.fan_out(&record.id, &members, &payload, &Uuid::new_v4().to_string(), &record.created_at)?;
+        Ok((view, events))
+    }
+
+    /// Adds and removes members of a group we created.
+    ///
+    /// The new member list goes to everyone in the old or new one, so removed
+    /// members learn they are out.
+    pub fn update_group_members(
+        &self,
+        group_id: &str,
+        add: &[String],
+        remove: &[String],
+    ) -> Result<(DmGroupView, Vec<DirectMessageEvent>)> {
+        let my_peer_id = self.local_peer_id()?;
+        let (mut record, current) = self.load_group(group_id)?;
+        if record.created_by != my_peer_id {
+            return Err(forbidden("only the group's creator can change its members"));
+        }
+        if !current.contains(&my_peer_id) {
+            return Err(forbidden(format!("not a member of group {}", group_id)));
+        }
+        if remove.contains(&my_peer_id) {
+            return Err(invalid("cannot remove yourself; leave the group instead"));
+        }
+
+        let mut members: Vec<String> = current
+            .iter()
+            .chain(add)
+            .filter(|peer_id| !remove.contains(peer_id))
+            .cloned()
+            .collect();
+        members.sort();
+        members.dedup();
+        self.check_group_members(&members, &my_peer_id)?;
+
+        record.membership_version += 1;
+        let view = self.database.with_repositories(|repos| {
+            repos.dm_groups().upsert(&record)?;
+            repos.dm_groups().set_members(group_id, &members)?;
+            group_view(&repos, record.clone(), &my_peer_id)
+        })?;
+
+        let mut recipients = current;
+        recipients.extend(members.iter().cloned());
+        recipients.sort();
+        recipients.dedup();
+        let payload = membership_payload(&record, &members);
+        let events = self.fan_out(group_id, &recipients, &payload, &Uuid::new_v4().to_string(), &now_utc_iso())?;
+        Ok((view, events))
+    }
+
+    /// Leaves a group. Its history stays readable locally.
+    pub fn leave_group(&self, group_id: &str) -> Result<Vec<DirectMessageEvent>> {
+        let my_peer_id = self.local_peer_id()?;
+        let (_, mut members) = self.load_group(group_id)?;
+        if !members.contains(&my_peer_id) {
+            return Err(forbidden(format!("not a member of group {}", group_id)));
+        }
+        members.retain(|peer_id| *peer_id != my_peer_id);
+        self.database
+            .with_repositories(|repos| repos.dm_groups().set_members(group_id, &members))?;
+        self.fan_out(group_id, &members, &GroupDmPayload::Leave, &Uuid::new_v4().to_string(), &now_utc_iso())
+    }
+
+    /// Sends a message to every member of a group we belong to.
+    ///
+    /// Each member gets a copy encrypted on our pairwise channel with them; we
+    /// keep one copy boxed to ourselves. Members we hold no key for are skipped.
+    /// Replies and attachments work as for `send_dm`.
+    pub fn send_group_dm(
+        &self,
+        group_id: &str,
+        body: &str,
+        reply_to: Option<&str>,
+        attachment_ids: &[String],
+    ) -> Result<(DirectMessageView, Vec<DirectMessageEvent>)> {
+        let my_peer_id = self.local_peer_id()?;
+        let my_secret = load_x25519_secret(&self.paths)?;
+        let (_, members) = self.load_group(group_id)?;
+        if !members.contains(&my_peer_id) {
+            return Err(forbidden(format!("not a member of group {}", group_id)));
+        }
+        let (reply_to, attachments) = self.prepare_extras(group_id, reply_to, attachment_ids)?;
+
+        let message_id = Uuid::new_v4().to_string();
+        let created_at = now_utc_iso();
+        let payload = GroupDmPayload::Message {
+            body: body.to_string(),
+            reply_to: reply_to.as_deref().map(|id| base_message_id(id).to_string()),
+            attachments: attachments.iter().map(wire_attachment).collect::<Result<_>>()?,
+        };
+        let events = self.fan_out(group_id, &members, &payload, &message_id, &created_at)?;
+
+        let (ciphertext, nonce) = encrypt_dm(body, &my_secret.secret, &my_secret.public)?;
+        let record = DirectMessageRecord {
+            id: message_id.clone(),
+            conversation_id: group_id.to_string(),
+            from_peer_id: my_peer_id.clone(),
+            to_peer_id: my_peer_id.clone(),
+            encrypted_body: ciphertext,
+            nonce: nonce.to_vec(),
+            created_at: created_at.clone(),
+            // Our own messages never count as unread
+            read_at: Some(created_at.clone()),
+            sealed_to_self: true,
+            reply_to: reply_to.clone(),
+        };
+        self.database.with_repositories(|repos| {
+            repos.direct_messages().create(&record)?;
+            attach_uploads(&repos, &message_id, &attachments)?;
+            repos
+                .dm_groups()
+                .update_last_message(group_id, &created_at, &message_preview(body, attachments.len()))
+        })?;
+
+        let view = DirectMessageView {
+            id: message_id,
+            conversation_id: group_id.to_string(),
+            from_peer_id: my_peer_id.clone(),
+            to_peer_id: my_peer_id,
+            body: body.to_string(),
+            created_at,
+            read_at: record.read_at,
+            delivery_state: Some(OUTBOX_QUEUED.to_string()),
+            reply_to,
+            attachments: attachments.into_iter().map(DmAttachmentView::from_record).collect(),
+        };
+        Ok((view, events))
+    }
+
+    /// List group conversations, most recent activity first.
+    pub fn list_groups(&self) -> Result<Vec<DmGroupView>> {
+        let my_peer_id = self.local_peer_id()?;
+        self.database.with_repositories(|repos| {
+            repos
+                .dm_groups()
+                .list()?
+                .into_iter()
+                .map(|record| group_view(&repos, record, &my_peer_id))
+                .collect()
+        })
+    }
+
+    pub fn get_group(&self, group_id: &str) -> Result<Option<DmGroupView>> {
+        let my_peer_id = self.local_peer_id()?;
+        self.database.with_repositories(|repos| {
+            repos
+                .dm_groups()
+                .get(group_id)?
+                .map(|record| group_view(&repos, record, &my_peer_id))
+                .transpose()
+        })
+    }
+
+    /// Get messages of a group conversation.
+    ///
+    /// Our own messages carry the least advanced delivery state of their copies.
+    pub fn get_group_messages(&self, group_id: &str, limit: usize) -> Result<Vec<DirectMessageView>> {
+        let my_peer_id = self.local_peer_id()?;
+        let my_secret = load_x25519_secret(&self.paths)?;
+        let (_, members) = self.load_group(group_id)?;
+
+        self.database.with_repositories(|repos| {
+            let records = repos.direct_messages().list_for_conversation(group_id, limit)?;
+            let mut views = Vec::new();
+            for record in records {
+                let Ok(nonce) = <[u8; 24]>::try_from(record.nonce.as_slice()) else {
+                    tracing::warn!("skipping message with invalid nonce length");
+                    continue;
+                };
+                // Group messages are always stored boxed to our own key
+                let body = match decrypt_dm(&record.encrypted_body, &nonce, &my_secret.secret, &my_secret.public) {
+                    Ok(body) => body,
+                    Err(e) => {
+                        tracing::warn!("failed to decrypt group DM {}: {}", record.id, e);
+                        continue;
+                    }
+                };
+                let delivery_state = if record.from_peer_id == my_peer_id {
+                    group_delivery_state(&repos, &record.id, &members, &my_peer_id)?
+                } else {
+                    None
+                };
+                views.push(DirectMessageView {
+                    attachments: attachment_views(&repos, &record.id)?,
+                    id: record.id,
+                    conversation_id: record.conversation_id,
+                    from_peer_id: record.from_peer_id,
+                    to_peer_id: record.to_peer_id,
+                    body,
+                    created_at: record.created_at,
+                    read_at: record.read_at,
+                    delivery_state,
+                    reply_to: record.reply_to,
+                });
+            }
+            Ok(views)
+        })
+    }
+
+    /// Decrypts a member's copy of a group DM and applies it.
+    ///
+    /// Messages from non-members and membership changes not made by the
+    /// group's creator are dropped with a warning. Until the creator's member
+    /// list arrives, messages are kept on pending membership: their senders
+    /// join the placeholder group and whatever the real list leaves out is
+    /// deleted once it lands. A ratcheted copy can't be decrypted twice, so
+    /// dropping it and waiting for a retransmit would lose it.
+    fn ingest_group_dm(&self, dm: &DirectMessageEvent, group_id: &str) -> Result<()> {
+        let my_secret = load_x25519_secret(&self.paths)?;
+        let plaintext = match &dm.ratchet {
+            Some(header) => self.ratchet_decrypt(&my_secret, dm, header)?,
+            None => self.static_decrypt(&my_secret, dm)?,
+        };
+        let payload: GroupDmPayload = serde_json::from_str(&plaintext).context("malformed group DM payload")?;
+
+        match payload {
+            GroupDmPayload::Message {
+                body,
+                reply_to,
+                attachments,
+            } => {
+                let (ciphertext, nonce) = encrypt_dm(&body, &my_secret.secret, &my_secret.public)?;
+                self.database.with_repositories(|repos| {
+                    let groups = repos.dm_groups();
+                    let members = match groups.get(group_id)? {
+                        Some(group) if group.membership_version > 0 => groups.list_members(group_id)?,
+                        Some(_) => {
+                            // Still a placeholder: admit the sender pending the real list
+                            let mut members = groups.list_members(group_id)?;
+                            if !members.contains(&dm.from_peer_id) {
+                                members.push(dm.from_peer_id.clone());
+                                groups.set_members(group_id, &members)?;
+                            }
+                            members
+                        }
+                        None => {
+                            // The membership copy may still be on its way; until it
+                            // arrives the sender stands in as the group's creator
+                            let members = vec![dm.from_peer_id.clone(), dm.to_peer_id.clone()];
+                            groups.upsert(&DmGroupRecord {
+                                id: group_id.to_string(),
+                                name: String::new(),
+                                created_by: dm.from_peer_id.clone(),
+                                membership_version: 0,
+                                created_at: dm.created_at.clone(),
+                                last_message_at: None,
+                                last_message_preview: None,
+                            })?;
+                            groups.set_members(group_id, &members)?;
+                            members
+                        }
+                    };
+                    if !members.contains(&dm.from_peer_id) || !members.contains(&dm.to_peer_id) {
+                        tracing::warn!(group_id, from = %dm.from_peer_id, "dropping group DM outside the membership");
+                        return Ok(());
+                    }
+
+                    // Replies name the message as sent; we hold it under that ID
+                    // only if it was ours, otherwise under our copy's ID
+                    let reply_to = match reply_to {
+                        Some(id) if repos.direct_messages().get(&id)?.is_some() => Some(id),
+                        Some(id) => Some(group_copy_id(&id, &dm.to_peer_id)),
+                        None => None,
+                    };
+                    repos.direct_messages().create(&DirectMessageRecord {
+                        id: dm.message_id.clone(),
+                        conversation_id: group_id.to_string(),
+                        from_peer_id: dm.from_peer_id.clone(),
+                        to_peer_id: dm.to_peer_id.clone(),
+                        encrypted_body: ciphertext,
+                        nonce: nonce.to_vec(),
+                        created_at: dm.created_at.clone(),
+                        read_at: None,
+                        sealed_to_self: true,
+                        reply_to,
+                    })?;
+                    store_received_attachments(&repos, &dm.message_id, &attachments)?;
+                    groups.update_last_message(group_id, &dm.created_at, &message_preview(&body, attachments.len()))
+                })
+            }
+            GroupDmPayload::Membership {
+                name,
+                created_by,
+                members,
+                version,
+                created_at,
+            } => self.database.with_repositories(|repos| {
+                let groups = repos.dm_groups();
+                let stored = groups.get(group_id)?;
+                // A placeholder (version 0) takes its creator from the first real update
+                let creator = match &stored {
+                    Some(group) if group.membership_version > 0 => group.created_by.clone(),
+                    _ => created_by.clone(),
+                };
+                if dm.from_peer_id != creator || created_by != creator {
+                    tracing::warn!(group_id, from = %dm.from_peer_id, "dropping membership change not made by the group's creator");
+                    return Ok(());
+                }
+                if stored.as_ref().is_some_and(|group| version <= group.membership_version) {
+                    // Replayed or out of date
+                    return Ok(());
+                }
+                groups.upsert(&DmGroupRecord {
+                    id: group_id.to_string(),
+                    name,
+                    created_by,
+                    membership_version: version,
+                    created_at,
+                    last_message_at: None,
+                    last_message_preview: None,
+                })?;
+                groups.set_members(group_id, &members)?;
+                if stored.is_some_and(|group| group.membership_version == 0) {
+                    let dropped = repos.direct_messages().delete_from_non_members(group_id)?;
+                    if dropped > 0 {
+                        tracing::warn!(group_id, dropped, "dropped pending group DMs from non-members");
+                    }
+                }
+                Ok(())
+            }),
+            GroupDmPayload::Leave => self.database.with_repositories(|repos| {
+                let groups = repos.dm_groups();
+                let mut members = groups.list_members(group_id)?;
+                members.retain(|peer_id| *peer_id != dm.from_peer_id);
+                groups.set_members(group_id, &members)
+            }),
+        }
+    }
+
+    /// Encrypts `payload` for every member but ourselves, one copy each.
+    ///
+    /// Copies are numbered `{message_id}:{member}` so each gets its own outbox
+    /// entry and receipt.
+    fn fan_out(
+        &self,
+        group_id: &str,
+        recipients: &[String],
+        payload: &GroupDmPayload,
+        message_id: &str,
+        created_at: &str,
+    ) -> Result<Vec<DirectMessageEvent>> {
+        let my_peer_id = self.local_peer_id()?;
+        let my_secret = load_x25519_secret(&self.paths)?;
+        let plaintext = serde_json::to_string(payload)?;
+
+        let mut events = Vec::new();
+        for peer_id in recipients.iter().filter(|peer_id| **peer_id != my_peer_id) {
+            let their_pubkey = match self.database.with_repositories(|repos| peer_x25519(&repos, peer_id)) {
+                Ok(key) => key,
+                Err(err) => {
+                    tracing::warn!(group_id, peer_id = %peer_id, error = ?err, "skipping group member we can't encrypt to");
+                    continue;
+                }
+            };
+            let conversation_id = Self::derive_conversation_id(&my_peer_id, peer_id);
+            let (encrypted_body, nonce, ratchet) =
+                match self.ratchet_encrypt(&my_secret, &their_pubkey, &conversation_id, peer_id, &plaintext)? {
+                    Some((header, body)) => (body, Vec::new(), Some(header)),
+                    None => {
+                        let (body, nonce) = encrypt_dm(&plaintext, &my_secret.secret, &their_pubkey)?;
+                        (body, nonce.to_vec(), None)
+                    }
+                };
+            events.push(DirectMessageEvent {
+                from_peer_id: my_peer_id.clone(),
+                to_peer_id: peer_id.clone(),
+                encrypted_body,
+                nonce,
+                message_id: group_copy_id(message_id, peer_id),
+                conversation_id,
+                created_at: created_at.to_string(),
+                ratchet,
+                group_id: Some(group_id.to_string()),
+            });
+        }
+        Ok(events)
+    }
+
+    /// Checks a reply target and the uploads to send along with a message.
+    fn prepare_extras(
+        &self,
+        conversation_id: &str,
+        reply_to: Option<&str>,
+        attachment_ids: &[String],
+    ) -> Result<(Option<String>, Vec<DmAttachmentRecord>)> {
+        if attachment_ids.len() > MAX_DM_ATTACHMENTS {
+            return Err(invalid(format!("a message may carry at most {} attachments", MAX_DM_ATTACHMENTS)));
+        }
+        self.database.with_repositories(|repos| {
+            if let Some(id) = reply_to {
+                let target = repos.direct_messages().get(id)?;
+                if !target.is_some_and(|message| message.conversation_id == conversation_id) {
+                    return Err(invalid(format!("reply target not found in this conversation: {}", id)));
+                }
+            }
+            let mut attachments = Vec::new();
+            for id in attachment_ids {
+                let record = repos
+                    .dm_attachments()
+                    .get(id)?
+                    .ok_or_else(|| invalid(format!("attachment not found: {}", id)))?;
+                if record.message_id.is_some() {
+                    return Err(invalid(format!("attachment {} was already sent", id)));
+                }
+                attachments.push(record);
+            }
+            Ok((reply_to.map(str::to_string), attachments))
+        })
+    }
+
+    fn load_group(&self, group_id: &str) -> Result<(DmGroupRecord, Vec<String>)> {
+        self.database.with_repositories(|repos| {
+            let record = repos
+                .dm_groups()
+                .get(group_id)?
+                .ok_or_else(|| not_found(format!("group not found: {}", group_id)))?;
+            let members = repos.dm_groups().list_members(group_id)?;
+            Ok((record, members))
+        })
+    }
+
+    /// Rejects oversized groups and members we couldn't encrypt to.
+    fn check_group_members(&self, members: &[String], my_peer_id: &str) -> Result<()> {
+        if members.len() > MAX_GROUP_MEMBERS {
+            return Err(invalid(format!("a group may have at most {} members", MAX_GROUP_MEMBERS)));
+        }
+        self.database.with_repositories(|repos| {
+            for peer_id in members.iter().filter(|peer_id| *peer_id != my_peer_id) {
+                peer_x25519(&repos, peer_id)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn local_peer_id(&self) -> Result<String> {
+        let (my_peer_id, _, _) = self
+            .database
+            .get_identity()?
+            .ok_or_else(|| anyhow!("no local identity found"))?;
+        Ok(my_peer_id)
+    }
+}
+
+/// The peer's stored prekey, unless it was published for an older identity key.
+fn peer_prekey(repos: &SqliteRepositories<'_>, peer_id: &str, their_pubkey: &PublicKey) -> Result<Option<(u32, PublicKey)>> {
+    let Some(json) = repos.dm_sessions().get_prekey_bundle(peer_id)? else {
+        return Ok(None);
+    };
+    let bundle: DmPrekeyBundle = serde_json::from_str(&json).context("corrupt DM prekey bundle")?;
+    if decode_x25519(&bundle.identity_key)? != *their_pubkey {
+        return Ok(None);
+    }
+    Ok(Some((bundle.prekey_id, decode_x25519(&bundle.prekey)?)))
+}
+
+fn store_session(repos: &SqliteRepositories<'_>, session: &RatchetSession, conversation_id: &str, peer_id: &str) -> Result<()> {
+    repos.dm_sessions().upsert(&DmSessionRecord {
+        session_id: session.session_id(),
+        conversation_id: conversation_id.to_string(),
+        peer_id: peer_id.to_string(),
+        state_json: serde_json::to_string(session)?,
+        updated_at: chrono::Utc::now().timestamp(),
+    })?;
+    repos.dm_sessions().prune(conversation_id, DM_SESSIONS_KEPT)?;
+    Ok(())
+}
+
+fn decode_x25519(value: &str) -> Result<PublicKey> {
+    let bytes: [u8; 32] = BASE64_STANDARD
+        .decode(value)
+        .with_context(|| "failed to decode X25519 public key")?
+        .try_into()
+        .map_err(|bytes: Vec<u8>| anyhow!("invalid X25519 public key length: {}", bytes.len()))?;
+    Ok(PublicKey::from(bytes))
+}
+
+fn peer_x25519(repos: &SqliteRepositories<'_>, peer_id: &str) -> Result<PublicKey> {
+    let peer = repos
+        .peers()
+        .get(peer_id)?
+        .ok_or_else(|| invalid(format!("peer not found: {}", peer_id)))?;
+    let pubkey = peer
+        .x25519_pubkey
+        .ok_or_else(|| invalid(format!("peer {} has no X25519 public key", peer_id)))?;
+    decode_x25519(&pubkey)
+}
+
+fn membership_payload(record: &DmGroupRecord, members: &[String]) -> GroupDmPayload {
+    GroupDmPayload::Membership {
+        name: record.name.clone(),
+        created_by: record.created_by.clone(),
+        members: members.to_vec(),
+        version: record.membership_version,
+        created_at: record.created_at.clone(),
+    }
+}
+
+/// Message ID of the copy of a group message sent to `peer_id`.
+fn group_copy_id(message_id: &str, peer_id: &str) -> String {
+    format!("{}:{}", message_id, peer_id)
+}
+
+/// The ID a group message was sent under, given any member's copy of it.
+fn base_message_id(message_id: &str) -> &str {
+    message_id.split(':').next().unwrap_or(message_id)
+}
+
+fn message_preview(body: &str, attachments: usize) -> String {
+    if body.trim().is_empty() && attachments > 0 {
+        return format!("[{} attachment{}]", attachments, if attachments == 1 { "" } else { "s" });
+    }
+    body.chars().take(100).collect()
+}
+
+fn wire_attachment(record: &DmAttachmentRecord) -> Result<DmAttachment> {
+    Ok(DmAttachment {
+        id: record.id.clone(),
+        name: record.original_name.clone(),
+        mime: record.mime.clone(),
+        size_bytes: record.size_bytes,
+        blob_id: record.blob_id.clone(),
+        ticket: record
+            .ticket
+            .clone()
+            .ok_or_else(|| invalid(format!("attachment {} has no blob ticket", record.id)))?,
+        key: record.encryption_key.clone(),
+    })
+}
+
+fn attach_uploads(repos: &SqliteRepositories<'_>, message_id: &str, attachments: &[DmAttachmentRecord]) -> Result<()> {
+    for attachment in attachments {
+        if !repos.dm_attachments().attach_to_message(&attachment.id, message_id)? {
+            return Err(invalid(format!("attachment {} was already sent", attachment.id)));
+        }
+    }
+    Ok(())
+}
+
+/// Records the attachments of a received message; they are fetched on request.
+///
+/// Each gets a fresh local ID: the sender's ID names our file on disk and
+/// can't be trusted to be a UUID.
+fn store_received_attachments(repos: &SqliteRepositories<'_>, message_id: &str, attachments: &[DmAttachment]) -> Result<()> {
+    for attachment in attachments.iter().take(MAX_DM_ATTACHMENTS) {
+        repos.dm_attachments().create(&DmAttachmentRecord {
+            id: Uuid::new_v4().to_string(),
+            message_id: Some(message_id.to_string()),
+            original_name: attachment.name.clone(),
+            mime: attachment.mime.clone(),
+            size_bytes: attachment.size_bytes,
+            blob_id: attachment.blob_id.clone(),
+            ticket: Some(attachment.ticket.clone()),
+            encryption_key: attachment.key.clone(),
+            path: None,
+            download_status: "pending".to_string(),
+            created_at: now_utc_iso(),
+        })?;
+    }
+    Ok(())
+}
+
+fn attachment_views(repos: &SqliteRepositories<'_>, message_id: &str) -> Result<Vec<DmAttachmentView>> {
+    Ok(repos
+        .dm_attachments()
+        .list_for_message(message_id)?
+        .into_iter()
+        .map(DmAttachmentView::from_record)
+        .collect())
+}
+
+fn delivery_rank(state: &str) -> u8 {
+    match state {
+        OUTBOX_FAILED => 0,
+        OUTBOX_QUEUED => 1,
+        OUTBOX_SENT => 2,
+        _ => 3,
+    }
+}
+
+/// The least advanced outbox state among the copies of one of our group messages.
+fn group_delivery_state(
+    repos: &SqliteRepositories<'_>,
+    message_id: &str,
+    members: &[String],
+    my_peer_id: &str,
+) -> Result<Option<String>> {
+    let mut least: Option<String> = None;
+    for peer_id in members.iter().filter(|peer_id| *peer_id != my_peer_id) {
+        let Some(entry) = repos.dm_outbox().get(&group_copy_id(message_id, peer_id))? else {
+            continue;
+        };
+        let behind = match &least {
+            Some(state) => delivery_rank(&entry.state) < delivery_rank(state),
+            None => true,
+        };
+        if behind {
+            least = Some(entry.state);
+        }
+    }
+    Ok(least)
+}
+
+fn group_view(repos: &SqliteRepositories<'_>, record: DmGroupRecord, my_peer_id: &str) -> Result<DmGroupView> {
+    let mut members = Vec::new();
+    for peer_id in repos.dm_groups().list_members(&record.id)? {
+        let peer = repos.peers().get(&peer_id)?;
+        members.push(GroupMemberView {
+            username: peer.as_ref().and_then(|peer| peer.username.clone()),
+            alias: peer.and_then(|peer| peer.alias),
+            peer_id,
+        });
+    }
+    let unread_count = repos
+        .direct_messages()
+        .count_unread_in_conversation(&record.id, my_peer_id)?;
+    Ok(DmGroupView {
+        is_member: members.iter().any(|member| member.peer_id == my_peer_id),
+        id: record.id,
+        name: record.name,
+        created_by: record.created_by,
+        members,
+        last_message_at: record.last_message_at,
+        last_message_preview: record.last_message_preview,
+        unread_count: unread_count as u32,
+    })
 }
 
 /// View model for a direct message with decrypted body.
@@ -353,6 +1323,37 @@ pub struct DirectMessageView {
     pub body: String,
     pub created_at: String,
     pub read_at: Option<String>,
+    /// Outbox state of a message we sent: "queued", "sent" or "acked"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delivery_state: Option<String>,
+    /// ID of the message this one replies to, as listed in the same conversation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_to: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<DmAttachmentView>,
+}
+
+/// A file sent with a DM; served from `/dms/attachments/{id}` once available.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DmAttachmentView {
+    pub id: String,
+    pub original_name: Option<String>,
+    pub mime: Option<String>,
+    pub size_bytes: i64,
+    /// "pending" until fetched, then "downloading", "available" or "failed"
+    pub download_status: String,
+}
+
+impl DmAttachmentView {
+    pub fn from_record(record: DmAttachmentRecord) -> Self {
+        Self {
+            id: record.id,
+            original_name: record.original_name,
+            mime: record.mime,
+            size_bytes: record.size_bytes,
+            download_status: record.download_status,
+        }
+    }
 }
 
 /// View model for a conversation with peer info.
@@ -367,9 +1368,441 @@ pub struct ConversationView {
     pub unread_count: u32,
 }
 
+/// View model for a group DM conversation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DmGroupView {
+    pub id: String,
+    /// Empty until the creator's membership update arrived
+    pub name: String,
+    pub created_by: String,
+    pub members: Vec<GroupMemberView>,
+    /// False once we left or were removed; the history stays readable
+    pub is_member: bool,
+    pub last_message_at: Option<String>,
+    pub last_message_preview: Option<String>,
+    pub unread_count: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupMemberView {
+    pub peer_id: String,
+    pub username: Option<String>,
+    pub alias: Option<String>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::crypto::ensure_x25519_identity;
+    use crate::database::models::PeerRecord;
+    use crate::errors::RequestError;
+    use rusqlite::Connection;
+    use tempfile::TempDir;
+
+    struct Node {
+        peer_id: String,
+        service: DmService,
+        database: Database,
+        paths: GraphchanPaths,
+        x25519_pubkey: String,
+        _keys: TempDir,
+    }
+
+    fn node(peer_id: &str) -> Node {
+        let keys = TempDir::new().unwrap();
+        let paths = GraphchanPaths {
+            keys_dir: keys.path().to_path_buf(),
+            ..Default::default()
+        };
+        let (x25519_pubkey, _) = ensure_x25519_identity(&paths).unwrap();
+        let database = Database::from_connection(Connection::open_in_memory().unwrap(), true);
+        database.ensure_migrations().unwrap();
+        database.save_identity(peer_id, "iroh", "friendcode").unwrap();
+        Node {
+            peer_id: peer_id.into(),
+            service: DmService::new(database.clone(), paths.clone()),
+            database,
+            paths,
+            x25519_pubkey,
+            _keys: keys,
+        }
+    }
+
+    fn add_peer(node: &Node, peer_id: &str, x25519_pubkey: Option<&str>) {
+        node.database
+            .with_repositories(|repos| {
+                repos.peers().upsert(&PeerRecord {
+                    id: peer_id.into(),
+                    alias: None,
+                    username: None,
+                    bio: None,
+                    friendcode: None,
+                    iroh_peer_id: None,
+                    gpg_fingerprint: Some(peer_id.into()),
+                    x25519_pubkey: x25519_pubkey.map(Into::into),
+                    last_seen: None,
+                    avatar_file_id: None,
+                    trust_state: "friend".into(),
+                    agents: None,
+                    gpg_public_key: None,
+                })
+            })
+            .unwrap();
+    }
+
+    /// Alice and Bob know each other; Bob's prekey bundle reached Alice.
+    fn alice_and_bob() -> (Node, Node) {
+        let alice = node("ALICE");
+        let bob = node("BOB");
+        for n in [&alice, &bob] {
+            add_peer(n, "ALICE", Some(&alice.x25519_pubkey));
+            add_peer(n, "BOB", Some(&bob.x25519_pubkey));
+        }
+        let prekey = ensure_dm_prekey(&bob.paths).unwrap();
+        let bundle = DmPrekeyBundle {
+            peer_id: "BOB".into(),
+            identity_key: bob.x25519_pubkey.clone(),
+            prekey_id: prekey.id,
+            prekey: BASE64_STANDARD.encode(prekey.public.as_bytes()),
+            created_at: prekey.created_at,
+            // Checked by network/ingest.rs before a bundle is stored
+            signature: String::new(),
+            signer_public_key: None,
+        };
+        alice
+            .database
+            .with_repositories(|repos| {
+                repos
+                    .dm_sessions()
+                    .set_prekey_bundle("BOB", &serde_json::to_string(&bundle)?, bundle.created_at)
+            })
+            .unwrap();
+        (alice, bob)
+    }
+
+    fn bodies(node: &Node, peer_id: &str) -> Vec<String> {
+        let mut bodies: Vec<String> = node
+            .service
+            .get_messages(peer_id, 50)
+            .unwrap()
+            .into_iter()
+            .map(|message| message.body)
+            .collect();
+        bodies.sort();
+        bodies
+    }
+
+    #[test]
+    fn dms_ratchet_once_a_prekey_is_known() {
+        let (alice, bob) = alice_and_bob();
+
+        let (_, opening) = alice.service.send_dm("BOB", "hi bob", None, &[]).unwrap();
+        let header = opening.ratchet.clone().expect("ratcheted");
+        assert!(header.prekey_id.is_some());
+        assert!(opening.nonce.is_empty());
+        bob.service.ingest_dm(&opening).unwrap();
+
+        let (_, reply) = bob.service.send_dm("ALICE", "hi alice", None, &[]).unwrap();
+        let reply_header = reply.ratchet.clone().expect("ratcheted");
+        assert_eq!(reply_header.session_id, header.session_id);
+        assert_eq!(reply_header.prekey_id, None);
+        alice.service.ingest_dm(&reply).unwrap();
+
+        // Both sides can still read their own messages and the other's
+        assert_eq!(bodies(&alice, "BOB"), ["hi alice", "hi bob"]);
+        assert_eq!(bodies(&bob, "ALICE"), ["hi alice", "hi bob"]);
+
+        // The opening message's key is used up
+        let mut replay = opening.clone();
+        replay.message_id = "replayed".into();
+        assert!(bob.service.ingest_dm(&replay).is_err());
+    }
+
+    #[test]
+    fn dms_fall_back_to_the_static_box_without_a_prekey() {
+        let (alice, bob) = alice_and_bob();
+
+        // Bob never got Alice's prekey, so he opens with the static box
+        let (_, legacy) = bob.service.send_dm("ALICE", "from an old client", None, &[]).unwrap();
+        assert!(legacy.ratchet.is_none());
+        assert_eq!(legacy.nonce.len(), 24);
+        alice.service.ingest_dm(&legacy).unwrap();
+        assert_eq!(bodies(&alice, "BOB"), ["from an old client"]);
+    }
+
+    /// Three nodes who all know each other; no prekeys, so copies use the static box.
+    fn alice_bob_and_carol() -> Vec<Node> {
+        let nodes = vec![node("ALICE"), node("BOB"), node("CAROL")];
+        for n in &nodes {
+            for other in &nodes {
+                add_peer(n, &other.peer_id, Some(&other.x25519_pubkey));
+            }
+        }
+        nodes
+    }
+
+    fn deliver(nodes: &[Node], events: Vec<DirectMessageEvent>) {
+        for event in events {
+            let recipient = nodes.iter().find(|n| n.peer_id == event.to_peer_id).unwrap();
+            recipient.service.ingest_dm(&event).unwrap();
+        }
+    }
+
+    fn group_bodies(node: &Node, group_id: &str) -> Vec<String> {
+        let mut bodies: Vec<String> = node
+            .service
+            .get_group_messages(group_id, 50)
+            .unwrap()
+            .into_iter()
+            .map(|message| message.body)
+            .collect();
+        bodies.sort();
+        bodies
+    }
+
+    #[test]
+    fn group_dms_reach_every_member() {
+        let nodes = alice_bob_and_carol();
+        let (alice, bob, carol) = (&nodes[0], &nodes[1], &nodes[2]);
+
+        let (group, events) = alice
+            .service
+            .create_group("planning", &["BOB".into(), "CAROL".into()])
+            .unwrap();
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().all(|event| event.group_id.as_deref() == Some(group.id.as_str())));
+        deliver(&nodes, events);
+        let bobs_view = bob.service.get_group(&group.id).unwrap().unwrap();
+        assert_eq!(bobs_view.name, "planning");
+        assert_eq!(bobs_view.members.len(), 3);
+
+        let (message, events) = bob.service.send_group_dm(&group.id, "hello all", None, &[]).unwrap();
+        assert_eq!(message.conversation_id, group.id);
+        assert_eq!(events.len(), 2);
+        deliver(&nodes, events);
+        for n in [alice, bob, carol] {
+            assert_eq!(group_bodies(n, &group.id), ["hello all"]);
+        }
+        assert_eq!(carol.service.get_group(&group.id).unwrap().unwrap().unread_count, 1);
+        assert_eq!(bob.service.get_group(&group.id).unwrap().unwrap().unread_count, 0);
+    }
+
+    #[test]
+    fn only_the_creator_changes_group_membership() {
+        let nodes = alice_bob_and_carol();
+        let (alice, bob, carol) = (&nodes[0], &nodes[1], &nodes[2]);
+        let (group, events) = alice
+            .service
+            .create_group("planning", &["BOB".into(), "CAROL".into()])
+            .unwrap();
+        deliver(&nodes, events);
+
+        let err = bob.service.update_group_members(&group.id, &[], &["CAROL".into()]).unwrap_err();
+        assert!(matches!(err.downcast_ref::<RequestError>(), Some(RequestError::Forbidden(_))));
+        let err = bob.service.get_group_messages("no-such-group", 10).unwrap_err();
+        assert!(matches!(err.downcast_ref::<RequestError>(), Some(RequestError::NotFound(_))));
+
+        // Carol is told she's out and can no longer post
+        let (view, events) = alice
+            .service
+            .update_group_members(&group.id, &[], &["CAROL".into()])
+            .unwrap();
+        assert_eq!(view.members.len(), 2);
+        assert_eq!(events.len(), 2);
+        deliver(&nodes, events);
+        assert!(!carol.service.get_group(&group.id).unwrap().unwrap().is_member);
+        assert!(carol.service.send_group_dm(&group.id, "still here?", None, &[]).is_err());
+
+        // Bob leaves; Alice stops sending to him
+        deliver(&nodes, bob.service.leave_group(&group.id).unwrap());
+        let (_, events) = alice.service.send_group_dm(&group.id, "just me", None, &[]).unwrap();
+        assert!(events.is_empty());
+        assert_eq!(group_bodies(bob, &group.id), Vec::<String>::new());
+    }
+
+    #[test]
+    fn membership_changes_from_other_members_are_ignored() {
+        let nodes = alice_bob_and_carol();
+        let (alice, bob) = (&nodes[0], &nodes[1]);
+        let (group, events) = alice.service.create_group("planning", &["BOB".into()]).unwrap();
+        deliver(&nodes, events);
+
+        // Bob forges a membership change naming himself as creator
+        let forged = GroupDmPayload::Membership {
+            name: "taken over".into(),
+            created_by: "BOB".into(),
+            members: vec!["BOB".into(), "ALICE".into(), "CAROL".into()],
+            version: 9,
+            created_at: now_utc_iso(),
+        };
+        deliver(&nodes, bob.service.fan_out(&group.id, &["ALICE".into()], &forged, "forged", &now_utc_iso()).unwrap());
+        let view = alice.service.get_group(&group.id).unwrap().unwrap();
+        assert_eq!(view.name, "planning");
+        assert_eq!(view.members.len(), 2);
+    }
+
+    #[test]
+    fn group_dms_before_the_member_list_wait_for_it() {
+        let mut nodes = alice_bob_and_carol();
+        nodes.push(node("DAVE"));
+        for n in &nodes {
+            for other in &nodes {
+                add_peer(n, &other.peer_id, Some(&other.x25519_pubkey));
+            }
+        }
+        let (alice, bob, carol, dave) = (&nodes[0], &nodes[1], &nodes[2], &nodes[3]);
+        let (group, events) = alice
+            .service
+            .create_group("planning", &["BOB".into(), "CAROL".into()])
+            .unwrap();
+        // Bob's copy of the member list is held up
+        let (to_bob, others): (Vec<_>, Vec<_>) = events.into_iter().partition(|event| event.to_peer_id == "BOB");
+        deliver(&nodes, others);
+
+        let (_, events) = carol.service.send_group_dm(&group.id, "from carol", None, &[]).unwrap();
+        deliver(&nodes, events);
+        let (_, events) = alice.service.send_group_dm(&group.id, "from alice", None, &[]).unwrap();
+        deliver(&nodes, events);
+        let intruder = GroupDmPayload::Message {
+            body: "from dave".into(),
+            reply_to: None,
+            attachments: Vec::new(),
+        };
+        deliver(&nodes, dave.service.fan_out(&group.id, &["BOB".into()], &intruder, "intruder", &now_utc_iso()).unwrap());
+        assert_eq!(group_bodies(bob, &group.id), ["from alice", "from carol", "from dave"]);
+
+        deliver(&nodes, to_bob);
+        let view = bob.service.get_group(&group.id).unwrap().unwrap();
+        assert_eq!(view.name, "planning");
+        assert_eq!(view.created_by, "ALICE");
+        assert_eq!(view.members.len(), 3);
+        assert_eq!(group_bodies(bob, &group.id), ["from alice", "from carol"]);
+    }
+
+    /// A finished upload of ours, as `FileService::save_dm_attachment` leaves it.
+    fn upload(node: &Node, id: &str) {
+        node.database
+            .with_repositories(|repos| {
+                repos.dm_attachments().create(&DmAttachmentRecord {
+                    id: id.into(),
+                    message_id: None,
+                    original_name: Some("notes.txt".into()),
+                    mime: Some("text/plain".into()),
+                    size_bytes: 5,
+                    blob_id: "b".repeat(64),
+                    ticket: Some("blob-ticket".into()),
+                    encryption_key: BASE64_STANDARD.encode([7u8; 32]),
+                    path: Some(format!("files/dm/{id}.txt")),
+                    download_status: "available".into(),
+                    created_at: now_utc_iso(),
+                })
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn replies_and_attachments_travel_inside_the_encrypted_dm() {
+        let (alice, bob) = alice_and_bob();
+        let (opening, event) = alice.service.send_dm("BOB", "hi bob", None, &[]).unwrap();
+        assert!(!event.structured);
+        bob.service.ingest_dm(&event).unwrap();
+
+        upload(&bob, "file-1");
+        let (reply, event) = bob
+            .service
+            .send_dm("ALICE", "", Some(&opening.id), &["file-1".into()])
+            .unwrap();
+        assert!(event.structured);
+        assert_eq!(reply.attachments.len(), 1);
+        // An upload goes out once
+        assert!(bob.service.send_dm("ALICE", "again", None, &["file-1".into()]).is_err());
+        assert!(bob.service.send_dm("ALICE", "which?", Some("unknown"), &[]).is_err());
+
+        alice.service.ingest_dm(&event).unwrap();
+        let received = alice
+            .service
+            .get_messages("BOB", 50)
+            .unwrap()
+            .into_iter()
+            .find(|message| message.id == reply.id)
+            .unwrap();
+        assert_eq!(received.reply_to.as_deref(), Some(opening.id.as_str()));
+        assert_eq!(received.attachments.len(), 1);
+        assert_eq!(received.attachments[0].download_status, "pending");
+        assert_eq!(received.attachments[0].original_name.as_deref(), Some("notes.txt"));
+
+        // The key and ticket came through for the download, under an ID of our own
+        assert_ne!(received.attachments[0].id, "file-1");
+        let stored = alice
+            .database
+            .with_repositories(|repos| repos.dm_attachments().get(&received.attachments[0].id))
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.message_id.as_deref(), Some(reply.id.as_str()));
+        assert_eq!(stored.ticket.as_deref(), Some("blob-ticket"));
+        assert_eq!(stored.encryption_key, BASE64_STANDARD.encode([7u8; 32]));
+        let conversation = &alice.service.list_conversations().unwrap()[0];
+        assert_eq!(conversation.last_message_preview.as_deref(), Some("[1 attachment]"));
+    }
+
+    #[test]
+    fn received_attachment_ids_never_name_paths() {
+        let (alice, bob) = alice_and_bob();
+        upload(&bob, "../../../escape");
+        let (sent, event) = bob
+            .service
+            .send_dm("ALICE", "look", None, &["../../../escape".into()])
+            .unwrap();
+        alice.service.ingest_dm(&event).unwrap();
+
+        let received = alice
+            .service
+            .get_messages("BOB", 50)
+            .unwrap()
+            .into_iter()
+            .find(|message| message.id == sent.id)
+            .unwrap();
+        assert_eq!(received.attachments.len(), 1);
+        assert!(Uuid::parse_str(&received.attachments[0].id).is_ok());
+        assert!(alice
+            .database
+            .with_repositories(|repos| repos.dm_attachments().get("../../../escape"))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn group_replies_point_at_each_members_copy() {
+        let nodes = alice_bob_and_carol();
+        let (alice, bob, carol) = (&nodes[0], &nodes[1], &nodes[2]);
+        let (group, events) = alice
+            .service
+            .create_group("planning", &["BOB".into(), "CAROL".into()])
+            .unwrap();
+        deliver(&nodes, events);
+        let (question, events) = bob.service.send_group_dm(&group.id, "lunch?", None, &[]).unwrap();
+        deliver(&nodes, events);
+
+        // Carol answers her copy of Bob's message
+        let carols_copy = group_copy_id(&question.id, "CAROL");
+        let (_, events) = carol
+            .service
+            .send_group_dm(&group.id, "yes", Some(&carols_copy), &[])
+            .unwrap();
+        deliver(&nodes, events);
+
+        let reply_to = |node: &Node| {
+            node.service
+                .get_group_messages(&group.id, 50)
+                .unwrap()
+                .into_iter()
+                .find(|message| message.body == "yes")
+                .and_then(|message| message.reply_to)
+        };
+        assert_eq!(reply_to(alice), Some(group_copy_id(&question.id, "ALICE")));
+        assert_eq!(reply_to(bob), Some(question.id.clone()));
+        assert_eq!(reply_to(carol), Some(carols_copy));
+    }
 
     #[test]
     fn test_conversation_id_is_deterministic() {
@@ -0,0 +1,47 @@
+use thiserror::Error;
+
+/// A failure caused by the request rather than by the node.
+///
+/// Services return these inside `anyhow::Error`; `api::ApiError` downcasts them
+/// to pick a status code instead of matching on message text.
+#[derive(Debug, Error)]
+pub enum RequestError {
+    /// The thread, post, peer or other record the request names does not exist.
+    #[error("{0}")]
+    NotFound(String),
+    /// The request is understood but not valid for the current state.
+    #[error("{0}")]
+    Invalid(String),
+    /// The caller is not allowed to do this.
+    #[error("{0}")]
+    Forbidden(String),
+}
+
+pub fn not_found(message: impl Into<String>) -> anyhow::Error {
+    RequestError::NotFound(message.into()).into()
+}
+
+pub fn invalid(message: impl Into<String>) -> anyhow::Error {
+    RequestError::Invalid(message.into()).into()
+}
+
+pub fn forbidden(message: impl Into<String>) -> anyhow::Error {
+    RequestError::Forbidden(message.into()).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn survives_added_context() {
+        let err = Err::<(), _>(not_found("thread not found"))
+            .context("loading members")
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<RequestError>(),
+            Some(RequestError::NotFound(message)) if message == "thread not found"
+        ));
+    }
+}
@@ -1,14 +1,24 @@
 use crate::config::{FileConfig, GraphchanPaths};
-use crate::database::models::FileRecord;
-use crate::database::repositories::{FileRepository, PostRepository};
+use crate::crypto::encrypt_thread_blob;
+use crate::database::models::{DmAttachmentRecord, FileRecord};
+use crate::database::repositories::{DmAttachmentRepository, FileRepository, PostRepository};
 use crate::database::Database;
+use crate::dms::DmAttachmentView;
+use crate::errors::invalid;
+use crate::private_threads::{open_attachment, PrivateThreadService};
+use crate::utils::now_utc_iso;
 use anyhow::{anyhow, Context, Result};
+use base64::prelude::*;
 use bytes::Bytes;
 use infer::Infer;
+use iroh::Endpoint;
 use iroh_blobs::store::fs::FsStore;
 use iroh_blobs::ticket::BlobTicket;
+use iroh_blobs::Hash;
+use rand::RngCore;
 use serde::Serialize;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use tokio::fs;
 use uuid::Uuid;
 
@@ -50,7 +60,7 @@ impl FileService {
         }
 
         let post_id = input.post_id.clone();
-        self.ensure_post_exists(&post_id)?;
+        let thread_id = self.post_thread_id(&post_id)?;
 
         let file_id = Uuid::new_v4().to_string();
         let original_name = input.original_name.as_deref().map(sanitize_filename);
@@ -80,9 +90,17 @@ impl FileService {
                 )
             })?;
 
+        // Attachments of private threads are shared as ciphertext; the local copy stays plain
+        let sealed = PrivateThreadService::new(self.database.clone(), self.paths.clone())
+            .seal_attachment(&thread_id, &file_id, bytes.as_ref())?;
+        let (blob_bytes, encryption_key) = match sealed {
+            Some((ciphertext, key)) => (Bytes::from(ciphertext), Some(key)),
+            None => (bytes.clone(), None),
+        };
+
         let mut temp_tag = self
             .blobs
-            .add_bytes(bytes.clone())
+            .add_bytes(blob_bytes)
             .temp_tag()
             .await
             .context("failed to store blob in iroh-blobs store")?;
@@ -91,7 +109,11 @@ impl FileService {
         temp_tag.leak();
 
         let size_bytes = bytes.len() as i64;
-        let checksum = Some(format!("blake3:{}", blob_hex));
+        let checksum = if encryption_key.is_some() {
+            Some(format!("blake3:{}", blake3::hash(bytes.as_ref()).to_hex()))
+        } else {
+            Some(format!("blake3:{}", blob_hex))
+        };
         let blob_id = Some(blob_hex.clone());
         let detected_mime = input.mime.clone().or_else(|| infer_mime(bytes.as_ref()));
 
@@ -106,6 +128,7 @@ impl FileService {
             checksum: checksum.clone(),
             ticket: None,
             download_status: Some("available".to_string()),
+            encryption_key,
         };
 
         self.database.with_repositories(|repos| {
@@ -184,12 +207,163 @@ impl FileService {
         })
     }
 
-    fn ensure_post_exists(&self, post_id: &str) -> Result<()> {
+    /// Stores a file to be sent with a DM, encrypted with a fresh random key.
+    ///
+    /// The blob holds only ciphertext; the key travels inside the DM that
+    /// references the attachment. Our own copy stays plain.
+    pub async fn save_dm_attachment(&self, input: SaveDmAttachmentInput) -> Result<DmAttachmentView> {
+        if input.data.is_empty() {
+            return Err(invalid("file data may not be empty"));
+        }
+        if let Some(limit) = self.config.max_upload_bytes {
+            if (input.data.len() as u64) > limit {
+                return Err(invalid(format!(
+                    "file exceeds configured maximum of {} bytes",
+                    limit
+                )));
+            }
+        }
+
+        let id = Uuid::new_v4().to_string();
+        let original_name = input.original_name.as_deref().map(sanitize_filename);
+        let relative_path = dm_attachment_path(&id, original_name.as_deref())?;
+        let absolute_path = self.paths.base.join(&relative_path);
+        if let Some(parent) = absolute_path.parent() {
+            fs::create_dir_all(parent).await.with_context(|| {
+                format!("failed to create DM attachment directory {}", parent.display())
+            })?;
+        }
+        fs::write(&absolute_path, &input.data)
+            .await
+            .with_context(|| format!("failed to write DM attachment to {}", absolute_path.display()))?;
+
+        let mut key = [0u8; 32];
+        rand::rng().fill_bytes(&mut key);
+        let ciphertext = encrypt_thread_blob(&input.data, &key)?;
+        let blob_id = self.import_blob(ciphertext).await?;
+
+        let record = DmAttachmentRecord {
+            id,
+            message_id: None,
+            original_name,
+            mime: input.mime.clone().or_else(|| infer_mime(&input.data)),
+            size_bytes: input.data.len() as i64,
+            blob_id,
+            ticket: None,
+            encryption_key: BASE64_STANDARD.encode(key),
+            path: Some(relative_path),
+            download_status: "available".to_string(),
+            created_at: now_utc_iso(),
+        };
+        self.database
+            .with_repositories(|repos| repos.dm_attachments().create(&record))?;
+        Ok(DmAttachmentView::from_record(record))
+    }
+
+    pub fn persist_dm_attachment_ticket(&self, id: &str, ticket: &BlobTicket) -> Result<()> {
+        self.database
+            .with_repositories(|repos| repos.dm_attachments().set_ticket(id, &ticket.to_string()))
+    }
+
+    /// Fetches a received DM attachment from the sender and decrypts it.
+    pub async fn fetch_dm_attachment(&self, id: &str, endpoint: &Endpoint) -> Result<()> {
+        let record = self.dm_attachment(id)?;
+        let ticket = record
+            .ticket
+            .as_deref()
+            .ok_or_else(|| anyhow!("attachment {id} has no blob ticket"))
+            .and_then(|ticket| BlobTicket::from_str(ticket).context("invalid attachment ticket"))?;
+
         self.database.with_repositories(|repos| {
-            if repos.posts().get(post_id)?.is_none() {
-                return Err(anyhow!("post not found"));
+            repos.dm_attachments().set_download_state(id, None, "downloading")
+        })?;
+        let result = async {
+            if !self.blobs.has(ticket.hash()).await.context("failed to check blob existence")? {
+                self.blobs
+                    .downloader(endpoint)
+                    .download(ticket.hash(), Some(ticket.addr().id))
+                    .await
+                    .context("failed to download blob")?;
             }
-            Ok(())
+            self.open_dm_attachment(&record).await
+        }
+        .await;
+
+        if let Err(err) = &result {
+            tracing::warn!(attachment_id = %id, error = ?err, "DM attachment download failed");
+            self.database.with_repositories(|repos| {
+                repos.dm_attachments().set_download_state(id, None, "failed")
+            })?;
+        }
+        result
+    }
+
+    /// Decrypts an attachment whose blob is in our store into a plain local copy.
+    async fn open_dm_attachment(&self, record: &DmAttachmentRecord) -> Result<()> {
+        let hash: Hash = record
+            .blob_id
+            .parse()
+            .map_err(|_| anyhow!("invalid blob id {}", record.blob_id))?;
+        let relative_path = dm_attachment_path(&record.id, record.original_name.as_deref())?;
+        let absolute_path = self.paths.base.join(&relative_path);
+        if let Some(parent) = absolute_path.parent() {
+            fs::create_dir_all(parent).await.with_context(|| {
+                format!("failed to create DM attachment directory {}", parent.display())
+            })?;
+        }
+        self.blobs
+            .export(hash, absolute_path.clone())
+            .await
+            .with_context(|| format!("failed to export blob to {}", absolute_path.display()))?;
+
+        let ciphertext = fs::read(&absolute_path)
+            .await
+            .with_context(|| format!("failed to read exported file {}", absolute_path.display()))?;
+        let data = open_attachment(&record.encryption_key, &ciphertext)
+            .with_context(|| format!("failed to decrypt DM attachment {}", record.id))?;
+        fs::write(&absolute_path, &data)
+            .await
+            .with_context(|| format!("failed to write decrypted file {}", absolute_path.display()))?;
+
+        self.database.with_repositories(|repos| {
+            repos
+                .dm_attachments()
+                .set_download_state(&record.id, Some(&relative_path), "available")
+        })
+    }
+
+    /// The plain local copy of a DM attachment, once uploaded or downloaded.
+    pub fn prepare_dm_attachment_download(&self, id: &str) -> Result<Option<(DmAttachmentView, PathBuf)>> {
+        let record = self
+            .database
+            .with_repositories(|repos| repos.dm_attachments().get(id))?;
+        let Some(record) = record else {
+            return Ok(None);
+        };
+        let Some(path) = record.path.as_deref().filter(|_| record.download_status == "available") else {
+            return Ok(None);
+        };
+        let absolute_path = self.paths.base.join(path);
+        if !absolute_path.exists() {
+            tracing::warn!(path = %absolute_path.display(), "DM attachment missing on disk");
+            return Ok(None);
+        }
+        Ok(Some((DmAttachmentView::from_record(record), absolute_path)))
+    }
+
+    fn dm_attachment(&self, id: &str) -> Result<DmAttachmentRecord> {
+        self.database
+            .with_repositories(|repos| repos.dm_attachments().get(id))?
+            .ok_or_else(|| anyhow!("attachment not found: {id}"))
+    }
+
+    fn post_thread_id(&self, post_id: &str) -> Result<String> {
+        self.database.with_repositories(|repos| {
+            repos
+                .posts()
+                .get(post_id)?
+                .map(|post| post.thread_id)
+                .ok_or_else(|| anyhow!("post not found"))
         })
     }
 }
@@ -202,6 +376,13 @@ pub struct SaveFileInput {
     pub data: Vec<u8>,
 }
 
+#[derive(Debug, Clone)]
+pub struct SaveDmAttachmentInput {
+    pub original_name: Option<String>,
+    pub mime: Option<String>,
+    pub data: Vec<u8>,
+}
+
 #[derive(Debug, Clone, Serialize, serde::Deserialize)]
 pub struct FileView {
     pub id: String,
@@ -216,6 +397,9 @@ pub struct FileView {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub present: Option<bool>,
     pub download_status: Option<String>,
+    /// Per-file key for private thread attachments; only travels inside encrypted snapshots
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encryption_key: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -238,10 +422,35 @@ impl FileView {
             path: record.path,
             present: None,
             download_status: record.download_status,
+            encryption_key: record.encryption_key,
         }
     }
 }
 
+/// Where a DM attachment's plain copy lives, relative to the base directory.
+///
+/// IDs are local UUIDs; anything else could name a path outside `files/dm`.
+fn dm_attachment_path(id: &str, original_name: Option<&str>) -> Result<String> {
+    let id = Uuid::parse_str(id).map_err(|_| anyhow!("invalid DM attachment id {id:?}"))?;
+    Ok(match original_name
+        .and_then(|name| Path::new(name).extension().and_then(|ext| ext.to_str()))
+        .filter(|ext| !ext.is_empty() && ext.chars().all(|c| c.is_ascii_alphanumeric()))
+    {
+        Some(ext) => format!("files/dm/{id}.{ext}"),
+        None => format!("files/dm/{id}"),
+    })
+}
+
+/// Where a downloaded thread file lives, relative to the base directory.
+///
+/// File IDs come from the network, so only plain names are accepted.
+pub(crate) fn download_path(file_id: &str) -> Result<String> {
+    if file_id.is_empty() || !file_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        anyhow::bail!("invalid file id {file_id:?}");
+    }
+    Ok(format!("files/downloads/{file_id}"))
+}
+
 fn sanitize_filename(name: &str) -> String {
     Path::new(name)
         .file_name()
@@ -490,4 +699,76 @@ mod tests {
             assert!(result.is_err());
         });
     }
+
+    #[test]
+    fn dm_attachments_are_stored_encrypted_and_opened_with_their_key() {
+        let rt = Runtime::new().unwrap();
+        rt.block_on(async {
+            let temp = tempdir().expect("tempdir");
+            let paths = GraphchanPaths::from_base_dir(temp.path()).expect("paths");
+            let conn = Connection::open_in_memory().expect("db");
+            let db = Database::from_connection(conn, true);
+            db.ensure_migrations().expect("migrations");
+
+            let blob_store = FsStore::load(&paths.blobs_dir).await.expect("blob store");
+            let service = FileService::new(
+                db.clone(),
+                paths.clone(),
+                FileConfig::default(),
+                blob_store.clone(),
+            );
+            let view = service
+                .save_dm_attachment(SaveDmAttachmentInput {
+                    original_name: Some("notes.txt".into()),
+                    mime: None,
+                    data: b"hello".to_vec(),
+                })
+                .await
+                .expect("save attachment");
+            assert_eq!(view.size_bytes, 5);
+            assert_eq!(view.download_status, "available");
+
+            // The blob peers fetch is ciphertext
+            let record = db
+                .with_repositories(|repos| repos.dm_attachments().get(&view.id))
+                .expect("query")
+                .expect("record");
+            let exported = temp.path().join("exported");
+            let hash: Hash = record.blob_id.parse().expect("hash");
+            blob_store.export(hash, exported.clone()).await.expect("export");
+            let ciphertext = std::fs::read(&exported).expect("read blob");
+            assert_ne!(ciphertext, b"hello");
+            assert_eq!(open_attachment(&record.encryption_key, &ciphertext).expect("open"), b"hello");
+
+            // A recipient holding the blob and the key from the DM gets the plain file
+            let received_id = Uuid::new_v4().to_string();
+            let received = DmAttachmentRecord {
+                id: received_id.clone(),
+                path: None,
+                download_status: "pending".into(),
+                ..record
+            };
+            db.with_repositories(|repos| repos.dm_attachments().create(&received))
+                .expect("record received");
+            assert!(service.prepare_dm_attachment_download(&received_id).expect("prepare").is_none());
+            service.open_dm_attachment(&received).await.expect("open attachment");
+            let (opened, path) = service
+                .prepare_dm_attachment_download(&received_id)
+                .expect("prepare")
+                .expect("available");
+            assert_eq!(opened.download_status, "available");
+            assert_eq!(std::fs::read(path).expect("read file"), b"hello");
+        });
+    }
+
+    #[test]
+    fn stored_paths_reject_traversal_ids() {
+        let id = Uuid::new_v4().to_string();
+        assert_eq!(dm_attachment_path(&id, Some("notes.txt")).unwrap(), format!("files/dm/{id}.txt"));
+        assert_eq!(dm_attachment_path(&id, Some("notes.t%t")).unwrap(), format!("files/dm/{id}"));
+        assert!(dm_attachment_path("../../escape", None).is_err());
+        assert_eq!(download_path("file-1").unwrap(), "files/downloads/file-1");
+        assert!(download_path("../escape").is_err());
+        assert!(download_path("").is_err());
+    }
 }
@@ -70,6 +70,7 @@ fn ensure_gpg_identity(paths: &GraphchanPaths) -> Result<(String, bool)> {
     if fingerprint_path.exists() {
         let fingerprint = fs::read_to_string(&fingerprint_path)?.trim().to_string();
         if !fingerprint.is_empty() {
+            ensure_signing_subkey(paths)?;
             return Ok((fingerprint, false));
         }
     }
@@ -89,11 +90,12 @@ fn generate_gpg_identity(paths: &GraphchanPaths) -> Result<String> {
     let user_id = format!("{uid} <{email}>");
 
     // Generate a new Cert (Primary Key + Subkeys)
-    // We want Ed25519 for signing (primary) and CV25519 for encryption (subkey)
-    // set_cipher_suite(Cv25519) creates an Ed25519 primary key (Sign, Certify) and a Cv25519 subkey (Encrypt)
+    // set_cipher_suite(Cv25519) gives an Ed25519 certification-only primary key, so
+    // we add an explicit Ed25519 signing subkey for post/reaction signatures
     let (cert, _revocation) = CertBuilder::new()
         .add_userid(user_id.as_str())
         .set_cipher_suite(openpgp::cert::CipherSuite::Cv25519)
+        .add_signing_subkey()
         .generate()?;
 
     let fingerprint = cert.fingerprint().to_string();
@@ -111,6 +113,61 @@ fn generate_gpg_identity(paths: &GraphchanPaths) -> Result<String> {
     Ok(fingerprint)
 }
 
+/// Upgrades identities created before signing was wired up.
+///
+/// Older certs only had a certification-capable primary key. We bind a new Ed25519
+/// signing subkey to the existing primary so the fingerprint (peer ID) is unchanged.
+fn ensure_signing_subkey(paths: &GraphchanPaths) -> Result<()> {
+    use openpgp::packet::key::{Key4, SecretParts, SubordinateRole};
+    use openpgp::packet::signature::SignatureBuilder;
+    use openpgp::packet::Key;
+    use openpgp::parse::Parse;
+    use openpgp::policy::StandardPolicy;
+    use openpgp::types::{Curve, KeyFlags, SignatureType};
+
+    if !paths.gpg_private_key.exists() {
+        return Ok(());
+    }
+    let cert = openpgp::Cert::from_file(&paths.gpg_private_key)?;
+    let policy = StandardPolicy::new();
+    let can_sign = cert
+        .keys()
+        .unencrypted_secret()
+        .with_policy(&policy, None)
+        .supported()
+        .alive()
+        .revoked(false)
+        .for_signing()
+        .next()
+        .is_some();
+    if can_sign {
+        return Ok(());
+    }
+
+    tracing::info!("adding signing subkey to existing OpenPGP identity");
+    let mut primary_signer = cert
+        .primary_key()
+        .key()
+        .clone()
+        .parts_into_secret()?
+        .into_keypair()?;
+    let subkey: Key<SecretParts, SubordinateRole> = Key4::generate_ecc(true, Curve::Ed25519)?.into();
+    let mut subkey_signer = subkey.clone().into_keypair()?;
+    let backsig = SignatureBuilder::new(SignatureType::PrimaryKeyBinding)
+        .sign_primary_key_binding(&mut subkey_signer, cert.primary_key().key(), &subkey)?;
+    let template = SignatureBuilder::new(SignatureType::SubkeyBinding)
+        .set_key_flags(KeyFlags::empty().set_signing())?
+        .set_embedded_signature(backsig)?;
+    let binding = subkey.bind(&mut primary_signer, &cert, template)?;
+    let (cert, _) = cert.insert_packets(vec![openpgp::Packet::from(subkey), binding.into()])?;
+
+    cert.armored().serialize(&mut fs::File::create(&paths.gpg_public_key)?)?;
+    cert.as_tsk().armored().serialize(&mut fs::File::create(&paths.gpg_private_key)?)?;
+    tighten_permissions(&paths.gpg_private_key)?;
+    tighten_permissions(&paths.gpg_public_key)?;
+    Ok(())
+}
+
 fn tighten_permissions(path: &Path) -> Result<()> {
     #[cfg(unix)]
     {
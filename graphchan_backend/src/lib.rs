@@ -1,4 +1,6 @@
+pub mod agents;
 pub mod api;
+pub mod api_tokens;
 pub mod blocking;
 pub mod bootstrap;
 pub mod cli;
@@ -6,12 +8,18 @@ pub mod config;
 pub mod crypto;
 pub mod database;
 pub mod dms;
+pub mod errors;
 pub mod files;
 pub mod identity;
 pub mod importer;
+pub mod live;
 pub mod network;
 pub mod node;
 pub mod peers;
+pub mod pow;
+pub mod private_threads;
 pub mod telemetry;
 pub mod threading;
+pub mod topic_config;
+pub mod trust;
 pub mod utils;
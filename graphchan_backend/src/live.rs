@@ -0,0 +1,349 @@
+use crate::database::models::LiveEventRecord;
+use crate::database::repositories::{LiveEventRepository, TopicRepository};
+use crate::database::Database;
+use crate::utils::now_utc_iso;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// Events kept for resuming clients; older ones are pruned on append.
+pub const RETAINED_EVENTS: i64 = 10_000;
+
+/// Something ingest applied that API clients may want pushed to them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LiveEventData {
+    /// A new thread, or a new version of one, was announced to us
+    ThreadAnnounced {
+        thread_id: String,
+        title: String,
+        creator_peer_id: String,
+        topics: Vec<String>,
+    },
+    /// A gossiped post was stored (quarantined posts are not reported)
+    PostReceived {
+        post_id: String,
+        thread_id: String,
+        author_peer_id: Option<String>,
+    },
+    PostEdited {
+        post_id: String,
+        thread_id: String,
+        author_peer_id: String,
+        deleted: bool,
+    },
+    ReactionChanged {
+        post_id: String,
+        thread_id: String,
+        reactor_peer_id: String,
+        emoji: String,
+        removed: bool,
+    },
+    /// A DM (or group DM) addressed to us arrived; the body stays behind `GET /dms/...`
+    DirectMessage {
+        message_id: String,
+        conversation_id: String,
+        from_peer_id: String,
+        group_id: Option<String>,
+    },
+    /// `status` follows `files.download_status`: "downloading", "available" or "failed"
+    FileDownload {
+        file_id: String,
+        post_id: String,
+        thread_id: String,
+        status: String,
+    },
+    BlockChanged {
+        blocker_peer_id: String,
+        blocked_peer_id: String,
+        unblocked: bool,
+    },
+}
+
+impl LiveEventData {
+    pub const KINDS: &'static [&'static str] = &[
+        "thread_announced",
+        "post_received",
+        "post_edited",
+        "reaction_changed",
+        "direct_message",
+        "file_download",
+        "block_changed",
+    ];
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            LiveEventData::ThreadAnnounced { .. } => "thread_announced",
+            LiveEventData::PostReceived { .. } => "post_received",
+            LiveEventData::PostEdited { .. } => "post_edited",
+            LiveEventData::ReactionChanged { .. } => "reaction_changed",
+            LiveEventData::DirectMessage { .. } => "direct_message",
+            LiveEventData::FileDownload { .. } => "file_download",
+            LiveEventData::BlockChanged { .. } => "block_changed",
+        }
+    }
+
+    pub fn thread_id(&self) -> Option<&str> {
+        match self {
+            LiveEventData::ThreadAnnounced { thread_id, .. }
+            | LiveEventData::PostReceived { thread_id, .. }
+            | LiveEventData::PostEdited { thread_id, .. }
+            | LiveEventData::ReactionChanged { thread_id, .. }
+            | LiveEventData::FileDownload { thread_id, .. } => Some(thread_id),
+            LiveEventData::DirectMessage { .. } | LiveEventData::BlockChanged { .. } => None,
+        }
+    }
+
+    /// The peer the event is about, for `peer` filters.
+    pub fn peer_id(&self) -> Option<&str> {
+        match self {
+            LiveEventData::ThreadAnnounced { creator_peer_id, .. } => Some(creator_peer_id),
+            LiveEventData::PostReceived { author_peer_id, .. } => author_peer_id.as_deref(),
+            LiveEventData::PostEdited { author_peer_id, .. } => Some(author_peer_id),
+            LiveEventData::ReactionChanged { reactor_peer_id, .. } => Some(reactor_peer_id),
+            LiveEventData::DirectMessage { from_peer_id, .. } => Some(from_peer_id),
+            LiveEventData::FileDownload { .. } => None,
+            LiveEventData::BlockChanged { blocker_peer_id, .. } => Some(blocker_peer_id),
+        }
+    }
+}
+
+/// A logged event as clients receive it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LiveEvent {
+    pub seq: i64,
+    pub created_at: String,
+    /// Topics of the event's thread, if any
+    pub topics: Vec<String>,
+    #[serde(flatten)]
+    pub data: LiveEventData,
+}
+
+/// Which events a client wants; unset fields match everything.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LiveFilter {
+    pub thread_id: Option<String>,
+    pub topic: Option<String>,
+    pub peer_id: Option<String>,
+    pub kinds: Option<Vec<String>>,
+    /// Leaves out `direct_message` events, for clients without the DM scope
+    pub hide_direct_messages: bool,
+}
+
+impl LiveFilter {
+    /// Rejects kinds that no event has, so typos don't silently match nothing.
+    pub fn validate(&self) -> Result<()> {
+        for kind in self.kinds.iter().flatten() {
+            if !LiveEventData::KINDS.contains(&kind.as_str()) {
+                bail!("unknown event type: {kind}");
+            }
+        }
+        Ok(())
+    }
+
+    pub fn matches(&self, event: &LiveEvent) -> bool {
+        self.thread_id
+            .as_deref()
+            .map_or(true, |thread| event.data.thread_id() == Some(thread))
+            && self.topic.as_ref().map_or(true, |topic| event.topics.contains(topic))
+            && self
+                .peer_id
+                .as_deref()
+                .map_or(true, |peer| event.data.peer_id() == Some(peer))
+            && self
+                .kinds
+                .as_ref()
+                .map_or(true, |kinds| kinds.iter().any(|kind| kind == event.data.kind()))
+            && !(self.hide_direct_messages && matches!(event.data, LiveEventData::DirectMessage { .. }))
+    }
+}
+
+/// Append-only log of ingest events, read by `GET /events`.
+///
+/// Events are persisted with an increasing `seq` so a client that reconnects
+/// with its last seen `seq` gets everything it missed, as long as it is still
+/// among the newest `RETAINED_EVENTS`.
+#[derive(Clone)]
+pub struct LiveEventService {
+    database: Database,
+}
+
+impl LiveEventService {
+    pub fn new(database: Database) -> Self {
+        Self { database }
+    }
+
+    /// Logs an event. Failures are only logged: the stream must never hold up ingest.
+    pub fn publish(&self, data: LiveEventData) {
+        if let Err(err) = self.append(data) {
+            tracing::warn!(error = ?err, "failed to log live event");
+        }
+    }
+
+    pub fn append(&self, data: LiveEventData) -> Result<i64> {
+        let kind = data.kind();
+        let body = serde_json::to_string(&data).context("failed to encode live event")?;
+        self.database.with_repositories(|repos| {
+            let topics = match &data {
+                LiveEventData::ThreadAnnounced { topics, .. } => topics.clone(),
+                other => match other.thread_id() {
+                    Some(thread_id) => repos.topics().list_thread_topics(thread_id)?,
+                    None => Vec::new(),
+                },
+            };
+            let seq = repos.live_events().append(&LiveEventRecord {
+                seq: 0,
+                kind: kind.to_string(),
+                thread_id: data.thread_id().map(str::to_string),
+                peer_id: data.peer_id().map(str::to_string),
+                topics: serde_json::to_string(&topics)?,
+                data: body,
+                created_at: now_utc_iso(),
+            })?;
+            if seq > RETAINED_EVENTS {
+                repos.live_events().prune_through(seq - RETAINED_EVENTS)?;
+            }
+            Ok(seq)
+        })
+    }
+
+    /// Up to `limit` events after `cursor`, oldest first.
+    pub fn after(&self, cursor: i64, limit: usize) -> Result<Vec<LiveEvent>> {
+        let records = self
+            .database
+            .with_repositories(|repos| repos.live_events().list_after(cursor, limit))?;
+        records
+            .into_iter()
+            .map(|record| {
+                Ok(LiveEvent {
+                    seq: record.seq,
+                    created_at: record.created_at,
+                    topics: serde_json::from_str(&record.topics).unwrap_or_default(),
+                    data: serde_json::from_str(&record.data)
+                        .with_context(|| format!("corrupt live event {}", record.seq))?,
+                })
+            })
+            .collect()
+    }
+
+    /// Cursor for "only what happens from now on".
+    pub fn latest_seq(&self) -> Result<i64> {
+        self.database.with_repositories(|repos| repos.live_events().latest_seq())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::ThreadRecord;
+    use crate::database::repositories::ThreadRepository;
+    use rusqlite::Connection;
+
+    fn service() -> LiveEventService {
+        let conn = Connection::open_in_memory().unwrap();
+        let database = Database::from_connection(conn, true);
+        database.ensure_migrations().unwrap();
+        LiveEventService::new(database)
+    }
+
+    fn post(post_id: &str, thread_id: &str, author: &str) -> LiveEventData {
+        LiveEventData::PostReceived {
+            post_id: post_id.into(),
+            thread_id: thread_id.into(),
+            author_peer_id: Some(author.into()),
+        }
+    }
+
+    #[test]
+    fn events_resume_from_cursor_and_filter() {
+        let service = service();
+        assert_eq!(service.latest_seq().unwrap(), 0);
+
+        let first = service
+            .append(LiveEventData::ThreadAnnounced {
+                thread_id: "t1".into(),
+                title: "Cats".into(),
+                creator_peer_id: "ALICE".into(),
+                topics: vec!["cats".into()],
+            })
+            .unwrap();
+        service
+            .database
+            .with_repositories(|repos| {
+                repos.threads().create(&ThreadRecord {
+                    id: "t1".into(),
+                    title: "Cats".into(),
+                    creator_peer_id: Some("ALICE".into()),
+                    created_at: "2026-01-01T00:00:00Z".into(),
+                    pinned: false,
+                    thread_hash: None,
+                    visibility: "social".into(),
+                    topic_secret: None,
+                    sync_status: "downloaded".into(),
+                    source_url: None,
+                    source_platform: None,
+                    last_refreshed_at: None,
+                })?;
+                repos.topics().add_thread_topic("t1", "cats")
+            })
+            .unwrap();
+        let second = service.append(post("p1", "t1", "BOB")).unwrap();
+        service.append(post("p2", "t2", "ALICE")).unwrap();
+        assert_eq!(service.latest_seq().unwrap(), second + 1);
+
+        let resumed = service.after(first, 100).unwrap();
+        assert_eq!(resumed.len(), 2);
+        assert_eq!(resumed[0].seq, second);
+        // Post events pick up their thread's topics
+        assert_eq!(resumed[0].topics, vec!["cats".to_string()]);
+
+        let on_topic = LiveFilter { topic: Some("cats".into()), ..Default::default() };
+        let by_alice = LiveFilter { peer_id: Some("ALICE".into()), ..Default::default() };
+        let posts_in_t1 = LiveFilter {
+            thread_id: Some("t1".into()),
+            kinds: Some(vec!["post_received".into()]),
+            ..Default::default()
+        };
+        let all = service.after(0, 100).unwrap();
+        let seqs = |filter: &LiveFilter| all.iter().filter(|e| filter.matches(e)).map(|e| e.seq).collect::<Vec<_>>();
+        assert_eq!(seqs(&on_topic), vec![first, second]);
+        assert_eq!(seqs(&by_alice), vec![first, second + 1]);
+        assert_eq!(seqs(&posts_in_t1), vec![second]);
+
+        assert!(LiveFilter { kinds: Some(vec!["posts".into()]), ..Default::default() }.validate().is_err());
+        assert!(posts_in_t1.validate().is_ok());
+    }
+
+    #[test]
+    fn direct_messages_can_be_hidden() {
+        let service = service();
+        let post_seq = service.append(post("p1", "t1", "BOB")).unwrap();
+        let dm_seq = service
+            .append(LiveEventData::DirectMessage {
+                message_id: "m1".into(),
+                conversation_id: "c1".into(),
+                from_peer_id: "BOB".into(),
+                group_id: None,
+            })
+            .unwrap();
+        let all = service.after(0, 100).unwrap();
+        let seqs = |filter: &LiveFilter| all.iter().filter(|e| filter.matches(e)).map(|e| e.seq).collect::<Vec<_>>();
+        assert_eq!(seqs(&LiveFilter::default()), vec![post_seq, dm_seq]);
+        let without_dms = LiveFilter { hide_direct_messages: true, ..Default::default() };
+        assert_eq!(seqs(&without_dms), vec![post_seq]);
+        // Asking for them by type doesn't get around it
+        let only_dms = LiveFilter { kinds: Some(vec!["direct_message".into()]), ..without_dms };
+        assert!(seqs(&only_dms).is_empty());
+    }
+
+    #[test]
+    fn old_events_are_pruned_but_cursor_keeps_counting() {
+        let service = service();
+        for i in 0..(RETAINED_EVENTS + 3) {
+            service.append(post(&format!("p{i}"), "t1", "BOB")).unwrap();
+        }
+        let retained = service.after(0, 20_000).unwrap();
+        assert_eq!(retained.len() as i64, RETAINED_EVENTS);
+        assert_eq!(retained[0].seq, 4);
+        assert_eq!(service.latest_seq().unwrap(), RETAINED_EVENTS + 3);
+    }
+}
@@ -1,5 +1,6 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
+use graphchan_backend::api_tokens::{ApiScope, ApiTokenStore};
 use graphchan_backend::cli;
 use graphchan_backend::config::GraphchanConfig;
 use graphchan_backend::node::GraphchanNode;
@@ -19,6 +20,26 @@ enum Command {
     Serve,
     /// Start the interactive CLI for friendcodes, threads, and posts
     Cli,
+    /// Manage HTTP API tokens for bots and agents (works while the node is stopped)
+    Token {
+        #[command(subcommand)]
+        action: TokenCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum TokenCommand {
+    /// Create a token; the secret is printed once
+    Create {
+        name: String,
+        /// Comma-separated: read, post, dm, admin
+        #[arg(long, value_delimiter = ',', default_value = "read")]
+        scopes: Vec<String>,
+    },
+    /// List tokens (without secrets)
+    List,
+    /// Revoke a token by ID or name
+    Revoke { id_or_name: String },
 }
 
 #[tokio::main]
@@ -29,6 +50,10 @@ async fn main() -> Result<()> {
     let args = Args::parse();
 
     let config = GraphchanConfig::from_env()?;
+    let command = match args.command.unwrap_or(Command::Cli) {
+        Command::Token { action } => return run_token_command(&config, action),
+        command => command,
+    };
     let node = GraphchanNode::start(config).await?;
     tracing::info!(
         gpg_fingerprint = %node.identity().gpg_fingerprint,
@@ -36,7 +61,7 @@ async fn main() -> Result<()> {
         "bootstrap complete"
     );
 
-    match args.command.unwrap_or(Command::Cli) {
+    match command {
         Command::Serve => node.run_http_server().await,
         Command::Cli => {
             let snapshot = node.snapshot();
@@ -49,5 +74,36 @@ async fn main() -> Result<()> {
             )
             .await
         }
+        Command::Token { .. } => unreachable!("handled before the node starts"),
+    }
+}
+
+fn run_token_command(config: &GraphchanConfig, action: TokenCommand) -> Result<()> {
+    std::fs::create_dir_all(&config.paths.data_dir).context("failed to create data directory")?;
+    let store = ApiTokenStore::open(&config.paths)?;
+    match action {
+        TokenCommand::Create { name, scopes } => {
+            let scopes = scopes
+                .iter()
+                .map(|scope| ApiScope::parse(scope.trim()))
+                .collect::<Result<Vec<_>>>()?;
+            let (token, secret) = store.create(&name, &scopes)?;
+            println!("Created token '{}' ({})", token.name, token.id);
+            println!("{secret}");
+        }
+        TokenCommand::List => {
+            for token in store.list() {
+                let scopes: Vec<&str> = token.scopes.iter().map(|scope| scope.as_str()).collect();
+                println!("{}  {}  [{}]  {}", token.id, token.name, scopes.join(","), token.created_at);
+            }
+        }
+        TokenCommand::Revoke { id_or_name } => {
+            if store.revoke(&id_or_name)? {
+                println!("Revoked {id_or_name}");
+            } else {
+                anyhow::bail!("token not found: {id_or_name}");
+            }
+        }
     }
+    Ok(())
 }
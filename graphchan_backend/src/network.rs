@@ -1,12 +1,20 @@
 use crate::config::{GraphchanPaths, NetworkConfig};
+pub mod address_book;
+pub mod addresses;
+pub mod dedup;
+pub mod direct;
 mod events;
 pub mod ingest;
+pub mod outbox;
+pub mod ratelimit;
 pub mod schelling;
+pub mod sync;
 pub mod topics;
 
 use crate::database::Database;
 use crate::database::repositories::{ThreadRepository, PostRepository, PeerRepository};
 use crate::identity::{load_iroh_secret, FriendCodePayload};
+use crate::private_threads::{PrivateThreadService, PRIVATE_VISIBILITY};
 use crate::threading::{PostView, ThreadDetails};
 use anyhow::{Context, Result};
 use events::{EventPayload, NetworkEvent};
@@ -34,12 +42,25 @@ use std::sync::atomic::{AtomicBool, Ordering};
 
 const GRAPHCHAN_ALPN: &[u8] = b"graphchan/0";
 const GOSSIP_BUFFER: usize = 128;
+/// How often our signed DM prekey is re-published (it rotates weekly).
+const DM_PREKEY_PUBLISH_SECS: u64 = 24 * 60 * 60;
+/// Delay before the first publication, so the startup subscriptions exist.
+const DM_PREKEY_FIRST_PUBLISH_SECS: u64 = 30;
+/// Delays (from joining a topic) at which we ask it for its metadata record,
+/// since the first request often goes out before any neighbor is connected.
+const TOPIC_METADATA_FETCH_DELAYS_SECS: &[u64] = &[5, 30, 120];
 
 pub use events::BlockActionEvent;
 pub use events::DirectMessageEvent;
+pub use events::DmPrekeyBundle;
+pub use events::DmReceiptEvent;
 pub use events::FileAnnouncement;
+pub use events::PeerProtocol;
+pub use events::PostEditEvent;
+pub use events::{CAPABILITIES, PROTOCOL_VERSION};
 pub use events::ProfileUpdate;
 pub use events::ReactionUpdate;
+pub use events::WrappedThreadKey;
 
 type TopicId = iroh_gossip::proto::TopicId;
 
@@ -86,6 +107,8 @@ pub struct NetworkHandle {
     dht_senders: Arc<RwLock<HashMap<String, DhtTopicSender>>>,
     blobs: FsStore,
     database: Database,
+    /// Node paths, used to load the X25519 key that opens private thread keys
+    paths: GraphchanPaths,
     /// Whether DHT is reachable (true = connected, false = unreachable, None = still checking)
     dht_connected: Arc<AtomicBool>,
     dht_checked: Arc<AtomicBool>,
@@ -95,6 +118,12 @@ pub struct NetworkHandle {
     iroh_secret_bytes: [u8; 32],
     /// StaticProvider for injecting out-of-band peer addresses (from Schelling discovery)
     static_provider: StaticProvider,
+    /// Protocol version and capabilities advertised by each verified envelope sender
+    peer_protocols: events::PeerProtocols,
+    /// Gossip dedup window shared with the ingest loop
+    seen_messages: dedup::SeenCache,
+    /// Per-neighbor and per-author flood limits enforced by the ingest loop
+    ingest_limiter: ratelimit::IngestLimiter,
 }
 
 impl NetworkHandle {
@@ -173,23 +202,45 @@ impl NetworkHandle {
 
         let event_worker_topics_clone = event_worker_topics.clone();
         let event_worker_dht_senders_clone = event_worker_dht_senders.clone();
+        let (inbound_tx, inbound_rx) = mpsc::channel(GOSSIP_BUFFER);
+        // Envelopes are signed with the endpoint key so `sender` is our node ID
+        let envelope_signer = ed25519_dalek::SigningKey::from_bytes(&iroh_secret_bytes);
+        let direct_client = direct::DirectClient::new(
+            endpoint.clone(),
+            database.clone(),
+            envelope_signer.clone(),
+            inbound_tx.clone(),
+        );
         let event_worker = tokio::spawn(async move {
-            events::run_event_loop(event_worker_gossip, event_worker_topics_clone, event_worker_dht_senders_clone, rx).await;
+            events::run_event_loop(
+                event_worker_gossip,
+                event_worker_topics_clone,
+                event_worker_dht_senders_clone,
+                envelope_signer,
+                direct_client,
+                rx,
+            )
+            .await;
         });
 
-        let (inbound_tx, inbound_rx) = mpsc::channel(GOSSIP_BUFFER);
         let ingest_publisher = tx.clone();
         let ingest_database = database.clone();
         let ingest_paths = paths.clone();
         let ingest_store = blob_store.clone();
         let ingest_endpoint = endpoint.clone();
         let ingest_local_peer_id = local_peer_id.clone();
+        let seen_messages = dedup::SeenCache::load(database.clone())
+            .context("failed to load gossip dedup cache")?;
+        let ingest_seen_messages = seen_messages.clone();
+        let ingest_limiter = ratelimit::IngestLimiter::new(local_peer_id.clone());
+        let ingest_worker_limiter = ingest_limiter.clone();
 
         // Create IP blocker and load cache
         let ip_blocker = crate::blocking::IpBlockChecker::new(database.clone());
         if let Err(err) = ip_blocker.load_cache().await {
             tracing::warn!(error = ?err, "failed to load IP block cache");
         }
+        tokio::spawn(ip_blocker.clone().run_hit_flush_loop());
 
         let ingest_worker = tokio::spawn(async move {
             ingest::run_ingest_loop(
@@ -201,6 +252,8 @@ impl NetworkHandle {
                 ingest_endpoint,
                 ingest_local_peer_id,
                 ip_blocker,
+                ingest_seen_messages,
+                ingest_worker_limiter,
             )
             .await;
         });
@@ -208,10 +261,20 @@ impl NetworkHandle {
         // No longer need global receiver loop - each subscribe_to_peer/subscribe_to_thread
         // spawns its own receiver task
 
+        let peer_protocols = events::PeerProtocols::default();
         let blob_protocol = BlobsProtocol::new(&blob_store, None);
+        let direct_protocol = direct::DirectProtocol::new(
+            database.clone(),
+            paths.clone(),
+            local_peer_id.clone(),
+            inbound_tx.clone(),
+            peer_protocols.clone(),
+        );
         let router = Router::builder(endpoint.as_ref().clone())
             .accept(GRAPHCHAN_ALPN, gossip.clone())
             .accept(BLOBS_ALPN, blob_protocol)
+            .accept(sync::SYNC_ALPN, sync::ThreadSyncProtocol::new(database.clone()))
+            .accept(direct::DIRECT_ALPN, direct_protocol)
             .spawn();
         let router = Arc::new(router);
 
@@ -230,13 +293,23 @@ impl NetworkHandle {
             dht_senders: event_worker_dht_senders,
             blobs: blob_store.clone(),
             database: database.clone(),
+            paths: paths.clone(),
             dht_connected: dht_connected.clone(),
             dht_checked: dht_checked.clone(),
             iroh_secret_bytes,
             static_provider,
+            peer_protocols,
+            seen_messages,
+            ingest_limiter,
         };
         tracing::info!(peer_id = %handle.peer_id(), "iroh endpoint started");
 
+        // Resend unacknowledged DMs on a backoff timer
+        tokio::spawn(handle.dm_outbox().run_retry_loop());
+
+        // Keep friends supplied with our current DM prekey
+        tokio::spawn(handle.clone().run_dm_prekey_publisher());
+
         // Spawn DHT connectivity check in background
         if config.enable_dht {
             tokio::spawn(async move {
@@ -379,9 +452,21 @@ impl NetworkHandle {
 
     /// Broadcasts a thread announcement (metadata + blob ticket).
     /// Always stores full thread as blob, sends lightweight announcement via gossip.
+    /// Private threads are stored as ciphertext and carry the wrapped member keys instead
+    /// of a title, preview or topics.
     pub async fn publish_thread_announcement(&self, snapshot: ThreadDetails, local_peer_id: &str) -> Result<()> {
+        let is_private = snapshot.thread.visibility == PRIVATE_VISIBILITY;
+
         // Store complete thread as blob
-        let json_bytes = serde_json::to_vec(&snapshot)?;
+        let mut json_bytes = serde_json::to_vec(&snapshot)?;
+        let mut key_grant = None;
+        if is_private {
+            let private_threads = self.private_threads();
+            key_grant = Some(private_threads.key_grant(&snapshot.thread.id)?.with_context(|| {
+                format!("no key held for private thread {}", snapshot.thread.id)
+            })?);
+            json_bytes = private_threads.seal_snapshot(&snapshot.thread.id, &json_bytes)?;
+        }
         let size = json_bytes.len();
 
         let outcome = self.blobs
@@ -411,21 +496,49 @@ impl NetworkHandle {
             .unwrap_or(&snapshot.thread.created_at)
             .to_string();
 
-        // Calculate thread hash from all posts
-        let thread_hash = crate::threading::calculate_thread_hash(&snapshot.posts);
+        // Calculate thread hash from all posts (legacy form too, for older peers)
+        let thread_hash = crate::threading::calculate_legacy_thread_hash(&snapshot.posts);
+        let canonical_thread_hash = Some(crate::threading::calculate_thread_hash(&snapshot.posts));
 
         // Get topics for this thread from database
         use crate::database::repositories::TopicRepository;
-        let topics = self.database.with_repositories(|repos| {
-            repos.topics().list_thread_topics(&snapshot.thread.id)
-        }).unwrap_or_default();
+        let topics = if is_private {
+            Vec::new()
+        } else {
+            self.database.with_repositories(|repos| {
+                repos.topics().list_thread_topics(&snapshot.thread.id)
+            }).unwrap_or_default()
+        };
+
+        // Title and preview would leak private content in the clear
+        let (title, preview) = if is_private {
+            ("Private thread".to_string(), String::new())
+        } else {
+            (snapshot.thread.title.clone(), preview)
+        };
+        let (member_keys, key_epoch, wrapper_x25519_pubkey, key_grant_signature, creator_public_key) = match key_grant {
+            Some(grant) => (
+                grant.member_keys,
+                grant.key_epoch,
+                grant.wrapper_x25519_pubkey,
+                grant.signature,
+                grant.creator_public_key,
+            ),
+            None => (Vec::new(), 0, None, None, None),
+        };
+
+        // Topics with a difficulty want proof of work; minted once, then reused from the database
+        let creator_peer_id = snapshot.thread.creator_peer_id.clone().unwrap_or_else(|| local_peer_id.to_string());
+        let pow = crate::pow::PowService::new(self.database.clone())
+            .stamp_thread(&snapshot.thread.id, &creator_peer_id, &snapshot.thread.created_at, &topics)
+            .await?;
 
         // Create lightweight announcement
         let announcement = events::ThreadAnnouncement {
             thread_id: snapshot.thread.id.clone(),
-            creator_peer_id: snapshot.thread.creator_peer_id.clone().unwrap_or_else(|| local_peer_id.to_string()),
+            creator_peer_id,
             announcer_peer_id: local_peer_id.to_string(),
-            title: snapshot.thread.title.clone(),
+            title,
             preview,
             ticket,
             post_count: snapshot.posts.len(),
@@ -433,8 +546,15 @@ impl NetworkHandle {
             created_at: snapshot.thread.created_at.clone(),
             last_activity,
             thread_hash,
+            canonical_thread_hash,
             visibility: snapshot.thread.visibility.clone(),
             topics,
+            member_keys,
+            key_epoch,
+            wrapper_x25519_pubkey,
+            key_grant_signature,
+            creator_public_key,
+            pow,
         };
 
         tracing::info!(
@@ -454,43 +574,177 @@ impl NetworkHandle {
     }
 
     /// Broadcasts a single post update to connected peers.
-    pub async fn publish_post_update(&self, post: PostView) -> Result<()> {
+    ///
+    /// Posts in private threads are skipped; they only travel inside the
+    /// encrypted snapshot of the next thread announcement. Posts on topics
+    /// with a difficulty get a proof-of-work stamp first.
+    pub async fn publish_post_update(&self, mut post: PostView) -> Result<()> {
+        if self.private_threads().is_private(&post.thread_id)? {
+            tracing::debug!(thread_id = %post.thread_id, "not broadcasting plaintext post of private thread");
+            return Ok(());
+        }
+        crate::pow::PowService::new(self.database.clone()).stamp_post(&mut post).await?;
         let event = NetworkEvent::Broadcast(EventPayload::PostUpdate(post));
         self.publisher.send(event).await.ok();
         Ok(())
     }
 
     /// Broadcasts that an attachment blob is available for download.
+    ///
+    /// For private threads the thread is re-announced instead, so the file
+    /// metadata and key stay inside the encrypted snapshot.
     pub async fn publish_file_available(&self, announcement: FileAnnouncement) -> Result<()> {
+        if self.private_threads().is_private(&announcement.thread_id)? {
+            return self.reannounce_private_thread(&announcement.thread_id).await;
+        }
         let event = NetworkEvent::Broadcast(EventPayload::FileAvailable(announcement));
         self.publisher.send(event).await.ok();
         Ok(())
     }
 
+    /// Broadcasts a signed edit or deletion of one of our posts.
+    ///
+    /// Private threads are re-announced instead so the new version only
+    /// travels inside the encrypted snapshot.
+    pub async fn publish_post_edit(&self, edit: PostEditEvent) -> Result<()> {
+        if self.private_threads().is_private(&edit.thread_id)? {
+            return self.reannounce_private_thread(&edit.thread_id).await;
+        }
+        let event = NetworkEvent::Broadcast(EventPayload::PostEdit(edit));
+        self.publisher.send(event).await.ok();
+        Ok(())
+    }
+
     pub async fn publish_profile_update(&self, update: ProfileUpdate) -> Result<()> {
         let event = NetworkEvent::Broadcast(EventPayload::ProfileUpdate(update));
         self.publisher.send(event).await.ok();
         Ok(())
     }
 
+    /// Re-publishes our signed DM prekey in a profile update on a timer.
+    ///
+    /// `DmService::prekey_bundle` rotates the prekey when it is due, so friends
+    /// who missed the last update still get a current one. The update also
+    /// carries our agent roster so missed revocations catch up the same way.
+    async fn run_dm_prekey_publisher(self) {
+        let start = tokio::time::Instant::now() + std::time::Duration::from_secs(DM_PREKEY_FIRST_PUBLISH_SECS);
+        let mut interval = tokio::time::interval_at(start, std::time::Duration::from_secs(DM_PREKEY_PUBLISH_SECS));
+        loop {
+            interval.tick().await;
+            if self.publisher.is_closed() {
+                break;
+            }
+            if let Err(err) = self.publish_dm_prekey().await {
+                tracing::warn!(error = ?err, "failed to publish DM prekey");
+            }
+        }
+    }
+
+    async fn publish_dm_prekey(&self) -> Result<()> {
+        let bundle = crate::dms::DmService::new(self.database.clone(), self.paths.clone()).prekey_bundle()?;
+        // Fields left empty don't change the profile on the receiving side
+        self.publish_profile_update(ProfileUpdate {
+            peer_id: bundle.peer_id.clone(),
+            avatar_file_id: None,
+            ticket: None,
+            username: None,
+            bio: None,
+            agents: None,
+            x25519_pubkey: Some(bundle.identity_key.clone()),
+            dm_prekey: Some(bundle),
+            agent_keys: self.agent_roster(),
+        })
+        .await
+    }
+
+    /// Our agent delegations and revocations; `None` if there are none or they can't be read.
+    fn agent_roster(&self) -> Option<Vec<crate::agents::DelegatedAgent>> {
+        crate::agents::AgentService::new(self.database.clone(), self.paths.clone())
+            .roster()
+            .map_err(|err| tracing::warn!(error = ?err, "failed to load agent roster"))
+            .ok()
+            .filter(|roster| !roster.is_empty())
+    }
+
     pub async fn publish_reaction_update(&self, update: ReactionUpdate) -> Result<()> {
+        // Reactions on private threads would reveal who is talking in the clear
+        if self.private_threads().is_private(&update.thread_id)? {
+            tracing::debug!(thread_id = %update.thread_id, "not broadcasting reaction on private thread");
+            return Ok(());
+        }
         let event = NetworkEvent::Broadcast(EventPayload::ReactionUpdate(update));
         self.publisher.send(event).await.ok();
         Ok(())
     }
 
+    /// Sends a DM through the outbox, which keeps retrying until the recipient's receipt arrives.
     pub async fn publish_direct_message(&self, dm: events::DirectMessageEvent) -> Result<()> {
-        let event = NetworkEvent::Broadcast(EventPayload::DirectMessage(dm));
+        self.dm_outbox().send(dm).await
+    }
+
+    pub fn dm_outbox(&self) -> outbox::DmOutbox {
+        outbox::DmOutbox::new(self.database.clone(), self.publisher.clone())
+    }
+
+    pub async fn publish_topic_metadata(&self, metadata: events::TopicMetadata) -> Result<()> {
+        let event = NetworkEvent::Broadcast(EventPayload::TopicMetadata(metadata));
         self.publisher.send(event).await.ok();
         Ok(())
     }
 
+    /// Asks a topic for its metadata a few times after joining, until a newer
+    /// record than the one we started with arrives.
+    async fn run_topic_metadata_fetch(self, topic_id: String) {
+        let service = crate::topic_config::TopicConfigService::new(self.database.clone());
+        let Ok(initial_version) = service.metadata_version(&topic_id) else {
+            return;
+        };
+        for delay in TOPIC_METADATA_FETCH_DELAYS_SECS {
+            tokio::time::sleep(std::time::Duration::from_secs(*delay)).await;
+            let known_version = match service.metadata_version(&topic_id) {
+                Ok(version) if version > initial_version => return,
+                Ok(version) => version,
+                Err(err) => {
+                    tracing::warn!(error = ?err, topic = %topic_id, "failed to read topic metadata");
+                    return;
+                }
+            };
+            if self.publisher.is_closed() {
+                return;
+            }
+            let event = NetworkEvent::Broadcast(EventPayload::TopicMetadataRequest(events::TopicMetadataRequest {
+                topic_id: topic_id.clone(),
+                known_version,
+            }));
+            self.publisher.send(event).await.ok();
+        }
+    }
+
     pub async fn publish_block_action(&self, action: events::BlockActionEvent) -> Result<()> {
         let event = NetworkEvent::Broadcast(EventPayload::BlockAction(action));
         self.publisher.send(event).await.ok();
         Ok(())
     }
 
+    fn topic_address_book(&self) -> address_book::TopicAddressBook {
+        address_book::TopicAddressBook::new(self.database.clone())
+    }
+
+    fn private_threads(&self) -> PrivateThreadService {
+        PrivateThreadService::new(self.database.clone(), self.paths.clone())
+    }
+
+    async fn reannounce_private_thread(&self, thread_id: &str) -> Result<()> {
+        let Some((local_peer_id, _, _)) = self.database.get_identity()? else {
+            return Ok(());
+        };
+        let service = crate::threading::ThreadService::with_file_paths(self.database.clone(), self.paths.clone());
+        if let Some(details) = service.get_thread(thread_id)? {
+            self.publish_thread_announcement(details, &local_peer_id).await?;
+        }
+        Ok(())
+    }
+
     /// Requests a file blob from a specific peer.
     pub async fn request_file(&self, peer_id: &str, file_id: &str) -> Result<()> {
         let event = NetworkEvent::Direct {
@@ -498,6 +752,7 @@ impl NetworkHandle {
             payload: EventPayload::FileRequest(events::FileRequest {
                 file_id: file_id.to_string(),
             }),
+            gossip_fallback: false,
         };
         self.publisher.send(event).await.ok();
         Ok(())
@@ -533,6 +788,7 @@ impl NetworkHandle {
 
         let mut receiver = receiver_topic;
         let inbound_tx = self.inbound_tx.clone();
+        let peer_protocols = self.peer_protocols.clone();
 
         // Spawn a task to forward messages from the global topic to the ingest loop
         tokio::spawn(async move {
@@ -540,17 +796,19 @@ impl NetworkHandle {
             while let Some(event_result) = receiver.next().await {
                 match event_result {
                     Ok(iroh_gossip::api::Event::Received(message)) => {
-                        match serde_json::from_slice::<events::EventEnvelope>(&message.content) {
-                            Ok(envelope) => {
+                        match events::decode_envelope(&message.content, &peer_protocols) {
+                            Ok(Some(payload)) => {
                                 let gossip = events::InboundGossip {
                                     peer_id: Some(message.delivered_from.to_string()),
-                                    payload: envelope.payload,
+                                    payload,
+                                    size_bytes: message.content.len(),
                                 };
                                 if inbound_tx.send(gossip).await.is_err() {
                                     tracing::warn!(topic = %topic_name, "inbound channel closed, stopping global topic receiver");
                                     break;
                                 }
                             }
+                            Ok(None) => {}
                             Err(err) => {
                                 tracing::warn!(error = ?err, topic = %topic_name, "failed to deserialize message on global topic");
                             }
@@ -588,12 +846,32 @@ impl NetworkHandle {
         // This is the PRIMARY discovery mechanism: if your friend is online and on
         // the same topic, iroh-gossip connects directly via the bootstrap list.
         // No DHT needed. iroh resolves addresses via DhtDiscovery/Pkarr.
-        let bootstrap_peers = self.gather_friend_bootstrap_peers();
+        let mut bootstrap_peers = self.gather_friend_bootstrap_peers();
+        let friend_bootstrap_count = bootstrap_peers.len();
+
+        // Endpoints we met on this topic before (possibly before a restart).
+        // Their addresses go into the StaticProvider so the dial doesn't depend on the DHT.
+        let address_book = self.topic_address_book();
+        let cached_addrs = address_book.bootstrap(topic_name).unwrap_or_else(|err| {
+            tracing::warn!(error = ?err, topic = %topic_name, "failed to load cached topic addresses");
+            Vec::new()
+        });
+        let own_id = self.endpoint.id();
+        for addr in cached_addrs {
+            if addr.id == own_id {
+                continue;
+            }
+            if !bootstrap_peers.contains(&addr.id) {
+                bootstrap_peers.push(addr.id);
+            }
+            self.static_provider.add_endpoint_info(addr);
+        }
 
         tracing::info!(
             topic = %topic_name,
-            friend_bootstrap_count = bootstrap_peers.len(),
-            "bootstrapping topic subscription with known friends"
+            friend_bootstrap_count,
+            cached_bootstrap_count = bootstrap_peers.len() - friend_bootstrap_count,
+            "bootstrapping topic subscription with known friends and cached addresses"
         );
 
         // Subscribe to the standard gossip topic with friend bootstrapping
@@ -610,24 +888,30 @@ impl NetworkHandle {
 
         // Spawn receiver task for standard gossip
         let inbound_tx = self.inbound_tx.clone();
+        let peer_protocols = self.peer_protocols.clone();
         let topic_key_for_receiver = topic_key.clone();
+        let receiver_endpoint = self.endpoint.clone();
+        let receiver_address_book = address_book.clone();
+        let receiver_topic_name = topic_name.to_string();
         tokio::spawn(async move {
             use futures_util::StreamExt;
             let mut receiver = receiver_topic;
             while let Some(event_result) = receiver.next().await {
                 match event_result {
                     Ok(iroh_gossip::api::Event::Received(message)) => {
-                        match serde_json::from_slice::<events::EventEnvelope>(&message.content) {
-                            Ok(envelope) => {
+                        match events::decode_envelope(&message.content, &peer_protocols) {
+                            Ok(Some(payload)) => {
                                 let gossip = events::InboundGossip {
                                     peer_id: Some(message.delivered_from.to_string()),
-                                    payload: envelope.payload,
+                                    payload,
+                                    size_bytes: message.content.len(),
                                 };
                                 if inbound_tx.send(gossip).await.is_err() {
                                     tracing::warn!(topic = %topic_key_for_receiver, "inbound channel closed");
                                     break;
                                 }
                             }
+                            Ok(None) => {}
                             Err(err) => {
                                 tracing::warn!(error = ?err, topic = %topic_key_for_receiver, "failed to deserialize message");
                             }
@@ -635,6 +919,10 @@ impl NetworkHandle {
                     }
                     Ok(iroh_gossip::api::Event::NeighborUp(neighbor_id)) => {
                         tracing::info!(peer = %neighbor_id.fmt_short(), topic = %topic_key_for_receiver, "🎉 peer discovered on topic!");
+                        let addr = address_book::current_addr(&receiver_endpoint, neighbor_id);
+                        if let Err(err) = receiver_address_book.record_connected(&receiver_topic_name, &addr) {
+                            tracing::warn!(error = ?err, topic = %topic_key_for_receiver, "failed to cache topic neighbor address");
+                        }
                     }
                     Ok(iroh_gossip::api::Event::NeighborDown(neighbor_id)) => {
                         tracing::info!(peer = %neighbor_id.fmt_short(), topic = %topic_key_for_receiver, "peer left topic");
@@ -656,8 +944,11 @@ impl NetworkHandle {
         let gossip = self.gossip.clone();
         let topic_name_owned = topic_name.to_string();
         let inbound_tx_dht = self.inbound_tx.clone();
+        let peer_protocols = self.peer_protocols.clone();
         let dht_senders = self.dht_senders.clone();
         let iroh_secret_bytes = self.iroh_secret_bytes;
+        let dht_endpoint = self.endpoint.clone();
+        let dht_address_book = address_book.clone();
 
         tokio::spawn(async move {
             use distributed_topic_tracker::AutoDiscoveryGossip;
@@ -724,16 +1015,18 @@ impl NetworkHandle {
                             while let Some(event_result) = dht_receiver.next().await {
                                 match event_result {
                                     Ok(iroh_gossip::api::Event::Received(message)) => {
-                                        match serde_json::from_slice::<events::EventEnvelope>(&message.content) {
-                                            Ok(envelope) => {
+                                        match events::decode_envelope(&message.content, &peer_protocols) {
+                                            Ok(Some(payload)) => {
                                                 let gossip_msg = events::InboundGossip {
                                                     peer_id: Some(message.delivered_from.to_string()),
-                                                    payload: envelope.payload,
+                                                    payload,
+                                                    size_bytes: message.content.len(),
                                                 };
                                                 if inbound_tx_dht.send(gossip_msg).await.is_err() {
                                                     break;
                                                 }
                                             }
+                                            Ok(None) => {}
                                             Err(err) => {
                                                 tracing::warn!(error = ?err, topic = %topic_key_dht, "failed to deserialize DHT message");
                                             }
@@ -741,6 +1034,10 @@ impl NetworkHandle {
                                     }
                                     Ok(iroh_gossip::api::Event::NeighborUp(neighbor_id)) => {
                                         tracing::info!(peer = %neighbor_id.fmt_short(), topic = %topic_key_dht, "🎉 DHT peer discovered!");
+                                        let addr = address_book::current_addr(&dht_endpoint, neighbor_id);
+                                        if let Err(err) = dht_address_book.record_connected(&topic_name_owned, &addr) {
+                                            tracing::warn!(error = ?err, topic = %topic_key_dht, "failed to cache topic neighbor address");
+                                        }
                                     }
                                     Ok(iroh_gossip::api::Event::NeighborDown(neighbor_id)) => {
                                         tracing::info!(peer = %neighbor_id.fmt_short(), topic = %topic_key_dht, "DHT peer left");
@@ -787,10 +1084,13 @@ impl NetworkHandle {
                 schelling_endpoint,
                 schelling_sender,
                 schelling_static_provider,
+                address_book,
             )
             .await;
         });
 
+        tokio::spawn(self.clone().run_topic_metadata_fetch(topic_name.to_string()));
+
         tracing::info!(topic = %topic_name, "✓ subscribed to topic (DTT + Schelling discovery running in background)");
 
         Ok(())
@@ -838,6 +1138,9 @@ impl NetworkHandle {
 
         let mut receiver = receiver_topic;
         let inbound_tx = self.inbound_tx.clone();
+        let peer_protocols = self.peer_protocols.clone();
+        let dm_outbox = self.dm_outbox();
+        let topic_peer_id = peer_id.to_string();
 
         // Spawn a task to forward messages from this peer's topic to the ingest loop
         tokio::spawn(async move {
@@ -845,17 +1148,19 @@ impl NetworkHandle {
             while let Some(event_result) = receiver.next().await {
                 match event_result {
                     Ok(iroh_gossip::api::Event::Received(message)) => {
-                        match serde_json::from_slice::<events::EventEnvelope>(&message.content) {
-                            Ok(envelope) => {
+                        match events::decode_envelope(&message.content, &peer_protocols) {
+                            Ok(Some(payload)) => {
                                 let gossip = events::InboundGossip {
                                     peer_id: Some(message.delivered_from.to_string()),
-                                    payload: envelope.payload,
+                                    payload,
+                                    size_bytes: message.content.len(),
                                 };
                                 if inbound_tx.send(gossip).await.is_err() {
                                     tracing::warn!(topic = %topic_name, "inbound channel closed, stopping peer topic receiver");
                                     break;
                                 }
                             }
+                            Ok(None) => {}
                             Err(err) => {
                                 tracing::warn!(error = ?err, topic = %topic_name, "failed to deserialize message on peer topic");
                             }
@@ -863,6 +1168,14 @@ impl NetworkHandle {
                     }
                     Ok(iroh_gossip::api::Event::NeighborUp(neighbor_id)) => {
                         tracing::info!(peer = %neighbor_id.fmt_short(), topic = %topic_name, "neighbor up on peer topic");
+                        // Someone on the recipient's topic can carry queued DMs now
+                        let dm_outbox = dm_outbox.clone();
+                        let topic_peer_id = topic_peer_id.clone();
+                        tokio::spawn(async move {
+                            if let Err(err) = dm_outbox.flush_peer(&topic_peer_id).await {
+                                tracing::warn!(error = ?err, peer_id = %topic_peer_id, "failed to flush DM outbox");
+                            }
+                        });
                     }
                     Ok(iroh_gossip::api::Event::NeighborDown(neighbor_id)) => {
                         tracing::info!(peer = %neighbor_id.fmt_short(), topic = %topic_name, "neighbor down on peer topic");
@@ -945,6 +1258,7 @@ impl NetworkHandle {
 
         let mut receiver = receiver_topic;
         let inbound_tx = self.inbound_tx.clone();
+        let peer_protocols = self.peer_protocols.clone();
 
         // Spawn a task to forward messages from this topic to the ingest loop
         tokio::spawn(async move {
@@ -952,17 +1266,19 @@ impl NetworkHandle {
             while let Some(event_result) = receiver.next().await {
                 match event_result {
                     Ok(iroh_gossip::api::Event::Received(message)) => {
-                        match serde_json::from_slice::<events::EventEnvelope>(&message.content) {
-                            Ok(envelope) => {
+                        match events::decode_envelope(&message.content, &peer_protocols) {
+                            Ok(Some(payload)) => {
                                 let gossip = events::InboundGossip {
                                     peer_id: Some(message.delivered_from.to_string()),
-                                    payload: envelope.payload,
+                                    payload,
+                                    size_bytes: message.content.len(),
                                 };
                                 if inbound_tx.send(gossip).await.is_err() {
                                     tracing::warn!(topic = %topic_name, "inbound channel closed, stopping thread topic receiver");
                                     break;
                                 }
                             }
+                            Ok(None) => {}
                             Err(err) => {
                                 tracing::warn!(error = ?err, topic = %topic_name, "failed to deserialize message on thread topic");
                             }
@@ -1024,6 +1340,31 @@ impl NetworkHandle {
             DhtStatus::Unreachable
         }
     }
+
+    /// Protocol version and capabilities a node last advertised in a signed envelope.
+    pub fn peer_protocol(&self, node_id: &str) -> Option<PeerProtocol> {
+        self.peer_protocols
+            .read()
+            .ok()
+            .and_then(|guard| guard.get(node_id).cloned())
+    }
+
+    /// Hit/eviction counters of the gossip dedup cache.
+    pub fn dedup_stats(&self) -> dedup::DedupStats {
+        self.seen_messages.stats()
+    }
+
+    /// Flood-protection counters, active neighbor penalties and recent drops.
+    pub fn rate_limit_stats(&self) -> ratelimit::RateLimitStats {
+        self.ingest_limiter.stats()
+    }
+
+    /// Whether a node has advertised `capability`. Unknown nodes (or v1 senders) support nothing.
+    pub fn peer_supports(&self, node_id: &str, capability: &str) -> bool {
+        self.peer_protocol(node_id)
+            .map(|protocol| protocol.supports(capability))
+            .unwrap_or(false)
+    }
 }
 
 /// Checks if we can connect to the BitTorrent mainline DHT.
@@ -0,0 +1,184 @@
+use crate::database::models::TopicPeerAddressRecord;
+use crate::database::repositories::TopicAddressRepository;
+use crate::database::Database;
+use anyhow::Result;
+use iroh::endpoint::{ConnectionType, Endpoint};
+use iroh::Watcher;
+use iroh_base::{EndpointAddr, PublicKey, RelayUrl};
+use std::net::SocketAddr;
+use std::str::FromStr;
+
+/// Entries kept per topic; the least recently useful are evicted first.
+pub const MAX_ADDRESSES_PER_TOPIC: usize = 64;
+/// Endpoints handed to gossip and the `StaticProvider` when (re)joining a topic.
+pub const BOOTSTRAP_ADDRESSES: usize = 16;
+/// Direct addresses kept per endpoint.
+const MAX_DIRECT_ADDRS: usize = 8;
+/// Discovered but never connected: forgotten after two days.
+const UNCONFIRMED_TTL_SECS: i64 = 2 * 24 * 60 * 60;
+/// Connected at least once: forgotten thirty days after the last success.
+const CONFIRMED_TTL_SECS: i64 = 30 * 24 * 60 * 60;
+
+/// Persisted per-topic cache of endpoints met through discovery or gossip.
+///
+/// Schelling discovery feeds in addresses it finds; gossip `NeighborUp` events
+/// mark an endpoint as reachable. On (re)subscribing, the freshest entries
+/// seed the topic's bootstrap list so a restarted node rejoins without waiting
+/// for new DHT rounds.
+#[derive(Clone)]
+pub struct TopicAddressBook {
+    database: Database,
+}
+
+impl TopicAddressBook {
+    pub fn new(database: Database) -> Self {
+        Self { database }
+    }
+
+    /// Remembers an address found out-of-band (Schelling records).
+    pub fn record_discovered(&self, topic: &str, addr: &EndpointAddr) -> Result<()> {
+        self.record_at(topic, addr, false, chrono::Utc::now().timestamp())
+    }
+
+    /// Remembers an endpoint that just became our gossip neighbor on `topic`.
+    pub fn record_connected(&self, topic: &str, addr: &EndpointAddr) -> Result<()> {
+        self.record_at(topic, addr, true, chrono::Utc::now().timestamp())
+    }
+
+    fn record_at(&self, topic: &str, addr: &EndpointAddr, success: bool, now: i64) -> Result<()> {
+        let relay_url = addr.relay_urls().next().map(|url| url.to_string());
+        let direct: Vec<String> = addr
+            .ip_addrs()
+            .take(MAX_DIRECT_ADDRS)
+            .map(|sock| sock.to_string())
+            .collect();
+        let direct_json = if direct.is_empty() {
+            None
+        } else {
+            Some(serde_json::to_string(&direct)?)
+        };
+        let endpoint_id = addr.id.to_string();
+        self.database.with_repositories(|repos| {
+            let book = repos.topic_addresses();
+            book.record(
+                topic,
+                &endpoint_id,
+                relay_url.as_deref(),
+                direct_json.as_deref(),
+                now,
+                success,
+            )?;
+            book.evict_beyond(topic, MAX_ADDRESSES_PER_TOPIC)?;
+            Ok(())
+        })
+    }
+
+    /// Ages out stale entries, then returns the best cached addresses for `topic`.
+    pub fn bootstrap(&self, topic: &str) -> Result<Vec<EndpointAddr>> {
+        self.bootstrap_at(topic, chrono::Utc::now().timestamp())
+    }
+
+    fn bootstrap_at(&self, topic: &str, now: i64) -> Result<Vec<EndpointAddr>> {
+        let records = self.database.with_repositories(|repos| {
+            let book = repos.topic_addresses();
+            let expired = book.expire(topic, now - UNCONFIRMED_TTL_SECS, now - CONFIRMED_TTL_SECS)?;
+            if expired > 0 {
+                tracing::debug!(topic = %topic, expired, "aged out cached topic addresses");
+            }
+            book.list_for_topic(topic, BOOTSTRAP_ADDRESSES)
+        })?;
+        Ok(records.iter().filter_map(record_to_addr).collect())
+    }
+}
+
+/// Rebuilds an `EndpointAddr` from a cached row; rows with a bad endpoint ID are skipped.
+fn record_to_addr(record: &TopicPeerAddressRecord) -> Option<EndpointAddr> {
+    let id = PublicKey::from_str(&record.endpoint_id).ok()?;
+    let mut addr = EndpointAddr::new(id);
+    if let Some(relay) = record.relay_url.as_deref().and_then(|url| url.parse::<RelayUrl>().ok()) {
+        addr = addr.with_relay_url(relay);
+    }
+    let direct: Vec<String> = serde_json::from_str(&record.direct_addrs).unwrap_or_default();
+    for sock in direct.iter().filter_map(|entry| entry.parse::<SocketAddr>().ok()) {
+        addr = addr.with_ip_addr(sock);
+    }
+    Some(addr)
+}
+
+/// The address we currently reach `endpoint_id` on (just the ID if no path is known).
+pub fn current_addr(endpoint: &Endpoint, endpoint_id: PublicKey) -> EndpointAddr {
+    let mut addr = EndpointAddr::new(endpoint_id);
+    if let Some(mut conn_type) = endpoint.conn_type(endpoint_id) {
+        match conn_type.get() {
+            ConnectionType::Direct(sock) => addr = addr.with_ip_addr(sock),
+            ConnectionType::Relay(url) => addr = addr.with_relay_url(url),
+            ConnectionType::Mixed(sock, url) => addr = addr.with_ip_addr(sock).with_relay_url(url),
+            ConnectionType::None => {}
+        }
+    }
+    addr
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    fn book() -> TopicAddressBook {
+        let conn = Connection::open_in_memory().unwrap();
+        let database = Database::from_connection(conn, true);
+        database.ensure_migrations().unwrap();
+        TopicAddressBook::new(database)
+    }
+
+    fn addr(seed: u8) -> EndpointAddr {
+        EndpointAddr::new(iroh::SecretKey::from_bytes(&[seed; 32]).public())
+    }
+
+    #[test]
+    fn connected_entries_bootstrap_first_and_keep_addresses() {
+        let book = book();
+        let relay: RelayUrl = "https://relay.example.com".parse().unwrap();
+        let sock: SocketAddr = "203.0.113.7:4433".parse().unwrap();
+        let now = 1_000_000;
+
+        book.record_at("cats", &addr(1).with_relay_url(relay.clone()).with_ip_addr(sock), false, now)
+            .unwrap();
+        book.record_at("cats", &addr(2), false, now + 10).unwrap();
+        // Reconnecting with no known path keeps the discovered addresses
+        book.record_at("cats", &addr(1), true, now + 20).unwrap();
+        book.record_at("dogs", &addr(3), true, now).unwrap();
+
+        let cached = book.bootstrap_at("cats", now + 30).unwrap();
+        assert_eq!(cached.len(), 2);
+        assert_eq!(cached[0].id, addr(1).id);
+        assert_eq!(cached[0].relay_urls().next(), Some(&relay));
+        assert_eq!(cached[0].ip_addrs().collect::<Vec<_>>(), vec![&sock]);
+        assert_eq!(cached[1].id, addr(2).id);
+    }
+
+    #[test]
+    fn stale_and_excess_entries_are_dropped() {
+        let book = book();
+        let now = 10_000_000;
+        book.record_at("cats", &addr(1), false, now).unwrap();
+        book.record_at("cats", &addr(2), true, now).unwrap();
+
+        // Unconfirmed entries go first; confirmed ones last much longer
+        let later = now + UNCONFIRMED_TTL_SECS + 1;
+        let cached = book.bootstrap_at("cats", later).unwrap();
+        assert_eq!(cached.iter().map(|a| a.id).collect::<Vec<_>>(), vec![addr(2).id]);
+        assert!(book.bootstrap_at("cats", now + CONFIRMED_TTL_SECS + 1).unwrap().is_empty());
+
+        for seed in 0..(MAX_ADDRESSES_PER_TOPIC as u8 + 5) {
+            book.record_at("dogs", &addr(seed), seed == 0, now + seed as i64).unwrap();
+        }
+        let kept = book
+            .database
+            .with_repositories(|repos| repos.topic_addresses().list_for_topic("dogs", 1000))
+            .unwrap();
+        assert_eq!(kept.len(), MAX_ADDRESSES_PER_TOPIC);
+        // The connected entry survives eviction even though it is the oldest
+        assert_eq!(kept[0].endpoint_id, addr(0).id.to_string());
+    }
+}
@@ -0,0 +1,195 @@
+use crate::blocking::IpBlockChecker;
+use crate::database::repositories::{EndpointPathRepository, PeerIpRepository, PeerRepository};
+use crate::database::Database;
+use anyhow::{Context, Result};
+use iroh::endpoint::{ConnectionType, Endpoint};
+use iroh::Watcher;
+use iroh_base::PublicKey;
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Path kind for a UDP path straight to the neighbor.
+pub const PATH_DIRECT: &str = "direct";
+/// Path kind for traffic forwarded through a relay server.
+pub const PATH_RELAY: &str = "relay";
+
+/// One way a remote endpoint is currently reachable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObservedPath {
+    /// IP address for direct paths, relay URL for relayed ones
+    pub address: String,
+    pub kind: &'static str,
+    /// The neighbor's own IP (direct paths only; a relay's IP says nothing about the peer)
+    pub ip: Option<IpAddr>,
+}
+
+/// Splits iroh's connection type into the paths we record.
+pub fn paths_for(conn_type: &ConnectionType) -> Vec<ObservedPath> {
+    let direct = |addr: &std::net::SocketAddr| {
+        let ip = addr.ip().to_canonical();
+        ObservedPath {
+            address: ip.to_string(),
+            kind: PATH_DIRECT,
+            ip: Some(ip),
+        }
+    };
+    let relay = |url: &iroh_base::RelayUrl| ObservedPath {
+        address: url.to_string(),
+        kind: PATH_RELAY,
+        ip: None,
+    };
+    match conn_type {
+        ConnectionType::Direct(addr) => vec![direct(addr)],
+        ConnectionType::Relay(url) => vec![relay(url)],
+        ConnectionType::Mixed(addr, url) => vec![direct(addr), relay(url)],
+        ConnectionType::None => Vec::new(),
+    }
+}
+
+/// Records the remote addresses of the gossip neighbors that deliver messages.
+///
+/// Each inbound message names the neighbor (`delivered_from`) that handed it to
+/// us. We ask the endpoint how that neighbor is connected, keep every path in
+/// `endpoint_paths`, mirror direct IPs into `peer_ips` once the endpoint belongs
+/// to a known peer, and count a hit on any IP block the address falls into.
+#[derive(Clone)]
+pub struct PeerAddressTracker {
+    database: Database,
+    endpoint: Arc<Endpoint>,
+    ip_blocker: IpBlockChecker,
+}
+
+impl PeerAddressTracker {
+    pub fn new(database: Database, endpoint: Arc<Endpoint>, ip_blocker: IpBlockChecker) -> Self {
+        Self {
+            database,
+            endpoint,
+            ip_blocker,
+        }
+    }
+
+    /// Records how `delivered_from` (an iroh endpoint ID) is connected right now.
+    pub async fn observe(&self, delivered_from: &str) -> Result<()> {
+        let endpoint_id = PublicKey::from_str(delivered_from)
+            .with_context(|| format!("invalid endpoint id {delivered_from}"))?;
+        let Some(mut conn_type) = self.endpoint.conn_type(endpoint_id) else {
+            return Ok(());
+        };
+        let paths = paths_for(&conn_type.get());
+        if paths.is_empty() {
+            return Ok(());
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        let direct_ips = record_paths(&self.database, delivered_from, &paths, now)?;
+        for ip in direct_ips {
+            if let (true, Some(block_id)) = self.ip_blocker.is_blocked(&ip).await? {
+                tracing::info!(
+                    endpoint_id = %delivered_from,
+                    ip = %ip,
+                    block_id,
+                    "🚫 gossip neighbor connected from blocked IP"
+                );
+                self.ip_blocker.record_hit(block_id);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Stores `paths` for the endpoint and returns its direct IPs.
+fn record_paths(database: &Database, endpoint_id: &str, paths: &[ObservedPath], now: i64) -> Result<Vec<IpAddr>> {
+    database.with_repositories(|repos| {
+        for path in paths {
+            repos.endpoint_paths().record(endpoint_id, &path.address, path.kind, now)?;
+        }
+
+        let direct_ips: Vec<IpAddr> = paths.iter().filter_map(|path| path.ip).collect();
+        if direct_ips.is_empty() {
+            return Ok(direct_ips);
+        }
+        // Until a friendcode ties the endpoint to a peer the path stays endpoint-only
+        if let Some(peer_id) = repos.peers().id_for_iroh_peer(endpoint_id)? {
+            for ip in &direct_ips {
+                repos.peer_ips().record_direct(&peer_id, &ip.to_string(), now)?;
+            }
+        }
+        Ok(direct_ips)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::PeerRecord;
+    use rusqlite::Connection;
+
+    fn database() -> Database {
+        let database = Database::from_connection(Connection::open_in_memory().expect("db"), true);
+        database.ensure_migrations().expect("migrations");
+        database
+    }
+
+    #[test]
+    fn mixed_connections_yield_direct_and_relay_paths() {
+        let addr: std::net::SocketAddr = "[::ffff:203.0.113.7]:4433".parse().unwrap();
+        let url: iroh_base::RelayUrl = "https://relay.example.org".parse().unwrap();
+
+        let paths = paths_for(&ConnectionType::Mixed(addr, url.clone()));
+        assert_eq!(paths.len(), 2);
+        // IPv4-mapped addresses are stored as plain IPv4 so ranges match
+        assert_eq!(paths[0].address, "203.0.113.7");
+        assert_eq!(paths[0].kind, PATH_DIRECT);
+        assert_eq!(paths[1].kind, PATH_RELAY);
+        assert_eq!(paths[1].ip, None);
+
+        assert!(paths_for(&ConnectionType::Relay(url))[0].ip.is_none());
+        assert!(paths_for(&ConnectionType::None).is_empty());
+    }
+
+    #[test]
+    fn direct_paths_feed_peer_ips_once_the_endpoint_is_known() {
+        let database = database();
+        let path = ObservedPath {
+            address: "198.51.100.4".into(),
+            kind: PATH_DIRECT,
+            ip: Some("198.51.100.4".parse().unwrap()),
+        };
+
+        record_paths(&database, "endpoint-1", &[path.clone()], 1_000).unwrap();
+        database
+            .with_repositories(|repos| {
+                assert!(repos.peer_ips().list_all()?.is_empty());
+                repos.peers().upsert(&PeerRecord {
+                    id: "PEER1".into(),
+                    alias: None,
+                    username: None,
+                    bio: None,
+                    friendcode: None,
+                    iroh_peer_id: Some("endpoint-1".into()),
+                    gpg_fingerprint: Some("PEER1".into()),
+                    x25519_pubkey: None,
+                    last_seen: None,
+                    avatar_file_id: None,
+                    trust_state: "stranger".into(),
+                    agents: None,
+                    gpg_public_key: None,
+                })
+            })
+            .unwrap();
+
+        record_paths(&database, "endpoint-1", &[path], 1_060).unwrap();
+        database
+            .with_repositories(|repos| {
+                let ip = repos.peer_ips().get("PEER1")?.expect("ip recorded");
+                assert_eq!((ip.ip_address.as_str(), ip.source.as_str()), ("198.51.100.4", "direct"));
+
+                let paths = repos.endpoint_paths().list_for_endpoint("endpoint-1")?;
+                assert_eq!(paths.len(), 1);
+                assert_eq!((paths[0].first_seen, paths[0].last_seen, paths[0].deliveries), (1_000, 1_060, 2));
+                Ok(())
+            })
+            .unwrap();
+    }
+}
@@ -0,0 +1,241 @@
+use crate::database::repositories::SeenMessageRepository;
+use crate::database::Database;
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+/// How many message IDs we remember at most.
+pub const DEFAULT_CAPACITY: usize = 50_000;
+/// How long a message ID is remembered after it was last seen.
+pub const DEFAULT_TTL_SECS: i64 = 24 * 60 * 60;
+/// Prune the persisted table once per this many new IDs.
+const PRUNE_INTERVAL: u64 = 1024;
+
+/// Counters reported by `/health` so operators can see how well dedup works.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct DedupStats {
+    pub entries: usize,
+    pub capacity: usize,
+    /// Duplicates suppressed
+    pub hits: u64,
+    /// New message IDs recorded
+    pub misses: u64,
+    /// Entries dropped because the cache was full
+    pub evictions: u64,
+    /// Entries dropped because they outlived the TTL
+    pub expirations: u64,
+}
+
+/// Bounded, time-windowed set of gossip message IDs seen by the ingest loop.
+///
+/// Entries expire `ttl_secs` after they were last seen, and the least recently
+/// seen entry is evicted once `capacity` is reached. New IDs are written through
+/// to the `seen_messages` table so a restarted node doesn't re-broadcast
+/// everything it hears again.
+#[derive(Clone)]
+pub struct SeenCache {
+    database: Database,
+    capacity: usize,
+    ttl_secs: i64,
+    inner: Arc<Mutex<SeenWindow>>,
+}
+
+#[derive(Default)]
+struct SeenWindow {
+    /// msg_id -> unix time it was last seen
+    entries: HashMap<String, i64>,
+    /// Recency queue; stale pairs (refreshed since) are skipped lazily
+    order: VecDeque<(String, i64)>,
+    stats: DedupStats,
+}
+
+impl SeenCache {
+    /// Loads the persisted window with the default limits.
+    pub fn load(database: Database) -> Result<Self> {
+        Self::with_limits(database, DEFAULT_CAPACITY, DEFAULT_TTL_SECS)
+    }
+
+    pub fn with_limits(database: Database, capacity: usize, ttl_secs: i64) -> Result<Self> {
+        let capacity = capacity.max(1);
+        let cutoff = chrono::Utc::now().timestamp() - ttl_secs;
+        let persisted = database.with_repositories(|repos| {
+            let seen = repos.seen_messages();
+            seen.prune(cutoff, capacity)?;
+            seen.list_since(cutoff, capacity)
+        })?;
+
+        let mut window = SeenWindow::default();
+        // `list_since` is newest first; the queue wants oldest first
+        for (msg_id, seen_at) in persisted.into_iter().rev() {
+            window.order.push_back((msg_id.clone(), seen_at));
+            window.entries.insert(msg_id, seen_at);
+        }
+        tracing::debug!(entries = window.entries.len(), "loaded gossip dedup cache");
+
+        Ok(Self {
+            database,
+            capacity,
+            ttl_secs,
+            inner: Arc::new(Mutex::new(window)),
+        })
+    }
+
+    /// Records `msg_id`; returns true the first time it is seen within the window.
+    pub fn insert(&self, msg_id: &str) -> bool {
+        self.insert_at(msg_id, chrono::Utc::now().timestamp())
+    }
+
+    pub fn stats(&self) -> DedupStats {
+        let window = self.inner.lock().expect("dedup cache mutex poisoned");
+        DedupStats {
+            entries: window.entries.len(),
+            capacity: self.capacity,
+            ..window.stats
+        }
+    }
+
+    fn insert_at(&self, msg_id: &str, now: i64) -> bool {
+        let misses = {
+            let mut window = self.inner.lock().expect("dedup cache mutex poisoned");
+            window.expire(now - self.ttl_secs);
+
+            if let Some(seen_at) = window.entries.get_mut(msg_id) {
+                *seen_at = now;
+                window.order.push_back((msg_id.to_string(), now));
+                window.stats.hits += 1;
+                window.compact(self.capacity);
+                return false;
+            }
+
+            window.entries.insert(msg_id.to_string(), now);
+            window.order.push_back((msg_id.to_string(), now));
+            window.stats.misses += 1;
+            while window.entries.len() > self.capacity {
+                if window.pop_oldest().is_none() {
+                    break;
+                }
+                window.stats.evictions += 1;
+            }
+            window.stats.misses
+        };
+
+        // Persistence is best effort: losing a row only means one extra rebroadcast after restart
+        let result = self.database.with_repositories(|repos| {
+            let seen = repos.seen_messages();
+            seen.record(msg_id, now)?;
+            if misses % PRUNE_INTERVAL == 0 {
+                seen.prune(now - self.ttl_secs, self.capacity)?;
+            }
+            Ok(())
+        });
+        if let Err(err) = result {
+            tracing::warn!(error = ?err, msg_id = %msg_id, "failed to persist dedup entry");
+        }
+
+        true
+    }
+}
+
+impl SeenWindow {
+    /// Drops entries last seen before `cutoff`.
+    fn expire(&mut self, cutoff: i64) {
+        while let Some((_, seen_at)) = self.order.front() {
+            if *seen_at >= cutoff {
+                break;
+            }
+            if let Some((msg_id, seen_at)) = self.order.pop_front() {
+                if self.entries.get(&msg_id) == Some(&seen_at) {
+                    self.entries.remove(&msg_id);
+                    self.stats.expirations += 1;
+                }
+            }
+        }
+    }
+
+    /// Removes the least recently seen entry.
+    fn pop_oldest(&mut self) -> Option<String> {
+        while let Some((msg_id, seen_at)) = self.order.pop_front() {
+            if self.entries.get(&msg_id) == Some(&seen_at) {
+                self.entries.remove(&msg_id);
+                return Some(msg_id);
+            }
+        }
+        None
+    }
+
+    /// Rebuilds the queue when refreshed duplicates have bloated it.
+    fn compact(&mut self, capacity: usize) {
+        if self.order.len() <= capacity.saturating_mul(2) {
+            return;
+        }
+        let mut live: Vec<(String, i64)> = self
+            .entries
+            .iter()
+            .map(|(msg_id, seen_at)| (msg_id.clone(), *seen_at))
+            .collect();
+        live.sort_by_key(|(_, seen_at)| *seen_at);
+        self.order = live.into();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    fn database() -> Database {
+        let database = Database::from_connection(Connection::open_in_memory().expect("db"), true);
+        database.ensure_migrations().expect("migrations");
+        database
+    }
+
+    #[test]
+    fn duplicates_are_counted_as_hits() {
+        let cache = SeenCache::with_limits(database(), 10, 60).unwrap();
+        assert!(cache.insert_at("post:1", 1_000));
+        assert!(!cache.insert_at("post:1", 1_001));
+        assert!(cache.insert_at("post:2", 1_002));
+
+        let stats = cache.stats();
+        assert_eq!((stats.entries, stats.hits, stats.misses), (2, 1, 2));
+    }
+
+    #[test]
+    fn least_recently_seen_entry_is_evicted_when_full() {
+        let cache = SeenCache::with_limits(database(), 2, 600).unwrap();
+        cache.insert_at("a", 1_000);
+        cache.insert_at("b", 1_001);
+        // Seeing "a" again makes "b" the oldest
+        assert!(!cache.insert_at("a", 1_002));
+        assert!(cache.insert_at("c", 1_003));
+
+        assert!(!cache.insert_at("a", 1_004));
+        assert!(cache.insert_at("b", 1_005));
+        assert!(cache.stats().evictions >= 1);
+    }
+
+    #[test]
+    fn entries_expire_after_ttl() {
+        let cache = SeenCache::with_limits(database(), 10, 60).unwrap();
+        cache.insert_at("thread:1", 1_000);
+        assert!(!cache.insert_at("thread:1", 1_030));
+        // TTL counts from the last sighting
+        assert!(!cache.insert_at("thread:1", 1_089));
+        assert!(cache.insert_at("thread:1", 1_150));
+        assert_eq!(cache.stats().expirations, 1);
+    }
+
+    #[test]
+    fn window_survives_restart() {
+        let database = database();
+        let now = chrono::Utc::now().timestamp();
+        let cache = SeenCache::with_limits(database.clone(), 10, 600).unwrap();
+        cache.insert_at("dm:1", now);
+        cache.insert_at("dm:old", now - 3_600);
+
+        let restarted = SeenCache::with_limits(database, 10, 600).unwrap();
+        assert!(!restarted.insert_at("dm:1", now + 1));
+        assert!(restarted.insert_at("dm:old", now + 1));
+    }
+}
@@ -0,0 +1,302 @@
+use crate::config::GraphchanPaths;
+use crate::database::repositories::PeerRepository;
+use crate::database::Database;
+use crate::network::events::{self, EventPayload, InboundGossip, PeerProtocols};
+use crate::network::ingest::{accept_direct_message, file_chunk_for};
+use anyhow::{bail, Context, Result};
+use ed25519_dalek::SigningKey;
+use iroh::endpoint::{Connection, Endpoint};
+use iroh::protocol::{AcceptError, ProtocolHandler};
+use iroh_base::{EndpointAddr, PublicKey};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::Sender;
+
+/// ALPN for point-to-point requests (DMs, receipts, file requests).
+pub const DIRECT_ALPN: &[u8] = b"orbweaver/direct/1";
+
+/// Largest request a server will read.
+const MAX_REQUEST_BYTES: usize = 16 * 1024 * 1024;
+/// Largest response a client will read (a whole file chunk).
+const MAX_RESPONSE_BYTES: usize = 64 * 1024 * 1024;
+/// Upper bound for connecting, sending and reading the reply.
+const DIRECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DirectResponse {
+    /// Request handled; `reply` goes into the requester's ingest loop
+    Ok { reply: Option<EventPayload> },
+    Error { message: String },
+}
+
+/// Serves signed envelopes sent to this node over `DIRECT_ALPN`.
+///
+/// DMs addressed to us are stored right away and answered with a signed
+/// receipt, file requests with the file. Receipts and file chunks are handed to
+/// the ingest loop like gossip. Everything else belongs on gossip and is refused.
+#[derive(Clone)]
+pub struct DirectProtocol {
+    database: Database,
+    paths: GraphchanPaths,
+    local_peer_id: String,
+    inbound_tx: Sender<InboundGossip>,
+    peer_protocols: PeerProtocols,
+}
+
+impl std::fmt::Debug for DirectProtocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DirectProtocol").finish_non_exhaustive()
+    }
+}
+
+impl DirectProtocol {
+    pub fn new(
+        database: Database,
+        paths: GraphchanPaths,
+        local_peer_id: String,
+        inbound_tx: Sender<InboundGossip>,
+        peer_protocols: PeerProtocols,
+    ) -> Self {
+        Self {
+            database,
+            paths,
+            local_peer_id,
+            inbound_tx,
+            peer_protocols,
+        }
+    }
+
+    async fn respond(&self, remote: &str, payload: EventPayload, size_bytes: usize) -> DirectResponse {
+        match self.try_respond(remote, payload, size_bytes).await {
+            Ok(reply) => DirectResponse::Ok { reply },
+            Err(err) => DirectResponse::Error {
+                message: format!("{err:#}"),
+            },
+        }
+    }
+
+    async fn try_respond(&self, remote: &str, payload: EventPayload, size_bytes: usize) -> Result<Option<EventPayload>> {
+        match payload {
+            EventPayload::DirectMessage(dm) => {
+                if dm.to_peer_id != self.local_peer_id {
+                    bail!("DM {} is not addressed to this node", dm.message_id);
+                }
+                let receipt = accept_direct_message(&self.database, &self.paths, &dm)?;
+                Ok(Some(EventPayload::DmReceipt(receipt)))
+            }
+            EventPayload::FileRequest(request) => {
+                Ok(file_chunk_for(&self.database, &self.paths, &request)?.map(EventPayload::FileChunk))
+            }
+            payload @ (EventPayload::DmReceipt(_) | EventPayload::FileChunk(_)) => {
+                self.inbound_tx
+                    .send(InboundGossip {
+                        peer_id: Some(remote.to_string()),
+                        payload,
+                        size_bytes,
+                    })
+                    .await
+                    .context("ingest loop stopped")?;
+                Ok(None)
+            }
+            other => bail!("{} is not accepted over direct connections", other.kind()),
+        }
+    }
+}
+
+impl ProtocolHandler for DirectProtocol {
+    async fn accept(&self, connection: Connection) -> Result<(), AcceptError> {
+        let remote = connection.remote_id().map_err(AcceptError::from_err)?.to_string();
+        // One request per bi-stream; the client closes the connection when done
+        while let Ok((mut send, mut recv)) = connection.accept_bi().await {
+            let bytes = recv
+                .read_to_end(MAX_REQUEST_BYTES)
+                .await
+                .map_err(AcceptError::from_err)?;
+            let response = match events::decode_direct_envelope(&bytes, &self.peer_protocols, &remote) {
+                Ok(Some(payload)) => self.respond(&remote, payload, bytes.len()).await,
+                Ok(None) => DirectResponse::Error {
+                    message: "unknown payload kind".to_string(),
+                },
+                Err(err) => DirectResponse::Error {
+                    message: format!("malformed direct request: {err:#}"),
+                },
+            };
+            let bytes = serde_json::to_vec(&response).map_err(AcceptError::from_err)?;
+            send.write_all(&bytes).await.map_err(AcceptError::from_err)?;
+            send.finish().map_err(AcceptError::from_err)?;
+        }
+        Ok(())
+    }
+}
+
+/// Sends `NetworkEvent::Direct` payloads to a single peer.
+#[derive(Clone)]
+pub struct DirectClient {
+    endpoint: Arc<Endpoint>,
+    database: Database,
+    signer: SigningKey,
+    inbound_tx: Sender<InboundGossip>,
+}
+
+impl DirectClient {
+    pub fn new(
+        endpoint: Arc<Endpoint>,
+        database: Database,
+        signer: SigningKey,
+        inbound_tx: Sender<InboundGossip>,
+    ) -> Self {
+        Self {
+            endpoint,
+            database,
+            signer,
+            inbound_tx,
+        }
+    }
+
+    /// Delivers `payload` to `peer_id` and feeds the peer's reply (if any) into ingest.
+    ///
+    /// Errors when the peer can't be resolved, reached or refuses the request;
+    /// the caller decides whether to fall back to gossip.
+    pub async fn deliver(&self, peer_id: &str, payload: &EventPayload) -> Result<()> {
+        let endpoint_id = resolve_endpoint(&self.database, peer_id)?;
+        let request = events::encode_envelope(payload, &self.signer)?;
+        let reply = tokio::time::timeout(DIRECT_TIMEOUT, async {
+            let connection = self
+                .endpoint
+                .connect(EndpointAddr::new(endpoint_id), DIRECT_ALPN)
+                .await
+                .context("failed to open direct connection")?;
+            let result = exchange(&connection, &request).await;
+            connection.close(0u32.into(), b"done");
+            result
+        })
+        .await
+        .context("direct request timed out")??;
+
+        if let Some(reply) = reply {
+            self.inbound_tx
+                .send(InboundGossip {
+                    peer_id: Some(endpoint_id.to_string()),
+                    payload: reply,
+                    size_bytes: 0,
+                })
+                .await
+                .context("ingest loop stopped")?;
+        }
+        Ok(())
+    }
+}
+
+async fn exchange(connection: &Connection, request: &[u8]) -> Result<Option<EventPayload>> {
+    let (mut send, mut recv) = connection.open_bi().await.context("failed to open direct stream")?;
+    send.write_all(request).await?;
+    send.finish()?;
+    let bytes = recv
+        .read_to_end(MAX_RESPONSE_BYTES)
+        .await
+        .context("failed to read direct response")?;
+    match serde_json::from_slice(&bytes).context("malformed direct response")? {
+        DirectResponse::Ok { reply } => Ok(reply),
+        DirectResponse::Error { message } => bail!("peer refused direct request: {message}"),
+    }
+}
+
+/// Maps a peer ID (GPG fingerprint) or endpoint ID string to the endpoint to dial.
+pub fn resolve_endpoint(database: &Database, peer_id: &str) -> Result<PublicKey> {
+    if let Ok(endpoint_id) = PublicKey::from_str(peer_id) {
+        return Ok(endpoint_id);
+    }
+    let iroh_peer_id = database
+        .with_repositories(|repos| repos.peers().get(peer_id))?
+        .and_then(|peer| peer.iroh_peer_id)
+        .with_context(|| format!("no endpoint known for peer {peer_id}"))?;
+    PublicKey::from_str(&iroh_peer_id).with_context(|| format!("invalid endpoint id {iroh_peer_id}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::PeerRecord;
+    use crate::network::events::{DirectMessageEvent, FileRequest};
+    use rusqlite::Connection as SqliteConnection;
+    use tempfile::tempdir;
+    use tokio::sync::mpsc;
+
+    fn database() -> Database {
+        let database = Database::from_connection(SqliteConnection::open_in_memory().expect("db"), true);
+        database.ensure_migrations().expect("migrations");
+        database
+    }
+
+    fn peer(id: &str, iroh_peer_id: Option<String>) -> PeerRecord {
+        PeerRecord {
+            id: id.into(),
+            alias: None,
+            username: None,
+            bio: None,
+            friendcode: None,
+            iroh_peer_id,
+            gpg_fingerprint: Some(id.into()),
+            x25519_pubkey: None,
+            last_seen: None,
+            avatar_file_id: None,
+            trust_state: "friend".into(),
+            agents: None,
+            gpg_public_key: None,
+        }
+    }
+
+    #[test]
+    fn peers_resolve_through_their_endpoint_id() {
+        let database = database();
+        let endpoint_id = iroh::SecretKey::from_bytes(&[3u8; 32]).public();
+        database
+            .with_repositories(|repos| {
+                repos.peers().upsert(&peer("FRIEND", Some(endpoint_id.to_string())))?;
+                repos.peers().upsert(&peer("STUB", None))
+            })
+            .unwrap();
+
+        assert_eq!(resolve_endpoint(&database, "FRIEND").unwrap(), endpoint_id);
+        assert_eq!(resolve_endpoint(&database, &endpoint_id.to_string()).unwrap(), endpoint_id);
+        assert!(resolve_endpoint(&database, "STUB").is_err());
+        assert!(resolve_endpoint(&database, "UNKNOWN").is_err());
+    }
+
+    #[tokio::test]
+    async fn only_point_to_point_payloads_are_served() {
+        let temp = tempdir().expect("tempdir");
+        let paths = GraphchanPaths::from_base_dir(temp.path()).expect("paths");
+        let (tx, mut rx) = mpsc::channel(4);
+        let protocol = DirectProtocol::new(database(), paths, "ME".into(), tx, PeerProtocols::default());
+
+        let not_ours = EventPayload::DirectMessage(DirectMessageEvent {
+            from_peer_id: "ALICE".into(),
+            to_peer_id: "BOB".into(),
+            encrypted_body: vec![1],
+            nonce: vec![0; 24],
+            message_id: "dm-1".into(),
+            conversation_id: "conv".into(),
+            created_at: "2026-01-01T00:00:00Z".into(),
+            ratchet: None,
+            group_id: None,
+            structured: false,
+        });
+        assert!(matches!(protocol.respond("remote", not_ours, 0).await, DirectResponse::Error { .. }));
+
+        let unknown_file = EventPayload::FileRequest(FileRequest { file_id: "missing".into() });
+        assert!(matches!(protocol.respond("remote", unknown_file, 0).await, DirectResponse::Ok { reply: None }));
+
+        let chunk = EventPayload::FileChunk(events::FileChunk {
+            file_id: "f".into(),
+            data: vec![1, 2],
+            eof: true,
+        });
+        assert!(matches!(protocol.respond("remote", chunk, 0).await, DirectResponse::Ok { reply: None }));
+        let forwarded = rx.try_recv().expect("forwarded to ingest");
+        assert_eq!(forwarded.peer_id.as_deref(), Some("remote"));
+    }
+}
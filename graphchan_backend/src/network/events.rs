@@ -1,12 +1,19 @@
+use crate::agents::DelegatedAgent;
+use crate::crypto::RatchetHeader;
+use crate::network::direct::DirectClient;
 use crate::threading::{PostView, ThreadDetails};
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
 use futures_util::StreamExt;
 use iroh_blobs::ticket::BlobTicket;
 use iroh_gossip::api::GossipTopic;
 use iroh_gossip::net::Gossip;
 use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
 use std::collections::HashMap;
+use std::str::FromStr;
 use std::sync::Arc;
 use tokio::sync::{
     mpsc::{Receiver, Sender},
@@ -16,13 +23,118 @@ use base64::{Engine as _, engine::general_purpose};
 
 type TopicId = iroh_gossip::proto::TopicId;
 
+/// Envelope version we send. Version 2 adds `capabilities`, `sender` and `signature`.
+pub const PROTOCOL_VERSION: u8 = 2;
+/// Oldest envelope version we still accept (v1 envelopes are unsigned).
+pub const MIN_PROTOCOL_VERSION: u8 = 1;
+/// End of the v1 deprecation window: from then on unsigned v1 envelopes are refused.
+pub const V1_ENVELOPE_SUNSET: &str = "2027-01-01T00:00:00Z";
+/// Optional features this node understands, advertised on every envelope.
+pub const CAPABILITIES: &[&str] = &[
+    "signed-envelope",
+    "signed-posts",
+    "signed-reactions",
+    "sequenced-block-actions",
+    "private-threads",
+    "thread-sync",
+    "canonical-thread-hash",
+    "post-edits",
+    "dm-receipts",
+    "direct-alpn",
+    "dm-ratchet",
+    "dm-groups",
+    "dm-attachments",
+    "topic-metadata",
+];
+
+/// Wire format for every gossip message.
+///
+/// The payload is kept as raw JSON so the signature covers the exact bytes that
+/// were sent, and so a payload kind we don't know yet can be skipped instead of
+/// failing the whole envelope. Peers running v1 code ignore the extra fields.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EventEnvelope {
     pub version: u8,
     pub topic: String,
-    pub payload: EventPayload,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub capabilities: Vec<String>,
+    /// Iroh node ID of the originating node (v2+)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sender: Option<String>,
+    /// Base64 Ed25519 signature by `sender` over `signing_payload()` (v2+)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    pub payload: Box<RawValue>,
+}
+
+impl EventEnvelope {
+    /// Canonical bytes covered by the sender's signature.
+    pub fn signing_payload(&self, sender: &str) -> Vec<u8> {
+        #[derive(Serialize)]
+        struct SigningPayload<'a> {
+            domain: &'static str,
+            version: u8,
+            topic: &'a str,
+            sender: &'a str,
+            capabilities: &'a [String],
+            payload: &'a str,
+        }
+
+        serde_json::to_vec(&SigningPayload {
+            domain: "orbweaver-envelope-v2",
+            version: self.version,
+            topic: &self.topic,
+            sender,
+            capabilities: &self.capabilities,
+            payload: self.payload.get(),
+        })
+        .expect("envelope signing payload serializes")
+    }
+
+    /// Checks the envelope signature and returns the verified sender.
+    ///
+    /// v2+ envelopes must be signed. Unsigned v1 envelopes are accepted without
+    /// a sender so older peers keep working during the upgrade.
+    fn verified_sender(&self) -> Result<Option<String>> {
+        let (sender, signature) = match (&self.sender, &self.signature) {
+            (Some(sender), Some(signature)) => (sender, signature),
+            _ if self.version < 2 => return Ok(None),
+            _ => bail!("v{} envelope is missing sender or signature", self.version),
+        };
+
+        let node_id = iroh_base::PublicKey::from_str(sender)
+            .with_context(|| format!("invalid envelope sender {sender}"))?;
+        let verifying_key = VerifyingKey::from_bytes(node_id.as_bytes())
+            .context("envelope sender is not a valid ed25519 key")?;
+        let signature_bytes: [u8; 64] = general_purpose::STANDARD
+            .decode(signature)
+            .context("envelope signature is not base64")?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("envelope signature has wrong length"))?;
+        verifying_key
+            .verify_strict(&self.signing_payload(sender), &Signature::from_bytes(&signature_bytes))
+            .context("envelope signature does not verify")?;
+
+        Ok(Some(sender.clone()))
+    }
 }
 
+/// Protocol version and capabilities last advertised by a node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerProtocol {
+    pub version: u8,
+    pub capabilities: Vec<String>,
+}
+
+impl PeerProtocol {
+    pub fn supports(&self, capability: &str) -> bool {
+        self.capabilities.iter().any(|c| c == capability)
+    }
+}
+
+/// What each verified sender (keyed by iroh node ID) has advertised.
+pub type PeerProtocols = Arc<std::sync::RwLock<HashMap<String, PeerProtocol>>>;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum EventPayload {
     ThreadAnnouncement(ThreadAnnouncement),
@@ -34,6 +146,153 @@ pub enum EventPayload {
     ReactionUpdate(ReactionUpdate),
     DirectMessage(DirectMessageEvent),
     BlockAction(BlockActionEvent),
+    PostEdit(PostEditEvent),
+    DmReceipt(DmReceiptEvent),
+    TopicMetadata(TopicMetadata),
+    TopicMetadataRequest(TopicMetadataRequest),
+}
+
+impl EventPayload {
+    /// Every payload kind this build understands (the serde variant names).
+    pub const KINDS: &'static [&'static str] = &[
+        "ThreadAnnouncement",
+        "PostUpdate",
+        "FileAvailable",
+        "FileRequest",
+        "FileChunk",
+        "ProfileUpdate",
+        "ReactionUpdate",
+        "DirectMessage",
+        "BlockAction",
+        "PostEdit",
+        "DmReceipt",
+        "TopicMetadata",
+        "TopicMetadataRequest",
+    ];
+
+    /// Variant name as it appears on the wire.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            EventPayload::ThreadAnnouncement(_) => "ThreadAnnouncement",
+            EventPayload::PostUpdate(_) => "PostUpdate",
+            EventPayload::FileAvailable(_) => "FileAvailable",
+            EventPayload::FileRequest(_) => "FileRequest",
+            EventPayload::FileChunk(_) => "FileChunk",
+            EventPayload::ProfileUpdate(_) => "ProfileUpdate",
+            EventPayload::ReactionUpdate(_) => "ReactionUpdate",
+            EventPayload::DirectMessage(_) => "DirectMessage",
+            EventPayload::BlockAction(_) => "BlockAction",
+            EventPayload::PostEdit(_) => "PostEdit",
+            EventPayload::DmReceipt(_) => "DmReceipt",
+            EventPayload::TopicMetadata(_) => "TopicMetadata",
+            EventPayload::TopicMetadataRequest(_) => "TopicMetadataRequest",
+        }
+    }
+}
+
+/// Serializes and signs `payload` as a current-version envelope.
+pub fn encode_envelope(payload: &EventPayload, signer: &SigningKey) -> Result<Vec<u8>> {
+    let capabilities = CAPABILITIES.iter().map(|c| c.to_string()).collect();
+    encode_envelope_with(payload, signer, capabilities)
+}
+
+fn encode_envelope_with(
+    payload: &EventPayload,
+    signer: &SigningKey,
+    capabilities: Vec<String>,
+) -> Result<Vec<u8>> {
+    let sender = iroh_base::PublicKey::from_bytes(signer.verifying_key().as_bytes())?.to_string();
+    let mut envelope = EventEnvelope {
+        version: PROTOCOL_VERSION,
+        topic: topic_for_payload(payload),
+        capabilities,
+        sender: None,
+        signature: None,
+        payload: serde_json::value::to_raw_value(payload)?,
+    };
+    let signature = signer.sign(&envelope.signing_payload(&sender));
+    envelope.signature = Some(general_purpose::STANDARD.encode(signature.to_bytes()));
+    envelope.sender = Some(sender);
+    Ok(serde_json::to_vec(&envelope)?)
+}
+
+/// Decodes one gossip message.
+///
+/// Returns `Ok(None)` for payload kinds this build doesn't know, so newer peers
+/// can add variants without breaking us. Errors on malformed JSON, unsupported
+/// versions and bad signatures. Verified senders have their advertised version
+/// and capabilities recorded in `peer_protocols`.
+pub fn decode_envelope(bytes: &[u8], peer_protocols: &PeerProtocols) -> Result<Option<EventPayload>> {
+    decode_envelope_from(bytes, peer_protocols, None, min_protocol_version_at(Utc::now()))
+}
+
+/// Decodes a request received over `DIRECT_ALPN` from endpoint `remote`.
+///
+/// Unlike gossip, nothing here is relayed: the envelope must be signed by the
+/// endpoint on the other end of the connection.
+pub fn decode_direct_envelope(bytes: &[u8], peer_protocols: &PeerProtocols, remote: &str) -> Result<Option<EventPayload>> {
+    decode_envelope_from(bytes, peer_protocols, Some(remote), min_protocol_version_at(Utc::now()))
+}
+
+/// Oldest envelope version accepted at `now`: v1 until `V1_ENVELOPE_SUNSET`, v2 after.
+pub fn min_protocol_version_at(now: DateTime<Utc>) -> u8 {
+    let sunset = DateTime::parse_from_rfc3339(V1_ENVELOPE_SUNSET)
+        .expect("V1_ENVELOPE_SUNSET is RFC 3339")
+        .with_timezone(&Utc);
+    if now >= sunset {
+        2
+    } else {
+        MIN_PROTOCOL_VERSION
+    }
+}
+
+fn decode_envelope_from(
+    bytes: &[u8],
+    peer_protocols: &PeerProtocols,
+    expected_sender: Option<&str>,
+    min_version: u8,
+) -> Result<Option<EventPayload>> {
+    let envelope: EventEnvelope = serde_json::from_slice(bytes).context("malformed gossip envelope")?;
+    if envelope.version < min_version {
+        bail!("unsupported envelope version {}", envelope.version);
+    }
+
+    let sender = envelope.verified_sender()?;
+    if let Some(expected) = expected_sender {
+        if sender.as_deref() != Some(expected) {
+            bail!("direct envelope is not signed by the connecting endpoint {}", expected);
+        }
+    }
+    if let Some(sender) = sender {
+        let protocol = PeerProtocol {
+            version: envelope.version,
+            capabilities: envelope.capabilities.clone(),
+        };
+        if let Ok(mut guard) = peer_protocols.write() {
+            guard.insert(sender, protocol);
+        }
+    }
+
+    decode_payload(envelope.payload.get())
+}
+
+fn decode_payload(raw: &str) -> Result<Option<EventPayload>> {
+    match serde_json::from_str::<EventPayload>(raw) {
+        Ok(payload) => Ok(Some(payload)),
+        Err(err) => {
+            // Payloads are externally tagged ({"Kind": {...}}), so the tag is the only key
+            let kind = serde_json::from_str::<HashMap<String, serde::de::IgnoredAny>>(raw)
+                .ok()
+                .and_then(|tagged| tagged.into_keys().next());
+            match kind {
+                Some(kind) if !EventPayload::KINDS.contains(&kind.as_str()) => {
+                    tracing::debug!(kind = %kind, "skipping unknown gossip payload kind");
+                    Ok(None)
+                }
+                _ => Err(err).context("malformed gossip payload"),
+            }
+        }
+    }
 }
 
 /// Announces that a thread exists and where to download it.
@@ -50,11 +309,40 @@ pub struct ThreadAnnouncement {
     pub has_images: bool,
     pub created_at: String,
     pub last_activity: String,       // Most recent post timestamp
-    pub thread_hash: String,         // Hash of all post hashes - for sync detection
+    pub thread_hash: String,         // Legacy hash of all post hashes - for older peers
+    /// Canonical thread hash (covers DAG edges and attachments); absent from older peers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canonical_thread_hash: Option<String>,
     #[serde(default = "default_visibility")]
     pub visibility: String,          // "social", "private", or "global" (DEPRECATED - use topics)
     #[serde(default)]
     pub topics: Vec<String>,         // List of topic IDs to announce on
+    /// Private threads only: the thread key wrapped for each member (ticket points at ciphertext)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub member_keys: Vec<WrappedThreadKey>,
+    /// Private threads only: rotation counter of the key the snapshot is sealed with
+    #[serde(default)]
+    pub key_epoch: i64,
+    /// Private threads only: base64 X25519 key the member keys were wrapped with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wrapper_x25519_pubkey: Option<String>,
+    /// Private threads only: creator's signature over the key grant (`ThreadKeyGrant::signing_payload`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_grant_signature: Option<String>,
+    /// Private threads only: creator's armored public key, to check `key_grant_signature`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub creator_public_key: Option<String>,
+    /// Creator's proof of work over `pow::thread_resource`; required on topics with a difficulty
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pow: Option<crate::pow::PowStamp>,
+}
+
+/// A private thread key sealed (crypto_box) to one member's X25519 key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WrappedThreadKey {
+    pub member_peer_id: String,
+    pub ciphertext: String,  // base64
+    pub nonce: String,       // base64, 24 bytes
 }
 
 fn default_visibility() -> String {
@@ -119,6 +407,57 @@ pub struct ProfileUpdate {
     pub agents: Option<Vec<String>>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub x25519_pubkey: Option<String>,
+    /// Signed prekey for forward-secret DMs; absent from peers that haven't upgraded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dm_prekey: Option<DmPrekeyBundle>,
+    /// Signed agent delegations and revocations; replaces nothing, only adds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_keys: Option<Vec<DelegatedAgent>>,
+}
+
+/// A peer's signed X3DH prekey, published in `ProfileUpdate::dm_prekey`.
+///
+/// Senders holding a verified bundle open double-ratchet DM sessions with it
+/// (`crypto::RatchetSession`); without one they fall back to the static box.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DmPrekeyBundle {
+    pub peer_id: String,
+    /// The peer's long-term X25519 key (base64); must match `x25519_pubkey`
+    pub identity_key: String,
+    pub prekey_id: u32,
+    /// X25519 prekey (base64)
+    pub prekey: String,
+    /// Unix seconds; a bundle only replaces an older one
+    pub created_at: i64,
+    /// Armored detached OpenPGP signature over `signing_payload()`
+    pub signature: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signer_public_key: Option<String>,
+}
+
+impl DmPrekeyBundle {
+    /// Canonical bytes covered by the peer's signature.
+    pub fn signing_payload(&self) -> Vec<u8> {
+        #[derive(Serialize)]
+        struct SigningPayload<'a> {
+            domain: &'static str,
+            peer_id: &'a str,
+            identity_key: &'a str,
+            prekey_id: u32,
+            prekey: &'a str,
+            created_at: i64,
+        }
+
+        serde_json::to_vec(&SigningPayload {
+            domain: "orbweaver-dm-prekey-v1",
+            peer_id: &self.peer_id,
+            identity_key: &self.identity_key,
+            prekey_id: self.prekey_id,
+            prekey: &self.prekey,
+            created_at: self.created_at,
+        })
+        .expect("prekey signing payload serializes")
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -127,12 +466,103 @@ pub struct ReactionUpdate {
     pub thread_id: String,
     pub reactor_peer_id: String,
     pub emoji: String,
+    /// Armored detached OpenPGP signature over `signing_payload()` (adds and removals)
     pub signature: String,
     pub created_at: String,
     pub is_removal: bool, // true if this is a reaction removal
+    /// Reactor's armored public key so peers that have not seen them yet can verify
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reactor_public_key: Option<String>,
+}
+
+impl ReactionUpdate {
+    /// Canonical bytes covered by the reactor's signature.
+    pub fn signing_payload(&self) -> Vec<u8> {
+        #[derive(Serialize)]
+        struct SigningPayload<'a> {
+            domain: &'static str,
+            post_id: &'a str,
+            thread_id: &'a str,
+            reactor_peer_id: &'a str,
+            emoji: &'a str,
+            created_at: &'a str,
+            is_removal: bool,
+        }
+
+        serde_json::to_vec(&SigningPayload {
+            domain: "orbweaver-reaction-v1",
+            post_id: &self.post_id,
+            thread_id: &self.thread_id,
+            reactor_peer_id: &self.reactor_peer_id,
+            emoji: &self.emoji,
+            created_at: &self.created_at,
+            is_removal: self.is_removal,
+        })
+        .expect("reaction signing payload serializes")
+    }
+}
+
+/// An author's edit or retraction (tombstone) of one of their posts.
+///
+/// `signature` is the author's signature over the edited post's
+/// `PostView::signing_payload()`, so the new version verifies the same way
+/// whether it arrives here, in a PostUpdate or in a snapshot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PostEditEvent {
+    pub post_id: String,
+    pub thread_id: String,
+    pub author_peer_id: String,
+    /// New body; empty for tombstones
+    pub body: String,
+    /// Version stamp; peers only apply edits newer than what they hold
+    pub updated_at: String,
+    pub deleted: bool,
+    pub signature: String,
+    /// Author's armored public key (must match `author_peer_id`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author_public_key: Option<String>,
+}
+
+impl PostEditEvent {
+    /// Builds the event for a post that was just edited or deleted locally.
+    pub fn from_post(post: &PostView) -> Result<Self> {
+        Ok(Self {
+            post_id: post.id.clone(),
+            thread_id: post.thread_id.clone(),
+            author_peer_id: post.author_peer_id.clone().context("edited post has no author")?,
+            body: post.body.clone(),
+            updated_at: post.updated_at.clone().context("edited post has no updated_at")?,
+            deleted: post.deleted,
+            signature: post.signature.clone().context("edited post is not signed")?,
+            author_public_key: post.author_public_key.clone(),
+        })
+    }
+
+    /// The stored post with this edit applied (for signature checks and storage).
+    pub fn apply_to(&self, post: &PostView) -> PostView {
+        let mut edited = post.clone();
+        edited.body = self.body.clone();
+        edited.updated_at = Some(self.updated_at.clone());
+        edited.deleted = self.deleted;
+        edited.signature = Some(self.signature.clone());
+        edited.author_public_key = self.author_public_key.clone();
+        if self.deleted {
+            edited.files.clear();
+        }
+        edited
+    }
 }
 
 /// Encrypted DM delivery via gossip (routed to recipient's peer topic).
+///
+/// With `ratchet` set the body is a double-ratchet ciphertext and `nonce` is
+/// empty; otherwise it is a static crypto_box between the two X25519 keys.
+///
+/// A group DM is sent as one such event per member, each encrypted on the
+/// pair's own channel. `group_id` marks those copies; their plaintext is then
+/// a JSON `GroupDmPayload` (see `dms.rs`) rather than the bare message body.
+/// A one-to-one DM with a reply or attachments sets `structured` and carries
+/// a JSON `DmContent` instead; plain text stays bare so older peers read it.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DirectMessageEvent {
     pub from_peer_id: String,
@@ -142,24 +572,213 @@ pub struct DirectMessageEvent {
     pub message_id: String,
     pub conversation_id: String,
     pub created_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ratchet: Option<RatchetHeader>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group_id: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub structured: bool,
+}
+
+/// Signed acknowledgement that a DM reached its recipient.
+///
+/// Sent by the recipient (`from_peer_id`) on its own peer topic, which the
+/// original sender follows. The sender stops retrying once it verifies one;
+/// friends holding the DM for store-and-forward drop their copy.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DmReceiptEvent {
+    pub message_id: String,
+    /// The DM's recipient, who signs the receipt
+    pub from_peer_id: String,
+    /// The DM's original sender
+    pub to_peer_id: String,
+    pub received_at: String,
+    /// Armored detached OpenPGP signature over `signing_payload()`
+    pub signature: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signer_public_key: Option<String>,
+}
+
+impl DmReceiptEvent {
+    /// Canonical bytes covered by the recipient's signature.
+    pub fn signing_payload(&self) -> Vec<u8> {
+        #[derive(Serialize)]
+        struct SigningPayload<'a> {
+            domain: &'static str,
+            message_id: &'a str,
+            from_peer_id: &'a str,
+            to_peer_id: &'a str,
+            received_at: &'a str,
+        }
+
+        serde_json::to_vec(&SigningPayload {
+            domain: "orbweaver-dm-receipt-v1",
+            message_id: &self.message_id,
+            from_peer_id: &self.from_peer_id,
+            to_peer_id: &self.to_peer_id,
+            received_at: &self.received_at,
+        })
+        .expect("receipt signing payload serializes")
+    }
 }
 
 /// Block/unblock action broadcast for shared blocklist features.
+///
+/// Subscribers only apply actions signed by the maintainer (`blocker_peer_id`)
+/// whose `sequence` is higher than the last one they applied.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BlockActionEvent {
     pub blocker_peer_id: String,
     pub blocked_peer_id: String,
     pub reason: Option<String>,
     pub is_unblock: bool,
+    /// Monotonically increasing per maintainer; 0 means unsequenced (legacy, rejected)
+    #[serde(default)]
+    pub sequence: u64,
+    #[serde(default)]
+    pub created_at: String,
+    /// Armored detached OpenPGP signature over `signing_payload()`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    /// Maintainer's armored public key (must match `blocker_peer_id`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blocker_public_key: Option<String>,
+}
+
+impl BlockActionEvent {
+    /// Canonical bytes covered by the maintainer's signature.
+    pub fn signing_payload(&self) -> Vec<u8> {
+        #[derive(Serialize)]
+        struct SigningPayload<'a> {
+            domain: &'static str,
+            blocker_peer_id: &'a str,
+            blocked_peer_id: &'a str,
+            reason: Option<&'a str>,
+            is_unblock: bool,
+            sequence: u64,
+            created_at: &'a str,
+        }
+
+        serde_json::to_vec(&SigningPayload {
+            domain: "orbweaver-block-v1",
+            blocker_peer_id: &self.blocker_peer_id,
+            blocked_peer_id: &self.blocked_peer_id,
+            reason: self.reason.as_deref(),
+            is_unblock: self.is_unblock,
+            sequence: self.sequence,
+            created_at: &self.created_at,
+        })
+        .expect("block action signing payload serializes")
+    }
+}
+
+/// Signed description and policy of a user topic, published by its founder.
+///
+/// Subscribers keep the first founder they see for a topic and only accept
+/// newer `version`s signed by them (see `topic_config.rs`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TopicMetadata {
+    pub topic_id: String,
+    pub founder_peer_id: String,
+    pub description: String,
+    #[serde(default)]
+    pub rules: Vec<String>,
+    #[serde(default)]
+    pub suggested_blocklists: Vec<SuggestedBlocklist>,
+    /// Proof-of-work bits the founder asks subscribers to demand; local overrides win
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pow_difficulty: Option<u32>,
+    #[serde(default)]
+    pub media_policy: MediaPolicy,
+    /// Monotonically increasing per topic
+    pub version: u64,
+    pub updated_at: String,
+    /// Armored detached OpenPGP signature over `signing_payload()`
+    pub signature: String,
+    /// Founder's armored public key (must match `founder_peer_id`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub founder_public_key: Option<String>,
+}
+
+impl TopicMetadata {
+    /// Canonical bytes covered by the founder's signature.
+    pub fn signing_payload(&self) -> Vec<u8> {
+        #[derive(Serialize)]
+        struct SigningPayload<'a> {
+            domain: &'static str,
+            topic_id: &'a str,
+            founder_peer_id: &'a str,
+            description: &'a str,
+            rules: &'a [String],
+            suggested_blocklists: &'a [SuggestedBlocklist],
+            pow_difficulty: Option<u32>,
+            media_policy: MediaPolicy,
+            version: u64,
+            updated_at: &'a str,
+        }
+
+        serde_json::to_vec(&SigningPayload {
+            domain: "orbweaver-topic-metadata-v1",
+            topic_id: &self.topic_id,
+            founder_peer_id: &self.founder_peer_id,
+            description: &self.description,
+            rules: &self.rules,
+            suggested_blocklists: &self.suggested_blocklists,
+            pow_difficulty: self.pow_difficulty,
+            media_policy: self.media_policy,
+            version: self.version,
+            updated_at: &self.updated_at,
+        })
+        .expect("topic metadata signing payload serializes")
+    }
+}
+
+/// A shared blocklist a topic founder recommends to subscribers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SuggestedBlocklist {
+    pub maintainer_peer_id: String,
+    pub name: String,
+}
+
+/// Which attachments a topic wants; applied when deciding what to auto-download.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaPolicy {
+    #[default]
+    Any,
+    ImagesOnly,
+    TextOnly,
+}
+
+impl MediaPolicy {
+    pub fn allows(&self, mime: Option<&str>) -> bool {
+        match self {
+            MediaPolicy::Any => true,
+            MediaPolicy::ImagesOnly => mime.is_some_and(|mime| mime.starts_with("image/")),
+            MediaPolicy::TextOnly => false,
+        }
+    }
+}
+
+/// Broadcast on a topic after joining it; holders of a newer record answer with it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopicMetadataRequest {
+    pub topic_id: String,
+    /// Version we already hold (0 for none)
+    #[serde(default)]
+    pub known_version: u64,
 }
 
 #[derive(Debug)]
 pub enum NetworkEvent {
     Broadcast(EventPayload),
+    /// Request to one peer over `DIRECT_ALPN`; any reply is fed to the ingest loop
     Direct {
-        #[allow(dead_code)]
+        /// Peer ID (GPG fingerprint) or iroh endpoint ID
         peer_id: String,
         payload: EventPayload,
+        /// Broadcast on the payload's topic when the peer can't be reached directly
+        gossip_fallback: bool,
     },
 }
 
@@ -167,12 +786,16 @@ pub enum NetworkEvent {
 pub struct InboundGossip {
     pub peer_id: Option<String>,
     pub payload: EventPayload,
+    /// Encoded envelope size as received; 0 for replies to our own direct requests
+    pub size_bytes: usize,
 }
 
 pub async fn run_event_loop(
     gossip: Gossip,
     topics: Arc<RwLock<HashMap<String, GossipTopic>>>,
     dht_senders: Arc<RwLock<HashMap<String, crate::network::DhtTopicSender>>>,
+    signer: SigningKey,
+    direct: DirectClient,
     mut rx: Receiver<NetworkEvent>,
 ) {
     tracing::info!("network event loop starting with iroh-gossip");
@@ -186,7 +809,7 @@ pub async fn run_event_loop(
                         // Broadcast to all topics
                         for topic_id in &announcement.topics {
                             let topic_name = format!("topic:{}", topic_id);
-                            if let Err(err) = broadcast_to_topic(&gossip, &topics, &dht_senders, &topic_name, payload.clone()).await {
+                            if let Err(err) = broadcast_to_topic(&gossip, &topics, &dht_senders, &signer, &topic_name, payload.clone()).await {
                                 tracing::warn!(error = ?err, topic = %topic_name, "failed to broadcast thread announcement to topic");
                             }
                         }
@@ -196,19 +819,36 @@ pub async fn run_event_loop(
 
                 // Default routing for all other payloads
                 let topic_name = topic_for_payload(&payload);
-                if let Err(err) = broadcast_to_topic(&gossip, &topics, &dht_senders, &topic_name, payload).await {
+                if let Err(err) = broadcast_to_topic(&gossip, &topics, &dht_senders, &signer, &topic_name, payload).await {
                     tracing::warn!(error = ?err, topic = %topic_name, "failed to broadcast event");
                 }
             }
             NetworkEvent::Direct {
-                peer_id: _,
+                peer_id,
                 payload,
+                gossip_fallback,
             } => {
-                // iroh-gossip doesn't support direct messaging, so broadcast instead
-                let topic_name = topic_for_payload(&payload);
-                if let Err(err) = broadcast_to_topic(&gossip, &topics, &dht_senders, &topic_name, payload).await {
-                    tracing::warn!(error = ?err, topic = %topic_name, "failed to broadcast direct event");
-                }
+                // Connecting can take a while; don't hold up broadcasts behind it
+                let direct = direct.clone();
+                let gossip = gossip.clone();
+                let topics = topics.clone();
+                let dht_senders = dht_senders.clone();
+                let signer = signer.clone();
+                tokio::spawn(async move {
+                    let err = match direct.deliver(&peer_id, &payload).await {
+                        Ok(()) => return,
+                        Err(err) => err,
+                    };
+                    if !gossip_fallback {
+                        tracing::warn!(error = ?err, peer = %peer_id, payload_type = %payload.kind(), "direct request failed");
+                        return;
+                    }
+                    tracing::debug!(error = ?err, peer = %peer_id, "peer unreachable directly, falling back to gossip");
+                    let topic_name = topic_for_payload(&payload);
+                    if let Err(err) = broadcast_to_topic(&gossip, &topics, &dht_senders, &signer, &topic_name, payload).await {
+                        tracing::warn!(error = ?err, topic = %topic_name, "failed to broadcast direct event");
+                    }
+                });
             }
         }
     }
@@ -220,6 +860,7 @@ async fn broadcast_to_topic(
     gossip: &Gossip,
     topics: &Arc<RwLock<HashMap<String, GossipTopic>>>,
     dht_senders: &Arc<RwLock<HashMap<String, crate::network::DhtTopicSender>>>,
+    signer: &SigningKey,
     topic_name: &str,
     payload: EventPayload,
 ) -> Result<()> {
@@ -240,21 +881,9 @@ async fn broadcast_to_topic(
         }
     }
 
-    let envelope = envelope_for(payload);
-    let bytes = serde_json::to_vec(&envelope)?;
+    let bytes = encode_envelope(&payload, signer)?;
     let size = bytes.len();
-
-    let payload_type = match &envelope.payload {
-        EventPayload::ThreadAnnouncement(_) => "ThreadAnnouncement",
-        EventPayload::PostUpdate(_) => "PostUpdate",
-        EventPayload::FileAvailable(_) => "FileAnnouncement",
-        EventPayload::FileRequest(_) => "FileRequest",
-        EventPayload::FileChunk(_) => "FileChunk",
-        EventPayload::ProfileUpdate(_) => "ProfileUpdate",
-        EventPayload::ReactionUpdate(_) => "ReactionUpdate",
-        EventPayload::DirectMessage(_) => "DirectMessage",
-        EventPayload::BlockAction(_) => "BlockAction",
-    };
+    let payload_type = payload.kind();
 
     let mut broadcasted = false;
 
@@ -305,6 +934,7 @@ pub async fn run_gossip_receiver_loop(
     gossip: Gossip,
     _topics: Arc<RwLock<HashMap<String, GossipTopic>>>,
     inbound_tx: Sender<InboundGossip>,
+    peer_protocols: PeerProtocols,
 ) -> Result<()> {
     // Subscribe to global topic for receiving
     // Note: This is a separate subscription from the one used for broadcasting
@@ -318,31 +948,20 @@ pub async fn run_gossip_receiver_loop(
         match event_result {
             Ok(iroh_gossip::api::Event::Received(message)) => {
                 let msg_size = message.content.len();
-                match serde_json::from_slice::<EventEnvelope>(&message.content) {
-                    Ok(envelope) => {
+                match decode_envelope(&message.content, &peer_protocols) {
+                    Ok(Some(payload)) => {
                         let peer_id = Some(message.delivered_from.to_string());
-                        let payload_type = match &envelope.payload {
-                            EventPayload::ThreadAnnouncement(_) => "ThreadAnnouncement",
-                            EventPayload::PostUpdate(_) => "PostUpdate",
-                            EventPayload::FileAvailable(_) => "FileAnnouncement",
-                            EventPayload::FileRequest(_) => "FileRequest",
-                            EventPayload::FileChunk(_) => "FileChunk",
-                            EventPayload::ProfileUpdate(_) => "ProfileUpdate",
-                            EventPayload::ReactionUpdate(_) => "ReactionUpdate",
-                            EventPayload::DirectMessage(_) => "DirectMessage",
-                            EventPayload::BlockAction(_) => "BlockAction",
-                        };
                         tracing::info!(
                             from_peer = %message.delivered_from.fmt_short(),
-                            topic = %envelope.topic,
-                            payload_type = %payload_type,
+                            payload_type = %payload.kind(),
                             size_bytes = msg_size,
                             "received gossip message"
                         );
                         if let Err(err) = inbound_tx
                             .send(InboundGossip {
                                 peer_id,
-                                payload: envelope.payload,
+                                payload,
+                                size_bytes: msg_size,
                             })
                             .await
                         {
@@ -350,11 +969,12 @@ pub async fn run_gossip_receiver_loop(
                             break;
                         }
                     }
+                    Ok(None) => {}
                     Err(err) => {
                         tracing::warn!(
                             error = ?err,
                             size_bytes = msg_size,
-                            "⚠️  failed to decode gossip envelope - message may be too large or corrupted"
+                            "⚠️  failed to decode gossip envelope - message may be too large, corrupted or badly signed"
                         );
                     }
                 }
@@ -379,15 +999,6 @@ pub async fn run_gossip_receiver_loop(
     Ok(())
 }
 
-fn envelope_for(payload: EventPayload) -> EventEnvelope {
-    let topic = topic_for_payload(&payload);
-    EventEnvelope {
-        version: 1,
-        topic,
-        payload,
-    }
-}
-
 fn topic_for_payload(payload: &EventPayload) -> String {
     match payload {
         // Thread announcements route based on visibility:
@@ -408,15 +1019,363 @@ fn topic_for_payload(payload: &EventPayload) -> String {
         EventPayload::PostUpdate(post) => format!("thread-{}", post.thread_id),
         EventPayload::FileAvailable(file) => format!("thread-{}", file.thread_id),
         EventPayload::ReactionUpdate(reaction) => format!("thread-{}", reaction.thread_id),
+        EventPayload::PostEdit(edit) => format!("thread-{}", edit.thread_id),
 
         // DMs route to recipient's peer topic
         EventPayload::DirectMessage(dm) => format!("peer-{}", dm.to_peer_id),
 
+        // Receipts go out on the recipient's own topic, which the sender follows
+        EventPayload::DmReceipt(receipt) => format!("peer-{}", receipt.from_peer_id),
+
         // Block actions route to blocker's peer topic (for shared blocklist subscribers)
         EventPayload::BlockAction(action) => format!("peer-{}", action.blocker_peer_id),
 
+        // Topic metadata and requests for it stay on the topic itself
+        EventPayload::TopicMetadata(metadata) => format!("topic:{}", metadata.topic_id),
+        EventPayload::TopicMetadataRequest(request) => format!("topic:{}", request.topic_id),
+
         // These shouldn't be used with the current blob-based file transfer
         EventPayload::FileRequest(_) => "deprecated-file-request".to_string(),
         EventPayload::FileChunk(_) => "deprecated-file-chunk".to_string(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIGNATURE: &str = "-----BEGIN PGP SIGNATURE-----\n\nwsBz\n-----END PGP SIGNATURE-----\n";
+
+    fn golden(name: &str) -> &'static str {
+        match name {
+            "payload_profile_update" => include_str!("../../tests/golden/payload_profile_update.json"),
+            "payload_reaction_update" => include_str!("../../tests/golden/payload_reaction_update.json"),
+            "payload_block_action" => include_str!("../../tests/golden/payload_block_action.json"),
+            "payload_direct_message" => include_str!("../../tests/golden/payload_direct_message.json"),
+            "payload_direct_message_ratchet" => include_str!("../../tests/golden/payload_direct_message_ratchet.json"),
+            "payload_direct_message_group" => include_str!("../../tests/golden/payload_direct_message_group.json"),
+            "payload_direct_message_structured" => include_str!("../../tests/golden/payload_direct_message_structured.json"),
+            "payload_profile_update_prekey" => include_str!("../../tests/golden/payload_profile_update_prekey.json"),
+            "payload_file_available" => include_str!("../../tests/golden/payload_file_available.json"),
+            "payload_post_edit" => include_str!("../../tests/golden/payload_post_edit.json"),
+            "payload_dm_receipt" => include_str!("../../tests/golden/payload_dm_receipt.json"),
+            "payload_topic_metadata" => include_str!("../../tests/golden/payload_topic_metadata.json"),
+            "payload_topic_metadata_request" => include_str!("../../tests/golden/payload_topic_metadata_request.json"),
+            "envelope_v1_legacy" => include_str!("../../tests/golden/envelope_v1_legacy.json"),
+            "envelope_v2_signed" => include_str!("../../tests/golden/envelope_v2_signed.json"),
+            "envelope_v2_unknown_kind" => include_str!("../../tests/golden/envelope_v2_unknown_kind.json"),
+            other => panic!("no golden file named {other}"),
+        }
+    }
+
+    fn golden_value(name: &str) -> serde_json::Value {
+        serde_json::from_str(golden(name)).unwrap()
+    }
+
+    fn golden_signer() -> SigningKey {
+        SigningKey::from_bytes(&[7u8; 32])
+    }
+
+    fn profile_update() -> EventPayload {
+        EventPayload::ProfileUpdate(ProfileUpdate {
+            peer_id: "PEERFINGERPRINT0001".into(),
+            avatar_file_id: None,
+            ticket: None,
+            username: Some("alice".into()),
+            bio: Some("hello".into()),
+            agents: None,
+            x25519_pubkey: Some("AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=".into()),
+            dm_prekey: None,
+            agent_keys: None,
+        })
+    }
+
+    fn golden_payloads() -> Vec<(&'static str, EventPayload)> {
+        vec![
+            ("payload_profile_update", profile_update()),
+            (
+                "payload_reaction_update",
+                EventPayload::ReactionUpdate(ReactionUpdate {
+                    post_id: "post-1".into(),
+                    thread_id: "thread-1".into(),
+                    reactor_peer_id: "PEERFINGERPRINT0001".into(),
+                    emoji: "👍".into(),
+                    signature: SIGNATURE.into(),
+                    created_at: "2026-01-01T00:00:00Z".into(),
+                    is_removal: false,
+                    reactor_public_key: None,
+                }),
+            ),
+            (
+                "payload_block_action",
+                EventPayload::BlockAction(BlockActionEvent {
+                    blocker_peer_id: "PEERFINGERPRINT0001".into(),
+                    blocked_peer_id: "PEERFINGERPRINT0002".into(),
+                    reason: Some("spam".into()),
+                    is_unblock: false,
+                    sequence: 7,
+                    created_at: "2026-01-01T00:00:00Z".into(),
+                    signature: Some(SIGNATURE.into()),
+                    blocker_public_key: None,
+                }),
+            ),
+            (
+                "payload_direct_message",
+                EventPayload::DirectMessage(DirectMessageEvent {
+                    from_peer_id: "PEERFINGERPRINT0001".into(),
+                    to_peer_id: "PEERFINGERPRINT0002".into(),
+                    encrypted_body: vec![1, 2, 3, 4],
+                    nonce: vec![9, 9, 9],
+                    message_id: "msg-1".into(),
+                    conversation_id: "conv-1".into(),
+                    created_at: "2026-01-01T00:00:00Z".into(),
+                    ratchet: None,
+                    group_id: None,
+                    structured: false,
+                }),
+            ),
+            (
+                "payload_direct_message_ratchet",
+                EventPayload::DirectMessage(DirectMessageEvent {
+                    from_peer_id: "PEERFINGERPRINT0001".into(),
+                    to_peer_id: "PEERFINGERPRINT0002".into(),
+                    encrypted_body: vec![1, 2, 3, 4],
+                    nonce: Vec::new(),
+                    message_id: "msg-2".into(),
+                    conversation_id: "conv-1".into(),
+                    created_at: "2026-01-01T00:00:00Z".into(),
+                    ratchet: Some(RatchetHeader {
+                        session_id: "zo060cy2M+x7cMF4FKXHbs0CloUFDTRHRboFhw5YfVk=".into(),
+                        ratchet_key: "UKYUCbHd0DJemxa3AOcZ6XcsBwALG9d4bpB8ZT0gSV0=".into(),
+                        previous_chain_length: 0,
+                        message_number: 0,
+                        prekey_id: Some(1),
+                    }),
+                    group_id: None,
+                    structured: false,
+                }),
+            ),
+            (
+                "payload_direct_message_group",
+                EventPayload::DirectMessage(DirectMessageEvent {
+                    from_peer_id: "PEERFINGERPRINT0001".into(),
+                    to_peer_id: "PEERFINGERPRINT0002".into(),
+                    encrypted_body: vec![1, 2, 3, 4],
+                    nonce: vec![9, 9, 9],
+                    message_id: "msg-3:PEERFINGERPRINT0002".into(),
+                    conversation_id: "conv-1".into(),
+                    created_at: "2026-01-01T00:00:00Z".into(),
+                    ratchet: None,
+                    group_id: Some("group-1".into()),
+                    structured: false,
+                }),
+            ),
+            (
+                "payload_direct_message_structured",
+                EventPayload::DirectMessage(DirectMessageEvent {
+                    from_peer_id: "PEERFINGERPRINT0001".into(),
+                    to_peer_id: "PEERFINGERPRINT0002".into(),
+                    encrypted_body: vec![1, 2, 3, 4],
+                    nonce: vec![9, 9, 9],
+                    message_id: "msg-4".into(),
+                    conversation_id: "conv-1".into(),
+                    created_at: "2026-01-01T00:00:00Z".into(),
+                    ratchet: None,
+                    group_id: None,
+                    structured: true,
+                }),
+            ),
+            (
+                "payload_profile_update_prekey",
+                EventPayload::ProfileUpdate(ProfileUpdate {
+                    peer_id: "PEERFINGERPRINT0002".into(),
+                    avatar_file_id: None,
+                    ticket: None,
+                    username: Some("bob".into()),
+                    bio: None,
+                    agents: None,
+                    x25519_pubkey: Some("Xf7dO2vUf2+ijuFdlp1bsOpTd01Ii9r53xxuASSz7yI=".into()),
+                    dm_prekey: Some(DmPrekeyBundle {
+                        peer_id: "PEERFINGERPRINT0002".into(),
+                        identity_key: "Xf7dO2vUf2+ijuFdlp1bsOpTd01Ii9r53xxuASSz7yI=".into(),
+                        prekey_id: 1,
+                        prekey: "rAGyIJ6GNU+4UyN7XeD0+rE8f8v0M6YcAZNpYX/s8Qs=".into(),
+                        created_at: 1767225600,
+                        signature: SIGNATURE.into(),
+                        signer_public_key: None,
+                    }),
+                    agent_keys: None,
+                }),
+            ),
+            (
+                "payload_file_available",
+                EventPayload::FileAvailable(FileAnnouncement {
+                    id: "file-1".into(),
+                    post_id: "post-1".into(),
+                    thread_id: "thread-1".into(),
+                    original_name: Some("cat.png".into()),
+                    mime: Some("image/png".into()),
+                    size_bytes: Some(1024),
+                    checksum: Some("blake3:abcd".into()),
+                    blob_id: Some("abcd".into()),
+                    ticket: None,
+                }),
+            ),
+            (
+                "payload_post_edit",
+                EventPayload::PostEdit(PostEditEvent {
+                    post_id: "post-1".into(),
+                    thread_id: "thread-1".into(),
+                    author_peer_id: "PEERFINGERPRINT0001".into(),
+                    body: "edited".into(),
+                    updated_at: "2026-01-02T00:00:00Z".into(),
+                    deleted: false,
+                    signature: SIGNATURE.into(),
+                    author_public_key: None,
+                }),
+            ),
+            (
+                "payload_dm_receipt",
+                EventPayload::DmReceipt(DmReceiptEvent {
+                    message_id: "dm-1".into(),
+                    from_peer_id: "PEERFINGERPRINT0002".into(),
+                    to_peer_id: "PEERFINGERPRINT0001".into(),
+                    received_at: "2026-01-01T00:00:05Z".into(),
+                    signature: SIGNATURE.into(),
+                    signer_public_key: None,
+                }),
+            ),
+            (
+                "payload_topic_metadata",
+                EventPayload::TopicMetadata(TopicMetadata {
+                    topic_id: "cats".into(),
+                    founder_peer_id: "PEERFINGERPRINT0001".into(),
+                    description: "Pictures of cats".into(),
+                    rules: vec!["Cats only".into()],
+                    suggested_blocklists: vec![SuggestedBlocklist {
+                        maintainer_peer_id: "PEERFINGERPRINT0002".into(),
+                        name: "Dog people".into(),
+                    }],
+                    pow_difficulty: Some(18),
+                    media_policy: MediaPolicy::ImagesOnly,
+                    version: 3,
+                    updated_at: "2026-01-01T00:00:00Z".into(),
+                    signature: SIGNATURE.into(),
+                    founder_public_key: None,
+                }),
+            ),
+            (
+                "payload_topic_metadata_request",
+                EventPayload::TopicMetadataRequest(TopicMetadataRequest {
+                    topic_id: "cats".into(),
+                    known_version: 2,
+                }),
+            ),
+        ]
+    }
+
+    #[test]
+    fn payloads_match_golden_files() {
+        for (name, payload) in golden_payloads() {
+            assert_eq!(serde_json::to_value(&payload).unwrap(), golden_value(name), "{name} drifted");
+
+            let decoded = decode_payload(golden(name)).unwrap().expect("known kind");
+            assert_eq!(decoded.kind(), payload.kind());
+            assert!(EventPayload::KINDS.contains(&decoded.kind()));
+        }
+    }
+
+    #[test]
+    fn encoded_envelope_matches_golden_file() {
+        let bytes = encode_envelope_with(&profile_update(), &golden_signer(), vec!["signed-envelope".into()]).unwrap();
+        let encoded: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(encoded, golden_value("envelope_v2_signed"));
+    }
+
+    #[test]
+    fn signed_envelope_records_sender_protocol() {
+        let protocols = PeerProtocols::default();
+        let payload = decode_envelope(golden("envelope_v2_signed").as_bytes(), &protocols)
+            .unwrap()
+            .expect("known kind");
+        assert_eq!(payload.kind(), "ProfileUpdate");
+
+        let sender = iroh_base::PublicKey::from_bytes(golden_signer().verifying_key().as_bytes())
+            .unwrap()
+            .to_string();
+        let protocol = protocols.read().unwrap().get(&sender).cloned().expect("sender recorded");
+        assert_eq!(protocol.version, 2);
+        assert!(protocol.supports("signed-envelope"));
+        assert!(!protocol.supports("private-threads"));
+    }
+
+    #[test]
+    fn tampered_or_unsigned_v2_envelopes_are_rejected() {
+        let protocols = PeerProtocols::default();
+
+        let tampered = golden("envelope_v2_signed").replace("\"alice\"", "\"mallory\"");
+        assert!(decode_envelope(tampered.as_bytes(), &protocols).is_err());
+
+        let mut unsigned = golden_value("envelope_v2_signed");
+        unsigned.as_object_mut().unwrap().remove("signature");
+        let bytes = serde_json::to_vec(&unsigned).unwrap();
+        assert!(decode_envelope(&bytes, &protocols).is_err());
+
+        assert!(protocols.read().unwrap().is_empty());
+    }
+
+    #[test]
+    fn direct_envelopes_must_come_from_the_connecting_endpoint() {
+        let protocols = PeerProtocols::default();
+        let bytes = golden("envelope_v2_signed");
+        let sender = iroh_base::PublicKey::from_bytes(golden_signer().verifying_key().as_bytes())
+            .unwrap()
+            .to_string();
+        let other = iroh::SecretKey::from_bytes(&[9u8; 32]).public().to_string();
+
+        assert!(decode_direct_envelope(bytes.as_bytes(), &protocols, &sender).unwrap().is_some());
+        assert!(decode_direct_envelope(bytes.as_bytes(), &protocols, &other).is_err());
+    }
+
+    #[test]
+    fn unknown_payload_kind_is_skipped() {
+        let protocols = PeerProtocols::default();
+        let decoded = decode_envelope(golden("envelope_v2_unknown_kind").as_bytes(), &protocols).unwrap();
+        assert!(decoded.is_none());
+
+        // The sender still gets recorded, including capabilities we don't know
+        let guard = protocols.read().unwrap();
+        let protocol = guard.values().next().expect("sender recorded");
+        assert!(protocol.supports("future-event"));
+    }
+
+    #[test]
+    fn malformed_known_payload_is_an_error() {
+        assert!(decode_payload(r#"{"ProfileUpdate":{"peer_id":7}}"#).is_err());
+        assert!(decode_payload("[]").is_err());
+    }
+
+    #[test]
+    fn legacy_v1_envelope_is_accepted_unsigned_until_sunset() {
+        let protocols = PeerProtocols::default();
+        let at = |time: &str| min_protocol_version_at(DateTime::parse_from_rfc3339(time).unwrap().with_timezone(&Utc));
+        let during_window = at("2026-06-01T00:00:00Z");
+        let legacy = golden("envelope_v1_legacy");
+        let payload = decode_envelope_from(legacy.as_bytes(), &protocols, None, during_window)
+            .unwrap()
+            .expect("known kind");
+        assert_eq!(payload.kind(), "ProfileUpdate");
+        assert!(protocols.read().unwrap().is_empty());
+
+        let mut version_zero = golden_value("envelope_v1_legacy");
+        version_zero["version"] = 0.into();
+        let bytes = serde_json::to_vec(&version_zero).unwrap();
+        assert!(decode_envelope_from(&bytes, &protocols, None, during_window).is_err());
+
+        // Once the window closes only signed v2 envelopes get through
+        let after_sunset = at(V1_ENVELOPE_SUNSET);
+        assert!(decode_envelope_from(legacy.as_bytes(), &protocols, None, after_sunset).is_err());
+        let signed = golden("envelope_v2_signed");
+        assert!(decode_envelope_from(signed.as_bytes(), &protocols, None, after_sunset).is_ok());
+    }
+}
@@ -1,25 +1,38 @@
+use crate::agents::DelegatedAgent;
 use crate::blocking::IpBlockChecker;
 use crate::config::GraphchanPaths;
 use crate::database::models::{FileRecord, PostRecord, ReactionRecord, ThreadRecord};
-use crate::database::repositories::{FileRepository, PeerIpRepository, PeerRepository, PostRepository, ReactionRepository, ThreadRepository};
+use crate::database::repositories::{AgentKeyRepository, BlocklistRepository, DirectMessageRepository, DmOutboxRepository, DmSessionRepository, FileRepository, PeerIpRepository, PeerRepository, PostRepository, QuarantineRepository, ReactionRepository, SqliteRepositories, ThreadRepository};
 use crate::database::Database;
+use crate::live::{LiveEventData, LiveEventService};
+use crate::network::addresses::PeerAddressTracker;
+use crate::network::dedup::SeenCache;
+use crate::network::ratelimit::{AuthorBudget, IngestLimiter};
+use crate::network::sync;
 use crate::network::events::{
-    EventPayload, FileAnnouncement, FileChunk, FileRequest, InboundGossip, NetworkEvent,
-    ProfileUpdate, ReactionUpdate,
+    BlockActionEvent, DirectMessageEvent, DmPrekeyBundle, DmReceiptEvent, EventPayload, FileAnnouncement, FileChunk,
+    FileRequest, InboundGossip, NetworkEvent, PostEditEvent, ProfileUpdate, ReactionUpdate,
 };
+use crate::network::outbox::DmOutbox;
 use crate::peers::PeerService;
-use crate::threading::{PostView, ThreadDetails};
+use crate::pow::{post_resource, stamp_meets, thread_resource, PowService};
+use crate::private_threads::{open_attachment, PrivateThreadService, ThreadKeyGrant, PRIVATE_VISIBILITY};
+use crate::topic_config::{MetadataReplyThrottle, TopicConfigService};
+use crate::trust::{post_author_level, quarantine_record, AnnouncementBudget, TrustLevel, TrustService};
+use crate::threading::{
+    is_legacy_thread_hash, thread_hash_matches, PostView, ThreadDetails, SIGNATURE_UNSIGNED,
+    SIGNATURE_UNVERIFIED, SIGNATURE_VERIFIED,
+};
 use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 use blake3::Hasher;
 use iroh::endpoint::Endpoint;
 use iroh_blobs::store::fs::FsStore;
 use iroh_blobs::ticket::BlobTicket;
 use rusqlite::OptionalExtension;
-use std::collections::HashSet;
 use std::fs;
 use std::sync::Arc;
 use tokio::sync::mpsc::{Receiver, Sender};
-use tokio::sync::Mutex;
 
 /// Request to resynchronize a thread due to detected hash mismatch
 struct ResyncRequest {
@@ -36,15 +49,36 @@ pub async fn run_ingest_loop(
     endpoint: Arc<Endpoint>,
     local_peer_id: String,
     ip_blocker: IpBlockChecker,
+    seen_messages: SeenCache,
+    limiter: IngestLimiter,
 ) {
     tracing::info!("network ingest loop started");
 
-    // Cache of recently seen message IDs to prevent re-broadcast loops
+    // `seen_messages` holds recently seen message IDs to prevent re-broadcast loops
     // Format: "post:{id}" or "thread:{id}" or "file:{id}" etc.
-    let seen_messages: Arc<Mutex<HashSet<String>>> = Arc::new(Mutex::new(HashSet::new()));
+
+    let addresses = PeerAddressTracker::new(database.clone(), endpoint.clone(), ip_blocker.clone());
+    let trust = TrustService::new(database.clone());
+    let mut announcement_budget = AnnouncementBudget::default();
+    let topic_config = TopicConfigService::new(database.clone());
+    let mut metadata_replies = MetadataReplyThrottle::default();
 
     while let Some(message) = rx.recv().await {
         let peer = message.peer_id.clone();
+        // Flood protection runs before anything touches the database
+        let kind = message.payload.kind();
+        if let Some(reason) = limiter.check(peer.as_deref(), kind, message.size_bytes) {
+            tracing::debug!(?peer, kind, size_bytes = message.size_bytes, ?reason, "dropping rate-limited gossip payload");
+            continue;
+        }
+        // Authors are charged by the handlers, once their signature verified
+        let author_budget = limiter.author_budget(peer.as_deref(), kind, message.size_bytes);
+        // Record how the delivering neighbor is connected (feeds IP blocking)
+        if let Some(ref delivered_from) = peer {
+            if let Err(err) = addresses.observe(delivered_from).await {
+                tracing::debug!(error = ?err, "failed to capture peer IP");
+            }
+        }
         match handle_message(
             &database,
             &paths,
@@ -56,6 +90,11 @@ pub async fn run_ingest_loop(
             &seen_messages,
             &local_peer_id,
             &ip_blocker,
+            &trust,
+            &mut announcement_budget,
+            &topic_config,
+            &mut metadata_replies,
+            &author_budget,
         ).await {
             Ok(Some(resync_request)) => {
                 // Spawn background task to re-download thread
@@ -70,10 +109,11 @@ pub async fn run_ingest_loop(
                         thread_id = %resync_request.thread_id,
                         "🔄 triggering automatic thread re-sync due to hash mismatch"
                     );
-                    if let Err(err) = download_thread_snapshot_blob(
+                    if let Err(err) = resync_thread(
                         &db,
                         &p,
                         &pub_clone,
+                        &resync_request.thread_id,
                         resync_request.ticket,
                         blobs_clone,
                         ep,
@@ -102,33 +142,6 @@ pub async fn run_ingest_loop(
     tracing::info!("network ingest loop shutting down");
 }
 
-/// Capture and store the IP address of a peer from the endpoint connection
-///
-/// TODO: Iroh 0.94's Endpoint API doesn't directly expose per-peer connection information.
-/// Possible solutions:
-/// 1. Use endpoint.magic_endpoint() to access underlying connection tracking
-/// 2. Monitor connection events via endpoint event streams
-/// 3. Track connections at a lower level (QUIC connection events)
-/// 4. Wait for Iroh API updates that expose this information
-///
-/// For now, this is a stub. We'll need to implement proper IP tracking based on
-/// the actual Iroh API capabilities.
-async fn capture_peer_ip(
-    _database: &Database,
-    _endpoint: &Endpoint,
-    peer_id_str: &str,
-) -> Result<()> {
-    tracing::trace!(
-        peer_id = %peer_id_str,
-        "IP capture not yet implemented for Iroh 0.94 endpoint API"
-    );
-
-    // TODO: Implement actual IP extraction once we determine the correct Iroh API
-    // The infrastructure (database tables, repositories) is ready.
-
-    Ok(())
-}
-
 async fn handle_message(
     database: &Database,
     paths: &GraphchanPaths,
@@ -137,17 +150,15 @@ async fn handle_message(
     payload: EventPayload,
     blobs: &FsStore,
     endpoint: &Arc<Endpoint>,
-    seen_messages: &Arc<Mutex<HashSet<String>>>,
+    seen_messages: &SeenCache,
     local_peer_id: &str,
     ip_blocker: &IpBlockChecker,
+    trust: &TrustService,
+    announcement_budget: &mut AnnouncementBudget,
+    topic_config: &TopicConfigService,
+    metadata_replies: &mut MetadataReplyThrottle,
+    author_budget: &AuthorBudget,
 ) -> Result<Option<ResyncRequest>> {
-    // Capture peer IP address if available
-    if let Some(ref peer_id_str) = peer_id {
-        if let Err(err) = capture_peer_ip(database, endpoint, peer_id_str).await {
-            tracing::debug!(error = ?err, "failed to capture peer IP");
-        }
-    }
-
     match payload {
         EventPayload::ThreadAnnouncement(announcement) => {
             tracing::info!(
@@ -158,13 +169,95 @@ async fn handle_message(
                 "📢 received thread announcement (will download on-demand)"
             );
 
-            let msg_id = format!("thread:{}:{}", announcement.thread_id, announcement.thread_hash);
-            let should_rebroadcast = {
-                let mut seen = seen_messages.lock().await;
-                seen.insert(msg_id)
-            };
+            // Muted creators are ignored; strangers only get a few new threads stored per hour,
+            // and none on topics where we mute strangers
+            let creator_level = trust.level(&announcement.creator_peer_id)?;
+            let creator_policy = creator_level.policy();
+            let is_new_thread = database
+                .with_repositories(|repos| Ok(repos.threads().get(&announcement.thread_id)?.is_none()))?;
+            let muted_on_topic = is_new_thread
+                && creator_level == TrustLevel::Stranger
+                && topic_config.mutes_strangers(&announcement.topics)?;
+            if !creator_policy.accept_content
+                || muted_on_topic
+                || (is_new_thread && !announcement_budget.allow(&announcement.creator_peer_id, &creator_policy))
+            {
+                tracing::debug!(
+                    thread_id = %announcement.thread_id,
+                    creator = %announcement.creator_peer_id,
+                    "dropping thread announcement - creator muted, over their hourly allowance, or a stranger on a muted topic"
+                );
+                return Ok(None);
+            }
+
+            // New threads on topics with a difficulty need the creator's proof of work, unless they're a friend
+            let pow = PowService::new(database.clone());
+            let stamp_resource = thread_resource(
+                &announcement.thread_id,
+                &announcement.creator_peer_id,
+                &announcement.created_at,
+            );
+            if is_new_thread && creator_policy.require_pow {
+                let required = pow.required_for_topics(&announcement.topics)?;
+                if !stamp_meets(announcement.pow.as_ref(), &stamp_resource, required) {
+                    tracing::debug!(
+                        thread_id = %announcement.thread_id,
+                        creator = %announcement.creator_peer_id,
+                        required,
+                        "dropping under-stamped thread announcement"
+                    );
+                    return Ok(None);
+                }
+            }
+
+            // Private threads are only kept by members; everyone else just sees ciphertext
+            let private_grant = (announcement.visibility == PRIVATE_VISIBILITY).then(|| ThreadKeyGrant {
+                key_epoch: announcement.key_epoch,
+                wrapper_x25519_pubkey: announcement.wrapper_x25519_pubkey.clone(),
+                member_keys: announcement.member_keys.clone(),
+                signature: announcement.key_grant_signature.clone(),
+                creator_public_key: announcement.creator_public_key.clone(),
+            });
+            let private_threads = PrivateThreadService::new(database.clone(), paths.clone());
+            if let Some(grant) = &private_grant {
+                if !private_threads.is_member_or_invited(&announcement.thread_id, grant)? {
+                    tracing::debug!(
+                        thread_id = %announcement.thread_id,
+                        "ignoring private thread announcement - not a member"
+                    );
+                    return Ok(None);
+                }
+            }
+
+            let msg_id = format!("thread:{}:{}", announcement.thread_id, announced_thread_hash(&announcement));
+            let should_rebroadcast = seen_messages.insert(&msg_id);
 
             apply_thread_announcement(database, announcement.clone())?;
+            if should_rebroadcast {
+                LiveEventService::new(database.clone()).publish(LiveEventData::ThreadAnnounced {
+                    thread_id: announcement.thread_id.clone(),
+                    title: announcement.title.clone(),
+                    creator_peer_id: announcement.creator_peer_id.clone(),
+                    topics: announcement.topics.clone(),
+                });
+            }
+            if let Some(stamp) = &announcement.pow {
+                pow.remember_thread_stamp(&announcement.thread_id, &stamp_resource, stamp)?;
+            }
+            if let Some(grant) = &private_grant {
+                // A forged or unsigned grant is dropped; the announcement itself still relays
+                if let Err(err) = private_threads.accept_key_grant(
+                    &announcement.thread_id,
+                    &announcement.creator_peer_id,
+                    grant,
+                ) {
+                    tracing::warn!(
+                        thread_id = %announcement.thread_id,
+                        error = %err,
+                        "rejected private thread key grant"
+                    );
+                }
+            }
 
             // Re-broadcast only if this is the first time we've seen this version
             // CRITICAL: Change announcer_peer_id to OUR peer ID so we publish to OUR peer topic
@@ -191,16 +284,24 @@ async fn handle_message(
             );
 
             let msg_id = format!("post:{}", post.id);
-            let should_rebroadcast = {
-                let mut seen = seen_messages.lock().await;
-                seen.insert(msg_id)
-            };
-
-            let result = apply_post_update(database, ip_blocker, post.clone()).await?;
+            let should_rebroadcast = seen_messages.insert(&msg_id);
+
+            let gate = PostGate::for_thread(database, &post.thread_id)?.charging(author_budget);
+            let (outcome, result) = apply_post_update(database, ip_blocker, post.clone(), &gate).await?;
+            // Stubs of IP-blocked authors still relay the original, but aren't shown as new posts
+            let relay = matches!(outcome, PostOutcome::Stored | PostOutcome::Stub);
+            if should_rebroadcast && outcome == PostOutcome::Stored {
+                LiveEventService::new(database.clone()).publish(LiveEventData::PostReceived {
+                    post_id: post.id.clone(),
+                    thread_id: post.thread_id.clone(),
+                    author_peer_id: post.author_peer_id.clone(),
+                });
+            }
 
             // Re-broadcast only if this is the first time we've seen this post
             // This enables transitive post propagation: A → B → C → ...
-            if should_rebroadcast {
+            // Quarantined posts are not relayed until someone vouches for their author
+            if should_rebroadcast && relay {
                 let publisher_clone = publisher.clone();
                 let post_clone = post.clone();
                 tokio::spawn(async move {
@@ -212,6 +313,36 @@ async fn handle_message(
 
             Ok(result)
         }
+        EventPayload::PostEdit(edit) => {
+            let msg_id = format!("post-edit:{}:{}", edit.post_id, edit.updated_at);
+            if !seen_messages.insert(&msg_id) {
+                return Ok(None);
+            }
+
+            let Some(removed_files) = apply_post_edit(database, &edit, Some(author_budget))? else {
+                return Ok(None);
+            };
+            LiveEventService::new(database.clone()).publish(LiveEventData::PostEdited {
+                post_id: edit.post_id.clone(),
+                thread_id: edit.thread_id.clone(),
+                author_peer_id: edit.author_peer_id.clone(),
+                deleted: edit.deleted,
+            });
+            for path in removed_files {
+                if let Err(err) = fs::remove_file(paths.base.join(&path)) {
+                    tracing::debug!(error = ?err, path = %path, "failed to remove attachment of deleted post");
+                }
+            }
+
+            let publisher_clone = publisher.clone();
+            tokio::spawn(async move {
+                if let Err(err) = publisher_clone.send(NetworkEvent::Broadcast(EventPayload::PostEdit(edit))).await {
+                    tracing::warn!(error = ?err, "failed to re-broadcast PostEdit");
+                }
+            });
+
+            Ok(None)
+        }
         EventPayload::FileAvailable(announcement) => {
             tracing::debug!(
                 file_id = %announcement.id,
@@ -220,10 +351,7 @@ async fn handle_message(
                 "received FileAnnouncement"
             );
             let msg_id = format!("file:{}", announcement.id);
-            let should_rebroadcast = {
-                let mut seen = seen_messages.lock().await;
-                seen.insert(msg_id)
-            };
+            let should_rebroadcast = seen_messages.insert(&msg_id);
 
             let fetch_needed = apply_file_announcement(database, paths, &announcement)?;
             if fetch_needed && announcement.ticket.is_some() {
@@ -278,12 +406,6 @@ async fn handle_message(
             Ok(None)
         }
         EventPayload::ProfileUpdate(update) => {
-            let msg_id = format!("profile:{}", update.peer_id);
-            let should_rebroadcast = {
-                let mut seen = seen_messages.lock().await;
-                seen.insert(msg_id)
-            };
-
             // Download avatar blob if a ticket is provided and we don't have it locally
             if let (Some(ref avatar_id), Some(ref ticket)) = (&update.avatar_file_id, &update.ticket) {
                 let hash = ticket.hash();
@@ -313,10 +435,8 @@ async fn handle_message(
                 }
             }
 
-            apply_profile_update(database, update.clone())?;
-
             // Re-broadcast profile updates only if first time seeing this update
-            if should_rebroadcast {
+            if receive_profile_update(database, seen_messages, &update)? {
                 let publisher_clone = publisher.clone();
                 let update_clone = update.clone();
                 tokio::spawn(async move {
@@ -329,19 +449,31 @@ async fn handle_message(
             Ok(None)
         }
         EventPayload::ReactionUpdate(reaction) => {
+            if !trust.level(&reaction.reactor_peer_id)?.policy().accept_content {
+                return Ok(None);
+            }
             let msg_id = format!(
-                "reaction:{}:{}:{}:{}",
+                "reaction:{}:{}:{}:{}:{}",
                 reaction.post_id,
                 reaction.reactor_peer_id,
                 reaction.emoji,
-                reaction.is_removal
+                reaction.is_removal,
+                reaction.created_at
             );
-            let should_rebroadcast = {
-                let mut seen = seen_messages.lock().await;
-                seen.insert(msg_id)
-            };
+            let should_rebroadcast = seen_messages.insert(&msg_id);
 
-            apply_reaction_update(database, reaction.clone())?;
+            if !apply_reaction_update(database, reaction.clone(), Some(author_budget))? {
+                return Ok(None);
+            }
+            if should_rebroadcast {
+                LiveEventService::new(database.clone()).publish(LiveEventData::ReactionChanged {
+                    post_id: reaction.post_id.clone(),
+                    thread_id: reaction.thread_id.clone(),
+                    reactor_peer_id: reaction.reactor_peer_id.clone(),
+                    emoji: reaction.emoji.clone(),
+                    removed: reaction.is_removal,
+                });
+            }
 
             // Re-broadcast reaction updates only if first time seeing this update
             if should_rebroadcast {
@@ -358,113 +490,368 @@ async fn handle_message(
         }
 
         EventPayload::DirectMessage(dm) => {
-            let msg_id = format!("dm:{}", dm.message_id);
-            {
-                let mut seen = seen_messages.lock().await;
-                if !seen.insert(msg_id) {
-                    return Ok(None); // Already processed
+            if dm.to_peer_id != local_peer_id {
+                // Not ours: hold a copy if it's between two friends so it still
+                // arrives when the sender goes offline before the recipient is back
+                let msg_id = format!("dm:{}", dm.message_id);
+                if !seen_messages.insert(&msg_id) || dm.from_peer_id == local_peer_id {
+                    return Ok(None);
+                }
+                if DmOutbox::new(database.clone(), publisher.clone()).hold(&dm)? {
+                    tracing::debug!(message_id = %dm.message_id, "holding DM for offline friend");
                 }
+                return Ok(None);
             }
 
-            tracing::info!(
-                from = %dm.from_peer_id,
-                to = %dm.to_peer_id,
-                message_id = %dm.message_id,
-                "received DM via gossip"
-            );
+            // Gossip copies are the fallback when the sender couldn't reach us directly
+            let receipt = accept_direct_message(database, paths, &dm)?;
+            publisher
+                .send(NetworkEvent::Direct {
+                    peer_id: dm.from_peer_id.clone(),
+                    payload: EventPayload::DmReceipt(receipt),
+                    gossip_fallback: true,
+                })
+                .await
+                .context("failed to publish DM receipt")?;
+
+            // Don't re-broadcast DMs - they're point-to-point
+            Ok(None)
+        }
 
-            // Store the DM using DmService
-            let service = crate::dms::DmService::new(database.clone(), paths.clone());
-            if let Err(err) = service.ingest_dm(
-                &dm.from_peer_id,
-                &dm.to_peer_id,
-                &dm.encrypted_body,
-                &dm.nonce,
-                &dm.message_id,
-                &dm.conversation_id,
-                &dm.created_at,
-            ) {
-                tracing::warn!(error = ?err, "failed to ingest DM from gossip");
+        EventPayload::DmReceipt(receipt) => {
+            let msg_id = format!("dm-receipt:{}:{}", receipt.message_id, receipt.received_at);
+            if !seen_messages.insert(&msg_id) {
+                return Ok(None);
             }
 
-            // Don't re-broadcast DMs - they're point-to-point
+            if apply_dm_receipt(database, publisher, &receipt)? {
+                tracing::debug!(message_id = %receipt.message_id, "DM delivery acknowledged");
+            }
             Ok(None)
         }
 
         EventPayload::BlockAction(action) => {
+            // Include the signature so a forged copy can't shadow the real action
             let msg_id = format!(
                 "block:{}:{}:{}",
-                action.blocker_peer_id, action.blocked_peer_id, action.is_unblock
+                action.blocker_peer_id,
+                action.sequence,
+                blake3::hash(action.signature.as_deref().unwrap_or_default().as_bytes())
             );
-            {
-                let mut seen = seen_messages.lock().await;
-                if !seen.insert(msg_id) {
-                    return Ok(None);
-                }
+            if !seen_messages.insert(&msg_id) {
+                return Ok(None);
             }
 
             tracing::info!(
                 blocker = %action.blocker_peer_id,
                 blocked = %action.blocked_peer_id,
                 is_unblock = action.is_unblock,
+                sequence = action.sequence,
                 "received block action via gossip"
             );
 
-            // Apply block action if we're subscribed to this peer's blocklist with auto_apply
-            let checker = crate::blocking::BlockChecker::new(database.clone());
-            if let Ok(subscriptions) = checker.list_blocklist_subscriptions() {
-                for sub in &subscriptions {
-                    if sub.maintainer_peer_id == action.blocker_peer_id && sub.auto_apply {
-                        if action.is_unblock {
-                            let _ = checker.unblock_peer(&action.blocked_peer_id);
-                        } else {
-                            let _ = checker.block_peer(&action.blocked_peer_id, action.reason.clone());
-                        }
-                        break;
-                    }
-                }
+            // Only actions that changed our blocks are reported
+            if !apply_block_action(database, &action, Some(author_budget))? {
+                return Ok(None);
+            }
+            LiveEventService::new(database.clone()).publish(LiveEventData::BlockChanged {
+                blocker_peer_id: action.blocker_peer_id.clone(),
+                blocked_peer_id: action.blocked_peer_id.clone(),
+                unblocked: action.is_unblock,
+            });
+
+            Ok(None)
+        }
+
+        EventPayload::TopicMetadata(metadata) => {
+            // Include the signature so a forged copy can't shadow the founder's record
+            let msg_id = format!(
+                "topic-meta:{}:{}:{}",
+                metadata.topic_id,
+                metadata.version,
+                blake3::hash(metadata.signature.as_bytes())
+            );
+            if !seen_messages.insert(&msg_id) {
+                return Ok(None);
+            }
+
+            // Gossip already floods the topic, so accepted records aren't re-broadcast
+            if topic_config.accept_metadata(&metadata)? {
+                tracing::info!(
+                    topic = %metadata.topic_id,
+                    founder = %metadata.founder_peer_id,
+                    version = metadata.version,
+                    "stored topic metadata"
+                );
             }
+            Ok(None)
+        }
 
+        EventPayload::TopicMetadataRequest(request) => {
+            let Some(metadata) = topic_config.metadata(&request.topic_id)? else {
+                return Ok(None);
+            };
+            if metadata.version > request.known_version && metadata_replies.allow(&request.topic_id) {
+                tracing::debug!(topic = %request.topic_id, version = metadata.version, "answering topic metadata request");
+                let publisher_clone = publisher.clone();
+                tokio::spawn(async move {
+                    if let Err(err) = publisher_clone.send(NetworkEvent::Broadcast(EventPayload::TopicMetadata(metadata))).await {
+                        tracing::warn!(error = ?err, "failed to answer topic metadata request");
+                    }
+                });
+            }
             Ok(None)
         }
     }
 }
 
+/// Applies a maintainer's block action to every matching auto-apply subscription.
+///
+/// Only subscribed maintainers are considered, the action must carry a valid
+/// signature from the maintainer's key, and its sequence must be newer than the
+/// last one applied (so replayed or reordered actions are ignored).
+/// Returns whether a block or unblock was applied.
+fn apply_block_action(database: &Database, action: &BlockActionEvent, author_budget: Option<&AuthorBudget>) -> Result<bool> {
+    let checker = crate::blocking::BlockChecker::new(database.clone());
+    let subscriptions: Vec<_> = checker
+        .list_blocklist_subscriptions()?
+        .into_iter()
+        .filter(|sub| sub.maintainer_peer_id == action.blocker_peer_id)
+        .collect();
+    if subscriptions.is_empty() {
+        return Ok(false);
+    }
+
+    let Some(signature) = action.signature.as_deref() else {
+        anyhow::bail!("unsigned block action from {}", action.blocker_peer_id);
+    };
+    if action.sequence == 0 {
+        anyhow::bail!("unsequenced block action from {}", action.blocker_peer_id);
+    }
+    let sequence = i64::try_from(action.sequence).context("block action sequence out of range")?;
+
+    let should_apply = database.with_repositories(|repos| {
+        let candidates: Vec<&str> = action.blocker_public_key.as_deref().into_iter().collect();
+        let learned_key = verify_signed_by(
+            &repos,
+            &action.blocker_peer_id,
+            &candidates,
+            &action.signing_payload(),
+            signature,
+        )
+        .with_context(|| format!("invalid block action signature from {}", action.blocker_peer_id))?;
+        if let Some(key) = learned_key {
+            repos.peers().set_gpg_public_key(&action.blocker_peer_id, &key)?;
+        }
+        if author_budget.is_some_and(|budget| !budget.spend(&action.blocker_peer_id)) {
+            return Ok(None);
+        }
+
+        let mut should_apply = false;
+        for sub in &subscriptions {
+            let advanced = repos.blocklists().advance_sequence(&sub.id, sequence)?;
+            should_apply |= advanced && sub.auto_apply;
+        }
+        Ok(Some(should_apply))
+    })?;
+
+    let Some(should_apply) = should_apply else {
+        tracing::debug!(blocker = %action.blocker_peer_id, "dropping block action - maintainer over their rate limit");
+        return Ok(false);
+    };
+    if !should_apply {
+        tracing::debug!(
+            blocker = %action.blocker_peer_id,
+            sequence = action.sequence,
+            "ignoring stale or non-auto-apply block action"
+        );
+        return Ok(false);
+    }
+
+    if action.is_unblock {
+        checker.unblock_peer(&action.blocked_peer_id)?;
+    } else {
+        checker.block_peer(&action.blocked_peer_id, action.reason.clone())?;
+    }
+    Ok(true)
+}
+
+/// Stores a gossiped profile; returns whether to relay it, i.e. whether this
+/// exact profile is new to us.
+fn receive_profile_update(database: &Database, seen_messages: &SeenCache, update: &ProfileUpdate) -> Result<bool> {
+    let relay = seen_messages.insert(&profile_message_id(update)?);
+    apply_profile_update(database, update.clone())?;
+    Ok(relay)
+}
+
+/// Dedup ID of a profile update, keyed on its content: a peer's changed
+/// profile (a new agent revocation, say) must travel as far as its first did.
+fn profile_message_id(update: &ProfileUpdate) -> Result<String> {
+    let encoded = serde_json::to_vec(update)?;
+    Ok(format!("profile:{}:{}", update.peer_id, blake3::hash(&encoded).to_hex()))
+}
+
 fn apply_profile_update(database: &Database, update: ProfileUpdate) -> Result<()> {
     let service = PeerService::new(database.clone());
     service.update_profile(&update.peer_id, update.avatar_file_id, update.username, update.bio, update.agents, update.x25519_pubkey)?;
+
+    if let Some(bundle) = update.dm_prekey {
+        // A bad prekey shouldn't undo the rest of the profile
+        match apply_dm_prekey_bundle(database, &update.peer_id, &bundle) {
+            Ok(true) => tracing::debug!(peer_id = %update.peer_id, prekey_id = bundle.prekey_id, "stored DM prekey"),
+            Ok(false) => {}
+            Err(err) => tracing::warn!(peer_id = %update.peer_id, error = ?err, "rejected DM prekey bundle"),
+        }
+    }
+
+    for entry in update.agent_keys.iter().flatten() {
+        if let Err(err) = apply_delegated_agent(database, &update.peer_id, entry) {
+            tracing::warn!(
+                peer_id = %update.peer_id,
+                agent_id = %entry.delegation.agent_id,
+                error = ?err,
+                "rejected agent delegation"
+            );
+        }
+    }
     Ok(())
 }
 
-fn apply_reaction_update(database: &Database, reaction: ReactionUpdate) -> Result<()> {
+/// Stores a peer's agent key (and its revocation, if any) from their roster.
+///
+/// Both must be signed by the peer, whose key we need to hold already; agents
+/// of peers we don't know yet are learned from their posts instead.
+fn apply_delegated_agent(database: &Database, peer_id: &str, entry: &DelegatedAgent) -> Result<()> {
+    let delegation = &entry.delegation;
+    if delegation.owner_peer_id != peer_id {
+        anyhow::bail!("agent of {} sent in the profile of {}", delegation.owner_peer_id, peer_id);
+    }
+    if let Some(revocation) = &entry.revocation {
+        if revocation.agent_id != delegation.agent_id || revocation.owner_peer_id != peer_id {
+            anyhow::bail!("revocation does not match agent {}", delegation.agent_id);
+        }
+    }
+    database.with_repositories(|repos| {
+        verify_signed_by(&repos, peer_id, &[], &delegation.signing_payload(), &delegation.signature)
+            .context("invalid signature on agent delegation")?;
+        if let Some(revocation) = &entry.revocation {
+            verify_signed_by(&repos, peer_id, &[], &revocation.signing_payload(), &revocation.signature)
+                .context("invalid signature on agent revocation")?;
+        }
+        if let Some(stored) = repos.agent_keys().get(peer_id, &delegation.agent_id)? {
+            if stored.public_key != delegation.public_key {
+                anyhow::bail!("agent {} changed key", delegation.agent_id);
+            }
+        }
+        repos.agent_keys().upsert(&entry.to_record()?)
+    })
+}
+
+/// Stores a peer's signed DM prekey for opening forward-secret sessions.
+///
+/// The bundle must be the sender's own, name the X25519 identity key we hold
+/// for them and carry their signature. Returns whether it replaced an older one.
+fn apply_dm_prekey_bundle(database: &Database, peer_id: &str, bundle: &DmPrekeyBundle) -> Result<bool> {
+    if bundle.peer_id != peer_id {
+        anyhow::bail!("prekey bundle of {} sent in the profile of {}", bundle.peer_id, peer_id);
+    }
+    if !matches!(BASE64.decode(&bundle.prekey), Ok(key) if key.len() == 32) {
+        anyhow::bail!("prekey bundle from {} carries a malformed key", peer_id);
+    }
     database.with_repositories(|repos| {
-        if reaction.is_removal {
-            // Remove reaction
-            tracing::info!(
+        let Some(peer) = repos.peers().get(peer_id)? else {
+            return Ok(false);
+        };
+        if peer.x25519_pubkey.as_deref() != Some(bundle.identity_key.as_str()) {
+            anyhow::bail!("prekey bundle from {} names another identity key", peer_id);
+        }
+        let candidates: Vec<&str> = bundle.signer_public_key.as_deref().into_iter().collect();
+        let learned_key = verify_signed_by(&repos, peer_id, &candidates, &bundle.signing_payload(), &bundle.signature)
+            .with_context(|| format!("invalid signature on DM prekey from {}", peer_id))?;
+        if let Some(key) = learned_key {
+            repos.peers().set_gpg_public_key(peer_id, &key)?;
+        }
+        repos
+            .dm_sessions()
+            .set_prekey_bundle(peer_id, &serde_json::to_string(bundle)?, bundle.created_at)
+    })
+}
+
+/// Verifies and applies a reaction; returns whether it changed the post's reactions.
+fn apply_reaction_update(database: &Database, reaction: ReactionUpdate, author_budget: Option<&AuthorBudget>) -> Result<bool> {
+    database.with_repositories(|repos| {
+        // Reject forged or stripped reactions before touching the table
+        let candidates: Vec<&str> = reaction.reactor_public_key.as_deref().into_iter().collect();
+        let learned_key = verify_signed_by(
+            &repos,
+            &reaction.reactor_peer_id,
+            &candidates,
+            &reaction.signing_payload(),
+            &reaction.signature,
+        )
+        .with_context(|| {
+            format!(
+                "invalid reaction signature from {} on post {}",
+                reaction.reactor_peer_id, reaction.post_id
+            )
+        })?;
+        if let Some(key) = learned_key {
+            repos.peers().set_gpg_public_key(&reaction.reactor_peer_id, &key)?;
+        }
+        if author_budget.is_some_and(|budget| !budget.spend(&reaction.reactor_peer_id)) {
+            tracing::debug!(reactor = %reaction.reactor_peer_id, "dropping reaction - reactor over their rate limit");
+            return Ok(false);
+        }
+
+        // Last writer wins between the stored reaction, its newest removal and this update
+        let reactions = repos.reactions();
+        let current = reactions
+            .list_for_post(&reaction.post_id)?
+            .into_iter()
+            .find(|r| r.reactor_peer_id == reaction.reactor_peer_id && r.emoji == reaction.emoji);
+        let removed_at = reactions.removed_at(&reaction.post_id, &reaction.reactor_peer_id, &reaction.emoji)?;
+        let newer_state = current
+            .map(|r| r.created_at)
+            .into_iter()
+            .chain(removed_at)
+            .any(|at| at >= reaction.created_at);
+        if newer_state {
+            tracing::debug!(
                 post_id = %reaction.post_id,
                 reactor = %reaction.reactor_peer_id,
-                emoji = %reaction.emoji,
+                is_removal = reaction.is_removal,
+                "ignoring stale reaction update"
+            );
+            return Ok(false);
+        }
+
+        let is_removal = reaction.is_removal;
+        let record = ReactionRecord {
+            post_id: reaction.post_id,
+            reactor_peer_id: reaction.reactor_peer_id,
+            emoji: reaction.emoji,
+            signature: reaction.signature,
+            created_at: reaction.created_at,
+        };
+        if is_removal {
+            tracing::info!(
+                post_id = %record.post_id,
+                reactor = %record.reactor_peer_id,
+                emoji = %record.emoji,
                 "👎 removing reaction via gossip"
             );
-            repos.reactions().remove(&reaction.post_id, &reaction.reactor_peer_id, &reaction.emoji)?;
+            reactions.remove(&record.post_id, &record.reactor_peer_id, &record.emoji)?;
+            reactions.record_removal(&record)?;
         } else {
-            // Add reaction
             tracing::info!(
-                post_id = %reaction.post_id,
-                reactor = %reaction.reactor_peer_id,
-                emoji = %reaction.emoji,
+                post_id = %record.post_id,
+                reactor = %record.reactor_peer_id,
+                emoji = %record.emoji,
                 "👍 adding reaction via gossip"
             );
-            let record = ReactionRecord {
-                post_id: reaction.post_id,
-                reactor_peer_id: reaction.reactor_peer_id,
-                emoji: reaction.emoji,
-                signature: reaction.signature,
-                created_at: reaction.created_at,
-            };
-            repos.reactions().add(&record)?;
+            reactions.add(&record)?;
         }
-        Ok(())
+        Ok(true)
     })
 }
 
@@ -478,18 +865,34 @@ fn apply_thread_announcement(
     database.with_repositories(|repos| {
         // Check if we already have this thread
         let existing = repos.threads().get(&announcement.thread_id)?;
+        let remote_hash = announced_thread_hash(&announcement).to_string();
         if let Some(existing_thread) = existing {
             // Compare hashes to detect if we need to sync
-            match (&existing_thread.thread_hash, &announcement.thread_hash) {
-                (Some(local_hash), remote_hash) if local_hash == remote_hash => {
+            let in_sync = match &existing_thread.thread_hash {
+                Some(local_hash) if local_hash == &remote_hash => true,
+                // Stored and announced hashes use different versions (older peer, or a hash
+                // stored before upgrading) - recompute in the announcer's version instead
+                Some(local_hash) if is_legacy_thread_hash(local_hash) != is_legacy_thread_hash(&remote_hash) => {
+                    thread_hash_matches(&local_post_views(&repos, &announcement.thread_id)?, &remote_hash)
+                }
+                _ => false,
+            };
+            match (&existing_thread.thread_hash, in_sync) {
+                (Some(local_hash), true) => {
                     tracing::debug!(
                         thread_id = %announcement.thread_id,
                         hash = %local_hash,
                         "thread hash matches - already in sync"
                     );
+                    // Upgrade a legacy stored hash once the canonical one is confirmed
+                    if is_legacy_thread_hash(local_hash) && !is_legacy_thread_hash(&remote_hash) {
+                        let mut upgraded = existing_thread.clone();
+                        upgraded.thread_hash = Some(remote_hash);
+                        repos.threads().upsert(&upgraded)?;
+                    }
                     return Ok(());
                 }
-                (Some(local_hash), remote_hash) => {
+                (Some(local_hash), false) => {
                     tracing::info!(
                         thread_id = %announcement.thread_id,
                         local_hash = %local_hash,
@@ -498,13 +901,19 @@ fn apply_thread_announcement(
                     );
                     // Update the thread record with new hash and ticket
                     // The actual sync will happen when user views the thread
+                    // Private announcements carry a placeholder title; keep the real one
+                    let title = if announcement.visibility == PRIVATE_VISIBILITY {
+                        existing_thread.title.clone()
+                    } else {
+                        announcement.title.clone()
+                    };
                     let updated_thread = ThreadRecord {
                         id: announcement.thread_id.clone(),
-                        title: announcement.title.clone(),
+                        title,
                         creator_peer_id: Some(announcement.creator_peer_id.clone()),
                         created_at: announcement.created_at.clone(),
                         pinned: existing_thread.pinned,
-                        thread_hash: Some(announcement.thread_hash.clone()),
+                        thread_hash: Some(remote_hash.clone()),
                         visibility: existing_thread.visibility.clone(),
                         topic_secret: existing_thread.topic_secret.clone(),
                         sync_status: existing_thread.sync_status.clone(),
@@ -546,8 +955,9 @@ fn apply_thread_announcement(
                 x25519_pubkey: None,
                 last_seen: None,
                 avatar_file_id: None,
-                trust_state: "unknown".into(),
+                trust_state: "stranger".into(),
                 agents: None,
+                gpg_public_key: None,
             };
             peers_repo.upsert(&stub_peer)?;
         }
@@ -559,8 +969,12 @@ fn apply_thread_announcement(
             creator_peer_id: Some(announcement.creator_peer_id.clone()),
             created_at: announcement.created_at.clone(),
             pinned: false,
-            thread_hash: Some(announcement.thread_hash.clone()),
-            visibility: "social".to_string(),
+            thread_hash: Some(remote_hash.clone()),
+            visibility: if announcement.visibility == PRIVATE_VISIBILITY {
+                PRIVATE_VISIBILITY.to_string()
+            } else {
+                "social".to_string()
+            },
             topic_secret: None,
             sync_status: "announced".to_string(),  // Mark as announced but not yet downloaded
             source_url: None,
@@ -580,6 +994,8 @@ fn apply_thread_announcement(
             created_at: announcement.created_at.clone(),
             updated_at: None,
             metadata: None,
+            signature: None,
+            signature_status: None,
         };
         repos.posts().upsert(&op_post)?;
 
@@ -628,59 +1044,10 @@ fn apply_thread_snapshot(
         }
     }
 
+    let gate = PostGate::for_thread(database, &thread.id)?;
     database.with_repositories(|repos| {
         // First, ingest all peers from the snapshot
-        let peers_repo = repos.peers();
-        for peer in &snapshot.peers {
-            let record = crate::database::models::PeerRecord {
-                id: peer.id.clone(),
-                alias: peer.alias.clone(),
-                username: peer.username.clone(),
-                bio: peer.bio.clone(),
-                friendcode: peer.friendcode.clone(),
-                iroh_peer_id: peer.iroh_peer_id.clone(),
-                gpg_fingerprint: peer.gpg_fingerprint.clone(),
-                x25519_pubkey: peer.x25519_pubkey.clone(),
-                last_seen: peer.last_seen.clone(),
-                avatar_file_id: peer.avatar_file_id.clone(),
-                trust_state: peer.trust_state.clone(),
-                agents: peer.agents.as_ref().and_then(|a| serde_json::to_string(a).ok()),
-            };
-            peers_repo.upsert(&record)?;
-        }
-
-        // Collect all author peer IDs from posts
-        let mut all_author_ids = std::collections::HashSet::new();
-        if let Some(creator_id) = &thread.creator_peer_id {
-            all_author_ids.insert(creator_id.clone());
-        }
-        for post in &posts {
-            if let Some(author_id) = &post.author_peer_id {
-                all_author_ids.insert(author_id.clone());
-            }
-        }
-
-        // Create stub peer records for any authors not in the snapshot
-        for author_id in all_author_ids {
-            if peers_repo.get(&author_id)?.is_none() {
-                tracing::info!(peer_id = %author_id, "creating stub peer for unknown author in thread snapshot");
-                let stub_peer = crate::database::models::PeerRecord {
-                    id: author_id.clone(),
-                    alias: None,
-                    username: Some(format!("Unknown ({})", &author_id[..8])),
-                    bio: None,
-                    friendcode: None,
-                    iroh_peer_id: None,
-                    gpg_fingerprint: Some(author_id.clone()),
-                    x25519_pubkey: None,
-                    last_seen: None,
-                    avatar_file_id: None,
-                    trust_state: "unknown".into(),
-                    agents: None,
-                };
-                peers_repo.upsert(&stub_peer)?;
-            }
-        }
+        store_snapshot_peers(&repos, thread.creator_peer_id.as_deref(), &snapshot.peers, &posts)?;
 
         // Now upsert thread and posts
         // Calculate hash from the posts we're applying
@@ -702,9 +1069,6 @@ fn apply_thread_snapshot(
         };
         repos.threads().upsert(&thread_record)?;
 
-        let posts_repo = repos.posts();
-        let files_repo = repos.files();
-
         // Delete the preview placeholder post if it exists
         let preview_post_id = format!("{}-preview", thread.id);
         if let Err(err) = repos.conn().execute(
@@ -714,63 +1078,206 @@ fn apply_thread_snapshot(
             tracing::warn!(error = ?err, post_id = %preview_post_id, "failed to delete preview post");
         }
 
-        for post in &posts {
-            upsert_post(&posts_repo, &post)?;
-
-            // Also save file metadata from the post
-            for file in &post.files {
-                let file_record = crate::database::models::FileRecord {
-                    id: file.id.clone(),
-                    post_id: file.post_id.clone(),
-                    path: file.path.clone(),
-                    original_name: file.original_name.clone(),
-                    mime: file.mime.clone(),
-                    blob_id: file.blob_id.clone(),
-                    size_bytes: file.size_bytes,
-                    checksum: file.checksum.clone(),
-                    ticket: file.ticket.clone(),
-                    download_status: file.download_status.clone().or(Some("pending".to_string())),
-                };
-                files_repo.upsert(&file_record)?;
-            }
-        }
+        store_snapshot_posts(&repos, &thread.id, &posts, &snapshot.peers, &gate)?;
 
         Ok(())
     })?;
 
     // After creating posts, check for any files that need downloading
     // (Files might have arrived before the posts existed)
-    for post_id in post_ids {
-        let files = database.with_repositories(|repos| {
-            repos.files().list_for_post(&post_id)
-        })?;
+    download_pending_files(database, paths, &thread.id, &post_ids, blobs, endpoint)
+}
 
-        for file in files {
-            tracing::debug!(
-                file_id = %file.id,
-                size_bytes = ?file.size_bytes,
-                size_mb = file.size_bytes.map(|s| s / (1024 * 1024)),
-                has_ticket = file.ticket.is_some(),
-                "checking file from thread snapshot"
-            );
+/// Upserts the peers carried alongside snapshot or sync posts, plus stub peers
+/// for any author (or the creator) we still know nothing about.
+fn store_snapshot_peers(
+    repos: &SqliteRepositories<'_>,
+    creator_peer_id: Option<&str>,
+    peers: &[crate::peers::PeerView],
+    posts: &[PostView],
+) -> Result<()> {
+    let peers_repo = repos.peers();
+    for peer in peers {
+        let record = crate::database::models::PeerRecord {
+            id: peer.id.clone(),
+            alias: peer.alias.clone(),
+            username: peer.username.clone(),
+            bio: peer.bio.clone(),
+            friendcode: peer.friendcode.clone(),
+            iroh_peer_id: peer.iroh_peer_id.clone(),
+            gpg_fingerprint: peer.gpg_fingerprint.clone(),
+            x25519_pubkey: peer.x25519_pubkey.clone(),
+            last_seen: peer.last_seen.clone(),
+            avatar_file_id: peer.avatar_file_id.clone(),
+            // Only applies to new rows (upsert keeps our own trust); never adopt the sender's
+            trust_state: TrustLevel::Stranger.as_str().into(),
+            agents: peer.agents.as_ref().and_then(|a| serde_json::to_string(a).ok()),
+            // Only keep keys that actually belong to this peer ID
+            gpg_public_key: peer
+                .gpg_public_key
+                .clone()
+                .filter(|key| crate::crypto::public_key_matches(key, &peer.id)),
+        };
+        peers_repo.upsert(&record)?;
+    }
 
-            let needs_fetch = file_needs_download(paths, &file)?;
+    // Collect all author peer IDs from posts
+    let mut all_author_ids = std::collections::HashSet::new();
+    if let Some(creator_id) = creator_peer_id {
+        all_author_ids.insert(creator_id.to_string());
+    }
+    for post in posts {
+        if let Some(author_id) = &post.author_peer_id {
+            all_author_ids.insert(author_id.clone());
+        }
+    }
 
-            // Don't auto-download large files - let user manually trigger download
-            const AUTO_DOWNLOAD_SIZE_LIMIT: i64 = 50 * 1024 * 1024; // 50MB
-            let should_auto_download = if let Some(size) = file.size_bytes {
-                let auto_dl = size <= AUTO_DOWNLOAD_SIZE_LIMIT;
-                tracing::debug!(
-                    file_id = %file.id,
-                    size_mb = size / (1024 * 1024),
-                    limit_mb = AUTO_DOWNLOAD_SIZE_LIMIT / (1024 * 1024),
-                    should_auto_download = auto_dl,
-                    "file size check result"
-                );
-                auto_dl
-            } else {
-                tracing::warn!(file_id = %file.id, "no size info, allowing auto-download");
-                true // If no size info, allow auto-download
+    // Create stub peer records for any authors not in the snapshot
+    for author_id in all_author_ids {
+        if peers_repo.get(&author_id)?.is_none() {
+            tracing::info!(peer_id = %author_id, "creating stub peer for unknown author in thread snapshot");
+            let stub_peer = crate::database::models::PeerRecord {
+                id: author_id.clone(),
+                alias: None,
+                username: Some(format!("Unknown ({})", &author_id[..8])),
+                bio: None,
+                friendcode: None,
+                iroh_peer_id: None,
+                gpg_fingerprint: Some(author_id.clone()),
+                x25519_pubkey: None,
+                last_seen: None,
+                avatar_file_id: None,
+                trust_state: "stranger".into(),
+                agents: None,
+                gpg_public_key: None,
+            };
+            peers_repo.upsert(&stub_peer)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Verifies and stores posts (with their DAG edges and file metadata) that
+/// arrived in bulk. Posts with bad signatures, or that `gate` turns away, are
+/// dropped individually; new posts of strangers are quarantined.
+fn store_snapshot_posts(
+    repos: &SqliteRepositories<'_>,
+    thread_id: &str,
+    posts: &[PostView],
+    peers: &[crate::peers::PeerView],
+    gate: &PostGate,
+) -> Result<()> {
+    let posts_repo = repos.posts();
+    let files_repo = repos.files();
+
+    for post in posts {
+        let author_key = post.author_peer_id.as_ref().and_then(|author_id| {
+            peers
+                .iter()
+                .find(|peer| &peer.id == author_id)
+                .and_then(|peer| peer.gpg_public_key.as_deref())
+        });
+        let check = match verify_post_signature(repos, post, author_key) {
+            Ok(check) => check,
+            Err(err) => {
+                tracing::warn!(
+                    error = ?err,
+                    post_id = %post.id,
+                    thread_id = %thread_id,
+                    "dropping post with bad signature from thread snapshot"
+                );
+                continue;
+            }
+        };
+        check.remember_key(repos, post)?;
+        let PostAdmission::Store { quarantine } = gate.admit(repos, post, check.status)? else {
+            tracing::debug!(
+                post_id = %post.id,
+                thread_id = %thread_id,
+                "dropping snapshot post - author muted, or a stranger on a topic that mutes them"
+            );
+            continue;
+        };
+        let is_new = posts_repo.get(&post.id)?.is_none();
+        upsert_post(&posts_repo, post, check.status)?;
+        if quarantine && is_new {
+            repos
+                .quarantine()
+                .add(&quarantine_record(&post.id, thread_id, post.author_peer_id.as_deref()))?;
+        }
+        if posts_repo.is_tombstoned(&post.id)? {
+            // Deleted posts keep no attachments
+            continue;
+        }
+
+        // Also save file metadata from the post
+        for file in &post.files {
+            let file_record = crate::database::models::FileRecord {
+                id: file.id.clone(),
+                post_id: file.post_id.clone(),
+                path: file.path.clone(),
+                original_name: file.original_name.clone(),
+                mime: file.mime.clone(),
+                blob_id: file.blob_id.clone(),
+                size_bytes: file.size_bytes,
+                checksum: file.checksum.clone(),
+                ticket: file.ticket.clone(),
+                download_status: file.download_status.clone().or(Some("pending".to_string())),
+                encryption_key: file.encryption_key.clone(),
+            };
+            files_repo.upsert(&file_record)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Starts blob downloads for files of `post_ids` that are missing locally and
+/// small enough to fetch automatically.
+fn download_pending_files(
+    database: &Database,
+    paths: &GraphchanPaths,
+    thread_id: &str,
+    post_ids: &[String],
+    blobs: &FsStore,
+    endpoint: &Arc<Endpoint>,
+) -> Result<()> {
+    for post_id in post_ids {
+        let files = database.with_repositories(|repos| {
+            repos.files().list_for_post(post_id)
+        })?;
+        if files.is_empty() {
+            continue;
+        }
+
+        for file in files {
+            let author_trusted = auto_download_allowed(database, post_id, file.mime.as_deref())?;
+            tracing::debug!(
+                file_id = %file.id,
+                size_bytes = ?file.size_bytes,
+                size_mb = file.size_bytes.map(|s| s / (1024 * 1024)),
+                has_ticket = file.ticket.is_some(),
+                "checking file from thread snapshot"
+            );
+
+            let needs_fetch = file_needs_download(paths, &file)?;
+
+            // Don't auto-download large files - let user manually trigger download
+            const AUTO_DOWNLOAD_SIZE_LIMIT: i64 = 50 * 1024 * 1024; // 50MB
+            let should_auto_download = if let Some(size) = file.size_bytes {
+                let auto_dl = size <= AUTO_DOWNLOAD_SIZE_LIMIT;
+                tracing::debug!(
+                    file_id = %file.id,
+                    size_mb = size / (1024 * 1024),
+                    limit_mb = AUTO_DOWNLOAD_SIZE_LIMIT / (1024 * 1024),
+                    should_auto_download = auto_dl,
+                    "file size check result"
+                );
+                auto_dl
+            } else {
+                tracing::warn!(file_id = %file.id, "no size info, allowing auto-download");
+                true // If no size info, allow auto-download
             };
 
             tracing::debug!(
@@ -781,7 +1288,7 @@ fn apply_thread_snapshot(
                 "download decision factors"
             );
 
-            if needs_fetch && file.ticket.is_some() && should_auto_download {
+            if needs_fetch && file.ticket.is_some() && should_auto_download && author_trusted {
                 tracing::info!(
                     file_id = %file.id,
                     post_id = %post_id,
@@ -791,7 +1298,7 @@ fn apply_thread_snapshot(
                 let announcement = FileAnnouncement {
                     id: file.id.clone(),
                     post_id: file.post_id.clone(),
-                    thread_id: thread.id.clone(),
+                    thread_id: thread_id.to_string(),
                     original_name: file.original_name.clone(),
                     mime: file.mime.clone(),
                     size_bytes: file.size_bytes,
@@ -818,6 +1325,12 @@ fn apply_thread_snapshot(
                     size_mb = file.size_bytes.unwrap_or(0) / (1024 * 1024),
                     "⏸️ file exceeds auto-download limit, marked as pending for manual download"
                 );
+            } else if needs_fetch && !author_trusted {
+                tracing::info!(
+                    file_id = %file.id,
+                    post_id = %post_id,
+                    "⏸️ author or topic settings don't allow auto-download, marked as pending for manual download"
+                );
             }
         }
     }
@@ -826,12 +1339,20 @@ fn apply_thread_snapshot(
 }
 
 /// Create a stub post for IP-blocked content to preserve graph structure
+///
+/// Only fills a gap: a post we already hold is left alone, so a copy claiming
+/// a blocked author can't replace it. Returns whether the stub was stored.
 fn create_stub_post_for_blocked_ip(
     database: &Database,
     post: &PostView,
     blocked_ip: std::net::IpAddr,
-) -> Result<Option<ResyncRequest>> {
+) -> Result<bool> {
     database.with_repositories(|repos| {
+        if repos.posts().get(&post.id)?.is_some() {
+            tracing::debug!(post_id = %post.id, "keeping stored post instead of an IP-block stub");
+            return Ok(false);
+        }
+
         // Create stub post record with placeholder body
         let stub_body = format!("[Post from IP-blocked peer: {}]", blocked_ip);
 
@@ -844,6 +1365,8 @@ fn create_stub_post_for_blocked_ip(
             created_at: post.created_at.clone(),
             updated_at: post.updated_at.clone(),
             metadata: None,
+            signature: None,
+            signature_status: None,
         };
 
         // Store stub post
@@ -858,11 +1381,63 @@ fn create_stub_post_for_blocked_ip(
             "✅ created stub post for IP-blocked content"
         );
 
-        Ok(None) // No resync needed for stub posts
+        Ok(true)
     })
 }
 
-async fn apply_post_update(database: &Database, ip_blocker: &IpBlockChecker, post: PostView) -> Result<Option<ResyncRequest>> {
+/// The hash a thread announcement should be compared by: canonical when the sender has it.
+fn announced_thread_hash(announcement: &crate::network::events::ThreadAnnouncement) -> &str {
+    announcement
+        .canonical_thread_hash
+        .as_deref()
+        .unwrap_or(&announcement.thread_hash)
+}
+
+/// Rebuilds the local posts of a thread (with parents and files) for hash comparison.
+fn local_post_views(repos: &SqliteRepositories<'_>, thread_id: &str) -> Result<Vec<PostView>> {
+    let posts = repos.posts().list_for_thread(thread_id)?;
+    Ok(posts.iter().map(|p| {
+        let parents = repos.posts().parents_of(&p.id).unwrap_or_default();
+        let files = repos.files().list_for_post(&p.id).unwrap_or_default();
+        let file_views = files.into_iter()
+            .map(crate::files::FileView::from_record)
+            .collect();
+        // Parse metadata JSON if present
+        let metadata = p.metadata.as_ref().and_then(|json_str| {
+            serde_json::from_str::<crate::threading::PostMetadata>(json_str).ok()
+        });
+        PostView {
+            id: p.id.clone(),
+            thread_id: p.thread_id.clone(),
+            author_peer_id: p.author_peer_id.clone(),
+            author_friendcode: p.author_friendcode.clone(),
+            body: p.body.clone(),
+            created_at: p.created_at.clone(),
+            updated_at: p.updated_at.clone(),
+            deleted: repos.posts().is_tombstoned(&p.id).unwrap_or(false),
+            quarantined: false,
+            pow: None,
+            parent_post_ids: parents,
+            files: file_views,
+            thread_hash: None,
+            canonical_thread_hash: None,
+            metadata,
+            signature: p.signature.clone(),
+            signature_status: p.signature_status.clone(),
+            author_public_key: None,
+        }
+    }).collect())
+}
+
+async fn apply_post_update(
+    database: &Database,
+    ip_blocker: &IpBlockChecker,
+    post: PostView,
+    gate: &PostGate,
+) -> Result<(PostOutcome, Option<ResyncRequest>)> {
+    // Reject forged posts before anything is stored or rebroadcast, stubs included
+    let check = database.with_repositories(|repos| verify_post_signature(&repos, &post, None))?;
+
     // Check if author's IP is blocked (using previously stored IP from peer_ips table)
     if let Some(author_id) = &post.author_peer_id {
         match ip_blocker.is_peer_blocked(author_id).await {
@@ -877,12 +1452,10 @@ async fn apply_post_update(database: &Database, ip_blocker: &IpBlockChecker, pos
                 );
 
                 // Record hit for statistics
-                if let Err(err) = ip_blocker.record_hit(block_id).await {
-                    tracing::warn!(error = ?err, "failed to record IP block hit");
-                }
+                ip_blocker.record_hit(block_id);
 
                 // Create stub post that preserves graph structure
-                return create_stub_post_for_blocked_ip(database, &post, ip);
+                return Ok((stub_outcome(create_stub_post_for_blocked_ip(database, &post, ip)?), None));
             }
             Ok((true, None, Some(ip))) => {
                 // Blocked but no block_id (shouldn't happen but handle gracefully)
@@ -892,7 +1465,7 @@ async fn apply_post_update(database: &Database, ip_blocker: &IpBlockChecker, pos
                     ip = %ip,
                     "IP blocked but no block_id found - creating stub anyway"
                 );
-                return create_stub_post_for_blocked_ip(database, &post, ip);
+                return Ok((stub_outcome(create_stub_post_for_blocked_ip(database, &post, ip)?), None));
             }
             Ok((true, _, None)) => {
                 // Blocked but couldn't determine IP (shouldn't happen)
@@ -922,42 +1495,21 @@ async fn apply_post_update(database: &Database, ip_blocker: &IpBlockChecker, pos
                 post_id = %post.id,
                 "⚠️ skipping PostUpdate - thread unknown (may need to download thread first)"
             );
-            return Ok(None);
+            return Ok((PostOutcome::Dropped, None));
         }
 
         // Check thread hash for synchronization
         let mut resync_request = None;
-        if let Some(remote_hash) = &post.thread_hash {
-            // Get current local posts and calculate local hash
-            let posts = repos.posts().list_for_thread(&post.thread_id)?;
-            let local_posts: Vec<PostView> = posts.iter().map(|p| {
-                let parents = repos.posts().parents_of(&p.id).unwrap_or_default();
-                let files = repos.files().list_for_post(&p.id).unwrap_or_default();
-                let file_views = files.into_iter()
-                    .map(crate::files::FileView::from_record)
-                    .collect();
-                // Parse metadata JSON if present
-                let metadata = p.metadata.as_ref().and_then(|json_str| {
-                    serde_json::from_str::<crate::threading::PostMetadata>(json_str).ok()
-                });
-                PostView {
-                    id: p.id.clone(),
-                    thread_id: p.thread_id.clone(),
-                    author_peer_id: p.author_peer_id.clone(),
-                    author_friendcode: p.author_friendcode.clone(),
-                    body: p.body.clone(),
-                    created_at: p.created_at.clone(),
-                    updated_at: p.updated_at.clone(),
-                    parent_post_ids: parents,
-                    files: file_views,
-                    thread_hash: None,
-                    metadata,
-                }
-            }).collect();
-
-            let local_hash = crate::threading::calculate_thread_hash(&local_posts);
+        if let Some(remote_hash) = post.canonical_thread_hash.as_ref().or(post.thread_hash.as_ref()) {
+            // Get current local posts and compare in the hash version the sender used
+            let local_posts = local_post_views(&repos, &post.thread_id)?;
 
-            if &local_hash != remote_hash {
+            if !thread_hash_matches(&local_posts, remote_hash) {
+                let local_hash = if is_legacy_thread_hash(remote_hash) {
+                    crate::threading::calculate_legacy_thread_hash(&local_posts)
+                } else {
+                    crate::threading::calculate_thread_hash(&local_posts)
+                };
                 tracing::warn!(
                     thread_id = %post.thread_id,
                     post_id = %post.id,
@@ -1000,7 +1552,7 @@ async fn apply_post_update(database: &Database, ip_blocker: &IpBlockChecker, pos
             } else {
                 tracing::debug!(
                     thread_id = %post.thread_id,
-                    hash = %local_hash,
+                    hash = %remote_hash,
                     "thread hash matches - in sync"
                 );
             }
@@ -1074,36 +1626,336 @@ async fn apply_post_update(database: &Database, ip_blocker: &IpBlockChecker, pos
                     x25519_pubkey: None,
                     last_seen: None,
                     avatar_file_id: None,
-                    trust_state: "unknown".into(),
+                    trust_state: "stranger".into(),
                     agents: None,
+                    gpg_public_key: None,
                 };
                 peers_repo.upsert(&stub_peer)?;
             }
         }
 
+        check.remember_key(&repos, &post)?;
+        let PostAdmission::Store { quarantine } = gate.admit(&repos, &post, check.status)? else {
+            tracing::debug!(post_id = %post.id, author = ?post.author_peer_id, "dropping post - author muted, or a stranger on a topic that mutes them");
+            return Ok((PostOutcome::Dropped, resync_request));
+        };
+
         let posts_repo = repos.posts();
-        upsert_post(&posts_repo, &post)?;
+        let is_new = posts_repo.get(&post.id)?.is_none();
+        upsert_post(&posts_repo, &post, check.status)?;
+        // Only first arrivals; a post already shown (or approved) stays visible
+        if quarantine && is_new {
+            repos
+                .quarantine()
+                .add(&quarantine_record(&post.id, &post.thread_id, post.author_peer_id.as_deref()))?;
+        }
 
         tracing::info!(
             post_id = %post.id,
             thread_id = %post.thread_id,
+            signature_status = check.status,
+            quarantined = quarantine && is_new,
             "✅ applied PostUpdate successfully"
         );
 
-        Ok(resync_request)
+        let outcome = if quarantine && is_new { PostOutcome::Quarantined } else { PostOutcome::Stored };
+        Ok((outcome, resync_request))
     })
 }
 
-fn upsert_post<R>(repo: &R, post: &PostView) -> Result<()>
+/// `Stub` if `create_stub_post_for_blocked_ip` stored one; otherwise the post changed nothing.
+fn stub_outcome(stored: bool) -> PostOutcome {
+    if stored {
+        PostOutcome::Stub
+    } else {
+        PostOutcome::Dropped
+    }
+}
+
+/// What `apply_post_update` did with a post.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PostOutcome {
+    /// Not stored: its thread is unknown, or `PostGate` turned it away
+    Dropped,
+    /// Its author's IP is blocked; a stub keeps the thread's shape where we had no copy
+    Stub,
+    /// Stored and held for review
+    Quarantined,
+    Stored,
+}
+
+/// What `PostGate` decided for a post.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PostAdmission {
+    Drop,
+    Store { quarantine: bool },
+}
+
+/// Trust rules a received post must pass, whether it arrived live, in a
+/// snapshot or through sync.
+///
+/// Thread-wide settings are read up front; the decision itself is made under
+/// the connection lock, right after the post's signature was checked, so the
+/// author's trust is only taken from a verified claim.
+struct PostGate {
+    mutes_strangers: bool,
+    /// Proof of work the thread's topics demand of authors who aren't friends
+    pow_required: u32,
+    /// Live posts are charged to their verified author; bulk transfers are not
+    author_budget: Option<AuthorBudget>,
+}
+
+impl PostGate {
+    fn for_thread(database: &Database, thread_id: &str) -> Result<Self> {
+        Ok(Self {
+            mutes_strangers: TopicConfigService::new(database.clone()).thread_mutes_strangers(thread_id)?,
+            pow_required: PowService::new(database.clone()).required_for_thread(thread_id)?,
+            author_budget: None,
+        })
+    }
+
+    fn charging(self, author_budget: &AuthorBudget) -> Self {
+        Self {
+            author_budget: Some(author_budget.clone()),
+            ..self
+        }
+    }
+
+    /// Drops posts of muted authors, of strangers on topics that mute them, and
+    /// of non-friends without enough proof of work; strangers' posts are
+    /// otherwise stored for review. Verified authors over their rate limit are
+    /// dropped too.
+    fn admit(&self, repos: &SqliteRepositories<'_>, post: &PostView, signature_status: &str) -> Result<PostAdmission> {
+        let verified = signature_status == SIGNATURE_VERIFIED;
+        let level = post_author_level(repos, post.author_peer_id.as_deref(), verified)?;
+        let policy = level.policy();
+        if !policy.accept_content || (level == TrustLevel::Stranger && self.mutes_strangers) {
+            return Ok(PostAdmission::Drop);
+        }
+        if policy.require_pow && !stamp_meets(post.pow.as_ref(), &post_resource(post), self.pow_required) {
+            tracing::debug!(post_id = %post.id, author = ?post.author_peer_id, required = self.pow_required, "dropping under-stamped post");
+            return Ok(PostAdmission::Drop);
+        }
+        let over_budget = match (&self.author_budget, post.author_peer_id.as_deref()) {
+            (Some(budget), Some(author)) if verified => !budget.spend(author),
+            _ => false,
+        };
+        if over_budget {
+            tracing::debug!(post_id = %post.id, author = ?post.author_peer_id, "dropping post - author over their rate limit");
+            return Ok(PostAdmission::Drop);
+        }
+        Ok(PostAdmission::Store {
+            quarantine: policy.quarantine_posts,
+        })
+    }
+}
+
+/// Result of checking a post's author signature.
+struct SignatureCheck {
+    status: &'static str,
+    /// Key that verified the post but is not yet stored for the author
+    learned_key: Option<String>,
+}
+
+impl SignatureCheck {
+    fn remember_key(&self, repos: &SqliteRepositories<'_>, post: &PostView) -> Result<()> {
+        if let (Some(key), Some(author_id)) = (&self.learned_key, &post.author_peer_id) {
+            repos.peers().set_gpg_public_key(author_id, key)?;
+        }
+        Ok(())
+    }
+}
+
+/// Verifies a post's detached signature against the author's OpenPGP key.
+///
+/// The key is taken from the stored peer record, or else from a candidate
+/// (embedded in the post or carried by a snapshot) whose fingerprint must equal
+/// `author_peer_id`. Errors mean the post must not be stored: the signature is
+/// invalid, or the update would downgrade an already verified post.
+fn verify_post_signature(
+    repos: &SqliteRepositories<'_>,
+    post: &PostView,
+    candidate_key: Option<&str>,
+) -> Result<SignatureCheck> {
+    let existing_verified = repos
+        .posts()
+        .get(&post.id)?
+        .and_then(|existing| existing.signature_status)
+        .is_some_and(|status| status == SIGNATURE_VERIFIED);
+    let ensure_not_downgrade = |status: &'static str| -> Result<SignatureCheck> {
+        if existing_verified {
+            anyhow::bail!("refusing to replace verified post {} with {} copy", post.id, status);
+        }
+        Ok(SignatureCheck { status, learned_key: None })
+    };
+
+    // An agent claim is only as good as the author's signature over it
+    let agent = post.metadata.as_ref().and_then(|meta| meta.agent.as_ref());
+    let reject_unchecked_agent = |agent: &AgentInfo| -> Result<SignatureCheck> {
+        anyhow::bail!("post {} names agent '{}' without a verifiable author signature", post.id, agent.name)
+    };
+
+    let Some(signature) = post.signature.as_deref() else {
+        if let Some(agent) = agent {
+            return reject_unchecked_agent(agent);
+        }
+        // Authors whose key we hold sign everything; an unsigned copy is a forgery or a stripped signature
+        if let Some(author_id) = post.author_peer_id.as_deref() {
+            if stored_public_key(repos, author_id)?.is_some() {
+                anyhow::bail!("unsigned post {} claims author {} whose key we hold", post.id, author_id);
+            }
+        }
+        return ensure_not_downgrade(SIGNATURE_UNSIGNED);
+    };
+    let Some(author_id) = post.author_peer_id.as_deref() else {
+        anyhow::bail!("signed post {} has no author", post.id);
+    };
+    let candidates: Vec<&str> = [post.author_public_key.as_deref(), candidate_key]
+        .into_iter()
+        .flatten()
+        .collect();
+    if candidates.is_empty() && stored_public_key(repos, author_id)?.is_none() {
+        if let Some(agent) = agent {
+            return reject_unchecked_agent(agent);
+        }
+        // Signed, but we have no way to check it yet
+        return ensure_not_downgrade(SIGNATURE_UNVERIFIED);
+    }
+
+    let learned_key = verify_signed_by(repos, author_id, &candidates, &post.signing_payload(), signature)
+        .with_context(|| format!("invalid signature on post {} from {}", post.id, author_id))?;
+    if let Some(agent) = agent {
+        verify_post_agent(repos, post, agent, author_id, &candidates)?;
+    }
+    Ok(SignatureCheck { status: SIGNATURE_VERIFIED, learned_key })
+}
+
+/// Checks that `agent` signed the post with a key its author delegated and had
+/// not revoked when the post was made, then remembers the delegation.
+///
+/// Revocation is judged when a post is first seen (`made_before_revocation`);
+/// copies of posts we already hold passed that check when they arrived.
+fn verify_post_agent(
+    repos: &SqliteRepositories<'_>,
+    post: &PostView,
+    agent: &AgentInfo,
+    author_id: &str,
+    candidates: &[&str],
+) -> Result<()> {
+    let delegation = crate::agents::check_post_agent(post, agent)?;
+    verify_signed_by(repos, author_id, candidates, &delegation.signing_payload(), &delegation.signature)
+        .with_context(|| format!("agent '{}' on post {} was not delegated by {}", agent.name, post.id, author_id))?;
+
+    let agent_keys = repos.agent_keys();
+    let Some(stored) = agent_keys.get(author_id, &delegation.agent_id)? else {
+        let entry = DelegatedAgent { delegation: delegation.clone(), revocation: None };
+        return agent_keys.upsert(&entry.to_record()?);
+    };
+    if stored.public_key != delegation.public_key {
+        anyhow::bail!("agent '{}' on post {} presents a replaced key", agent.name, post.id);
+    }
+    if let Some(revoked_at) = stored.revoked_at {
+        let first_seen = repos.posts().get(&post.id)?.is_none();
+        let received_at = chrono::Utc::now().timestamp();
+        if first_seen && !crate::agents::made_before_revocation(&post.created_at, revoked_at, received_at) {
+            anyhow::bail!("agent '{}' was revoked before post {}", agent.name, post.id);
+        }
+    }
+    Ok(())
+}
+
+fn stored_public_key(repos: &SqliteRepositories<'_>, peer_id: &str) -> Result<Option<String>> {
+    Ok(repos.peers().get(peer_id)?.and_then(|peer| peer.gpg_public_key))
+}
+
+/// Verifies `signature` over `payload` as made by `signer_id`.
+///
+/// Uses the signer's stored key when we have one; otherwise tries each candidate
+/// key (fingerprint-bound to `signer_id`) and returns the one that verified so the
+/// caller can persist it.
+fn verify_signed_by(
+    repos: &SqliteRepositories<'_>,
+    signer_id: &str,
+    candidate_keys: &[&str],
+    payload: &[u8],
+    signature: &str,
+) -> Result<Option<String>> {
+    if let Some(stored_key) = stored_public_key(repos, signer_id)? {
+        crate::crypto::verify_detached(&stored_key, signer_id, payload, signature)?;
+        return Ok(None);
+    }
+    if candidate_keys.is_empty() {
+        anyhow::bail!("no public key known for {}", signer_id);
+    }
+    for key in candidate_keys {
+        if crate::crypto::verify_detached(key, signer_id, payload, signature).is_ok() {
+            return Ok(Some(key.to_string()));
+        }
+    }
+    anyhow::bail!("signature does not verify against any key for {}", signer_id)
+}
+
+/// Stores a remote copy of a post, keeping whichever version is newest.
+///
+/// Posts are versioned by `updated_at` (falling back to `created_at`): a newer
+/// copy is applied as an edit (or tombstone) so the previous body lands in the
+/// revision history, an older copy only contributes reply edges, and a
+/// tombstoned post is never resurrected. Edits and tombstones are only taken
+/// from copies whose signature verified against the author's key; anyone can
+/// bump `updated_at` on an unsigned copy.
+fn upsert_post<R>(repo: &R, post: &PostView, signature_status: &str) -> Result<()>
 where
     R: PostRepository,
 {
+    let verified = signature_status == SIGNATURE_VERIFIED;
+    if let Some(existing) = repo.get(&post.id)? {
+        if existing.author_peer_id != post.author_peer_id {
+            anyhow::bail!("post {} changed author", post.id);
+        }
+        let stored_version = existing.updated_at.as_deref().unwrap_or(&existing.created_at);
+        let incoming_version = post.updated_at.as_deref().unwrap_or(&post.created_at);
+        let newer = incoming_version > stored_version;
+
+        if repo.is_tombstoned(&post.id)? {
+            tracing::debug!(post_id = %post.id, "ignoring copy of deleted post");
+        } else if newer && !verified {
+            tracing::debug!(post_id = %post.id, signature_status, "ignoring unverified edit or deletion");
+        } else if newer && post.deleted {
+            repo.tombstone(&post.id, incoming_version, post.signature.as_deref(), signature_status)?;
+        } else if newer {
+            repo.apply_edit(&post.id, &post.body, incoming_version, post.signature.as_deref(), signature_status)?;
+        } else if incoming_version < stored_version {
+            tracing::debug!(post_id = %post.id, "ignoring stale copy of edited post");
+        } else {
+            repo.upsert(&post_record(post, signature_status))?;
+        }
+        repo.add_relationships(&post.id, &post.parent_post_ids)?;
+        return Ok(());
+    }
+
+    if post.deleted && !verified {
+        // An unverified tombstone would block the real post from ever being stored
+        tracing::debug!(post_id = %post.id, signature_status, "ignoring unverified deleted post");
+        return Ok(());
+    }
+    let record = post_record(post, signature_status);
+    repo.upsert(&record)?;
+    repo.add_relationships(&record.id, &post.parent_post_ids)?;
+    if post.deleted {
+        // First sight of an already retracted post: keep it as a tombstone
+        let deleted_at = post.updated_at.as_deref().unwrap_or(&post.created_at);
+        repo.tombstone(&post.id, deleted_at, post.signature.as_deref(), signature_status)?;
+    }
+    Ok(())
+}
+
+fn post_record(post: &PostView, signature_status: &str) -> PostRecord {
     // Serialize metadata to JSON if present
     let metadata_json = post.metadata.as_ref().and_then(|meta| {
         serde_json::to_string(meta).ok()
     });
 
-    let record = PostRecord {
+    PostRecord {
         id: post.id.clone(),
         thread_id: post.thread_id.clone(),
         author_peer_id: post.author_peer_id.clone(),
@@ -1112,10 +1964,133 @@ where
         created_at: post.created_at.clone(),
         updated_at: post.updated_at.clone(),
         metadata: metadata_json,
-    };
-    repo.upsert(&record)?;
-    repo.add_relationships(&record.id, &post.parent_post_ids)?;
-    Ok(())
+        signature: post.signature.clone(),
+        signature_status: Some(signature_status.to_string()),
+    }
+}
+
+/// Stores a DM addressed to us and returns the receipt acknowledging it.
+///
+/// Retransmits are expected until a receipt gets through, so a message we
+/// already hold is not stored again but still acknowledged.
+pub(crate) fn accept_direct_message(
+    database: &Database,
+    paths: &GraphchanPaths,
+    dm: &DirectMessageEvent,
+) -> Result<DmReceiptEvent> {
+    let service = crate::dms::DmService::new(database.clone(), paths.clone());
+    let already_stored = database
+        .with_repositories(|repos| repos.direct_messages().get(&dm.message_id))?
+        .is_some();
+    if !already_stored {
+        tracing::info!(
+            from = %dm.from_peer_id,
+            to = %dm.to_peer_id,
+            message_id = %dm.message_id,
+            "received DM"
+        );
+        service.ingest_dm(dm).context("failed to ingest DM")?;
+        LiveEventService::new(database.clone()).publish(LiveEventData::DirectMessage {
+            message_id: dm.message_id.clone(),
+            conversation_id: dm.conversation_id.clone(),
+            from_peer_id: dm.from_peer_id.clone(),
+            group_id: dm.group_id.clone(),
+        });
+    }
+    service.delivery_receipt(&dm.message_id, &dm.from_peer_id)
+}
+
+/// Applies a delivery receipt to our outbox, if it concerns a DM we track.
+///
+/// The receipt must be signed by the DM's recipient. Returns whether an
+/// outbox entry was acknowledged or released.
+fn apply_dm_receipt(database: &Database, publisher: &Sender<NetworkEvent>, receipt: &DmReceiptEvent) -> Result<bool> {
+    let tracked = database.with_repositories(|repos| {
+        if repos.dm_outbox().get(&receipt.message_id)?.is_none() {
+            return Ok(false);
+        }
+        let candidates: Vec<&str> = receipt.signer_public_key.as_deref().into_iter().collect();
+        let learned_key = verify_signed_by(
+            &repos,
+            &receipt.from_peer_id,
+            &candidates,
+            &receipt.signing_payload(),
+            &receipt.signature,
+        )
+        .with_context(|| format!("invalid receipt for DM {}", receipt.message_id))?;
+        if let Some(key) = learned_key {
+            repos.peers().set_gpg_public_key(&receipt.from_peer_id, &key)?;
+        }
+        Ok(true)
+    })?;
+    if !tracked {
+        return Ok(false);
+    }
+    DmOutbox::new(database.clone(), publisher.clone()).acknowledge(receipt)
+}
+
+/// Applies an author's edit or deletion of a post we already hold.
+///
+/// The edit is checked against the stored post: same author and thread, and a
+/// signature over the edited post that verifies with the author's key.
+/// Returns the local attachment paths of a deleted post so they can be removed,
+/// or `None` when nothing changed: the post is unknown or deleted, the edit is
+/// not newer than the stored version, or the author is over their rate limit.
+fn apply_post_edit(
+    database: &Database,
+    edit: &PostEditEvent,
+    author_budget: Option<&AuthorBudget>,
+) -> Result<Option<Vec<String>>> {
+    database.with_repositories(|repos| {
+        let Some(stored) = repos.posts().get(&edit.post_id)? else {
+            tracing::debug!(post_id = %edit.post_id, "ignoring edit of unknown post");
+            return Ok(None);
+        };
+        if stored.thread_id != edit.thread_id {
+            anyhow::bail!("edit of post {} names the wrong thread", edit.post_id);
+        }
+        if stored.author_peer_id.as_deref() != Some(edit.author_peer_id.as_str()) {
+            anyhow::bail!("edit of post {} is not from its author", edit.post_id);
+        }
+
+        let current = local_post_views(&repos, &edit.thread_id)?
+            .into_iter()
+            .find(|post| post.id == edit.post_id)
+            .context("edited post vanished")?;
+        let stored_version = current.updated_at.as_deref().unwrap_or(&current.created_at);
+        if current.deleted || edit.updated_at.as_str() <= stored_version {
+            tracing::debug!(post_id = %edit.post_id, "ignoring stale edit or edit of a deleted post");
+            return Ok(None);
+        }
+        let edited = edit.apply_to(&current);
+        let check = verify_post_signature(&repos, &edited, None)?;
+        if check.status != SIGNATURE_VERIFIED {
+            anyhow::bail!("edit of post {} could not be verified", edit.post_id);
+        }
+        check.remember_key(&repos, &edited)?;
+        if author_budget.is_some_and(|budget| !budget.spend(&edit.author_peer_id)) {
+            tracing::debug!(post_id = %edit.post_id, "dropping edit - author over their rate limit");
+            return Ok(None);
+        }
+
+        let removed_files: Vec<String> = if edit.deleted {
+            repos.files().list_for_post(&edit.post_id)?
+                .into_iter()
+                .map(|file| file.path)
+                .collect()
+        } else {
+            Vec::new()
+        };
+        upsert_post(&repos.posts(), &edited, check.status)?;
+
+        tracing::info!(
+            post_id = %edit.post_id,
+            thread_id = %edit.thread_id,
+            deleted = edit.deleted,
+            "✏️ applied PostEdit"
+        );
+        Ok(Some(removed_files))
+    })
 }
 
 fn apply_file_announcement(
@@ -1130,10 +2105,15 @@ fn apply_file_announcement(
     );
 
     // Check if we already have this file locally (we're the uploader)
-    let existing_record = database.with_repositories(|repos| {
-        repos.files().get(&announcement.id)
+    let (existing_record, post_deleted) = database.with_repositories(|repos| {
+        Ok((repos.files().get(&announcement.id)?, repos.posts().is_tombstoned(&announcement.post_id)?))
     })?;
 
+    if post_deleted {
+        tracing::debug!(file_id = %announcement.id, post_id = %announcement.post_id, "ignoring file of deleted post");
+        return Ok(false);
+    }
+
     if let Some(existing) = &existing_record {
         let existing_path = paths.base.join(&existing.path);
         if existing_path.exists() {
@@ -1153,7 +2133,7 @@ fn apply_file_announcement(
     }
 
     // We don't have it locally, create/update record for download
-    let relative_path = format!("files/downloads/{}", announcement.id);
+    let relative_path = crate::files::download_path(&announcement.id)?;
     let record = FileRecord {
         id: announcement.id.clone(),
         post_id: announcement.post_id.clone(),
@@ -1165,6 +2145,7 @@ fn apply_file_announcement(
         checksum: announcement.checksum.clone(),
         ticket: announcement.ticket.as_ref().map(|t| t.to_string()),
         download_status: Some("pending".to_string()),
+        encryption_key: None,
     };
 
     // Always persist the file record, even if post doesn't exist yet
@@ -1192,6 +2173,16 @@ fn apply_file_announcement(
         "checked if download needed"
     );
 
+    if needs_fetch && !auto_download_allowed(database, &announcement.post_id, announcement.mime.as_deref())? {
+        tracing::info!(
+            file_id = %announcement.id,
+            post_id = %announcement.post_id,
+            "⏸️ author or topic settings don't allow auto-download, marked as pending for manual download"
+        );
+        ensure_download_directory(paths)?;
+        return Ok(false);
+    }
+
     // Don't auto-download large files - let user manually trigger download
     const AUTO_DOWNLOAD_SIZE_LIMIT: i64 = 50 * 1024 * 1024; // 50MB
     if needs_fetch {
@@ -1230,6 +2221,24 @@ fn apply_file_announcement(
     Ok(needs_fetch)
 }
 
+/// Whether an attachment of type `mime` on `post_id` may be fetched unasked: the
+/// author must be trusted enough, and the thread's topics must allow it.
+fn auto_download_allowed(database: &Database, post_id: &str, mime: Option<&str>) -> Result<bool> {
+    let (post, author_level) = database.with_repositories(|repos| {
+        let post = repos.posts().get(post_id)?;
+        let verified = post
+            .as_ref()
+            .is_some_and(|post| post.signature_status.as_deref() == Some(SIGNATURE_VERIFIED));
+        let level = post_author_level(&repos, post.as_ref().and_then(|post| post.author_peer_id.as_deref()), verified)?;
+        Ok((post, level))
+    })?;
+    let author_trusted = author_level.policy().auto_download;
+    match post {
+        Some(post) if author_trusted => TopicConfigService::new(database.clone()).auto_download_allowed(&post.thread_id, mime),
+        _ => Ok(author_trusted),
+    }
+}
+
 fn ensure_download_directory(paths: &GraphchanPaths) -> Result<()> {
     if !paths.downloads_dir.exists() {
         fs::create_dir_all(&paths.downloads_dir)?;
@@ -1268,52 +2277,53 @@ fn respond_with_file_chunk(
     peer_id: &str,
     request: FileRequest,
 ) -> Result<()> {
-    let record = match database.with_repositories(|repos| repos.files().get(&request.file_id))? {
-        Some(record) => record,
-        None => {
-            tracing::debug!(file_id = %request.file_id, "ignoring file request for unknown file");
-            return Ok(());
-        }
-    };
-    let absolute = paths.base.join(&record.path);
-    if !absolute.exists() {
-        tracing::warn!(file_id = %request.file_id, path = %absolute.display(), "⚠️  requested file missing locally");
+    let Some(chunk) = file_chunk_for(database, paths, &request)? else {
         return Ok(());
-    }
-    let data = fs::read(&absolute)
-        .with_context(|| format!("failed to read file for chunk: {}", absolute.display()))?;
-    tracing::info!(file_id = %request.file_id, size = %data.len(), "sending file chunk");
-    let chunk_payload = EventPayload::FileChunk(FileChunk {
-        file_id: request.file_id,
-        data,
-        eof: true,
-    });
+    };
 
     let direct_sender = publisher.clone();
     let direct_peer = peer_id.to_string();
-    let direct_payload = chunk_payload.clone();
     tokio::spawn(async move {
         if let Err(err) = direct_sender
             .send(NetworkEvent::Direct {
                 peer_id: direct_peer,
-                payload: direct_payload,
+                payload: EventPayload::FileChunk(chunk),
+                gossip_fallback: false,
             })
             .await
         {
             tracing::warn!(error = ?err, "failed to enqueue direct file chunk");
         }
     });
+    Ok(())
+}
 
-    let broadcast_sender = publisher.clone();
-    tokio::spawn(async move {
-        if let Err(err) = broadcast_sender
-            .send(NetworkEvent::Broadcast(chunk_payload))
-            .await
-        {
-            tracing::warn!(error = ?err, "failed to broadcast file chunk");
+/// Reads a local file for a `FileRequest`; `None` if we don't have it.
+pub(crate) fn file_chunk_for(
+    database: &Database,
+    paths: &GraphchanPaths,
+    request: &FileRequest,
+) -> Result<Option<FileChunk>> {
+    let record = match database.with_repositories(|repos| repos.files().get(&request.file_id))? {
+        Some(record) => record,
+        None => {
+            tracing::debug!(file_id = %request.file_id, "ignoring file request for unknown file");
+            return Ok(None);
         }
-    });
-    Ok(())
+    };
+    let absolute = paths.base.join(&record.path);
+    if !absolute.exists() {
+        tracing::warn!(file_id = %request.file_id, path = %absolute.display(), "⚠️  requested file missing locally");
+        return Ok(None);
+    }
+    let data = fs::read(&absolute)
+        .with_context(|| format!("failed to read file for chunk: {}", absolute.display()))?;
+    tracing::info!(file_id = %request.file_id, size = %data.len(), "sending file chunk");
+    Ok(Some(FileChunk {
+        file_id: request.file_id.clone(),
+        data,
+        eof: true,
+    }))
 }
 
 fn apply_file_chunk(database: &Database, paths: &GraphchanPaths, chunk: FileChunk) -> Result<()> {
@@ -1321,7 +2331,7 @@ fn apply_file_chunk(database: &Database, paths: &GraphchanPaths, chunk: FileChun
         tracing::debug!(file_id = %chunk.file_id, "received non-eof chunk; treating as complete file");
     }
     ensure_download_directory(paths)?;
-    let relative = format!("files/downloads/{}", chunk.file_id);
+    let relative = crate::files::download_path(&chunk.file_id)?;
     let absolute = paths.base.join(&relative);
 
     fs::write(&absolute, &chunk.data)
@@ -1360,10 +2370,93 @@ fn apply_file_chunk(database: &Database, paths: &GraphchanPaths, chunk: FileChun
     Ok(())
 }
 
+/// Brings a thread back in line with a peer after a hash mismatch.
+///
+/// Public and social threads are reconciled over `SYNC_ALPN`, fetching only
+/// the posts whose hashes differ. Private threads, and peers that can't serve
+/// sync, fall back to re-downloading the full snapshot blob.
+async fn resync_thread(
+    database: &Database,
+    paths: &GraphchanPaths,
+    publisher: &Sender<NetworkEvent>,
+    thread_id: &str,
+    ticket: BlobTicket,
+    blob_store: FsStore,
+    endpoint: Arc<Endpoint>,
+) -> Result<()> {
+    let local = crate::threading::ThreadService::new(database.clone()).get_thread(thread_id)?;
+    if let Some(local) = local.filter(|details| details.thread.visibility != PRIVATE_VISIBILITY) {
+        match sync::fetch_missing_posts(&endpoint, ticket.addr().clone(), thread_id, &local.posts).await {
+            Ok(None) => {
+                tracing::info!(thread_id = %thread_id, "thread already matches peer - nothing to sync");
+                return Ok(());
+            }
+            Ok(Some(delta)) => {
+                tracing::info!(
+                    thread_id = %thread_id,
+                    post_count = delta.posts.len(),
+                    "applying posts fetched via thread sync"
+                );
+                return apply_synced_posts(database, paths, thread_id, delta, &blob_store, &endpoint);
+            }
+            Err(err) => {
+                tracing::info!(
+                    error = ?err,
+                    thread_id = %thread_id,
+                    "thread sync unavailable - falling back to snapshot download"
+                );
+            }
+        }
+    }
+
+    download_thread_snapshot_blob(database, paths, publisher, thread_id, ticket, blob_store, endpoint).await
+}
+
+/// Stores the posts of a `SyncDelta` and refreshes the local thread hash.
+fn apply_synced_posts(
+    database: &Database,
+    paths: &GraphchanPaths,
+    thread_id: &str,
+    delta: sync::SyncDelta,
+    blobs: &FsStore,
+    endpoint: &Arc<Endpoint>,
+) -> Result<()> {
+    let post_ids: Vec<String> = delta.posts.iter().map(|p| p.id.clone()).collect();
+
+    let gate = PostGate::for_thread(database, thread_id)?;
+    database.with_repositories(|repos| {
+        let creator = repos.threads().get(thread_id)?.and_then(|thread| thread.creator_peer_id);
+        store_snapshot_peers(&repos, creator.as_deref(), &delta.peers, &delta.posts)?;
+        store_snapshot_posts(&repos, thread_id, &delta.posts, &delta.peers, &gate)
+    })?;
+
+    let local = crate::threading::ThreadService::new(database.clone())
+        .get_thread(thread_id)?
+        .with_context(|| format!("thread {thread_id} disappeared during sync"))?;
+    let thread_hash = crate::threading::calculate_thread_hash(&local.posts);
+    database.with_repositories(|repos| {
+        if let Some(mut record) = repos.threads().get(thread_id)? {
+            record.thread_hash = Some(thread_hash.clone());
+            repos.threads().upsert(&record)?;
+        }
+        Ok(())
+    })?;
+
+    if thread_hash == delta.remote_thread_hash {
+        tracing::info!(thread_id = %thread_id, "✅ thread hash matches peer after sync");
+    } else {
+        // We may hold posts the peer hasn't seen yet; those are theirs to fetch
+        tracing::debug!(thread_id = %thread_id, "thread hash still differs from peer after sync");
+    }
+
+    download_pending_files(database, paths, thread_id, &post_ids, blobs, endpoint)
+}
+
 async fn download_thread_snapshot_blob(
     database: &Database,
     paths: &GraphchanPaths,
     publisher: &Sender<NetworkEvent>,
+    thread_id: &str,
     ticket: BlobTicket,
     blob_store: FsStore,
     endpoint: Arc<Endpoint>,
@@ -1410,6 +2503,10 @@ async fn download_thread_snapshot_blob(
     // Clean up temp file
     let _ = std::fs::remove_file(&temp_path);
 
+    // Snapshots of private threads are sealed with the thread key
+    let blob_bytes = PrivateThreadService::new(database.clone(), paths.clone())
+        .open_snapshot(thread_id, blob_bytes)?;
+
     // Deserialize the ThreadDetails
     let snapshot: crate::threading::ThreadDetails = serde_json::from_slice(&blob_bytes)
         .context("failed to deserialize thread snapshot from blob")?;
@@ -1456,6 +2553,7 @@ async fn download_blob(
         );
 
         // Set status to 'downloading'
+        publish_download_status(database, announcement, "downloading");
         let db_clone = database.clone();
         let file_id = announcement.id.clone();
         let _ = tokio::task::spawn_blocking(move || -> Result<()> {
@@ -1491,6 +2589,7 @@ async fn download_blob(
                 );
 
                 // Set status to 'failed'
+                publish_download_status(database, announcement, "failed");
                 let db_clone = database.clone();
                 let file_id = announcement.id.clone();
                 let _ = tokio::task::spawn_blocking(move || -> Result<()> {
@@ -1512,7 +2611,7 @@ async fn download_blob(
 
     // Export blob to file
     ensure_download_directory(paths)?;
-    let relative_path = format!("files/downloads/{}", announcement.id);
+    let relative_path = crate::files::download_path(&announcement.id)?;
     let absolute_path = paths.base.join(&relative_path);
 
     // Read blob data and write to file - use export method
@@ -1521,9 +2620,20 @@ async fn download_blob(
         .await
         .with_context(|| format!("failed to export blob to {}", absolute_path.display()))?;
 
-    let data = fs::read(&absolute_path)
+    let mut data = fs::read(&absolute_path)
         .with_context(|| format!("failed to read exported file {}", absolute_path.display()))?;
 
+    // Attachments of private threads arrive as ciphertext; the key came in the encrypted snapshot
+    let encryption_key = database.with_repositories(|repos| {
+        Ok(repos.files().get(&announcement.id)?.and_then(|record| record.encryption_key))
+    })?;
+    if let Some(key) = encryption_key {
+        data = open_attachment(&key, &data)
+            .with_context(|| format!("failed to decrypt private attachment {}", announcement.id))?;
+        fs::write(&absolute_path, &data)
+            .with_context(|| format!("failed to write decrypted file {}", absolute_path.display()))?;
+    }
+
     tracing::info!(
         file_id = %announcement.id,
         path = %absolute_path.display(),
@@ -1549,10 +2659,20 @@ async fn download_blob(
         }
         Ok(())
     })?;
+    publish_download_status(database, announcement, "available");
 
     Ok(())
 }
 
+fn publish_download_status(database: &Database, announcement: &FileAnnouncement, status: &str) {
+    LiveEventService::new(database.clone()).publish(LiveEventData::FileDownload {
+        file_id: announcement.id.clone(),
+        post_id: announcement.post_id.clone(),
+        thread_id: announcement.thread_id.clone(),
+        status: status.to_string(),
+    });
+}
+
 /// Public wrapper for applying a downloaded thread to the database.
 /// This is called when a user manually downloads a thread on-demand.
 pub async fn apply_thread_from_download(
@@ -1589,7 +2709,7 @@ mod tests {
     use super::*;
     use crate::config::GraphchanPaths;
     use crate::database::models::{PeerRecord, PostRecord, ThreadRecord};
-    use crate::database::repositories::{PeerRepository, PostRepository, ThreadRepository};
+    use crate::database::repositories::{PeerRepository, PostRepository, ThreadRepository, TopicRepository};
     use crate::database::Database;
     use crate::utils::now_utc_iso;
     use iroh::SecretKey;
@@ -1653,6 +2773,9 @@ mod tests {
         let ingest_paths = paths.clone();
         let ingest_publisher = publisher_tx.clone();
         let ingest_endpoint = endpoint.clone();
+        let ingest_ip_blocker = IpBlockChecker::new(database.clone());
+        let ingest_seen = SeenCache::load(database.clone()).expect("dedup cache");
+        let ingest_limiter = IngestLimiter::new("test-peer-id");
         let handle = tokio::spawn(async move {
             run_ingest_loop(
                 ingest_db,
@@ -1662,6 +2785,9 @@ mod tests {
                 blob_store,
                 ingest_endpoint,
                 "test-peer-id".to_string(),
+                ingest_ip_blocker,
+                ingest_seen,
+                ingest_limiter,
             )
             .await;
         });
@@ -1687,6 +2813,7 @@ mod tests {
             .send(InboundGossip {
                 peer_id: Some("peer-1".into()),
                 payload: EventPayload::FileAvailable(announcement),
+                size_bytes: 0,
             })
             .await
             .expect("send announcement");
@@ -1707,4 +2834,697 @@ mod tests {
         assert_eq!(record.ticket.as_deref(), Some(ticket_string.as_str()));
         assert_eq!(record.blob_id.as_deref(), Some(blob_hex.as_str()));
     }
+
+    fn signed_reaction(
+        cert: &sequoia_openpgp::Cert,
+        emoji: &str,
+        created_at: &str,
+        is_removal: bool,
+    ) -> ReactionUpdate {
+        let mut update = ReactionUpdate {
+            post_id: "post-1".into(),
+            thread_id: "thread-1".into(),
+            reactor_peer_id: cert.fingerprint().to_hex(),
+            emoji: emoji.into(),
+            signature: String::new(),
+            created_at: created_at.into(),
+            is_removal,
+            reactor_public_key: Some(crate::crypto::armor_public_cert(cert).unwrap()),
+        };
+        update.signature =
+            crate::crypto::sign_detached_with_cert(cert, &update.signing_payload()).unwrap();
+        update
+    }
+
+    #[test]
+    fn reaction_signatures_are_enforced() {
+        use sequoia_openpgp::cert::{CertBuilder, CipherSuite};
+
+        let database = Database::from_connection(Connection::open_in_memory().unwrap(), true);
+        database.ensure_migrations().expect("migrations");
+        let (cert, _) = CertBuilder::new()
+            .set_cipher_suite(CipherSuite::Cv25519)
+            .add_signing_subkey()
+            .generate()
+            .unwrap();
+        let reactor_id = cert.fingerprint().to_hex();
+        database
+            .with_repositories(|repos| {
+                repos.peers().upsert(&PeerRecord {
+                    id: reactor_id.clone(),
+                    alias: None,
+                    username: None,
+                    bio: None,
+                    friendcode: None,
+                    iroh_peer_id: None,
+                    gpg_fingerprint: Some(reactor_id.clone()),
+                    x25519_pubkey: None,
+                    last_seen: None,
+                    avatar_file_id: None,
+                    trust_state: "stranger".into(),
+                    agents: None,
+                    gpg_public_key: None,
+                })?;
+                repos.threads().create(&ThreadRecord {
+                    id: "thread-1".into(),
+                    title: "Thread".into(),
+                    creator_peer_id: None,
+                    created_at: now_utc_iso(),
+                    pinned: false,
+                    thread_hash: None,
+                    visibility: "social".into(),
+                    topic_secret: None,
+                    sync_status: "downloaded".into(),
+                    source_url: None,
+                    source_platform: None,
+                    last_refreshed_at: None,
+                })?;
+                repos.posts().create(&PostRecord {
+                    id: "post-1".into(),
+                    thread_id: "thread-1".into(),
+                    author_peer_id: None,
+                    author_friendcode: None,
+                    body: "body".into(),
+                    created_at: now_utc_iso(),
+                    updated_at: None,
+                    metadata: None,
+                    signature: None,
+                    signature_status: None,
+                })
+            })
+            .expect("seed");
+        let count = || {
+            database
+                .with_repositories(|repos| repos.reactions().list_for_post("post-1"))
+                .unwrap()
+                .len()
+        };
+
+        // Tampered emoji: signature no longer covers the payload
+        let mut forged = signed_reaction(&cert, "👍", "2024-01-01T00:00:00+00:00", false);
+        forged.emoji = "💩".into();
+        assert!(apply_reaction_update(&database, forged, None).is_err());
+        assert_eq!(count(), 0);
+
+        let added = signed_reaction(&cert, "👍", "2024-01-01T00:00:00+00:00", false);
+        assert!(apply_reaction_update(&database, added, None).expect("valid reaction"));
+        assert_eq!(count(), 1);
+        let learned = database
+            .with_repositories(|repos| repos.peers().get(&reactor_id))
+            .unwrap()
+            .and_then(|peer| peer.gpg_public_key);
+        assert!(learned.is_some(), "verified key is remembered for the reactor");
+
+        // Removal with a stripped signature is rejected
+        let mut stripped = signed_reaction(&cert, "👍", "2024-01-02T00:00:00+00:00", true);
+        stripped.signature = String::new();
+        assert!(apply_reaction_update(&database, stripped, None).is_err());
+        assert_eq!(count(), 1);
+
+        let removed = signed_reaction(&cert, "👍", "2024-01-02T00:00:00+00:00", true);
+        assert!(apply_reaction_update(&database, removed, None).expect("valid removal"));
+        assert_eq!(count(), 0);
+
+        // The removal outlives the dedup window: replaying the original add doesn't restore it
+        let replayed = signed_reaction(&cert, "👍", "2024-01-01T00:00:00+00:00", false);
+        assert!(!apply_reaction_update(&database, replayed, None).expect("stale add is ignored"));
+        assert_eq!(count(), 0);
+
+        let re_added = signed_reaction(&cert, "👍", "2024-01-03T00:00:00+00:00", false);
+        assert!(apply_reaction_update(&database, re_added, None).expect("newer add"));
+        assert_eq!(count(), 1);
+    }
+
+    #[test]
+    fn only_verified_reactions_spend_the_reactor_budget() {
+        use crate::network::ratelimit::{BucketRate, RateLimits};
+        use sequoia_openpgp::cert::{CertBuilder, CipherSuite};
+
+        let database = Database::from_connection(Connection::open_in_memory().unwrap(), true);
+        database.ensure_migrations().expect("migrations");
+        let (cert, _) = CertBuilder::new()
+            .set_cipher_suite(CipherSuite::Cv25519)
+            .add_signing_subkey()
+            .generate()
+            .unwrap();
+        let reactor_id = cert.fingerprint().to_hex();
+        database
+            .with_repositories(|repos| {
+                repos.peers().upsert(&test_peer(&reactor_id))?;
+                repos.threads().create(&test_thread("thread-1"))?;
+                repos.posts().create(&PostRecord {
+                    id: "post-1".into(),
+                    thread_id: "thread-1".into(),
+                    author_peer_id: None,
+                    author_friendcode: None,
+                    body: "body".into(),
+                    created_at: now_utc_iso(),
+                    updated_at: None,
+                    metadata: None,
+                    signature: None,
+                    signature_status: None,
+                })
+            })
+            .expect("seed");
+        let limits = RateLimits {
+            author: BucketRate {
+                per_second: 0.0,
+                burst: 1.0,
+            },
+            ..RateLimits::default()
+        };
+        let limiter = IngestLimiter::with_limits("local", limits);
+        let budget = limiter.author_budget(Some("neighbor"), "ReactionUpdate", 100);
+
+        // Forgeries naming the reactor don't use up their allowance
+        for _ in 0..3 {
+            let mut forged = signed_reaction(&cert, "👍", "2024-01-01T00:00:00+00:00", false);
+            forged.emoji = "💩".into();
+            assert!(apply_reaction_update(&database, forged, Some(&budget)).is_err());
+        }
+        assert_eq!(limiter.stats().author_limited, 0);
+
+        let first = signed_reaction(&cert, "👍", "2024-01-01T00:00:00+00:00", false);
+        assert!(apply_reaction_update(&database, first, Some(&budget)).unwrap());
+        let second = signed_reaction(&cert, "🎉", "2024-01-01T00:00:00+00:00", false);
+        assert!(!apply_reaction_update(&database, second, Some(&budget)).unwrap());
+        let stored = database
+            .with_repositories(|repos| repos.reactions().list_for_post("post-1"))
+            .unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(limiter.stats().author_limited, 1);
+    }
+
+    fn test_peer(id: &str) -> PeerRecord {
+        PeerRecord {
+            id: id.into(),
+            alias: None,
+            username: None,
+            bio: None,
+            friendcode: None,
+            iroh_peer_id: None,
+            gpg_fingerprint: Some(id.into()),
+            x25519_pubkey: None,
+            last_seen: None,
+            avatar_file_id: None,
+            trust_state: "stranger".into(),
+            agents: None,
+            gpg_public_key: None,
+        }
+    }
+
+    fn test_thread(id: &str) -> ThreadRecord {
+        ThreadRecord {
+            id: id.into(),
+            title: "Thread".into(),
+            creator_peer_id: None,
+            created_at: now_utc_iso(),
+            pinned: false,
+            thread_hash: None,
+            visibility: "social".into(),
+            topic_secret: None,
+            sync_status: "downloaded".into(),
+            source_url: None,
+            source_platform: None,
+            last_refreshed_at: None,
+        }
+    }
+
+    fn test_post(id: &str, author: Option<&str>) -> PostView {
+        PostView {
+            id: id.into(),
+            thread_id: "thread-1".into(),
+            author_peer_id: author.map(str::to_string),
+            author_friendcode: None,
+            body: format!("body of {id}"),
+            created_at: "2024-01-01T00:00:00Z".into(),
+            updated_at: None,
+            deleted: false,
+            quarantined: false,
+            pow: None,
+            parent_post_ids: Vec::new(),
+            files: Vec::new(),
+            thread_hash: None,
+            canonical_thread_hash: None,
+            metadata: None,
+            signature: None,
+            signature_status: None,
+            author_public_key: None,
+        }
+    }
+
+    #[test]
+    fn unsigned_posts_are_refused_for_authors_with_known_keys() {
+        use sequoia_openpgp::cert::{CertBuilder, CipherSuite};
+
+        let database = Database::from_connection(Connection::open_in_memory().unwrap(), true);
+        database.ensure_migrations().expect("migrations");
+        let (cert, _) = CertBuilder::new()
+            .set_cipher_suite(CipherSuite::Cv25519)
+            .add_signing_subkey()
+            .generate()
+            .unwrap();
+        let author = cert.fingerprint().to_hex();
+        database
+            .with_repositories(|repos| {
+                repos.peers().upsert(&PeerRecord {
+                    gpg_public_key: Some(crate::crypto::armor_public_cert(&cert)?),
+                    ..test_peer(&author)
+                })?;
+                repos.peers().upsert(&test_peer("LEGACY"))
+            })
+            .expect("seed peers");
+
+        database
+            .with_repositories(|repos| {
+                let stripped = test_post("post-1", Some(&author));
+                assert!(verify_post_signature(&repos, &stripped, None).is_err());
+
+                let legacy = verify_post_signature(&repos, &test_post("post-2", Some("LEGACY")), None)?;
+                assert_eq!(legacy.status, SIGNATURE_UNSIGNED);
+                Ok(())
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn unverified_edits_and_deletions_are_ignored() {
+        let database = Database::from_connection(Connection::open_in_memory().unwrap(), true);
+        database.ensure_migrations().expect("migrations");
+        database
+            .with_repositories(|repos| {
+                repos.threads().create(&ThreadRecord {
+                    id: "thread-1".into(),
+                    title: "Thread".into(),
+                    creator_peer_id: None,
+                    created_at: now_utc_iso(),
+                    pinned: false,
+                    thread_hash: None,
+                    visibility: "social".into(),
+                    topic_secret: None,
+                    sync_status: "downloaded".into(),
+                    source_url: None,
+                    source_platform: None,
+                    last_refreshed_at: None,
+                })?;
+                repos.peers().upsert(&test_peer("AUTHOR"))?;
+                upsert_post(&repos.posts(), &test_post("post-1", Some("AUTHOR")), SIGNATURE_UNSIGNED)
+            })
+            .expect("seed");
+
+        let edited = PostView {
+            body: "rewritten".into(),
+            updated_at: Some("2024-02-01T00:00:00Z".into()),
+            ..test_post("post-1", Some("AUTHOR"))
+        };
+        let deleted = PostView {
+            body: String::new(),
+            deleted: true,
+            ..edited.clone()
+        };
+        database
+            .with_repositories(|repos| {
+                let posts = repos.posts();
+                for status in [SIGNATURE_UNSIGNED, SIGNATURE_UNVERIFIED] {
+                    upsert_post(&posts, &edited, status)?;
+                    upsert_post(&posts, &deleted, status)?;
+                }
+                let stored = posts.get("post-1")?.expect("post kept");
+                assert_eq!(stored.body, "body of post-1");
+                assert!(!posts.is_tombstoned("post-1")?);
+                assert!(posts.list_revisions("post-1")?.is_empty());
+
+                // A tombstone for a post we never saw must not claim its ID
+                let unknown = PostView { id: "post-2".into(), ..deleted.clone() };
+                upsert_post(&posts, &unknown, SIGNATURE_UNSIGNED)?;
+                assert!(posts.get("post-2")?.is_none());
+
+                upsert_post(&posts, &edited, SIGNATURE_VERIFIED)?;
+                assert_eq!(posts.get("post-1")?.expect("post kept").body, "rewritten");
+                Ok(())
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn only_edits_that_change_a_post_are_applied() {
+        use sequoia_openpgp::cert::{CertBuilder, CipherSuite};
+
+        let database = Database::from_connection(Connection::open_in_memory().unwrap(), true);
+        database.ensure_migrations().expect("migrations");
+        let (cert, _) = CertBuilder::new()
+            .set_cipher_suite(CipherSuite::Cv25519)
+            .add_signing_subkey()
+            .generate()
+            .unwrap();
+        let author = cert.fingerprint().to_hex();
+        let sign = |post: &mut PostView| {
+            post.signature = Some(crate::crypto::sign_detached_with_cert(&cert, &post.signing_payload()).unwrap());
+        };
+        let mut original = test_post("post-1", Some(&author));
+        sign(&mut original);
+        database
+            .with_repositories(|repos| {
+                repos.peers().upsert(&PeerRecord {
+                    gpg_public_key: Some(crate::crypto::armor_public_cert(&cert)?),
+                    ..test_peer(&author)
+                })?;
+                repos.threads().create(&test_thread("thread-1"))?;
+                upsert_post(&repos.posts(), &original, SIGNATURE_VERIFIED)
+            })
+            .expect("seed");
+
+        let mut edited = PostView {
+            body: "rewritten".into(),
+            updated_at: Some("2024-01-02T00:00:00Z".into()),
+            ..original.clone()
+        };
+        sign(&mut edited);
+        let edit = PostEditEvent::from_post(&edited).unwrap();
+        assert_eq!(apply_post_edit(&database, &edit, None).unwrap(), Some(Vec::new()));
+        // A replay, or an edit of a post we never saw, changes nothing
+        assert_eq!(apply_post_edit(&database, &edit, None).unwrap(), None);
+        let unknown = PostEditEvent {
+            post_id: "post-2".into(),
+            ..edit.clone()
+        };
+        assert_eq!(apply_post_edit(&database, &unknown, None).unwrap(), None);
+        let stored = database.with_repositories(|repos| repos.posts().get("post-1")).unwrap();
+        assert_eq!(stored.expect("post kept").body, "rewritten");
+    }
+
+    #[tokio::test]
+    async fn ip_block_stubs_never_replace_stored_posts_or_take_forgeries() {
+        use sequoia_openpgp::cert::{CertBuilder, CipherSuite};
+
+        let database = Database::from_connection(Connection::open_in_memory().unwrap(), true);
+        database.ensure_migrations().expect("migrations");
+        let (cert, _) = CertBuilder::new()
+            .set_cipher_suite(CipherSuite::Cv25519)
+            .add_signing_subkey()
+            .generate()
+            .unwrap();
+        let author = cert.fingerprint().to_hex();
+        let sign = |post: &mut PostView| {
+            post.signature = Some(crate::crypto::sign_detached_with_cert(&cert, &post.signing_payload()).unwrap());
+        };
+        let mut original = test_post("post-1", Some(&author));
+        sign(&mut original);
+        database
+            .with_repositories(|repos| {
+                repos.peers().upsert(&PeerRecord {
+                    gpg_public_key: Some(crate::crypto::armor_public_cert(&cert)?),
+                    trust_state: "friend".into(),
+                    ..test_peer(&author)
+                })?;
+                repos.peer_ips().update(&author, "203.0.113.5", chrono::Utc::now().timestamp())?;
+                repos.threads().create(&test_thread("thread-1"))?;
+                upsert_post(&repos.posts(), &original, SIGNATURE_VERIFIED)
+            })
+            .expect("seed");
+        let ip_blocker = IpBlockChecker::new(database.clone());
+        ip_blocker.add_block("203.0.113.5", None).await.expect("block");
+        let gate = PostGate::for_thread(&database, "thread-1").expect("gate");
+        let stored_body = |id: &str| {
+            database
+                .with_repositories(|repos| repos.posts().get(id))
+                .unwrap()
+                .map(|post| post.body)
+        };
+
+        // Forged copies are refused before the block is looked at
+        let forged = PostView { body: "forged".into(), ..test_post("post-1", Some(&author)) };
+        assert!(apply_post_update(&database, &ip_blocker, forged, &gate).await.is_err());
+        let mut tampered = original.clone();
+        tampered.body = "tampered".into();
+        assert!(apply_post_update(&database, &ip_blocker, tampered, &gate).await.is_err());
+        assert!(apply_post_update(&database, &ip_blocker, test_post("post-3", Some(&author)), &gate)
+            .await
+            .is_err());
+        assert_eq!(stored_body("post-3"), None);
+
+        // A genuine copy of a post we hold leaves it as it was
+        let (outcome, _) = apply_post_update(&database, &ip_blocker, original, &gate).await.unwrap();
+        assert_eq!(outcome, PostOutcome::Dropped);
+        assert_eq!(stored_body("post-1").as_deref(), Some("body of post-1"));
+
+        // New posts of the blocked author become stubs
+        let mut fresh = test_post("post-2", Some(&author));
+        sign(&mut fresh);
+        let (outcome, _) = apply_post_update(&database, &ip_blocker, fresh, &gate).await.unwrap();
+        assert_eq!(outcome, PostOutcome::Stub);
+        assert!(stored_body("post-2").is_some_and(|body| body.starts_with("[Post from IP-blocked peer")));
+    }
+
+    #[test]
+    fn snapshot_posts_pass_the_trust_gate() {
+        let database = Database::from_connection(Connection::open_in_memory().unwrap(), true);
+        database.ensure_migrations().expect("migrations");
+        database
+            .with_repositories(|repos| {
+                repos.threads().create(&test_thread("thread-1"))?;
+                repos.peers().upsert(&PeerRecord { trust_state: "friend".into(), ..peer("FRIEND") })?;
+                repos.peers().upsert(&PeerRecord { trust_state: "muted".into(), ..peer("MUTED") })
+            })
+            .expect("seed");
+
+        let posts = [
+            test_post("from-friend", Some("FRIEND")),
+            test_post("from-muted", Some("MUTED")),
+            test_post("anonymous", None),
+        ];
+        let gate = PostGate { mutes_strangers: false, pow_required: 0, author_budget: None };
+        database
+            .with_repositories(|repos| store_snapshot_posts(&repos, "thread-1", &posts, &[], &gate))
+            .expect("store");
+
+        let trust = TrustService::new(database.clone());
+        let stored = |id: &str| database.with_repositories(|repos| repos.posts().get(id)).unwrap().is_some();
+        assert!(!stored("from-muted"));
+        // An unsigned copy doesn't prove it came from our friend
+        assert!(stored("from-friend"));
+        assert!(trust.is_quarantined("from-friend").unwrap());
+        assert!(!auto_download_allowed(&database, "from-friend", None).unwrap());
+        assert!(trust.is_quarantined("anonymous").unwrap());
+
+        let gate = PostGate { mutes_strangers: true, pow_required: 0, author_budget: None };
+        let late = [test_post("late", None)];
+        database
+            .with_repositories(|repos| store_snapshot_posts(&repos, "thread-1", &late, &[], &gate))
+            .expect("store");
+        assert!(!stored("late"));
+    }
+
+    #[test]
+    fn snapshot_posts_need_the_thread_proof_of_work() {
+        let database = Database::from_connection(Connection::open_in_memory().unwrap(), true);
+        database.ensure_migrations().expect("migrations");
+        database
+            .with_repositories(|repos| {
+                repos.threads().create(&test_thread("thread-1"))?;
+                repos.topics().subscribe("cats")?;
+                repos.topics().add_thread_topic("thread-1", "cats")
+            })
+            .expect("seed");
+        PowService::new(database.clone()).set_topic_difficulty("cats", 4).unwrap();
+        let gate = PostGate::for_thread(&database, "thread-1").unwrap();
+        assert_eq!(gate.pow_required, 4);
+
+        let mut stamped = test_post("stamped", None);
+        stamped.pow = Some(crate::pow::PowStamp::compute(&post_resource(&stamped), 4));
+        let posts = [stamped, test_post("unstamped", None)];
+        database
+            .with_repositories(|repos| store_snapshot_posts(&repos, "thread-1", &posts, &[], &gate))
+            .expect("store");
+
+        let stored = |id: &str| database.with_repositories(|repos| repos.posts().get(id)).unwrap().is_some();
+        assert!(stored("stamped"));
+        assert!(!stored("unstamped"));
+    }
+
+    fn signed_prekey_bundle(cert: &sequoia_openpgp::Cert, identity_key: &str, created_at: i64) -> DmPrekeyBundle {
+        let mut bundle = DmPrekeyBundle {
+            peer_id: cert.fingerprint().to_hex(),
+            identity_key: identity_key.into(),
+            prekey_id: 1,
+            prekey: BASE64.encode([4u8; 32]),
+            created_at,
+            signature: String::new(),
+            signer_public_key: Some(crate::crypto::armor_public_cert(cert).unwrap()),
+        };
+        bundle.signature = crate::crypto::sign_detached_with_cert(cert, &bundle.signing_payload()).unwrap();
+        bundle
+    }
+
+    #[test]
+    fn dm_prekeys_must_be_signed_for_the_current_identity_key() {
+        use sequoia_openpgp::cert::{CertBuilder, CipherSuite};
+
+        let database = Database::from_connection(Connection::open_in_memory().unwrap(), true);
+        database.ensure_migrations().expect("migrations");
+        let (cert, _) = CertBuilder::new()
+            .set_cipher_suite(CipherSuite::Cv25519)
+            .add_signing_subkey()
+            .generate()
+            .unwrap();
+        let peer_id = cert.fingerprint().to_hex();
+        let identity_key = BASE64.encode([3u8; 32]);
+        database
+            .with_repositories(|repos| {
+                repos.peers().upsert(&PeerRecord {
+                    x25519_pubkey: Some(identity_key.clone()),
+                    ..test_peer(&peer_id)
+                })
+            })
+            .expect("seed");
+        let stored = || {
+            database
+                .with_repositories(|repos| repos.dm_sessions().get_prekey_bundle(&peer_id))
+                .unwrap()
+                .map(|json| serde_json::from_str::<DmPrekeyBundle>(&json).unwrap().created_at)
+        };
+
+        let mut forged = signed_prekey_bundle(&cert, &identity_key, 100);
+        forged.prekey = BASE64.encode([5u8; 32]);
+        assert!(apply_dm_prekey_bundle(&database, &peer_id, &forged).is_err());
+        let stale_identity = signed_prekey_bundle(&cert, &BASE64.encode([9u8; 32]), 100);
+        assert!(apply_dm_prekey_bundle(&database, &peer_id, &stale_identity).is_err());
+        assert!(apply_dm_prekey_bundle(&database, "SOMEONE-ELSE", &signed_prekey_bundle(&cert, &identity_key, 100)).is_err());
+        assert_eq!(stored(), None);
+
+        assert!(apply_dm_prekey_bundle(&database, &peer_id, &signed_prekey_bundle(&cert, &identity_key, 200)).unwrap());
+        // A replayed older bundle doesn't roll the prekey back
+        assert!(!apply_dm_prekey_bundle(&database, &peer_id, &signed_prekey_bundle(&cert, &identity_key, 100)).unwrap());
+        assert_eq!(stored(), Some(200));
+    }
+
+    fn signed_block_action(
+        cert: &sequoia_openpgp::Cert,
+        blocked: &str,
+        is_unblock: bool,
+        sequence: u64,
+    ) -> BlockActionEvent {
+        let mut action = BlockActionEvent {
+            blocker_peer_id: cert.fingerprint().to_hex(),
+            blocked_peer_id: blocked.into(),
+            reason: Some("spam".into()),
+            is_unblock,
+            sequence,
+            created_at: now_utc_iso(),
+            signature: None,
+            blocker_public_key: Some(crate::crypto::armor_public_cert(cert).unwrap()),
+        };
+        action.signature =
+            Some(crate::crypto::sign_detached_with_cert(cert, &action.signing_payload()).unwrap());
+        action
+    }
+
+    #[test]
+    fn block_actions_require_signature_and_fresh_sequence() {
+        use sequoia_openpgp::cert::{CertBuilder, CipherSuite};
+
+        let database = Database::from_connection(Connection::open_in_memory().unwrap(), true);
+        database.ensure_migrations().expect("migrations");
+        let (maintainer, _) = CertBuilder::new()
+            .set_cipher_suite(CipherSuite::Cv25519)
+            .add_signing_subkey()
+            .generate()
+            .unwrap();
+        let maintainer_id = maintainer.fingerprint().to_hex();
+        database
+            .with_repositories(|repos| {
+                repos.peers().upsert(&test_peer(&maintainer_id))?;
+                repos.peers().upsert(&test_peer("TARGET"))
+            })
+            .expect("seed peers");
+        let checker = crate::blocking::BlockChecker::new(database.clone());
+        // Not subscribed yet: nothing changes
+        assert!(!apply_block_action(&database, &signed_block_action(&maintainer, "TARGET", false, 1), None).unwrap());
+        checker
+            .subscribe_blocklist("list-1", &maintainer_id, "List".into(), None, true)
+            .expect("subscribe");
+
+        // Forged: signature doesn't cover the altered target
+        let mut forged = signed_block_action(&maintainer, "OTHER", false, 1);
+        forged.blocked_peer_id = "TARGET".into();
+        assert!(apply_block_action(&database, &forged, None).is_err());
+        assert!(!checker.is_blocked("TARGET").unwrap());
+
+        assert!(apply_block_action(&database, &signed_block_action(&maintainer, "TARGET", false, 2), None)
+            .expect("signed block"));
+        assert!(checker.is_blocked("TARGET").unwrap());
+
+        // Replayed older unblock is ignored
+        assert!(!apply_block_action(&database, &signed_block_action(&maintainer, "TARGET", true, 1), None)
+            .expect("stale unblock"));
+        assert!(checker.is_blocked("TARGET").unwrap());
+
+        assert!(apply_block_action(&database, &signed_block_action(&maintainer, "TARGET", true, 3), None)
+            .expect("fresh unblock"));
+        assert!(!checker.is_blocked("TARGET").unwrap());
+    }
+
+    #[test]
+    fn roster_changes_travel_past_the_first_hop() {
+        let dir = tempdir().expect("tempdir");
+        let paths = GraphchanPaths::from_base_dir(dir.path()).expect("paths");
+        std::fs::create_dir_all(&paths.gpg_dir).expect("gpg dir");
+        std::fs::create_dir_all(&paths.keys_dir).expect("keys dir");
+        let identity = crate::identity::ensure_local_identity(&paths).expect("identity");
+        let origin_id = identity.gpg_fingerprint.clone();
+        let origin = Database::from_connection(Connection::open_in_memory().unwrap(), true);
+        origin.ensure_migrations().expect("migrations");
+        origin
+            .save_identity(&origin_id, &identity.iroh_peer_id, &identity.friendcode)
+            .expect("save identity");
+        let agents = crate::agents::AgentService::new(origin, paths.clone());
+        let agent = agents.create("scout").expect("create agent");
+        let public_key = crate::crypto::load_public_key_armored(&paths).expect("public key");
+
+        // The first node hears the origin directly, the second only through the first
+        let hops: Vec<(Database, SeenCache)> = (0..2)
+            .map(|_| {
+                let database = Database::from_connection(Connection::open_in_memory().unwrap(), true);
+                database.ensure_migrations().expect("migrations");
+                let origin_peer = PeerRecord { gpg_public_key: Some(public_key.clone()), ..test_peer(&origin_id) };
+                database.with_repositories(|repos| repos.peers().upsert(&origin_peer)).expect("origin known");
+                let seen = SeenCache::load(database.clone()).expect("dedup cache");
+                (database, seen)
+            })
+            .collect();
+        let profile = || ProfileUpdate {
+            peer_id: origin_id.clone(),
+            avatar_file_id: None,
+            ticket: None,
+            username: None,
+            bio: None,
+            agents: None,
+            x25519_pubkey: None,
+            dm_prekey: None,
+            agent_keys: Some(agents.roster().expect("roster")),
+        };
+        // How many hops an update reaches, each passing on only what it relays
+        let gossip = |update: &ProfileUpdate| {
+            let mut reached = 0;
+            for (database, seen) in &hops {
+                reached += 1;
+                if !receive_profile_update(database, seen, update).expect("apply profile") {
+                    break;
+                }
+            }
+            reached
+        };
+
+        assert_eq!(gossip(&profile()), 2);
+        assert_eq!(gossip(&profile()), 1, "an unchanged profile stops at the first hop");
+
+        agents.revoke("scout").expect("revoke").expect("agent exists");
+        assert_eq!(gossip(&profile()), 2, "the revocation is a new profile");
+        let (far, _) = &hops[1];
+        let stored = far
+            .with_repositories(|repos| repos.agent_keys().get(&origin_id, &agent.agent_id))
+            .expect("lookup")
+            .expect("agent stored two hops away");
+        assert!(stored.revoked_at.is_some());
+    }
 }
@@ -0,0 +1,334 @@
+use crate::database::models::DmOutboxRecord;
+use crate::database::repositories::{DmOutboxRepository, PeerRepository};
+use crate::database::Database;
+use crate::network::events::{DirectMessageEvent, DmReceiptEvent, EventPayload, NetworkEvent};
+use crate::trust::TrustLevel;
+use anyhow::{Context, Result};
+use tokio::sync::mpsc::Sender;
+
+/// Stored, not yet handed to gossip.
+pub const OUTBOX_QUEUED: &str = "queued";
+/// Broadcast at least once, no receipt yet.
+pub const OUTBOX_SENT: &str = "sent";
+/// The recipient's signed receipt arrived.
+pub const OUTBOX_ACKED: &str = "acked";
+/// A friend's DM we forward until the recipient acknowledges it.
+pub const OUTBOX_HELD: &str = "held";
+/// One of our DMs that got no receipt within `SEND_TTL_SECS`; no longer retried.
+pub const OUTBOX_FAILED: &str = "failed";
+
+/// Delay before the first periodic retry; doubles per attempt.
+const BASE_RETRY_SECS: i64 = 60;
+/// Longest delay between periodic retries.
+const MAX_RETRY_SECS: i64 = 60 * 60;
+/// How long we hold DMs for offline friends.
+pub const HOLD_TTL_SECS: i64 = 7 * 24 * 60 * 60;
+/// How long we retry our own DMs before marking them failed (the same window friends hold them).
+pub const SEND_TTL_SECS: i64 = HOLD_TTL_SECS;
+/// How often the retry loop wakes up.
+pub const RETRY_INTERVAL_SECS: u64 = 60;
+
+/// Seconds to wait after the last attempt before a periodic retry.
+pub fn retry_delay(attempts: i64) -> i64 {
+    let doublings = attempts.saturating_sub(1).clamp(0, 16) as u32;
+    BASE_RETRY_SECS.saturating_mul(1 << doublings).min(MAX_RETRY_SECS)
+}
+
+fn is_due(record: &DmOutboxRecord, now: i64) -> bool {
+    match record.last_attempt_at {
+        Some(last) => now - last >= retry_delay(record.attempts),
+        None => true,
+    }
+}
+
+/// Persisted DM outbox with retries until the recipient acknowledges.
+///
+/// A DM sent while the recipient is offline reaches nobody. Every outgoing DM is
+/// therefore kept here and resent when the recipient's peer topic gains a
+/// neighbor and on a backoff timer, until a signed `DmReceiptEvent` arrives.
+/// Friends of both parties also keep ("hold") DMs that fell back to gossip, so
+/// the message can arrive while the sender is offline.
+#[derive(Clone)]
+pub struct DmOutbox {
+    database: Database,
+    publisher: Sender<NetworkEvent>,
+}
+
+impl DmOutbox {
+    pub fn new(database: Database, publisher: Sender<NetworkEvent>) -> Self {
+        Self { database, publisher }
+    }
+
+    /// Queues one of our DMs and transmits it once.
+    pub async fn send(&self, dm: DirectMessageEvent) -> Result<()> {
+        let record = outbox_record(&dm, OUTBOX_QUEUED, chrono::Utc::now().timestamp())?;
+        self.database
+            .with_repositories(|repos| repos.dm_outbox().enqueue(&record))?;
+        self.transmit(&record).await
+    }
+
+    /// Holds a DM between two of our friends for store-and-forward.
+    ///
+    /// Returns false when either side is not a friend of ours.
+    pub fn hold(&self, dm: &DirectMessageEvent) -> Result<bool> {
+        let now = chrono::Utc::now().timestamp();
+        self.database.with_repositories(|repos| {
+            let peers = repos.peers();
+            let is_friend = |peer_id: &str| -> Result<bool> {
+                Ok(peers
+                    .get(peer_id)?
+                    .is_some_and(|peer| TrustLevel::from_state(&peer.trust_state) == TrustLevel::Friend))
+            };
+            if !is_friend(&dm.from_peer_id)? || !is_friend(&dm.to_peer_id)? {
+                return Ok(false);
+            }
+            repos.dm_outbox().enqueue(&outbox_record(dm, OUTBOX_HELD, now)?)
+        })
+    }
+
+    /// Resends everything pending for `peer_id`, ignoring backoff.
+    ///
+    /// Called when the peer's topic reports a new neighbor.
+    pub async fn flush_peer(&self, peer_id: &str) -> Result<usize> {
+        let pending = self
+            .database
+            .with_repositories(|repos| repos.dm_outbox().list_pending(Some(peer_id)))?;
+        for record in &pending {
+            self.transmit(record).await?;
+        }
+        Ok(pending.len())
+    }
+
+    /// Resends entries whose backoff elapsed, drops expired held copies and
+    /// marks our own DMs that outlived `SEND_TTL_SECS` as failed.
+    pub async fn retry_due(&self) -> Result<usize> {
+        let now = chrono::Utc::now().timestamp();
+        let pending = self.database.with_repositories(|repos| {
+            let outbox = repos.dm_outbox();
+            outbox.prune_held(now - HOLD_TTL_SECS)?;
+            let failed = outbox.fail_expired(now - SEND_TTL_SECS)?;
+            if failed > 0 {
+                tracing::warn!(count = failed, "giving up on unacknowledged DMs");
+            }
+            outbox.list_pending(None)
+        })?;
+        let due: Vec<_> = pending.into_iter().filter(|record| is_due(record, now)).collect();
+        for record in &due {
+            self.transmit(record).await?;
+        }
+        Ok(due.len())
+    }
+
+    /// Applies a receipt whose signature the caller verified.
+    ///
+    /// Marks our own message acknowledged or drops a held copy. Returns false
+    /// for receipts of messages we don't track.
+    pub fn acknowledge(&self, receipt: &DmReceiptEvent) -> Result<bool> {
+        let now = chrono::Utc::now().timestamp();
+        self.database.with_repositories(|repos| {
+            let outbox = repos.dm_outbox();
+            let Some(record) = outbox.get(&receipt.message_id)? else {
+                return Ok(false);
+            };
+            if record.to_peer_id != receipt.from_peer_id || record.from_peer_id != receipt.to_peer_id {
+                anyhow::bail!("receipt for {} does not match its recipient", receipt.message_id);
+            }
+            if record.state == OUTBOX_HELD {
+                outbox.remove(&record.message_id)?;
+                return Ok(true);
+            }
+            outbox.mark_acked(&record.message_id, now)
+        })
+    }
+
+    /// Retries due messages every `RETRY_INTERVAL_SECS` until the event loop stops.
+    pub async fn run_retry_loop(self) {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(RETRY_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            if self.publisher.is_closed() {
+                break;
+            }
+            match self.retry_due().await {
+                Ok(0) => {}
+                Ok(count) => tracing::debug!(count, "retried pending DMs"),
+                Err(err) => tracing::warn!(error = ?err, "failed to retry pending DMs"),
+            }
+        }
+    }
+
+    async fn transmit(&self, record: &DmOutboxRecord) -> Result<()> {
+        let dm: DirectMessageEvent =
+            serde_json::from_str(&record.event_json).context("corrupt DM outbox entry")?;
+        // Our own DMs fall back to the recipient's topic so friends can hold them;
+        // held copies only ever go straight to the recipient
+        self.publisher
+            .send(NetworkEvent::Direct {
+                peer_id: record.to_peer_id.clone(),
+                payload: EventPayload::DirectMessage(dm),
+                gossip_fallback: record.state != OUTBOX_HELD,
+            })
+            .await
+            .context("network event loop stopped")?;
+        let now = chrono::Utc::now().timestamp();
+        self.database
+            .with_repositories(|repos| repos.dm_outbox().mark_attempt(&record.message_id, now))
+    }
+}
+
+fn outbox_record(dm: &DirectMessageEvent, state: &str, now: i64) -> Result<DmOutboxRecord> {
+    Ok(DmOutboxRecord {
+        message_id: dm.message_id.clone(),
+        from_peer_id: dm.from_peer_id.clone(),
+        to_peer_id: dm.to_peer_id.clone(),
+        event_json: serde_json::to_string(dm)?,
+        state: state.to_string(),
+        attempts: 0,
+        created_at: now,
+        last_attempt_at: None,
+        acked_at: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::PeerRecord;
+    use rusqlite::Connection;
+    use tokio::sync::mpsc;
+
+    fn database() -> Database {
+        let database = Database::from_connection(Connection::open_in_memory().expect("db"), true);
+        database.ensure_migrations().expect("migrations");
+        database
+    }
+
+    fn dm(id: &str, from: &str, to: &str) -> DirectMessageEvent {
+        DirectMessageEvent {
+            from_peer_id: from.into(),
+            to_peer_id: to.into(),
+            encrypted_body: vec![1, 2, 3],
+            nonce: vec![0; 24],
+            message_id: id.into(),
+            conversation_id: "conv".into(),
+            created_at: "2026-01-01T00:00:00Z".into(),
+            ratchet: None,
+            group_id: None,
+            structured: false,
+        }
+    }
+
+    fn receipt(id: &str, from: &str, to: &str) -> DmReceiptEvent {
+        DmReceiptEvent {
+            message_id: id.into(),
+            from_peer_id: from.into(),
+            to_peer_id: to.into(),
+            received_at: "2026-01-01T00:00:05Z".into(),
+            signature: String::new(),
+            signer_public_key: None,
+        }
+    }
+
+    fn friend(id: &str, trust_state: &str) -> PeerRecord {
+        PeerRecord {
+            id: id.into(),
+            alias: None,
+            username: None,
+            bio: None,
+            friendcode: None,
+            iroh_peer_id: None,
+            gpg_fingerprint: Some(id.into()),
+            x25519_pubkey: None,
+            last_seen: None,
+            avatar_file_id: None,
+            trust_state: trust_state.into(),
+            agents: None,
+            gpg_public_key: None,
+        }
+    }
+
+    #[test]
+    fn retry_delay_backs_off_and_caps() {
+        assert_eq!(retry_delay(1), 60);
+        assert_eq!(retry_delay(2), 120);
+        assert_eq!(retry_delay(40), MAX_RETRY_SECS);
+    }
+
+    #[tokio::test]
+    async fn sent_messages_are_retried_until_acknowledged() {
+        let database = database();
+        let (tx, mut rx) = mpsc::channel(16);
+        let outbox = DmOutbox::new(database.clone(), tx);
+
+        outbox.send(dm("dm-1", "ME", "BOB")).await.unwrap();
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(NetworkEvent::Direct { payload: EventPayload::DirectMessage(_), gossip_fallback: true, .. })
+        ));
+        let stored = database.with_repositories(|repos| repos.dm_outbox().get("dm-1")).unwrap().unwrap();
+        assert_eq!((stored.state.as_str(), stored.attempts), (OUTBOX_SENT, 1));
+
+        // Bob's topic gains a neighbor: resend regardless of backoff
+        assert_eq!(outbox.flush_peer("BOB").await.unwrap(), 1);
+        assert!(rx.try_recv().is_ok());
+
+        // A receipt signed by someone else's identity doesn't count
+        assert!(outbox.acknowledge(&receipt("dm-1", "MALLORY", "ME")).is_err());
+        assert!(outbox.acknowledge(&receipt("dm-1", "BOB", "ME")).unwrap());
+        assert_eq!(outbox.flush_peer("BOB").await.unwrap(), 0);
+        let stored = database.with_repositories(|repos| repos.dm_outbox().get("dm-1")).unwrap().unwrap();
+        assert_eq!(stored.state, OUTBOX_ACKED);
+    }
+
+    #[tokio::test]
+    async fn unacknowledged_messages_fail_after_the_send_window() {
+        let database = database();
+        let (tx, mut rx) = mpsc::channel(16);
+        let outbox = DmOutbox::new(database.clone(), tx);
+        let now = chrono::Utc::now().timestamp();
+        let mut expired = outbox_record(&dm("dm-old", "ME", "BOB"), OUTBOX_SENT, now - SEND_TTL_SECS - 1).unwrap();
+        expired.attempts = 12;
+        let fresh = outbox_record(&dm("dm-new", "ME", "BOB"), OUTBOX_QUEUED, now).unwrap();
+        database
+            .with_repositories(|repos| {
+                repos.dm_outbox().enqueue(&expired)?;
+                repos.dm_outbox().enqueue(&fresh)
+            })
+            .unwrap();
+
+        // Only the fresh message is retried; the old one is given up on
+        assert_eq!(outbox.retry_due().await.unwrap(), 1);
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(NetworkEvent::Direct { payload: EventPayload::DirectMessage(dm), .. }) if dm.message_id == "dm-new"
+        ));
+        let state = |id: &str| database.with_repositories(|repos| repos.dm_outbox().get(id)).unwrap().unwrap().state;
+        assert_eq!(state("dm-old"), OUTBOX_FAILED);
+        assert_eq!(outbox.flush_peer("BOB").await.unwrap(), 1);
+
+        // A receipt that shows up late still counts as delivered
+        assert!(outbox.acknowledge(&receipt("dm-old", "BOB", "ME")).unwrap());
+        assert_eq!(state("dm-old"), OUTBOX_ACKED);
+    }
+
+    #[tokio::test]
+    async fn only_dms_between_friends_are_held() {
+        let database = database();
+        database
+            .with_repositories(|repos| {
+                repos.peers().upsert(&friend("ALICE", "friend"))?;
+                repos.peers().upsert(&friend("BOB", "friend"))?;
+                repos.peers().upsert(&friend("STRANGER", "stranger"))
+            })
+            .unwrap();
+        let (tx, _rx) = mpsc::channel(16);
+        let outbox = DmOutbox::new(database.clone(), tx);
+
+        assert!(outbox.hold(&dm("dm-1", "ALICE", "BOB")).unwrap());
+        assert!(!outbox.hold(&dm("dm-2", "STRANGER", "BOB")).unwrap());
+
+        // Bob's receipt releases our copy
+        assert!(outbox.acknowledge(&receipt("dm-1", "BOB", "ALICE")).unwrap());
+        assert!(database.with_repositories(|repos| repos.dm_outbox().get("dm-1")).unwrap().is_none());
+    }
+}
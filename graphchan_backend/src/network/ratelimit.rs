@@ -0,0 +1,527 @@
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Largest encoded envelope the ingest loop accepts (file chunks excepted).
+pub const MAX_PAYLOAD_BYTES: usize = 1024 * 1024;
+/// Neighbors and authors tracked before idle entries are swept.
+const MAX_TRACKED: usize = 4096;
+/// Drop events kept for the stats endpoint.
+const RECENT_EVENTS: usize = 100;
+
+/// Refill rate and capacity of a token bucket; one message costs one token.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct BucketRate {
+    pub per_second: f64,
+    pub burst: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimits {
+    pub max_payload_bytes: usize,
+    /// Everything a single gossip neighbor hands us, whoever wrote it
+    pub neighbor: BucketRate,
+    /// Everything claiming one author, whichever neighbor relays it
+    pub author: BucketRate,
+    /// Violations within `strike_window` that get a neighbor dropped
+    pub strikes_before_penalty: u32,
+    pub strike_window: Duration,
+    /// First penalty; each repeat offence doubles it up to `max_penalty`
+    pub base_penalty: Duration,
+    pub max_penalty: Duration,
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        Self {
+            max_payload_bytes: MAX_PAYLOAD_BYTES,
+            neighbor: BucketRate {
+                per_second: 20.0,
+                burst: 200.0,
+            },
+            author: BucketRate {
+                per_second: 5.0,
+                burst: 50.0,
+            },
+            strikes_before_penalty: 30,
+            strike_window: Duration::from_secs(60),
+            base_penalty: Duration::from_secs(5 * 60),
+            max_penalty: Duration::from_secs(60 * 60),
+        }
+    }
+}
+
+/// Why an inbound payload was dropped before reaching a handler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DropReason {
+    Oversized,
+    NeighborLimited,
+    AuthorLimited,
+    /// The delivering neighbor is serving a penalty
+    NeighborPenalized,
+}
+
+/// One dropped payload, as listed by the stats endpoint.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RateLimitEvent {
+    /// Unix time of the drop
+    pub at: i64,
+    pub neighbor: Option<String>,
+    pub author: Option<String>,
+    pub kind: &'static str,
+    pub size_bytes: usize,
+    pub reason: DropReason,
+    /// This violation started a penalty for the neighbor
+    pub penalized: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NeighborPenalty {
+    pub neighbor: String,
+    /// Penalties served so far, including this one
+    pub offences: u32,
+    pub remaining_secs: u64,
+}
+
+/// Counters and recent drops reported by `GET /network/rate-limits`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RateLimitStats {
+    pub accepted: u64,
+    pub oversized: u64,
+    pub neighbor_limited: u64,
+    pub author_limited: u64,
+    /// Payloads dropped because their neighbor was penalized
+    pub penalized_drops: u64,
+    /// Penalties handed out
+    pub penalties: u64,
+    pub tracked_neighbors: usize,
+    pub tracked_authors: usize,
+    pub max_payload_bytes: usize,
+    pub active_penalties: Vec<NeighborPenalty>,
+    /// Newest first; drops by already penalized neighbors are only counted
+    pub recent_events: Vec<RateLimitEvent>,
+}
+
+/// Token buckets per delivering neighbor and per verified author.
+///
+/// The ingest loop checks the neighbor before any payload touches the database.
+/// Oversized payloads and neighbor-limit violations are strikes against the
+/// neighbor; enough strikes within a minute drop everything it sends for a
+/// while. Authors are charged by the handlers once their signature verified,
+/// and limits only drop the payload, since honest neighbors relay floods they
+/// didn't write. Cheap to clone; `NetworkHandle` and the ingest
+/// loop share one instance.
+#[derive(Clone)]
+pub struct IngestLimiter {
+    local_peer_id: String,
+    limits: RateLimits,
+    inner: Arc<Mutex<LimiterState>>,
+}
+
+/// A payload's pending charge against its author's bucket.
+///
+/// Handed to the ingest handlers, which spend it only after the author's
+/// signature verified, so a forged author claim never costs the real author.
+#[derive(Clone)]
+pub struct AuthorBudget {
+    limiter: IngestLimiter,
+    neighbor: Option<String>,
+    kind: &'static str,
+    size_bytes: usize,
+}
+
+impl AuthorBudget {
+    /// Takes one token from `author`'s bucket; false means drop the payload.
+    pub fn spend(&self, author: &str) -> bool {
+        self.limiter.charge_author_at(self, author, Instant::now())
+    }
+}
+
+#[derive(Default)]
+struct LimiterState {
+    neighbors: HashMap<String, NeighborState>,
+    authors: HashMap<String, TokenBucket>,
+    events: VecDeque<RateLimitEvent>,
+    stats: RateLimitStats,
+}
+
+struct NeighborState {
+    bucket: TokenBucket,
+    strikes: u32,
+    strikes_since: Instant,
+    penalized_until: Option<Instant>,
+    offences: u32,
+}
+
+struct TokenBucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn full(rate: BucketRate, now: Instant) -> Self {
+        Self {
+            tokens: rate.burst,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, rate: BucketRate, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate.per_second).min(rate.burst);
+        self.updated = now;
+    }
+
+    fn try_take(&mut self, rate: BucketRate, now: Instant) -> bool {
+        self.refill(rate, now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn is_full(&mut self, rate: BucketRate, now: Instant) -> bool {
+        self.refill(rate, now);
+        self.tokens >= rate.burst
+    }
+}
+
+impl NeighborState {
+    fn new(rate: BucketRate, now: Instant) -> Self {
+        Self {
+            bucket: TokenBucket::full(rate, now),
+            strikes: 0,
+            strikes_since: now,
+            penalized_until: None,
+            offences: 0,
+        }
+    }
+
+    fn is_penalized(&self, now: Instant) -> bool {
+        self.penalized_until.is_some_and(|until| now < until)
+    }
+
+    /// Counts a violation; returns true when it starts a penalty.
+    fn strike(&mut self, limits: &RateLimits, now: Instant) -> bool {
+        if now.saturating_duration_since(self.strikes_since) >= limits.strike_window {
+            self.strikes = 0;
+            self.strikes_since = now;
+        }
+        self.strikes += 1;
+        if self.strikes < limits.strikes_before_penalty {
+            return false;
+        }
+        self.offences += 1;
+        let doublings = (self.offences - 1).min(16);
+        let penalty = limits
+            .base_penalty
+            .saturating_mul(1 << doublings)
+            .min(limits.max_penalty);
+        self.penalized_until = Some(now + penalty);
+        self.strikes = 0;
+        self.strikes_since = now;
+        true
+    }
+}
+
+impl IngestLimiter {
+    pub fn new(local_peer_id: impl Into<String>) -> Self {
+        Self::with_limits(local_peer_id, RateLimits::default())
+    }
+
+    pub fn with_limits(local_peer_id: impl Into<String>, limits: RateLimits) -> Self {
+        Self {
+            local_peer_id: local_peer_id.into(),
+            limits,
+            inner: Arc::new(Mutex::new(LimiterState::default())),
+        }
+    }
+
+    /// Admits one inbound payload by its delivering neighbor; `Some(reason)` means drop it.
+    ///
+    /// `neighbor` is the node that delivered it, `kind` its `EventPayload::kind`.
+    /// The author it claims is unverified here, so only the neighbor is charged;
+    /// handlers charge the author through `author_budget` once the signature checks out.
+    pub fn check(&self, neighbor: Option<&str>, kind: &'static str, size_bytes: usize) -> Option<DropReason> {
+        self.check_at(neighbor, kind, size_bytes, Instant::now())
+    }
+
+    /// The author charge for a payload `check` admitted.
+    pub fn author_budget(&self, neighbor: Option<&str>, kind: &'static str, size_bytes: usize) -> AuthorBudget {
+        AuthorBudget {
+            limiter: self.clone(),
+            neighbor: neighbor.map(str::to_string),
+            kind,
+            size_bytes,
+        }
+    }
+
+    pub fn stats(&self) -> RateLimitStats {
+        self.stats_at(Instant::now())
+    }
+
+    fn check_at(&self, neighbor: Option<&str>, kind: &'static str, size_bytes: usize, now: Instant) -> Option<DropReason> {
+        let limits = &self.limits;
+        let mut guard = self.inner.lock().expect("ingest limiter mutex poisoned");
+        let state = &mut *guard;
+        if state.neighbors.len() + state.authors.len() >= MAX_TRACKED {
+            state.sweep(limits, now);
+        }
+
+        let mut neighbor_state = neighbor.map(|id| {
+            state
+                .neighbors
+                .entry(id.to_string())
+                .or_insert_with(|| NeighborState::new(limits.neighbor, now))
+        });
+        if neighbor_state.as_ref().is_some_and(|entry| entry.is_penalized(now)) {
+            state.stats.penalized_drops += 1;
+            return Some(DropReason::NeighborPenalized);
+        }
+
+        // File chunks answer our own requests and carry whole attachments
+        let reason = if kind != "FileChunk" && size_bytes > limits.max_payload_bytes {
+            DropReason::Oversized
+        } else if neighbor_state
+            .as_mut()
+            .is_some_and(|entry| !entry.bucket.try_take(limits.neighbor, now))
+        {
+            DropReason::NeighborLimited
+        } else {
+            state.stats.accepted += 1;
+            return None;
+        };
+
+        let penalized = neighbor_state.is_some_and(|entry| entry.strike(limits, now));
+        match reason {
+            DropReason::Oversized => state.stats.oversized += 1,
+            _ => state.stats.neighbor_limited += 1,
+        }
+        if penalized {
+            state.stats.penalties += 1;
+            tracing::warn!(neighbor = ?neighbor, ?reason, "penalizing gossip neighbor for flooding");
+        }
+        state.record(RateLimitEvent {
+            at: chrono::Utc::now().timestamp(),
+            neighbor: neighbor.map(str::to_string),
+            author: None,
+            kind,
+            size_bytes,
+            reason,
+            penalized,
+        });
+        Some(reason)
+    }
+
+    fn charge_author_at(&self, budget: &AuthorBudget, author: &str, now: Instant) -> bool {
+        if author == self.local_peer_id {
+            return true;
+        }
+        let limits = &self.limits;
+        let mut guard = self.inner.lock().expect("ingest limiter mutex poisoned");
+        let state = &mut *guard;
+        if state.neighbors.len() + state.authors.len() >= MAX_TRACKED {
+            state.sweep(limits, now);
+        }
+        let allowed = state
+            .authors
+            .entry(author.to_string())
+            .or_insert_with(|| TokenBucket::full(limits.author, now))
+            .try_take(limits.author, now);
+        if !allowed {
+            state.stats.author_limited += 1;
+            state.record(RateLimitEvent {
+                at: chrono::Utc::now().timestamp(),
+                neighbor: budget.neighbor.clone(),
+                author: Some(author.to_string()),
+                kind: budget.kind,
+                size_bytes: budget.size_bytes,
+                reason: DropReason::AuthorLimited,
+                penalized: false,
+            });
+        }
+        allowed
+    }
+
+    fn stats_at(&self, now: Instant) -> RateLimitStats {
+        let state = self.inner.lock().expect("ingest limiter mutex poisoned");
+        let mut active_penalties: Vec<NeighborPenalty> = state
+            .neighbors
+            .iter()
+            .filter_map(|(neighbor, entry)| {
+                let until = entry.penalized_until.filter(|until| now < *until)?;
+                Some(NeighborPenalty {
+                    neighbor: neighbor.clone(),
+                    offences: entry.offences,
+                    remaining_secs: until.saturating_duration_since(now).as_secs(),
+                })
+            })
+            .collect();
+        active_penalties.sort_by(|a, b| b.remaining_secs.cmp(&a.remaining_secs).then(a.neighbor.cmp(&b.neighbor)));
+        RateLimitStats {
+            tracked_neighbors: state.neighbors.len(),
+            tracked_authors: state.authors.len(),
+            max_payload_bytes: self.limits.max_payload_bytes,
+            active_penalties,
+            recent_events: state.events.iter().cloned().collect(),
+            ..state.stats.clone()
+        }
+    }
+}
+
+impl LimiterState {
+    fn record(&mut self, event: RateLimitEvent) {
+        if self.events.len() >= RECENT_EVENTS {
+            self.events.pop_back();
+        }
+        self.events.push_front(event);
+    }
+
+    /// Forgets idle entries: full buckets, no penalty, and (for neighbors) no record of offences.
+    fn sweep(&mut self, limits: &RateLimits, now: Instant) {
+        self.neighbors.retain(|_, entry| {
+            entry.offences > 0 || entry.is_penalized(now) || !entry.bucket.is_full(limits.neighbor, now)
+        });
+        self.authors.retain(|_, bucket| !bucket.is_full(limits.author, now));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits() -> RateLimits {
+        RateLimits {
+            max_payload_bytes: 1024,
+            neighbor: BucketRate {
+                per_second: 1.0,
+                burst: 3.0,
+            },
+            author: BucketRate {
+                per_second: 1.0,
+                burst: 2.0,
+            },
+            strikes_before_penalty: 3,
+            strike_window: Duration::from_secs(60),
+            base_penalty: Duration::from_secs(60),
+            max_penalty: Duration::from_secs(150),
+        }
+    }
+
+    #[test]
+    fn neighbor_bucket_drops_past_burst_and_refills() {
+        let limiter = IngestLimiter::with_limits("local", limits());
+        let start = Instant::now();
+        for _ in 0..3 {
+            assert_eq!(limiter.check_at(Some("n1"), "PostUpdate", 10, start), None);
+        }
+        assert_eq!(
+            limiter.check_at(Some("n1"), "PostUpdate", 10, start),
+            Some(DropReason::NeighborLimited)
+        );
+        // Other neighbors have their own bucket
+        assert_eq!(limiter.check_at(Some("n2"), "PostUpdate", 10, start), None);
+        // One token per second comes back
+        let later = start + Duration::from_secs(1);
+        assert_eq!(limiter.check_at(Some("n1"), "PostUpdate", 10, later), None);
+        assert_eq!(
+            limiter.check_at(Some("n1"), "PostUpdate", 10, later),
+            Some(DropReason::NeighborLimited)
+        );
+
+        let stats = limiter.stats_at(later);
+        assert_eq!(stats.accepted, 5);
+        assert_eq!(stats.neighbor_limited, 2);
+        assert_eq!(stats.recent_events.len(), 2);
+        assert!(stats.active_penalties.is_empty());
+    }
+
+    #[test]
+    fn repeated_strikes_penalize_the_neighbor_with_growing_penalties() {
+        let limiter = IngestLimiter::with_limits("local", limits());
+        let start = Instant::now();
+        for _ in 0..3 {
+            limiter.check_at(Some("n1"), "PostUpdate", 10, start);
+        }
+        // Three violations: two strikes, then the third starts a penalty
+        limiter.check_at(Some("n1"), "PostUpdate", 10, start);
+        limiter.check_at(Some("n1"), "PostUpdate", 4096, start);
+        assert_eq!(
+            limiter.check_at(Some("n1"), "PostUpdate", 10, start),
+            Some(DropReason::NeighborLimited)
+        );
+        let stats = limiter.stats_at(start);
+        assert_eq!(stats.penalties, 1);
+        assert_eq!(stats.oversized, 1);
+        assert!(stats.recent_events[0].penalized);
+        assert_eq!(stats.active_penalties.len(), 1);
+        assert_eq!(stats.active_penalties[0].remaining_secs, 60);
+
+        // Everything is dropped while penalized, even with tokens to spare
+        let during = start + Duration::from_secs(30);
+        assert_eq!(
+            limiter.check_at(Some("n1"), "PostUpdate", 10, during),
+            Some(DropReason::NeighborPenalized)
+        );
+        assert_eq!(limiter.stats_at(during).penalized_drops, 1);
+
+        // After it expires the neighbor is heard again; a repeat offence doubles the penalty
+        let after = start + Duration::from_secs(61);
+        assert_eq!(limiter.check_at(Some("n1"), "PostUpdate", 10, after), None);
+        for _ in 0..3 {
+            limiter.check_at(Some("n1"), "PostUpdate", 4096, after);
+        }
+        let stats = limiter.stats_at(after);
+        assert_eq!(stats.penalties, 2);
+        assert_eq!(stats.active_penalties[0].offences, 2);
+        assert_eq!(stats.active_penalties[0].remaining_secs, 120);
+
+        // Capped at `max_penalty`
+        let third = after + Duration::from_secs(121);
+        for _ in 0..3 {
+            limiter.check_at(Some("n1"), "PostUpdate", 4096, third);
+        }
+        assert_eq!(limiter.stats_at(third).active_penalties[0].remaining_secs, 150);
+    }
+
+    #[test]
+    fn author_limits_span_neighbors_without_penalizing_them() {
+        let limiter = IngestLimiter::with_limits("local", limits());
+        let now = Instant::now();
+        let from = |neighbor: &str| limiter.author_budget(Some(neighbor), "PostUpdate", 10);
+        assert!(limiter.charge_author_at(&from("n1"), "spammer", now));
+        assert!(limiter.charge_author_at(&from("n2"), "spammer", now));
+        for _ in 0..3 {
+            assert!(!limiter.charge_author_at(&from("n3"), "spammer", now));
+        }
+        // Our own payloads are exempt from the author limit, file chunks from the size limit
+        for _ in 0..3 {
+            assert!(limiter.charge_author_at(&from("n2"), "local", now));
+        }
+        assert_eq!(limiter.check_at(None, "FileChunk", 1 << 20, now), None);
+
+        let stats = limiter.stats_at(now);
+        assert_eq!(stats.author_limited, 3);
+        assert_eq!(stats.penalties, 0);
+        assert!(stats.active_penalties.is_empty());
+        assert_eq!(stats.tracked_authors, 1);
+        assert_eq!(stats.recent_events[0].author.as_deref(), Some("spammer"));
+        assert_eq!(stats.recent_events[0].neighbor.as_deref(), Some("n3"));
+    }
+
+    #[test]
+    fn the_neighbor_check_never_charges_an_author() {
+        let limiter = IngestLimiter::with_limits("local", limits());
+        let now = Instant::now();
+        // Forged payloads naming a victim cost only the delivering neighbors
+        for neighbor in ["n1", "n2", "n3", "n4"] {
+            assert_eq!(limiter.check_at(Some(neighbor), "PostUpdate", 10, now), None);
+        }
+        assert_eq!(limiter.stats_at(now).tracked_authors, 0);
+        assert!(limiter.charge_author_at(&limiter.author_budget(Some("n1"), "PostUpdate", 10), "victim", now));
+    }
+}
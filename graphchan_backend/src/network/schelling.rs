@@ -15,6 +15,7 @@
 //! - Last-writer-wins on the same (key, salt) slot is acceptable because we
 //!   query both current and previous minute windows and republish every ~30s
 
+use super::address_book::TopicAddressBook;
 use anyhow::{Context, Result};
 use chacha20poly1305::{
     aead::{Aead, KeyInit},
@@ -240,12 +241,14 @@ fn query_records(
 ///
 /// Takes a `GossipSender` for the topic's existing subscription so we can
 /// call `join_peers()` to add discovered peers without creating redundant
-/// subscription handles.
+/// subscription handles. Every address found is also written to the topic's
+/// persisted address book for reuse after a restart.
 pub async fn run_schelling_loop(
     topic_name: String,
     endpoint: Arc<Endpoint>,
     gossip_sender: GossipSender,
     static_provider: StaticProvider,
+    address_book: TopicAddressBook,
 ) {
     tracing::info!(topic = %topic_name, "starting schelling point discovery loop");
 
@@ -295,6 +298,14 @@ pub async fn run_schelling_loop(
                 Ok(addr) => {
                     // Inject into StaticProvider so iroh can resolve this peer
                     static_provider.add_endpoint_info(addr.clone());
+                    if let Err(err) = address_book.record_discovered(&topic_name, &addr) {
+                        tracing::warn!(
+                            error = ?err,
+                            peer = %record.node_id,
+                            topic = %topic_name,
+                            "schelling: failed to cache peer address"
+                        );
+                    }
 
                     if is_new {
                         tracing::info!(
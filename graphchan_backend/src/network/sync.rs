@@ -0,0 +1,365 @@
+use crate::database::Database;
+use crate::peers::PeerView;
+use crate::private_threads::PRIVATE_VISIBILITY;
+use crate::threading::{
+    calculate_post_hash, calculate_thread_hash, PostView, ThreadService, THREAD_HASH_VERSION,
+};
+use anyhow::{bail, Context, Result};
+use iroh::endpoint::{Connection, Endpoint};
+use iroh::protocol::{AcceptError, ProtocolHandler};
+use iroh_base::EndpointAddr;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+/// ALPN for set-reconciliation thread sync.
+pub const SYNC_ALPN: &[u8] = b"orbweaver/thread-sync/1";
+
+/// Largest request a server will read (a `Posts` batch of IDs fits easily).
+const MAX_REQUEST_BYTES: usize = 1024 * 1024;
+/// Largest response a client will read.
+const MAX_RESPONSE_BYTES: usize = 64 * 1024 * 1024;
+/// Post IDs requested per stream, so one response stays well under the limit.
+const POSTS_PER_REQUEST: usize = 256;
+/// Upper bound for a whole reconciliation round.
+const SYNC_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SyncRequest {
+    /// Ask for the per-post hashes of a thread
+    Summary { thread_id: String },
+    /// Ask for full posts (with parents and files) by ID
+    Posts { thread_id: String, post_ids: Vec<String> },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SyncResponse {
+    Summary(ThreadHashes),
+    Posts { posts: Vec<PostView>, peers: Vec<PeerView> },
+    Error { message: String },
+}
+
+/// Per-post hashes (`calculate_post_hash`) of one thread plus the thread hash they roll up to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThreadHashes {
+    /// `THREAD_HASH_VERSION` the hashes were computed with
+    #[serde(default = "legacy_hash_version")]
+    pub hash_version: u8,
+    pub thread_id: String,
+    pub thread_hash: String,
+    pub posts: Vec<PostHash>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PostHash {
+    pub id: String,
+    pub hash: String,
+}
+
+impl ThreadHashes {
+    pub fn from_posts(thread_id: &str, posts: &[PostView]) -> Self {
+        Self {
+            hash_version: THREAD_HASH_VERSION,
+            thread_id: thread_id.to_string(),
+            thread_hash: calculate_thread_hash(posts),
+            posts: posts
+                .iter()
+                .map(|post| PostHash {
+                    id: post.id.clone(),
+                    hash: calculate_post_hash(post),
+                })
+                .collect(),
+        }
+    }
+
+    /// IDs of posts the remote side has that we lack or hold a different version of.
+    pub fn missing_from(&self, local_posts: &[PostView]) -> Vec<String> {
+        let local: HashMap<&str, String> = local_posts
+            .iter()
+            .map(|post| (post.id.as_str(), calculate_post_hash(post)))
+            .collect();
+        self.posts
+            .iter()
+            .filter(|remote| local.get(remote.id.as_str()) != Some(&remote.hash))
+            .map(|remote| remote.id.clone())
+            .collect()
+    }
+}
+
+fn legacy_hash_version() -> u8 {
+    1
+}
+
+/// Posts fetched from a peer, ready to be applied by ingest.
+#[derive(Debug, Clone)]
+pub struct SyncDelta {
+    pub remote_thread_hash: String,
+    pub posts: Vec<PostView>,
+    pub peers: Vec<PeerView>,
+}
+
+/// Serves `SyncRequest`s for public and social threads.
+///
+/// Private threads are refused; their content only travels inside sealed snapshots.
+#[derive(Clone)]
+pub struct ThreadSyncProtocol {
+    database: Database,
+}
+
+impl std::fmt::Debug for ThreadSyncProtocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ThreadSyncProtocol").finish_non_exhaustive()
+    }
+}
+
+impl ThreadSyncProtocol {
+    pub fn new(database: Database) -> Self {
+        Self { database }
+    }
+
+    fn respond(&self, request: SyncRequest) -> SyncResponse {
+        match self.try_respond(request) {
+            Ok(response) => response,
+            Err(err) => SyncResponse::Error {
+                message: format!("{err:#}"),
+            },
+        }
+    }
+
+    fn try_respond(&self, request: SyncRequest) -> Result<SyncResponse> {
+        let thread_id = match &request {
+            SyncRequest::Summary { thread_id } | SyncRequest::Posts { thread_id, .. } => thread_id,
+        };
+        let details = ThreadService::new(self.database.clone())
+            .get_thread(thread_id)?
+            .with_context(|| format!("unknown thread {thread_id}"))?;
+        if details.thread.visibility == PRIVATE_VISIBILITY {
+            bail!("private threads are not served over sync");
+        }
+
+        match request {
+            SyncRequest::Summary { thread_id } => {
+                Ok(SyncResponse::Summary(ThreadHashes::from_posts(&thread_id, &details.posts)))
+            }
+            SyncRequest::Posts { post_ids, .. } => {
+                if post_ids.len() > POSTS_PER_REQUEST {
+                    bail!("at most {POSTS_PER_REQUEST} posts per request");
+                }
+                let wanted: HashSet<&str> = post_ids.iter().map(String::as_str).collect();
+                let posts: Vec<PostView> = details
+                    .posts
+                    .into_iter()
+                    .filter(|post| wanted.contains(post.id.as_str()))
+                    .collect();
+                let authors: HashSet<&str> = posts
+                    .iter()
+                    .filter_map(|post| post.author_peer_id.as_deref())
+                    .collect();
+                let peers = details
+                    .peers
+                    .into_iter()
+                    .filter(|peer| authors.contains(peer.id.as_str()))
+                    .collect();
+                Ok(SyncResponse::Posts { posts, peers })
+            }
+        }
+    }
+}
+
+impl ProtocolHandler for ThreadSyncProtocol {
+    async fn accept(&self, connection: Connection) -> Result<(), AcceptError> {
+        // One request per bi-stream; the client closes the connection when done
+        while let Ok((mut send, mut recv)) = connection.accept_bi().await {
+            let bytes = recv
+                .read_to_end(MAX_REQUEST_BYTES)
+                .await
+                .map_err(AcceptError::from_err)?;
+            let response = match serde_json::from_slice::<SyncRequest>(&bytes) {
+                Ok(request) => self.respond(request),
+                Err(err) => SyncResponse::Error {
+                    message: format!("malformed sync request: {err}"),
+                },
+            };
+            let bytes = serde_json::to_vec(&response).map_err(AcceptError::from_err)?;
+            send.write_all(&bytes).await.map_err(AcceptError::from_err)?;
+            send.finish().map_err(AcceptError::from_err)?;
+        }
+        Ok(())
+    }
+}
+
+/// Reconciles `local_posts` against the copy of the thread held by `addr`.
+///
+/// Returns `None` when both sides already agree, otherwise the posts that
+/// differ. Errors (including peers that don't speak `SYNC_ALPN`) tell the
+/// caller to fall back to a full snapshot download.
+pub async fn fetch_missing_posts(
+    endpoint: &Endpoint,
+    addr: EndpointAddr,
+    thread_id: &str,
+    local_posts: &[PostView],
+) -> Result<Option<SyncDelta>> {
+    tokio::time::timeout(SYNC_TIMEOUT, async {
+        let connection = endpoint
+            .connect(addr, SYNC_ALPN)
+            .await
+            .context("failed to open thread sync connection")?;
+        let result = reconcile(&connection, thread_id, local_posts).await;
+        connection.close(0u32.into(), b"done");
+        result
+    })
+    .await
+    .context("thread sync timed out")?
+}
+
+async fn reconcile(
+    connection: &Connection,
+    thread_id: &str,
+    local_posts: &[PostView],
+) -> Result<Option<SyncDelta>> {
+    let summary = match request(connection, &SyncRequest::Summary { thread_id: thread_id.to_string() }).await? {
+        SyncResponse::Summary(summary) => summary,
+        other => bail!("unexpected sync response: {}", response_kind(&other)),
+    };
+    if summary.thread_id != thread_id {
+        bail!("sync summary is for thread {}, not {}", summary.thread_id, thread_id);
+    }
+    if summary.hash_version != THREAD_HASH_VERSION {
+        // Post hashes from another version would all look different
+        bail!("peer uses thread hash version {}", summary.hash_version);
+    }
+
+    let missing = summary.missing_from(local_posts);
+    if missing.is_empty() {
+        return Ok(None);
+    }
+
+    let mut delta = SyncDelta {
+        remote_thread_hash: summary.thread_hash,
+        posts: Vec::with_capacity(missing.len()),
+        peers: Vec::new(),
+    };
+    for batch in missing.chunks(POSTS_PER_REQUEST) {
+        let request_posts = SyncRequest::Posts {
+            thread_id: thread_id.to_string(),
+            post_ids: batch.to_vec(),
+        };
+        match request(connection, &request_posts).await? {
+            SyncResponse::Posts { posts, peers } => {
+                // Only accept what we asked for, in the thread we asked about
+                delta.posts.extend(
+                    posts
+                        .into_iter()
+                        .filter(|post| post.thread_id == thread_id && batch.contains(&post.id)),
+                );
+                delta.peers.extend(peers);
+            }
+            other => bail!("unexpected sync response: {}", response_kind(&other)),
+        }
+    }
+
+    Ok(Some(delta))
+}
+
+async fn request(connection: &Connection, request: &SyncRequest) -> Result<SyncResponse> {
+    let (mut send, mut recv) = connection.open_bi().await.context("failed to open sync stream")?;
+    send.write_all(&serde_json::to_vec(request)?).await?;
+    send.finish()?;
+    let bytes = recv
+        .read_to_end(MAX_RESPONSE_BYTES)
+        .await
+        .context("failed to read sync response")?;
+    match serde_json::from_slice(&bytes).context("malformed sync response")? {
+        SyncResponse::Error { message } => bail!("peer refused thread sync: {message}"),
+        response => Ok(response),
+    }
+}
+
+fn response_kind(response: &SyncResponse) -> &'static str {
+    match response {
+        SyncResponse::Summary(_) => "summary",
+        SyncResponse::Posts { .. } => "posts",
+        SyncResponse::Error { .. } => "error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::threading::{CreatePostInput, CreateThreadInput};
+    use rusqlite::Connection as SqliteConnection;
+
+    fn database() -> Database {
+        let database = Database::from_connection(SqliteConnection::open_in_memory().expect("db"), true);
+        database.ensure_migrations().expect("migrations");
+        database
+    }
+
+    fn seeded_thread(database: &Database) -> (ThreadService, String) {
+        let service = ThreadService::new(database.clone());
+        let thread = service
+            .create_thread(CreateThreadInput {
+                title: "Sync".into(),
+                body: Some("op".into()),
+                ..Default::default()
+            })
+            .expect("thread");
+        for body in ["one", "two"] {
+            service
+                .create_post(CreatePostInput {
+                    thread_id: thread.thread.id.clone(),
+                    body: body.into(),
+                    ..Default::default()
+                })
+                .expect("post");
+        }
+        (service, thread.thread.id)
+    }
+
+    #[test]
+    fn only_missing_or_changed_posts_are_requested() {
+        let database = database();
+        let (service, thread_id) = seeded_thread(&database);
+        let remote = service.get_thread(&thread_id).unwrap().unwrap().posts;
+
+        let summary = ThreadHashes::from_posts(&thread_id, &remote);
+        assert!(summary.missing_from(&remote).is_empty());
+
+        let mut local = remote.clone();
+        let dropped = local.pop().unwrap();
+        local[0].body = "edited".into();
+        let mut missing = summary.missing_from(&local);
+        missing.sort();
+        let mut expected = vec![dropped.id, remote[0].id.clone()];
+        expected.sort();
+        assert_eq!(missing, expected);
+    }
+
+    #[test]
+    fn server_answers_summary_and_posts() {
+        let database = database();
+        let (service, thread_id) = seeded_thread(&database);
+        let protocol = ThreadSyncProtocol::new(database);
+
+        let SyncResponse::Summary(summary) = protocol.respond(SyncRequest::Summary { thread_id: thread_id.clone() }) else {
+            panic!("expected summary");
+        };
+        assert_eq!(summary.posts.len(), 3);
+
+        let wanted = vec![summary.posts[1].id.clone(), "not-in-thread".to_string()];
+        let SyncResponse::Posts { posts, .. } = protocol.respond(SyncRequest::Posts { thread_id, post_ids: wanted }) else {
+            panic!("expected posts");
+        };
+        assert_eq!(posts.len(), 1);
+        assert_eq!(posts[0].id, summary.posts[1].id);
+    }
+
+    #[test]
+    fn server_refuses_unknown_threads() {
+        let protocol = ThreadSyncProtocol::new(database());
+        let response = protocol.respond(SyncRequest::Summary { thread_id: "missing".into() });
+        assert!(matches!(response, SyncResponse::Error { .. }));
+    }
+}
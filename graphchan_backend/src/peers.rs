@@ -2,6 +2,7 @@ use crate::database::models::PeerRecord;
 use crate::database::repositories::{PeerIpRepository, PeerRepository};
 use crate::database::Database;
 use crate::identity::{decode_friendcode_auto, encode_short_friendcode, FriendCodePayload};
+use crate::trust::TrustLevel;
 use crate::utils::now_utc_iso;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
@@ -43,8 +44,9 @@ impl PeerService {
                 x25519_pubkey: None, // Will be populated from friendcode when available
                 last_seen: Some(now_utc_iso()),
                 avatar_file_id: None,
-                trust_state: "trusted".into(),
+                trust_state: "friend".into(),
                 agents: None,
+                gpg_public_key: None,
             };
             repos.peers().upsert(&record)?;
             Ok(PeerView::from_record(record))
@@ -62,6 +64,8 @@ impl PeerService {
 
         self.database.with_repositories(|repos| {
             repos.peers().upsert(&record)?;
+            // Adding someone's friendcode makes them a friend, whatever they were before
+            repos.peers().set_trust_state(&record.id, TrustLevel::Friend.as_str())?;
 
             // Store IP addresses for this peer
             let timestamp = chrono::Utc::now().timestamp();
@@ -119,6 +123,9 @@ pub struct PeerView {
     pub trust_state: String,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub agents: Option<Vec<String>>,
+    /// Armored OpenPGP public key used to verify this peer's signatures
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gpg_public_key: Option<String>,
 }
 
 impl PeerView {
@@ -150,6 +157,7 @@ impl PeerView {
             avatar_file_id: record.avatar_file_id,
             trust_state: record.trust_state,
             agents,
+            gpg_public_key: record.gpg_public_key,
         }
     }
 }
@@ -166,8 +174,9 @@ fn payload_to_peer_record(friendcode: &str, payload: &FriendCodePayload) -> Peer
         x25519_pubkey: payload.x25519_pubkey.clone(),
         last_seen: Some(now_utc_iso()),
         avatar_file_id: None,
-        trust_state: "unknown".into(),
+        trust_state: TrustLevel::Friend.as_str().into(),
         agents: None,
+        gpg_public_key: None,
     }
 }
 
@@ -221,6 +230,7 @@ mod tests {
         let friendcode = encode_friendcode("peer-xyz", "FPRINTXYZ", None).unwrap();
         let view = service.register_friendcode(&friendcode).unwrap();
         assert_eq!(view.gpg_fingerprint.as_deref(), Some("FPRINTXYZ"));
+        assert_eq!(view.trust_state, "friend");
     }
 
     #[test]
@@ -0,0 +1,296 @@
+use crate::database::repositories::{ThreadRepository, TopicRepository};
+use crate::database::Database;
+use crate::threading::PostView;
+use crate::topic_config::TopicConfigService;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// Leading zero bits required on topics nobody configured: none, topics opt in.
+pub const DEFAULT_TOPIC_DIFFICULTY: u32 = 0;
+/// Highest difficulty a topic may demand; each extra bit doubles the work.
+pub const MAX_TOPIC_DIFFICULTY: u32 = 24;
+
+const POW_DOMAIN: &[u8] = b"orbweaver-pow-v1";
+
+/// Hashcash-style proof of work: `blake3(domain ‖ resource ‖ nonce)` starts with
+/// at least `difficulty` zero bits.
+///
+/// Stamps are self-verifying and bound to the content's identity (not its
+/// body), so they aren't covered by signatures and survive edits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PowStamp {
+    pub difficulty: u32,
+    pub nonce: u64,
+}
+
+impl PowStamp {
+    /// Searches nonces from zero until one meets `difficulty`. CPU-bound; see `mint`.
+    pub fn compute(resource: &[u8], difficulty: u32) -> Self {
+        let mut nonce = 0u64;
+        while leading_zero_bits(&stamp_hash(resource, nonce)) < difficulty {
+            nonce += 1;
+        }
+        Self { difficulty, nonce }
+    }
+
+    /// True if the hash really has the claimed number of zero bits.
+    pub fn verify(&self, resource: &[u8]) -> bool {
+        leading_zero_bits(&stamp_hash(resource, self.nonce)) >= self.difficulty
+    }
+}
+
+/// Computes a stamp on the blocking pool so async callers don't stall the runtime.
+pub async fn mint(resource: Vec<u8>, difficulty: u32) -> Result<PowStamp> {
+    tokio::task::spawn_blocking(move || PowStamp::compute(&resource, difficulty))
+        .await
+        .context("proof-of-work task failed")
+}
+
+/// Whether `stamp` proves at least `required` bits of work over `resource`.
+pub fn stamp_meets(stamp: Option<&PowStamp>, resource: &[u8], required: u32) -> bool {
+    required == 0 || stamp.is_some_and(|stamp| stamp.difficulty >= required && stamp.verify(resource))
+}
+
+/// What a thread announcement's stamp covers.
+pub fn thread_resource(thread_id: &str, creator_peer_id: &str, created_at: &str) -> Vec<u8> {
+    format!("thread\n{thread_id}\n{creator_peer_id}\n{created_at}").into_bytes()
+}
+
+/// What a post's stamp covers.
+pub fn post_resource(post: &PostView) -> Vec<u8> {
+    format!(
+        "post\n{}\n{}\n{}\n{}",
+        post.id,
+        post.thread_id,
+        post.author_peer_id.as_deref().unwrap_or_default(),
+        post.created_at
+    )
+    .into_bytes()
+}
+
+fn stamp_hash(resource: &[u8], nonce: u64) -> [u8; 32] {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(POW_DOMAIN);
+    hasher.update(&(resource.len() as u64).to_le_bytes());
+    hasher.update(resource);
+    hasher.update(&nonce.to_le_bytes());
+    *hasher.finalize().as_bytes()
+}
+
+fn leading_zero_bits(hash: &[u8; 32]) -> u32 {
+    let mut bits = 0;
+    for byte in hash {
+        if *byte == 0 {
+            bits += 8;
+        } else {
+            bits += byte.leading_zeros();
+            break;
+        }
+    }
+    bits
+}
+
+/// Per-topic difficulties and the stamps we attach to our own content.
+#[derive(Clone)]
+pub struct PowService {
+    database: Database,
+}
+
+impl PowService {
+    pub fn new(database: Database) -> Self {
+        Self { database }
+    }
+
+    /// Difficulty we demand on `topic_id` (and stamp with when posting there):
+    /// our own setting, else the founder's suggestion, else the default.
+    pub fn topic_difficulty(&self, topic_id: &str) -> Result<u32> {
+        let configured = self
+            .database
+            .with_repositories(|repos| repos.topics().pow_difficulty(topic_id))?;
+        if let Some(difficulty) = configured {
+            return Ok(difficulty);
+        }
+        let suggested = TopicConfigService::new(self.database.clone())
+            .metadata(topic_id)?
+            .and_then(|metadata| metadata.pow_difficulty);
+        Ok(suggested.unwrap_or(DEFAULT_TOPIC_DIFFICULTY))
+    }
+
+    pub fn set_topic_difficulty(&self, topic_id: &str, difficulty: u32) -> Result<()> {
+        if difficulty > MAX_TOPIC_DIFFICULTY {
+            bail!("difficulty must be at most {MAX_TOPIC_DIFFICULTY}");
+        }
+        let updated = self
+            .database
+            .with_repositories(|repos| repos.topics().set_pow_difficulty(topic_id, difficulty))?;
+        if !updated {
+            bail!("not subscribed to topic {topic_id}");
+        }
+        Ok(())
+    }
+
+    /// Strictest difficulty among `topics`; 0 when the content isn't on any topic.
+    pub fn required_for_topics(&self, topics: &[String]) -> Result<u32> {
+        let mut required = 0;
+        for topic_id in topics {
+            required = required.max(self.topic_difficulty(topic_id)?);
+        }
+        Ok(required)
+    }
+
+    /// Difficulty for posts in `thread_id`, from the topics it was announced on.
+    pub fn required_for_thread(&self, thread_id: &str) -> Result<u32> {
+        let topics = self
+            .database
+            .with_repositories(|repos| repos.topics().list_thread_topics(thread_id))?;
+        self.required_for_topics(&topics)
+    }
+
+    pub fn thread_stamp(&self, thread_id: &str) -> Result<Option<PowStamp>> {
+        let stamp = self
+            .database
+            .with_repositories(|repos| repos.threads().pow_stamp(thread_id))?;
+        Ok(stamp.map(|(difficulty, nonce)| PowStamp { difficulty, nonce }))
+    }
+
+    /// Keeps a verified stamp from an announcement so our own re-announcements carry it.
+    /// Only replaces a weaker stamp; returns whether it was stored.
+    pub fn remember_thread_stamp(&self, thread_id: &str, resource: &[u8], stamp: &PowStamp) -> Result<bool> {
+        if !stamp.verify(resource) {
+            return Ok(false);
+        }
+        let stronger = !matches!(
+            self.thread_stamp(thread_id)?,
+            Some(existing) if existing.difficulty >= stamp.difficulty
+        );
+        if stronger {
+            self.database.with_repositories(|repos| {
+                repos.threads().set_pow_stamp(thread_id, stamp.difficulty, stamp.nonce)
+            })?;
+        }
+        Ok(stronger)
+    }
+
+    /// Stamp to announce a thread with: the stored one if it is strong enough for
+    /// `topics`, otherwise a freshly minted (and stored) one.
+    pub async fn stamp_thread(
+        &self,
+        thread_id: &str,
+        creator_peer_id: &str,
+        created_at: &str,
+        topics: &[String],
+    ) -> Result<Option<PowStamp>> {
+        let required = self.required_for_topics(topics)?;
+        let stored = self.thread_stamp(thread_id)?;
+        if required == 0 || stored.is_some_and(|stamp| stamp.difficulty >= required) {
+            return Ok(stored);
+        }
+        let started = std::time::Instant::now();
+        let stamp = mint(thread_resource(thread_id, creator_peer_id, created_at), required).await?;
+        tracing::debug!(thread_id = %thread_id, difficulty = required, elapsed = ?started.elapsed(), "minted thread stamp");
+        self.database.with_repositories(|repos| {
+            repos.threads().set_pow_stamp(thread_id, stamp.difficulty, stamp.nonce)
+        })?;
+        Ok(Some(stamp))
+    }
+
+    /// Adds a stamp to `post` if its thread's topics ask for one it doesn't have.
+    pub async fn stamp_post(&self, post: &mut PostView) -> Result<()> {
+        let required = self.required_for_thread(&post.thread_id)?;
+        if required == 0 || post.pow.is_some_and(|stamp| stamp.difficulty >= required) {
+            return Ok(());
+        }
+        let started = std::time::Instant::now();
+        let stamp = mint(post_resource(post), required).await?;
+        tracing::debug!(post_id = %post.id, difficulty = required, elapsed = ?started.elapsed(), "minted post stamp");
+        post.pow = Some(stamp);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::ThreadRecord;
+    use rusqlite::Connection;
+
+    fn database() -> Database {
+        let conn = Connection::open_in_memory().unwrap();
+        let database = Database::from_connection(conn, true);
+        database.ensure_migrations().unwrap();
+        database
+    }
+
+    #[test]
+    fn stamps_verify_only_for_their_resource() {
+        let resource = thread_resource("thread-1", "PEER", "2026-01-01T00:00:00Z");
+        let stamp = PowStamp::compute(&resource, 12);
+        assert!(stamp.verify(&resource));
+        assert!(leading_zero_bits(&stamp_hash(&resource, stamp.nonce)) >= 12);
+        assert!(stamp_meets(Some(&stamp), &resource, 12));
+        assert!(stamp_meets(Some(&stamp), &resource, 8));
+        // A stamp claiming more work than it did, or asked to cover more, fails
+        assert!(!stamp_meets(Some(&stamp), &resource, 13));
+        let other = thread_resource("thread-2", "PEER", "2026-01-01T00:00:00Z");
+        assert!(!stamp_meets(Some(&stamp), &other, 12));
+        // No stamp is fine only where no work is required
+        assert!(stamp_meets(None, &resource, 0));
+        assert!(!stamp_meets(None, &resource, 1));
+        assert_eq!(leading_zero_bits(&[0u8; 32]), 256);
+    }
+
+    #[tokio::test]
+    async fn topic_difficulties_drive_minting() {
+        let database = database();
+        let pow = PowService::new(database.clone());
+        database
+            .with_repositories(|repos| {
+                repos.threads().create(&ThreadRecord {
+                    id: "thread-1".into(),
+                    title: "t".into(),
+                    creator_peer_id: Some("PEER".into()),
+                    created_at: "2026-01-01T00:00:00Z".into(),
+                    pinned: false,
+                    thread_hash: None,
+                    visibility: "social".into(),
+                    topic_secret: None,
+                    sync_status: "downloaded".into(),
+                    source_url: None,
+                    source_platform: None,
+                    last_refreshed_at: None,
+                })?;
+                repos.topics().subscribe("cats")?;
+                repos.topics().add_thread_topic("thread-1", "cats")
+            })
+            .unwrap();
+
+        assert_eq!(pow.required_for_topics(&[]).unwrap(), 0);
+        assert_eq!(pow.topic_difficulty("cats").unwrap(), DEFAULT_TOPIC_DIFFICULTY);
+        assert!(pow.set_topic_difficulty("dogs", 4).is_err());
+        assert!(pow.set_topic_difficulty("cats", MAX_TOPIC_DIFFICULTY + 1).is_err());
+        pow.set_topic_difficulty("cats", 6).unwrap();
+        assert_eq!(pow.required_for_thread("thread-1").unwrap(), 6);
+        // Unconfigured topics demand nothing
+        assert_eq!(pow.required_for_topics(&["dogs".to_string()]).unwrap(), 0);
+        assert_eq!(
+            pow.required_for_topics(&["cats".to_string(), "dogs".to_string()]).unwrap(),
+            6
+        );
+
+        let topics = vec!["cats".to_string()];
+        let resource = thread_resource("thread-1", "PEER", "2026-01-01T00:00:00Z");
+        let stamp = pow
+            .stamp_thread("thread-1", "PEER", "2026-01-01T00:00:00Z", &topics)
+            .await
+            .unwrap()
+            .expect("stamp");
+        assert!(stamp_meets(Some(&stamp), &resource, 6));
+        // Stored and reused while strong enough
+        assert_eq!(pow.thread_stamp("thread-1").unwrap(), Some(stamp));
+        assert!(!pow.remember_thread_stamp("thread-1", &resource, &PowStamp { difficulty: 30, nonce: 0 }).unwrap());
+        assert_eq!(
+            pow.stamp_thread("thread-1", "PEER", "2026-01-01T00:00:00Z", &topics).await.unwrap(),
+            Some(stamp)
+        );
+    }
+}
@@ -0,0 +1,701 @@
+use crate::config::GraphchanPaths;
+use crate::crypto::{
+    decrypt_thread_blob, derive_file_key, encrypt_thread_blob, load_public_key_armored,
+    load_x25519_secret, sign_detached, unwrap_thread_key, verify_detached, wrap_thread_key,
+    WrappedKey, X25519Identity,
+};
+use crate::database::models::{ThreadMemberKey, ThreadRecord};
+use crate::database::repositories::{
+    PeerRepository, SqliteRepositories, ThreadMemberKeyRepository, ThreadRepository,
+};
+use crate::database::Database;
+use crate::errors::{invalid, not_found};
+use crate::network::WrappedThreadKey;
+use anyhow::{anyhow, Context, Result};
+use base64::prelude::*;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use x25519_dalek::PublicKey;
+
+/// Thread visibility value that turns on end-to-end encryption.
+pub const PRIVATE_VISIBILITY: &str = "private";
+
+/// Manages per-thread keys for private threads.
+///
+/// Every member holds the thread key wrapped to their own X25519 key in
+/// `thread_member_keys`. The creator also holds the wrapped copies for the
+/// other members so it can ship them inside thread announcements.
+#[derive(Clone)]
+pub struct PrivateThreadService {
+    database: Database,
+    paths: GraphchanPaths,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ThreadMemberView {
+    pub peer_id: String,
+    pub username: Option<String>,
+    pub key_epoch: i64,
+    pub is_creator: bool,
+}
+
+/// Key material carried by the announcement of a private thread.
+///
+/// Signed by the thread creator's OpenPGP key so nobody else can hand out keys
+/// or claim a newer epoch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadKeyGrant {
+    pub key_epoch: i64,
+    pub wrapper_x25519_pubkey: Option<String>,
+    pub member_keys: Vec<WrappedThreadKey>,
+    /// Creator's detached signature over `signing_payload`
+    #[serde(default)]
+    pub signature: Option<String>,
+    /// Creator's armored public key, for members who have not stored it yet
+    #[serde(default)]
+    pub creator_public_key: Option<String>,
+}
+
+impl ThreadKeyGrant {
+    /// Canonical bytes the creator signs; member keys are sorted so order doesn't matter.
+    pub fn signing_payload(&self, thread_id: &str, creator_peer_id: &str) -> Vec<u8> {
+        #[derive(Serialize)]
+        struct SigningPayload<'a> {
+            domain: &'static str,
+            thread_id: &'a str,
+            creator_peer_id: &'a str,
+            key_epoch: i64,
+            wrapper_x25519_pubkey: Option<&'a str>,
+            member_keys: Vec<(&'a str, &'a str, &'a str)>,
+        }
+        let mut member_keys: Vec<_> = self
+            .member_keys
+            .iter()
+            .map(|key| (key.member_peer_id.as_str(), key.ciphertext.as_str(), key.nonce.as_str()))
+            .collect();
+        member_keys.sort();
+        serde_json::to_vec(&SigningPayload {
+            domain: "orbweaver-thread-key-grant-v1",
+            thread_id,
+            creator_peer_id,
+            key_epoch: self.key_epoch,
+            wrapper_x25519_pubkey: self.wrapper_x25519_pubkey.as_deref(),
+            member_keys,
+        })
+        .expect("thread key grant signing payload serializes")
+    }
+}
+
+impl PrivateThreadService {
+    pub fn new(database: Database, paths: GraphchanPaths) -> Self {
+        Self { database, paths }
+    }
+
+    pub fn is_private(&self, thread_id: &str) -> Result<bool> {
+        self.database.with_repositories(|repos| {
+            Ok(repos
+                .threads()
+                .get(thread_id)?
+                .map(|thread| thread.visibility == PRIVATE_VISIBILITY)
+                .unwrap_or(false))
+        })
+    }
+
+    /// Generates the first key of a new private thread, held only by the creator.
+    pub fn create_thread_key(&self, thread_id: &str) -> Result<()> {
+        let local_peer_id = self.local_peer_id()?;
+        let identity = load_x25519_secret(&self.paths)?;
+        let record = wrap_for_member(
+            thread_id,
+            &local_peer_id,
+            &random_thread_key(),
+            0,
+            &identity.public,
+            &identity,
+        )?;
+        self.database
+            .with_repositories(|repos| repos.thread_member_keys().add(&record))
+    }
+
+    /// Returns the current thread key, or `None` if this node is not a member.
+    pub fn thread_key(&self, thread_id: &str) -> Result<Option<[u8; 32]>> {
+        let local_peer_id = self.local_peer_id()?;
+        let record = self.database.with_repositories(|repos| {
+            repos.thread_member_keys().get(thread_id, &local_peer_id)
+        })?;
+        let Some(record) = record else {
+            return Ok(None);
+        };
+        let identity = load_x25519_secret(&self.paths)?;
+        unwrap_member_key(&record, &identity).map(Some)
+    }
+
+    pub fn list_members(&self, thread_id: &str) -> Result<Vec<ThreadMemberView>> {
+        self.database.with_repositories(|repos| {
+            let thread = repos
+                .threads()
+                .get(thread_id)?
+                .ok_or_else(|| not_found("thread not found"))?;
+            let mut members = Vec::new();
+            for key in repos.thread_member_keys().list_for_thread(thread_id)? {
+                let username = repos
+                    .peers()
+                    .get(&key.member_peer_id)?
+                    .and_then(|peer| peer.username);
+                members.push(ThreadMemberView {
+                    is_creator: thread.creator_peer_id.as_deref() == Some(key.member_peer_id.as_str()),
+                    peer_id: key.member_peer_id,
+                    username,
+                    key_epoch: key.key_epoch,
+                });
+            }
+            Ok(members)
+        })
+    }
+
+    /// Wraps the current thread key for `peer_id`. Only the creator may invite.
+    pub fn invite_member(&self, thread_id: &str, peer_id: &str) -> Result<ThreadMemberView> {
+        let local_peer_id = self.local_peer_id()?;
+        let identity = load_x25519_secret(&self.paths)?;
+        self.database.with_repositories(|repos| {
+            ensure_creator(&repos, thread_id, &local_peer_id)?;
+            let own = repos
+                .thread_member_keys()
+                .get(thread_id, &local_peer_id)?
+                .ok_or_else(|| anyhow!("no key held for private thread {thread_id}"))?;
+            let thread_key = unwrap_member_key(&own, &identity)?;
+
+            let peer = repos
+                .peers()
+                .get(peer_id)?
+                .ok_or_else(|| not_found(format!("peer not found: {peer_id}")))?;
+            let member_public = decode_x25519(peer.x25519_pubkey.as_deref().ok_or_else(|| {
+                invalid(format!("peer {peer_id} has no X25519 public key; ask them for their full friendcode"))
+            })?)?;
+
+            let record = wrap_for_member(
+                thread_id,
+                peer_id,
+                &thread_key,
+                own.key_epoch,
+                &member_public,
+                &identity,
+            )?;
+            repos.thread_member_keys().add(&record)?;
+
+            Ok(ThreadMemberView {
+                peer_id: peer_id.to_string(),
+                username: peer.username,
+                key_epoch: own.key_epoch,
+                is_creator: false,
+            })
+        })
+    }
+
+    /// Removes `peer_id` and rotates the thread key so later snapshots and
+    /// attachments are sealed with a key the removed member never saw.
+    pub fn remove_member(&self, thread_id: &str, peer_id: &str) -> Result<i64> {
+        let local_peer_id = self.local_peer_id()?;
+        if peer_id == local_peer_id {
+            return Err(invalid("the thread creator cannot be removed"));
+        }
+        let identity = load_x25519_secret(&self.paths)?;
+        self.database.with_repositories(|repos| {
+            ensure_creator(&repos, thread_id, &local_peer_id)?;
+            let current = repos.thread_member_keys().list_for_thread(thread_id)?;
+            if !current.iter().any(|key| key.member_peer_id == peer_id) {
+                return Err(invalid(format!("peer {peer_id} is not a member of thread {thread_id}")));
+            }
+            let key_epoch = current
+                .iter()
+                .map(|key| key.key_epoch)
+                .max()
+                .unwrap_or(0)
+                + 1;
+
+            let thread_key = random_thread_key();
+            let mut rotated = Vec::with_capacity(current.len());
+            for member in current.iter().filter(|key| key.member_peer_id != peer_id) {
+                let member_public = if member.member_peer_id == local_peer_id {
+                    identity.public
+                } else {
+                    let encoded = repos
+                        .peers()
+                        .get(&member.member_peer_id)?
+                        .and_then(|peer| peer.x25519_pubkey)
+                        .ok_or_else(|| {
+                            invalid(format!("member {} has no X25519 public key", member.member_peer_id))
+                        })?;
+                    decode_x25519(&encoded)?
+                };
+                rotated.push(wrap_for_member(
+                    thread_id,
+                    &member.member_peer_id,
+                    &thread_key,
+                    key_epoch,
+                    &member_public,
+                    &identity,
+                )?);
+            }
+            repos.thread_member_keys().replace_all(thread_id, &rotated)?;
+            Ok(key_epoch)
+        })
+    }
+
+    /// Returns the signed key grant to ship with an announcement, or `None` if we hold no key.
+    ///
+    /// The creator signs a fresh grant from its rows; members relay the last
+    /// creator-signed grant they accepted, since they only hold their own key.
+    pub fn key_grant(&self, thread_id: &str) -> Result<Option<ThreadKeyGrant>> {
+        let local_peer_id = self.local_peer_id()?;
+        let (is_creator, grant, relayed) = self.database.with_repositories(|repos| {
+            let is_creator = repos
+                .threads()
+                .get(thread_id)?
+                .is_some_and(|thread| thread.creator_peer_id.as_deref() == Some(local_peer_id.as_str()));
+            let keys = repos.thread_member_keys().list_for_thread(thread_id)?;
+            let Some(own) = keys.iter().find(|key| key.member_peer_id == local_peer_id) else {
+                return Ok((is_creator, None, None));
+            };
+            let key_epoch = own.key_epoch;
+            let wrapper_x25519_pubkey = own.wrapper_x25519_pubkey.clone();
+            let member_keys = keys
+                .iter()
+                .filter(|key| key.key_epoch == key_epoch)
+                .map(|key| WrappedThreadKey {
+                    member_peer_id: key.member_peer_id.clone(),
+                    ciphertext: BASE64_STANDARD.encode(&key.wrapped_key_ciphertext),
+                    nonce: BASE64_STANDARD.encode(&key.wrapped_key_nonce),
+                })
+                .collect();
+            let grant = ThreadKeyGrant {
+                key_epoch,
+                wrapper_x25519_pubkey,
+                member_keys,
+                signature: None,
+                creator_public_key: None,
+            };
+            Ok((is_creator, Some(grant), repos.thread_member_keys().signed_grant(thread_id)?))
+        })?;
+        let Some(mut grant) = grant else {
+            return Ok(None);
+        };
+
+        if is_creator {
+            let payload = grant.signing_payload(thread_id, &local_peer_id);
+            grant.signature = Some(sign_detached(&self.paths, &payload)?);
+            grant.creator_public_key = Some(load_public_key_armored(&self.paths)?);
+            return Ok(Some(grant));
+        }
+        match relayed {
+            Some(json) => serde_json::from_str(&json)
+                .map(Some)
+                .context("stored thread key grant is malformed"),
+            // Nothing signed to relay; receivers will ignore the unsigned copy
+            None => Ok(Some(grant)),
+        }
+    }
+
+    /// True if we already hold a key for the thread or `grant` carries one for us.
+    pub fn is_member_or_invited(&self, thread_id: &str, grant: &ThreadKeyGrant) -> Result<bool> {
+        let local_peer_id = self.local_peer_id()?;
+        if grant.member_keys.iter().any(|key| key.member_peer_id == local_peer_id) {
+            return Ok(true);
+        }
+        self.database.with_repositories(|repos| {
+            Ok(repos
+                .thread_member_keys()
+                .get(thread_id, &local_peer_id)?
+                .is_some())
+        })
+    }
+
+    /// Stores our wrapped key from an announcement if it is newer than the one we hold.
+    ///
+    /// The thread row must already exist, and the grant must carry a valid signature
+    /// by that thread's creator; nothing from an unsigned or forged grant is stored.
+    /// When the creator's X25519 key is known, the grant must also have been wrapped
+    /// with it. Returns true if we hold a key afterwards.
+    pub fn accept_key_grant(
+        &self,
+        thread_id: &str,
+        creator_peer_id: &str,
+        grant: &ThreadKeyGrant,
+    ) -> Result<bool> {
+        let local_peer_id = self.local_peer_id()?;
+        let identity = load_x25519_secret(&self.paths)?;
+        self.database.with_repositories(|repos| {
+            let thread = repos
+                .threads()
+                .get(thread_id)?
+                .ok_or_else(|| anyhow!("thread {thread_id} not found"))?;
+            if thread.creator_peer_id.as_deref() != Some(creator_peer_id) {
+                anyhow::bail!("key grant for {thread_id} names {creator_peer_id}, not the thread creator");
+            }
+            let signature = grant
+                .signature
+                .as_deref()
+                .ok_or_else(|| anyhow!("key grant for {thread_id} is not signed by the thread creator"))?;
+            let creator = repos.peers().get(creator_peer_id)?;
+            let creator_public_key = creator
+                .as_ref()
+                .and_then(|peer| peer.gpg_public_key.as_deref())
+                .or(grant.creator_public_key.as_deref())
+                .ok_or_else(|| anyhow!("no public key known for thread creator {creator_peer_id}"))?;
+            verify_detached(
+                creator_public_key,
+                creator_peer_id,
+                &grant.signing_payload(thread_id, creator_peer_id),
+                signature,
+            )
+            .with_context(|| format!("key grant for {thread_id} is not signed by the thread creator"))?;
+
+            let existing = repos.thread_member_keys().get(thread_id, &local_peer_id)?;
+            if let Some(existing) = &existing {
+                if grant.key_epoch <= existing.key_epoch {
+                    return Ok(true);
+                }
+            }
+
+            let Some(entry) = grant
+                .member_keys
+                .iter()
+                .find(|key| key.member_peer_id == local_peer_id)
+            else {
+                if existing.is_some() {
+                    tracing::info!(
+                        thread_id = %thread_id,
+                        key_epoch = grant.key_epoch,
+                        "private thread key rotated without us - no longer a member"
+                    );
+                }
+                return Ok(existing.is_some());
+            };
+
+            let wrapper = grant
+                .wrapper_x25519_pubkey
+                .as_deref()
+                .ok_or_else(|| anyhow!("key grant is missing the wrapper public key"))?;
+            if let Some(creator_key) = creator.and_then(|peer| peer.x25519_pubkey) {
+                if creator_key != wrapper {
+                    anyhow::bail!("thread key for {thread_id} was not wrapped by the thread creator");
+                }
+            }
+
+            let record = ThreadMemberKey {
+                thread_id: thread_id.to_string(),
+                member_peer_id: local_peer_id.clone(),
+                wrapped_key_ciphertext: BASE64_STANDARD
+                    .decode(&entry.ciphertext)
+                    .context("invalid wrapped key encoding")?,
+                wrapped_key_nonce: BASE64_STANDARD
+                    .decode(&entry.nonce)
+                    .context("invalid wrapped key nonce encoding")?,
+                key_epoch: grant.key_epoch,
+                wrapper_x25519_pubkey: Some(wrapper.to_string()),
+            };
+            // Only persist keys we can actually open
+            unwrap_member_key(&record, &identity)?;
+            repos.thread_member_keys().add(&record)?;
+            repos.thread_member_keys().save_signed_grant(
+                thread_id,
+                grant.key_epoch,
+                &serde_json::to_string(grant)?,
+            )?;
+            Ok(true)
+        })
+    }
+
+    /// Encrypts a serialized `ThreadDetails` snapshot with the current thread key.
+    pub fn seal_snapshot(&self, thread_id: &str, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let key = self
+            .thread_key(thread_id)?
+            .ok_or_else(|| anyhow!("no key held for private thread {thread_id}"))?;
+        encrypt_thread_blob(plaintext, &key)
+    }
+
+    /// Decrypts a downloaded snapshot if we hold a key for the thread; other blobs pass through.
+    pub fn open_snapshot(&self, thread_id: &str, blob: Vec<u8>) -> Result<Vec<u8>> {
+        match self.thread_key(thread_id)? {
+            Some(key) => decrypt_thread_blob(&blob, &key)
+                .with_context(|| format!("failed to decrypt snapshot of private thread {thread_id}")),
+            None => Ok(blob),
+        }
+    }
+
+    /// Encrypts an attachment of a private thread with a per-file key.
+    ///
+    /// Returns `(ciphertext, base64 file key)`, or `None` if the thread is not private.
+    pub fn seal_attachment(
+        &self,
+        thread_id: &str,
+        file_id: &str,
+        plaintext: &[u8],
+    ) -> Result<Option<(Vec<u8>, String)>> {
+        if !self.is_private(thread_id)? {
+            return Ok(None);
+        }
+        let thread_key = self
+            .thread_key(thread_id)?
+            .ok_or_else(|| anyhow!("no key held for private thread {thread_id}"))?;
+        let file_key = derive_file_key(&thread_key, file_id);
+        let ciphertext = encrypt_thread_blob(plaintext, &file_key)?;
+        Ok(Some((ciphertext, BASE64_STANDARD.encode(file_key))))
+    }
+
+    fn local_peer_id(&self) -> Result<String> {
+        self.database
+            .get_identity()?
+            .map(|(fingerprint, _, _)| fingerprint)
+            .ok_or_else(|| anyhow!("no local identity found"))
+    }
+}
+
+/// Decrypts a downloaded attachment using the base64 key from `FileRecord.encryption_key`.
+pub fn open_attachment(encryption_key: &str, ciphertext: &[u8]) -> Result<Vec<u8>> {
+    let key: [u8; 32] = BASE64_STANDARD
+        .decode(encryption_key)
+        .context("invalid attachment key encoding")?
+        .try_into()
+        .map_err(|_| anyhow!("attachment key must be 32 bytes"))?;
+    decrypt_thread_blob(ciphertext, &key)
+}
+
+fn ensure_creator(
+    repos: &SqliteRepositories<'_>,
+    thread_id: &str,
+    local_peer_id: &str,
+) -> Result<ThreadRecord> {
+    let thread = repos
+        .threads()
+        .get(thread_id)?
+        .ok_or_else(|| not_found("thread not found"))?;
+    if thread.visibility != PRIVATE_VISIBILITY {
+        return Err(invalid(format!("thread {thread_id} is not a private thread")));
+    }
+    if thread.creator_peer_id.as_deref() != Some(local_peer_id) {
+        return Err(invalid("only the thread creator can manage members"));
+    }
+    Ok(thread)
+}
+
+fn random_thread_key() -> [u8; 32] {
+    let mut key = [0u8; 32];
+    rand::rng().fill_bytes(&mut key);
+    key
+}
+
+fn wrap_for_member(
+    thread_id: &str,
+    member_peer_id: &str,
+    thread_key: &[u8; 32],
+    key_epoch: i64,
+    member_public: &PublicKey,
+    wrapper: &X25519Identity,
+) -> Result<ThreadMemberKey> {
+    let wrapped = wrap_thread_key(thread_key, member_public, &wrapper.secret)?;
+    Ok(ThreadMemberKey {
+        thread_id: thread_id.to_string(),
+        member_peer_id: member_peer_id.to_string(),
+        wrapped_key_ciphertext: wrapped.ciphertext,
+        wrapped_key_nonce: wrapped.nonce.to_vec(),
+        key_epoch,
+        wrapper_x25519_pubkey: Some(BASE64_STANDARD.encode(wrapper.public.as_bytes())),
+    })
+}
+
+fn unwrap_member_key(record: &ThreadMemberKey, identity: &X25519Identity) -> Result<[u8; 32]> {
+    let wrapper_public = match record.wrapper_x25519_pubkey.as_deref() {
+        Some(encoded) => decode_x25519(encoded)?,
+        None => identity.public,
+    };
+    let nonce: [u8; 24] = record
+        .wrapped_key_nonce
+        .clone()
+        .try_into()
+        .map_err(|_| anyhow!("wrapped key nonce must be 24 bytes"))?;
+    let wrapped = WrappedKey {
+        ciphertext: record.wrapped_key_ciphertext.clone(),
+        nonce,
+    };
+    unwrap_thread_key(&wrapped, &wrapper_public, &identity.secret)
+}
+
+fn decode_x25519(encoded: &str) -> Result<PublicKey> {
+    let bytes: [u8; 32] = BASE64_STANDARD
+        .decode(encoded)
+        .context("failed to decode X25519 public key")?
+        .try_into()
+        .map_err(|_| anyhow!("X25519 public key must be 32 bytes"))?;
+    Ok(PublicKey::from(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::{PeerRecord, ThreadRecord};
+    use crate::errors::RequestError;
+    use crate::identity::ensure_local_identity;
+    use rusqlite::Connection;
+    use tempfile::tempdir;
+
+    fn peer(id: &str, x25519_pubkey: Option<String>) -> PeerRecord {
+        PeerRecord {
+            id: id.into(),
+            alias: None,
+            username: Some(id.into()),
+            bio: None,
+            friendcode: None,
+            iroh_peer_id: None,
+            gpg_fingerprint: Some(id.into()),
+            x25519_pubkey,
+            last_seen: None,
+            avatar_file_id: None,
+            trust_state: "friend".into(),
+            agents: None,
+            gpg_public_key: None,
+        }
+    }
+
+    /// A node with a real identity; returns its database, paths, peer ID and X25519 key.
+    fn node(dir: &std::path::Path) -> (Database, GraphchanPaths, String, String) {
+        let paths = GraphchanPaths::from_base_dir(dir).expect("paths");
+        std::fs::create_dir_all(&paths.gpg_dir).expect("gpg dir");
+        std::fs::create_dir_all(&paths.keys_dir).expect("keys dir");
+        let identity = ensure_local_identity(&paths).expect("identity");
+        let peer_id = identity.gpg_fingerprint.clone();
+        let database = Database::from_connection(Connection::open_in_memory().expect("db"), true);
+        database.ensure_migrations().expect("migrations");
+        database.save_identity(&peer_id, "iroh", "friendcode").expect("identity");
+        database
+            .with_repositories(|repos| repos.peers().upsert(&peer(&peer_id, Some(identity.x25519_pubkey.clone()))))
+            .expect("local peer");
+        (database, paths, peer_id, identity.x25519_pubkey)
+    }
+
+    fn private_thread(id: &str, creator: &str) -> ThreadRecord {
+        ThreadRecord {
+            id: id.into(),
+            title: "secret".into(),
+            creator_peer_id: Some(creator.into()),
+            created_at: "2024-01-01T00:00:00Z".into(),
+            pinned: false,
+            thread_hash: None,
+            visibility: PRIVATE_VISIBILITY.into(),
+            topic_secret: None,
+            sync_status: "downloaded".into(),
+            source_url: None,
+            source_platform: None,
+            last_refreshed_at: None,
+        }
+    }
+
+    /// Alice creates private thread `t1` and both nodes know each other's X25519 key.
+    fn alice_and_bob(
+        alice_dir: &std::path::Path,
+        bob_dir: &std::path::Path,
+    ) -> (PrivateThreadService, PrivateThreadService, String, String) {
+        let (alice_db, alice_paths, alice_id, alice_pub) = node(alice_dir);
+        let (bob_db, bob_paths, bob_id, bob_pub) = node(bob_dir);
+        alice_db
+            .with_repositories(|repos| {
+                repos.peers().upsert(&peer(&bob_id, Some(bob_pub.clone())))?;
+                repos.threads().create(&private_thread("t1", &alice_id))
+            })
+            .expect("seed alice");
+        bob_db
+            .with_repositories(|repos| {
+                repos.peers().upsert(&peer(&alice_id, Some(alice_pub.clone())))?;
+                repos.threads().create(&private_thread("t1", &alice_id))
+            })
+            .expect("seed bob");
+        (
+            PrivateThreadService::new(alice_db, alice_paths),
+            PrivateThreadService::new(bob_db, bob_paths),
+            alice_id,
+            bob_id,
+        )
+    }
+
+    #[test]
+    fn invited_member_opens_snapshot_until_removed() {
+        let alice_dir = tempdir().expect("tempdir");
+        let bob_dir = tempdir().expect("tempdir");
+        let (alice, bob, alice_id, bob_id) = alice_and_bob(alice_dir.path(), bob_dir.path());
+        alice.create_thread_key("t1").unwrap();
+        alice.invite_member("t1", &bob_id).unwrap();
+
+        let grant = alice.key_grant("t1").unwrap().expect("grant");
+        assert_eq!(grant.member_keys.len(), 2);
+        assert!(bob.is_member_or_invited("t1", &grant).unwrap());
+        assert!(bob.accept_key_grant("t1", &alice_id, &grant).unwrap());
+
+        let sealed = alice.seal_snapshot("t1", b"{\"posts\":[]}").unwrap();
+        assert_ne!(sealed, b"{\"posts\":[]}".to_vec());
+        assert_eq!(bob.open_snapshot("t1", sealed).unwrap(), b"{\"posts\":[]}".to_vec());
+
+        let (ciphertext, file_key) = alice.seal_attachment("t1", "f1", b"image").unwrap().expect("private");
+        assert_eq!(open_attachment(&file_key, &ciphertext).unwrap(), b"image".to_vec());
+
+        // Members relay the creator's signed grant rather than one of their own
+        let relayed = bob.key_grant("t1").unwrap().expect("relayed grant");
+        assert_eq!(relayed.signature, grant.signature);
+        assert_eq!(relayed.member_keys.len(), 2);
+
+        // Removing Bob rotates the key; his old key no longer opens new snapshots
+        assert_eq!(alice.remove_member("t1", &bob_id).unwrap(), 1);
+        let rotated = alice.key_grant("t1").unwrap().expect("grant");
+        assert_eq!(rotated.member_keys.len(), 1);
+        assert!(rotated.member_keys.iter().all(|key| key.member_peer_id != bob_id));
+        // Bob keeps his stale key, which cannot open anything sealed after the rotation
+        bob.accept_key_grant("t1", &alice_id, &rotated).unwrap();
+        let sealed = alice.seal_snapshot("t1", b"after removal").unwrap();
+        assert!(bob.open_snapshot("t1", sealed).is_err());
+    }
+
+    #[test]
+    fn forged_key_grants_are_rejected() {
+        let alice_dir = tempdir().expect("tempdir");
+        let bob_dir = tempdir().expect("tempdir");
+        let (alice, bob, alice_id, bob_id) = alice_and_bob(alice_dir.path(), bob_dir.path());
+        alice.create_thread_key("t1").unwrap();
+        alice.invite_member("t1", &bob_id).unwrap();
+        let grant = alice.key_grant("t1").unwrap().expect("grant");
+
+        // Unsigned
+        let mut unsigned = grant.clone();
+        unsigned.signature = None;
+        assert!(bob.accept_key_grant("t1", &alice_id, &unsigned).is_err());
+
+        // A huge epoch the creator never signed
+        let mut inflated = grant.clone();
+        inflated.key_epoch = i64::MAX;
+        assert!(bob.accept_key_grant("t1", &alice_id, &inflated).is_err());
+
+        // Signed by someone other than the creator
+        let mut resigned = grant.clone();
+        resigned.signature = Some(sign_detached(&bob.paths, &grant.signing_payload("t1", &alice_id)).unwrap());
+        resigned.creator_public_key = Some(load_public_key_armored(&bob.paths).unwrap());
+        assert!(bob.accept_key_grant("t1", &alice_id, &resigned).is_err());
+
+        // Claiming a different creator than the stored thread
+        assert!(bob.accept_key_grant("t1", &bob_id, &grant).is_err());
+
+        // None of the forgeries stored anything, so the real grant still lands
+        assert!(bob.thread_key("t1").unwrap().is_none());
+        assert!(bob.accept_key_grant("t1", &alice_id, &grant).unwrap());
+        assert!(bob.thread_key("t1").unwrap().is_some());
+    }
+
+    #[test]
+    fn only_creator_manages_members() {
+        let alice_dir = tempdir().expect("tempdir");
+        let bob_dir = tempdir().expect("tempdir");
+        let (_, bob, alice_id, _) = alice_and_bob(alice_dir.path(), bob_dir.path());
+
+        let err = bob.invite_member("t1", &alice_id).unwrap_err();
+        assert!(matches!(err.downcast_ref::<RequestError>(), Some(RequestError::Invalid(_))));
+        let err = bob.list_members("missing").unwrap_err();
+        assert!(matches!(err.downcast_ref::<RequestError>(), Some(RequestError::NotFound(_))));
+    }
+}
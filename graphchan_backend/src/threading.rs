@@ -1,9 +1,12 @@
+use crate::agents::{AgentDelegation, AgentService};
 use crate::database::models::{PostRecord, ThreadRecord};
-use crate::database::repositories::{PeerRepository, PostRepository, ThreadRepository};
+use crate::database::repositories::{PeerRepository, PostRepository, SqliteRepositories, ThreadRepository};
 use crate::database::Database;
+use crate::errors::{invalid, not_found};
 use crate::utils::now_utc_iso;
 use crate::database::repositories::FileRepository;
 use crate::config::GraphchanPaths;
+use crate::private_threads::{PrivateThreadService, PRIVATE_VISIBILITY};
 use anyhow::{anyhow, Context, Result};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -121,7 +124,10 @@ impl ThreadService {
                         view
                     })
                     .collect();
-                views.push(PostView::from_record(post, parents, file_views));
+                let deleted = posts_repo.is_tombstoned(&post.id)?;
+                let mut view = PostView::from_record(post, parents, file_views);
+                view.deleted = deleted;
+                views.push(view);
             }
 
             let mut peers = Vec::new();
@@ -141,33 +147,151 @@ impl ThreadService {
     }
 
     pub fn get_post(&self, post_id: &str) -> Result<Option<PostView>> {
+        self.database.with_repositories(|repos| self.load_post(&repos, post_id))
+    }
+
+    /// Posts `post_id` replies to, transitively, up to `depth` generations (`None` for all).
+    pub fn post_ancestors(&self, post_id: &str, depth: Option<usize>) -> Result<Option<PostGraph>> {
+        self.traverse(post_id, depth, PostDirection::Ancestors)
+    }
+
+    /// Replies to `post_id`, transitively, up to `depth` generations (`None` for the whole subtree).
+    pub fn post_descendants(&self, post_id: &str, depth: Option<usize>) -> Result<Option<PostGraph>> {
+        self.traverse(post_id, depth, PostDirection::Descendants)
+    }
+
+    /// Shortest chain of reply links joining two posts through their nearest
+    /// common ancestor, or `None` if they share none within `MAX_TRAVERSAL_POSTS`
+    /// ancestors of each. Errors `NotFound` if either post is unknown.
+    pub fn post_path(&self, from: &str, to: &str) -> Result<Option<PostPath>> {
+        self.database.with_repositories(|repos| {
+            let (from_ancestry, from_truncated) =
+                ancestry(&repos, from)?.ok_or_else(|| not_found(format!("post {from} not found")))?;
+            let (to_ancestry, to_truncated) =
+                ancestry(&repos, to)?.ok_or_else(|| not_found(format!("post {to} not found")))?;
+            let common = from_ancestry
+                .iter()
+                .filter_map(|(id, (from_distance, _))| {
+                    to_ancestry
+                        .get(id)
+                        .map(|(to_distance, _)| (from_distance + to_distance, id))
+                })
+                .min();
+            let Some((_, common)) = common else {
+                return Ok(None);
+            };
+
+            // `from` up to the common ancestor, then back down to `to`
+            let mut ids = chain_to_origin(&from_ancestry, common);
+            ids.reverse();
+            ids.extend(chain_to_origin(&to_ancestry, common).into_iter().skip(1));
+            let mut posts = Vec::with_capacity(ids.len());
+            for id in &ids {
+                posts.extend(self.load_post(&repos, id)?);
+            }
+            Ok(Some(PostPath {
+                from: from.to_string(),
+                to: to.to_string(),
+                common_ancestor: common.clone(),
+                posts,
+                truncated: from_truncated || to_truncated,
+            }))
+        })
+    }
+
+    /// Breadth-first walk of reply links, closest posts first.
+    fn traverse(&self, post_id: &str, depth: Option<usize>, direction: PostDirection) -> Result<Option<PostGraph>> {
         self.database.with_repositories(|repos| {
             let posts_repo = repos.posts();
-            let post = posts_repo.get(post_id)?;
-            let Some(post) = post else {
+            if posts_repo.get(post_id)?.is_none() {
                 return Ok(None);
+            }
+            let links = |id: &str| match direction {
+                PostDirection::Ancestors => posts_repo.parents_of(id),
+                PostDirection::Descendants => posts_repo.children_of(id),
             };
-            let parents = posts_repo.parents_of(post_id)?;
-            let files = repos.files().list_for_post(post_id)?;
-            let file_views = files
-                .into_iter()
-                .map(|record| {
-                    let mut view = crate::files::FileView::from_record(record.clone());
-                    // Set present flag if file_paths is available
-                    if let Some(ref base) = self.file_paths {
-                        let absolute = base.base.join(&record.path);
-                        view.present = Some(absolute.exists());
+
+            let mut seen = std::collections::HashSet::from([post_id.to_string()]);
+            let mut frontier = vec![post_id.to_string()];
+            let mut posts = Vec::new();
+            let mut missing_post_ids = Vec::new();
+            let mut truncated = false;
+            'walk: for _ in 0..depth.unwrap_or(usize::MAX) {
+                let mut next = Vec::new();
+                for id in &frontier {
+                    for linked in links(id)? {
+                        if !seen.insert(linked.clone()) {
+                            continue;
+                        }
+                        if posts.len() >= MAX_TRAVERSAL_POSTS {
+                            truncated = true;
+                            break 'walk;
+                        }
+                        // Posts may name parents this node never received
+                        match self.load_post(&repos, &linked)? {
+                            Some(view) => {
+                                posts.push(view);
+                                next.push(linked);
+                            }
+                            None => missing_post_ids.push(linked),
+                        }
                     }
-                    view
-                })
-                .collect();
-            Ok(Some(PostView::from_record(post, parents, file_views)))
+                }
+                frontier = next;
+                if frontier.is_empty() {
+                    break;
+                }
+            }
+            if !truncated {
+                for id in &frontier {
+                    if links(id)?.iter().any(|linked| !seen.contains(linked)) {
+                        truncated = true;
+                        break;
+                    }
+                }
+            }
+
+            Ok(Some(PostGraph {
+                post_id: post_id.to_string(),
+                posts,
+                missing_post_ids,
+                truncated,
+            }))
         })
     }
 
+    fn load_post(&self, repos: &SqliteRepositories<'_>, post_id: &str) -> Result<Option<PostView>> {
+        let posts_repo = repos.posts();
+        let post = posts_repo.get(post_id)?;
+        let Some(post) = post else {
+            return Ok(None);
+        };
+        let parents = posts_repo.parents_of(post_id)?;
+        let files = repos.files().list_for_post(post_id)?;
+        let file_views = files
+            .into_iter()
+            .map(|record| {
+                let mut view = crate::files::FileView::from_record(record.clone());
+                // Set present flag if file_paths is available
+                if let Some(ref base) = self.file_paths {
+                    let absolute = base.base.join(&record.path);
+                    view.present = Some(absolute.exists());
+                }
+                view
+            })
+            .collect();
+        let mut view = PostView::from_record(post, parents, file_views);
+        view.deleted = posts_repo.is_tombstoned(post_id)?;
+        Ok(Some(view))
+    }
+
     pub fn create_thread(&self, input: CreateThreadInput) -> Result<ThreadDetails> {
         if input.title.trim().is_empty() {
-            anyhow::bail!("thread title may not be empty");
+            return Err(invalid("thread title may not be empty"));
+        }
+        let is_private = input.visibility.as_deref() == Some(PRIVATE_VISIBILITY);
+        if is_private && self.file_paths.is_none() {
+            anyhow::bail!("private threads need access to the node's encryption keys");
         }
         let thread_id = Uuid::new_v4().to_string();
         let created_at = input.created_at.unwrap_or_else(now_utc_iso);
@@ -188,7 +312,21 @@ impl ThreadService {
 
         let initial_post_body = input.body.clone();
         let author_peer_id = input.creator_peer_id.clone();
-        let topics = input.topics.clone();
+        // Private threads are never announced on public topics
+        let topics = if is_private { Vec::new() } else { input.topics.clone() };
+        let local_fingerprint = self.local_fingerprint()?;
+
+        // The OP names its agent like any other post (see `create_post`)
+        let op_id = Uuid::new_v4().to_string();
+        let mut op_metadata = input.metadata;
+        let agent = op_metadata.as_mut().and_then(|meta| meta.agent.as_mut());
+        if let (Some(paths), Some(agent)) = (&self.file_paths, agent) {
+            if local_fingerprint.is_some() && author_peer_id == local_fingerprint {
+                AgentService::new(self.database.clone(), paths.clone())
+                    .attest(agent, &op_id, &thread_id, &created_at)?;
+            }
+        }
+        let op_metadata_json = op_metadata.as_ref().and_then(|meta| serde_json::to_string(meta).ok());
 
         self.database.with_repositories(|repos| {
             use crate::database::repositories::TopicRepository;
@@ -222,29 +360,37 @@ impl ThreadService {
                         None
                     };
 
-                    let post_record = PostRecord {
-                        id: Uuid::new_v4().to_string(),
+                    let mut post_record = PostRecord {
+                        id: op_id,
                         thread_id: thread_id.clone(),
                         author_peer_id,
                         author_friendcode,
                         body,
                         created_at: created_at.clone(),
                         updated_at: None,
-                        metadata: None,
+                        metadata: op_metadata_json,
+                        signature: None,
+                        signature_status: None,
                     };
+                    self.sign_record(&mut post_record, &[], local_fingerprint.as_deref())?;
                     repos.posts().create(&post_record)?;
                 }
             }
             Ok(())
         })?;
 
+        if let (true, Some(paths)) = (is_private, &self.file_paths) {
+            PrivateThreadService::new(self.database.clone(), paths.clone())
+                .create_thread_key(&thread_id)?;
+        }
+
         self.get_thread(&thread_id)
             .and_then(|opt| opt.context("thread creation lost newly inserted record"))
     }
 
     pub fn create_post(&self, input: CreatePostInput) -> Result<PostView> {
         if input.body.trim().is_empty() {
-            anyhow::bail!("post body may not be empty");
+            return Err(invalid("post body may not be empty"));
         }
 
         // Look up author's full friend code (v2 legacy format with multiaddrs for IP extraction)
@@ -279,26 +425,44 @@ impl ThreadService {
             None
         };
 
+        let post_id = Uuid::new_v4().to_string();
+        let created_at = input.created_at.unwrap_or_else(now_utc_iso);
+        let local_fingerprint = self.local_fingerprint()?;
+
+        // Our agents sign with their delegated key; names we never delegated are refused
+        let mut metadata = input.metadata;
+        let agent = metadata.as_mut().and_then(|meta| meta.agent.as_mut());
+        if let (Some(paths), Some(agent)) = (&self.file_paths, agent) {
+            if local_fingerprint.is_some() && input.author_peer_id == local_fingerprint {
+                AgentService::new(self.database.clone(), paths.clone())
+                    .attest(agent, &post_id, &input.thread_id, &created_at)?;
+            }
+        }
+
         // Serialize metadata to JSON if present
-        let metadata_json = input.metadata.as_ref().and_then(|meta| {
+        let metadata_json = metadata.as_ref().and_then(|meta| {
             serde_json::to_string(meta).ok()
         });
 
-        let post_record = PostRecord {
-            id: Uuid::new_v4().to_string(),
+        let mut post_record = PostRecord {
+            id: post_id,
             thread_id: input.thread_id.clone(),
             author_peer_id: input.author_peer_id.clone(),
             author_friendcode,
             body: input.body,
-            created_at: input.created_at.unwrap_or_else(now_utc_iso),
+            created_at,
             updated_at: None,
             metadata: metadata_json,
+            signature: None,
+            signature_status: None,
         };
+        let author_public_key =
+            self.sign_record(&mut post_record, &input.parent_post_ids, local_fingerprint.as_deref())?;
 
         let stored_post = self.database.with_repositories(|repos| {
             // ensure thread exists
             if repos.threads().get(&post_record.thread_id)?.is_none() {
-                anyhow::bail!("thread not found");
+                return Err(not_found(format!("thread {} not found", post_record.thread_id)));
             }
 
             // Update thread's rebroadcast flag
@@ -323,14 +487,154 @@ impl ThreadService {
             body: stored_post.body,
             created_at: stored_post.created_at,
             updated_at: stored_post.updated_at,
+            deleted: false,
+            quarantined: false,
+            pow: None,
             parent_post_ids: input.parent_post_ids,
             files: Vec::new(),
             thread_hash: None, // Only populated for network broadcast
+            canonical_thread_hash: None,
             metadata,
+            signature: stored_post.signature,
+            signature_status: stored_post.signature_status,
+            author_public_key,
         })
     }
+
+    /// Replaces the body of a post authored by the local node and re-signs it.
+    ///
+    /// The previous body is kept as a revision (see `post_history`).
+    pub fn edit_post(&self, post_id: &str, body: String) -> Result<PostView> {
+        if body.trim().is_empty() {
+            return Err(invalid("post body may not be empty"));
+        }
+        let (mut post, author_public_key) = self.signed_revision(post_id, |post| {
+            post.body = body;
+        })?;
+        self.database.with_repositories(|repos| {
+            repos.posts().apply_edit(
+                post_id,
+                &post.body,
+                post.updated_at.as_deref().unwrap_or_default(),
+                post.signature.as_deref(),
+                SIGNATURE_VERIFIED,
+            )
+        })?;
+        post.author_public_key = author_public_key;
+        Ok(post)
+    }
+
+    /// Retracts a post authored by the local node, leaving a signed tombstone.
+    ///
+    /// Replies keep pointing at the post; its body, revisions and attachments
+    /// are dropped. Returns the tombstone and the local paths of removed files.
+    pub fn delete_post(&self, post_id: &str) -> Result<(PostView, Vec<String>)> {
+        let (mut post, author_public_key) = self.signed_revision(post_id, |post| {
+            post.body.clear();
+            post.deleted = true;
+        })?;
+        let file_paths = self.database.with_repositories(|repos| {
+            let paths = repos
+                .files()
+                .list_for_post(post_id)?
+                .into_iter()
+                .map(|file| file.path)
+                .collect();
+            repos.posts().tombstone(
+                post_id,
+                post.updated_at.as_deref().unwrap_or_default(),
+                post.signature.as_deref(),
+                SIGNATURE_VERIFIED,
+            )?;
+            Ok(paths)
+        })?;
+        post.files.clear();
+        post.author_public_key = author_public_key;
+        Ok((post, file_paths))
+    }
+
+    /// Current post plus its earlier bodies, oldest first.
+    pub fn post_history(&self, post_id: &str) -> Result<Option<PostHistory>> {
+        let Some(post) = self.get_post(post_id)? else {
+            return Ok(None);
+        };
+        let revisions = self
+            .database
+            .with_repositories(|repos| repos.posts().list_revisions(post_id))?;
+        Ok(Some(PostHistory { post, revisions }))
+    }
+
+    /// Applies `change` to the local author's post, stamps `updated_at` and signs the result.
+    fn signed_revision(
+        &self,
+        post_id: &str,
+        change: impl FnOnce(&mut PostView),
+    ) -> Result<(PostView, Option<String>)> {
+        let mut post = self
+            .get_post(post_id)?
+            .ok_or_else(|| not_found(format!("post {post_id} not found")))?;
+        if post.deleted {
+            return Err(invalid("post has been deleted"));
+        }
+        let (Some(paths), Some(local)) = (&self.file_paths, self.local_fingerprint()?) else {
+            anyhow::bail!("editing posts needs access to the node's signing key");
+        };
+        if post.author_peer_id.as_deref() != Some(local.as_str()) {
+            return Err(invalid("only the author can edit or delete a post"));
+        }
+
+        change(&mut post);
+        // Peers apply the version with the newest `updated_at`
+        post.updated_at = Some(now_utc_iso());
+        let signature = crate::crypto::sign_detached(paths, &post.signing_payload())
+            .context("failed to sign post")?;
+        post.signature = Some(signature);
+        post.signature_status = Some(SIGNATURE_VERIFIED.to_string());
+        Ok((post, Some(crate::crypto::load_public_key_armored(paths)?)))
+    }
+
+    /// Returns the local node's GPG fingerprint when signing is possible (key paths configured).
+    fn local_fingerprint(&self) -> Result<Option<String>> {
+        if self.file_paths.is_none() {
+            return Ok(None);
+        }
+        Ok(self.database.get_identity()?.map(|(fingerprint, _, _)| fingerprint))
+    }
+
+    /// Signs a post authored by the local node with its OpenPGP key.
+    ///
+    /// Posts by other authors (or when no key is available) are left unsigned.
+    /// Returns the armored public key so broadcasts can carry it to peers that
+    /// have not seen this node yet.
+    fn sign_record(
+        &self,
+        record: &mut PostRecord,
+        parent_post_ids: &[String],
+        local_fingerprint: Option<&str>,
+    ) -> Result<Option<String>> {
+        let (Some(paths), Some(local)) = (&self.file_paths, local_fingerprint) else {
+            return Ok(None);
+        };
+        if record.author_peer_id.as_deref() != Some(local) {
+            return Ok(None);
+        }
+
+        let view = PostView::from_record(record.clone(), parent_post_ids.to_vec(), Vec::new());
+        let signature = crate::crypto::sign_detached(paths, &view.signing_payload())
+            .context("failed to sign post")?;
+        record.signature = Some(signature);
+        record.signature_status = Some(SIGNATURE_VERIFIED.to_string());
+        Ok(Some(crate::crypto::load_public_key_armored(paths)?))
+    }
 }
 
+/// Post signature verified against the author's key.
+pub const SIGNATURE_VERIFIED: &str = "verified";
+/// Post carried no signature (legacy peers).
+pub const SIGNATURE_UNSIGNED: &str = "unsigned";
+/// Post was signed but no key for the author was available to check it.
+pub const SIGNATURE_UNVERIFIED: &str = "unverified";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ThreadSummary {
     pub id: String,
@@ -358,6 +662,12 @@ pub struct AgentInfo {
     pub name: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub version: Option<String>,
+    /// The author node's grant of the agent's posting key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delegation: Option<AgentDelegation>,
+    /// The agent key's Ed25519 signature (base64) over `agents::post_resource`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -378,15 +688,114 @@ pub struct PostView {
     pub body: String,
     pub created_at: String,
     pub updated_at: Option<String>,
+    /// Retracted by its author: the body is empty and only its place in the DAG remains
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub deleted: bool,
+    /// Held for review because its author is a stranger; set only in local API views (see `trust.rs`)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub quarantined: bool,
+    /// Proof of work for topics that demand it (see `pow.rs`); not part of the signed payload
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pow: Option<crate::pow::PowStamp>,
     pub parent_post_ids: Vec<String>,
     #[serde(default)]
     pub files: Vec<crate::files::FileView>,
     /// Thread hash for synchronization - allows peers to detect they're out of sync
     #[serde(skip_serializing_if = "Option::is_none")]
     pub thread_hash: Option<String>,
+    /// Canonical thread hash (`THREAD_HASH_VERSION`); `thread_hash` carries the legacy one for older peers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canonical_thread_hash: Option<String>,
     /// Post metadata (agent info, client info, etc.)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub metadata: Option<PostMetadata>,
+    /// ASCII-armored detached OpenPGP signature over `signing_payload()`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    /// Local verification result: "verified", "unsigned" or "unverified"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature_status: Option<String>,
+    /// Author's armored public key; only trusted if its fingerprint equals `author_peer_id`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author_public_key: Option<String>,
+}
+
+/// Posts reached from `post_id` by following reply links in one direction.
+#[derive(Debug, Clone, Serialize)]
+pub struct PostGraph {
+    pub post_id: String,
+    /// Closest first; each post's `parent_post_ids` give the edges
+    pub posts: Vec<PostView>,
+    /// Parents referenced by a post in the graph that this node doesn't have
+    pub missing_post_ids: Vec<String>,
+    /// More posts lie beyond the requested depth or `MAX_TRAVERSAL_POSTS`
+    pub truncated: bool,
+}
+
+/// The reply chain joining two posts.
+#[derive(Debug, Clone, Serialize)]
+pub struct PostPath {
+    pub from: String,
+    pub to: String,
+    /// Where the two chains meet; `from` or `to` itself when one replies (transitively) to the other
+    pub common_ancestor: String,
+    /// From `from` up to `common_ancestor`, then down to `to`
+    pub posts: Vec<PostView>,
+    /// An ancestry walk stopped at `MAX_TRAVERSAL_POSTS`, so a shorter path may exist
+    pub truncated: bool,
+}
+
+#[derive(Clone, Copy)]
+enum PostDirection {
+    Ancestors,
+    Descendants,
+}
+
+/// Largest number of posts a single traversal returns.
+pub const MAX_TRAVERSAL_POSTS: usize = 500;
+
+/// Post ID to its distance from the walk's origin and the post one step closer to it.
+type Ancestry = std::collections::HashMap<String, (usize, Option<String>)>;
+
+/// `post_id` and up to `MAX_TRAVERSAL_POSTS` of its stored ancestors, closest
+/// first, and whether more lie beyond; `None` if the post is unknown.
+fn ancestry(repos: &SqliteRepositories<'_>, post_id: &str) -> Result<Option<(Ancestry, bool)>> {
+    let posts_repo = repos.posts();
+    if posts_repo.get(post_id)?.is_none() {
+        return Ok(None);
+    }
+    let mut found = Ancestry::from([(post_id.to_string(), (0, None))]);
+    let mut queue = std::collections::VecDeque::from([(post_id.to_string(), 0)]);
+    while let Some((id, distance)) = queue.pop_front() {
+        for parent in posts_repo.parents_of(&id)? {
+            if found.contains_key(&parent) || posts_repo.get(&parent)?.is_none() {
+                continue;
+            }
+            // The origin itself doesn't count towards the cap
+            if found.len() > MAX_TRAVERSAL_POSTS {
+                return Ok(Some((found, true)));
+            }
+            found.insert(parent.clone(), (distance + 1, Some(id.clone())));
+            queue.push_back((parent, distance + 1));
+        }
+    }
+    Ok(Some((found, false)))
+}
+
+/// `start` followed by the posts leading back to the origin of an `ancestry` walk.
+fn chain_to_origin(ancestry: &Ancestry, start: &str) -> Vec<String> {
+    let mut chain = vec![start.to_string()];
+    while let Some((_, Some(next))) = ancestry.get(chain.last().expect("chain is never empty")) {
+        chain.push(next.clone());
+    }
+    chain
+}
+
+/// A post together with the bodies it replaced.
+#[derive(Debug, Clone, Serialize)]
+pub struct PostHistory {
+    pub post: PostView,
+    pub revisions: Vec<crate::database::models::PostRevisionRecord>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -412,6 +821,9 @@ pub struct CreateThreadInput {
     /// List of topic IDs to announce this thread on (for public discovery)
     #[serde(default)]
     pub topics: Vec<String>,
+    /// Metadata for the initial post (agent info, client info, etc.)
+    #[serde(default)]
+    pub metadata: Option<PostMetadata>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -471,11 +883,58 @@ impl PostView {
             body: record.body,
             created_at: record.created_at,
             updated_at: record.updated_at,
+            deleted: false,
+            quarantined: false,
+            pow: None,
             parent_post_ids,
             files,
             thread_hash: None, // Only populated for network broadcast
+            canonical_thread_hash: None,
             metadata,
+            signature: record.signature,
+            signature_status: record.signature_status,
+            author_public_key: None,
+        }
+    }
+
+    /// Canonical bytes covered by the author's signature.
+    ///
+    /// Excludes transport-only fields (files, thread_hash, signature itself) so that
+    /// the same post verifies whether it arrives as a PostUpdate or in a snapshot.
+    pub fn signing_payload(&self) -> Vec<u8> {
+        #[derive(Serialize)]
+        struct SigningPayload<'a> {
+            domain: &'static str,
+            id: &'a str,
+            thread_id: &'a str,
+            author_peer_id: Option<&'a str>,
+            body: &'a str,
+            created_at: &'a str,
+            updated_at: Option<&'a str>,
+            parent_post_ids: Vec<&'a str>,
+            metadata: Option<&'a PostMetadata>,
+            // Only present on tombstones, so signatures of live posts are unchanged
+            #[serde(skip_serializing_if = "std::ops::Not::not")]
+            deleted: bool,
         }
+
+        let mut parent_post_ids: Vec<&str> =
+            self.parent_post_ids.iter().map(String::as_str).collect();
+        parent_post_ids.sort_unstable();
+
+        let payload = SigningPayload {
+            domain: "orbweaver-post-v1",
+            id: &self.id,
+            thread_id: &self.thread_id,
+            author_peer_id: self.author_peer_id.as_deref(),
+            body: &self.body,
+            created_at: &self.created_at,
+            updated_at: self.updated_at.as_deref(),
+            parent_post_ids,
+            metadata: self.metadata.as_ref(),
+            deleted: self.deleted,
+        };
+        serde_json::to_vec(&payload).expect("post signing payload serializes")
     }
 }
 
@@ -504,6 +963,7 @@ mod tests {
                 created_at: None,
                 visibility: None,
                 topics: vec![],
+                metadata: None,
             })
             .expect("create thread");
         assert_eq!(details.thread.title, "Example");
@@ -523,6 +983,7 @@ mod tests {
                 created_at: None,
                 visibility: None,
                 topics: vec![],
+                metadata: None,
             })
             .expect("create thread");
 
@@ -545,22 +1006,467 @@ mod tests {
         assert_eq!(fetched.posts.len(), 1);
         assert_eq!(fetched.posts[0].body, "Reply");
     }
+
+    #[test]
+    fn signing_payload_ignores_parent_order_and_transport_fields() {
+        let mut post = PostView {
+            id: "post".into(),
+            thread_id: "thread".into(),
+            author_peer_id: Some("AUTHOR".into()),
+            author_friendcode: None,
+            body: "Body".into(),
+            created_at: "2024-01-01T00:00:00Z".into(),
+            updated_at: None,
+            deleted: false,
+            quarantined: false,
+            pow: None,
+            parent_post_ids: vec!["b".into(), "a".into()],
+            files: Vec::new(),
+            thread_hash: None,
+            canonical_thread_hash: None,
+            metadata: None,
+            signature: None,
+            signature_status: None,
+            author_public_key: None,
+        };
+        let original = post.signing_payload();
+
+        post.parent_post_ids.reverse();
+        post.thread_hash = Some("hash".into());
+        post.signature = Some("sig".into());
+        assert_eq!(post.signing_payload(), original);
+
+        post.body = "Edited".into();
+        assert_ne!(post.signing_payload(), original);
+    }
+
+    #[test]
+    fn reply_links_can_be_walked_without_the_whole_thread() {
+        let service = setup_service();
+        let thread = service
+            .create_thread(CreateThreadInput {
+                title: "Forks".into(),
+                body: Some("op".into()),
+                ..Default::default()
+            })
+            .expect("thread");
+        let op = thread.posts[0].id.clone();
+        let reply = |body: &str, parents: &[&String]| {
+            service
+                .create_post(CreatePostInput {
+                    thread_id: thread.thread.id.clone(),
+                    body: body.into(),
+                    parent_post_ids: parents.iter().map(|id| id.to_string()).collect(),
+                    ..Default::default()
+                })
+                .expect("reply")
+                .id
+        };
+        let a = reply("a", &[&op]);
+        let b = reply("b", &[&a]);
+        let c = reply("c", &[&op]);
+
+        let ancestors = service.post_ancestors(&b, None).unwrap().expect("post exists");
+        let ids: Vec<_> = ancestors.posts.iter().map(|post| post.id.clone()).collect();
+        assert_eq!(ids, vec![a.clone(), op.clone()]);
+        assert!(!ancestors.truncated);
+
+        let children = service.post_descendants(&op, Some(1)).unwrap().expect("post exists");
+        assert_eq!(children.posts.len(), 2);
+        assert!(children.truncated, "b lies below the requested depth");
+
+        let path = service.post_path(&b, &c).unwrap().expect("connected");
+        assert_eq!(path.common_ancestor, op);
+        let ids: Vec<_> = path.posts.iter().map(|post| post.id.clone()).collect();
+        assert_eq!(ids, vec![b.clone(), a, op.clone(), c]);
+
+        let lineal = service.post_path(&op, &b).unwrap().expect("connected");
+        assert_eq!(lineal.common_ancestor, op);
+        assert_eq!(lineal.posts.len(), 3);
+        assert!(service.post_ancestors("missing", None).unwrap().is_none());
+    }
+
+    /// A thread whose OP is followed by `chain` replies, each answering the one before.
+    fn reply_chain(service: &ThreadService, chain: usize) -> Vec<String> {
+        let thread = service
+            .create_thread(CreateThreadInput {
+                title: "Chain".into(),
+                body: Some("op".into()),
+                ..Default::default()
+            })
+            .expect("thread");
+        let mut ids = vec![thread.posts[0].id.clone()];
+        for n in 0..chain {
+            let post = service
+                .create_post(CreatePostInput {
+                    thread_id: thread.thread.id.clone(),
+                    body: format!("reply {n}"),
+                    parent_post_ids: vec![ids[n].clone()],
+                    ..Default::default()
+                })
+                .expect("reply");
+            ids.push(post.id);
+        }
+        ids
+    }
+
+    fn ids(posts: &[PostView]) -> Vec<String> {
+        posts.iter().map(|post| post.id.clone()).collect()
+    }
+
+    #[test]
+    fn traversals_report_depth_cuts_and_missing_parents() {
+        let service = setup_service();
+        let chain = reply_chain(&service, 2);
+        let (op, a, b) = (&chain[0], &chain[1], &chain[2]);
+        // Links to posts we never received can't be inserted with foreign keys on
+        service
+            .database
+            .with_conn(|conn| {
+                conn.execute_batch("PRAGMA foreign_keys = OFF;")?;
+                conn.execute(
+                    "INSERT INTO post_relationships (parent_id, child_id) VALUES ('never-received', ?1)",
+                    [a],
+                )?;
+                conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+                Ok(())
+            })
+            .expect("dangling parent");
+
+        let parent = service.post_ancestors(b, Some(1)).unwrap().expect("post exists");
+        assert_eq!(ids(&parent.posts), vec![a.clone()]);
+        assert!(parent.truncated, "a has parents beyond depth 1");
+        assert!(parent.missing_post_ids.is_empty());
+
+        let all = service.post_ancestors(b, None).unwrap().expect("post exists");
+        assert_eq!(ids(&all.posts), vec![a.clone(), op.clone()]);
+        assert!(!all.truncated);
+        assert_eq!(all.missing_post_ids, vec!["never-received".to_string()]);
+
+        let leaf = service.post_descendants(b, None).unwrap().expect("post exists");
+        assert!(leaf.posts.is_empty());
+        assert!(!leaf.truncated);
+    }
+
+    #[test]
+    fn walks_stop_at_the_traversal_cap() {
+        let service = setup_service();
+        let chain = reply_chain(&service, MAX_TRAVERSAL_POSTS + 1);
+        let tip = chain.last().expect("tip");
+
+        let ancestors = service.post_ancestors(tip, None).unwrap().expect("post exists");
+        assert_eq!(ancestors.posts.len(), MAX_TRAVERSAL_POSTS);
+        assert!(ancestors.truncated);
+        assert_eq!(ancestors.posts.last().map(|post| &post.id), Some(&chain[1]));
+
+        // The OP is one step beyond the cap, so the walk from the tip never meets it
+        assert!(service.post_path(tip, &chain[0]).unwrap().is_none());
+        let capped = service.post_path(tip, &chain[10]).unwrap().expect("within the cap");
+        assert_eq!(capped.common_ancestor, chain[10]);
+        assert_eq!(capped.posts.len(), chain.len() - 10);
+        assert!(capped.truncated);
+
+        let short = service.post_path(&chain[1], &chain[3]).unwrap().expect("connected");
+        assert_eq!(ids(&short.posts), chain[1..=3].to_vec());
+        assert!(!short.truncated);
+    }
+
+    #[test]
+    fn posts_without_a_common_ancestor_have_no_path() {
+        let service = setup_service();
+        let first = reply_chain(&service, 1);
+        let second = reply_chain(&service, 1);
+
+        assert!(service.post_path(&first[1], &second[1]).unwrap().is_none());
+        let err = service.post_path(&first[1], "missing").unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<crate::errors::RequestError>(),
+            Some(crate::errors::RequestError::NotFound(_))
+        ));
+    }
+
+    /// A service that signs as the local node, plus that node's fingerprint.
+    fn signing_service(dir: &std::path::Path) -> (ThreadService, String) {
+        let paths = GraphchanPaths::from_base_dir(dir).expect("paths");
+        std::fs::create_dir_all(&paths.gpg_dir).expect("gpg dir");
+        let identity = crate::identity::ensure_local_identity(&paths).expect("identity");
+        let database = Database::from_connection(Connection::open_in_memory().expect("db"), true);
+        database.ensure_migrations().expect("migrations");
+        database
+            .save_identity(&identity.gpg_fingerprint, &identity.iroh_peer_id, &identity.friendcode)
+            .expect("save identity");
+        (ThreadService::with_file_paths(database, paths), identity.gpg_fingerprint)
+    }
+
+    #[test]
+    fn edits_are_signed_and_keep_history() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let (service, local) = signing_service(dir.path());
+        let thread = service
+            .create_thread(CreateThreadInput {
+                title: "Edits".into(),
+                body: Some("first".into()),
+                creator_peer_id: Some(local.clone()),
+                ..Default::default()
+            })
+            .expect("thread");
+        let op_id = thread.posts[0].id.clone();
+
+        let edited = service.edit_post(&op_id, "second".into()).expect("edit");
+        assert_eq!(edited.body, "second");
+        assert!(edited.updated_at.is_some());
+        let public_key = edited.author_public_key.clone().expect("public key");
+        crate::crypto::verify_detached(
+            &public_key,
+            &local,
+            &edited.signing_payload(),
+            edited.signature.as_deref().unwrap(),
+        )
+        .expect("edit signature verifies");
+
+        let history = service.post_history(&op_id).unwrap().expect("post exists");
+        assert_eq!(history.post.body, "second");
+        assert_eq!(history.revisions.len(), 1);
+        assert_eq!(history.revisions[0].body, "first");
+    }
+
+    #[test]
+    fn deleting_leaves_a_tombstone_that_keeps_replies_attached() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let (service, local) = signing_service(dir.path());
+        let thread = service
+            .create_thread(CreateThreadInput {
+                title: "Tombstones".into(),
+                body: Some("regret".into()),
+                creator_peer_id: Some(local.clone()),
+                ..Default::default()
+            })
+            .expect("thread");
+        let op_id = thread.posts[0].id.clone();
+        let reply = service
+            .create_post(CreatePostInput {
+                thread_id: thread.thread.id.clone(),
+                author_peer_id: Some(local),
+                body: "reply".into(),
+                parent_post_ids: vec![op_id.clone()],
+                ..Default::default()
+            })
+            .expect("reply");
+        service.edit_post(&op_id, "more regret".into()).expect("edit");
+
+        let (tombstone, _) = service.delete_post(&op_id).expect("delete");
+        assert!(tombstone.deleted);
+        assert!(tombstone.body.is_empty());
+
+        let fetched = service.get_thread(&thread.thread.id).unwrap().unwrap();
+        let op = fetched.posts.iter().find(|p| p.id == op_id).expect("tombstone kept");
+        assert!(op.deleted);
+        let reply = fetched.posts.iter().find(|p| p.id == reply.id).expect("reply");
+        assert_eq!(reply.parent_post_ids, vec![op_id.clone()]);
+        assert!(service.post_history(&op_id).unwrap().unwrap().revisions.is_empty());
+        assert!(service.edit_post(&op_id, "undo".into()).is_err());
+    }
+
+    #[test]
+    fn only_the_author_can_edit() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let (service, _) = signing_service(dir.path());
+        let thread = service
+            .create_thread(CreateThreadInput {
+                title: "Someone else".into(),
+                body: Some("not mine".into()),
+                creator_peer_id: None,
+                ..Default::default()
+            })
+            .expect("thread");
+        let err = service.edit_post(&thread.posts[0].id, "mine now".into()).unwrap_err();
+        assert!(err.to_string().contains("only the author"));
+    }
+
+    #[test]
+    fn posting_errors_are_request_errors() {
+        use crate::errors::RequestError;
+        let dir = tempfile::tempdir().expect("tempdir");
+        let (service, local) = signing_service(dir.path());
+        let thread = service
+            .create_thread(CreateThreadInput { title: "Errors".into(), ..Default::default() })
+            .expect("thread");
+        let post = |thread_id: &str, body: &str, agent: Option<&str>| {
+            service.create_post(CreatePostInput {
+                thread_id: thread_id.into(),
+                author_peer_id: Some(local.clone()),
+                body: body.into(),
+                metadata: agent.map(|name| PostMetadata {
+                    agent: Some(AgentInfo {
+                        name: name.into(),
+                        version: None,
+                        delegation: None,
+                        signature: None,
+                    }),
+                    client: None,
+                }),
+                ..Default::default()
+            })
+        };
+        let kind = |err: anyhow::Error| match err.downcast_ref::<RequestError>() {
+            Some(RequestError::NotFound(_)) => "not found",
+            Some(RequestError::Invalid(_)) => "invalid",
+            Some(RequestError::Forbidden(_)) => "forbidden",
+            None => "internal",
+        };
+
+        assert_eq!(kind(post("missing", "hi", None).unwrap_err()), "not found");
+        assert_eq!(kind(post(&thread.thread.id, "  ", None).unwrap_err()), "invalid");
+        assert_eq!(kind(post(&thread.thread.id, "hi", Some("ghost")).unwrap_err()), "invalid");
+        let untitled = CreateThreadInput { title: " ".into(), ..Default::default() };
+        assert_eq!(kind(service.create_thread(untitled).unwrap_err()), "invalid");
+        assert_eq!(kind(service.edit_post("missing", "hi".into()).unwrap_err()), "not found");
+    }
+
+    fn hash_test_post(id: &str, created_at: &str, parents: &[&str]) -> PostView {
+        PostView {
+            id: id.into(),
+            thread_id: "thread".into(),
+            author_peer_id: None,
+            author_friendcode: None,
+            body: format!("body of {id}"),
+            created_at: created_at.into(),
+            updated_at: None,
+            deleted: false,
+            quarantined: false,
+            pow: None,
+            parent_post_ids: parents.iter().map(|p| p.to_string()).collect(),
+            files: Vec::new(),
+            thread_hash: None,
+            canonical_thread_hash: None,
+            metadata: None,
+            signature: None,
+            signature_status: None,
+            author_public_key: None,
+        }
+    }
+
+    #[test]
+    fn thread_hash_is_independent_of_order_and_timestamp_ties() {
+        let a = hash_test_post("a", "2024-01-01T00:00:00Z", &[]);
+        let b = hash_test_post("b", "2024-01-01T00:00:00Z", &["a"]);
+        let c = hash_test_post("c", "2024-01-01T00:00:00Z", &["a", "b"]);
+        let mut reordered = c.clone();
+        reordered.parent_post_ids.reverse();
+
+        let forward = calculate_thread_hash(&[a.clone(), b.clone(), c]);
+        assert_eq!(forward, calculate_thread_hash(&[reordered, b, a]));
+        assert!(!is_legacy_thread_hash(&forward));
+    }
+
+    #[test]
+    fn thread_hash_covers_edges_and_attachments() {
+        let a = hash_test_post("a", "2024-01-01T00:00:00Z", &[]);
+        let b = hash_test_post("b", "2024-01-01T00:00:01Z", &["a"]);
+        let base = calculate_thread_hash(&[a.clone(), b.clone()]);
+
+        let mut detached = b.clone();
+        detached.parent_post_ids.clear();
+        assert_ne!(base, calculate_thread_hash(&[a.clone(), detached]));
+
+        let mut with_file = b;
+        with_file.files.push(crate::files::FileView {
+            id: "file".into(),
+            post_id: "b".into(),
+            original_name: None,
+            mime: None,
+            size_bytes: Some(3),
+            checksum: Some("abc".into()),
+            blob_id: Some("blob".into()),
+            ticket: None,
+            path: String::new(),
+            present: None,
+            download_status: None,
+            encryption_key: None,
+        });
+        assert_ne!(base, calculate_thread_hash(&[a, with_file]));
+    }
+
+    #[test]
+    fn hashes_are_compared_in_the_senders_version() {
+        let posts = vec![
+            hash_test_post("a", "2024-01-01T00:00:00Z", &[]),
+            hash_test_post("b", "2024-01-01T00:00:01Z", &["a"]),
+        ];
+        let legacy = calculate_legacy_thread_hash(&posts);
+        assert!(is_legacy_thread_hash(&legacy));
+        assert!(thread_hash_matches(&posts, &legacy));
+        assert!(thread_hash_matches(&posts, &calculate_thread_hash(&posts)));
+        assert!(!thread_hash_matches(&posts[..1], &legacy));
+    }
 }
 
+/// Version of the canonical thread hash; stored and announced hashes carry a `v{N}:` prefix.
+pub const THREAD_HASH_VERSION: u8 = 2;
+const THREAD_HASH_PREFIX: &str = "v2:";
+
 /// Calculate a hash for a single post (for sync purposes)
+///
+/// Covers the post's content, its sorted parent edges and its attachments, so
+/// two peers only agree when they hold the same DAG and the same files.
 pub fn calculate_post_hash(post: &PostView) -> String {
     let mut hasher = blake3::Hasher::new();
-    hasher.update(post.id.as_bytes());
-    hasher.update(post.body.as_bytes());
-    hasher.update(post.created_at.as_bytes());
-    if let Some(updated) = &post.updated_at {
-        hasher.update(updated.as_bytes());
+    hasher.update(b"orbweaver-post-hash-v2");
+    hash_field(&mut hasher, post.id.as_bytes());
+    hash_field(&mut hasher, post.thread_id.as_bytes());
+    hash_field(&mut hasher, post.body.as_bytes());
+    hash_field(&mut hasher, post.created_at.as_bytes());
+    hash_optional(&mut hasher, post.updated_at.as_deref());
+
+    let mut parents: Vec<&str> = post.parent_post_ids.iter().map(String::as_str).collect();
+    parents.sort_unstable();
+    parents.dedup();
+    hasher.update(&(parents.len() as u64).to_le_bytes());
+    for parent in parents {
+        hash_field(&mut hasher, parent.as_bytes());
+    }
+
+    let mut files: Vec<&crate::files::FileView> = post.files.iter().collect();
+    files.sort_by(|a, b| a.id.cmp(&b.id));
+    files.dedup_by(|a, b| a.id == b.id);
+    hasher.update(&(files.len() as u64).to_le_bytes());
+    for file in files {
+        hash_field(&mut hasher, file.id.as_bytes());
+        hash_optional(&mut hasher, file.blob_id.as_deref());
+        hash_optional(&mut hasher, file.checksum.as_deref());
+        hasher.update(&file.size_bytes.unwrap_or(-1).to_le_bytes());
     }
+
     hasher.finalize().to_hex().to_string()
 }
 
 /// Calculate a thread hash from all post hashes (for sync detection)
+///
+/// Posts are ordered by ID, which is unique, so equal timestamps can't make two
+/// peers order the same set differently.
 pub fn calculate_thread_hash(posts: &[PostView]) -> String {
+    let mut post_hashes: Vec<(&str, String)> = posts
+        .iter()
+        .map(|post| (post.id.as_str(), calculate_post_hash(post)))
+        .collect();
+    post_hashes.sort_by(|a, b| a.0.cmp(b.0));
+    post_hashes.dedup_by(|a, b| a.0 == b.0);
+
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(b"orbweaver-thread-hash-v2");
+    hasher.update(&(post_hashes.len() as u64).to_le_bytes());
+    for (_, post_hash) in post_hashes {
+        hasher.update(post_hash.as_bytes());
+    }
+
+    format!("{THREAD_HASH_PREFIX}{}", hasher.finalize().to_hex())
+}
+
+/// Thread hash as computed by nodes that predate `THREAD_HASH_VERSION` 2.
+///
+/// Still published in the `thread_hash` wire fields so older peers don't see a
+/// permanent mismatch and resync on every update.
+pub fn calculate_legacy_thread_hash(posts: &[PostView]) -> String {
     let mut hasher = blake3::Hasher::new();
 
     // Sort posts by created_at to ensure consistent ordering
@@ -568,9 +1474,46 @@ pub fn calculate_thread_hash(posts: &[PostView]) -> String {
     sorted_posts.sort_by(|a, b| a.created_at.cmp(&b.created_at));
 
     for post in sorted_posts {
-        let post_hash = calculate_post_hash(post);
-        hasher.update(post_hash.as_bytes());
+        let mut post_hasher = blake3::Hasher::new();
+        post_hasher.update(post.id.as_bytes());
+        post_hasher.update(post.body.as_bytes());
+        post_hasher.update(post.created_at.as_bytes());
+        if let Some(updated) = &post.updated_at {
+            post_hasher.update(updated.as_bytes());
+        }
+        hasher.update(post_hasher.finalize().to_hex().as_bytes());
     }
 
     hasher.finalize().to_hex().to_string()
 }
+
+/// True if `hash` was produced by `calculate_legacy_thread_hash`.
+pub fn is_legacy_thread_hash(hash: &str) -> bool {
+    !hash.starts_with(THREAD_HASH_PREFIX)
+}
+
+/// Compares a peer's thread hash against `posts` using the hash version the peer used.
+pub fn thread_hash_matches(posts: &[PostView], remote_hash: &str) -> bool {
+    if is_legacy_thread_hash(remote_hash) {
+        calculate_legacy_thread_hash(posts) == remote_hash
+    } else {
+        calculate_thread_hash(posts) == remote_hash
+    }
+}
+
+fn hash_field(hasher: &mut blake3::Hasher, bytes: &[u8]) {
+    hasher.update(&(bytes.len() as u64).to_le_bytes());
+    hasher.update(bytes);
+}
+
+fn hash_optional(hasher: &mut blake3::Hasher, value: Option<&str>) {
+    match value {
+        Some(value) => {
+            hasher.update(&[1]);
+            hash_field(hasher, value.as_bytes());
+        }
+        None => {
+            hasher.update(&[0]);
+        }
+    }
+}
@@ -0,0 +1,544 @@
+use crate::config::GraphchanPaths;
+use crate::database::models::{TopicMetadataRecord, TopicSettingsRecord};
+use crate::database::repositories::TopicRepository;
+use crate::database::Database;
+use crate::network::events::{MediaPolicy, SuggestedBlocklist, TopicMetadata};
+use crate::pow::MAX_TOPIC_DIFFICULTY;
+use crate::utils::now_utc_iso;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Longest description a founder may publish, in bytes.
+pub const MAX_DESCRIPTION_BYTES: usize = 4096;
+/// Most rules a topic may list.
+pub const MAX_RULES: usize = 32;
+/// Most blocklists a topic may suggest.
+pub const MAX_SUGGESTED_BLOCKLISTS: usize = 16;
+
+/// How loudly a client should surface new activity on a topic.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationLevel {
+    #[default]
+    All,
+    /// Only replies to our own posts
+    Replies,
+    Off,
+}
+
+impl NotificationLevel {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            NotificationLevel::All => "all",
+            NotificationLevel::Replies => "replies",
+            NotificationLevel::Off => "off",
+        }
+    }
+
+    /// Strict; errors with "unknown notification level".
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "all" => Ok(NotificationLevel::All),
+            "replies" => Ok(NotificationLevel::Replies),
+            "off" => Ok(NotificationLevel::Off),
+            other => bail!("unknown notification level {other}"),
+        }
+    }
+}
+
+/// Local, unpublished settings for one topic.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TopicSettings {
+    /// Fetch attachments of threads on this topic without asking
+    pub auto_download: bool,
+    /// Drop content from strangers instead of quarantining it
+    pub mute_strangers: bool,
+    pub notification_level: NotificationLevel,
+}
+
+impl Default for TopicSettings {
+    fn default() -> Self {
+        Self {
+            auto_download: true,
+            mute_strangers: false,
+            notification_level: NotificationLevel::All,
+        }
+    }
+}
+
+/// Partial update for `PATCH /topics/:id`; absent fields are left alone.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TopicSettingsUpdate {
+    pub auto_download: Option<bool>,
+    pub mute_strangers: Option<bool>,
+    pub notification_level: Option<NotificationLevel>,
+}
+
+/// What a founder fills in; the service adds identity, version and signature.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TopicMetadataDraft {
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub rules: Vec<String>,
+    #[serde(default)]
+    pub suggested_blocklists: Vec<SuggestedBlocklist>,
+    pub pow_difficulty: Option<u32>,
+    #[serde(default)]
+    pub media_policy: MediaPolicy,
+}
+
+/// Everything the API reports about a topic.
+#[derive(Debug, Clone, Serialize)]
+pub struct TopicView {
+    pub topic_id: String,
+    pub subscribed: bool,
+    pub metadata: Option<TopicMetadata>,
+    /// True when we published `metadata`, or nobody has yet
+    pub can_edit_metadata: bool,
+    pub settings: TopicSettings,
+    /// Proof-of-work bits demanded of non-friends, after overrides
+    pub pow_difficulty: u32,
+}
+
+/// Founder metadata and local settings for user topics.
+#[derive(Clone)]
+pub struct TopicConfigService {
+    database: Database,
+}
+
+impl TopicConfigService {
+    pub fn new(database: Database) -> Self {
+        Self { database }
+    }
+
+    pub fn view(&self, topic_id: &str, local_peer_id: &str) -> Result<TopicView> {
+        let (subscribed, settings) = self.database.with_repositories(|repos| {
+            let settings = repos.topics().settings(topic_id)?;
+            Ok((settings.is_some(), settings))
+        })?;
+        let metadata = self.metadata(topic_id)?;
+        let can_edit_metadata =
+            !matches!(&metadata, Some(metadata) if metadata.founder_peer_id != local_peer_id);
+        Ok(TopicView {
+            topic_id: topic_id.to_string(),
+            subscribed,
+            metadata,
+            can_edit_metadata,
+            settings: settings.map(settings_from_record).transpose()?.unwrap_or_default(),
+            pow_difficulty: crate::pow::PowService::new(self.database.clone()).topic_difficulty(topic_id)?,
+        })
+    }
+
+    /// Stored settings, or the defaults for topics we aren't subscribed to.
+    pub fn settings(&self, topic_id: &str) -> Result<TopicSettings> {
+        let record = self
+            .database
+            .with_repositories(|repos| repos.topics().settings(topic_id))?;
+        Ok(record.map(settings_from_record).transpose()?.unwrap_or_default())
+    }
+
+    /// Applies `update`; errors with "not subscribed to topic X".
+    pub fn update_settings(&self, topic_id: &str, update: TopicSettingsUpdate) -> Result<TopicSettings> {
+        let mut settings = self.settings(topic_id)?;
+        if let Some(auto_download) = update.auto_download {
+            settings.auto_download = auto_download;
+        }
+        if let Some(mute_strangers) = update.mute_strangers {
+            settings.mute_strangers = mute_strangers;
+        }
+        if let Some(level) = update.notification_level {
+            settings.notification_level = level;
+        }
+        let record = TopicSettingsRecord {
+            topic_id: topic_id.to_string(),
+            auto_download: settings.auto_download,
+            mute_strangers: settings.mute_strangers,
+            notification_level: settings.notification_level.as_str().to_string(),
+        };
+        let updated = self
+            .database
+            .with_repositories(|repos| repos.topics().update_settings(&record))?;
+        if !updated {
+            bail!("not subscribed to topic {topic_id}");
+        }
+        Ok(settings)
+    }
+
+    pub fn metadata(&self, topic_id: &str) -> Result<Option<TopicMetadata>> {
+        let record = self
+            .database
+            .with_repositories(|repos| repos.topics().metadata(topic_id))?;
+        record
+            .map(|record| serde_json::from_str(&record.record_json).context("stored topic metadata is corrupt"))
+            .transpose()
+    }
+
+    /// Version of the stored record, 0 when there is none.
+    pub fn metadata_version(&self, topic_id: &str) -> Result<u64> {
+        Ok(self.metadata(topic_id)?.map_or(0, |metadata| metadata.version))
+    }
+
+    /// Signs and stores a new version of a topic's metadata with our key.
+    ///
+    /// The caller broadcasts the result.
+    pub fn publish_metadata(
+        &self,
+        paths: &GraphchanPaths,
+        local_peer_id: &str,
+        topic_id: &str,
+        draft: TopicMetadataDraft,
+    ) -> Result<TopicMetadata> {
+        let mut metadata = self.next_metadata(local_peer_id, topic_id, draft)?;
+        metadata.signature = crate::crypto::sign_detached(paths, &metadata.signing_payload())?;
+        metadata.founder_public_key = Some(crate::crypto::load_public_key_armored(paths)?);
+        self.store_metadata(&metadata)?;
+        Ok(metadata)
+    }
+
+    /// Unsigned successor of the stored record. Errors with "not subscribed to
+    /// topic X", "topic X is maintained by Y" or a validation message.
+    fn next_metadata(&self, local_peer_id: &str, topic_id: &str, draft: TopicMetadataDraft) -> Result<TopicMetadata> {
+        let subscribed = self
+            .database
+            .with_repositories(|repos| repos.topics().is_subscribed(topic_id))?;
+        if !subscribed {
+            bail!("not subscribed to topic {topic_id}");
+        }
+        let current = self.metadata(topic_id)?;
+        if let Some(current) = &current {
+            if current.founder_peer_id != local_peer_id {
+                bail!("topic {topic_id} is maintained by {}", current.founder_peer_id);
+            }
+        }
+        let metadata = TopicMetadata {
+            topic_id: topic_id.to_string(),
+            founder_peer_id: local_peer_id.to_string(),
+            description: draft.description.trim().to_string(),
+            rules: draft
+                .rules
+                .into_iter()
+                .map(|rule| rule.trim().to_string())
+                .filter(|rule| !rule.is_empty())
+                .collect(),
+            suggested_blocklists: draft.suggested_blocklists,
+            pow_difficulty: draft.pow_difficulty,
+            media_policy: draft.media_policy,
+            version: current.map_or(0, |current| current.version) + 1,
+            updated_at: now_utc_iso(),
+            signature: String::new(),
+            founder_public_key: None,
+        };
+        validate(&metadata)?;
+        Ok(metadata)
+    }
+
+    /// Stores a gossiped record if it is valid and newer than ours; returns whether it was.
+    ///
+    /// The first founder seen for a topic is kept: later records must be signed
+    /// by the same peer. Records for topics we don't follow are ignored.
+    pub fn accept_metadata(&self, metadata: &TopicMetadata) -> Result<bool> {
+        let subscribed = self
+            .database
+            .with_repositories(|repos| repos.topics().is_subscribed(&metadata.topic_id))?;
+        if !subscribed {
+            return Ok(false);
+        }
+        validate(metadata)?;
+        let Some(public_key) = metadata.founder_public_key.as_deref() else {
+            bail!("topic metadata for {} carries no founder key", metadata.topic_id);
+        };
+        crate::crypto::verify_detached(
+            public_key,
+            &metadata.founder_peer_id,
+            &metadata.signing_payload(),
+            &metadata.signature,
+        )
+        .with_context(|| format!("invalid topic metadata signature for {}", metadata.topic_id))?;
+
+        if let Some(current) = self.metadata(&metadata.topic_id)? {
+            if current.founder_peer_id != metadata.founder_peer_id {
+                tracing::debug!(
+                    topic = %metadata.topic_id,
+                    founder = %current.founder_peer_id,
+                    claimant = %metadata.founder_peer_id,
+                    "ignoring topic metadata from someone other than the founder"
+                );
+                return Ok(false);
+            }
+            if metadata.version <= current.version {
+                return Ok(false);
+            }
+        }
+        self.store_metadata(metadata)?;
+        Ok(true)
+    }
+
+    fn store_metadata(&self, metadata: &TopicMetadata) -> Result<()> {
+        let record = TopicMetadataRecord {
+            topic_id: metadata.topic_id.clone(),
+            founder_peer_id: metadata.founder_peer_id.clone(),
+            version: i64::try_from(metadata.version).context("topic metadata version out of range")?,
+            record_json: serde_json::to_string(metadata)?,
+            received_at: now_utc_iso(),
+        };
+        self.database
+            .with_repositories(|repos| repos.topics().upsert_metadata(&record))
+    }
+
+    /// Whether any of `topics` drops content from strangers.
+    pub fn mutes_strangers(&self, topics: &[String]) -> Result<bool> {
+        for topic_id in topics {
+            if self.settings(topic_id)?.mute_strangers {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Same as `mutes_strangers`, over the topics a thread was announced on.
+    pub fn thread_mutes_strangers(&self, thread_id: &str) -> Result<bool> {
+        let topics = self
+            .database
+            .with_repositories(|repos| repos.topics().list_thread_topics(thread_id))?;
+        self.mutes_strangers(&topics)
+    }
+
+    /// Whether an attachment of type `mime` in `thread_id` may be fetched unasked:
+    /// every topic of the thread must allow auto-download and its media policy.
+    pub fn auto_download_allowed(&self, thread_id: &str, mime: Option<&str>) -> Result<bool> {
+        let topics = self
+            .database
+            .with_repositories(|repos| repos.topics().list_thread_topics(thread_id))?;
+        for topic_id in &topics {
+            if !self.settings(topic_id)?.auto_download {
+                return Ok(false);
+            }
+            if let Some(metadata) = self.metadata(topic_id)? {
+                if !metadata.media_policy.allows(mime) {
+                    return Ok(false);
+                }
+            }
+        }
+        Ok(true)
+    }
+}
+
+fn settings_from_record(record: TopicSettingsRecord) -> Result<TopicSettings> {
+    Ok(TopicSettings {
+        auto_download: record.auto_download,
+        mute_strangers: record.mute_strangers,
+        notification_level: NotificationLevel::parse(&record.notification_level)?,
+    })
+}
+
+fn validate(metadata: &TopicMetadata) -> Result<()> {
+    if metadata.description.len() > MAX_DESCRIPTION_BYTES {
+        bail!("description must be at most {MAX_DESCRIPTION_BYTES} bytes");
+    }
+    if metadata.rules.len() > MAX_RULES {
+        bail!("at most {MAX_RULES} rules are allowed");
+    }
+    if metadata.suggested_blocklists.len() > MAX_SUGGESTED_BLOCKLISTS {
+        bail!("at most {MAX_SUGGESTED_BLOCKLISTS} suggested blocklists are allowed");
+    }
+    if metadata.pow_difficulty.is_some_and(|difficulty| difficulty > MAX_TOPIC_DIFFICULTY) {
+        bail!("difficulty must be at most {MAX_TOPIC_DIFFICULTY}");
+    }
+    Ok(())
+}
+
+/// Limits how often we answer `TopicMetadataRequest`s for one topic, so a
+/// join doesn't make every subscriber re-broadcast the same record.
+///
+/// Owned by the ingest loop, so it needs no locking and resets on restart.
+#[derive(Default)]
+pub struct MetadataReplyThrottle {
+    last_reply: HashMap<String, Instant>,
+}
+
+const METADATA_REPLY_INTERVAL: Duration = Duration::from_secs(60);
+/// Topics tracked before stale entries are swept.
+const MAX_TRACKED_TOPICS: usize = 1024;
+
+impl MetadataReplyThrottle {
+    /// Records a reply for `topic_id`; false if we answered within the last minute.
+    pub fn allow(&mut self, topic_id: &str) -> bool {
+        self.allow_at(topic_id, Instant::now())
+    }
+
+    fn allow_at(&mut self, topic_id: &str, now: Instant) -> bool {
+        if self.last_reply.len() >= MAX_TRACKED_TOPICS {
+            self.last_reply
+                .retain(|_, replied| now.duration_since(*replied) < METADATA_REPLY_INTERVAL);
+        }
+        match self.last_reply.get(topic_id) {
+            Some(replied) if now.duration_since(*replied) < METADATA_REPLY_INTERVAL => false,
+            _ => {
+                self.last_reply.insert(topic_id.to_string(), now);
+                true
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::ThreadRecord;
+    use rusqlite::Connection;
+    use sequoia_openpgp::cert::{CertBuilder, CipherSuite};
+    use sequoia_openpgp::Cert;
+
+    fn setup() -> (Database, TopicConfigService) {
+        let database = Database::from_connection(Connection::open_in_memory().unwrap(), true);
+        database.ensure_migrations().unwrap();
+        database
+            .with_repositories(|repos| {
+                repos.topics().subscribe("cats")?;
+                repos.topics().subscribe("dogs")
+            })
+            .unwrap();
+        (database.clone(), TopicConfigService::new(database))
+    }
+
+    fn founder() -> Cert {
+        CertBuilder::new()
+            .set_cipher_suite(CipherSuite::Cv25519)
+            .add_signing_subkey()
+            .generate()
+            .unwrap()
+            .0
+    }
+
+    fn signed(service: &TopicConfigService, cert: &Cert, draft: TopicMetadataDraft) -> TopicMetadata {
+        let mut metadata = service
+            .next_metadata(&cert.fingerprint().to_hex(), "cats", draft)
+            .unwrap();
+        metadata.signature = crate::crypto::sign_detached_with_cert(cert, &metadata.signing_payload()).unwrap();
+        metadata.founder_public_key = Some(crate::crypto::armor_public_cert(cert).unwrap());
+        metadata
+    }
+
+    #[test]
+    fn metadata_is_pinned_to_the_first_founder() {
+        let (_, service) = setup();
+        let alice = founder();
+        let mallory = founder();
+        let draft = TopicMetadataDraft {
+            description: "Pictures of cats".into(),
+            rules: vec!["Cats only".into(), "  ".into()],
+            pow_difficulty: Some(12),
+            media_policy: MediaPolicy::ImagesOnly,
+            ..Default::default()
+        };
+
+        let first = signed(&service, &alice, draft.clone());
+        assert_eq!(first.version, 1);
+        assert_eq!(first.rules, vec!["Cats only".to_string()]);
+        assert!(service.accept_metadata(&first).unwrap());
+        assert!(!service.accept_metadata(&first).unwrap(), "same version is stale");
+
+        // Tampered copies don't verify
+        let mut tampered = first.clone();
+        tampered.version = 9;
+        tampered.description = "Pictures of dogs".into();
+        assert!(service.accept_metadata(&tampered).is_err());
+
+        // Someone else can't take the topic over, even with a valid signature of their own
+        let mallory_id = mallory.fingerprint().to_hex();
+        assert!(service
+            .next_metadata(&mallory_id, "cats", draft.clone())
+            .unwrap_err()
+            .to_string()
+            .contains("is maintained by"));
+        let mut hijack = first.clone();
+        hijack.founder_peer_id = mallory_id;
+        hijack.version = 2;
+        hijack.signature = crate::crypto::sign_detached_with_cert(&mallory, &hijack.signing_payload()).unwrap();
+        hijack.founder_public_key = Some(crate::crypto::armor_public_cert(&mallory).unwrap());
+        assert!(!service.accept_metadata(&hijack).unwrap());
+
+        let mut second = signed(&service, &alice, draft);
+        assert_eq!(second.version, 2);
+        second.pow_difficulty = Some(MAX_TOPIC_DIFFICULTY + 1);
+        second.signature = crate::crypto::sign_detached_with_cert(&alice, &second.signing_payload()).unwrap();
+        assert!(service.accept_metadata(&second).is_err(), "difficulty above the cap");
+        assert_eq!(service.metadata("cats").unwrap(), Some(first));
+    }
+
+    #[test]
+    fn settings_drive_muting_and_downloads() {
+        let (database, service) = setup();
+        database
+            .with_repositories(|repos| {
+                repos.threads().create(&ThreadRecord {
+                    id: "thread-1".into(),
+                    title: "t".into(),
+                    creator_peer_id: None,
+                    created_at: "2026-01-01T00:00:00Z".into(),
+                    pinned: false,
+                    thread_hash: None,
+                    visibility: "social".into(),
+                    topic_secret: None,
+                    sync_status: "downloaded".into(),
+                    source_url: None,
+                    source_platform: None,
+                    last_refreshed_at: None,
+                })?;
+                repos.topics().add_thread_topic("thread-1", "cats")?;
+                repos.topics().add_thread_topic("thread-1", "dogs")
+            })
+            .unwrap();
+
+        assert_eq!(service.settings("cats").unwrap(), TopicSettings::default());
+        assert!(service.update_settings("birds", TopicSettingsUpdate::default()).is_err());
+        assert!(service.auto_download_allowed("thread-1", Some("video/mp4")).unwrap());
+        assert!(!service.thread_mutes_strangers("thread-1").unwrap());
+
+        let settings = service
+            .update_settings(
+                "dogs",
+                TopicSettingsUpdate {
+                    mute_strangers: Some(true),
+                    notification_level: Some(NotificationLevel::Replies),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert!(settings.auto_download);
+        assert_eq!(service.settings("dogs").unwrap().notification_level, NotificationLevel::Replies);
+        assert!(service.thread_mutes_strangers("thread-1").unwrap());
+        assert!(!service.mutes_strangers(&["cats".to_string()]).unwrap());
+
+        // The founder's media policy applies alongside our own switch
+        let alice = founder();
+        let metadata = signed(
+            &service,
+            &alice,
+            TopicMetadataDraft {
+                media_policy: MediaPolicy::ImagesOnly,
+                ..Default::default()
+            },
+        );
+        assert!(service.accept_metadata(&metadata).unwrap());
+        assert!(service.auto_download_allowed("thread-1", Some("image/png")).unwrap());
+        assert!(!service.auto_download_allowed("thread-1", Some("video/mp4")).unwrap());
+        service
+            .update_settings("dogs", TopicSettingsUpdate { auto_download: Some(false), ..Default::default() })
+            .unwrap();
+        assert!(!service.auto_download_allowed("thread-1", Some("image/png")).unwrap());
+    }
+
+    #[test]
+    fn metadata_replies_are_throttled_per_topic() {
+        let mut throttle = MetadataReplyThrottle::default();
+        let start = Instant::now();
+        assert!(throttle.allow_at("cats", start));
+        assert!(!throttle.allow_at("cats", start + Duration::from_secs(10)));
+        assert!(throttle.allow_at("dogs", start + Duration::from_secs(10)));
+        assert!(throttle.allow_at("cats", start + METADATA_REPLY_INTERVAL));
+    }
+}
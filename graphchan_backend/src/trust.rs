@@ -0,0 +1,486 @@
+use crate::database::models::QuarantinedPostRecord;
+use crate::database::repositories::{
+    BlockedPeerRepository, BlocklistRepository, PeerRepository, PostRepository, QuarantineRepository,
+    SqliteRepositories, ThreadRepository,
+};
+use crate::database::Database;
+use crate::threading::{PostView, ThreadDetails};
+use crate::utils::now_utc_iso;
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Quarantine status of a post nobody has reviewed yet.
+pub const QUARANTINE_PENDING: &str = "pending";
+/// Quarantine status of a post the user turned down; it stays hidden.
+pub const QUARANTINE_REJECTED: &str = "rejected";
+
+/// How much we trust a peer, stored in `peers.trust_state`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrustLevel {
+    /// Added by friendcode
+    Friend,
+    /// Vouched for by the user without being a friend
+    Known,
+    /// Everyone else, e.g. authors met on a public topic
+    Stranger,
+    /// Content is dropped; blocked peers count as muted too
+    Muted,
+}
+
+impl TrustLevel {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TrustLevel::Friend => "friend",
+            TrustLevel::Known => "known",
+            TrustLevel::Stranger => "stranger",
+            TrustLevel::Muted => "muted",
+        }
+    }
+
+    /// Reads a stored `trust_state`; legacy "trusted" is a friend and anything unknown a stranger.
+    pub fn from_state(state: &str) -> Self {
+        match state {
+            "friend" | "trusted" => TrustLevel::Friend,
+            "known" => TrustLevel::Known,
+            "muted" => TrustLevel::Muted,
+            _ => TrustLevel::Stranger,
+        }
+    }
+
+    /// Strict parse for user input.
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "friend" => Ok(TrustLevel::Friend),
+            "known" => Ok(TrustLevel::Known),
+            "stranger" => Ok(TrustLevel::Stranger),
+            "muted" => Ok(TrustLevel::Muted),
+            other => bail!("unknown trust level: {other}"),
+        }
+    }
+
+    pub fn policy(self) -> IngestPolicy {
+        match self {
+            TrustLevel::Friend => IngestPolicy {
+                accept_content: true,
+                quarantine_posts: false,
+                auto_download: true,
+                announcements_per_hour: None,
+                require_pow: false,
+            },
+            TrustLevel::Known => IngestPolicy {
+                accept_content: true,
+                quarantine_posts: false,
+                auto_download: true,
+                announcements_per_hour: None,
+                require_pow: true,
+            },
+            TrustLevel::Stranger => IngestPolicy {
+                accept_content: true,
+                quarantine_posts: true,
+                auto_download: false,
+                announcements_per_hour: Some(STRANGER_ANNOUNCEMENTS_PER_HOUR),
+                require_pow: true,
+            },
+            TrustLevel::Muted => IngestPolicy {
+                accept_content: false,
+                quarantine_posts: false,
+                auto_download: false,
+                announcements_per_hour: Some(0),
+                require_pow: true,
+            },
+        }
+    }
+}
+
+/// Thread announcements a stranger may get stored per hour.
+const STRANGER_ANNOUNCEMENTS_PER_HOUR: u32 = 10;
+
+/// What ingest does with content authored by a peer of a given trust level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IngestPolicy {
+    /// Store (and relay) their threads, posts and reactions at all
+    pub accept_content: bool,
+    /// Hold their new posts for review, however they arrive; they are not relayed either
+    pub quarantine_posts: bool,
+    /// Fetch their attachments without being asked
+    pub auto_download: bool,
+    /// Thread announcements stored per hour; `None` is unlimited
+    pub announcements_per_hour: Option<u32>,
+    /// Drop their new threads and posts on topics with a difficulty unless stamped (see `pow.rs`)
+    pub require_pow: bool,
+}
+
+#[derive(Clone)]
+pub struct TrustService {
+    database: Database,
+}
+
+impl TrustService {
+    pub fn new(database: Database) -> Self {
+        Self { database }
+    }
+
+    /// Effective trust of `peer_id`: blocked peers are muted, unknown ones strangers.
+    pub fn level(&self, peer_id: &str) -> Result<TrustLevel> {
+        self.database.with_repositories(|repos| trust_level(&repos, peer_id))
+    }
+
+    /// Sets a peer's trust. Becoming a friend or known releases their quarantined posts;
+    /// returns how many were released, or `None` if we don't know the peer.
+    pub fn set_level(&self, peer_id: &str, level: TrustLevel) -> Result<Option<usize>> {
+        self.database.with_repositories(|repos| {
+            if !repos.peers().set_trust_state(peer_id, level.as_str())? {
+                return Ok(None);
+            }
+            let released = match level {
+                TrustLevel::Friend | TrustLevel::Known => repos.quarantine().release_author(peer_id)?,
+                TrustLevel::Stranger | TrustLevel::Muted => 0,
+            };
+            Ok(Some(released))
+        })
+    }
+
+    /// Holds a post for review. Returns false if it already had a quarantine row.
+    pub fn quarantine_post(&self, post_id: &str, thread_id: &str, author_peer_id: Option<&str>) -> Result<bool> {
+        self.database.with_repositories(|repos| {
+            repos.quarantine().add(&quarantine_record(post_id, thread_id, author_peer_id))
+        })
+    }
+
+    /// The review queue, oldest first.
+    pub fn list_quarantined(&self) -> Result<Vec<QuarantinedPostView>> {
+        self.database.with_repositories(|repos| {
+            let mut views = Vec::new();
+            for record in repos.quarantine().list(Some(QUARANTINE_PENDING))? {
+                let Some(post) = repos.posts().get(&record.post_id)? else {
+                    continue;
+                };
+                let thread_title = repos.threads().get(&record.thread_id)?.map(|thread| thread.title);
+                let author = match &record.author_peer_id {
+                    Some(peer_id) => repos.peers().get(peer_id)?,
+                    None => None,
+                };
+                views.push(QuarantinedPostView {
+                    post_id: record.post_id,
+                    thread_id: record.thread_id,
+                    thread_title,
+                    author_peer_id: record.author_peer_id,
+                    author_username: author.and_then(|peer| peer.username),
+                    body: post.body,
+                    quarantined_at: record.quarantined_at,
+                });
+            }
+            Ok(views)
+        })
+    }
+
+    /// Lets a quarantined post show normally.
+    pub fn approve(&self, post_id: &str) -> Result<()> {
+        self.database.with_repositories(|repos| {
+            if !repos.quarantine().release(post_id)? {
+                return Err(anyhow!("post not in quarantine: {post_id}"));
+            }
+            Ok(())
+        })
+    }
+
+    /// Keeps a quarantined post hidden for good.
+    pub fn reject(&self, post_id: &str) -> Result<()> {
+        self.database.with_repositories(|repos| {
+            if !repos.quarantine().set_status(post_id, QUARANTINE_REJECTED)? {
+                return Err(anyhow!("post not in quarantine: {post_id}"));
+            }
+            Ok(())
+        })
+    }
+
+    /// Applies quarantine to a thread shown to the user: pending posts are
+    /// flagged, rejected ones removed.
+    pub fn apply_to_thread(&self, details: &mut ThreadDetails) -> Result<()> {
+        let statuses: HashMap<String, String> = self.database.with_repositories(|repos| {
+            Ok(repos
+                .quarantine()
+                .list_for_thread(&details.thread.id)?
+                .into_iter()
+                .map(|record| (record.post_id, record.status))
+                .collect())
+        })?;
+        if statuses.is_empty() {
+            return Ok(());
+        }
+        details
+            .posts
+            .retain(|post| statuses.get(&post.id).map(String::as_str) != Some(QUARANTINE_REJECTED));
+        for post in &mut details.posts {
+            post.quarantined = statuses.contains_key(&post.id);
+        }
+        Ok(())
+    }
+
+    /// `apply_to_thread` for posts gathered from anywhere.
+    pub fn apply_to_posts(&self, posts: &mut Vec<PostView>) -> Result<()> {
+        let statuses: HashMap<String, String> = self.database.with_repositories(|repos| {
+            let quarantine = repos.quarantine();
+            let mut statuses = HashMap::new();
+            for post in posts.iter() {
+                if let Some(record) = quarantine.get(&post.id)? {
+                    statuses.insert(record.post_id, record.status);
+                }
+            }
+            Ok(statuses)
+        })?;
+        posts.retain(|post| statuses.get(&post.id).map(String::as_str) != Some(QUARANTINE_REJECTED));
+        for post in posts.iter_mut() {
+            post.quarantined = statuses.contains_key(&post.id);
+        }
+        Ok(())
+    }
+
+    /// Whether a post is held back from the user (pending or rejected).
+    pub fn is_quarantined(&self, post_id: &str) -> Result<bool> {
+        self.database
+            .with_repositories(|repos| Ok(repos.quarantine().get(post_id)?.is_some()))
+    }
+}
+
+/// A fresh pending quarantine row (ingest adds it while already holding the repositories).
+/// `TrustService::level` on a connection that is already open.
+pub(crate) fn trust_level(repos: &SqliteRepositories<'_>, peer_id: &str) -> Result<TrustLevel> {
+    if repos.blocked_peers().is_blocked(peer_id)? || repos.blocklists().is_in_any_blocklist(peer_id)? {
+        return Ok(TrustLevel::Muted);
+    }
+    Ok(repos
+        .peers()
+        .get(peer_id)?
+        .map(|peer| TrustLevel::from_state(&peer.trust_state))
+        .unwrap_or(TrustLevel::Stranger))
+}
+
+/// Trust of a received post's author.
+///
+/// `author_peer_id` is only a claim until the post's signature verifies: an
+/// unverified post counts as a stranger's, unless the claimed author is muted,
+/// since claiming an author must never earn more trust than posting anonymously.
+pub(crate) fn post_author_level(
+    repos: &SqliteRepositories<'_>,
+    author_peer_id: Option<&str>,
+    signature_verified: bool,
+) -> Result<TrustLevel> {
+    let Some(author_peer_id) = author_peer_id else {
+        return Ok(TrustLevel::Stranger);
+    };
+    let claimed = trust_level(repos, author_peer_id)?;
+    if signature_verified || claimed == TrustLevel::Muted {
+        Ok(claimed)
+    } else {
+        Ok(TrustLevel::Stranger)
+    }
+}
+
+pub(crate) fn quarantine_record(post_id: &str, thread_id: &str, author_peer_id: Option<&str>) -> QuarantinedPostRecord {
+    QuarantinedPostRecord {
+        post_id: post_id.to_string(),
+        thread_id: thread_id.to_string(),
+        author_peer_id: author_peer_id.map(str::to_string),
+        status: QUARANTINE_PENDING.to_string(),
+        quarantined_at: now_utc_iso(),
+    }
+}
+
+/// A quarantined post in the review queue.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuarantinedPostView {
+    pub post_id: String,
+    pub thread_id: String,
+    pub thread_title: Option<String>,
+    pub author_peer_id: Option<String>,
+    pub author_username: Option<String>,
+    pub body: String,
+    pub quarantined_at: String,
+}
+
+/// Per-creator hourly windows enforcing `IngestPolicy::announcements_per_hour`.
+///
+/// Owned by the ingest loop, so it needs no locking and resets on restart.
+#[derive(Default)]
+pub struct AnnouncementBudget {
+    windows: HashMap<String, (Instant, u32)>,
+}
+
+const ANNOUNCEMENT_WINDOW: Duration = Duration::from_secs(60 * 60);
+/// Creators tracked before expired windows are swept.
+const MAX_TRACKED_CREATORS: usize = 4096;
+
+impl AnnouncementBudget {
+    /// Counts an announcement by `creator`; false once its hourly allowance is spent.
+    pub fn allow(&mut self, creator: &str, policy: &IngestPolicy) -> bool {
+        self.allow_at(creator, policy, Instant::now())
+    }
+
+    fn allow_at(&mut self, creator: &str, policy: &IngestPolicy, now: Instant) -> bool {
+        let Some(limit) = policy.announcements_per_hour else {
+            return true;
+        };
+        if self.windows.len() >= MAX_TRACKED_CREATORS {
+            self.windows
+                .retain(|_, (started, _)| now.duration_since(*started) < ANNOUNCEMENT_WINDOW);
+        }
+        let (started, count) = self.windows.entry(creator.to_string()).or_insert((now, 0));
+        if now.duration_since(*started) >= ANNOUNCEMENT_WINDOW {
+            *started = now;
+            *count = 0;
+        }
+        if *count >= limit {
+            return false;
+        }
+        *count += 1;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::{BlockedPeerRecord, PeerRecord, PostRecord, ThreadRecord};
+    use crate::threading::ThreadService;
+    use rusqlite::Connection;
+
+    fn setup() -> (Database, TrustService) {
+        let database = Database::from_connection(Connection::open_in_memory().unwrap(), true);
+        database.ensure_migrations().unwrap();
+        (database.clone(), TrustService::new(database))
+    }
+
+    fn peer(id: &str, trust_state: &str) -> PeerRecord {
+        PeerRecord {
+            id: id.into(),
+            alias: None,
+            username: Some(id.to_lowercase()),
+            bio: None,
+            friendcode: None,
+            iroh_peer_id: None,
+            gpg_fingerprint: Some(id.into()),
+            x25519_pubkey: None,
+            last_seen: None,
+            avatar_file_id: None,
+            trust_state: trust_state.into(),
+            agents: None,
+            gpg_public_key: None,
+        }
+    }
+
+    fn seed_thread(database: &Database, posts: &[(&str, &str)]) {
+        database
+            .with_repositories(|repos| {
+                repos.threads().create(&ThreadRecord {
+                    id: "thread-1".into(),
+                    title: "Topic thread".into(),
+                    creator_peer_id: None,
+                    created_at: now_utc_iso(),
+                    pinned: false,
+                    thread_hash: None,
+                    visibility: "social".into(),
+                    topic_secret: None,
+                    sync_status: "downloaded".into(),
+                    source_url: None,
+                    source_platform: None,
+                    last_refreshed_at: None,
+                })?;
+                for (id, author) in posts {
+                    repos.posts().create(&PostRecord {
+                        id: id.to_string(),
+                        thread_id: "thread-1".into(),
+                        author_peer_id: Some(author.to_string()),
+                        author_friendcode: None,
+                        body: format!("body of {id}"),
+                        created_at: now_utc_iso(),
+                        updated_at: None,
+                        metadata: None,
+                        signature: None,
+                        signature_status: None,
+                    })?;
+                }
+                Ok(())
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn levels_come_from_trust_state_and_blocks() {
+        let (database, trust) = setup();
+        database
+            .with_repositories(|repos| {
+                repos.peers().upsert(&peer("FRIEND", "trusted"))?;
+                repos.peers().upsert(&peer("KNOWN", "known"))?;
+                repos.peers().upsert(&peer("BLOCKED", "friend"))?;
+                repos.blocked_peers().block(&BlockedPeerRecord {
+                    peer_id: "BLOCKED".into(),
+                    reason: None,
+                    blocked_at: now_utc_iso(),
+                })
+            })
+            .unwrap();
+
+        assert_eq!(trust.level("FRIEND").unwrap(), TrustLevel::Friend);
+        assert_eq!(trust.level("KNOWN").unwrap(), TrustLevel::Known);
+        assert_eq!(trust.level("NOBODY").unwrap(), TrustLevel::Stranger);
+        assert_eq!(trust.level("BLOCKED").unwrap(), TrustLevel::Muted);
+        assert!(!TrustLevel::Stranger.policy().auto_download);
+        assert!(!TrustLevel::Muted.policy().accept_content);
+        assert!(!TrustLevel::Friend.policy().require_pow);
+        assert!(TrustLevel::Known.policy().require_pow);
+    }
+
+    #[test]
+    fn upsert_keeps_trust_and_set_level_releases_quarantine() {
+        let (database, trust) = setup();
+        database
+            .with_repositories(|repos| repos.peers().upsert(&peer("STRANGER", "stranger")))
+            .unwrap();
+        seed_thread(&database, &[("post-1", "STRANGER"), ("post-2", "STRANGER")]);
+        assert!(trust.quarantine_post("post-1", "thread-1", Some("STRANGER")).unwrap());
+        assert!(trust.quarantine_post("post-2", "thread-1", Some("STRANGER")).unwrap());
+        assert!(!trust.quarantine_post("post-1", "thread-1", Some("STRANGER")).unwrap());
+        trust.reject("post-2").unwrap();
+
+        // A snapshot claiming the peer is our friend must not change anything
+        database
+            .with_repositories(|repos| repos.peers().upsert(&peer("STRANGER", "friend")))
+            .unwrap();
+        assert_eq!(trust.level("STRANGER").unwrap(), TrustLevel::Stranger);
+
+        let queue = trust.list_quarantined().unwrap();
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue[0].post_id, "post-1");
+        assert_eq!(queue[0].thread_title.as_deref(), Some("Topic thread"));
+
+        let mut details = ThreadService::new(database.clone()).get_thread("thread-1").unwrap().unwrap();
+        trust.apply_to_thread(&mut details).unwrap();
+        assert_eq!(details.posts.len(), 1);
+        assert!(details.posts[0].quarantined);
+
+        assert_eq!(trust.set_level("STRANGER", TrustLevel::Known).unwrap(), Some(1));
+        assert!(!trust.is_quarantined("post-1").unwrap());
+        // Rejections stand
+        assert!(trust.is_quarantined("post-2").unwrap());
+        assert_eq!(trust.set_level("NOBODY", TrustLevel::Known).unwrap(), None);
+    }
+
+    #[test]
+    fn stranger_announcements_are_capped_per_hour() {
+        let mut budget = AnnouncementBudget::default();
+        let start = Instant::now();
+        let stranger = TrustLevel::Stranger.policy();
+        for _ in 0..STRANGER_ANNOUNCEMENTS_PER_HOUR {
+            assert!(budget.allow_at("CREATOR", &stranger, start));
+        }
+        assert!(!budget.allow_at("CREATOR", &stranger, start));
+        assert!(budget.allow_at("OTHER", &stranger, start));
+        assert!(budget.allow_at("CREATOR", &stranger, start + ANNOUNCEMENT_WINDOW));
+        assert!(!budget.allow_at("MUTED", &TrustLevel::Muted.policy(), start));
+        assert!(budget.allow_at("FRIEND", &TrustLevel::Friend.policy(), start));
+    }
+}
@@ -1,3 +1,4 @@
+use graphchan_backend::api_tokens::ApiTokenStore;
 use graphchan_backend::bootstrap;
 use graphchan_backend::config::{GraphchanConfig, GraphchanPaths, NetworkConfig};
 use graphchan_backend::identity::{decode_friendcode, FriendCodePayload};
@@ -38,8 +39,7 @@ fn next_port() -> u16 {
         .port()
 }
 
-async fn wait_for_health(base_url: &str) {
-    let client = reqwest::Client::new();
+async fn wait_for_health(client: &reqwest::Client, base_url: &str) {
     for _ in 0..50 {
         if let Ok(resp) = client.get(format!("{base_url}/health")).send().await {
             if resp.status().is_success() {
@@ -68,6 +68,10 @@ async fn rest_roundtrip_with_file_upload() {
     let blob_store = FsStore::load(&config.paths.blobs_dir)
         .await
         .expect("blob store");
+    let token = ApiTokenStore::open(&config.paths)
+        .expect("token store")
+        .ensure_owner_token()
+        .expect("owner token");
 
     let network = NetworkHandle::start(
         &config.paths,
@@ -95,9 +99,23 @@ async fn rest_roundtrip_with_file_upload() {
     });
 
     let base_url = format!("http://127.0.0.1:{port}");
-    wait_for_health(&base_url).await;
-
-    let client = reqwest::Client::new();
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert(
+        reqwest::header::AUTHORIZATION,
+        format!("Bearer {token}").parse().expect("auth header"),
+    );
+    let client = reqwest::Client::builder()
+        .default_headers(headers)
+        .build()
+        .expect("client");
+    wait_for_health(&client, &base_url).await;
+
+    let anonymous = reqwest::Client::new()
+        .get(format!("{base_url}/threads"))
+        .send()
+        .await
+        .expect("anonymous response");
+    assert_eq!(anonymous.status(), reqwest::StatusCode::UNAUTHORIZED);
 
     let thread_resp: serde_json::Value = client
         .post(format!("{}/threads", base_url))
@@ -214,7 +232,9 @@ fn advertised_addresses(addr: &iroh_base::EndpointAddr) -> Vec<String> {
 async fn spawn_node(port: u16) -> TestNode {
     let dir = tempdir().expect("tempdir");
     let paths = GraphchanPaths::from_base_dir(dir.path()).expect("paths");
-    let config = GraphchanConfig::new(port, paths.clone(), NetworkConfig::default());
+    let mut config = GraphchanConfig::new(port, paths.clone(), NetworkConfig::default());
+    // Gossip is under test here, not API auth
+    config.api.require_auth = false;
     let bootstrap = bootstrap::initialize(&config).await.expect("bootstrap");
     let identity = bootstrap.identity.clone();
     let database = bootstrap.database.clone();
@@ -247,7 +267,7 @@ async fn spawn_node(port: u16) -> TestNode {
     });
 
     let base_url = format!("http://127.0.0.1:{port}");
-    wait_for_health(&base_url).await;
+    wait_for_health(&reqwest::Client::new(), &base_url).await;
     wait_for_addresses(&network).await;
 
     TestNode {
@@ -1,5 +1,6 @@
 use anyhow::{anyhow, Result};
 use graphchan_backend::api;
+use graphchan_backend::api_tokens::ApiTokenStore;
 use graphchan_backend::config::GraphchanConfig;
 use graphchan_backend::node::GraphchanNode;
 use graphchan_backend::telemetry;
@@ -17,6 +18,12 @@ fn main() -> Result<()> {
     let snapshot = node.snapshot();
     drop(node);
 
+    // Provision the owner token before the server starts so both agree on it
+    if snapshot.config.api.require_auth {
+        let token = ApiTokenStore::open(&snapshot.config.paths)?.ensure_owner_token()?;
+        std::env::set_var("GRAPHCHAN_API_TOKEN", token);
+    }
+
     let server_snapshot = snapshot.clone();
     let server = runtime.spawn(async move {
         if let Err(err) = api::serve_http(
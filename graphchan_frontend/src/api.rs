@@ -3,13 +3,15 @@ use std::time::Duration;
 
 use anyhow::{Context, Result};
 use reqwest::blocking::Client;
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
 use reqwest::Url;
 
 use crate::models::{
     AddPeerRequest, BlockedPeerView, BlocklistEntryView, BlocklistSubscriptionView,
-    BlockPeerRequest, ConversationView, CreatePostInput, CreateThreadInput, DirectMessageView,
-    FileResponse, PeerView, PostResponse, PostView, ReactionsResponse, SearchResponse, SendDmRequest,
-    SubscribeBlocklistRequest, ThreadDetails, ThreadSummary, UnreadCountResponse,
+    BlockPeerRequest, ConversationView, CreateGroupRequest, CreatePostInput, CreateThreadInput,
+    DirectMessageView, DmAttachmentView, DmGroupView, FileResponse, PeerView, PostResponse, PostView, ReactionsResponse,
+    SearchResponse, SendDmRequest, SendGroupDmRequest, SubscribeBlocklistRequest, ThreadDetails,
+    ThreadSummary, TopicMetadataDraft, TopicSettingsUpdate, TopicView, UnreadCountResponse,
 };
 
 static SHARED_CLIENT: OnceLock<Client> = OnceLock::new();
@@ -23,6 +25,7 @@ pub fn get_shared_client() -> Result<&'static Client> {
     let client = Client::builder()
         .timeout(Duration::from_secs(30))
         .user_agent("GraphchanFrontend/0.1")
+        .default_headers(auth_headers()?)
         .build()
         .context("failed to build HTTP client")?;
 
@@ -39,6 +42,7 @@ pub fn get_upload_client() -> Result<&'static Client> {
     let client = Client::builder()
         .timeout(Duration::from_secs(3600)) // 1 hour for large file uploads
         .user_agent("GraphchanFrontend/0.1")
+        .default_headers(auth_headers()?)
         .build()
         .context("failed to build upload HTTP client")?;
 
@@ -46,6 +50,21 @@ pub fn get_upload_client() -> Result<&'static Client> {
     Ok(UPLOAD_CLIENT.get().unwrap())
 }
 
+/// `Authorization: Bearer` from `GRAPHCHAN_API_TOKEN` (set by the desktop build), if any.
+fn auth_headers() -> Result<HeaderMap> {
+    let mut headers = HeaderMap::new();
+    if let Ok(token) = std::env::var("GRAPHCHAN_API_TOKEN") {
+        let token = token.trim();
+        if !token.is_empty() {
+            let mut value =
+                HeaderValue::from_str(&format!("Bearer {token}")).context("invalid GRAPHCHAN_API_TOKEN")?;
+            value.set_sensitive(true);
+            headers.insert(AUTHORIZATION, value);
+        }
+    }
+    Ok(headers)
+}
+
 #[derive(Clone)]
 pub struct ApiClient {
     base_url: String,
@@ -300,11 +319,19 @@ impl ApiClient {
         Ok(response.json()?)
     }
 
-    pub fn send_dm(&self, to_peer_id: &str, body: &str) -> Result<DirectMessageView> {
+    pub fn send_dm(
+        &self,
+        to_peer_id: &str,
+        body: &str,
+        reply_to: Option<&str>,
+        attachments: Vec<String>,
+    ) -> Result<DirectMessageView> {
         let url = self.url("/dms/send")?;
         let request = SendDmRequest {
             to_peer_id: to_peer_id.to_string(),
             body: body.to_string(),
+            reply_to: reply_to.map(str::to_string),
+            attachments,
         };
         let response = self.client.post(url).json(&request).send()?.error_for_status()?;
         Ok(response.json()?)
@@ -328,6 +355,74 @@ impl ApiClient {
         Ok(response.json()?)
     }
 
+    pub fn list_dm_groups(&self) -> Result<Vec<DmGroupView>> {
+        let url = self.url("/dms/groups")?;
+        let response = self.client.get(url).send()?.error_for_status()?;
+        Ok(response.json()?)
+    }
+
+    pub fn create_dm_group(&self, name: &str, members: Vec<String>) -> Result<DmGroupView> {
+        let url = self.url("/dms/groups")?;
+        let request = CreateGroupRequest {
+            name: name.to_string(),
+            members,
+        };
+        let response = self.client.post(url).json(&request).send()?.error_for_status()?;
+        Ok(response.json()?)
+    }
+
+    pub fn get_group_messages(&self, group_id: &str, limit: usize) -> Result<Vec<DirectMessageView>> {
+        let url = format!("{}/dms/groups/{}/messages?limit={}", self.base_url(), group_id, limit);
+        let response = self.client.get(&url).send()?.error_for_status()?;
+        Ok(response.json()?)
+    }
+
+    pub fn send_group_dm(
+        &self,
+        group_id: &str,
+        body: &str,
+        reply_to: Option<&str>,
+        attachments: Vec<String>,
+    ) -> Result<DirectMessageView> {
+        let url = format!("{}/dms/groups/{}/messages", self.base_url(), group_id);
+        let request = SendGroupDmRequest {
+            body: body.to_string(),
+            reply_to: reply_to.map(str::to_string),
+            attachments,
+        };
+        let response = self.client.post(&url).json(&request).send()?.error_for_status()?;
+        Ok(response.json()?)
+    }
+
+    pub fn upload_dm_attachment(&self, path: &std::path::Path) -> Result<DmAttachmentView> {
+        let url = self.url("/dms/attachments")?;
+        let form = reqwest::blocking::multipart::Form::new()
+            .file("file", path)?;
+
+        let response = self.upload_client().post(url)
+            .multipart(form)
+            .send()?
+            .error_for_status()?;
+
+        Ok(response.json()?)
+    }
+
+    pub fn dm_attachment_url(&self, attachment_id: &str) -> String {
+        format!("{}/dms/attachments/{}", self.base_url, attachment_id)
+    }
+
+    pub fn trigger_dm_attachment_download(&self, attachment_id: &str) -> Result<()> {
+        let url = self.url(&format!("/dms/attachments/{}/download", attachment_id))?;
+        self.client.post(url).send()?.error_for_status()?;
+        Ok(())
+    }
+
+    pub fn leave_dm_group(&self, group_id: &str) -> Result<()> {
+        let url = format!("{}/dms/groups/{}/leave", self.base_url(), group_id);
+        self.client.post(&url).send()?.error_for_status()?;
+        Ok(())
+    }
+
     // Blocking and Moderation methods
 
     pub fn list_blocked_peers(&self) -> Result<Vec<BlockedPeerView>> {
@@ -470,6 +565,24 @@ impl ApiClient {
         Ok(())
     }
 
+    pub fn get_topic(&self, topic_id: &str) -> Result<TopicView> {
+        let url = self.url(&format!("/topics/{}", topic_id))?;
+        let response = self.client.get(url).send()?.error_for_status()?;
+        Ok(response.json()?)
+    }
+
+    pub fn update_topic_settings(&self, topic_id: &str, update: &TopicSettingsUpdate) -> Result<TopicView> {
+        let url = self.url(&format!("/topics/{}", topic_id))?;
+        let response = self.client.patch(url).json(update).send()?.error_for_status()?;
+        Ok(response.json()?)
+    }
+
+    pub fn publish_topic_metadata(&self, topic_id: &str, draft: &TopicMetadataDraft) -> Result<TopicView> {
+        let url = self.url(&format!("/topics/{}/metadata", topic_id))?;
+        let response = self.client.put(url).json(draft).send()?.error_for_status()?;
+        Ok(response.json()?)
+    }
+
     pub fn get_theme_color(&self) -> Result<(u8, u8, u8)> {
         #[derive(serde::Deserialize)]
         struct ThemeColorResponse {
@@ -2,7 +2,8 @@ use eframe::egui;
 use log::{error, info};
 
 use crate::models::{
-    ConversationView, DirectMessageView, PeerView, ReactionsResponse, SearchResponse,
+    ConversationView, DirectMessageView, DmGroupView, PeerView, ReactionsResponse, SearchResponse,
+    TopicView,
 };
 
 use super::state::{ThreadState, ViewState};
@@ -224,6 +225,8 @@ impl GraphchanApp {
                     Ok(message) => {
                         state.messages.push(message);
                         state.new_message_body.clear();
+                        state.reply_to = None;
+                        state.draft_attachments.clear();
                         state.send_error = None;
                         // Reload conversations to update unread counts
                         self.spawn_load_conversations();
@@ -237,6 +240,93 @@ impl GraphchanApp {
         }
     }
 
+    pub(super) fn handle_dm_groups_loaded(&mut self, result: Result<Vec<DmGroupView>, anyhow::Error>) {
+        match result {
+            Ok(groups) => self.dm_state.groups = groups,
+            Err(err) => {
+                error!("Failed to load group conversations: {}", err);
+                self.dm_state.group_error = Some(err.to_string());
+            }
+        }
+    }
+
+    pub(super) fn handle_dm_group_created(&mut self, result: Result<DmGroupView, anyhow::Error>) {
+        self.dm_state.creating_group = false;
+        match result {
+            Ok(group) => {
+                self.dm_state.show_new_group_picker = false;
+                self.dm_state.new_group_name.clear();
+                self.dm_state.new_group_members.clear();
+                self.dm_state.group_error = None;
+                self.spawn_load_conversations();
+                super::ui::conversations::open_group_conversation(self, group);
+            }
+            Err(err) => {
+                error!("Failed to create group: {}", err);
+                self.dm_state.group_error = Some(err.to_string());
+            }
+        }
+    }
+
+    pub(super) fn handle_group_messages_loaded(&mut self, group_id: String, result: Result<Vec<DirectMessageView>, anyhow::Error>) {
+        if let ViewState::Conversation(ref mut state) = self.view {
+            if state.group.as_ref().is_some_and(|group| group.id == group_id) {
+                state.messages_loading = false;
+                match result {
+                    Ok(messages) => {
+                        state.messages = messages;
+                        state.messages_error = None;
+                    }
+                    Err(err) => {
+                        error!("Failed to load messages for group {}: {}", group_id, err);
+                        state.messages_error = Some(err.to_string());
+                    }
+                }
+            }
+        }
+    }
+
+    pub(super) fn handle_group_dm_sent(&mut self, group_id: String, result: Result<DirectMessageView, anyhow::Error>) {
+        if let ViewState::Conversation(ref mut state) = self.view {
+            if state.group.as_ref().is_some_and(|group| group.id == group_id) {
+                state.sending = false;
+                match result {
+                    Ok(message) => {
+                        state.messages.push(message);
+                        state.new_message_body.clear();
+                        state.reply_to = None;
+                        state.draft_attachments.clear();
+                        state.send_error = None;
+                        self.spawn_load_conversations();
+                    }
+                    Err(err) => {
+                        error!("Failed to send to group {}: {}", group_id, err);
+                        state.send_error = Some(err.to_string());
+                    }
+                }
+            }
+        }
+    }
+
+    pub(super) fn handle_dm_group_left(&mut self, group_id: String, result: Result<(), anyhow::Error>) {
+        match result {
+            Ok(()) => {
+                if let ViewState::Conversation(ref mut state) = self.view {
+                    if let Some(group) = state.group.as_mut().filter(|group| group.id == group_id) {
+                        group.is_member = false;
+                    }
+                }
+                self.spawn_load_conversations();
+            }
+            Err(err) => {
+                error!("Failed to leave group {}: {}", group_id, err);
+                if let ViewState::Conversation(ref mut state) = self.view {
+                    state.send_error = Some(err.to_string());
+                }
+            }
+        }
+    }
+
     // Search handler
 
     pub(super) fn handle_search_completed(&mut self, query: String, result: Result<SearchResponse, anyhow::Error>) {
@@ -310,6 +400,10 @@ impl GraphchanApp {
             Ok(()) => {
                 self.subscribed_topics.retain(|t| t != &topic_id);
                 self.selected_topics.remove(&topic_id);
+                self.topic_details.details.remove(&topic_id);
+                if self.topic_details.selected.as_deref() == Some(topic_id.as_str()) {
+                    self.topic_details.selected = None;
+                }
                 info!("Successfully unsubscribed from topic: {}", topic_id);
             }
             Err(err) => {
@@ -319,6 +413,60 @@ impl GraphchanApp {
         }
     }
 
+    pub(super) fn handle_topic_loaded(&mut self, topic_id: String, result: Result<TopicView, anyhow::Error>) {
+        self.topic_details.loading = false;
+        match result {
+            Ok(view) => self.store_topic_view(topic_id, view),
+            Err(err) => {
+                error!("Failed to load topic {}: {}", topic_id, err);
+                self.topic_details.error = Some(err.to_string());
+            }
+        }
+    }
+
+    pub(super) fn handle_topic_updated(&mut self, topic_id: String, result: Result<TopicView, anyhow::Error>) {
+        self.topic_details.saving = false;
+        match result {
+            Ok(view) => {
+                // Re-seed the form from what was actually published
+                self.topic_details.draft_topic = None;
+                self.store_topic_view(topic_id, view);
+            }
+            Err(err) => {
+                error!("Failed to update topic {}: {}", topic_id, err);
+                self.topic_details.error = Some(err.to_string());
+            }
+        }
+    }
+
+    fn store_topic_view(&mut self, topic_id: String, view: TopicView) {
+        let state = &mut self.topic_details;
+        state.error = None;
+        if state.draft_topic.as_deref() != Some(topic_id.as_str()) {
+            let metadata = view.metadata.as_ref();
+            state.draft_description = metadata.map(|m| m.description.clone()).unwrap_or_default();
+            state.draft_rules = metadata.map(|m| m.rules.join("\n")).unwrap_or_default();
+            state.draft_blocklists = metadata
+                .map(|m| {
+                    m.suggested_blocklists
+                        .iter()
+                        .map(|list| format!("{} {}", list.maintainer_peer_id, list.name))
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                })
+                .unwrap_or_default();
+            state.draft_pow = metadata
+                .and_then(|m| m.pow_difficulty)
+                .map(|difficulty| difficulty.to_string())
+                .unwrap_or_default();
+            state.draft_media_policy = metadata
+                .map(|m| m.media_policy.clone())
+                .unwrap_or_else(|| "any".to_string());
+            state.draft_topic = Some(topic_id.clone());
+        }
+        state.details.insert(topic_id, view);
+    }
+
     // Theme handler
 
     pub(super) fn handle_theme_color_loaded(&mut self, result: Result<(u8, u8, u8), anyhow::Error>) {
@@ -2,8 +2,8 @@ use std::collections::HashMap;
 
 use crate::models::{
     BlockedPeerView, BlocklistEntryView, BlocklistSubscriptionView, ConversationView,
-    DirectMessageView, FileResponse, PeerView, PostView, ReactionsResponse, SearchResponse, ThreadDetails,
-    ThreadSummary,
+    DirectMessageView, DmGroupView, FileResponse, PeerView, PostView, ReactionsResponse, SearchResponse, ThreadDetails,
+    ThreadSummary, TopicView,
 };
 
 use super::state::LoadedImage;
@@ -86,6 +86,20 @@ pub enum AppMessage {
         to_peer_id: String,
         result: Result<DirectMessageView, anyhow::Error>,
     },
+    DmGroupsLoaded(Result<Vec<DmGroupView>, anyhow::Error>),
+    DmGroupCreated(Result<DmGroupView, anyhow::Error>),
+    GroupMessagesLoaded {
+        group_id: String,
+        result: Result<Vec<DirectMessageView>, anyhow::Error>,
+    },
+    GroupDmSent {
+        group_id: String,
+        result: Result<DirectMessageView, anyhow::Error>,
+    },
+    DmGroupLeft {
+        group_id: String,
+        result: Result<(), anyhow::Error>,
+    },
     BlockedPeersLoaded(Result<Vec<BlockedPeerView>, anyhow::Error>),
     PeerBlocked {
         peer_id: String,
@@ -156,6 +170,14 @@ pub enum AppMessage {
         topic_id: String,
         result: Result<(), anyhow::Error>,
     },
+    TopicLoaded {
+        topic_id: String,
+        result: Result<TopicView, anyhow::Error>,
+    },
+    TopicUpdated {
+        topic_id: String,
+        result: Result<TopicView, anyhow::Error>,
+    },
     // Theme management messages
     ThemeColorLoaded(Result<(u8, u8, u8), anyhow::Error>),
 }
@@ -203,6 +225,11 @@ pub(super) fn process_messages(app: &mut GraphchanApp) {
             AppMessage::ConversationsLoaded(result) => app.handle_conversations_loaded(result),
             AppMessage::MessagesLoaded { peer_id, result } => app.handle_messages_loaded(peer_id, result),
             AppMessage::DmSent { to_peer_id, result } => app.handle_dm_sent(to_peer_id, result),
+            AppMessage::DmGroupsLoaded(result) => app.handle_dm_groups_loaded(result),
+            AppMessage::DmGroupCreated(result) => app.handle_dm_group_created(result),
+            AppMessage::GroupMessagesLoaded { group_id, result } => app.handle_group_messages_loaded(group_id, result),
+            AppMessage::GroupDmSent { group_id, result } => app.handle_group_dm_sent(group_id, result),
+            AppMessage::DmGroupLeft { group_id, result } => app.handle_dm_group_left(group_id, result),
 
             // Blocking handlers (handlers_blocking.rs)
             AppMessage::BlockedPeersLoaded(result) => app.handle_blocked_peers_loaded(result),
@@ -235,6 +262,8 @@ pub(super) fn process_messages(app: &mut GraphchanApp) {
             AppMessage::TopicsLoaded(result) => app.handle_topics_loaded(result),
             AppMessage::TopicSubscribed { topic_id, result } => app.handle_topic_subscribed(topic_id, result),
             AppMessage::TopicUnsubscribed { topic_id, result } => app.handle_topic_unsubscribed(topic_id, result),
+            AppMessage::TopicLoaded { topic_id, result } => app.handle_topic_loaded(topic_id, result),
+            AppMessage::TopicUpdated { topic_id, result } => app.handle_topic_updated(topic_id, result),
             AppMessage::ThemeColorLoaded(result) => app.handle_theme_color_loaded(result),
         }
     }
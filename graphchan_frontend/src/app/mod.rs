@@ -31,7 +31,7 @@ use messages::AppMessage;
 pub use file_viewer::{FileType, FileDownloadState, FileViewerState, FileViewerContent};
 use state::{
     BlockingState, ConversationState, CreateThreadState, DmState, ImporterState, LoadedImage,
-    ThreadDisplayMode, ThreadState, ViewState,
+    ThreadDisplayMode, ThreadState, TopicDetailsState, ViewState,
 };
 
 pub struct GraphchanApp {
@@ -87,6 +87,7 @@ pub struct GraphchanApp {
     show_topic_manager: bool,
     new_topic_input: String,
     selected_topics: HashSet<String>, // For create thread dialog
+    topic_details: TopicDetailsState, // Metadata and settings for the topic picked in the manager
     // Theme customization
     primary_color: egui::Color32,
     theme_dirty: bool, // Flag to reapply theme on next frame
@@ -208,6 +209,7 @@ impl GraphchanApp {
             show_topic_manager: false,
             new_topic_input: String::new(),
             selected_topics: HashSet::new(),
+            topic_details: TopicDetailsState::default(),
             // Theme customization
             primary_color: egui::Color32::from_rgb(64, 128, 255), // Default blue
             theme_dirty: true, // Apply default theme on first frame
@@ -1,7 +1,7 @@
 use super::state;
 use super::tasks;
 use super::GraphchanApp;
-use crate::models::CreatePostInput;
+use crate::models::{CreatePostInput, TopicMetadataDraft, TopicSettingsUpdate};
 
 use super::state::{ConversationState, ThreadState};
 
@@ -121,6 +121,27 @@ impl GraphchanApp {
         tasks::unsubscribe_topic(self.api.clone(), self.tx.clone(), topic_id);
     }
 
+    pub(super) fn spawn_load_topic(&mut self, topic_id: String) {
+        self.topic_details.loading = true;
+        self.topic_details.error = None;
+        tasks::load_topic(self.api.clone(), self.tx.clone(), topic_id);
+    }
+
+    pub(super) fn spawn_update_topic_settings(&mut self, topic_id: String, update: TopicSettingsUpdate) {
+        self.topic_details.saving = true;
+        self.topic_details.error = None;
+        tasks::update_topic_settings(self.api.clone(), self.tx.clone(), topic_id, update);
+    }
+
+    pub(super) fn spawn_publish_topic_metadata(&mut self, topic_id: String, draft: TopicMetadataDraft) {
+        if self.topic_details.saving {
+            return;
+        }
+        self.topic_details.saving = true;
+        self.topic_details.error = None;
+        tasks::publish_topic_metadata(self.api.clone(), self.tx.clone(), topic_id, draft);
+    }
+
     pub(super) fn spawn_load_recent_posts(&mut self) {
         if self.recent_posts_loading {
             return;
@@ -138,6 +159,7 @@ impl GraphchanApp {
         self.dm_state.conversations_loading = true;
         self.dm_state.conversations_error = None;
         tasks::load_conversations(self.api.clone(), self.tx.clone());
+        tasks::load_dm_groups(self.api.clone(), self.tx.clone());
     }
 
     pub(super) fn spawn_load_messages(&mut self, state: &mut ConversationState) {
@@ -146,25 +168,73 @@ impl GraphchanApp {
         }
         state.messages_loading = true;
         state.messages_error = None;
-        tasks::load_messages(self.api.clone(), self.tx.clone(), state.peer_id.clone());
+        match &state.group {
+            Some(group) => tasks::load_group_messages(self.api.clone(), self.tx.clone(), group.id.clone()),
+            None => tasks::load_messages(self.api.clone(), self.tx.clone(), state.peer_id.clone()),
+        }
     }
 
     pub(super) fn spawn_send_dm(&mut self, state: &mut ConversationState) {
         let body = state.new_message_body.trim().to_string();
-        if body.is_empty() {
+        if body.is_empty() && state.draft_attachments.is_empty() {
             state.send_error = Some("Message cannot be empty".into());
             return;
         }
         state.sending = true;
         state.send_error = None;
+        let reply_to = state.reply_to.as_ref().map(|message| message.id.clone());
+        let attachments = state.draft_attachments.clone();
+        if let Some(group) = &state.group {
+            tasks::send_group_dm(
+                self.api.clone(),
+                self.tx.clone(),
+                group.id.clone(),
+                body,
+                reply_to,
+                attachments,
+            );
+            return;
+        }
         tasks::send_dm(
             self.api.clone(),
             self.tx.clone(),
             state.peer_id.clone(),
             body,
+            reply_to,
+            attachments,
+        );
+    }
+
+    pub(super) fn spawn_download_dm_attachment(&self, state: &ConversationState, attachment_id: String) {
+        tasks::trigger_dm_attachment_download(
+            self.api.clone(),
+            self.tx.clone(),
+            attachment_id,
+            state.peer_id.clone(),
+            state.group.as_ref().map(|group| group.id.clone()),
         );
     }
 
+    pub(super) fn spawn_create_dm_group(&mut self) {
+        let name = self.dm_state.new_group_name.trim().to_string();
+        if name.is_empty() {
+            self.dm_state.group_error = Some("Group name cannot be empty".into());
+            return;
+        }
+        if self.dm_state.new_group_members.is_empty() {
+            self.dm_state.group_error = Some("Pick at least one friend".into());
+            return;
+        }
+        self.dm_state.creating_group = true;
+        self.dm_state.group_error = None;
+        let members = self.dm_state.new_group_members.iter().cloned().collect();
+        tasks::create_dm_group(self.api.clone(), self.tx.clone(), name, members);
+    }
+
+    pub(super) fn spawn_leave_dm_group(&mut self, group_id: String) {
+        tasks::leave_dm_group(self.api.clone(), self.tx.clone(), group_id);
+    }
+
     pub(super) fn spawn_load_blocked_peers(&mut self) {
         if self.blocking_state.blocked_peers_loading {
             return;
@@ -6,7 +6,7 @@ use serde::{Deserialize, Serialize};
 use eframe::egui;
 
 use crate::models::{
-    ConversationView, DirectMessageView, FileResponse, PeerView, ReactionsResponse, SearchResultView, ThreadDetails,
+    ConversationView, DirectMessageView, DmGroupView, FileResponse, PeerView, ReactionsResponse, SearchResultView, ThreadDetails,
     ThreadSummary,
 };
 
@@ -177,10 +177,16 @@ pub struct LoadedImage {
 pub struct ConversationState {
     pub peer_id: String,
     pub peer_info: Option<PeerView>,
+    /// Set for a group conversation, which loads and sends by group ID instead of `peer_id`
+    pub group: Option<DmGroupView>,
     pub messages: Vec<DirectMessageView>,
     pub messages_loading: bool,
     pub messages_error: Option<String>,
     pub new_message_body: String,
+    /// Message the next send replies to
+    pub reply_to: Option<DirectMessageView>,
+    /// Files uploaded as encrypted DM attachments when the message is sent
+    pub draft_attachments: Vec<std::path::PathBuf>,
     pub sending: bool,
     pub send_error: Option<String>,
 }
@@ -193,6 +199,12 @@ pub struct DmState {
     pub unread_count: usize,
     pub show_new_conversation_picker: bool,
     pub new_conversation_filter: String,
+    pub groups: Vec<DmGroupView>,
+    pub show_new_group_picker: bool,
+    pub new_group_name: String,
+    pub new_group_members: HashSet<String>,
+    pub creating_group: bool,
+    pub group_error: Option<String>,
 }
 
 // Blocking State
@@ -277,3 +289,22 @@ pub struct SearchState {
     pub is_loading: bool,
     pub error: Option<String>,
 }
+
+// Topic Details State
+
+#[derive(Default)]
+pub struct TopicDetailsState {
+    pub selected: Option<String>,
+    pub details: HashMap<String, crate::models::TopicView>,
+    pub loading: bool,
+    pub saving: bool,
+    pub error: Option<String>,
+
+    // Founder metadata form (prefilled when a topic is selected)
+    pub draft_topic: Option<String>,
+    pub draft_description: String,
+    pub draft_rules: String,      // One rule per line
+    pub draft_blocklists: String, // One "PEER_ID Name" per line
+    pub draft_pow: String,        // Empty = no suggestion
+    pub draft_media_policy: String,
+}
@@ -4,7 +4,7 @@ use log::error;
 
 use crate::api::ApiClient;
 use crate::importer;
-use crate::models::{CreatePostInput, CreateThreadInput};
+use crate::models::{CreatePostInput, CreateThreadInput, TopicMetadataDraft, TopicSettingsUpdate};
 
 use super::messages::AppMessage;
 use super::state::LoadedImage;
@@ -462,9 +462,18 @@ pub fn load_messages(client: ApiClient, tx: Sender<AppMessage>, peer_id: String)
     });
 }
 
-pub fn send_dm(client: ApiClient, tx: Sender<AppMessage>, to_peer_id: String, body: String) {
+pub fn send_dm(
+    client: ApiClient,
+    tx: Sender<AppMessage>,
+    to_peer_id: String,
+    body: String,
+    reply_to: Option<String>,
+    attachments: Vec<std::path::PathBuf>,
+) {
     thread::spawn(move || {
-        let result = client.send_dm(&to_peer_id, &body);
+        let result = upload_dm_attachments(&client, &attachments).and_then(|ids| {
+            client.send_dm(&to_peer_id, &body, reply_to.as_deref(), ids)
+        });
         let message = AppMessage::DmSent {
             to_peer_id,
             result,
@@ -475,6 +484,101 @@ pub fn send_dm(client: ApiClient, tx: Sender<AppMessage>, to_peer_id: String, bo
     });
 }
 
+pub fn load_dm_groups(client: ApiClient, tx: Sender<AppMessage>) {
+    thread::spawn(move || {
+        let result = client.list_dm_groups();
+        if tx.send(AppMessage::DmGroupsLoaded(result)).is_err() {
+            error!("failed to send DmGroupsLoaded message");
+        }
+    });
+}
+
+pub fn create_dm_group(client: ApiClient, tx: Sender<AppMessage>, name: String, members: Vec<String>) {
+    thread::spawn(move || {
+        let result = client.create_dm_group(&name, members);
+        if tx.send(AppMessage::DmGroupCreated(result)).is_err() {
+            error!("failed to send DmGroupCreated message");
+        }
+    });
+}
+
+pub fn load_group_messages(client: ApiClient, tx: Sender<AppMessage>, group_id: String) {
+    thread::spawn(move || {
+        let result = client.get_group_messages(&group_id, 50);
+        let message = AppMessage::GroupMessagesLoaded { group_id, result };
+        if tx.send(message).is_err() {
+            error!("failed to send GroupMessagesLoaded message");
+        }
+    });
+}
+
+pub fn send_group_dm(
+    client: ApiClient,
+    tx: Sender<AppMessage>,
+    group_id: String,
+    body: String,
+    reply_to: Option<String>,
+    attachments: Vec<std::path::PathBuf>,
+) {
+    thread::spawn(move || {
+        let result = upload_dm_attachments(&client, &attachments).and_then(|ids| {
+            client.send_group_dm(&group_id, &body, reply_to.as_deref(), ids)
+        });
+        let message = AppMessage::GroupDmSent { group_id, result };
+        if tx.send(message).is_err() {
+            error!("failed to send GroupDmSent message");
+        }
+    });
+}
+
+/// Uploads DM attachments ahead of the message; any failure aborts the send.
+fn upload_dm_attachments(client: &ApiClient, paths: &[std::path::PathBuf]) -> anyhow::Result<Vec<String>> {
+    paths
+        .iter()
+        .map(|path| client.upload_dm_attachment(path).map(|attachment| attachment.id))
+        .collect()
+}
+
+/// Asks the backend to fetch a received DM attachment, then reloads the
+/// conversation so the new download status shows up.
+pub fn trigger_dm_attachment_download(
+    client: ApiClient,
+    tx: Sender<AppMessage>,
+    attachment_id: String,
+    peer_id: String,
+    group_id: Option<String>,
+) {
+    thread::spawn(move || {
+        if let Err(e) = client.trigger_dm_attachment_download(&attachment_id) {
+            error!("Failed to trigger DM attachment download: {}", e);
+            return;
+        }
+        let message = match group_id {
+            Some(group_id) => {
+                let result = client.get_group_messages(&group_id, 50);
+                AppMessage::GroupMessagesLoaded { group_id, result }
+            }
+            None => {
+                let result = client.get_messages(&peer_id, 50);
+                AppMessage::MessagesLoaded { peer_id, result }
+            }
+        };
+        if tx.send(message).is_err() {
+            error!("failed to send messages after DM attachment download trigger");
+        }
+    });
+}
+
+pub fn leave_dm_group(client: ApiClient, tx: Sender<AppMessage>, group_id: String) {
+    thread::spawn(move || {
+        let result = client.leave_dm_group(&group_id);
+        let message = AppMessage::DmGroupLeft { group_id, result };
+        if tx.send(message).is_err() {
+            error!("failed to send DmGroupLeft message");
+        }
+    });
+}
+
 pub fn load_blocked_peers(client: ApiClient, tx: Sender<AppMessage>) {
     thread::spawn(move || {
         let result = client.list_blocked_peers();
@@ -774,3 +878,40 @@ pub fn unsubscribe_topic(client: ApiClient, tx: Sender<AppMessage>, topic_id: St
         }
     });
 }
+
+pub fn load_topic(client: ApiClient, tx: Sender<AppMessage>, topic_id: String) {
+    thread::spawn(move || {
+        let result = client.get_topic(&topic_id);
+        if tx.send(AppMessage::TopicLoaded { topic_id, result }).is_err() {
+            error!("failed to send TopicLoaded message");
+        }
+    });
+}
+
+pub fn update_topic_settings(
+    client: ApiClient,
+    tx: Sender<AppMessage>,
+    topic_id: String,
+    update: TopicSettingsUpdate,
+) {
+    thread::spawn(move || {
+        let result = client.update_topic_settings(&topic_id, &update);
+        if tx.send(AppMessage::TopicUpdated { topic_id, result }).is_err() {
+            error!("failed to send TopicUpdated message");
+        }
+    });
+}
+
+pub fn publish_topic_metadata(
+    client: ApiClient,
+    tx: Sender<AppMessage>,
+    topic_id: String,
+    draft: TopicMetadataDraft,
+) {
+    thread::spawn(move || {
+        let result = client.publish_topic_metadata(&topic_id, &draft);
+        if tx.send(AppMessage::TopicUpdated { topic_id, result }).is_err() {
+            error!("failed to send TopicUpdated message");
+        }
+    });
+}
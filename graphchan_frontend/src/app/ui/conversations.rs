@@ -3,23 +3,52 @@ use eframe::egui::{self, Color32, RichText, ScrollArea};
 use crate::app::{state::ConversationState, GraphchanApp};
 
 pub fn render_conversation(app: &mut GraphchanApp, ui: &mut egui::Ui, state: &mut ConversationState) {
+    let my_peer_id = app.identity_state.local_peer.as_ref().map(|peer| peer.id.clone());
+    let mut leave_group = false;
+    let mut reply_target: Option<crate::models::DirectMessageView> = None;
+    let mut attachment_to_download: Option<String> = None;
+
     ui.horizontal(|ui| {
         if ui.button("← Back to Private Threads").clicked() {
             app.view = crate::app::state::ViewState::Catalog;
         }
 
-        ui.heading("Direct Message");
-
-        if let Some(peer) = &state.peer_info {
-            ui.label(RichText::new(format!(
-                "with {}",
-                peer.username.as_deref().or(peer.alias.as_deref()).unwrap_or(&peer.id)
-            )).strong());
+        if let Some(group) = &state.group {
+            // Members are listed below the header
+            ui.heading(group.display_name());
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if group.is_member && ui.button("Leave group").clicked() {
+                    leave_group = true;
+                }
+            });
         } else {
-            ui.label(RichText::new(&state.peer_id).monospace().size(10.0));
+            ui.heading("Direct Message");
+
+            if let Some(peer) = &state.peer_info {
+                ui.label(RichText::new(format!(
+                    "with {}",
+                    peer.username.as_deref().or(peer.alias.as_deref()).unwrap_or(&peer.id)
+                )).strong());
+            } else {
+                ui.label(RichText::new(&state.peer_id).monospace().size(10.0));
+            }
         }
     });
 
+    if let Some(group) = &state.group {
+        let members: Vec<&str> = group.members.iter().map(|member| member.display_name()).collect();
+        ui.label(RichText::new(format!("Members: {}", members.join(", "))).size(11.0).weak());
+        if !group.is_member {
+            ui.label(RichText::new("You are no longer a member of this group.").weak());
+        }
+    }
+
+    if leave_group {
+        if let Some(group) = &state.group {
+            app.spawn_leave_dm_group(group.id.clone());
+        }
+    }
+
     ui.separator();
 
     // Messages area
@@ -38,7 +67,19 @@ pub fn render_conversation(app: &mut GraphchanApp, ui: &mut egui::Ui, state: &mu
             }
 
             for message in &state.messages {
-                let is_outgoing = message.from_peer_id != state.peer_id;
+                let is_outgoing = match &state.group {
+                    Some(_) => my_peer_id.as_deref() == Some(message.from_peer_id.as_str()),
+                    None => message.from_peer_id != state.peer_id,
+                };
+                // Group messages name their sender
+                let sender = state.group.as_ref().filter(|_| !is_outgoing).map(|group| {
+                    group
+                        .members
+                        .iter()
+                        .find(|member| member.peer_id == message.from_peer_id)
+                        .map(|member| member.display_name().to_string())
+                        .unwrap_or_else(|| message.from_peer_id.clone())
+                });
 
                 egui::Frame::none()
                     .inner_margin(egui::vec2(8.0, 4.0))
@@ -60,13 +101,73 @@ pub fn render_conversation(app: &mut GraphchanApp, ui: &mut egui::Ui, state: &mu
                                 .show(ui, |ui| {
                                     ui.set_max_width(ui.available_width() - 50.0);
 
-                                    ui.label(&message.body);
+                                    if let Some(sender) = &sender {
+                                        ui.label(RichText::new(sender).size(10.0).strong());
+                                    }
+                                    if let Some(reply_to) = &message.reply_to {
+                                        let quoted = state
+                                            .messages
+                                            .iter()
+                                            .find(|candidate| &candidate.id == reply_to)
+                                            .map(|candidate| quote_snippet(candidate))
+                                            .unwrap_or_else(|| "earlier message".to_string());
+                                        ui.label(RichText::new(format!("↪ {}", quoted)).size(10.0).italics().weak());
+                                    }
+                                    if !message.body.is_empty() {
+                                        ui.label(&message.body);
+                                    }
+
+                                    for attachment in &message.attachments {
+                                        ui.horizontal(|ui| {
+                                            ui.label(format!("📎 {}", attachment.original_name));
+                                            ui.label(RichText::new(format_size(attachment.size_bytes)).size(9.0).weak());
+                                            match attachment.download_status.as_str() {
+                                                "available" => {
+                                                    ui.hyperlink_to("Open", app.api.dm_attachment_url(&attachment.id));
+                                                }
+                                                "downloading" => {
+                                                    ui.add(egui::Spinner::new());
+                                                }
+                                                "failed" => {
+                                                    if ui.small_button("↻ Retry").clicked() {
+                                                        attachment_to_download = Some(attachment.id.clone());
+                                                    }
+                                                }
+                                                _ => {
+                                                    if ui.small_button("⬇ Download").clicked() {
+                                                        attachment_to_download = Some(attachment.id.clone());
+                                                    }
+                                                }
+                                            }
+                                        });
+                                    }
 
                                     ui.horizontal(|ui| {
                                         ui.label(RichText::new(&message.created_at).size(9.0).weak());
                                         if message.read_at.is_some() {
                                             ui.label(RichText::new("✓ Read").size(9.0).weak());
                                         }
+                                        if is_outgoing {
+                                            match message.delivery_state.as_deref() {
+                                                Some("acked") => {
+                                                    ui.label(RichText::new("✓ Delivered").size(9.0).weak());
+                                                }
+                                                Some("queued") | Some("sent") => {
+                                                    ui.label(RichText::new("⏳ Pending").size(9.0).weak());
+                                                }
+                                                Some("failed") => {
+                                                    ui.label(
+                                                        RichText::new("⚠ Not delivered")
+                                                            .size(9.0)
+                                                            .color(Color32::LIGHT_RED),
+                                                    );
+                                                }
+                                                _ => {}
+                                            }
+                                        }
+                                        if ui.small_button("↩ Reply").clicked() {
+                                            reply_target = Some(message.clone());
+                                        }
                                     });
                                 });
 
@@ -80,10 +181,54 @@ pub fn render_conversation(app: &mut GraphchanApp, ui: &mut egui::Ui, state: &mu
             }
         });
 
+    if let Some(message) = reply_target {
+        state.reply_to = Some(message);
+    }
+    if let Some(attachment_id) = attachment_to_download {
+        app.spawn_download_dm_attachment(state, attachment_id);
+    }
+
     ui.separator();
 
+    if state.group.as_ref().is_some_and(|group| !group.is_member) {
+        return;
+    }
+
+    let mut cancel_reply = false;
+    if let Some(message) = &state.reply_to {
+        ui.horizontal(|ui| {
+            ui.label(RichText::new(format!("Replying to: {}", quote_snippet(message))).size(11.0).weak());
+            if ui.small_button("❌").clicked() {
+                cancel_reply = true;
+            }
+        });
+    }
+    if cancel_reply {
+        state.reply_to = None;
+    }
+
+    let mut attachment_to_remove: Option<usize> = None;
+    for (index, path) in state.draft_attachments.iter().enumerate() {
+        ui.horizontal(|ui| {
+            let name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+            ui.label(format!("📎 {}", name));
+            if ui.small_button("❌").clicked() {
+                attachment_to_remove = Some(index);
+            }
+        });
+    }
+    if let Some(index) = attachment_to_remove {
+        state.draft_attachments.remove(index);
+    }
+
     // Send message input
     ui.horizontal(|ui| {
+        if ui.button("📎").on_hover_text("Attach a file").clicked() {
+            if let Some(path) = rfd::FileDialog::new().pick_file() {
+                state.draft_attachments.push(path);
+            }
+        }
+
         let response = ui.add_sized(
             [ui.available_width() - 80.0, 40.0],
             egui::TextEdit::multiline(&mut state.new_message_body)
@@ -106,6 +251,33 @@ pub fn render_conversation(app: &mut GraphchanApp, ui: &mut egui::Ui, state: &mu
     }
 }
 
+/// First line of a message, shortened for reply quotes.
+fn quote_snippet(message: &crate::models::DirectMessageView) -> String {
+    let line = message.body.lines().next().unwrap_or_default();
+    if line.is_empty() {
+        return match message.attachments.len() {
+            0 => String::new(),
+            1 => "[1 attachment]".to_string(),
+            n => format!("[{} attachments]", n),
+        };
+    }
+    if line.chars().count() > 60 {
+        format!("{}…", line.chars().take(60).collect::<String>())
+    } else {
+        line.to_string()
+    }
+}
+
+fn format_size(bytes: i64) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
+
 pub fn render_conversations_list(
     app: &mut GraphchanApp,
     ui: &mut egui::Ui,
@@ -119,9 +291,18 @@ pub fn render_conversations_list(
                 app.dm_state.show_new_conversation_picker = !app.dm_state.show_new_conversation_picker;
                 app.dm_state.new_conversation_filter.clear();
             }
+            if ui.button("+ New Group").clicked() {
+                app.dm_state.show_new_group_picker = !app.dm_state.show_new_group_picker;
+                app.dm_state.group_error = None;
+            }
         });
     });
 
+    if app.dm_state.show_new_group_picker {
+        render_new_group_picker(app, ui);
+        ui.add_space(8.0);
+    }
+
     // Peer picker (shown when toggled)
     let mut peer_to_open: Option<crate::models::PeerView> = None;
 
@@ -196,7 +377,8 @@ pub fn render_conversations_list(
 
     ui.separator();
 
-    if conversations.is_empty() {
+    let groups = app.dm_state.groups.clone();
+    if conversations.is_empty() && groups.is_empty() {
         ui.add_space(20.0);
         ui.vertical_centered(|ui| {
             ui.label("No conversations yet.");
@@ -206,8 +388,45 @@ pub fn render_conversations_list(
     }
 
     let mut conversation_to_open: Option<String> = None;
+    let mut group_to_open: Option<crate::models::DmGroupView> = None;
 
     ScrollArea::vertical().show(ui, |ui| {
+        for group in &groups {
+            egui::Frame::group(ui.style())
+                .fill(ui.visuals().extreme_bg_color)
+                .inner_margin(egui::vec2(12.0, 8.0))
+                .show(ui, |ui| {
+                    ui.set_width(ui.available_width());
+
+                    ui.horizontal(|ui| {
+                        if ui.button(RichText::new(group.display_name()).strong()).clicked() {
+                            group_to_open = Some(group.clone());
+                        }
+                        ui.label(RichText::new(format!("{} members", group.members.len())).size(10.0).weak());
+
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            if group.unread_count > 0 {
+                                ui.label(
+                                    RichText::new(format!("{} unread", group.unread_count))
+                                        .color(Color32::from_rgb(100, 200, 100))
+                                        .strong()
+                                );
+                            }
+                        });
+                    });
+
+                    if let Some(preview) = &group.last_message_preview {
+                        ui.label(RichText::new(preview).size(11.0).weak());
+                    }
+
+                    if let Some(timestamp) = &group.last_message_at {
+                        ui.label(RichText::new(timestamp).size(9.0).weak());
+                    }
+                });
+
+            ui.add_space(8.0);
+        }
+
         for conv in conversations {
             egui::Frame::group(ui.style())
                 .fill(ui.visuals().extreme_bg_color)
@@ -252,6 +471,67 @@ pub fn render_conversations_list(
     if let Some(peer_id) = conversation_to_open {
         open_conversation(app, peer_id);
     }
+    if let Some(group) = group_to_open {
+        open_group_conversation(app, group);
+    }
+}
+
+fn render_new_group_picker(app: &mut GraphchanApp, ui: &mut egui::Ui) {
+    egui::Frame::group(ui.style())
+        .inner_margin(egui::vec2(12.0, 8.0))
+        .show(ui, |ui| {
+            ui.label(RichText::new("New group").strong());
+            ui.add_space(4.0);
+
+            ui.add(
+                egui::TextEdit::singleline(&mut app.dm_state.new_group_name)
+                    .hint_text("Group name")
+            );
+            ui.add_space(4.0);
+
+            // Collect peers to avoid borrow issues
+            let peers: Vec<_> = app.peers.values().cloned().collect();
+            if peers.is_empty() {
+                ui.label(RichText::new("No friends yet. Add peers from the Following page.").weak());
+            }
+            for peer in &peers {
+                let name = peer.username.as_deref()
+                    .or(peer.alias.as_deref())
+                    .unwrap_or(&peer.id);
+                let mut selected = app.dm_state.new_group_members.contains(&peer.id);
+                if ui.checkbox(&mut selected, name).changed() {
+                    if selected {
+                        app.dm_state.new_group_members.insert(peer.id.clone());
+                    } else {
+                        app.dm_state.new_group_members.remove(&peer.id);
+                    }
+                }
+            }
+
+            ui.add_space(4.0);
+            ui.horizontal(|ui| {
+                if ui.add_enabled(!app.dm_state.creating_group, egui::Button::new("Create group")).clicked() {
+                    app.spawn_create_dm_group();
+                }
+                if app.dm_state.creating_group {
+                    ui.add(egui::Spinner::new());
+                }
+            });
+
+            if let Some(err) = &app.dm_state.group_error {
+                ui.colored_label(Color32::LIGHT_RED, format!("Error: {}", err));
+            }
+        });
+}
+
+pub fn open_group_conversation(app: &mut GraphchanApp, group: crate::models::DmGroupView) {
+    let mut state = ConversationState {
+        group: Some(group),
+        ..Default::default()
+    };
+
+    app.spawn_load_messages(&mut state);
+    app.view = crate::app::state::ViewState::Conversation(state);
 }
 
 fn open_conversation(app: &mut GraphchanApp, peer_id: String) {
@@ -155,7 +155,11 @@ pub fn render_node(
 
                         ui.add_space(6.0 * zoom);
 
-                        render_post_body(ui, &layout.post.body);
+                        if layout.post.deleted {
+                            ui.label(RichText::new("[deleted]").weak().italics());
+                        } else {
+                            render_post_body(ui, &layout.post.body);
+                        }
 
                         render_node_attachments(app, ui, layout.attachments.as_ref(), api_base, zoom);
 
@@ -530,7 +534,10 @@ fn render_node_attachments(app: &mut GraphchanApp, ui: &mut egui::Ui, attachment
                              let url = format!("{}/files/{}", api_base, file.id);
                              let file_id = file.id.clone();
                              state.audio_promise = Some(poll_promise::Promise::spawn_thread("load_audio", move || {
-                                 let bytes = reqwest::blocking::get(url)
+                                 let client = crate::api::get_shared_client().map_err(|e| e.to_string())?;
+                                 let bytes = client
+                                     .get(url)
+                                     .send()
                                      .map_err(|e| e.to_string())?
                                      .bytes()
                                      .map_err(|e| e.to_string())?
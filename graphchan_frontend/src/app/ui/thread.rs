@@ -331,6 +331,9 @@ impl GraphchanApp {
                                         egui::Layout::right_to_left(egui::Align::Center),
                                         |ui| {
                                             ui.label(format_timestamp(&post.created_at));
+                                            if post.updated_at.is_some() && !post.deleted {
+                                                ui.label(RichText::new("(edited)").weak().italics());
+                                            }
                                         },
                                     );
                                 });
@@ -401,7 +404,9 @@ impl GraphchanApp {
                                 }
 
                                 ui.add_space(6.0);
-                                if let Some(thread_id) = render_post_body(ui, &post.body) {
+                                if post.deleted {
+                                    ui.label(RichText::new("[deleted]").weak().italics());
+                                } else if let Some(thread_id) = render_post_body(ui, &post.body) {
                                     action = ThreadAction::OpenThread(thread_id);
                                 }
 
@@ -1,6 +1,27 @@
 use eframe::egui::{self, Align2, Color32, Context};
 
+use super::super::state::{TopicDetailsState, ViewState};
 use super::super::GraphchanApp;
+use crate::models::{SuggestedBlocklist, TopicMetadataDraft, TopicSettingsUpdate};
+
+const MEDIA_POLICIES: &[(&str, &str)] = &[
+    ("any", "Any media"),
+    ("images_only", "Images only"),
+    ("text_only", "Text only"),
+];
+const NOTIFICATION_LEVELS: &[(&str, &str)] = &[
+    ("all", "All activity"),
+    ("replies", "Replies to me"),
+    ("off", "Off"),
+];
+
+/// Actions collected while the details panel is drawn, applied after the window closes.
+#[derive(Default)]
+struct TopicActions {
+    settings_update: Option<TopicSettingsUpdate>,
+    publish: Option<TopicMetadataDraft>,
+    open_blocklist: Option<SuggestedBlocklist>,
+}
 
 impl GraphchanApp {
     pub(crate) fn render_topic_manager(&mut self, ctx: &Context) {
@@ -11,6 +32,8 @@ impl GraphchanApp {
         let mut should_close = false;
         let mut topic_to_subscribe: Option<String> = None;
         let mut topic_to_unsubscribe: Option<String> = None;
+        let mut topic_to_select: Option<String> = None;
+        let mut actions = TopicActions::default();
 
         egui::Window::new("Topic Manager")
             .open(&mut self.show_topic_manager)
@@ -61,7 +84,10 @@ impl GraphchanApp {
                         .show(ui, |ui| {
                             for topic_id in &self.subscribed_topics.clone() {
                                 ui.horizontal(|ui| {
-                                    ui.label(format!("📡 {}", topic_id));
+                                    let selected = self.topic_details.selected.as_deref() == Some(topic_id.as_str());
+                                    if ui.selectable_label(selected, format!("📡 {}", topic_id)).clicked() {
+                                        topic_to_select = Some(topic_id.clone());
+                                    }
                                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                                         if ui.button("Unsubscribe").clicked() {
                                             topic_to_unsubscribe = Some(topic_id.clone());
@@ -76,6 +102,11 @@ impl GraphchanApp {
                     ui.label(format!("Total: {} topic(s)", self.subscribed_topics.len()));
                 }
 
+                if let Some(topic_id) = self.topic_details.selected.clone() {
+                    ui.add_space(12.0);
+                    render_topic_details(ui, &mut self.topic_details, &topic_id, &mut actions);
+                }
+
                 ui.add_space(12.0);
 
                 // Close button
@@ -96,8 +127,243 @@ impl GraphchanApp {
             self.spawn_unsubscribe_topic(topic_id);
         }
 
+        if let Some(topic_id) = topic_to_select {
+            self.topic_details.selected = Some(topic_id.clone());
+            self.spawn_load_topic(topic_id);
+        }
+
+        if let Some(topic_id) = self.topic_details.selected.clone() {
+            if let Some(update) = actions.settings_update {
+                self.spawn_update_topic_settings(topic_id.clone(), update);
+            }
+            if let Some(draft) = actions.publish {
+                self.spawn_publish_topic_metadata(topic_id, draft);
+            }
+        }
+
+        if let Some(list) = actions.open_blocklist {
+            // Prefill the subscribe form; the user still confirms it there
+            self.blocking_state.new_blocklist_id = list.name.clone();
+            self.blocking_state.new_blocklist_maintainer = list.maintainer_peer_id;
+            self.blocking_state.new_blocklist_name = list.name;
+            self.blocking_state.current_tab = 1;
+            self.view = ViewState::Blocking;
+            should_close = true;
+        }
+
         if should_close {
             self.show_topic_manager = false;
         }
     }
 }
+
+fn render_topic_details(
+    ui: &mut egui::Ui,
+    state: &mut TopicDetailsState,
+    topic_id: &str,
+    actions: &mut TopicActions,
+) {
+    ui.separator();
+    ui.heading(format!("📡 {}", topic_id));
+
+    if let Some(err) = &state.error {
+        ui.colored_label(Color32::LIGHT_RED, format!("Error: {}", err));
+    }
+
+    let Some(view) = state.details.get(topic_id).cloned() else {
+        if state.loading {
+            ui.horizontal(|ui| {
+                ui.add(egui::Spinner::new());
+                ui.label("Loading topic...");
+            });
+        }
+        return;
+    };
+
+    egui::ScrollArea::vertical()
+        .id_source("topic_details")
+        .max_height(320.0)
+        .show(ui, |ui| {
+            // Founder metadata
+            ui.group(|ui| {
+                match &view.metadata {
+                    Some(metadata) => {
+                        if !metadata.description.is_empty() {
+                            ui.label(&metadata.description);
+                        }
+                        if !metadata.rules.is_empty() {
+                            ui.add_space(4.0);
+                            ui.strong("Rules");
+                            for (index, rule) in metadata.rules.iter().enumerate() {
+                                ui.label(format!("{}. {}", index + 1, rule));
+                            }
+                        }
+                        if !metadata.suggested_blocklists.is_empty() {
+                            ui.add_space(4.0);
+                            ui.strong("Suggested blocklists");
+                            for list in &metadata.suggested_blocklists {
+                                ui.horizontal(|ui| {
+                                    ui.label(&list.name);
+                                    ui.colored_label(Color32::GRAY, short_id(&list.maintainer_peer_id));
+                                    if ui.small_button("Subscribe…").clicked() {
+                                        actions.open_blocklist = Some(list.clone());
+                                    }
+                                });
+                            }
+                        }
+                        ui.add_space(4.0);
+                        ui.colored_label(
+                            Color32::GRAY,
+                            format!(
+                                "Founder {} · version {} · {}",
+                                short_id(&metadata.founder_peer_id),
+                                metadata.version,
+                                policy_label(MEDIA_POLICIES, &metadata.media_policy)
+                            ),
+                        );
+                    }
+                    None => {
+                        ui.colored_label(Color32::GRAY, "No founder metadata yet.");
+                    }
+                }
+                ui.colored_label(
+                    Color32::GRAY,
+                    format!("Proof of work required: {} bits", view.pow_difficulty),
+                );
+            });
+
+            // Local settings; each change is saved immediately
+            ui.add_space(8.0);
+            ui.strong("Your settings");
+            let mut settings = view.settings.clone();
+            ui.add_enabled_ui(!state.saving, |ui| {
+                if ui.checkbox(&mut settings.auto_download, "Auto-download media").changed() {
+                    actions.settings_update = Some(TopicSettingsUpdate {
+                        auto_download: Some(settings.auto_download),
+                        ..Default::default()
+                    });
+                }
+                if ui
+                    .checkbox(&mut settings.mute_strangers, "Hide threads and posts from strangers")
+                    .changed()
+                {
+                    actions.settings_update = Some(TopicSettingsUpdate {
+                        mute_strangers: Some(settings.mute_strangers),
+                        ..Default::default()
+                    });
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Notifications:");
+                    egui::ComboBox::from_id_source("topic_notification_level")
+                        .selected_text(policy_label(NOTIFICATION_LEVELS, &settings.notification_level))
+                        .show_ui(ui, |ui| {
+                            for (value, label) in NOTIFICATION_LEVELS {
+                                if ui
+                                    .selectable_label(settings.notification_level == *value, *label)
+                                    .clicked()
+                                    && settings.notification_level != *value
+                                {
+                                    actions.settings_update = Some(TopicSettingsUpdate {
+                                        notification_level: Some(value.to_string()),
+                                        ..Default::default()
+                                    });
+                                }
+                            }
+                        });
+                });
+            });
+
+            if view.can_edit_metadata {
+                ui.add_space(8.0);
+                render_metadata_editor(ui, state, actions);
+            }
+        });
+}
+
+fn render_metadata_editor(ui: &mut egui::Ui, state: &mut TopicDetailsState, actions: &mut TopicActions) {
+    ui.collapsing("Edit topic metadata (founder)", |ui| {
+        ui.label("Description:");
+        ui.add(egui::TextEdit::multiline(&mut state.draft_description).desired_rows(2));
+        ui.label("Rules (one per line):");
+        ui.add(egui::TextEdit::multiline(&mut state.draft_rules).desired_rows(3));
+        ui.label("Suggested blocklists (one \"PEER_ID Name\" per line):");
+        ui.add(egui::TextEdit::multiline(&mut state.draft_blocklists).desired_rows(2));
+        ui.horizontal(|ui| {
+            ui.label("Suggested PoW bits:");
+            ui.add(
+                egui::TextEdit::singleline(&mut state.draft_pow)
+                    .desired_width(40.0)
+                    .hint_text("none"),
+            );
+            ui.label("Media:");
+            egui::ComboBox::from_id_source("topic_media_policy")
+                .selected_text(policy_label(MEDIA_POLICIES, &state.draft_media_policy))
+                .show_ui(ui, |ui| {
+                    for (value, label) in MEDIA_POLICIES {
+                        ui.selectable_value(&mut state.draft_media_policy, value.to_string(), *label);
+                    }
+                });
+        });
+
+        ui.horizontal(|ui| {
+            let button = ui.add_enabled(!state.saving, egui::Button::new("Publish"));
+            if state.saving {
+                ui.add(egui::Spinner::new());
+            }
+            if button.clicked() {
+                match build_draft(state) {
+                    Ok(draft) => actions.publish = Some(draft),
+                    Err(err) => state.error = Some(err),
+                }
+            }
+        });
+    });
+}
+
+fn build_draft(state: &TopicDetailsState) -> Result<TopicMetadataDraft, String> {
+    let pow_difficulty = match state.draft_pow.trim() {
+        "" => None,
+        bits => Some(
+            bits.parse::<u32>()
+                .map_err(|_| format!("invalid proof-of-work bits: {}", bits))?,
+        ),
+    };
+    let suggested_blocklists = state
+        .draft_blocklists
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let (peer, name) = line.split_once(char::is_whitespace).unwrap_or((line, line));
+            SuggestedBlocklist {
+                maintainer_peer_id: peer.to_string(),
+                name: name.trim().to_string(),
+            }
+        })
+        .collect();
+    Ok(TopicMetadataDraft {
+        description: state.draft_description.trim().to_string(),
+        rules: state
+            .draft_rules
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect(),
+        suggested_blocklists,
+        pow_difficulty,
+        media_policy: state.draft_media_policy.clone(),
+    })
+}
+
+fn policy_label<'a>(options: &[(&str, &'a str)], value: &'a str) -> &'a str {
+    options
+        .iter()
+        .find(|(key, _)| *key == value)
+        .map(|(_, label)| *label)
+        .unwrap_or(value)
+}
+
+fn short_id(peer_id: &str) -> String {
+    peer_id.chars().take(12).collect()
+}
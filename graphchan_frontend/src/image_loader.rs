@@ -100,7 +100,11 @@ impl ImageLoader {
                     // Download if missing
                     if !cache_path.exists() {
                         log::info!("Downloading image: {} -> {}", download_url_owned, cache_filename);
-                        if let Ok(resp) = reqwest::blocking::get(&download_url_owned) {
+                        let response = crate::api::get_shared_client()
+                            .ok()
+                            .and_then(|client| client.get(&download_url_owned).send().ok())
+                            .filter(|resp| resp.status().is_success());
+                        if let Some(resp) = response {
                             if let Ok(bytes) = resp.bytes() {
                                 if fs::write(&cache_path, &bytes).is_err() {
                                     log::warn!("Failed to write cache file for {}", file_id_owned);
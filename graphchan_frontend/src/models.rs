@@ -66,6 +66,9 @@ pub struct PostView {
     pub created_at: String,
     #[serde(default)]
     pub updated_at: Option<String>,
+    /// Tombstone: the author deleted this post; replies stay attached
+    #[serde(default)]
+    pub deleted: bool,
     #[serde(default)]
     pub parent_post_ids: Vec<String>,
     #[serde(default)]
@@ -209,12 +212,34 @@ pub struct DirectMessageView {
     pub body: String,
     pub created_at: String,
     pub read_at: Option<String>,
+    /// Outbox state of our own messages: "queued", "sent" or "acked"
+    #[serde(default)]
+    pub delivery_state: Option<String>,
+    /// ID of the message this one replies to, in our own copy of the conversation
+    #[serde(default)]
+    pub reply_to: Option<String>,
+    #[serde(default)]
+    pub attachments: Vec<DmAttachmentView>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DmAttachmentView {
+    pub id: String,
+    pub original_name: String,
+    pub mime: String,
+    pub size_bytes: i64,
+    /// "available", "pending", "downloading" or "failed"
+    pub download_status: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SendDmRequest {
     pub to_peer_id: String,
     pub body: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reply_to: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -222,6 +247,60 @@ pub struct UnreadCountResponse {
     pub count: usize,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DmGroupView {
+    pub id: String,
+    /// Empty until the creator's membership update arrived
+    pub name: String,
+    pub created_by: String,
+    pub members: Vec<GroupMemberView>,
+    pub is_member: bool,
+    pub last_message_at: Option<String>,
+    pub last_message_preview: Option<String>,
+    pub unread_count: u32,
+}
+
+impl DmGroupView {
+    pub fn display_name(&self) -> &str {
+        if self.name.is_empty() {
+            "Unnamed group"
+        } else {
+            &self.name
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupMemberView {
+    pub peer_id: String,
+    pub username: Option<String>,
+    pub alias: Option<String>,
+}
+
+impl GroupMemberView {
+    pub fn display_name(&self) -> &str {
+        self.username
+            .as_deref()
+            .or(self.alias.as_deref())
+            .unwrap_or(&self.peer_id)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateGroupRequest {
+    pub name: String,
+    pub members: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SendGroupDmRequest {
+    pub body: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reply_to: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<String>,
+}
+
 // Blocking and Moderation models
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -333,3 +412,66 @@ pub struct AddIpBlockRequest {
     pub ip_or_range: String,
     pub reason: Option<String>,
 }
+
+// Topic configuration models
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SuggestedBlocklist {
+    pub maintainer_peer_id: String,
+    pub name: String,
+}
+
+/// A topic founder's signed description and policy.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopicMetadata {
+    pub topic_id: String,
+    pub founder_peer_id: String,
+    pub description: String,
+    #[serde(default)]
+    pub rules: Vec<String>,
+    #[serde(default)]
+    pub suggested_blocklists: Vec<SuggestedBlocklist>,
+    #[serde(default)]
+    pub pow_difficulty: Option<u32>,
+    /// "any", "images_only" or "text_only"
+    pub media_policy: String,
+    pub version: u64,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TopicSettings {
+    pub auto_download: bool,
+    pub mute_strangers: bool,
+    /// "all", "replies" or "off"
+    pub notification_level: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopicView {
+    pub topic_id: String,
+    pub subscribed: bool,
+    pub metadata: Option<TopicMetadata>,
+    pub can_edit_metadata: bool,
+    pub settings: TopicSettings,
+    pub pow_difficulty: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TopicSettingsUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto_download: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mute_strangers: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notification_level: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopicMetadataDraft {
+    pub description: String,
+    pub rules: Vec<String>,
+    pub suggested_blocklists: Vec<SuggestedBlocklist>,
+    pub pow_difficulty: Option<u32>,
+    pub media_policy: String,
+}
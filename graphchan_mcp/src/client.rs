@@ -0,0 +1,225 @@
+use crate::JsonRpcError;
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+use reqwest::multipart::Form;
+use reqwest::{Client, Method, RequestBuilder, Response};
+use serde_json::Value;
+
+/// Backend address used when neither `--api-url` nor `GRAPHCHAN_API_URL` is given.
+pub const DEFAULT_API_URL: &str = "http://127.0.0.1:8080";
+
+/// Settings read from the command line and environment.
+#[derive(Debug, Clone)]
+pub struct ClientConfig {
+    pub api_url: String,
+    pub token: Option<String>,
+    /// Agent named in the metadata of every post we make
+    pub agent_name: Option<String>,
+    pub agent_version: Option<String>,
+}
+
+impl ClientConfig {
+    /// `--api-url` / `--agent` / `--agent-version` override
+    /// `GRAPHCHAN_API_URL` / `GRAPHCHAN_AGENT_NAME` / `GRAPHCHAN_AGENT_VERSION`.
+    pub fn from_env_and_args() -> anyhow::Result<Self> {
+        let env = |key: &str| {
+            std::env::var(key)
+                .ok()
+                .filter(|value| !value.trim().is_empty())
+        };
+        let mut config = Self {
+            api_url: env("GRAPHCHAN_API_URL").unwrap_or_else(|| DEFAULT_API_URL.to_string()),
+            token: env("GRAPHCHAN_API_TOKEN"),
+            agent_name: env("GRAPHCHAN_AGENT_NAME"),
+            agent_version: env("GRAPHCHAN_AGENT_VERSION"),
+        };
+
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| anyhow::anyhow!("{arg} needs a value"))
+            };
+            match arg.as_str() {
+                "--api-url" => config.api_url = value()?,
+                "--agent" => config.agent_name = Some(value()?),
+                "--agent-version" => config.agent_version = Some(value()?),
+                other => anyhow::bail!("unknown argument: {other}"),
+            }
+        }
+        config.api_url = config.api_url.trim_end_matches('/').to_string();
+        Ok(config)
+    }
+}
+
+/// Thin JSON client for the backend's REST API.
+///
+/// Responses are passed through as `Value`s so tools return every field the
+/// backend sends; failed requests become JSON-RPC errors carrying the
+/// backend's own error message.
+pub struct GraphchanClient {
+    http: Client,
+    base_url: String,
+    agent_name: Option<String>,
+    agent_version: Option<String>,
+}
+
+impl GraphchanClient {
+    /// Authenticates with the configured token.
+    /// Create one with `graphchan_backend token create <name> --scopes read,post,dm`.
+    pub fn new(config: &ClientConfig) -> anyhow::Result<Self> {
+        let mut headers = HeaderMap::new();
+        if let Some(token) = &config.token {
+            let mut value = HeaderValue::from_str(&format!("Bearer {}", token.trim()))?;
+            value.set_sensitive(true);
+            headers.insert(AUTHORIZATION, value);
+        } else {
+            eprintln!("GRAPHCHAN_API_TOKEN is not set; requests will be rejected unless API auth is disabled");
+        }
+        Ok(Self {
+            http: Client::builder().default_headers(headers).build()?,
+            base_url: config.api_url.clone(),
+            agent_name: config.agent_name.clone(),
+            agent_version: config.agent_version.clone(),
+        })
+    }
+
+    pub fn agent_name(&self) -> Option<&str> {
+        self.agent_name.as_deref()
+    }
+
+    /// `PostMetadata` attached to posts and thread OPs made through the server.
+    pub fn post_metadata(&self) -> Value {
+        let mut metadata = serde_json::json!({
+            "client": concat!("graphchan_mcp/", env!("CARGO_PKG_VERSION")),
+        });
+        if let Some(name) = &self.agent_name {
+            metadata["agent"] = serde_json::json!({ "name": name, "version": self.agent_version });
+        }
+        metadata
+    }
+
+    /// Makes sure our agent has a delegated posting key, so its posts aren't refused.
+    ///
+    /// Registering needs an admin-scoped token; with a narrower one the agent
+    /// must already have been added with `POST /identity/agents`.
+    pub async fn ensure_agent_registered(&self) {
+        let Some(name) = &self.agent_name else {
+            return;
+        };
+        let registered = self
+            .get("/identity/agents", &[])
+            .await
+            .ok()
+            .and_then(|agents| agents.get("agents").cloned())
+            .and_then(|names| serde_json::from_value::<Vec<String>>(names).ok())
+            .is_some_and(|names| names.contains(name));
+        if registered {
+            return;
+        }
+        let body = serde_json::json!({ "name": name });
+        if let Err(err) = self
+            .send(Method::POST, "/identity/agents", Some(&body))
+            .await
+        {
+            eprintln!(
+                "Agent '{name}' has no posting key and registering it failed ({}); posts will be rejected until it is added",
+                err.message
+            );
+        }
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    pub async fn get(&self, path: &str, query: &[(&str, String)]) -> Result<Value, JsonRpcError> {
+        read_response(
+            self.http
+                .get(self.url(path))
+                .query(query)
+                .send()
+                .await
+                .map_err(map_req_err)?,
+        )
+        .await
+    }
+
+    pub async fn send(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&Value>,
+    ) -> Result<Value, JsonRpcError> {
+        let mut request = self.http.request(method, self.url(path));
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+        read_response(request.send().await.map_err(map_req_err)?).await
+    }
+
+    pub async fn send_multipart(&self, path: &str, form: Form) -> Result<Value, JsonRpcError> {
+        read_response(
+            self.http
+                .post(self.url(path))
+                .multipart(form)
+                .send()
+                .await
+                .map_err(map_req_err)?,
+        )
+        .await
+    }
+
+    /// A request for the `/events` stream; sent by the reply watcher.
+    pub fn events(&self, query: &[(&str, String)]) -> RequestBuilder {
+        self.http.get(self.url("/events")).query(query)
+    }
+}
+
+/// Percent-encodes a value for use as one path segment.
+pub fn segment(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+/// Body of a successful response as JSON (`{"status": code}` if empty); the backend's
+/// `message` otherwise.
+async fn read_response(response: Response) -> Result<Value, JsonRpcError> {
+    let status = response.status();
+    let body = response.text().await.map_err(map_req_err)?;
+    if !status.is_success() {
+        let message = serde_json::from_str::<Value>(&body)
+            .ok()
+            .and_then(|error| {
+                error
+                    .get("message")
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+            })
+            .unwrap_or(body);
+        return Err(JsonRpcError {
+            code: -32000,
+            message: format!("{status}: {message}"),
+            data: None,
+        });
+    }
+    if body.trim().is_empty() {
+        return Ok(serde_json::json!({ "status": status.as_u16() }));
+    }
+    Ok(serde_json::from_str(&body).unwrap_or(Value::String(body)))
+}
+
+pub fn map_req_err(e: reqwest::Error) -> JsonRpcError {
+    JsonRpcError {
+        code: -32000,
+        message: format!("Request failed: {}", e),
+        data: None,
+    }
+}
@@ -1,7 +1,14 @@
+mod client;
+mod replies;
+mod tools;
+
+use client::{ClientConfig, GraphchanClient};
+use replies::{ReplyWatcher, MAX_REPLIES, REPLIES_URI};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use std::io::{self, BufRead};
-use reqwest::Client;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::Mutex;
 
 #[derive(Serialize, Deserialize, Debug)]
 struct JsonRpcRequest {
@@ -26,67 +33,40 @@ struct JsonRpcError {
     data: Option<Value>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct ThreadSummary {
-    id: String,
-    title: String,
-    creator_peer_id: Option<String>,
-    created_at: String,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-struct ThreadDetails {
-    thread: ThreadSummary,
-    posts: Vec<PostView>,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-struct PostView {
-    id: String,
-    thread_id: String,
-    author_peer_id: Option<String>,
-    body: String,
-    created_at: String,
-    parent_post_ids: Vec<String>,
-}
+/// MCP protocol revision we answer `initialize` with.
+const PROTOCOL_VERSION: &str = "2024-11-05";
 
-#[derive(Serialize, Deserialize, Debug)]
-struct ConversationView {
-    id: String,
-    peer_id: String,
-    peer_username: Option<String>,
-    peer_alias: Option<String>,
-    last_message_at: Option<String>,
-    last_message_preview: Option<String>,
-    unread_count: u32,
-}
+/// Stdout shared by responses and notifications, one JSON message per line.
+#[derive(Clone)]
+struct Output(Arc<Mutex<Box<dyn AsyncWrite + Send + Unpin>>>);
 
-#[derive(Serialize, Deserialize, Debug)]
-struct DirectMessageView {
-    id: String,
-    conversation_id: String,
-    from_peer_id: String,
-    to_peer_id: String,
-    body: String,
-    created_at: String,
-    read_at: Option<String>,
-}
+impl Output {
+    fn new(writer: impl AsyncWrite + Send + Unpin + 'static) -> Self {
+        Self(Arc::new(Mutex::new(Box::new(writer))))
+    }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct UnreadCountResponse {
-    count: usize,
+    async fn send(&self, message: &Value) -> std::io::Result<()> {
+        let mut line = message.to_string();
+        line.push('\n');
+        let mut stdout = self.0.lock().await;
+        stdout.write_all(line.as_bytes()).await?;
+        stdout.flush().await
+    }
 }
 
-const API_URL: &str = "http://127.0.0.1:8080";
-
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    let client = Client::new();
-    let stdin = io::stdin();
-    
+    let config = ClientConfig::from_env_and_args()?;
+    let client = Arc::new(GraphchanClient::new(&config)?);
+    client.ensure_agent_registered().await;
+
+    let output = Output::new(tokio::io::stdout());
+    let replies = Arc::new(ReplyWatcher::default());
+    tokio::spawn(replies.clone().run(client.clone(), output.clone()));
+
     // Simple line-based JSON-RPC processing
-    for line in stdin.lock().lines() {
-        let line = line?;
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    while let Some(line) = lines.next_line().await? {
         if line.trim().is_empty() {
             continue;
         }
@@ -99,18 +79,56 @@ async fn main() -> anyhow::Result<()> {
             }
         };
 
-        let response = handle_request(&client, request).await;
-        let response_json = serde_json::to_string(&response)?;
-        println!("{}", response_json);
+        // Notifications from the client expect no answer
+        let is_notification = request.method.starts_with("notifications/");
+        let response = handle_request(&client, &replies, request).await;
+        if !is_notification {
+            output.send(&serde_json::to_value(&response)?).await?;
+        }
     }
 
     Ok(())
 }
 
-async fn handle_request(client: &Client, req: JsonRpcRequest) -> JsonRpcResponse {
+async fn handle_request(
+    client: &GraphchanClient,
+    replies: &ReplyWatcher,
+    req: JsonRpcRequest,
+) -> JsonRpcResponse {
     let result = match req.method.as_str() {
-        "mcp.list_tools" => Ok(list_tools()),
-        "mcp.call_tool" => call_tool(client, req.params).await,
+        "initialize" => Ok(json!({
+            "protocolVersion": PROTOCOL_VERSION,
+            "capabilities": { "tools": {}, "resources": { "subscribe": true } },
+            "serverInfo": { "name": "graphchan_mcp", "version": env!("CARGO_PKG_VERSION") },
+        })),
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(
+            json!({ "tools": tools::definitions().into_iter().map(mcp_tool).collect::<Vec<_>>() }),
+        ),
+        "tools/call" => match tool_call(client, replies, req.params.as_ref()).await {
+            Ok(value) => Ok(tool_result(&value, false)),
+            Err(err) if err.code == -32000 => Ok(tool_result(&Value::String(err.message), true)),
+            Err(err) => Err(err),
+        },
+        // Original method names; results are returned as plain JSON
+        "mcp.list_tools" => Ok(json!({ "tools": tools::definitions() })),
+        "mcp.call_tool" => tool_call(client, replies, req.params.as_ref()).await,
+        "resources/list" => Ok(json!({
+            "resources": [{
+                "uri": REPLIES_URI,
+                "name": "Replies to my posts",
+                "description": "Replies to this node's posts (or its agent's), newest first",
+                "mimeType": "application/json",
+            }],
+        })),
+        "resources/read" => read_resource(replies, req.params.as_ref()),
+        "resources/subscribe" | "resources/unsubscribe" => {
+            resource_uri(req.params.as_ref()).map(|_| {
+                replies.set_subscribed(req.method == "resources/subscribe");
+                json!({})
+            })
+        }
+        method if method.starts_with("notifications/") => Ok(Value::Null),
         _ => Err(JsonRpcError {
             code: -32601,
             message: "Method not found".to_string(),
@@ -134,241 +152,77 @@ async fn handle_request(client: &Client, req: JsonRpcRequest) -> JsonRpcResponse
     }
 }
 
-fn list_tools() -> Value {
-    serde_json::json!({
-        "tools": [
-            {
-                "name": "read_thread",
-                "description": "Read a thread and its posts",
-                "parameters": {
-                    "type": "object",
-                    "properties": {
-                        "thread_id": { "type": "string" }
-                    },
-                    "required": ["thread_id"]
-                }
-            },
-            {
-                "name": "read_latest_posts",
-                "description": "Read the latest N posts of a thread",
-                "parameters": {
-                    "type": "object",
-                    "properties": {
-                        "thread_id": { "type": "string" },
-                        "n": { "type": "integer" }
-                    },
-                    "required": ["thread_id", "n"]
-                }
-            },
-            {
-                "name": "read_parents",
-                "description": "Read the parent posts of a specific post",
-                "parameters": {
-                    "type": "object",
-                    "properties": {
-                        "post_id": { "type": "string" },
-                        "n": { "type": "integer", "description": "Number of generations to go up (optional, default 1)" }
-                    },
-                    "required": ["post_id"]
-                }
-            },
-            {
-                "name": "list_threads",
-                "description": "List all available threads",
-                "parameters": {
-                    "type": "object",
-                    "properties": {},
-                }
-            },
-            {
-                "name": "list_conversations",
-                "description": "List all direct message conversations with peers",
-                "parameters": {
-                    "type": "object",
-                    "properties": {},
-                }
-            },
-            {
-                "name": "read_messages",
-                "description": "Read direct messages with a specific peer",
-                "parameters": {
-                    "type": "object",
-                    "properties": {
-                        "peer_id": {
-                            "type": "string",
-                            "description": "The peer ID (GPG fingerprint) to read messages from"
-                        },
-                        "limit": {
-                            "type": "integer",
-                            "description": "Maximum number of messages to retrieve (default 50)"
-                        }
-                    },
-                    "required": ["peer_id"]
-                }
-            },
-            {
-                "name": "send_dm",
-                "description": "Send an encrypted direct message to a peer",
-                "parameters": {
-                    "type": "object",
-                    "properties": {
-                        "to_peer_id": {
-                            "type": "string",
-                            "description": "The peer ID (GPG fingerprint) to send the message to"
-                        },
-                        "body": {
-                            "type": "string",
-                            "description": "The message content to send"
-                        }
-                    },
-                    "required": ["to_peer_id", "body"]
-                }
-            },
-            {
-                "name": "get_unread_count",
-                "description": "Get the total number of unread direct messages",
-                "parameters": {
-                    "type": "object",
-                    "properties": {},
-                }
-            }
-        ]
-    })
-}
-
-async fn call_tool(client: &Client, params: Option<Value>) -> Result<Value, JsonRpcError> {
+async fn tool_call(
+    client: &GraphchanClient,
+    replies: &ReplyWatcher,
+    params: Option<&Value>,
+) -> Result<Value, JsonRpcError> {
     let params = params.ok_or(JsonRpcError {
         code: -32602,
         message: "Invalid params".to_string(),
         data: None,
     })?;
 
-    let name = params.get("name").and_then(|v| v.as_str()).ok_or(JsonRpcError {
-        code: -32602,
-        message: "Missing tool name".to_string(),
-        data: None,
-    })?;
+    let name = params
+        .get("name")
+        .and_then(|v| v.as_str())
+        .ok_or(JsonRpcError {
+            code: -32602,
+            message: "Missing tool name".to_string(),
+            data: None,
+        })?;
 
-    let default_args = serde_json::json!({});
+    let default_args = json!({});
     let args = params.get("arguments").unwrap_or(&default_args);
+    tools::call_tool(client, replies, name, args).await
+}
 
-    match name {
-        "list_threads" => {
-            let threads: Vec<ThreadSummary> = client.get(format!("{}/threads", API_URL))
-                .send().await.map_err(map_req_err)?
-                .json().await.map_err(map_req_err)?;
-            Ok(serde_json::to_value(threads).unwrap())
-        }
-        "read_thread" => {
-            let thread_id = args.get("thread_id").and_then(|v| v.as_str()).ok_or(JsonRpcError {
-                code: -32602,
-                message: "Missing thread_id".to_string(),
-                data: None,
-            })?;
-            
-            let details: ThreadDetails = client.get(format!("{}/threads/{}", API_URL, thread_id))
-                .send().await.map_err(map_req_err)?
-                .json().await.map_err(map_req_err)?;
-            
-            Ok(serde_json::to_value(details).unwrap())
-        }
-        "read_latest_posts" => {
-            let thread_id = args.get("thread_id").and_then(|v| v.as_str()).ok_or(JsonRpcError {
-                code: -32602,
-                message: "Missing thread_id".to_string(),
-                data: None,
-            })?;
-            let n = args.get("n").and_then(|v| v.as_u64()).unwrap_or(10) as usize;
-
-            let details: ThreadDetails = client.get(format!("{}/threads/{}", API_URL, thread_id))
-                .send().await.map_err(map_req_err)?
-                .json().await.map_err(map_req_err)?;
-            
-            let mut posts = details.posts;
-            posts.sort_by(|a, b| b.created_at.cmp(&a.created_at)); // Newest first
-            posts.truncate(n);
-            
-            Ok(serde_json::to_value(posts).unwrap())
-        }
-        "read_parents" => {
-            // This is harder because we need to find the thread first or use an API that gets a post directly.
-            // Assuming we don't have a direct "get post" API, we might need to search.
-            // But wait, the backend usually has /posts/{id} or similar?
-            // Let's assume we have to scan threads or the user provides thread_id.
-            // For now, let's implement a naive search or error if not supported.
-            // Actually, `read_parents` implies we know the post.
-            // If the backend doesn't support fetching a post by ID directly, this is expensive.
-            // Let's assume we can fetch the thread if we knew it.
-            // But we don't.
-            // Let's return an error for now saying "Not implemented efficiently".
-            Err(JsonRpcError {
-                code: -32603,
-                message: "read_parents not implemented yet (requires backend support for direct post lookup)".to_string(),
-                data: None,
-            })
-        }
-        "list_conversations" => {
-            let conversations: Vec<ConversationView> = client.get(format!("{}/dms/conversations", API_URL))
-                .send().await.map_err(map_req_err)?
-                .json().await.map_err(map_req_err)?;
-            Ok(serde_json::to_value(conversations).unwrap())
-        }
-        "read_messages" => {
-            let peer_id = args.get("peer_id").and_then(|v| v.as_str()).ok_or(JsonRpcError {
-                code: -32602,
-                message: "Missing peer_id".to_string(),
-                data: None,
-            })?;
-            let limit = args.get("limit").and_then(|v| v.as_u64()).unwrap_or(50);
-
-            let messages: Vec<DirectMessageView> = client.get(format!("{}/dms/{}/messages?limit={}", API_URL, peer_id, limit))
-                .send().await.map_err(map_req_err)?
-                .json().await.map_err(map_req_err)?;
-
-            Ok(serde_json::to_value(messages).unwrap())
-        }
-        "send_dm" => {
-            let to_peer_id = args.get("to_peer_id").and_then(|v| v.as_str()).ok_or(JsonRpcError {
-                code: -32602,
-                message: "Missing to_peer_id".to_string(),
-                data: None,
-            })?;
-            let body = args.get("body").and_then(|v| v.as_str()).ok_or(JsonRpcError {
-                code: -32602,
-                message: "Missing body".to_string(),
-                data: None,
-            })?;
+/// A tool definition as `tools/list` expects it (`inputSchema` instead of `parameters`).
+fn mcp_tool(mut tool: Value) -> Value {
+    if let Some(schema) = tool
+        .as_object_mut()
+        .and_then(|tool| tool.remove("parameters"))
+    {
+        tool["inputSchema"] = schema;
+    }
+    tool
+}
 
-            let payload = serde_json::json!({
-                "to_peer_id": to_peer_id,
-                "body": body
-            });
+/// Wraps a tool's JSON output as MCP text content; backend failures are reported with `isError`.
+fn tool_result(value: &Value, is_error: bool) -> Value {
+    let text = match value {
+        Value::String(text) => text.clone(),
+        other => serde_json::to_string_pretty(other).unwrap_or_default(),
+    };
+    json!({ "content": [{ "type": "text", "text": text }], "isError": is_error })
+}
 
-            let message: DirectMessageView = client.post(format!("{}/dms/send", API_URL))
-                .json(&payload)
-                .send().await.map_err(map_req_err)?
-                .json().await.map_err(map_req_err)?;
+fn read_resource(replies: &ReplyWatcher, params: Option<&Value>) -> Result<Value, JsonRpcError> {
+    let uri = resource_uri(params)?;
+    Ok(json!({
+        "contents": [{
+            "uri": uri,
+            "mimeType": "application/json",
+            "text": Value::Array(replies.recent(MAX_REPLIES)).to_string(),
+        }],
+    }))
+}
 
-            Ok(serde_json::to_value(message).unwrap())
-        }
-        "get_unread_count" => {
-            let response: UnreadCountResponse = client.get(format!("{}/dms/unread/count", API_URL))
-                .send().await.map_err(map_req_err)?
-                .json().await.map_err(map_req_err)?;
-            Ok(serde_json::to_value(response).unwrap())
-        }
-        _ => Err(JsonRpcError {
-            code: -32601,
-            message: format!("Tool {} not found", name),
+fn resource_uri(params: Option<&Value>) -> Result<&str, JsonRpcError> {
+    let uri = params
+        .and_then(|params| params.get("uri"))
+        .and_then(Value::as_str);
+    match uri {
+        Some(REPLIES_URI) => Ok(REPLIES_URI),
+        Some(other) => Err(JsonRpcError {
+            code: -32002,
+            message: format!("Resource not found: {other}"),
+            data: None,
+        }),
+        None => Err(JsonRpcError {
+            code: -32602,
+            message: "Missing uri".to_string(),
             data: None,
         }),
-    }
-}
-
-fn map_req_err(e: reqwest::Error) -> JsonRpcError {
-    JsonRpcError {
-        code: -32000,
-        message: format!("Request failed: {}", e),
-        data: None,
     }
 }
@@ -0,0 +1,406 @@
+use crate::client::{map_req_err, segment, GraphchanClient};
+use crate::{JsonRpcError, Output};
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Resource listing the replies collected so far.
+pub const REPLIES_URI: &str = "graphchan://replies";
+/// Replies kept in memory, newest first.
+pub const MAX_REPLIES: usize = 100;
+/// Wait before reconnecting to `/events` after the stream drops.
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Watches the backend's `/events` stream for replies to our posts.
+///
+/// "Our posts" are the node's own, narrowed to those naming our agent when
+/// one is configured. Replies are kept for `list_replies` and the
+/// `graphchan://replies` resource; subscribed clients get a
+/// `notifications/resources/updated` for each new one.
+#[derive(Default)]
+pub struct ReplyWatcher {
+    replies: Mutex<VecDeque<Value>>,
+    subscribed: AtomicBool,
+}
+
+impl ReplyWatcher {
+    pub fn recent(&self, limit: usize) -> Vec<Value> {
+        let replies = self
+            .replies
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        replies.iter().take(limit).cloned().collect()
+    }
+
+    pub fn set_subscribed(&self, subscribed: bool) {
+        self.subscribed.store(subscribed, Ordering::Relaxed);
+    }
+
+    /// Follows the event stream until the process exits, reconnecting where it left off.
+    pub async fn run(self: Arc<Self>, client: Arc<GraphchanClient>, output: Output) {
+        let self_id = loop {
+            match client.get("/peers/self", &[]).await {
+                Ok(peer) => {
+                    if let Some(id) = peer.get("id").and_then(Value::as_str) {
+                        break id.to_string();
+                    }
+                    eprintln!("Backend has no local identity yet; reply notifications are paused");
+                }
+                Err(err) => eprintln!(
+                    "Cannot load our peer ID for reply notifications: {}",
+                    err.message
+                ),
+            }
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        };
+
+        let mut cursor = None;
+        loop {
+            if let Err(err) = self.follow(&client, &self_id, &mut cursor, &output).await {
+                eprintln!("Event stream interrupted: {}", err.message);
+            }
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    }
+
+    /// Reads server-sent events until the stream ends, keeping `cursor` at the last `id`.
+    async fn follow(
+        &self,
+        client: &GraphchanClient,
+        self_id: &str,
+        cursor: &mut Option<String>,
+        output: &Output,
+    ) -> Result<(), JsonRpcError> {
+        let mut request = client.events(&[("types", "post_received".to_string())]);
+        if let Some(last_id) = cursor.as_deref() {
+            request = request.header("Last-Event-ID", last_id);
+        }
+        let mut response = request.send().await.map_err(map_req_err)?;
+        if !response.status().is_success() {
+            return Err(JsonRpcError {
+                code: -32000,
+                message: format!("/events returned {}", response.status()),
+                data: None,
+            });
+        }
+
+        let mut buffer: Vec<u8> = Vec::new();
+        while let Some(chunk) = response.chunk().await.map_err(map_req_err)? {
+            buffer.extend_from_slice(&chunk);
+            while let Some(end) = buffer.windows(2).position(|window| window == b"\n\n") {
+                let frame: Vec<u8> = buffer.drain(..end + 2).collect();
+                let (id, data) = parse_frame(&String::from_utf8_lossy(&frame));
+                if id.is_some() {
+                    *cursor = id;
+                }
+                // Keep-alive comments carry no data; `gap` notices carry no post
+                let Some(event) = data.and_then(|data| serde_json::from_str::<Value>(&data).ok())
+                else {
+                    continue;
+                };
+                if let Some(reply) = self.reply_to_us(client, self_id, &event).await {
+                    self.record(reply, output).await;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The reply described by a `post_received` event, if it answers one of our posts.
+    async fn reply_to_us(
+        &self,
+        client: &GraphchanClient,
+        self_id: &str,
+        event: &Value,
+    ) -> Option<Value> {
+        if event.get("type").and_then(Value::as_str) != Some("post_received") {
+            return None;
+        }
+        if event.get("author_peer_id").and_then(Value::as_str) == Some(self_id) {
+            return None;
+        }
+        let thread_id = event.get("thread_id")?.as_str()?;
+        let post_id = event.get("post_id")?.as_str()?;
+
+        let post = match client
+            .get(&format!("/posts/{}", segment(post_id)), &[])
+            .await
+        {
+            Ok(response) => response.get("post")?.clone(),
+            Err(err) => {
+                eprintln!(
+                    "Cannot load post {post_id} to check for replies: {}",
+                    err.message
+                );
+                return None;
+            }
+        };
+        let mut ours = Vec::new();
+        for parent_id in post
+            .get("parent_post_ids")?
+            .as_array()?
+            .iter()
+            .filter_map(Value::as_str)
+        {
+            // Parents we never received (or that were rejected) can't be ours
+            let Ok(parent) = client
+                .get(&format!("/posts/{}", segment(parent_id)), &[])
+                .await
+            else {
+                continue;
+            };
+            if parent
+                .get("post")
+                .is_some_and(|parent| is_ours(parent, self_id, client.agent_name()))
+            {
+                ours.push(parent_id.to_string());
+            }
+        }
+        if ours.is_empty() {
+            return None;
+        }
+
+        Some(json!({
+            "thread_id": thread_id,
+            "in_reply_to": ours,
+            "post": post,
+        }))
+    }
+
+    async fn record(&self, reply: Value, output: &Output) {
+        {
+            let mut replies = self
+                .replies
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            replies.push_front(reply);
+            replies.truncate(MAX_REPLIES);
+        }
+        if self.subscribed.load(Ordering::Relaxed) {
+            let notification = json!({
+                "jsonrpc": "2.0",
+                "method": "notifications/resources/updated",
+                "params": { "uri": REPLIES_URI },
+            });
+            if let Err(err) = output.send(&notification).await {
+                eprintln!("Failed to send reply notification: {}", err);
+            }
+        }
+    }
+}
+
+/// Whether `post` is by us, and by our agent when one is configured.
+fn is_ours(post: &Value, self_id: &str, agent_name: Option<&str>) -> bool {
+    if post.get("author_peer_id").and_then(Value::as_str) != Some(self_id) {
+        return false;
+    }
+    let Some(agent_name) = agent_name else {
+        return true;
+    };
+    post.pointer("/metadata/agent/name").and_then(Value::as_str) == Some(agent_name)
+}
+
+/// The `id` and joined `data` lines of one server-sent event.
+fn parse_frame(frame: &str) -> (Option<String>, Option<String>) {
+    let mut id = None;
+    let mut data = Vec::new();
+    for line in frame.lines() {
+        if let Some(value) = line.strip_prefix("id:") {
+            id = Some(value.trim().to_string());
+        } else if let Some(value) = line.strip_prefix("data:") {
+            data.push(value.strip_prefix(' ').unwrap_or(value));
+        }
+    }
+    (id, (!data.is_empty()).then(|| data.join("\n")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::ClientConfig;
+    use std::sync::atomic::AtomicUsize;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn post(author: &str, agent: Option<&str>, parents: &[&str]) -> Value {
+        let mut post =
+            json!({ "author_peer_id": author, "parent_post_ids": parents, "metadata": {} });
+        if let Some(agent) = agent {
+            post["metadata"]["agent"] = json!({ "name": agent });
+        }
+        json!({ "post": post })
+    }
+
+    fn received(post_id: &str, author: &str) -> Value {
+        json!({ "type": "post_received", "thread_id": "t1", "post_id": post_id, "author_peer_id": author })
+    }
+
+    /// A backend serving our post `mine` (by agent `scout`), our other agent's
+    /// `sibling`, a stranger's `theirs` and the `reply` under test; anything else
+    /// is a 404. Returns its URL and a count of the lookups it answered.
+    async fn backend(reply_parents: &[&str]) -> (String, Arc<AtomicUsize>) {
+        let posts = [
+            ("/posts/reply", post("BOB", None, reply_parents)),
+            ("/posts/mine", post("ME", Some("scout"), &[])),
+            ("/posts/sibling", post("ME", Some("other-bot"), &[])),
+            ("/posts/theirs", post("BOB", None, &[])),
+        ];
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let url = format!("http://{}", listener.local_addr().expect("address"));
+        let lookups = Arc::new(AtomicUsize::new(0));
+        let counter = lookups.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                // Lookups are bodiless GETs, so the head is the whole request
+                let mut head = Vec::new();
+                let mut chunk = [0u8; 1024];
+                while !head.windows(4).any(|window| window == b"\r\n\r\n") {
+                    match stream.read(&mut chunk).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(read) => head.extend_from_slice(&chunk[..read]),
+                    }
+                }
+                counter.fetch_add(1, Ordering::SeqCst);
+                let head = String::from_utf8_lossy(&head);
+                let path = head.split(' ').nth(1).unwrap_or_default();
+                let (status, body) = match posts.iter().find(|(route, _)| *route == path) {
+                    Some((_, body)) => ("200 OK", body.to_string()),
+                    None => (
+                        "404 Not Found",
+                        json!({ "message": "not found" }).to_string(),
+                    ),
+                };
+                let response = format!(
+                    "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+                let _ = stream.shutdown().await;
+            }
+        });
+        (url, lookups)
+    }
+
+    fn client(url: &str, agent_name: Option<&str>) -> GraphchanClient {
+        GraphchanClient::new(&ClientConfig {
+            api_url: url.to_string(),
+            token: None,
+            agent_name: agent_name.map(str::to_string),
+            agent_version: None,
+        })
+        .expect("client")
+    }
+
+    #[tokio::test]
+    async fn replies_to_our_posts_are_detected() {
+        let (url, _) = backend(&["theirs", "mine", "missing"]).await;
+        let watcher = ReplyWatcher::default();
+
+        let reply = watcher
+            .reply_to_us(&client(&url, None), "ME", &received("reply", "BOB"))
+            .await
+            .expect("a reply to us");
+
+        assert_eq!(reply["thread_id"], "t1");
+        assert_eq!(reply["in_reply_to"], json!(["mine"]));
+        assert_eq!(reply["post"]["author_peer_id"], "BOB");
+    }
+
+    #[tokio::test]
+    async fn with_an_agent_only_its_own_posts_count() {
+        let (url, _) = backend(&["sibling", "mine"]).await;
+        let watcher = ReplyWatcher::default();
+        let event = received("reply", "BOB");
+
+        let as_scout = watcher
+            .reply_to_us(&client(&url, Some("scout")), "ME", &event)
+            .await
+            .expect("a reply to scout");
+        assert_eq!(as_scout["in_reply_to"], json!(["mine"]));
+
+        let as_node = watcher
+            .reply_to_us(&client(&url, None), "ME", &event)
+            .await
+            .expect("a reply to the node");
+        assert_eq!(as_node["in_reply_to"], json!(["sibling", "mine"]));
+    }
+
+    #[tokio::test]
+    async fn unrelated_posts_and_our_own_are_not_replies() {
+        let (url, lookups) = backend(&["theirs"]).await;
+        let watcher = ReplyWatcher::default();
+        let client = client(&url, None);
+
+        assert!(watcher
+            .reply_to_us(&client, "ME", &received("reply", "BOB"))
+            .await
+            .is_none());
+        let looked_up = lookups.load(Ordering::SeqCst);
+
+        // Our own posts and other event types are dropped without a lookup
+        assert!(watcher
+            .reply_to_us(&client, "ME", &received("reply", "ME"))
+            .await
+            .is_none());
+        let mut other = received("reply", "BOB");
+        other["type"] = json!("post_updated");
+        assert!(watcher.reply_to_us(&client, "ME", &other).await.is_none());
+        assert_eq!(lookups.load(Ordering::SeqCst), looked_up);
+    }
+
+    #[tokio::test]
+    async fn subscribers_are_notified_of_each_new_reply() {
+        let (writer, mut reader) = tokio::io::duplex(4096);
+        let output = Output::new(writer);
+        let watcher = ReplyWatcher::default();
+
+        watcher.record(json!({ "n": 1 }), &output).await;
+        watcher.set_subscribed(true);
+        watcher.record(json!({ "n": 2 }), &output).await;
+        watcher.set_subscribed(false);
+        watcher.record(json!({ "n": 3 }), &output).await;
+        drop(output);
+
+        let mut written = String::new();
+        reader.read_to_string(&mut written).await.expect("output");
+        let notifications: Vec<Value> = written
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("json line"))
+            .collect();
+        assert_eq!(
+            notifications,
+            [json!({
+                "jsonrpc": "2.0",
+                "method": "notifications/resources/updated",
+                "params": { "uri": REPLIES_URI },
+            })]
+        );
+        assert_eq!(watcher.recent(2), [json!({ "n": 3 }), json!({ "n": 2 })]);
+    }
+
+    #[tokio::test]
+    async fn only_the_newest_replies_are_kept() {
+        let (writer, _reader) = tokio::io::duplex(64);
+        let output = Output::new(writer);
+        let watcher = ReplyWatcher::default();
+
+        for n in 0..MAX_REPLIES + 5 {
+            watcher.record(json!({ "n": n }), &output).await;
+        }
+
+        let kept = watcher.recent(usize::MAX);
+        assert_eq!(kept.len(), MAX_REPLIES);
+        assert_eq!(kept[0], json!({ "n": MAX_REPLIES + 4 }));
+    }
+
+    #[test]
+    fn frames_join_data_lines_and_skip_comments() {
+        assert_eq!(
+            parse_frame("id: 42\ndata: {\"a\":\ndata:1}\n\n"),
+            (Some("42".to_string()), Some("{\"a\":\n1}".to_string()))
+        );
+        assert_eq!(parse_frame(": keep-alive\n\n"), (None, None));
+    }
+}
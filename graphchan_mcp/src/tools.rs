@@ -0,0 +1,846 @@
+use crate::client::{segment, GraphchanClient};
+use crate::replies::ReplyWatcher;
+use crate::JsonRpcError;
+use reqwest::multipart::{Form, Part};
+use reqwest::Method;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+/// The slice of a post needed for ordering; the full JSON is what tools return.
+#[derive(Deserialize)]
+struct PostTimestamp {
+    created_at: String,
+}
+
+/// Tool definitions; `parameters` is the JSON schema of `arguments`.
+pub fn definitions() -> Vec<Value> {
+    let string = |description: &str| json!({ "type": "string", "description": description });
+    let integer = |description: &str| json!({ "type": "integer", "description": description });
+    let strings = |description: &str| json!({ "type": "array", "items": { "type": "string" }, "description": description });
+    let tool = |name: &str, description: &str, properties: Value, required_args: &[&str]| {
+        json!({
+            "name": name,
+            "description": description,
+            "parameters": { "type": "object", "properties": properties, "required": required_args },
+        })
+    };
+    let post_id = || json!({ "post_id": string("The post ID") });
+
+    vec![
+        // Reading
+        tool(
+            "list_threads",
+            "List known threads, newest first",
+            json!({ "limit": integer("Maximum threads (default 50)") }),
+            &[],
+        ),
+        tool(
+            "read_thread",
+            "Read a thread and all its posts",
+            json!({ "thread_id": string("The thread ID") }),
+            &["thread_id"],
+        ),
+        tool(
+            "read_latest_posts",
+            "Read the latest N posts of a thread",
+            json!({ "thread_id": string("The thread ID"), "n": integer("Number of posts (default 10)") }),
+            &["thread_id"],
+        ),
+        tool("get_post", "Read a single post", post_id(), &["post_id"]),
+        tool(
+            "read_parents",
+            "Read the posts a post replies to, up to N generations up, closest first",
+            json!({
+                "post_id": string("The post ID"),
+                "n": integer("Number of generations to go up (default 1)"),
+            }),
+            &["post_id"],
+        ),
+        tool(
+            "read_replies",
+            "Read the replies below a post, up to N generations down, closest first",
+            json!({
+                "post_id": string("The post ID"),
+                "n": integer("Number of generations to go down (default: the whole subtree)"),
+            }),
+            &["post_id"],
+        ),
+        tool(
+            "post_path",
+            "Read the reply chain connecting two posts through their nearest common ancestor",
+            json!({
+                "post_id": string("The post to start from"),
+                "other_post_id": string("The post to end at"),
+            }),
+            &["post_id", "other_post_id"],
+        ),
+        tool(
+            "recent_posts",
+            "Latest posts across all threads",
+            json!({ "limit": integer("Maximum posts (default 50)") }),
+            &[],
+        ),
+        tool(
+            "post_history",
+            "A post and its earlier edited versions",
+            post_id(),
+            &["post_id"],
+        ),
+        tool(
+            "list_reactions",
+            "Emoji reactions on a post",
+            post_id(),
+            &["post_id"],
+        ),
+        tool(
+            "list_post_files",
+            "Files attached to a post",
+            post_id(),
+            &["post_id"],
+        ),
+        tool(
+            "search",
+            "Full-text search over posts and file names",
+            json!({ "query": string("Search terms"), "limit": integer("Maximum results (default 50)") }),
+            &["query"],
+        ),
+        tool(
+            "get_self",
+            "This node's own profile, peer ID and friend code",
+            json!({}),
+            &[],
+        ),
+        tool(
+            "list_peers",
+            "Known peers and their trust levels",
+            json!({}),
+            &[],
+        ),
+        tool("list_topics", "Topics this node follows", json!({}), &[]),
+        tool(
+            "list_replies",
+            "Replies to our posts (or our agent's) seen since the server started, newest first",
+            json!({ "limit": integer("Maximum replies (default 20)") }),
+            &[],
+        ),
+        // Writing
+        tool(
+            "create_thread",
+            "Start a thread; the first post carries the body and any files",
+            json!({
+                "title": string("Thread title"),
+                "body": string("Body of the first post"),
+                "topics": strings("Topic IDs to announce the thread on"),
+                "files": strings("Local paths of files to attach"),
+            }),
+            &["title"],
+        ),
+        tool(
+            "create_post",
+            "Post in a thread; list the posts you are replying to in parent_post_ids",
+            json!({
+                "thread_id": string("The thread ID"),
+                "body": string("Post body"),
+                "parent_post_ids": strings("IDs of the posts this replies to"),
+            }),
+            &["thread_id", "body"],
+        ),
+        tool(
+            "edit_post",
+            "Replace the body of one of our posts",
+            json!({ "post_id": string("The post ID"), "body": string("New body") }),
+            &["post_id", "body"],
+        ),
+        tool(
+            "delete_post",
+            "Retract one of our posts, leaving a tombstone",
+            post_id(),
+            &["post_id"],
+        ),
+        tool(
+            "react",
+            "Add an emoji reaction to a post",
+            json!({ "post_id": string("The post ID"), "emoji": string("The emoji") }),
+            &["post_id", "emoji"],
+        ),
+        tool(
+            "unreact",
+            "Remove our emoji reaction from a post",
+            json!({ "post_id": string("The post ID"), "emoji": string("The emoji") }),
+            &["post_id", "emoji"],
+        ),
+        tool(
+            "upload_file",
+            "Attach a local file to one of our posts",
+            json!({
+                "post_id": string("The post ID"),
+                "path": string("Local path of the file"),
+                "mime": string("MIME type (guessed from the extension if omitted)"),
+            }),
+            &["post_id", "path"],
+        ),
+        tool(
+            "download_thread",
+            "Fetch a thread announced by a peer",
+            json!({ "thread_id": string("The thread ID") }),
+            &["thread_id"],
+        ),
+        tool(
+            "follow_topic",
+            "Subscribe to a topic",
+            json!({ "topic_id": string("The topic ID") }),
+            &["topic_id"],
+        ),
+        tool(
+            "unfollow_topic",
+            "Unsubscribe from a topic",
+            json!({ "topic_id": string("The topic ID") }),
+            &["topic_id"],
+        ),
+        tool(
+            "add_friend",
+            "Add a peer from their friend code",
+            json!({ "friendcode": string("The peer's friend code") }),
+            &["friendcode"],
+        ),
+        tool(
+            "set_trust",
+            "Set a peer's trust level",
+            json!({
+                "peer_id": string("The peer ID (GPG fingerprint)"),
+                "level": string("friend, known, stranger or muted"),
+            }),
+            &["peer_id", "level"],
+        ),
+        // Direct messages
+        tool(
+            "list_conversations",
+            "List all direct message conversations with peers",
+            json!({}),
+            &[],
+        ),
+        tool(
+            "read_messages",
+            "Read direct messages with a specific peer",
+            json!({
+                "peer_id": string("The peer ID (GPG fingerprint) to read messages from"),
+                "limit": integer("Maximum number of messages to retrieve (default 50)"),
+            }),
+            &["peer_id"],
+        ),
+        tool(
+            "send_dm",
+            "Send an encrypted direct message to a peer",
+            json!({
+                "to_peer_id": string("The peer ID (GPG fingerprint) to send the message to"),
+                "body": string("The message content to send"),
+                "reply_to": string("ID of a message in the conversation to reply to"),
+            }),
+            &["to_peer_id", "body"],
+        ),
+        tool(
+            "mark_message_read",
+            "Mark a direct message as read",
+            json!({ "message_id": string("The message ID") }),
+            &["message_id"],
+        ),
+        tool(
+            "get_unread_count",
+            "Get the total number of unread direct messages",
+            json!({}),
+            &[],
+        ),
+    ]
+}
+
+pub async fn call_tool(
+    client: &GraphchanClient,
+    replies: &ReplyWatcher,
+    name: &str,
+    args: &Value,
+) -> Result<Value, JsonRpcError> {
+    match name {
+        "list_threads" => client.get("/threads", &optional_limit(args)).await,
+        "read_thread" => {
+            client
+                .get(
+                    &format!("/threads/{}", segment(required(args, "thread_id")?)),
+                    &[],
+                )
+                .await
+        }
+        "read_latest_posts" => {
+            let thread_id = required(args, "thread_id")?;
+            let n = args.get("n").and_then(Value::as_u64).unwrap_or(10) as usize;
+            let mut posts = thread_posts(client, thread_id).await?;
+            posts.sort_by(|a, b| b.1.created_at.cmp(&a.1.created_at)); // Newest first
+            posts.truncate(n);
+            Ok(Value::Array(
+                posts.into_iter().map(|(post, _)| post).collect(),
+            ))
+        }
+        "get_post" => client.get(&post_path(args, "")?, &[]).await,
+        "read_parents" => {
+            let depth = args.get("n").and_then(Value::as_u64).unwrap_or(1);
+            client
+                .get(
+                    &post_path(args, "ancestors")?,
+                    &[("depth", depth.to_string())],
+                )
+                .await
+        }
+        "read_replies" => {
+            let depth: Vec<_> = args
+                .get("n")
+                .and_then(Value::as_u64)
+                .map(|depth| ("depth", depth.to_string()))
+                .into_iter()
+                .collect();
+            client.get(&post_path(args, "descendants")?, &depth).await
+        }
+        "post_path" => {
+            let other = segment(required(args, "other_post_id")?);
+            client
+                .get(&post_path(args, &format!("path/{other}"))?, &[])
+                .await
+        }
+        "recent_posts" => client.get("/posts/recent", &optional_limit(args)).await,
+        "post_history" => client.get(&post_path(args, "history")?, &[]).await,
+        "list_reactions" => client.get(&post_path(args, "reactions")?, &[]).await,
+        "list_post_files" => client.get(&post_path(args, "files")?, &[]).await,
+        "search" => {
+            let mut query = vec![("q", required(args, "query")?.to_string())];
+            query.extend(optional_limit(args));
+            client.get("/search", &query).await
+        }
+        "get_self" => client.get("/peers/self", &[]).await,
+        "list_peers" => client.get("/peers", &[]).await,
+        "list_topics" => client.get("/topics", &[]).await,
+        "list_replies" => {
+            let limit = args.get("limit").and_then(Value::as_u64).unwrap_or(20) as usize;
+            Ok(Value::Array(replies.recent(limit)))
+        }
+        "create_thread" => {
+            let input = json!({
+                "title": required(args, "title")?,
+                "body": args.get("body").and_then(Value::as_str),
+                "topics": string_list(args, "topics"),
+                "metadata": client.post_metadata(),
+            });
+            let mut form = Form::new().text("json", input.to_string());
+            for path in string_list(args, "files") {
+                form = form.part("file", file_part(&path, None).await?);
+            }
+            client.send_multipart("/threads", form).await
+        }
+        "create_post" => {
+            let thread_id = required(args, "thread_id")?;
+            let body = json!({
+                "body": required(args, "body")?,
+                "parent_post_ids": string_list(args, "parent_post_ids"),
+                "metadata": client.post_metadata(),
+            });
+            client
+                .send(
+                    Method::POST,
+                    &format!("/threads/{}/posts", segment(thread_id)),
+                    Some(&body),
+                )
+                .await
+        }
+        "edit_post" => {
+            let body = json!({ "body": required(args, "body")? });
+            client
+                .send(Method::PATCH, &post_path(args, "")?, Some(&body))
+                .await
+        }
+        "delete_post" => {
+            client
+                .send(Method::DELETE, &post_path(args, "")?, None)
+                .await
+        }
+        "react" | "unreact" => {
+            let body = json!({ "emoji": required(args, "emoji")? });
+            client
+                .send(Method::POST, &post_path(args, name)?, Some(&body))
+                .await
+        }
+        "upload_file" => {
+            let path = required(args, "path")?;
+            let part = file_part(path, args.get("mime").and_then(Value::as_str)).await?;
+            client
+                .send_multipart(&post_path(args, "files")?, Form::new().part("file", part))
+                .await
+        }
+        "download_thread" => {
+            let thread_id = required(args, "thread_id")?;
+            client
+                .send(
+                    Method::POST,
+                    &format!("/threads/{}/download", segment(thread_id)),
+                    None,
+                )
+                .await
+        }
+        "follow_topic" => {
+            let body = json!({ "topic_id": required(args, "topic_id")? });
+            client.send(Method::POST, "/topics", Some(&body)).await
+        }
+        "unfollow_topic" => {
+            let topic_id = required(args, "topic_id")?;
+            client
+                .send(
+                    Method::DELETE,
+                    &format!("/topics/{}", segment(topic_id)),
+                    None,
+                )
+                .await
+        }
+        "add_friend" => {
+            let body = json!({ "friendcode": required(args, "friendcode")? });
+            client.send(Method::POST, "/peers", Some(&body)).await
+        }
+        "set_trust" => {
+            let peer_id = required(args, "peer_id")?;
+            let body = json!({ "level": required(args, "level")? });
+            client
+                .send(
+                    Method::PUT,
+                    &format!("/peers/{}/trust", segment(peer_id)),
+                    Some(&body),
+                )
+                .await
+        }
+        "list_conversations" => client.get("/dms/conversations", &[]).await,
+        "read_messages" => {
+            let peer_id = required(args, "peer_id")?;
+            let limit = args.get("limit").and_then(Value::as_u64).unwrap_or(50);
+            client
+                .get(
+                    &format!("/dms/{}/messages", segment(peer_id)),
+                    &[("limit", limit.to_string())],
+                )
+                .await
+        }
+        "send_dm" => {
+            let body = json!({
+                "to_peer_id": required(args, "to_peer_id")?,
+                "body": required(args, "body")?,
+                "reply_to": args.get("reply_to").and_then(Value::as_str),
+            });
+            client.send(Method::POST, "/dms/send", Some(&body)).await
+        }
+        "mark_message_read" => {
+            let message_id = required(args, "message_id")?;
+            client
+                .send(
+                    Method::POST,
+                    &format!("/dms/messages/{}/read", segment(message_id)),
+                    None,
+                )
+                .await
+        }
+        "get_unread_count" => client.get("/dms/unread/count", &[]).await,
+        _ => Err(JsonRpcError {
+            code: -32601,
+            message: format!("Tool {} not found", name),
+            data: None,
+        }),
+    }
+}
+
+/// A thread's posts as JSON, each with its timestamp.
+async fn thread_posts(
+    client: &GraphchanClient,
+    thread_id: &str,
+) -> Result<Vec<(Value, PostTimestamp)>, JsonRpcError> {
+    let details = client
+        .get(&format!("/threads/{}", segment(thread_id)), &[])
+        .await?;
+    let posts = details
+        .get("posts")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    posts
+        .into_iter()
+        .map(|post| {
+            let stamp = serde_json::from_value::<PostTimestamp>(post.clone()).map_err(|err| {
+                JsonRpcError {
+                    code: -32603,
+                    message: format!("Unexpected post in thread {thread_id}: {err}"),
+                    data: None,
+                }
+            })?;
+            Ok((post, stamp))
+        })
+        .collect()
+}
+
+async fn file_part(path: &str, mime: Option<&str>) -> Result<Part, JsonRpcError> {
+    let data = tokio::fs::read(path)
+        .await
+        .map_err(|err| invalid_params(format!("Cannot read {path}: {err}")))?;
+    let file_name = std::path::Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "file".to_string());
+    let mime = mime.unwrap_or_else(|| guess_mime(&file_name));
+    Part::bytes(data)
+        .file_name(file_name)
+        .mime_str(mime)
+        .map_err(|err| invalid_params(format!("Invalid MIME type {mime}: {err}")))
+}
+
+fn guess_mime(file_name: &str) -> &'static str {
+    let extension = file_name
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("svg") => "image/svg+xml",
+        Some("mp4") => "video/mp4",
+        Some("webm") => "video/webm",
+        Some("mp3") => "audio/mpeg",
+        Some("ogg") => "audio/ogg",
+        Some("wav") => "audio/wav",
+        Some("pdf") => "application/pdf",
+        Some("txt" | "md") => "text/plain",
+        Some("json") => "application/json",
+        _ => "application/octet-stream",
+    }
+}
+
+/// `/posts/{post_id}` or `/posts/{post_id}/{suffix}`.
+fn post_path(args: &Value, suffix: &str) -> Result<String, JsonRpcError> {
+    let post_id = segment(required(args, "post_id")?);
+    if suffix.is_empty() {
+        Ok(format!("/posts/{post_id}"))
+    } else {
+        Ok(format!("/posts/{post_id}/{suffix}"))
+    }
+}
+
+fn required<'a>(args: &'a Value, key: &str) -> Result<&'a str, JsonRpcError> {
+    args.get(key)
+        .and_then(Value::as_str)
+        .ok_or_else(|| invalid_params(format!("Missing {key}")))
+}
+
+fn string_list(args: &Value, key: &str) -> Vec<String> {
+    args.get(key)
+        .and_then(Value::as_array)
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn optional_limit(args: &Value) -> Vec<(&'static str, String)> {
+    args.get("limit")
+        .and_then(Value::as_u64)
+        .map(|limit| vec![("limit", limit.to_string())])
+        .unwrap_or_default()
+}
+
+fn invalid_params(message: String) -> JsonRpcError {
+    JsonRpcError {
+        code: -32602,
+        message,
+        data: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::ClientConfig;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    /// One request the fake backend answered; `target` is the path and query as sent.
+    #[derive(Clone)]
+    struct Request {
+        method: String,
+        target: String,
+        body: String,
+    }
+
+    impl Request {
+        fn json(&self) -> Value {
+            serde_json::from_str(&self.body).unwrap_or(Value::Null)
+        }
+    }
+
+    /// A stand-in backend on a loopback port: paths in `routes` (query ignored) get
+    /// their JSON, anything else a 404 with a `message`. Requests are recorded.
+    struct Backend {
+        url: String,
+        requests: Arc<Mutex<Vec<Request>>>,
+    }
+
+    impl Backend {
+        async fn start(routes: Vec<(&'static str, Value)>) -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+            let url = format!("http://{}", listener.local_addr().expect("address"));
+            let requests = Arc::new(Mutex::new(Vec::new()));
+            let log = requests.clone();
+            tokio::spawn(async move {
+                while let Ok((mut stream, _)) = listener.accept().await {
+                    let request = read_request(&mut stream).await;
+                    let path = request.target.split('?').next().unwrap_or_default();
+                    let (status, body) = match routes.iter().find(|(route, _)| *route == path) {
+                        Some((_, body)) => ("200 OK", body.to_string()),
+                        None => (
+                            "404 Not Found",
+                            json!({ "error": "not_found", "message": format!("{path} not found") })
+                                .to_string(),
+                        ),
+                    };
+                    log.lock().expect("requests").push(request);
+                    let response = format!(
+                        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                        body.len()
+                    );
+                    let _ = stream.write_all(response.as_bytes()).await;
+                    let _ = stream.shutdown().await;
+                }
+            });
+            Self { url, requests }
+        }
+
+        fn client(&self, agent_name: Option<&str>) -> GraphchanClient {
+            GraphchanClient::new(&ClientConfig {
+                api_url: self.url.clone(),
+                token: Some("test-token".into()),
+                agent_name: agent_name.map(str::to_string),
+                agent_version: agent_name.map(|_| "1.0".to_string()),
+            })
+            .expect("client")
+        }
+
+        fn requests(&self) -> Vec<Request> {
+            self.requests.lock().expect("requests").clone()
+        }
+    }
+
+    /// Reads the head and `Content-Length` bytes of body.
+    async fn read_request(stream: &mut TcpStream) -> Request {
+        let mut raw = Vec::new();
+        let mut chunk = [0u8; 4096];
+        let (head, body) = loop {
+            let read = stream.read(&mut chunk).await.unwrap_or(0);
+            raw.extend_from_slice(&chunk[..read]);
+            let text = String::from_utf8_lossy(&raw).into_owned();
+            if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                let length = head
+                    .lines()
+                    .filter_map(|line| line.split_once(':'))
+                    .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+                    .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+                    .unwrap_or(0);
+                if body.len() >= length || read == 0 {
+                    break (head.to_string(), body.to_string());
+                }
+            } else if read == 0 {
+                break (text, String::new());
+            }
+        };
+        let mut request_line = head.lines().next().unwrap_or_default().split(' ');
+        Request {
+            method: request_line.next().unwrap_or_default().to_string(),
+            target: request_line.next().unwrap_or_default().to_string(),
+            body,
+        }
+    }
+
+    async fn call(backend: &Backend, agent: Option<&str>, name: &str, args: Value) -> Value {
+        call_tool(
+            &backend.client(agent),
+            &ReplyWatcher::default(),
+            name,
+            &args,
+        )
+        .await
+        .unwrap_or_else(|err| panic!("{name} failed: {}", err.message))
+    }
+
+    #[tokio::test]
+    async fn read_parents_asks_for_one_generation_unless_told_otherwise() {
+        let backend =
+            Backend::start(vec![("/posts/p%201/ancestors", json!({ "posts": [] }))]).await;
+
+        call(&backend, None, "read_parents", json!({ "post_id": "p 1" })).await;
+        call(
+            &backend,
+            None,
+            "read_parents",
+            json!({ "post_id": "p 1", "n": 3 }),
+        )
+        .await;
+
+        let targets: Vec<_> = backend
+            .requests()
+            .into_iter()
+            .map(|request| request.target)
+            .collect();
+        assert_eq!(
+            targets,
+            [
+                "/posts/p%201/ancestors?depth=1",
+                "/posts/p%201/ancestors?depth=3"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn read_replies_only_limits_depth_when_given() {
+        let backend = Backend::start(vec![("/posts/p1/descendants", json!({ "posts": [] }))]).await;
+
+        call(&backend, None, "read_replies", json!({ "post_id": "p1" })).await;
+        call(
+            &backend,
+            None,
+            "read_replies",
+            json!({ "post_id": "p1", "n": 2 }),
+        )
+        .await;
+
+        let targets: Vec<_> = backend
+            .requests()
+            .into_iter()
+            .map(|request| request.target)
+            .collect();
+        assert_eq!(
+            targets,
+            ["/posts/p1/descendants", "/posts/p1/descendants?depth=2"]
+        );
+    }
+
+    #[tokio::test]
+    async fn create_post_sends_parents_and_agent_metadata() {
+        let backend = Backend::start(vec![("/threads/t1/posts", json!({ "id": "new" }))]).await;
+
+        let created = call(
+            &backend,
+            Some("scout"),
+            "create_post",
+            json!({ "thread_id": "t1", "body": "hi", "parent_post_ids": ["a", "b", 7] }),
+        )
+        .await;
+
+        assert_eq!(created, json!({ "id": "new" }));
+        let requests = backend.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, "POST");
+        let body = requests[0].json();
+        assert_eq!(body["body"], "hi");
+        // Non-string IDs are dropped rather than sent
+        assert_eq!(body["parent_post_ids"], json!(["a", "b"]));
+        assert_eq!(
+            body["metadata"]["agent"],
+            json!({ "name": "scout", "version": "1.0" })
+        );
+        assert!(body["metadata"]["client"]
+            .as_str()
+            .is_some_and(|client| client.starts_with("graphchan_mcp/")));
+    }
+
+    #[tokio::test]
+    async fn posts_without_an_agent_carry_no_agent_metadata() {
+        let backend = Backend::start(vec![("/threads/t1/posts", json!({ "id": "new" }))]).await;
+
+        call(
+            &backend,
+            None,
+            "create_post",
+            json!({ "thread_id": "t1", "body": "hi" }),
+        )
+        .await;
+
+        let body = backend.requests()[0].json();
+        assert_eq!(body["parent_post_ids"], json!([]));
+        assert!(body["metadata"].get("agent").is_none());
+        assert!(body["metadata"].get("client").is_some());
+    }
+
+    #[tokio::test]
+    async fn create_thread_attaches_agent_metadata_to_the_first_post() {
+        let backend = Backend::start(vec![("/threads", json!({ "thread": { "id": "t1" } }))]).await;
+
+        call(
+            &backend,
+            Some("scout"),
+            "create_thread",
+            json!({ "title": "Hello", "body": "first", "topics": ["cats"] }),
+        )
+        .await;
+
+        let request = &backend.requests()[0];
+        assert_eq!(
+            (request.method.as_str(), request.target.as_str()),
+            ("POST", "/threads")
+        );
+        // Multipart: the thread JSON is the `json` field
+        let input = request
+            .body
+            .lines()
+            .find_map(|line| {
+                serde_json::from_str::<Value>(line.trim())
+                    .ok()
+                    .filter(Value::is_object)
+            })
+            .expect("json part");
+        assert_eq!(input["title"], "Hello");
+        assert_eq!(input["topics"], json!(["cats"]));
+        assert_eq!(input["metadata"]["agent"]["name"], "scout");
+    }
+
+    #[tokio::test]
+    async fn missing_arguments_are_rejected_before_any_request() {
+        let backend = Backend::start(Vec::new()).await;
+        let client = backend.client(None);
+        let replies = ReplyWatcher::default();
+
+        let err = call_tool(
+            &client,
+            &replies,
+            "create_post",
+            &json!({ "thread_id": "t1" }),
+        )
+        .await
+        .expect_err("body is required");
+        assert_eq!(err.code, -32602);
+        let err = call_tool(&client, &replies, "read_parents", &json!({ "n": 2 }))
+            .await
+            .expect_err("post_id is required");
+        assert_eq!(err.code, -32602);
+        assert!(backend.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn backend_errors_carry_its_message() {
+        let backend = Backend::start(Vec::new()).await;
+
+        let err = call_tool(
+            &backend.client(None),
+            &ReplyWatcher::default(),
+            "get_post",
+            &json!({ "post_id": "gone" }),
+        )
+        .await
+        .expect_err("unknown post");
+
+        assert_eq!(err.code, -32000);
+        assert!(err.message.contains("404"));
+        assert!(err.message.contains("/posts/gone not found"));
+    }
+}